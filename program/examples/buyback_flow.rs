@@ -0,0 +1,35 @@
+// Dry run of a treasury buyback on the mock node and mock SDK:
+// detect a payment, verify it, then burn through BuybackBurn
+#[path = "../tests/harness/mod.rs"]
+mod harness;
+
+use bitcoin::Amount;
+use harness::BuybackHarness;
+use program::bitcoin::{PaymentIntent, PaymentMemo};
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let harness = BuybackHarness::new(100_000_000, 1_000_000)?;
+    println!("Treasury script: {}", harness.treasury_script());
+
+    // A payer sends 0.1 BTC to the treasury with a buyback memo
+    let memo = PaymentMemo { payer_id: [0x42; 16], intent: PaymentIntent::Buyback, nonce: 1 };
+    let txid = harness.seed_payment(Amount::from_sat(10_000_000), Some(memo), 1);
+    println!("Seeded payment {}", txid);
+
+    for mut payment in harness.detect_payments().await? {
+        println!("Detected {} sats in {}:{}", payment.amount_sats, payment.txid, payment.utxo.vout);
+
+        let verified = harness.verify(&mut payment).await?;
+        println!("Verified with {} confirmations, memo {:?}", payment.utxo.confirmations, verified.memo);
+
+        let supply_before = harness.state().total_supply;
+        harness.buyback(&payment, &verified)?;
+        println!("Burned {} OVT", supply_before - harness.state().total_supply);
+    }
+
+    for event in harness.client.logs() {
+        println!("{}", event);
+    }
+    Ok(())
+}
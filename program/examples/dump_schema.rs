@@ -0,0 +1,7 @@
+// Print the Borsh layouts of the program's types as JSON, for frontend codegen:
+// cargo run --features serde --example dump_schema > tests/fixtures/borsh_schema.json
+use program::schema::generate_schema;
+
+fn main() {
+    println!("{}", generate_schema().to_json_pretty());
+}
@@ -13,10 +13,11 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         port: 8332,
         username: "user".to_string(),
         password: "password".to_string(),
+        ..Default::default()
     };
     
     // Create RPC client using the proper constructor
-    let rpc_client = Arc::new(BitcoinRpcClient::new(config));
+    let rpc_client = Arc::new(BitcoinRpcClient::new(config)?);
     
     // Create UTXO tracker with 6 confirmations required
     let mut tracker = UtxoTracker::new(rpc_client.clone(), 6);
@@ -29,7 +30,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     );
     
     // Add UTXO with Pending status
-    tracker.add_utxo(utxo.clone(), UtxoStatus::Pending).await;
+    tracker.add_utxo(utxo.clone(), UtxoStatus::Pending).await?;
     println!("Added UTXO to tracker");
     
     // Update confirmations for tracked UTXOs
@@ -41,8 +42,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let total_value: u64 = all_utxos.iter().map(|(meta, _)| meta.amount_sats).sum();
     println!("Total value of UTXOs: {} sats", total_value);
     
-    // Mark UTXO as spent using the txid string
-    tracker.mark_utxo_spent(&utxo.txid).await;
+    // Mark UTXO as spent by its outpoint
+    tracker.mark_utxo_spent(&utxo.txid, utxo.vout).await;
     println!("Marked UTXO as spent");
     
     Ok(())
@@ -0,0 +1,85 @@
+use arch_program::{
+    account::AccountInfo,
+    msg,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+use bitcoin::hashes::{sha256, Hash};
+
+use crate::error::OVTError;
+
+// Define account seeds
+pub const OVT_STATE_SEED: &[u8] = b"ovt_state";
+pub const TREASURY_SEED: &[u8] = b"treasury";
+pub const BURN_HISTORY_SEED: &[u8] = b"burn_history";
+/// Followed by the payment's txid bytes, one account per payment burned for
+pub const SPENT_PAYMENT_SEED: &[u8] = b"spent_payment";
+/// Followed by the sha256 of an entry's key, one account per key WriteEntry stores
+pub const ENTRY_SEED: &[u8] = b"entry";
+
+/// The one account the program keeps its `OVTState` in
+///
+/// Addresses here come from the runtime's own derivation, so the seeds the
+/// program signs with in `invoke_signed` match the accounts it checks.
+pub fn state_address(program_id: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[OVT_STATE_SEED], program_id).0
+}
+
+/// Reject any state account other than the derived one
+pub fn check_state_account(program_id: &Pubkey, state_info: &AccountInfo) -> Result<(), ProgramError> {
+    if *state_info.key != state_address(program_id) {
+        msg!("State account {:?} is not the derived state address", state_info.key);
+        return Err(OVTError::InvalidProgramState.into());
+    }
+    Ok(())
+}
+
+/// The account BuybackBurn appends its `BurnRecord`s to
+pub fn burn_history_address(program_id: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[BURN_HISTORY_SEED], program_id).0
+}
+
+/// Reject any burn history account other than the derived one
+pub fn check_burn_history_account(program_id: &Pubkey, history_info: &AccountInfo) -> Result<(), ProgramError> {
+    if *history_info.key != burn_history_address(program_id) {
+        msg!("Burn history account {:?} is not the derived burn history address", history_info.key);
+        return Err(OVTError::InvalidProgramState.into());
+    }
+    Ok(())
+}
+
+/// The account that exists once the payment `payment_txid` has been burned
+/// for, holding its `BurnRecord`; unlike the burn history it is never overwritten
+pub fn spent_payment_address(program_id: &Pubkey, payment_txid: &[u8; 32]) -> Pubkey {
+    Pubkey::find_program_address(&[SPENT_PAYMENT_SEED, payment_txid], program_id).0
+}
+
+/// The account WriteEntry stores the value of `key` in; keys are hashed
+/// since a seed holds at most 32 bytes
+pub fn entry_address(program_id: &Pubkey, key: &[u8]) -> Pubkey {
+    Pubkey::find_program_address(&[ENTRY_SEED, &entry_key_hash(key)], program_id).0
+}
+
+/// The seed `entry_address` derives the account for `key` from
+pub fn entry_key_hash(key: &[u8]) -> [u8; 32] {
+    sha256::Hash::hash(key).to_byte_array()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_derivation() {
+        let program_id = Pubkey([7u8; 32]);
+        let (state, bump) = Pubkey::find_program_address(&[OVT_STATE_SEED], &program_id);
+        assert_eq!(state_address(&program_id), state);
+        assert_eq!(Pubkey::create_program_address(&[OVT_STATE_SEED, &[bump]], &program_id).ok(), Some(state));
+
+        assert_ne!(Pubkey::find_program_address(&[TREASURY_SEED], &program_id).0, state);
+        assert_ne!(burn_history_address(&program_id), state);
+        assert_ne!(spent_payment_address(&program_id, &[1; 32]), spent_payment_address(&program_id, &[2; 32]));
+        assert_ne!(entry_address(&program_id, b"ovt\0a"), entry_address(&program_id, b"ovt\0b"));
+        assert_ne!(state_address(&Pubkey([8u8; 32])), state);
+    }
+}
@@ -0,0 +1,25 @@
+use bitcoin::{BlockHash, Txid};
+use serde::Deserialize;
+
+/// Block summary as returned by `getblock` with verbosity 1
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct BlockInfo {
+    pub hash: BlockHash,
+    pub height: u32,
+    /// Block timestamp (seconds since the Unix epoch)
+    pub time: u64,
+    /// None for the genesis block
+    #[serde(rename = "previousblockhash")]
+    pub previous_block_hash: Option<BlockHash>,
+    /// Txids of the transactions in the block, coinbase first
+    pub tx: Vec<Txid>,
+    /// Confirmations of the block itself; -1 when it is no longer on the main chain
+    pub confirmations: i64,
+}
+
+impl BlockInfo {
+    /// Whether the block is still part of the node's best chain
+    pub fn is_in_main_chain(&self) -> bool {
+        self.confirmations >= 0
+    }
+}
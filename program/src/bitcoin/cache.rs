@@ -1,8 +1,38 @@
 use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex};
-use std::time::{Duration, SystemTime};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use arch_program::msg;
+use async_trait::async_trait;
+use futures::stream::{self, StreamExt};
+use borsh::{BorshDeserialize, BorshSerialize};
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+use tokio::time::{Instant, MissedTickBehavior};
+use crate::bitcoin::metrics::Metrics;
+use crate::bitcoin::BitcoinRpcConfig;
+use crate::bitcoin::config::{
+    env_secs, env_value, ConfigError, UTXO_CACHE_INVALID_TTL_ENV, UTXO_CACHE_MAINTENANCE_ENV, UTXO_CACHE_MAX_SIZE_ENV,
+    UTXO_CACHE_REFRESH_ENV,
+};
 use crate::bitcoin::utxo::{UtxoMeta, UtxoStatus};
-use crate::bitcoin::rpc::{BitcoinRpcClient, BitcoinRpcError};
+use crate::bitcoin::error::BitcoinRpcError;
+use crate::bitcoin::trace;
+
+/// Upstream the cache queries on a miss
+#[async_trait]
+pub trait UtxoStatusSource: Send + Sync {
+    /// Fetch the current status of a UTXO, bypassing any cache
+    async fn fetch_utxo_status(&self, utxo: &UtxoMeta) -> Result<UtxoStatus, BitcoinRpcError>;
+
+    /// Client metrics that cache hits and misses should also be reported to
+    fn cache_metrics(&self) -> Option<&Metrics> {
+        None
+    }
+}
 
 /// Configuration for the UTXO cache
 #[derive(Debug, Clone)]
@@ -13,6 +43,21 @@ pub struct UtxoCacheConfig {
     pub refresh_interval: Duration,
     /// Maximum time to keep invalid/spent UTXOs in cache
     pub invalid_ttl: Duration,
+    /// Per-status TTLs; statuses missing here fall back to `refresh_interval`
+    /// (active/pending) or `invalid_ttl` (spent/invalid)
+    pub status_ttls: HashMap<UtxoStatus, Duration>,
+    /// How long a "transaction not found" answer is cached
+    pub not_found_ttl: Duration,
+    /// Run `cleanup` in the background at this interval; `None` leaves it to the caller
+    pub maintenance_interval: Option<Duration>,
+    /// Maximum number of upstream lookups `prefetch` runs at once
+    pub prefetch_concurrency: usize,
+    /// When a refresh fails because the node is unreachable, keep serving
+    /// entries updated less than this long ago instead of failing
+    pub stale_if_error: Option<Duration>,
+    /// Confirmations an Active entry must still have after `handle_reorg`,
+    /// or it is demoted to Pending
+    pub min_confirmations: u32,
 }
 
 impl Default for UtxoCacheConfig {
@@ -21,63 +66,274 @@ impl Default for UtxoCacheConfig {
             max_size: 1000,
             refresh_interval: Duration::from_secs(60),
             invalid_ttl: Duration::from_secs(3600),
+            status_ttls: HashMap::from([
+                (UtxoStatus::Pending, Duration::from_secs(30)),
+                (UtxoStatus::Active, Duration::from_secs(600)),
+            ]),
+            not_found_ttl: Duration::from_secs(30),
+            maintenance_interval: None,
+            prefetch_concurrency: 8,
+            stale_if_error: None,
+            min_confirmations: 1,
         }
     }
 }
 
+impl UtxoCacheConfig {
+    /// Default settings, keeping Active entries to `config`'s confirmations
+    pub fn from_rpc_config(config: &BitcoinRpcConfig) -> Self {
+        Self {
+            min_confirmations: config.min_confirmations,
+            ..Self::default()
+        }
+    }
+
+    /// Time after which an entry with `status` is refreshed (active/pending)
+    /// or dropped (spent/invalid)
+    pub fn ttl_for(&self, status: UtxoStatus) -> Duration {
+        match self.status_ttls.get(&status) {
+            Some(ttl) => *ttl,
+            None => match status {
+                UtxoStatus::Active | UtxoStatus::Pending => self.refresh_interval,
+                UtxoStatus::Invalid | UtxoStatus::Spent => self.invalid_ttl,
+            },
+        }
+    }
+
+    /// Reject settings the cache can't work with, or that contradict each other
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if self.max_size == 0 {
+            return Err(ConfigError::ZeroCacheSize);
+        }
+        if self.refresh_interval.is_zero() {
+            return Err(ConfigError::ZeroDuration("refresh_interval"));
+        }
+        if self.refresh_interval > self.invalid_ttl {
+            return Err(ConfigError::RefreshIntervalExceedsInvalidTtl {
+                refresh_interval: self.refresh_interval,
+                invalid_ttl: self.invalid_ttl,
+            });
+        }
+        if self.maintenance_interval.is_some_and(|interval| interval.is_zero()) {
+            return Err(ConfigError::ZeroDuration("maintenance_interval"));
+        }
+        if self.prefetch_concurrency == 0 {
+            return Err(ConfigError::ZeroPrefetchConcurrency);
+        }
+        Ok(())
+    }
+}
+
+/// Builds a `UtxoCacheConfig`, or a cache from it, checking the result with
+/// `UtxoCacheConfig::validate`
+#[derive(Debug, Clone, Default)]
+pub struct UtxoCacheBuilder {
+    config: UtxoCacheConfig,
+}
+
+impl UtxoCacheBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The defaults, overridden by the `UTXO_CACHE_*` variables that are set
+    pub fn from_env() -> Result<Self, ConfigError> {
+        let mut builder = Self::new();
+        if let Some(max_size) = env_value(UTXO_CACHE_MAX_SIZE_ENV, "number of entries")? {
+            builder.config.max_size = max_size;
+        }
+        if let Some(interval) = env_secs(UTXO_CACHE_REFRESH_ENV)? {
+            builder.config.refresh_interval = interval;
+        }
+        if let Some(ttl) = env_secs(UTXO_CACHE_INVALID_TTL_ENV)? {
+            builder.config.invalid_ttl = ttl;
+        }
+        if let Some(interval) = env_secs(UTXO_CACHE_MAINTENANCE_ENV)? {
+            builder.config.maintenance_interval = Some(interval);
+        }
+        Ok(builder)
+    }
+
+    pub fn with_max_size(mut self, max_size: usize) -> Self {
+        self.config.max_size = max_size;
+        self
+    }
+
+    pub fn with_refresh_interval(mut self, interval: Duration) -> Self {
+        self.config.refresh_interval = interval;
+        self
+    }
+
+    pub fn with_invalid_ttl(mut self, ttl: Duration) -> Self {
+        self.config.invalid_ttl = ttl;
+        self
+    }
+
+    /// TTL for entries with `status`, instead of the interval their status falls back to
+    pub fn with_status_ttl(mut self, status: UtxoStatus, ttl: Duration) -> Self {
+        self.config.status_ttls.insert(status, ttl);
+        self
+    }
+
+    pub fn with_not_found_ttl(mut self, ttl: Duration) -> Self {
+        self.config.not_found_ttl = ttl;
+        self
+    }
+
+    pub fn with_maintenance_interval(mut self, interval: Duration) -> Self {
+        self.config.maintenance_interval = Some(interval);
+        self
+    }
+
+    pub fn with_prefetch_concurrency(mut self, concurrency: usize) -> Self {
+        self.config.prefetch_concurrency = concurrency;
+        self
+    }
+
+    pub fn with_stale_if_error(mut self, window: Duration) -> Self {
+        self.config.stale_if_error = Some(window);
+        self
+    }
+
+    pub fn with_min_confirmations(mut self, confirmations: u32) -> Self {
+        self.config.min_confirmations = confirmations;
+        self
+    }
+
+    pub fn build_config(self) -> Result<UtxoCacheConfig, ConfigError> {
+        self.config.validate()?;
+        Ok(self.config)
+    }
+
+    /// A cache without maintenance; pass `build_config` to `UtxoCache::shared` for that
+    pub fn build(self) -> Result<UtxoCache, ConfigError> {
+        Ok(UtxoCache::new(self.build_config()?))
+    }
+}
+
 /// Cached UTXO entry containing metadata and timing information
 #[derive(Debug, Clone)]
 struct CacheEntry {
     utxo: UtxoMeta,
     status: UtxoStatus,
-    last_updated: SystemTime,
-    last_accessed: SystemTime,
+    /// Height of the block that confirmed the UTXO, if known
+    block_height: Option<u32>,
+    /// The node didn't know the transaction; cached as Invalid with `not_found_ttl`
+    not_found: bool,
+    last_updated: Instant,
+    last_accessed: Instant,
 }
 
 impl CacheEntry {
     fn new(utxo: UtxoMeta, status: UtxoStatus) -> Self {
         Self {
+            block_height: utxo.block_height,
+            not_found: false,
             utxo,
             status,
-            last_updated: SystemTime::now(),
-            last_accessed: SystemTime::now(),
+            last_updated: Instant::now(),
+            last_accessed: Instant::now(),
         }
     }
 
-    fn needs_refresh(&self, config: &UtxoCacheConfig) -> bool {
-        let now = SystemTime::now();
-        match self.status {
-            UtxoStatus::Active | UtxoStatus::Pending => {
-                now.duration_since(self.last_updated).unwrap() >= config.refresh_interval
-            }
-            UtxoStatus::Invalid | UtxoStatus::Spent => {
-                now.duration_since(self.last_updated).unwrap() >= config.invalid_ttl
-            }
+    fn not_found(utxo: UtxoMeta) -> Self {
+        Self {
+            not_found: true,
+            ..Self::new(utxo, UtxoStatus::Invalid)
         }
     }
 
+    fn ttl(&self, config: &UtxoCacheConfig) -> Duration {
+        if self.not_found {
+            config.not_found_ttl
+        } else {
+            config.ttl_for(self.status)
+        }
+    }
+
+    fn needs_refresh(&self, config: &UtxoCacheConfig) -> bool {
+        self.last_updated.elapsed() >= self.ttl(config)
+    }
+
     fn access(&mut self) {
-        self.last_accessed = SystemTime::now();
+        self.last_accessed = Instant::now();
     }
 
     fn update(&mut self, status: UtxoStatus) {
         self.status = status;
-        self.last_updated = SystemTime::now();
+        self.last_updated = Instant::now();
     }
 }
 
+/// Cache key: the outpoint `(txid bytes, vout)`, so outputs of one transaction don't collide
+type CacheKey = ([u8; 32], u32);
+
+/// Cache key for a txid given as hex, None if it isn't a valid txid
+fn outpoint_key(txid: &str, vout: u32) -> Option<CacheKey> {
+    let txid = hex::decode(txid).ok()?.try_into().ok()?;
+    Some((txid, vout))
+}
+
+fn cache_key(utxo: &UtxoMeta) -> Result<CacheKey, BitcoinRpcError> {
+    let txid = utxo.txid_to_bytes()
+        .map_err(|_| BitcoinRpcError::InvalidResponse("Invalid txid format".to_string()))?;
+    Ok((txid, utxo.vout))
+}
+
+/// First bytes of a cache file written by `UtxoCache::save_to`
+const CACHE_FILE_MAGIC: &[u8; 4] = b"OVTC";
+/// Bumped whenever `PersistedEntry` changes shape
+const CACHE_FILE_VERSION: u8 = 2;
+
+/// On-disk form of a cache entry
+#[derive(Debug, BorshSerialize, BorshDeserialize)]
+struct PersistedEntry {
+    txid: [u8; 32],
+    vout: u32,
+    utxo: UtxoMeta,
+    status: UtxoStatus,
+    block_height: Option<u32>,
+    not_found: bool,
+    /// Unix seconds
+    last_updated: u64,
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs()
+}
+
+/// Result of a cache lookup
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CacheLookup {
+    pub status: UtxoStatus,
+    /// The refresh failed and this is the last known status (see `UtxoCacheConfig::stale_if_error`)
+    pub stale: bool,
+}
+
+/// Running counters behind `CacheStats`
+#[derive(Debug, Default)]
+struct CacheCounters {
+    hits: AtomicUsize,
+    misses: AtomicUsize,
+    refreshes: AtomicUsize,
+    capacity_evictions: AtomicUsize,
+    ttl_evictions: AtomicUsize,
+    stale_served: AtomicUsize,
+}
+
 #[derive(Debug)]
 pub struct UtxoCache {
     config: UtxoCacheConfig,
-    cache: Arc<Mutex<HashMap<[u8; 32], CacheEntry>>>,
+    cache: Arc<Mutex<HashMap<CacheKey, CacheEntry>>>,
+    /// Per-key locks for lookups currently going upstream, so concurrent
+    /// misses on the same outpoint share one fetch
+    in_flight: Arc<Mutex<HashMap<CacheKey, Arc<tokio::sync::Mutex<()>>>>>,
+    counters: Arc<CacheCounters>,
 }
 
 impl Default for UtxoCache {
     fn default() -> Self {
-        Self {
-            config: UtxoCacheConfig::default(),
-            cache: Arc::new(Mutex::new(HashMap::new())),
-        }
+        Self::new(UtxoCacheConfig::default())
     }
 }
 
@@ -92,69 +348,348 @@ impl UtxoCache {
         Self {
             config,
             cache: Arc::new(Mutex::new(HashMap::new())),
+            in_flight: Arc::new(Mutex::new(HashMap::new())),
+            counters: Arc::new(CacheCounters::default()),
         }
     }
 
-    /// Get UTXO status from cache, refreshing from RPC if needed
-    pub async fn get_utxo_status(
+    /// Write all entries to `path`, replacing any previous file
+    ///
+    /// The file is written next to `path` first and renamed into place, so a
+    /// crash mid-write never leaves a truncated cache behind.
+    pub fn save_to(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let now = unix_now();
+        let entries: Vec<PersistedEntry> = self.cache.lock().unwrap()
+            .iter()
+            .map(|((txid, vout), entry)| PersistedEntry {
+                txid: *txid,
+                vout: *vout,
+                utxo: entry.utxo.clone(),
+                status: entry.status,
+                block_height: entry.block_height,
+                not_found: entry.not_found,
+                last_updated: now.saturating_sub(entry.last_updated.elapsed().as_secs()),
+            })
+            .collect();
+
+        let mut data = CACHE_FILE_MAGIC.to_vec();
+        data.push(CACHE_FILE_VERSION);
+        entries.serialize(&mut data)?;
+
+        let path = path.as_ref();
+        let tmp_path = path.with_extension("tmp");
+        fs::write(&tmp_path, data)?;
+        fs::rename(tmp_path, path)
+    }
+
+    /// Create a cache pre-populated from a file written by `save_to`
+    ///
+    /// Entries already past their TTL under `config` are skipped. A missing
+    /// file gives an empty cache; an unreadable, corrupt or version-mismatched
+    /// one is logged and ignored.
+    pub fn load_from(path: impl AsRef<Path>, config: UtxoCacheConfig) -> Self {
+        let cache = Self::new(config);
+        let path = path.as_ref();
+        let data = match fs::read(path) {
+            Ok(data) => data,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return cache,
+            Err(e) => {
+                msg!("Ignoring UTXO cache file {}: {}", path.display(), e);
+                return cache;
+            }
+        };
+
+        let mut entries = match Self::decode_cache_file(&data) {
+            Ok(entries) => entries,
+            Err(reason) => {
+                msg!("Ignoring UTXO cache file {}: {}", path.display(), reason);
+                return cache;
+            }
+        };
+
+        // Oldest first, so the freshest entries survive if the file holds more than max_size
+        entries.sort_by_key(|entry| entry.last_updated);
+        let now = unix_now();
+        for persisted in entries {
+            let age = Duration::from_secs(now.saturating_sub(persisted.last_updated));
+            let key = (persisted.txid, persisted.vout);
+            let mut entry = CacheEntry::new(persisted.utxo, persisted.status);
+            entry.block_height = persisted.block_height;
+            entry.not_found = persisted.not_found;
+            if age >= entry.ttl(&cache.config) {
+                continue;
+            }
+            entry.last_updated = Instant::now().checked_sub(age).unwrap_or_else(Instant::now);
+            entry.last_accessed = entry.last_updated;
+            cache.insert(key, entry);
+        }
+        cache
+    }
+
+    fn decode_cache_file(data: &[u8]) -> Result<Vec<PersistedEntry>, String> {
+        let body = data
+            .strip_prefix(CACHE_FILE_MAGIC.as_slice())
+            .ok_or_else(|| "not a UTXO cache file".to_string())?;
+        let (version, body) = body
+            .split_first()
+            .ok_or_else(|| "missing version".to_string())?;
+        if *version != CACHE_FILE_VERSION {
+            return Err(format!("unsupported version {} (expected {})", version, CACHE_FILE_VERSION));
+        }
+        Vec::<PersistedEntry>::try_from_slice(body).map_err(|e| format!("corrupt entries: {}", e))
+    }
+
+    /// Create a shared cache, starting background maintenance if
+    /// `config.maintenance_interval` is set (requires a tokio runtime)
+    pub fn shared(config: UtxoCacheConfig) -> (Arc<Self>, Option<MaintenanceHandle>) {
+        let interval = config.maintenance_interval;
+        let cache = Arc::new(Self::new(config));
+        let maintenance = interval.map(|interval| cache.clone().spawn_maintenance(interval));
+        (cache, maintenance)
+    }
+
+    /// Run `cleanup` every `interval` on a background task until the handle
+    /// is shut down or dropped
+    pub fn spawn_maintenance(self: Arc<Self>, interval: Duration) -> MaintenanceHandle {
+        let (shutdown_tx, mut shutdown_rx) = oneshot::channel();
+        let task = tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            // The first tick completes immediately; there's nothing to clean yet
+            ticker.tick().await;
+            loop {
+                tokio::select! {
+                    _ = &mut shutdown_rx => break,
+                    _ = ticker.tick() => self.cleanup().await,
+                }
+            }
+        });
+
+        MaintenanceHandle {
+            shutdown: Some(shutdown_tx),
+            task: Some(task),
+        }
+    }
+
+    /// Return the cached status if the entry exists and is still fresh
+    fn lookup_fresh<S: UtxoStatusSource + ?Sized>(&self, source: &S, key: &CacheKey) -> Option<UtxoStatus> {
+        let mut cache = self.cache.lock().unwrap();
+        let entry = cache.get_mut(key)?;
+        entry.access();
+        if entry.needs_refresh(&self.config) {
+            return None;
+        }
+
+        self.counters.hits.fetch_add(1, Ordering::Relaxed);
+        if let Some(metrics) = source.cache_metrics() {
+            metrics.record_cache_hit();
+        }
+        Some(entry.status)
+    }
+
+    /// Get UTXO status from cache, refreshing from `source` if needed
+    pub async fn get_utxo_status<S: UtxoStatusSource + ?Sized>(
         &self,
-        rpc: &BitcoinRpcClient,
+        source: &S,
         utxo: &UtxoMeta,
     ) -> Result<UtxoStatus, BitcoinRpcError> {
-        let mut cache = self.cache.lock().unwrap();
-        
-        // Convert txid string to bytes for HashMap key
-        let key = utxo.txid_to_bytes()
-            .map_err(|_| BitcoinRpcError::InvalidResponse("Invalid txid format".to_string()))?;
-        
-        // Try to get from cache first
-        if let Some(entry) = cache.get_mut(&key) {
-            entry.access();
-            
-            // Return cached value if it doesn't need refresh
-            if !entry.needs_refresh(&self.config) {
-                return Ok(entry.status);
-            }
+        self.lookup(source, utxo).await.map(|lookup| lookup.status)
+    }
+
+    /// Like `get_utxo_status`, but also reports whether a stale entry was served
+    #[cfg_attr(feature = "tracing", tracing::instrument(
+        name = "cache_lookup",
+        skip_all,
+        fields(txid = %utxo.txid, vout = utxo.vout, hit = tracing::field::Empty),
+    ))]
+    pub async fn lookup<S: UtxoStatusSource + ?Sized>(
+        &self,
+        source: &S,
+        utxo: &UtxoMeta,
+    ) -> Result<CacheLookup, BitcoinRpcError> {
+        let fresh = |status| CacheLookup { status, stale: false };
+        let key = cache_key(utxo)?;
+        if let Some(status) = self.lookup_fresh(source, &key) {
+            trace::record("hit", true);
+            return Ok(fresh(status));
         }
-        
-        // Fetch fresh status from RPC
-        let status = rpc.get_utxo_status(utxo).await?;
-        
-        // Update cache
-        if cache.len() >= self.config.max_size {
+
+        // One lookup per key goes upstream; concurrent callers wait for it
+        // and are then answered from the cache it filled
+        let flight = self.in_flight.lock().unwrap().entry(key).or_default().clone();
+        let _flight_guard = flight.lock().await;
+        if let Some(status) = self.lookup_fresh(source, &key) {
+            trace::record("hit", true);
+            return Ok(fresh(status));
+        }
+        trace::record("hit", false);
+
+        // Refreshes of expired entries count as misses too
+        let is_refresh = self.cache.lock().unwrap().contains_key(&key);
+        self.counters.misses.fetch_add(1, Ordering::Relaxed);
+        if is_refresh {
+            self.counters.refreshes.fetch_add(1, Ordering::Relaxed);
+        }
+        if let Some(metrics) = source.cache_metrics() {
+            metrics.record_cache_miss();
+        }
+
+        // Fetch fresh status without holding the cache lock across the round trip
+        let result = match source.fetch_utxo_status(utxo).await {
+            Ok(status) => {
+                self.insert(key, CacheEntry::new(utxo.clone(), status));
+                Ok(fresh(status))
+            }
+            // Remember unknown txids so repeated lookups don't hit the node
+            Err(e) if e.is_not_found() => {
+                self.insert(key, CacheEntry::not_found(utxo.clone()));
+                Ok(fresh(UtxoStatus::Invalid))
+            }
+            Err(e) if e.is_retryable() => match self.stale_status(&key) {
+                Some(status) => {
+                    self.counters.stale_served.fetch_add(1, Ordering::Relaxed);
+                    Ok(CacheLookup { status, stale: true })
+                }
+                None => Err(e),
+            },
+            Err(e) => Err(e),
+        };
+        self.in_flight.lock().unwrap().remove(&key);
+        result
+    }
+
+    /// Status of an expired entry that is still within the `stale_if_error` window
+    fn stale_status(&self, key: &CacheKey) -> Option<UtxoStatus> {
+        let window = self.config.stale_if_error?;
+        let cache = self.cache.lock().unwrap();
+        let entry = cache.get(key)?;
+        (entry.last_updated.elapsed() < window).then_some(entry.status)
+    }
+
+    /// Warm the cache for a batch of UTXOs, fetching missing or stale entries
+    /// concurrently (at most `config.prefetch_concurrency` at a time)
+    ///
+    /// Results are returned in the order of `utxos`; fresh entries are served
+    /// from the cache as usual.
+    #[cfg_attr(feature = "tracing", tracing::instrument(name = "cache_prefetch", skip_all, fields(count = utxos.len())))]
+    pub async fn prefetch<S: UtxoStatusSource + ?Sized>(
+        &self,
+        source: &S,
+        utxos: &[UtxoMeta],
+    ) -> Vec<Result<UtxoStatus, BitcoinRpcError>> {
+        let mut results: Vec<_> = stream::iter(utxos.iter().enumerate())
+            .map(|(index, utxo)| async move { (index, self.get_utxo_status(source, utxo).await) })
+            .buffer_unordered(self.config.prefetch_concurrency.max(1))
+            .collect()
+            .await;
+        results.sort_unstable_by_key(|(index, _)| *index);
+        results.into_iter().map(|(_, result)| result).collect()
+    }
+
+    fn insert(&self, key: CacheKey, entry: CacheEntry) {
+        let mut cache = self.cache.lock().unwrap();
+        if !cache.contains_key(&key) && cache.len() >= self.config.max_size {
             // Remove oldest entry if at capacity
             if let Some(oldest_key) = cache.iter()
                 .min_by_key(|(_, entry)| entry.last_accessed)
                 .map(|(k, _)| *k)
             {
                 cache.remove(&oldest_key);
+                self.counters.capacity_evictions.fetch_add(1, Ordering::Relaxed);
             }
         }
-        
-        cache.insert(key, CacheEntry::new(utxo.clone(), status));
-        Ok(status)
+
+        cache.insert(key, entry);
+    }
+
+    /// Drop the cached status of one outpoint; returns whether it was cached
+    pub async fn invalidate(&self, txid: &str, vout: u32) -> bool {
+        match outpoint_key(txid, vout) {
+            Some(key) => self.cache.lock().unwrap().remove(&key).is_some(),
+            None => false,
+        }
+    }
+
+    /// Drop the cached status of every output of `txid`; returns how many were cached
+    pub async fn invalidate_tx(&self, txid: &str) -> usize {
+        let Some((txid, _)) = outpoint_key(txid, 0) else {
+            return 0;
+        };
+        let mut cache = self.cache.lock().unwrap();
+        let before = cache.len();
+        cache.retain(|(entry_txid, _), _| *entry_txid != txid);
+        before - cache.len()
     }
 
-    /// Invalidate cache entries affected by a reorg
-    pub async fn handle_reorg(&self, _height: u32) {
+    /// Record that an outpoint was spent by our own transaction, without
+    /// waiting for the node to report it
+    ///
+    /// Only outpoints already in the cache are updated; returns whether one was.
+    pub async fn mark_spent(&self, txid: &str, vout: u32) -> bool {
+        let Some(key) = outpoint_key(txid, vout) else {
+            return false;
+        };
+        match self.cache.lock().unwrap().get_mut(&key) {
+            Some(entry) => {
+                entry.not_found = false;
+                entry.update(UtxoStatus::Spent);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Invalidate cache entries affected by a reorg starting at `height`,
+    /// after which the tip is at `tip_height`
+    ///
+    /// Entries confirmed at or above `height` are dropped. Deeper entries have
+    /// their confirmations recounted from their block height against the new
+    /// tip, so they can go down; Active ones left below
+    /// `config.min_confirmations` are demoted to Pending, which refreshes
+    /// sooner. Confirmed entries of unknown height are dropped too since they
+    /// may sit in an orphaned block.
+    #[cfg_attr(feature = "tracing", tracing::instrument(
+        name = "cache_reorg",
+        skip(self),
+        fields(dropped = tracing::field::Empty, demoted = tracing::field::Empty),
+    ))]
+    pub async fn handle_reorg(&self, height: u32, tip_height: u32) {
         let mut cache = self.cache.lock().unwrap();
-        cache.clear();
+        let before = cache.len();
+        let mut demoted = 0;
+        cache.retain(|_, entry| match entry.block_height {
+            Some(block_height) if block_height >= height || block_height > tip_height => false,
+            Some(block_height) => {
+                entry.utxo.confirmations = tip_height - block_height + 1;
+                if entry.status == UtxoStatus::Active && !entry.utxo.confirmed().meets(self.config.min_confirmations) {
+                    entry.update(UtxoStatus::Pending);
+                    demoted += 1;
+                }
+                true
+            }
+            None => entry.status == UtxoStatus::Pending,
+        });
+        trace::record("dropped", before - cache.len());
+        trace::record("demoted", demoted);
     }
 
     /// Remove spent or invalid UTXOs that have exceeded their TTL
+    #[cfg_attr(feature = "tracing", tracing::instrument(name = "cache_cleanup", skip_all, fields(evicted = tracing::field::Empty)))]
     pub async fn cleanup(&self) {
         let mut cache = self.cache.lock().unwrap();
         let config = &self.config;
         
-        let to_remove: Vec<[u8; 32]> = cache
+        let to_remove: Vec<CacheKey> = cache
             .iter()
             .filter(|(_, entry)| {
                 matches!(entry.status, UtxoStatus::Spent | UtxoStatus::Invalid) 
-                    && SystemTime::now().duration_since(entry.last_updated).unwrap() >= config.invalid_ttl
+                    && entry.needs_refresh(config)
             })
             .map(|(key, _)| *key)
             .collect();
             
+        self.counters.ttl_evictions.fetch_add(to_remove.len(), Ordering::Relaxed);
+        trace::record("evicted", to_remove.len());
         for key in to_remove {
             cache.remove(&key);
         }
@@ -163,25 +698,83 @@ impl UtxoCache {
     /// Get cache statistics
     pub async fn get_stats(&self) -> CacheStats {
         let cache = self.cache.lock().unwrap();
+        let count_status = |status| cache.values().filter(|entry| entry.status == status).count();
         CacheStats {
             total_entries: cache.len(),
-            hits: 0, // TODO: Implement hit/miss tracking
-            misses: 0,
+            hits: self.counters.hits.load(Ordering::Relaxed),
+            misses: self.counters.misses.load(Ordering::Relaxed),
+            refreshes: self.counters.refreshes.load(Ordering::Relaxed),
+            capacity_evictions: self.counters.capacity_evictions.load(Ordering::Relaxed),
+            ttl_evictions: self.counters.ttl_evictions.load(Ordering::Relaxed),
+            stale_served: self.counters.stale_served.load(Ordering::Relaxed),
+            active_entries: count_status(UtxoStatus::Active),
+            pending_entries: count_status(UtxoStatus::Pending),
+            spent_entries: count_status(UtxoStatus::Spent),
+            invalid_entries: count_status(UtxoStatus::Invalid),
+            not_found_entries: cache.values().filter(|entry| entry.not_found).count(),
+        }
+    }
+}
+
+/// Handle to the background task started by `UtxoCache::spawn_maintenance`
+///
+/// Dropping the handle aborts the task; `shutdown` lets a running cleanup pass finish first.
+#[derive(Debug)]
+pub struct MaintenanceHandle {
+    shutdown: Option<oneshot::Sender<()>>,
+    task: Option<JoinHandle<()>>,
+}
+
+impl MaintenanceHandle {
+    /// Stop the maintenance task and wait for it to exit
+    pub async fn shutdown(mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+        if let Some(task) = self.task.take() {
+            let _ = task.await;
+        }
+    }
+}
+
+impl Drop for MaintenanceHandle {
+    fn drop(&mut self) {
+        if let Some(task) = self.task.take() {
+            task.abort();
         }
     }
 }
 
 /// Statistics about the cache
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
 pub struct CacheStats {
     pub total_entries: usize,
+    /// Lookups answered from a fresh cache entry
     pub hits: usize,
+    /// Lookups that went to the upstream source, including refreshes
     pub misses: usize,
+    /// Misses caused by an expired entry rather than a missing one
+    pub refreshes: usize,
+    /// Entries dropped to make room for a new one
+    pub capacity_evictions: usize,
+    /// Spent/invalid entries dropped by `cleanup` after their TTL
+    pub ttl_evictions: usize,
+    /// Expired entries served because the refresh failed (`stale_if_error`)
+    pub stale_served: usize,
+    pub active_entries: usize,
+    pub pending_entries: usize,
+    pub spent_entries: usize,
+    pub invalid_entries: usize,
+    /// Negative entries for txids the node didn't know (included in `invalid_entries`)
+    pub not_found_entries: usize,
 }
 
 #[cfg(all(test, not(target_arch = "wasm32")))]
 mod tests {
     use super::*;
+    use crate::bitcoin::mock::{MockBitcoinNode, MockBitcoinRpcClient};
+    use crate::bitcoin::rpc::BitcoinRpcConfig;
+    use bitcoin::{Amount, ScriptBuf, TxOut};
     use std::time::Duration;
     
     #[tokio::test]
@@ -190,6 +783,7 @@ mod tests {
             max_size: 2,
             refresh_interval: Duration::from_secs(1),
             invalid_ttl: Duration::from_secs(2),
+            ..Default::default()
         };
         
         let cache = UtxoCache::new(config);
@@ -207,14 +801,14 @@ mod tests {
             2000,
         );
         
-        // Add to cache using txid_to_bytes()
-        let key1 = utxo1.txid_to_bytes().unwrap();
+        // Add to cache under their outpoint keys
+        let key1 = cache_key(&utxo1).unwrap();
         cache.cache.lock().unwrap().insert(
             key1,
             CacheEntry::new(utxo1.clone(), UtxoStatus::Active),
         );
         
-        let key2 = utxo2.txid_to_bytes().unwrap();
+        let key2 = cache_key(&utxo2).unwrap();
         cache.cache.lock().unwrap().insert(
             key2,
             CacheEntry::new(utxo2.clone(), UtxoStatus::Pending),
@@ -231,6 +825,8 @@ mod tests {
             max_size: 10,
             refresh_interval: Duration::from_millis(50),
             invalid_ttl: Duration::from_millis(100),
+            status_ttls: HashMap::new(),
+            ..Default::default()
         };
         
         let cache = UtxoCache::new(config);
@@ -248,13 +844,13 @@ mod tests {
             2000,
         );
         
-        let key1 = utxo1.txid_to_bytes().unwrap();
+        let key1 = cache_key(&utxo1).unwrap();
         cache.cache.lock().unwrap().insert(
             key1,
             CacheEntry::new(utxo1.clone(), UtxoStatus::Spent),
         );
         
-        let key2 = utxo2.txid_to_bytes().unwrap();
+        let key2 = cache_key(&utxo2).unwrap();
         cache.cache.lock().unwrap().insert(
             key2,
             CacheEntry::new(utxo2.clone(), UtxoStatus::Invalid),
@@ -269,5 +865,540 @@ mod tests {
         // Verify entries were removed
         let stats = cache.get_stats().await;
         assert_eq!(stats.total_entries, 0);
+        assert_eq!(stats.ttl_evictions, 2);
+    }
+
+    #[tokio::test]
+    async fn test_prefetch_respects_concurrency_limit() {
+        let node = Arc::new(MockBitcoinNode::new());
+        let client = MockBitcoinRpcClient::new(
+            BitcoinRpcConfig {
+                endpoint: "mock".to_string(),
+                port: 0,
+                ..Default::default()
+            },
+            node.clone(),
+        )
+        .with_latency(Duration::from_millis(20));
+
+        let utxos: Vec<UtxoMeta> = (0..20u8)
+            .map(|i| {
+                let txid = format!("{:02x}{}", i + 1, "0".repeat(62));
+                node.add_transaction(&txid, 6, vec![TxOut {
+                    value: Amount::from_sat(1000),
+                    script_pubkey: ScriptBuf::new(),
+                }], true);
+                UtxoMeta::new(txid, 0, 1000)
+            })
+            .collect();
+
+        let cache = UtxoCache::new(UtxoCacheConfig {
+            prefetch_concurrency: 4,
+            ..Default::default()
+        });
+        let results = cache.prefetch(&client, &utxos).await;
+        assert_eq!(results.len(), 20);
+        assert!(results.iter().all(|result| matches!(result, Ok(UtxoStatus::Active))));
+
+        assert_eq!(client.utxo_status_calls(), 20);
+        assert_eq!(client.max_concurrent_utxo_status_calls(), 4);
+        let stats = cache.get_stats().await;
+        assert_eq!(stats.total_entries, 20);
+        assert_eq!(stats.misses, 20);
+
+        // Everything is warm now, so later lookups never reach the node
+        for utxo in &utxos {
+            cache.get_utxo_status(&client, utxo).await.unwrap();
+        }
+        assert_eq!(client.utxo_status_calls(), 20);
+    }
+
+    #[tokio::test]
+    async fn test_mark_spent_skips_rpc() {
+        let node = Arc::new(MockBitcoinNode::new());
+        let client = MockBitcoinRpcClient::new(
+            BitcoinRpcConfig {
+                endpoint: "mock".to_string(),
+                port: 0,
+                ..Default::default()
+            },
+            node.clone(),
+        );
+        let txid = "a000000000000000000000000000000000000000000000000000000000000000";
+        let outputs = vec![
+            TxOut { value: Amount::from_sat(1000), script_pubkey: ScriptBuf::new() },
+            TxOut { value: Amount::from_sat(2000), script_pubkey: ScriptBuf::new() },
+        ];
+        node.add_transaction(txid, 6, outputs, true);
+        let change = UtxoMeta::new(txid.to_string(), 0, 1000);
+        let treasury = UtxoMeta::new(txid.to_string(), 1, 2000);
+
+        let cache = UtxoCache::new(UtxoCacheConfig::default());
+        assert_eq!(cache.get_utxo_status(&client, &treasury).await.unwrap(), UtxoStatus::Active);
+        assert_eq!(cache.get_utxo_status(&client, &change).await.unwrap(), UtxoStatus::Active);
+        assert_eq!(client.utxo_status_calls(), 2);
+
+        // Our spender consumed the treasury output; the node hasn't seen it yet
+        assert!(cache.mark_spent(txid, 1).await);
+        assert_eq!(cache.get_utxo_status(&client, &treasury).await.unwrap(), UtxoStatus::Spent);
+        assert_eq!(client.utxo_status_calls(), 2);
+
+        // Unknown outpoints are left alone
+        assert!(!cache.mark_spent(txid, 7).await);
+        assert!(!cache.mark_spent("not-a-txid", 0).await);
+
+        assert!(cache.invalidate(txid, 1).await);
+        assert!(!cache.invalidate(txid, 1).await);
+        assert_eq!(cache.invalidate_tx(txid).await, 1);
+        assert_eq!(cache.get_stats().await.total_entries, 0);
+    }
+
+    #[tokio::test]
+    async fn test_stale_if_error_during_outage() {
+        let node = Arc::new(MockBitcoinNode::new());
+        let client = MockBitcoinRpcClient::new(
+            BitcoinRpcConfig {
+                endpoint: "mock".to_string(),
+                port: 0,
+                ..Default::default()
+            },
+            node.clone(),
+        );
+        let txid = "a000000000000000000000000000000000000000000000000000000000000000";
+        node.add_transaction(txid, 6, vec![TxOut {
+            value: Amount::from_sat(1000),
+            script_pubkey: ScriptBuf::new(),
+        }], true);
+        let utxo = UtxoMeta::new(txid.to_string(), 0, 1000);
+
+        let cache = UtxoCache::new(UtxoCacheConfig {
+            status_ttls: HashMap::from([(UtxoStatus::Active, Duration::from_millis(20))]),
+            stale_if_error: Some(Duration::from_millis(200)),
+            ..Default::default()
+        });
+        cache.get_utxo_status(&client, &utxo).await.unwrap();
+
+        // The entry expires while the node is down: the last known status is served
+        node.set_offline(true);
+        tokio::time::sleep(Duration::from_millis(30)).await;
+        let lookup = cache.lookup(&client, &utxo).await.unwrap();
+        assert_eq!(lookup, CacheLookup { status: UtxoStatus::Active, stale: true });
+        assert_eq!(cache.get_stats().await.stale_served, 1);
+
+        // Past the stale window the outage surfaces as an error
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        let result = cache.lookup(&client, &utxo).await;
+        assert!(matches!(result, Err(BitcoinRpcError::ConnectionFailed(_))));
+        assert_eq!(cache.get_stats().await.stale_served, 1);
+
+        // Once the node is back, lookups are fresh again
+        node.set_offline(false);
+        let lookup = cache.lookup(&client, &utxo).await.unwrap();
+        assert_eq!(lookup, CacheLookup { status: UtxoStatus::Active, stale: false });
+    }
+
+    /// Unique path in the temp dir for a test's cache file
+    fn temp_cache_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("utxo-cache-{}-{}.bin", name, std::process::id()))
+    }
+
+    #[tokio::test]
+    async fn test_save_and_load_round_trip() {
+        let path = temp_cache_path("round-trip");
+        let cache = UtxoCache::new(UtxoCacheConfig::default());
+
+        let mut active = UtxoMeta::new(
+            "a000000000000000000000000000000000000000000000000000000000000000".to_string(),
+            0,
+            1000,
+        );
+        active.block_height = Some(100);
+        let spent = UtxoMeta::new(
+            "b000000000000000000000000000000000000000000000000000000000000000".to_string(),
+            1,
+            2000,
+        );
+        let unknown = UtxoMeta::new(
+            "c000000000000000000000000000000000000000000000000000000000000000".to_string(),
+            0,
+            3000,
+        );
+        cache.insert(cache_key(&active).unwrap(), CacheEntry::new(active.clone(), UtxoStatus::Active));
+        cache.insert(cache_key(&spent).unwrap(), CacheEntry::new(spent.clone(), UtxoStatus::Spent));
+        cache.insert(cache_key(&unknown).unwrap(), CacheEntry::not_found(unknown.clone()));
+        cache.save_to(&path).unwrap();
+
+        let restored = UtxoCache::load_from(&path, UtxoCacheConfig::default());
+        let stats = restored.get_stats().await;
+        assert_eq!(stats.total_entries, 3);
+        assert_eq!(stats.active_entries, 1);
+        assert_eq!(stats.spent_entries, 1);
+        assert_eq!(stats.not_found_entries, 1);
+        {
+            let entries = restored.cache.lock().unwrap();
+            let entry = &entries[&cache_key(&active).unwrap()];
+            assert_eq!(entry.utxo, active);
+            assert_eq!(entry.block_height, Some(100));
+        }
+
+        // Spent entries have expired under a zero TTL and are skipped on load
+        let restored = UtxoCache::load_from(&path, UtxoCacheConfig {
+            invalid_ttl: Duration::ZERO,
+            ..Default::default()
+        });
+        let stats = restored.get_stats().await;
+        assert_eq!(stats.total_entries, 2);
+        assert_eq!(stats.spent_entries, 0);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_load_ignores_bad_files() {
+        let missing = UtxoCache::load_from(temp_cache_path("missing"), UtxoCacheConfig::default());
+        assert_eq!(missing.get_stats().await.total_entries, 0);
+
+        let path = temp_cache_path("corrupt");
+        std::fs::write(&path, b"OVTC\x01garbage").unwrap();
+        let corrupt = UtxoCache::load_from(&path, UtxoCacheConfig::default());
+        assert_eq!(corrupt.get_stats().await.total_entries, 0);
+
+        // A valid file from a different format version is not trusted either
+        let cache = UtxoCache::new(UtxoCacheConfig::default());
+        let utxo = UtxoMeta::new(
+            "a000000000000000000000000000000000000000000000000000000000000000".to_string(),
+            0,
+            1000,
+        );
+        cache.insert(cache_key(&utxo).unwrap(), CacheEntry::new(utxo, UtxoStatus::Active));
+        cache.save_to(&path).unwrap();
+        let mut data = std::fs::read(&path).unwrap();
+        data[CACHE_FILE_MAGIC.len()] = CACHE_FILE_VERSION + 1;
+        std::fs::write(&path, data).unwrap();
+        let mismatched = UtxoCache::load_from(&path, UtxoCacheConfig::default());
+        assert_eq!(mismatched.get_stats().await.total_entries, 0);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_maintenance_task_cleans_expired_entries() {
+        let cache = Arc::new(UtxoCache::new(UtxoCacheConfig {
+            invalid_ttl: Duration::from_secs(10),
+            status_ttls: HashMap::new(),
+            ..Default::default()
+        }));
+
+        let spent = UtxoMeta::new(
+            "c000000000000000000000000000000000000000000000000000000000000000".to_string(),
+            0,
+            1000,
+        );
+        let pending = UtxoMeta::new(
+            "d000000000000000000000000000000000000000000000000000000000000000".to_string(),
+            0,
+            1000,
+        );
+        cache.insert(cache_key(&spent).unwrap(), CacheEntry::new(spent, UtxoStatus::Spent));
+        cache.insert(cache_key(&pending).unwrap(), CacheEntry::new(pending, UtxoStatus::Pending));
+
+        let maintenance = cache.clone().spawn_maintenance(Duration::from_secs(5));
+
+        // Not expired yet at the first pass
+        tokio::time::sleep(Duration::from_secs(6)).await;
+        assert_eq!(cache.get_stats().await.total_entries, 2);
+
+        // The pass at 10s drops the spent entry; pending entries are never cleaned up
+        tokio::time::sleep(Duration::from_secs(10)).await;
+        let stats = cache.get_stats().await;
+        assert_eq!(stats.total_entries, 1);
+        assert_eq!(stats.pending_entries, 1);
+        assert_eq!(stats.ttl_evictions, 1);
+
+        maintenance.shutdown().await;
     }
-} 
\ No newline at end of file
+
+    #[tokio::test(start_paused = true)]
+    async fn test_maintenance_stops_after_shutdown() {
+        let cache = Arc::new(UtxoCache::new(UtxoCacheConfig {
+            invalid_ttl: Duration::from_secs(1),
+            status_ttls: HashMap::new(),
+            ..Default::default()
+        }));
+        let maintenance = cache.clone().spawn_maintenance(Duration::from_secs(1));
+        maintenance.shutdown().await;
+
+        let utxo = UtxoMeta::new(
+            "c000000000000000000000000000000000000000000000000000000000000000".to_string(),
+            0,
+            1000,
+        );
+        cache.insert(cache_key(&utxo).unwrap(), CacheEntry::new(utxo, UtxoStatus::Invalid));
+
+        tokio::time::sleep(Duration::from_secs(5)).await;
+        assert_eq!(cache.get_stats().await.total_entries, 1);
+        // The task no longer holds a reference to the cache
+        assert_eq!(Arc::strong_count(&cache), 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_shared_starts_maintenance_from_config() {
+        let (_, maintenance) = UtxoCache::shared(UtxoCacheConfig::default());
+        assert!(maintenance.is_none());
+
+        let (_, maintenance) = UtxoCache::shared(UtxoCacheConfig {
+            maintenance_interval: Some(Duration::from_secs(30)),
+            ..Default::default()
+        });
+        maintenance.expect("maintenance task").shutdown().await;
+    }
+
+    fn mock_source() -> (Arc<MockBitcoinNode>, MockBitcoinRpcClient) {
+        let node = Arc::new(MockBitcoinNode::new());
+        let client = MockBitcoinRpcClient::new(BitcoinRpcConfig::default(), node.clone());
+        (node, client)
+    }
+
+    fn add_mock_tx(node: &MockBitcoinNode, txid: &str, confirmations: u32) {
+        let output = TxOut {
+            value: Amount::from_sat(1000),
+            script_pubkey: ScriptBuf::new(),
+        };
+        node.add_transaction(txid, confirmations, vec![output], true);
+    }
+
+    #[tokio::test]
+    async fn test_cache_stats_counters() {
+        let (node, client) = mock_source();
+        let cache = UtxoCache::new(UtxoCacheConfig {
+            max_size: 2,
+            refresh_interval: Duration::from_millis(50),
+            invalid_ttl: Duration::from_secs(60),
+            status_ttls: HashMap::new(),
+            ..Default::default()
+        });
+
+        let txids = [
+            "a000000000000000000000000000000000000000000000000000000000000000",
+            "b000000000000000000000000000000000000000000000000000000000000000",
+            "c000000000000000000000000000000000000000000000000000000000000000",
+        ];
+        add_mock_tx(&node, txids[0], 6);
+        add_mock_tx(&node, txids[1], 0);
+        add_mock_tx(&node, txids[2], 6);
+        node.spend_utxo(txids[2], 0);
+        let utxos: Vec<UtxoMeta> = txids.iter().map(|txid| UtxoMeta::new(txid.to_string(), 0, 1000)).collect();
+
+        // Two cold lookups, then two warm ones
+        assert_eq!(cache.get_utxo_status(&client, &utxos[0]).await.unwrap(), UtxoStatus::Active);
+        assert_eq!(cache.get_utxo_status(&client, &utxos[1]).await.unwrap(), UtxoStatus::Pending);
+        cache.get_utxo_status(&client, &utxos[0]).await.unwrap();
+        cache.get_utxo_status(&client, &utxos[1]).await.unwrap();
+
+        // A third UTXO evicts the least recently used entry
+        assert_eq!(cache.get_utxo_status(&client, &utxos[2]).await.unwrap(), UtxoStatus::Spent);
+
+        let stats = cache.get_stats().await;
+        assert_eq!(stats.hits, 2);
+        assert_eq!(stats.misses, 3);
+        assert_eq!(stats.refreshes, 0);
+        assert_eq!(stats.capacity_evictions, 1);
+        assert_eq!(stats.total_entries, 2);
+        assert_eq!(stats.pending_entries, 1);
+        assert_eq!(stats.spent_entries, 1);
+        assert_eq!(stats.active_entries, 0);
+
+        // Once the refresh interval passes, the pending entry is re-fetched in place
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        cache.get_utxo_status(&client, &utxos[1]).await.unwrap();
+
+        let stats = cache.get_stats().await;
+        assert_eq!(stats.misses, 4);
+        assert_eq!(stats.refreshes, 1);
+        assert_eq!(stats.capacity_evictions, 1);
+    }
+
+    #[tokio::test]
+    async fn test_outputs_of_same_tx_cached_independently() {
+        let (node, client) = mock_source();
+        let cache = UtxoCache::new(UtxoCacheConfig::default());
+        let txid = "e000000000000000000000000000000000000000000000000000000000000000";
+        let outputs = vec![
+            TxOut { value: Amount::from_sat(1000), script_pubkey: ScriptBuf::new() },
+            TxOut { value: Amount::from_sat(2000), script_pubkey: ScriptBuf::new() },
+        ];
+        node.add_transaction(txid, 6, outputs, true);
+        node.spend_utxo(txid, 1);
+
+        let change = UtxoMeta::new(txid.to_string(), 0, 1000);
+        let spent = UtxoMeta::new(txid.to_string(), 1, 2000);
+        assert_eq!(cache.get_utxo_status(&client, &change).await.unwrap(), UtxoStatus::Active);
+        assert_eq!(cache.get_utxo_status(&client, &spent).await.unwrap(), UtxoStatus::Spent);
+
+        // Both are now served from the cache, each with its own status
+        assert_eq!(cache.get_utxo_status(&client, &change).await.unwrap(), UtxoStatus::Active);
+        assert_eq!(cache.get_utxo_status(&client, &spent).await.unwrap(), UtxoStatus::Spent);
+
+        let stats = cache.get_stats().await;
+        assert_eq!(stats.total_entries, 2);
+        assert_eq!(stats.hits, 2);
+    }
+
+    #[tokio::test]
+    async fn test_reorg_only_invalidates_entries_above_fork_point() {
+        let cache = UtxoCache::new(UtxoCacheConfig::default());
+
+        let mut deep = UtxoMeta::new(
+            "f000000000000000000000000000000000000000000000000000000000000000".to_string(),
+            0,
+            1000,
+        );
+        deep.update_block_info(90, "00".repeat(32));
+        deep.confirmations = 20;
+
+        let mut shallow = UtxoMeta::new(
+            "f100000000000000000000000000000000000000000000000000000000000000".to_string(),
+            0,
+            1000,
+        );
+        shallow.update_block_info(105, "11".repeat(32));
+        shallow.confirmations = 5;
+
+        let deep_key = cache_key(&deep).unwrap();
+        let shallow_key = cache_key(&shallow).unwrap();
+        cache.insert(deep_key, CacheEntry::new(deep.clone(), UtxoStatus::Active));
+        cache.insert(shallow_key, CacheEntry::new(shallow.clone(), UtxoStatus::Active));
+
+        // Rolled back to block 99
+        cache.handle_reorg(100, 99).await;
+
+        let entries = cache.cache.lock().unwrap();
+        assert!(!entries.contains_key(&shallow_key));
+        let deep_entry = entries.get(&deep_key).expect("deep entry must survive the reorg");
+        assert_eq!(deep_entry.status, UtxoStatus::Active);
+        // Blocks 90..=99 remain, so at most 10 confirmations
+        assert_eq!(deep_entry.utxo.confirmations, 10);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_reorg_demotes_shallow_active_entries() {
+        let (node, client) = mock_source();
+        node.mine_blocks(110);
+        let cache = UtxoCache::new(UtxoCacheConfig { min_confirmations: 6, ..Default::default() });
+        let txid = "d000000000000000000000000000000000000000000000000000000000000000";
+        // Mined at 104
+        add_mock_tx(&node, txid, 7);
+        let mut utxo = UtxoMeta::new(txid.to_string(), 0, 1000);
+        utxo.update_block_info(104, "44".repeat(32));
+        assert_eq!(cache.get_utxo_status(&client, &utxo).await.unwrap(), UtxoStatus::Active);
+
+        // Blocks 108 and up are replaced, leaving the tip at 108 and the UTXO 5 deep
+        node.simulate_reorg(2);
+        cache.handle_reorg(108, node.get_block_height()).await;
+        {
+            let entries = cache.cache.lock().unwrap();
+            let entry = &entries[&cache_key(&utxo).unwrap()];
+            assert_eq!((entry.status, entry.utxo.confirmations), (UtxoStatus::Pending, 5));
+        }
+        assert_eq!(cache.get_utxo_status(&client, &utxo).await.unwrap(), UtxoStatus::Pending);
+
+        // Pending entries refresh sooner; by then the chain has grown back
+        node.mine_blocks(1);
+        tokio::time::advance(Duration::from_secs(31)).await;
+        assert_eq!(cache.get_utxo_status(&client, &utxo).await.unwrap(), UtxoStatus::Active);
+    }
+
+    #[test]
+    fn test_ttl_for_status() {
+        let config = UtxoCacheConfig::default();
+        assert_eq!(config.ttl_for(UtxoStatus::Pending), Duration::from_secs(30));
+        assert_eq!(config.ttl_for(UtxoStatus::Active), Duration::from_secs(600));
+        assert_eq!(config.ttl_for(UtxoStatus::Spent), config.invalid_ttl);
+
+        let config = UtxoCacheConfig {
+            status_ttls: HashMap::new(),
+            ..config
+        };
+        assert_eq!(config.ttl_for(UtxoStatus::Active), config.refresh_interval);
+    }
+
+    #[tokio::test]
+    async fn test_missing_txid_is_negatively_cached() {
+        let address = mockito::server_url().replace("http://", "");
+        let (host, port) = address.rsplit_once(':').unwrap();
+        let client = crate::bitcoin::rpc::BitcoinRpcClient::new(BitcoinRpcConfig {
+            endpoint: host.to_string(),
+            port: port.parse().unwrap(),
+            ..Default::default()
+        }).unwrap();
+
+        let not_found = mockito::mock("POST", "/")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(r#"{
+                "result": null,
+                "error": { "code": -5, "message": "No such mempool or blockchain transaction" },
+                "id": "1"
+            }"#)
+            .expect(1)
+            .create();
+
+        let cache = UtxoCache::new(UtxoCacheConfig::default());
+        let bogus = UtxoMeta::new(
+            "0badbadbadbadbadbadbadbadbadbadbadbadbadbadbadbadbadbadbadbadbad".to_string(),
+            0,
+            1000,
+        );
+
+        assert_eq!(cache.get_utxo_status(&client, &bogus).await.unwrap(), UtxoStatus::Invalid);
+        // Within not_found_ttl the second lookup never reaches the node
+        assert_eq!(cache.get_utxo_status(&client, &bogus).await.unwrap(), UtxoStatus::Invalid);
+        not_found.assert();
+
+        let stats = cache.get_stats().await;
+        assert_eq!(stats.not_found_entries, 1);
+        assert_eq!(stats.hits, 1);
+        assert_eq!(stats.misses, 1);
+    }
+
+    #[test]
+    fn test_builder_sets_fields() {
+        let config = UtxoCacheBuilder::new()
+            .with_max_size(100)
+            .with_refresh_interval(Duration::from_secs(5))
+            .with_invalid_ttl(Duration::from_secs(50))
+            .with_status_ttl(UtxoStatus::Spent, Duration::from_secs(500))
+            .with_min_confirmations(3)
+            .build_config()
+            .unwrap();
+        assert_eq!((config.max_size, config.min_confirmations), (100, 3));
+        assert_eq!(config.ttl_for(UtxoStatus::Active), Duration::from_secs(600));
+        assert_eq!(config.ttl_for(UtxoStatus::Invalid), Duration::from_secs(50));
+        assert_eq!(config.ttl_for(UtxoStatus::Spent), Duration::from_secs(500));
+        assert!(UtxoCacheConfig::default().validate().is_ok());
+    }
+
+    #[test]
+    fn test_builder_rejects_invalid_combinations() {
+        let build = |builder: UtxoCacheBuilder| builder.build().err();
+        assert!(matches!(build(UtxoCacheBuilder::new().with_max_size(0)), Some(ConfigError::ZeroCacheSize)));
+        assert!(matches!(
+            build(UtxoCacheBuilder::new().with_refresh_interval(Duration::ZERO)),
+            Some(ConfigError::ZeroDuration("refresh_interval"))
+        ));
+        assert!(matches!(
+            build(UtxoCacheBuilder::new().with_refresh_interval(Duration::from_secs(60)).with_invalid_ttl(Duration::from_secs(30))),
+            Some(ConfigError::RefreshIntervalExceedsInvalidTtl { refresh_interval, invalid_ttl })
+                if refresh_interval == Duration::from_secs(60) && invalid_ttl == Duration::from_secs(30)
+        ));
+        assert!(matches!(
+            build(UtxoCacheBuilder::new().with_maintenance_interval(Duration::ZERO)),
+            Some(ConfigError::ZeroDuration("maintenance_interval"))
+        ));
+        assert!(matches!(build(UtxoCacheBuilder::new().with_prefetch_concurrency(0)), Some(ConfigError::ZeroPrefetchConcurrency)));
+
+        // Equal intervals are fine
+        let same = Duration::from_secs(30);
+        assert!(UtxoCacheBuilder::new().with_refresh_interval(same).with_invalid_ttl(same).build().is_ok());
+    }
+}
@@ -0,0 +1,112 @@
+use bitcoin::BlockHash;
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// How long a fetched tip is reused unless configured otherwise
+pub const DEFAULT_TIP_CACHE_TTL: Duration = Duration::from_secs(5);
+
+/// The node's tip height and hash, reused for `ttl` after they were fetched
+/// so hot loops don't ask the node every time
+///
+/// A height fetched on its own is cached separately from a (height, hash)
+/// pair, since the pair has to come from one consistent lookup. A zero `ttl`
+/// caches nothing.
+#[derive(Debug)]
+pub struct TipCache {
+    ttl: Duration,
+    state: Mutex<TipState>,
+}
+
+#[derive(Debug, Default)]
+struct TipState {
+    height: Option<(u64, Instant)>,
+    tip: Option<((u64, BlockHash), Instant)>,
+}
+
+impl TipCache {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            state: Mutex::new(TipState::default()),
+        }
+    }
+
+    fn is_fresh(&self, fetched: Instant) -> bool {
+        fetched.elapsed() < self.ttl
+    }
+
+    /// Most recently cached tip height, by either kind of lookup, if still fresh
+    pub fn height(&self) -> Option<u64> {
+        let state = self.state.lock().unwrap();
+        state.height.filter(|(_, fetched)| self.is_fresh(*fetched)).map(|(height, _)| height)
+    }
+
+    /// Cached tip height and hash, if still fresh
+    pub fn tip(&self) -> Option<(u64, BlockHash)> {
+        let state = self.state.lock().unwrap();
+        state.tip.filter(|(_, fetched)| self.is_fresh(*fetched)).map(|(tip, _)| tip)
+    }
+
+    pub fn store_height(&self, height: u64) {
+        self.state.lock().unwrap().height = Some((height, Instant::now()));
+    }
+
+    pub fn store_tip(&self, height: u64, hash: BlockHash) {
+        let mut state = self.state.lock().unwrap();
+        let now = Instant::now();
+        state.height = Some((height, now));
+        state.tip = Some(((height, hash), now));
+    }
+
+    /// Forget the cached tip, e.g. after a reorg
+    pub fn invalidate(&self) {
+        *self.state.lock().unwrap() = TipState::default();
+    }
+}
+
+impl Default for TipCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_TIP_CACHE_TTL)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bitcoin::hashes::Hash;
+
+    #[tokio::test(start_paused = true)]
+    async fn test_entries_expire_after_ttl() {
+        let cache = TipCache::new(Duration::from_secs(5));
+        let hash = BlockHash::from_byte_array([7; 32]);
+        assert_eq!(cache.height(), None);
+
+        cache.store_height(100);
+        assert_eq!(cache.height(), Some(100));
+        // A bare height says nothing about the hash
+        assert_eq!(cache.tip(), None);
+
+        tokio::time::advance(Duration::from_secs(3)).await;
+        cache.store_tip(101, hash);
+        assert_eq!(cache.height(), Some(101));
+        assert_eq!(cache.tip(), Some((101, hash)));
+
+        tokio::time::advance(Duration::from_secs(5)).await;
+        assert_eq!(cache.height(), None);
+        assert_eq!(cache.tip(), None);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_invalidate_and_zero_ttl() {
+        let cache = TipCache::default();
+        cache.store_tip(100, BlockHash::all_zeros());
+        cache.invalidate();
+        assert_eq!(cache.height(), None);
+        assert_eq!(cache.tip(), None);
+
+        let uncached = TipCache::new(Duration::ZERO);
+        uncached.store_height(100);
+        assert_eq!(uncached.height(), None);
+    }
+}
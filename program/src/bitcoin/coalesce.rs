@@ -0,0 +1,193 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::sync::watch;
+use tokio::time::Instant;
+
+use super::error::BitcoinRpcError;
+
+/// Raw JSON-RPC result, shared before each caller deserializes its own copy
+pub type SharedResult = Result<serde_json::Value, BitcoinRpcError>;
+
+/// Single-flight for identical requests
+///
+/// The first caller for a key makes the call. Callers arriving while it is in
+/// flight wait for it and get a clone of its result, as do callers arriving up
+/// to `window` after it succeeded. Errors are shared with the waiters but
+/// never kept.
+#[derive(Debug)]
+pub struct RequestCoalescer {
+    window: Duration,
+    slots: Mutex<HashMap<String, Slot>>,
+}
+
+#[derive(Debug)]
+enum Slot {
+    InFlight(watch::Receiver<Option<SharedResult>>),
+    Done { value: serde_json::Value, finished: Instant },
+}
+
+/// The leading call for a key; clears its slot if dropped before finishing,
+/// so a cancelled call leaves nobody waiting on it
+struct Flight<'a> {
+    coalescer: &'a RequestCoalescer,
+    key: String,
+    finished: bool,
+}
+
+impl Drop for Flight<'_> {
+    fn drop(&mut self) {
+        if !self.finished {
+            self.coalescer.slots.lock().unwrap().remove(&self.key);
+        }
+    }
+}
+
+impl RequestCoalescer {
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            slots: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Key for `method` called with `params`, which are canonicalized through
+    /// `serde_json::Value` so equal params always give the same key
+    pub fn key<T: serde::Serialize>(method: &str, params: &T) -> Result<String, BitcoinRpcError> {
+        let params = serde_json::to_value(params)
+            .map_err(|e| BitcoinRpcError::InvalidResponse(format!("Unserializable params: {}", e)))?;
+        Ok(format!("{}:{}", method, params))
+    }
+
+    /// Result of `call` for `key`, and whether it came from another caller's call
+    pub async fn run<F, Fut>(&self, key: String, call: F) -> (SharedResult, bool)
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = SharedResult>,
+    {
+        let joined = {
+            let mut slots = self.slots.lock().unwrap();
+            slots.retain(|_, slot| match slot {
+                Slot::Done { finished, .. } => finished.elapsed() <= self.window,
+                Slot::InFlight(_) => true,
+            });
+            match slots.get(&key) {
+                Some(Slot::Done { value, .. }) => return (Ok(value.clone()), true),
+                Some(Slot::InFlight(receiver)) => Err(receiver.clone()),
+                None => {
+                    let (sender, receiver) = watch::channel(None);
+                    slots.insert(key.clone(), Slot::InFlight(receiver));
+                    Ok(sender)
+                }
+            }
+        };
+
+        let sender = match joined {
+            Ok(sender) => sender,
+            Err(mut receiver) => {
+                let shared = receiver.wait_for(Option::is_some).await.map(|result| result.clone());
+                return match shared {
+                    Ok(Some(result)) => (result, true),
+                    // The leading call was cancelled, so make our own
+                    _ => (call().await, false),
+                };
+            }
+        };
+
+        let mut flight = Flight { coalescer: self, key, finished: false };
+        let result = call().await;
+        {
+            let mut slots = self.slots.lock().unwrap();
+            match &result {
+                Ok(value) if !self.window.is_zero() => {
+                    slots.insert(flight.key.clone(), Slot::Done { value: value.clone(), finished: Instant::now() });
+                }
+                _ => {
+                    slots.remove(&flight.key);
+                }
+            }
+        }
+        flight.finished = true;
+        // Nobody may be waiting, which is fine
+        let _ = sender.send(Some(result.clone()));
+        (result, false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tokio::time::sleep;
+
+    async fn slow_call(calls: &AtomicUsize, value: serde_json::Value) -> SharedResult {
+        calls.fetch_add(1, Ordering::SeqCst);
+        sleep(Duration::from_millis(50)).await;
+        Ok(value)
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_callers_share_one_call() {
+        let coalescer = RequestCoalescer::new(Duration::ZERO);
+        let calls = AtomicUsize::new(0);
+        let key = RequestCoalescer::key("getrawtransaction", &vec!["ab"]).unwrap();
+
+        let results = futures::future::join_all(
+            (0..5).map(|_| coalescer.run(key.clone(), || slow_call(&calls, json!("tx")))),
+        )
+        .await;
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert_eq!(results.iter().filter(|(_, shared)| *shared).count(), 4);
+        assert!(results.iter().all(|(result, _)| matches!(result, Ok(value) if *value == json!("tx"))));
+
+        // Without a window, finished calls aren't reused
+        coalescer.run(key, || slow_call(&calls, json!("tx"))).await.0.unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_window_and_keys() {
+        let coalescer = RequestCoalescer::new(Duration::from_secs(60));
+        let calls = AtomicUsize::new(0);
+        let first = RequestCoalescer::key("getblockhash", &vec![1]).unwrap();
+        let second = RequestCoalescer::key("getblockhash", &vec![2]).unwrap();
+        assert_ne!(first, second);
+
+        coalescer.run(first.clone(), || slow_call(&calls, json!(1))).await.0.unwrap();
+        let (result, shared) = coalescer.run(first, || slow_call(&calls, json!(1))).await;
+        assert!(shared);
+        assert_eq!(result.unwrap(), json!(1));
+        coalescer.run(second, || slow_call(&calls, json!(2))).await.0.unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+        // Errors are not kept for the window
+        let failing = RequestCoalescer::key("getblockhash", &vec![3]).unwrap();
+        for _ in 0..2 {
+            let (result, shared) = coalescer.run(failing.clone(), || async { Err(BitcoinRpcError::Timeout) }).await;
+            assert!(result.is_err() && !shared);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_leader_releases_waiters() {
+        let coalescer = RequestCoalescer::new(Duration::ZERO);
+        let calls = AtomicUsize::new(0);
+        let key = RequestCoalescer::key("getbestblockhash", &Vec::<String>::new()).unwrap();
+
+        let leader = coalescer.run(key.clone(), || async {
+            sleep(Duration::from_secs(60)).await;
+            Ok(json!("never"))
+        });
+        let follower = async {
+            sleep(Duration::from_millis(10)).await;
+            coalescer.run(key.clone(), || slow_call(&calls, json!("tip"))).await
+        };
+        // The leader is dropped once the timeout fires, while the follower waits on it
+        let (_, (result, shared)) = tokio::join!(tokio::time::timeout(Duration::from_millis(20), leader), follower);
+        assert!(!shared);
+        assert_eq!(result.unwrap(), json!("tip"));
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+}
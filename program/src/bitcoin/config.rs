@@ -0,0 +1,81 @@
+use std::env;
+use std::str::FromStr;
+use std::time::Duration;
+
+use crate::bitcoin::error::BitcoinRpcError;
+use crate::network_config::NetworkConfigError;
+
+/// Full node URL, taking precedence over the host and port
+pub const BITCOIN_RPC_URL_ENV: &str = "BITCOIN_RPC_URL";
+pub const BITCOIN_RPC_HOST_ENV: &str = "BITCOIN_RPC_HOST";
+pub const BITCOIN_RPC_PORT_ENV: &str = "BITCOIN_RPC_PORT";
+pub const BITCOIN_RPC_USER_ENV: &str = "BITCOIN_RPC_USER";
+pub const BITCOIN_RPC_PASSWORD_ENV: &str = "BITCOIN_RPC_PASSWORD";
+pub const BITCOIN_RPC_WALLET_ENV: &str = "BITCOIN_RPC_WALLET";
+/// Whole-request timeout, in seconds
+pub const BITCOIN_RPC_TIMEOUT_ENV: &str = "BITCOIN_RPC_TIMEOUT_SECS";
+pub const BITCOIN_RPC_MAX_RPS_ENV: &str = "BITCOIN_RPC_MAX_RPS";
+pub const ELECTRS_URL_ENV: &str = "ELECTRS_URL";
+pub const MIN_CONFIRMATIONS_ENV: &str = "BITCOIN_MIN_CONFIRMATIONS";
+pub const UTXO_CACHE_MAX_SIZE_ENV: &str = "UTXO_CACHE_MAX_SIZE";
+/// In seconds, like the other cache durations
+pub const UTXO_CACHE_REFRESH_ENV: &str = "UTXO_CACHE_REFRESH_SECS";
+pub const UTXO_CACHE_INVALID_TTL_ENV: &str = "UTXO_CACHE_INVALID_TTL_SECS";
+pub const UTXO_CACHE_MAINTENANCE_ENV: &str = "UTXO_CACHE_MAINTENANCE_SECS";
+
+/// Why a client or cache configuration was rejected
+#[derive(Debug, Clone, thiserror::Error)]
+pub enum ConfigError {
+    #[error("max_size must be at least 1")]
+    ZeroCacheSize,
+    #[error("refresh_interval {refresh_interval:?} is longer than invalid_ttl {invalid_ttl:?}, so spent and invalid entries would expire before active ones refresh")]
+    RefreshIntervalExceedsInvalidTtl { refresh_interval: Duration, invalid_ttl: Duration },
+    #[error("{0} must be longer than zero")]
+    ZeroDuration(&'static str),
+    #[error("prefetch_concurrency must be at least 1")]
+    ZeroPrefetchConcurrency,
+    #[error("No node to connect to: set an endpoint or a url")]
+    MissingEndpoint,
+    /// Why the node URL was rejected
+    #[error("{0}")]
+    InvalidUrl(String),
+    #[error("connect_timeout {connect_timeout:?} is longer than request_timeout {request_timeout:?}, which includes connecting")]
+    ConnectTimeoutExceedsRequestTimeout { connect_timeout: Duration, request_timeout: Duration },
+    #[error("max_requests_per_second must be at least 1; leave it unset for no limit")]
+    ZeroRateLimit,
+    #[error("electrs_fallback is set without an electrs_endpoint to fall back to")]
+    FallbackWithoutElectrs,
+    #[error("A username was given without a password, or a password without a username")]
+    IncompleteCredentials,
+    #[error("min_confirmations must be at least 1")]
+    ZeroConfirmations,
+    #[error("{var}={value:?} is not a valid {expected}")]
+    InvalidEnvVar { var: &'static str, value: String, expected: &'static str },
+    #[error(transparent)]
+    Network(#[from] NetworkConfigError),
+    /// The configuration was valid, but the HTTP client couldn't be built from it
+    #[error(transparent)]
+    Client(#[from] BitcoinRpcError),
+}
+
+/// `var` parsed as `T`, ignoring surrounding whitespace; None when unset or blank
+pub(crate) fn env_value<T: FromStr>(var: &'static str, expected: &'static str) -> Result<Option<T>, ConfigError> {
+    match env::var(var) {
+        Ok(value) if !value.trim().is_empty() => value
+            .trim()
+            .parse()
+            .map(Some)
+            .map_err(|_| ConfigError::InvalidEnvVar { var, value, expected }),
+        _ => Ok(None),
+    }
+}
+
+/// `var` as a whole number of seconds
+pub(crate) fn env_secs(var: &'static str) -> Result<Option<Duration>, ConfigError> {
+    Ok(env_value(var, "number of seconds")?.map(Duration::from_secs))
+}
+
+/// `var` as is, for values such as passwords where whitespace may matter; None when unset or empty
+pub(crate) fn env_string(var: &'static str) -> Option<String> {
+    env::var(var).ok().filter(|value| !value.is_empty())
+}
@@ -0,0 +1,235 @@
+use arch_program::{msg, program_error::ProgramError};
+use std::time::Duration;
+
+/// Bitcoin Core error code for unknown transactions, blocks and mempool entries
+pub const RPC_INVALID_ADDRESS_OR_KEY: i32 = -5;
+/// Bitcoin Core error code while the node is still loading its block index
+pub const RPC_IN_WARMUP: i32 = -28;
+
+/// Errors talking to a Bitcoin node or indexer
+///
+/// `code()` gives each kind a stable number in 2000-2099 for `ProgramError::Custom`.
+#[derive(Debug, Clone, thiserror::Error)]
+pub enum BitcoinRpcError {
+    #[error("RPC connection failed: {0}")]
+    ConnectionFailed(String),
+    #[error("Invalid credentials")]
+    AuthError,
+    #[error("Timeout error")]
+    Timeout,
+    #[error("Rate limited by node")]
+    RateLimited { retry_after: Option<Duration> },
+    #[error("Transaction not found: {0}")]
+    TxNotFound(String),
+    #[error("Insufficient confirmations: required {required}, got {actual}")]
+    InsufficientConfirmations { required: u32, actual: u32 },
+    #[error("Invalid response: {0}")]
+    InvalidResponse(String),
+    /// The node answered the call with a JSON-RPC error other than "not found"
+    #[error("Node error {code}: {message}")]
+    NodeError { code: i32, message: String },
+    #[error("Not implemented in WebAssembly")]
+    NotImplemented,
+}
+
+impl BitcoinRpcError {
+    /// Classify the `error` member of a JSON-RPC response
+    pub fn from_node_error(code: i32, message: String) -> Self {
+        match code {
+            RPC_INVALID_ADDRESS_OR_KEY => BitcoinRpcError::TxNotFound(message),
+            code => BitcoinRpcError::NodeError { code, message },
+        }
+    }
+
+    /// Whether the node was unreachable, overloaded or not ready yet, so the
+    /// same call may succeed later, as opposed to giving a definite answer
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            BitcoinRpcError::ConnectionFailed(_)
+                | BitcoinRpcError::Timeout
+                | BitcoinRpcError::RateLimited { .. }
+                | BitcoinRpcError::NodeError { code: RPC_IN_WARMUP, .. }
+        )
+    }
+
+    /// Whether the node doesn't know the transaction, block or mempool entry asked for
+    pub fn is_not_found(&self) -> bool {
+        matches!(
+            self,
+            BitcoinRpcError::TxNotFound(_)
+                | BitcoinRpcError::NodeError { code: RPC_INVALID_ADDRESS_OR_KEY, .. }
+        )
+    }
+
+    pub fn code(&self) -> u32 {
+        match self {
+            BitcoinRpcError::ConnectionFailed(_) => 2000,
+            BitcoinRpcError::AuthError => 2001,
+            BitcoinRpcError::Timeout => 2002,
+            BitcoinRpcError::RateLimited { .. } => 2003,
+            BitcoinRpcError::TxNotFound(_) => 2004,
+            BitcoinRpcError::InsufficientConfirmations { .. } => 2005,
+            BitcoinRpcError::InvalidResponse(_) => 2006,
+            BitcoinRpcError::NodeError { .. } => 2007,
+            BitcoinRpcError::NotImplemented => 2008,
+        }
+    }
+}
+
+/// What each `code()` means, for tools reading program logs and results
+const PROGRAM_ERROR_DESCRIPTIONS: [(u32, &str); 9] = [
+    (2000, "Bitcoin node unreachable"),
+    (2001, "Bitcoin node rejected the credentials"),
+    (2002, "Bitcoin node timed out"),
+    (2003, "Bitcoin node rate limited the request"),
+    (2004, "Transaction not found"),
+    (2005, "Insufficient confirmations"),
+    (2006, "Invalid response from the Bitcoin node"),
+    (2007, "Bitcoin node returned an RPC error"),
+    (2008, "Bitcoin RPC not available in WebAssembly"),
+];
+
+/// Describe a `ProgramError::Custom` code produced from a `BitcoinRpcError`
+pub fn describe_program_error(code: u32) -> Option<&'static str> {
+    PROGRAM_ERROR_DESCRIPTIONS
+        .iter()
+        .find(|(known, _)| *known == code)
+        .map(|(_, description)| *description)
+}
+
+/// The only conversion into `ProgramError`: the code says what kind of failure
+/// it was, and the full message is logged here since the code can't carry it
+impl From<BitcoinRpcError> for ProgramError {
+    fn from(e: BitcoinRpcError) -> Self {
+        msg!("Bitcoin RPC error {}: {}", e.code(), e);
+        ProgramError::Custom(e.code())
+    }
+}
+
+/// Keeps timeouts, rejected credentials and undecodable bodies distinguishable
+#[cfg(not(target_arch = "wasm32"))]
+impl From<reqwest::Error> for BitcoinRpcError {
+    fn from(e: reqwest::Error) -> Self {
+        let status = e.status();
+        if e.is_timeout() {
+            BitcoinRpcError::Timeout
+        } else if status == Some(reqwest::StatusCode::UNAUTHORIZED) || status == Some(reqwest::StatusCode::FORBIDDEN) {
+            BitcoinRpcError::AuthError
+        } else if status == Some(reqwest::StatusCode::TOO_MANY_REQUESTS) {
+            BitcoinRpcError::RateLimited { retry_after: None }
+        } else if e.is_decode() {
+            BitcoinRpcError::InvalidResponse(e.to_string())
+        } else {
+            BitcoinRpcError::ConnectionFailed(e.to_string())
+        }
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+    use mockito::{mock, server_url};
+
+    #[test]
+    fn test_node_errors() {
+        let payload: serde_json::Value = serde_json::from_str(
+            r#"{"code": -5, "message": "No such mempool or blockchain transaction"}"#,
+        )
+        .unwrap();
+        let classify = |payload: &serde_json::Value| {
+            BitcoinRpcError::from_node_error(
+                payload["code"].as_i64().unwrap() as i32,
+                payload["message"].as_str().unwrap().to_string(),
+            )
+        };
+
+        let not_found = classify(&payload);
+        assert!(matches!(&not_found, BitcoinRpcError::TxNotFound(message) if message.starts_with("No such")));
+        assert!(not_found.is_not_found());
+        assert!(!not_found.is_retryable());
+
+        let warmup = classify(&serde_json::json!({ "code": -28, "message": "Loading block index..." }));
+        assert!(matches!(warmup, BitcoinRpcError::NodeError { code: -28, .. }));
+        assert!(warmup.is_retryable());
+
+        let rejected = classify(&serde_json::json!({ "code": -26, "message": "min relay fee not met" }));
+        assert_eq!(rejected.to_string(), "Node error -26: min relay fee not met");
+        assert!(!rejected.is_retryable());
+        assert!(!rejected.is_not_found());
+    }
+
+    #[test]
+    fn test_classification() {
+        let retryable = [
+            BitcoinRpcError::ConnectionFailed("refused".to_string()),
+            BitcoinRpcError::Timeout,
+            BitcoinRpcError::RateLimited { retry_after: Some(Duration::from_secs(1)) },
+        ];
+        assert!(retryable.iter().all(BitcoinRpcError::is_retryable));
+
+        let definite = [
+            BitcoinRpcError::AuthError,
+            BitcoinRpcError::TxNotFound("ab".to_string()),
+            BitcoinRpcError::InsufficientConfirmations { required: 6, actual: 1 },
+            BitcoinRpcError::InvalidResponse("garbled".to_string()),
+            BitcoinRpcError::NotImplemented,
+        ];
+        assert!(!definite.iter().any(BitcoinRpcError::is_retryable));
+    }
+
+    #[test]
+    fn test_codes_are_distinct() {
+        let errors = [
+            BitcoinRpcError::ConnectionFailed(String::new()),
+            BitcoinRpcError::AuthError,
+            BitcoinRpcError::Timeout,
+            BitcoinRpcError::RateLimited { retry_after: None },
+            BitcoinRpcError::TxNotFound(String::new()),
+            BitcoinRpcError::InsufficientConfirmations { required: 1, actual: 0 },
+            BitcoinRpcError::InvalidResponse(String::new()),
+            BitcoinRpcError::NodeError { code: -1, message: String::new() },
+            BitcoinRpcError::NotImplemented,
+        ];
+        let codes: Vec<u32> = errors.iter().map(BitcoinRpcError::code).collect();
+        assert_eq!(codes, (2000..2009).collect::<Vec<_>>());
+        assert!(matches!(ProgramError::from(BitcoinRpcError::Timeout), ProgramError::Custom(2002)));
+
+        for code in codes {
+            assert!(describe_program_error(code).is_some(), "no description for {}", code);
+        }
+        assert_eq!(describe_program_error(2004), Some("Transaction not found"));
+        assert_eq!(describe_program_error(1999), None);
+        assert_eq!(describe_program_error(2009), None);
+    }
+
+    #[tokio::test]
+    async fn test_reqwest_errors() {
+        let client = reqwest::Client::new();
+
+        // Nothing listens on port 9
+        let refused = client.get("http://127.0.0.1:9/").send().await.unwrap_err();
+        assert!(matches!(BitcoinRpcError::from(refused), BitcoinRpcError::ConnectionFailed(_)));
+
+        let _unauthorized = mock("GET", "/error/unauthorized").with_status(401).create();
+        let unauthorized = client
+            .get(format!("{}/error/unauthorized", server_url()))
+            .send()
+            .await
+            .unwrap()
+            .error_for_status()
+            .unwrap_err();
+        assert!(matches!(BitcoinRpcError::from(unauthorized), BitcoinRpcError::AuthError));
+
+        let _garbled = mock("GET", "/error/garbled").with_status(200).with_body("not json").create();
+        let garbled = client
+            .get(format!("{}/error/garbled", server_url()))
+            .send()
+            .await
+            .unwrap()
+            .json::<serde_json::Value>()
+            .await
+            .unwrap_err();
+        assert!(matches!(BitcoinRpcError::from(garbled), BitcoinRpcError::InvalidResponse(_)));
+    }
+}
@@ -0,0 +1,132 @@
+use serde::Deserialize;
+
+use crate::bitcoin::utxo::{Confirmations, UtxoStatus};
+
+/// The `status` object of esplora's `/tx/:txid/status`, also embedded in
+/// outspends for the spending transaction
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+pub struct EsploraTxStatus {
+    pub confirmed: bool,
+    pub block_height: Option<u32>,
+    pub block_hash: Option<String>,
+    pub block_time: Option<u64>,
+}
+
+impl EsploraTxStatus {
+    /// Confirmations at `tip_height`: zero while unconfirmed, one in the tip block
+    ///
+    /// esplora doesn't report a count, only the block the transaction is in.
+    pub fn confirmations(&self, tip_height: u64) -> Confirmations {
+        match self.block_height {
+            Some(height) if self.confirmed => {
+                Confirmations::saturating_from((tip_height + 1).saturating_sub(u64::from(height)))
+            }
+            _ => Confirmations::ZERO,
+        }
+    }
+}
+
+/// Response of esplora's `/tx/:txid/outspend/:vout`; only `spent` is present
+/// for unspent outputs
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+pub struct EsploraOutspend {
+    pub spent: bool,
+    /// The spending transaction
+    pub txid: Option<String>,
+    /// Input of the spending transaction that spends the output
+    pub vin: Option<u32>,
+    pub status: Option<EsploraTxStatus>,
+}
+
+/// The transaction that spent an output
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpendInfo {
+    pub txid: String,
+    pub vin: u32,
+    /// Block of the spending transaction, `None` while it is unconfirmed
+    pub spent_height: Option<u32>,
+}
+
+impl EsploraOutspend {
+    /// The spender, if the output is spent and esplora named it
+    pub fn spend_info(&self) -> Option<SpendInfo> {
+        if !self.spent {
+            return None;
+        }
+        let status = self.status.as_ref().filter(|status| status.confirmed);
+        Some(SpendInfo {
+            txid: self.txid.clone()?,
+            vin: self.vin?,
+            spent_height: status.and_then(|status| status.block_height),
+        })
+    }
+}
+
+/// Status of an output from its funding transaction's `funding` status and
+/// its `outspend`, at `tip_height`
+///
+/// Spent outputs are Spent however deep the spender is; unspent ones are
+/// Active once the funding transaction has `min_confirmations`.
+pub fn outspend_status(
+    funding: &EsploraTxStatus,
+    outspend: &EsploraOutspend,
+    tip_height: u64,
+    min_confirmations: u32,
+) -> (UtxoStatus, Option<SpendInfo>) {
+    if outspend.spent {
+        return (UtxoStatus::Spent, outspend.spend_info());
+    }
+    if funding.confirmations(tip_height).meets(min_confirmations) {
+        (UtxoStatus::Active, None)
+    } else {
+        (UtxoStatus::Pending, None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SPENDER: &str = "5f1c3a9e8c2d4b7f0a6e9d8c7b5a4f3e2d1c0b9a8f7e6d5c4b3a29180706f5e4";
+
+    fn funding() -> EsploraTxStatus {
+        serde_json::from_str(include_str!("../../tests/fixtures/esplora_tx_status_confirmed.json")).unwrap()
+    }
+
+    fn outspend(fixture: &str) -> EsploraOutspend {
+        serde_json::from_str(fixture).unwrap()
+    }
+
+    #[test]
+    fn test_confirmations_from_block_height() {
+        let funding = funding();
+        assert_eq!(funding.block_height, Some(868_000));
+        assert_eq!(funding.confirmations(868_000), Confirmations(1));
+        assert_eq!(funding.confirmations(868_005), Confirmations(6));
+        // A tip behind the block, e.g. mid-reorg, doesn't count it
+        assert_eq!(funding.confirmations(867_999), Confirmations::ZERO);
+        assert_eq!(EsploraTxStatus::default().confirmations(868_005), Confirmations::ZERO);
+    }
+
+    #[test]
+    fn test_unspent_output() {
+        let unspent = outspend(include_str!("../../tests/fixtures/esplora_outspend_unspent.json"));
+        assert_eq!(unspent.spend_info(), None);
+
+        assert_eq!(outspend_status(&funding(), &unspent, 868_005, 6), (UtxoStatus::Active, None));
+        assert_eq!(outspend_status(&funding(), &unspent, 868_004, 6), (UtxoStatus::Pending, None));
+        assert_eq!(outspend_status(&EsploraTxStatus::default(), &unspent, 868_005, 1), (UtxoStatus::Pending, None));
+    }
+
+    #[test]
+    fn test_spent_output() {
+        let confirmed = outspend(include_str!("../../tests/fixtures/esplora_outspend_spent_confirmed.json"));
+        let spend = SpendInfo { txid: SPENDER.to_string(), vin: 1, spent_height: Some(868_004) };
+        assert_eq!(outspend_status(&funding(), &confirmed, 868_005, 6), (UtxoStatus::Spent, Some(spend.clone())));
+
+        let unconfirmed = outspend(include_str!("../../tests/fixtures/esplora_outspend_spent_unconfirmed.json"));
+        let spend = SpendInfo { spent_height: None, ..spend };
+        // Spent in the mempool is still spent, even before the output confirmed
+        assert_eq!(outspend_status(&funding(), &unconfirmed, 868_000, 6), (UtxoStatus::Spent, Some(spend)));
+    }
+}
@@ -0,0 +1,239 @@
+use std::io::{self, Write};
+
+use crate::bitcoin::utxo::{UtxoError, UtxoMeta, UtxoStatus};
+use crate::bitcoin::utxo_tracker::TrackerError;
+
+/// Columns of a CSV export, in order; `import_csv` expects the same header
+pub const CSV_COLUMNS: [&str; 7] = ["txid", "vout", "amount_sats", "status", "confirmations", "block_height", "added_at"];
+
+/// Layout of `UtxoTracker::export`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// A header row, then one row per UTXO with the fields of `CSV_COLUMNS`
+    Csv,
+    /// One JSON object per line, with the CSV columns as keys
+    JsonLines,
+}
+
+/// One exported UTXO
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExportRow {
+    pub utxo: UtxoMeta,
+    pub status: UtxoStatus,
+    /// Unix seconds, written as RFC 3339
+    pub added_at: Option<u64>,
+}
+
+/// Why a row of a CSV import was skipped
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ImportError {
+    #[error("Expected {expected} columns, found {found}")]
+    ColumnCount { expected: usize, found: usize },
+    #[error("Invalid {column}: {value:?}")]
+    InvalidField { column: &'static str, value: String },
+    #[error("Invalid UTXO: {0}")]
+    InvalidUtxo(#[from] UtxoError),
+    #[error(transparent)]
+    Rejected(#[from] TrackerError),
+}
+
+/// Outcome of `UtxoTracker::import_csv`; bad rows are reported, not fatal
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ImportReport {
+    pub imported: usize,
+    /// 1-based line number and the reason each skipped row was rejected
+    pub errors: Vec<(usize, ImportError)>,
+}
+
+pub fn status_name(status: UtxoStatus) -> &'static str {
+    match status {
+        UtxoStatus::Active => "active",
+        UtxoStatus::Pending => "pending",
+        UtxoStatus::Spent => "spent",
+        UtxoStatus::Invalid => "invalid",
+    }
+}
+
+pub fn parse_status(name: &str) -> Option<UtxoStatus> {
+    match name {
+        "active" => Some(UtxoStatus::Active),
+        "pending" => Some(UtxoStatus::Pending),
+        "spent" => Some(UtxoStatus::Spent),
+        "invalid" => Some(UtxoStatus::Invalid),
+        _ => None,
+    }
+}
+
+/// Write `rows` to `writer` in `format`
+pub fn write_rows(format: ExportFormat, rows: &[ExportRow], mut writer: impl Write) -> io::Result<()> {
+    if format == ExportFormat::Csv {
+        writeln!(writer, "{}", CSV_COLUMNS.join(","))?;
+    }
+    for row in rows {
+        let added_at = row.added_at.map(format_rfc3339);
+        match format {
+            // None of the fields can contain a comma or quote, so nothing needs escaping
+            ExportFormat::Csv => writeln!(
+                writer,
+                "{},{},{},{},{},{},{}",
+                row.utxo.txid,
+                row.utxo.vout,
+                row.utxo.amount_sats,
+                status_name(row.status),
+                row.utxo.confirmations,
+                row.utxo.block_height.map(|height| height.to_string()).unwrap_or_default(),
+                added_at.unwrap_or_default(),
+            )?,
+            ExportFormat::JsonLines => {
+                let line = serde_json::json!({
+                    "txid": row.utxo.txid,
+                    "vout": row.utxo.vout,
+                    "amount_sats": row.utxo.amount_sats,
+                    "status": status_name(row.status),
+                    "confirmations": row.utxo.confirmations,
+                    "block_height": row.utxo.block_height,
+                    "added_at": added_at,
+                });
+                writeln!(writer, "{}", line)?;
+            }
+        }
+    }
+    writer.flush()
+}
+
+/// Parse one CSV data row into a validated `ExportRow`
+///
+/// The outpoint and amount go through `UtxoMeta::try_new`; empty
+/// `block_height` and `added_at` cells mean unknown.
+pub fn parse_csv_row(line: &str) -> Result<ExportRow, ImportError> {
+    let cells: Vec<&str> = line.split(',').map(str::trim).collect();
+    if cells.len() != CSV_COLUMNS.len() {
+        return Err(ImportError::ColumnCount { expected: CSV_COLUMNS.len(), found: cells.len() });
+    }
+    let invalid = |index: usize| ImportError::InvalidField { column: CSV_COLUMNS[index], value: cells[index].to_string() };
+    let optional = |index: usize| (!cells[index].is_empty()).then_some(cells[index]);
+
+    let vout = cells[1].parse().map_err(|_| invalid(1))?;
+    let amount_sats = cells[2].parse().map_err(|_| invalid(2))?;
+    let status = parse_status(cells[3]).ok_or_else(|| invalid(3))?;
+    let confirmations = cells[4].parse().map_err(|_| invalid(4))?;
+    let block_height = optional(5).map(|height| height.parse().map_err(|_| invalid(5))).transpose()?;
+    let added_at = optional(6).map(|time| parse_rfc3339(time).ok_or_else(|| invalid(6))).transpose()?;
+
+    let mut utxo = UtxoMeta::try_new(cells[0].to_string(), vout, amount_sats)?;
+    utxo.confirmations = confirmations;
+    utxo.block_height = block_height;
+    Ok(ExportRow { utxo, status, added_at })
+}
+
+/// `unix_secs` as an RFC 3339 UTC timestamp, e.g. `2024-03-01T12:00:00Z`
+pub fn format_rfc3339(unix_secs: u64) -> String {
+    let days = unix_secs / 86_400;
+    let secs = unix_secs % 86_400;
+    let (year, month, day) = civil_from_days(days);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        secs / 3600,
+        secs % 3600 / 60,
+        secs % 60
+    )
+}
+
+/// Unix seconds of an RFC 3339 timestamp as written by `format_rfc3339`, or
+/// with a `+hh:mm`/`-hh:mm` offset; fractional seconds are dropped
+pub fn parse_rfc3339(timestamp: &str) -> Option<u64> {
+    let (date, time) = timestamp.split_once(['T', 't', ' '])?;
+    let mut date_parts = date.splitn(3, '-');
+    let year: u64 = fixed_digits(date_parts.next()?, 4)?;
+    let month: u64 = fixed_digits(date_parts.next()?, 2)?;
+    let day: u64 = fixed_digits(date_parts.next()?, 2)?;
+
+    let (clock, offset_secs) = match time.strip_suffix(['Z', 'z']) {
+        Some(clock) => (clock, 0i64),
+        None => {
+            let split = time.rfind(['+', '-'])?;
+            let (clock, offset) = time.split_at(split);
+            let (hours, minutes) = offset[1..].split_once(':')?;
+            let magnitude = (fixed_digits(hours, 2)? * 3600 + fixed_digits(minutes, 2)? * 60) as i64;
+            (clock, if offset.starts_with('-') { -magnitude } else { magnitude })
+        }
+    };
+    let clock = clock.split_once('.').map_or(clock, |(whole, _)| whole);
+    let mut clock_parts = clock.splitn(3, ':');
+    let hour = fixed_digits(clock_parts.next()?, 2)?;
+    let minute = fixed_digits(clock_parts.next()?, 2)?;
+    let second = fixed_digits(clock_parts.next()?, 2)?;
+
+    if year < 1970 || !(1..=12).contains(&month) || !(1..=days_in_month(year, month)).contains(&day)
+        || hour > 23 || minute > 59 || second > 60
+    {
+        return None;
+    }
+    let local = days_from_civil(year, month, day) * 86_400 + hour * 3600 + minute * 60 + second;
+    u64::try_from(i64::try_from(local).ok()? - offset_secs).ok()
+}
+
+fn fixed_digits(s: &str, len: usize) -> Option<u64> {
+    (s.len() == len && s.bytes().all(|b| b.is_ascii_digit())).then(|| s.parse().ok()).flatten()
+}
+
+fn is_leap_year(year: u64) -> bool {
+    (year % 4 == 0 && year % 100 != 0) || year % 400 == 0
+}
+
+fn days_in_month(year: u64, month: u64) -> u64 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Days since 1970-01-01 of a date on or after it (Howard Hinnant's algorithm)
+fn days_from_civil(year: u64, month: u64, day: u64) -> u64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year / 400;
+    let year_of_era = year % 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// Inverse of `days_from_civil`
+fn civil_from_days(days: u64) -> (u64, u64, u64) {
+    let days = days + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days % 146_097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 { shifted_month + 3 } else { shifted_month - 9 };
+    let year = year_of_era + era * 400 + u64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rfc3339_round_trip() {
+        assert_eq!(format_rfc3339(0), "1970-01-01T00:00:00Z");
+        assert_eq!(format_rfc3339(951_782_400), "2000-02-29T00:00:00Z");
+        assert_eq!(format_rfc3339(1_709_294_400), "2024-03-01T12:00:00Z");
+        for secs in [0, 951_782_400, 1_709_294_400, 4_102_444_799] {
+            assert_eq!(parse_rfc3339(&format_rfc3339(secs)), Some(secs));
+        }
+
+        assert_eq!(parse_rfc3339("2024-03-01T14:00:00.250+02:00"), Some(1_709_294_400));
+        assert_eq!(parse_rfc3339("2024-03-01T07:30:00-04:30"), Some(1_709_294_400));
+        for bad in ["2024-03-01", "2023-02-29T00:00:00Z", "2024-13-01T00:00:00Z", "2024-3-01T00:00:00Z", "1969-12-31T23:59:59Z"] {
+            assert_eq!(parse_rfc3339(bad), None, "{}", bad);
+        }
+    }
+}
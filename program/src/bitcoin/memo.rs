@@ -0,0 +1,183 @@
+use bitcoin::script::Instruction;
+use bitcoin::opcodes::all::OP_RETURN;
+use bitcoin::Transaction;
+use borsh::{BorshDeserialize, BorshSerialize};
+
+/// Prefix identifying an OVT payment memo inside an OP_RETURN output
+pub const PAYMENT_MEMO_MAGIC: &[u8; 4] = b"OVT1";
+
+/// Length of the payer reference carried by a memo
+pub const PAYER_ID_LEN: usize = 16;
+
+/// Memo payload: magic, payer id, intent byte, little-endian u64 nonce
+pub const PAYMENT_MEMO_LEN: usize = PAYMENT_MEMO_MAGIC.len() + PAYER_ID_LEN + 1 + 8;
+
+/// What the payer says a payment is for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, BorshSerialize, BorshDeserialize)]
+pub enum PaymentIntent {
+    Buyback,
+}
+
+impl PaymentIntent {
+    pub fn to_byte(self) -> u8 {
+        match self {
+            Self::Buyback => 1,
+        }
+    }
+
+    pub fn from_byte(byte: u8) -> Option<Self> {
+        match byte {
+            1 => Some(Self::Buyback),
+            _ => None,
+        }
+    }
+}
+
+/// Payer reference attached to a treasury payment, used to attribute burns
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, BorshSerialize, BorshDeserialize)]
+pub struct PaymentMemo {
+    pub payer_id: [u8; PAYER_ID_LEN],
+    pub intent: PaymentIntent,
+    /// Distinguishes payments from the same payer
+    pub nonce: u64,
+}
+
+impl PaymentMemo {
+    /// OP_RETURN payload for this memo
+    pub fn to_bytes(&self) -> [u8; PAYMENT_MEMO_LEN] {
+        let mut bytes = [0u8; PAYMENT_MEMO_LEN];
+        let (magic, rest) = bytes.split_at_mut(PAYMENT_MEMO_MAGIC.len());
+        magic.copy_from_slice(PAYMENT_MEMO_MAGIC);
+        let (payer_id, rest) = rest.split_at_mut(PAYER_ID_LEN);
+        payer_id.copy_from_slice(&self.payer_id);
+        rest[0] = self.intent.to_byte();
+        rest[1..].copy_from_slice(&self.nonce.to_le_bytes());
+        bytes
+    }
+
+    /// Decodes an OP_RETURN payload; None unless it is exactly a memo with a known intent
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() != PAYMENT_MEMO_LEN {
+            return None;
+        }
+        let rest = bytes.strip_prefix(PAYMENT_MEMO_MAGIC.as_slice())?;
+        let (payer_id, rest) = rest.split_at(PAYER_ID_LEN);
+        let intent = PaymentIntent::from_byte(rest[0])?;
+        Some(Self {
+            payer_id: payer_id.try_into().ok()?,
+            intent,
+            nonce: u64::from_le_bytes(rest[1..].try_into().ok()?),
+        })
+    }
+}
+
+impl std::fmt::Display for PaymentMemo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "payer {} intent {:?} nonce {}", hex::encode(self.payer_id), self.intent, self.nonce)
+    }
+}
+
+/// Memo carried by the first OP_RETURN output of `tx`
+///
+/// Only the first OP_RETURN output is considered, and it must hold a single
+/// push of a well-formed memo; anything else yields None.
+pub fn parse_payment_memo(tx: &Transaction) -> Option<PaymentMemo> {
+    let output = tx.output.iter().find(|output| output.script_pubkey.is_op_return())?;
+    let mut instructions = output.script_pubkey.instructions();
+    match instructions.next()? {
+        Ok(Instruction::Op(op)) if op == OP_RETURN => {}
+        _ => return None,
+    }
+    let payload = match instructions.next()? {
+        Ok(Instruction::PushBytes(bytes)) => bytes.as_bytes(),
+        _ => return None,
+    };
+    if instructions.next().is_some() {
+        return None;
+    }
+    PaymentMemo::from_bytes(payload)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bitcoin::absolute::LockTime;
+    use bitcoin::script::{Builder, PushBytesBuf};
+    use bitcoin::transaction::Version;
+    use bitcoin::{Amount, ScriptBuf, TxOut};
+
+    const MEMO: PaymentMemo = PaymentMemo {
+        payer_id: [0x42; PAYER_ID_LEN],
+        intent: PaymentIntent::Buyback,
+        nonce: 7,
+    };
+
+    fn op_return(payload: &[u8]) -> ScriptBuf {
+        ScriptBuf::new_op_return(PushBytesBuf::try_from(payload.to_vec()).unwrap())
+    }
+
+    fn tx_with_outputs(scripts: Vec<ScriptBuf>) -> Transaction {
+        Transaction {
+            version: Version::TWO,
+            lock_time: LockTime::ZERO,
+            input: vec![],
+            output: scripts
+                .into_iter()
+                .map(|script_pubkey| TxOut { value: Amount::ZERO, script_pubkey })
+                .collect(),
+        }
+    }
+
+    fn payment_script() -> ScriptBuf {
+        ScriptBuf::from_hex("0014751e76e8199196d454941c45d1b3a323f1433bd6").unwrap()
+    }
+
+    #[test]
+    fn test_valid_memo() {
+        let tx = tx_with_outputs(vec![payment_script(), op_return(&MEMO.to_bytes())]);
+        assert_eq!(parse_payment_memo(&tx), Some(MEMO));
+
+        let mut expected = b"OVT1".to_vec();
+        expected.extend_from_slice(&[0x42; PAYER_ID_LEN]);
+        expected.push(1);
+        expected.extend_from_slice(&7u64.to_le_bytes());
+        assert_eq!(MEMO.to_bytes().as_slice(), expected.as_slice());
+    }
+
+    #[test]
+    fn test_missing_memo() {
+        assert_eq!(parse_payment_memo(&tx_with_outputs(vec![payment_script()])), None);
+        assert_eq!(parse_payment_memo(&tx_with_outputs(vec![])), None);
+    }
+
+    #[test]
+    fn test_malformed_memos() {
+        let valid = MEMO.to_bytes();
+
+        let mut wrong_magic = valid;
+        wrong_magic[..4].copy_from_slice(b"OVT2");
+        let mut unknown_intent = valid;
+        unknown_intent[PAYMENT_MEMO_MAGIC.len() + PAYER_ID_LEN] = 0xff;
+        let mut too_long = valid.to_vec();
+        too_long.push(0);
+
+        for payload in [&wrong_magic[..], &unknown_intent[..], &valid[..valid.len() - 1], &too_long[..], &[][..]] {
+            let tx = tx_with_outputs(vec![op_return(payload)]);
+            assert_eq!(parse_payment_memo(&tx), None, "{:?}", payload);
+        }
+
+        // Two pushes after OP_RETURN
+        let split = Builder::new()
+            .push_opcode(OP_RETURN)
+            .push_slice(PushBytesBuf::try_from(valid[..4].to_vec()).unwrap())
+            .push_slice(PushBytesBuf::try_from(valid[4..].to_vec()).unwrap())
+            .into_script();
+        assert_eq!(parse_payment_memo(&tx_with_outputs(vec![split])), None);
+    }
+
+    #[test]
+    fn test_only_first_op_return_counts() {
+        let tx = tx_with_outputs(vec![op_return(b"something else"), op_return(&MEMO.to_bytes())]);
+        assert_eq!(parse_payment_memo(&tx), None);
+    }
+}
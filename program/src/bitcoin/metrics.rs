@@ -0,0 +1,269 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Upper bounds (inclusive, in milliseconds) of the RPC latency histogram buckets
+pub const LATENCY_BUCKETS_MS: [u64; 10] = [5, 10, 25, 50, 100, 250, 500, 1000, 2500, 10000];
+
+/// Fixed-bucket latency histogram backed by atomics
+#[derive(Debug, Default)]
+struct Histogram {
+    /// Per-bucket counts, with one extra overflow bucket for values above the last bound
+    buckets: [AtomicU64; LATENCY_BUCKETS_MS.len() + 1],
+    sum_ms: AtomicU64,
+    count: AtomicU64,
+}
+
+impl Histogram {
+    fn observe(&self, value_ms: u64) {
+        let index = LATENCY_BUCKETS_MS
+            .iter()
+            .position(|bound| value_ms <= *bound)
+            .unwrap_or(LATENCY_BUCKETS_MS.len());
+        self.buckets[index].fetch_add(1, Ordering::Relaxed);
+        self.sum_ms.fetch_add(value_ms, Ordering::Relaxed);
+        self.count.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn snapshot(&self) -> HistogramSnapshot {
+        let mut cumulative = 0;
+        let mut buckets = Vec::with_capacity(LATENCY_BUCKETS_MS.len());
+        for (bound, bucket) in LATENCY_BUCKETS_MS.iter().zip(self.buckets.iter()) {
+            cumulative += bucket.load(Ordering::Relaxed);
+            buckets.push((*bound, cumulative));
+        }
+
+        HistogramSnapshot {
+            buckets,
+            sum_ms: self.sum_ms.load(Ordering::Relaxed),
+            count: self.count.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Point-in-time view of a histogram
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HistogramSnapshot {
+    /// Cumulative counts as `(upper_bound_ms, observations <= bound)`
+    pub buckets: Vec<(u64, u64)>,
+    pub sum_ms: u64,
+    pub count: u64,
+}
+
+/// Counters shared by an RPC client, its cache and any tracker built on it
+#[derive(Debug, Default)]
+pub struct Metrics {
+    rpc_requests: Mutex<HashMap<String, u64>>,
+    rpc_failures: AtomicU64,
+    rpc_retries: AtomicU64,
+    rpc_latency: Histogram,
+    cache_hits: AtomicU64,
+    cache_misses: AtomicU64,
+    utxo_tracker_active: AtomicU64,
+    rpc_queue_depth: AtomicU64,
+    electrs_fallbacks: AtomicU64,
+    rpc_coalesced: AtomicU64,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Count an RPC call for `method` (once per call, not per attempt)
+    pub fn record_rpc_request(&self, method: &str) {
+        let mut requests = self.rpc_requests.lock().unwrap();
+        *requests.entry(method.to_string()).or_insert(0) += 1;
+    }
+
+    /// Count an RPC call that failed after exhausting its retries
+    pub fn record_rpc_failure(&self) {
+        self.rpc_failures.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_rpc_retry(&self) {
+        self.rpc_retries.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Record the latency of a single RPC attempt
+    pub fn record_rpc_latency(&self, latency: Duration) {
+        self.rpc_latency.observe(latency.as_millis() as u64);
+    }
+
+    /// A request started waiting for a rate limiter permit
+    pub fn rpc_queued(&self) {
+        self.rpc_queue_depth.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// A request stopped waiting for a rate limiter permit
+    pub fn rpc_dequeued(&self) {
+        self.rpc_queue_depth.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    /// Count a transaction lookup served by electrs instead of the node
+    pub fn record_electrs_fallback(&self) {
+        self.electrs_fallbacks.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Count an RPC call answered with the result of an identical concurrent one
+    pub fn record_rpc_coalesced(&self) {
+        self.rpc_coalesced.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_cache_hit(&self) {
+        self.cache_hits.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_cache_miss(&self) {
+        self.cache_misses.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Set the number of UTXOs the tracker currently considers Active
+    pub fn set_utxo_tracker_active(&self, count: u64) {
+        self.utxo_tracker_active.store(count, Ordering::Relaxed);
+    }
+
+    pub fn snapshot(&self) -> MetricsSnapshot {
+        let rpc_requests_total = self.rpc_requests.lock().unwrap()
+            .iter()
+            .map(|(method, count)| (method.clone(), *count))
+            .collect();
+
+        MetricsSnapshot {
+            rpc_requests_total,
+            rpc_failures_total: self.rpc_failures.load(Ordering::Relaxed),
+            rpc_retries_total: self.rpc_retries.load(Ordering::Relaxed),
+            rpc_latency_ms: self.rpc_latency.snapshot(),
+            rpc_queue_depth: self.rpc_queue_depth.load(Ordering::Relaxed),
+            electrs_fallbacks_total: self.electrs_fallbacks.load(Ordering::Relaxed),
+            rpc_coalesced_total: self.rpc_coalesced.load(Ordering::Relaxed),
+            utxo_tracker_active_count: self.utxo_tracker_active.load(Ordering::Relaxed),
+            cache_hits: self.cache_hits.load(Ordering::Relaxed),
+            cache_misses: self.cache_misses.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Point-in-time copy of all metrics, as returned by `BitcoinRpcClient::metrics()`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MetricsSnapshot {
+    /// RPC calls per JSON-RPC method
+    pub rpc_requests_total: BTreeMap<String, u64>,
+    pub rpc_failures_total: u64,
+    pub rpc_retries_total: u64,
+    pub rpc_latency_ms: HistogramSnapshot,
+    /// Requests currently waiting for a rate limiter permit
+    pub rpc_queue_depth: u64,
+    /// Transaction lookups that fell back to electrs
+    pub electrs_fallbacks_total: u64,
+    /// RPC calls that shared an identical call's result instead of reaching the node
+    pub rpc_coalesced_total: u64,
+    pub utxo_tracker_active_count: u64,
+    pub cache_hits: u64,
+    pub cache_misses: u64,
+}
+
+impl MetricsSnapshot {
+    /// Total RPC calls across all methods
+    pub fn total_rpc_requests(&self) -> u64 {
+        self.rpc_requests_total.values().sum()
+    }
+}
+
+#[cfg(feature = "metrics-export")]
+impl MetricsSnapshot {
+    /// Encode the snapshot in the Prometheus text exposition format
+    pub fn to_prometheus(&self) -> String {
+        use std::fmt::Write;
+
+        let mut out = String::new();
+        writeln!(out, "# TYPE rpc_requests_total counter").unwrap();
+        for (method, count) in &self.rpc_requests_total {
+            writeln!(out, "rpc_requests_total{{method=\"{}\"}} {}", method, count).unwrap();
+        }
+        writeln!(out, "# TYPE rpc_failures_total counter").unwrap();
+        writeln!(out, "rpc_failures_total {}", self.rpc_failures_total).unwrap();
+        writeln!(out, "# TYPE rpc_retries_total counter").unwrap();
+        writeln!(out, "rpc_retries_total {}", self.rpc_retries_total).unwrap();
+
+        writeln!(out, "# TYPE rpc_latency_ms histogram").unwrap();
+        for (bound, count) in &self.rpc_latency_ms.buckets {
+            writeln!(out, "rpc_latency_ms_bucket{{le=\"{}\"}} {}", bound, count).unwrap();
+        }
+        writeln!(out, "rpc_latency_ms_bucket{{le=\"+Inf\"}} {}", self.rpc_latency_ms.count).unwrap();
+        writeln!(out, "rpc_latency_ms_sum {}", self.rpc_latency_ms.sum_ms).unwrap();
+        writeln!(out, "rpc_latency_ms_count {}", self.rpc_latency_ms.count).unwrap();
+
+        writeln!(out, "# TYPE rpc_queue_depth gauge").unwrap();
+        writeln!(out, "rpc_queue_depth {}", self.rpc_queue_depth).unwrap();
+        writeln!(out, "# TYPE electrs_fallbacks_total counter").unwrap();
+        writeln!(out, "electrs_fallbacks_total {}", self.electrs_fallbacks_total).unwrap();
+        writeln!(out, "# TYPE rpc_coalesced_total counter").unwrap();
+        writeln!(out, "rpc_coalesced_total {}", self.rpc_coalesced_total).unwrap();
+        writeln!(out, "# TYPE utxo_tracker_active_count gauge").unwrap();
+        writeln!(out, "utxo_tracker_active_count {}", self.utxo_tracker_active_count).unwrap();
+        writeln!(out, "# TYPE cache_hits counter").unwrap();
+        writeln!(out, "cache_hits {}", self.cache_hits).unwrap();
+        writeln!(out, "# TYPE cache_misses counter").unwrap();
+        writeln!(out, "cache_misses {}", self.cache_misses).unwrap();
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_histogram_buckets_are_cumulative() {
+        let metrics = Metrics::new();
+        metrics.record_rpc_latency(Duration::from_millis(3));
+        metrics.record_rpc_latency(Duration::from_millis(40));
+        metrics.record_rpc_latency(Duration::from_millis(40));
+        metrics.record_rpc_latency(Duration::from_secs(30));
+
+        let latency = metrics.snapshot().rpc_latency_ms;
+        assert_eq!(latency.count, 4);
+        assert_eq!(latency.sum_ms, 30_083);
+        assert_eq!(latency.buckets[0], (5, 1));
+        assert_eq!(latency.buckets[3], (50, 3));
+        // The 30s observation only shows up in the implicit +Inf bucket
+        assert_eq!(*latency.buckets.last().unwrap(), (10000, 3));
+    }
+
+    #[test]
+    fn test_counters() {
+        let metrics = Metrics::new();
+        metrics.record_rpc_request("getblockcount");
+        metrics.record_rpc_request("getblockcount");
+        metrics.record_rpc_request("getrawtransaction");
+        metrics.record_rpc_failure();
+        metrics.record_cache_hit();
+        metrics.record_cache_miss();
+        metrics.record_cache_miss();
+        metrics.set_utxo_tracker_active(7);
+
+        let snapshot = metrics.snapshot();
+        assert_eq!(snapshot.rpc_requests_total.get("getblockcount"), Some(&2));
+        assert_eq!(snapshot.total_rpc_requests(), 3);
+        assert_eq!(snapshot.rpc_failures_total, 1);
+        assert_eq!(snapshot.cache_hits, 1);
+        assert_eq!(snapshot.cache_misses, 2);
+        assert_eq!(snapshot.utxo_tracker_active_count, 7);
+    }
+
+    #[cfg(feature = "metrics-export")]
+    #[test]
+    fn test_prometheus_encoding() {
+        let metrics = Metrics::new();
+        metrics.record_rpc_request("getbestblockhash");
+        metrics.record_rpc_latency(Duration::from_millis(12));
+
+        let text = metrics.snapshot().to_prometheus();
+        assert!(text.contains("rpc_requests_total{method=\"getbestblockhash\"} 1"));
+        assert!(text.contains("rpc_latency_ms_bucket{le=\"25\"} 1"));
+        assert!(text.contains("rpc_latency_ms_bucket{le=\"+Inf\"} 1"));
+        assert!(text.contains("rpc_latency_ms_sum 12"));
+    }
+}
@@ -1,7 +1,10 @@
 // Mock implementation for WebAssembly target
+use crate::bitcoin::block::BlockInfo;
+use crate::bitcoin::error::BitcoinRpcError;
 use crate::bitcoin::utxo::{UtxoMeta, UtxoStatus};
 use bitcoin::{Transaction, BlockHash, Block};
 use std::sync::Arc;
+use std::time::Duration;
 
 #[derive(Debug, Clone)]
 pub struct BitcoinRpcConfig {
@@ -9,12 +12,29 @@ pub struct BitcoinRpcConfig {
     pub port: u16,
     pub username: String,
     pub password: String,
+    pub connect_timeout: Duration,
+    pub request_timeout: Duration,
+    pub pool_idle_timeout: Option<Duration>,
+    pub tcp_keepalive: Option<Duration>,
+    pub proxy_url: Option<String>,
+    pub min_confirmations: u32,
 }
 
-#[derive(Debug, thiserror::Error)]
-pub enum BitcoinRpcError {
-    #[error("Not implemented in WebAssembly")]
-    NotImplemented,
+impl Default for BitcoinRpcConfig {
+    fn default() -> Self {
+        Self {
+            endpoint: "127.0.0.1".to_string(),
+            port: 8332,
+            username: String::new(),
+            password: String::new(),
+            connect_timeout: Duration::from_secs(10),
+            request_timeout: Duration::from_secs(30),
+            pool_idle_timeout: None,
+            tcp_keepalive: None,
+            proxy_url: None,
+            min_confirmations: 6,
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -26,13 +46,13 @@ pub struct BitcoinRpcClient {
 }
 
 impl BitcoinRpcClient {
-    pub fn new(config: BitcoinRpcConfig) -> Self {
-        Self {
+    pub fn new(config: BitcoinRpcConfig) -> Result<Self, BitcoinRpcError> {
+        Ok(Self {
             endpoint: config.endpoint,
             port: config.port,
             username: config.username,
             password: config.password,
-        }
+        })
     }
 
     // Mock implementations that return errors
@@ -44,7 +64,7 @@ impl BitcoinRpcClient {
         Err(BitcoinRpcError::NotImplemented)
     }
 
-    pub async fn update_utxo_confirmations(&self, _utxo: &mut UtxoMeta) -> Result<u64, BitcoinRpcError> {
+    pub async fn update_utxo_confirmations(&self, _utxo: &mut UtxoMeta) -> Result<u32, BitcoinRpcError> {
         Err(BitcoinRpcError::NotImplemented)
     }
 
@@ -56,11 +76,31 @@ impl BitcoinRpcClient {
         Err(BitcoinRpcError::NotImplemented)
     }
 
-    pub async fn get_block(&self, _hash: &BlockHash) -> Result<Block, BitcoinRpcError> {
+    pub async fn get_block_count(&self) -> Result<u64, BitcoinRpcError> {
+        Err(BitcoinRpcError::NotImplemented)
+    }
+
+    pub async fn get_chain_tip(&self) -> Result<(u64, BlockHash), BitcoinRpcError> {
+        Err(BitcoinRpcError::NotImplemented)
+    }
+
+    pub async fn get_block(&self, _hash: &BlockHash) -> Result<BlockInfo, BitcoinRpcError> {
+        Err(BitcoinRpcError::NotImplemented)
+    }
+
+    pub async fn get_block_raw(&self, _hash: &BlockHash) -> Result<Block, BitcoinRpcError> {
+        Err(BitcoinRpcError::NotImplemented)
+    }
+
+    pub async fn get_block_hash(&self, _height: u32) -> Result<BlockHash, BitcoinRpcError> {
+        Err(BitcoinRpcError::NotImplemented)
+    }
+
+    pub async fn get_block_at_height(&self, _height: u32) -> Result<BlockInfo, BitcoinRpcError> {
         Err(BitcoinRpcError::NotImplemented)
     }
 
-    pub async fn get_tx_block_info(&self, _txid: &str) -> Result<(u64, u32, String), BitcoinRpcError> {
+    pub async fn get_tx_block_info(&self, _txid: &str) -> Result<(u32, u32, String), BitcoinRpcError> {
         Err(BitcoinRpcError::NotImplemented)
     }
 }
@@ -0,0 +1,151 @@
+use std::collections::{HashMap, VecDeque};
+use std::time::Duration;
+
+use crate::bitcoin::error::BitcoinRpcError;
+
+/// Mock client calls a `FaultPolicy` can target
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MockMethod {
+    GetTransaction,
+    GetUtxoStatus,
+    GetOutspend,
+    GetConfirmations,
+    GetBestBlockHash,
+    GetBlockHeight,
+    GetBlock,
+    GetBlockHash,
+    GetTxBlockInfo,
+    GetTxOutProof,
+    BroadcastTransaction,
+    GetRawMempool,
+    GetMempoolEntry,
+    ListUnspent,
+}
+
+/// What goes wrong with one method
+#[derive(Debug, Clone, Default)]
+struct MethodFaults {
+    /// Errors returned by the next calls, in order, before anything else applies
+    scripted: VecDeque<BitcoinRpcError>,
+    /// Chance in [0, 1] that an unscripted call fails, and the error it fails with
+    failure_rate: Option<(f64, BitcoinRpcError)>,
+    /// Added before every call answers, failed or not
+    latency: Option<Duration>,
+}
+
+/// Failures and delays injected into `MockBitcoinRpcClient` calls
+///
+/// Scripted failures are deterministic; failure rates draw from a generator
+/// seeded with `with_seed`, so a given policy fails the same calls every run.
+#[derive(Debug, Clone)]
+pub struct FaultPolicy {
+    methods: HashMap<MockMethod, MethodFaults>,
+    rng_state: u64,
+}
+
+impl Default for FaultPolicy {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FaultPolicy {
+    /// A policy that injects nothing
+    pub fn new() -> Self {
+        Self { methods: HashMap::new(), rng_state: 0 }
+    }
+
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng_state = seed;
+        self
+    }
+
+    /// Fail the next `n` calls to `method` with `error`, after any failures already scripted
+    pub fn with_failures(mut self, method: MockMethod, n: usize, error: BitcoinRpcError) -> Self {
+        let scripted = &mut self.methods.entry(method).or_default().scripted;
+        scripted.extend(std::iter::repeat_n(error, n));
+        self
+    }
+
+    /// Fail unscripted calls to `method` with `error` with probability `rate`
+    pub fn with_failure_rate(mut self, method: MockMethod, rate: f64, error: BitcoinRpcError) -> Self {
+        self.methods.entry(method).or_default().failure_rate = Some((rate.clamp(0.0, 1.0), error));
+        self
+    }
+
+    /// Delay every call to `method` by `latency`
+    pub fn with_latency(mut self, method: MockMethod, latency: Duration) -> Self {
+        self.methods.entry(method).or_default().latency = Some(latency);
+        self
+    }
+
+    /// Decide the fate of one call to `method`: how long it stalls and the error it fails with, if any
+    pub(crate) fn next_call(&mut self, method: MockMethod) -> (Option<Duration>, Option<BitcoinRpcError>) {
+        let Some(faults) = self.methods.get_mut(&method) else {
+            return (None, None);
+        };
+        let latency = faults.latency;
+        if let Some(error) = faults.scripted.pop_front() {
+            return (latency, Some(error));
+        }
+        let failure = faults.failure_rate.clone();
+        let error = failure.and_then(|(rate, error)| (self.next_unit() < rate).then_some(error));
+        (latency, error)
+    }
+
+    /// Uniform draw from [0, 1) (splitmix64)
+    fn next_unit(&mut self) -> f64 {
+        self.rng_state = self.rng_state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.rng_state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^= z >> 31;
+        (z >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn offline() -> BitcoinRpcError {
+        BitcoinRpcError::ConnectionFailed("injected".to_string())
+    }
+
+    #[test]
+    fn test_scripted_failures_run_out() {
+        let mut policy = FaultPolicy::new().with_failures(MockMethod::GetTransaction, 2, offline());
+        assert!(policy.next_call(MockMethod::GetTransaction).1.is_some());
+        assert!(policy.next_call(MockMethod::GetUtxoStatus).1.is_none());
+        assert!(policy.next_call(MockMethod::GetTransaction).1.is_some());
+        assert!(policy.next_call(MockMethod::GetTransaction).1.is_none());
+    }
+
+    #[test]
+    fn test_failure_rate_is_reproducible() {
+        let policy = FaultPolicy::new()
+            .with_seed(42)
+            .with_failure_rate(MockMethod::GetConfirmations, 0.3, offline());
+        let run = |mut policy: FaultPolicy| {
+            (0..1000)
+                .map(|_| policy.next_call(MockMethod::GetConfirmations).1.is_some())
+                .collect::<Vec<_>>()
+        };
+        let first = run(policy.clone());
+        assert_eq!(first, run(policy));
+
+        let failures = first.iter().filter(|failed| **failed).count();
+        assert!((200..400).contains(&failures), "{} failures", failures);
+    }
+
+    #[test]
+    fn test_latency_applies_to_every_call() {
+        let mut policy = FaultPolicy::new()
+            .with_latency(MockMethod::GetUtxoStatus, Duration::from_millis(500))
+            .with_failures(MockMethod::GetUtxoStatus, 1, offline());
+        assert_eq!(policy.next_call(MockMethod::GetUtxoStatus).0, Some(Duration::from_millis(500)));
+        let (latency, error) = policy.next_call(MockMethod::GetUtxoStatus);
+        assert_eq!(latency, Some(Duration::from_millis(500)));
+        assert!(error.is_none());
+    }
+}
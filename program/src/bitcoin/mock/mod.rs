@@ -1,30 +1,171 @@
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+use tokio::time::sleep;
+use bitcoin::hashes::Hash;
 use bitcoin::{
     Transaction, 
+    Txid,
     TxIn, 
     TxOut, 
     Script,
     absolute::LockTime,
     transaction::Version,
     Amount,
+    BlockHash,
 };
-use crate::bitcoin::utxo::{UtxoMeta, UtxoStatus};
+use arch_program::msg;
+use async_trait::async_trait;
+use crate::bitcoin::block::BlockInfo;
+use crate::bitcoin::cache::UtxoStatusSource;
+use crate::bitcoin::utxo_tracker::TrackerRpc;
+use crate::bitcoin::utxo::{UtxoMeta, UtxoStatus, UtxoValidationRpc};
 
-use crate::bitcoin::rpc::{BitcoinRpcClient, BitcoinRpcConfig, BitcoinRpcError};
+use crate::bitcoin::error::BitcoinRpcError;
+use crate::bitcoin::rpc::{BitcoinRpcClient, BitcoinRpcConfig, MempoolEntry};
+
+mod faults;
+
+pub use faults::{FaultPolicy, MockMethod};
 
 #[derive(Debug, Clone)]
 struct MockTransaction {
     confirmations: u32,
     outputs: Vec<TxOut>,
     is_valid: bool,
+    /// Unconfirmed and picked up by the next `mine_blocks`
+    in_next_block: bool,
+    /// Height of the mock block containing the transaction, if confirmed
+    block_height: Option<u32>,
+    /// Mempool data reported while the transaction is unconfirmed
+    fee_sats: u64,
+    vsize: u64,
+    replaceable: bool,
+}
+
+/// Rough virtual size of a transaction with one P2WPKH input paying `outputs` P2WPKH outputs
+fn estimated_vsize(outputs: usize) -> u64 {
+    11 + 68 + 31 * outputs as u64
+}
+
+/// The transaction `MockBitcoinRpcClient::get_transaction` returns for a mock
+/// one: only the outputs are stored, so it has no inputs
+fn rebuilt_transaction(outputs: Vec<TxOut>) -> Transaction {
+    Transaction {
+        version: Version(2),
+        lock_time: LockTime::ZERO,
+        input: vec![],
+        output: outputs,
+    }
+}
+
+/// Spender recorded by `MockBitcoinNode::spend_utxo`, which doesn't name one
+pub const UNKNOWN_SPENDER: &str = "unknown";
+
+/// Whether an output known to the mock node has been spent, and by what
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MockUtxoState {
+    Unspent,
+    /// `height` is the spending transaction's block, None while it is unconfirmed
+    SpentBy { txid: String, height: Option<u32> },
+}
+
+/// Spend status of an output, shaped like esplora's `/tx/:txid/outspend/:vout`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Outspend {
+    pub spent: bool,
+    pub txid: Option<String>,
+    pub block_height: Option<u32>,
+}
+
+/// Bring spenders in line with the transactions that spent them: a spender
+/// that was invalidated frees the output again, a confirmed one records its
+/// height. Spenders the node doesn't know are left alone.
+fn refresh_spenders(
+    txs: &HashMap<String, MockTransaction>,
+    utxos: &mut HashMap<(String, u32), MockUtxoState>,
+) {
+    for state in utxos.values_mut() {
+        let MockUtxoState::SpentBy { txid, height } = state else {
+            continue;
+        };
+        match txs.get(txid.as_str()) {
+            Some(spender) if !spender.is_valid => *state = MockUtxoState::Unspent,
+            Some(spender) => *height = spender.block_height,
+            None => {}
+        }
+    }
 }
 
 pub struct MockBitcoinNode {
     transactions: Arc<Mutex<HashMap<String, MockTransaction>>>,
-    utxo_set: Arc<Mutex<HashMap<(String, u32), bool>>>, // (txid, vout) -> is_spent
+    utxo_set: Arc<Mutex<HashMap<(String, u32), MockUtxoState>>>, // (txid, vout) -> state
+    /// Simulated outage: status lookups fail with ConnectionFailed
+    offline: AtomicBool,
+    chain_tip: Mutex<ChainTip>,
+    faults: Mutex<FaultPolicy>,
+    /// Answers to `gettxoutproof`, by block hash and txid; the mock
+    /// blocks have no real headers, so proofs come from fixtures
+    txout_proofs: Mutex<HashMap<(BlockHash, Txid), Vec<u8>>>,
+}
+
+#[derive(Debug, Clone)]
+struct ChainTip {
+    height: u32,
+    /// First height of each fork, the current one last; the fork number is
+    /// part of every block hash, so blocks replaced by a reorg get new hashes
+    fork_starts: Vec<u32>,
+    /// Blocks rolled back by reorgs, as they were when they left the main chain
+    stale_blocks: HashMap<BlockHash, BlockInfo>,
+}
+
+impl ChainTip {
+    fn hash(&self) -> BlockHash {
+        self.block_hash(self.height).expect("the tip is on the main chain")
+    }
+
+    /// Hash of the main-chain block at `height`, which belongs to the latest fork started at or below it
+    fn block_hash(&self, height: u32) -> Option<BlockHash> {
+        if height > self.height {
+            return None;
+        }
+        let fork = self.fork_starts.iter().rposition(|start| *start <= height).unwrap_or(0) as u32;
+        let mut seed = [0u8; 8];
+        seed[..4].copy_from_slice(&height.to_le_bytes());
+        seed[4..].copy_from_slice(&fork.to_le_bytes());
+        Some(BlockHash::hash(&seed))
+    }
+
+    fn block_height(&self, hash: &BlockHash) -> Option<u32> {
+        (0..=self.height).rev().find(|height| self.block_hash(*height).as_ref() == Some(hash))
+    }
+
+    /// Summary of the main-chain block at `height`, listing the valid transactions confirmed in it
+    fn block_info(&self, txs: &HashMap<String, MockTransaction>, height: u32) -> Option<BlockInfo> {
+        let hash = self.block_hash(height)?;
+        let mut tx: Vec<Txid> = txs.iter()
+            .filter(|(_, tx)| tx.is_valid && tx.block_height == Some(height))
+            .filter_map(|(txid, _)| txid.parse().ok())
+            .collect();
+        tx.sort();
+        Some(BlockInfo {
+            hash,
+            height,
+            time: MOCK_GENESIS_TIME + u64::from(height) * MOCK_BLOCK_INTERVAL_SECS,
+            previous_block_hash: height.checked_sub(1).and_then(|parent| self.block_hash(parent)),
+            tx,
+            confirmations: i64::from(self.height - height) + 1,
+        })
+    }
 }
 
+/// Timestamp of the mock genesis block
+const MOCK_GENESIS_TIME: u64 = 1_700_000_000;
+
+/// Spacing of mock block timestamps
+const MOCK_BLOCK_INTERVAL_SECS: u64 = 600;
+
 impl Default for MockBitcoinNode {
     fn default() -> Self {
         Self::new()
@@ -36,9 +177,18 @@ impl MockBitcoinNode {
         Self {
             transactions: Arc::new(Mutex::new(HashMap::new())),
             utxo_set: Arc::new(Mutex::new(HashMap::new())),
+            offline: AtomicBool::new(false),
+            chain_tip: Mutex::new(ChainTip { height: 0, fork_starts: vec![0], stale_blocks: HashMap::new() }),
+            faults: Mutex::new(FaultPolicy::new()),
+            txout_proofs: Mutex::new(HashMap::new()),
         }
     }
 
+    /// Answer `gettxoutproof` for `txid` in `block_hash` with `proof`
+    pub fn set_txout_proof(&self, block_hash: BlockHash, txid: Txid, proof: Vec<u8>) {
+        self.txout_proofs.lock().unwrap().insert((block_hash, txid), proof);
+    }
+
     pub fn add_transaction(&self, txid: &str, confirmations: u32, outputs: Vec<TxOut>, is_valid: bool) {
         let mut txs = self.transactions.lock().unwrap();
         let mut utxos = self.utxo_set.lock().unwrap();
@@ -51,11 +201,22 @@ impl MockBitcoinNode {
         // Store outputs length before moving outputs
         let outputs_len = outputs.len();
 
+        // A confirmed transaction sits `confirmations - 1` blocks below the tip,
+        // or in the genesis block if the mock chain is shorter than that
+        let tip_height = self.get_block_height();
+        let block_height = (is_valid && confirmations > 0)
+            .then(|| tip_height.saturating_sub(confirmations - 1));
+
         // Update transaction first
         txs.insert(txid.to_string(), MockTransaction {
             confirmations,
+            vsize: estimated_vsize(outputs.len()),
             outputs,
             is_valid,
+            in_next_block: confirmations == 0,
+            block_height,
+            fee_sats: 0,
+            replaceable: false,
         });
 
         // Update UTXOs based on transaction validity and reorg status
@@ -67,55 +228,437 @@ impl MockBitcoinNode {
                 utxos.remove(&utxo_key);
             } else {
                 // For valid transactions, add UTXOs if they don't exist
-                if !utxos.contains_key(&utxo_key) {
-                    utxos.insert(utxo_key, false);
+                utxos.entry(utxo_key).or_insert(MockUtxoState::Unspent);
+            }
+        }
+    }
+
+    /// Add a valid transaction under the txid of what `get_transaction` will
+    /// return for it, so checks that compare the two agree, and return that txid
+    pub fn add_payment_transaction(&self, confirmations: u32, outputs: Vec<TxOut>) -> Txid {
+        let txid = rebuilt_transaction(outputs.clone()).compute_txid();
+        self.add_transaction(&txid.to_string(), confirmations, outputs, true);
+        txid
+    }
+
+    /// Add an unconfirmed transaction paying `fee_sats`, signalling BIP125
+    /// replaceability if `replaceable`
+    pub fn add_mempool_transaction(&self, txid: &str, outputs: Vec<TxOut>, fee_sats: u64, replaceable: bool) {
+        self.add_transaction(txid, 0, outputs, true);
+        let mut txs = self.transactions.lock().unwrap();
+        if let Some(tx) = txs.get_mut(txid) {
+            tx.fee_sats = fee_sats;
+            tx.replaceable = replaceable;
+        }
+    }
+
+    /// Replace mempool transaction `old_txid` with `new_txid`, following BIP125:
+    /// the old transaction must signal replaceability and the new one must pay
+    /// a higher fee. The old transaction is evicted along with its outputs, and
+    /// the outputs it spent are now spent by the replacement.
+    pub fn replace_transaction(
+        &self,
+        old_txid: &str,
+        new_txid: &str,
+        outputs: Vec<TxOut>,
+        fee_sats: u64,
+        replaceable: bool,
+    ) -> Result<(), BitcoinRpcError> {
+        let mut txs = self.transactions.lock().unwrap();
+        let mut utxos = self.utxo_set.lock().unwrap();
+        let old = txs.get(old_txid)
+            .filter(|tx| tx.is_valid && tx.confirmations == 0)
+            .ok_or_else(|| BitcoinRpcError::TxNotFound(old_txid.to_string()))?;
+        if !old.replaceable {
+            return Err(BitcoinRpcError::InvalidResponse(format!(
+                "txn-mempool-conflict: {} is not replaceable",
+                old_txid
+            )));
+        }
+        if fee_sats <= old.fee_sats {
+            return Err(BitcoinRpcError::InvalidResponse(format!(
+                "insufficient fee: replacement pays {} sats, {} paid {}",
+                fee_sats, old_txid, old.fee_sats
+            )));
+        }
+
+        txs.remove(old_txid);
+        utxos.retain(|(utxo_txid, _), _| utxo_txid != old_txid);
+        for state in utxos.values_mut() {
+            if let MockUtxoState::SpentBy { txid, .. } = state {
+                if txid == old_txid {
+                    *txid = new_txid.to_string();
                 }
             }
         }
+        for vout in 0..outputs.len() {
+            utxos.insert((new_txid.to_string(), vout as u32), MockUtxoState::Unspent);
+        }
+        txs.insert(new_txid.to_string(), MockTransaction {
+            confirmations: 0,
+            vsize: estimated_vsize(outputs.len()),
+            outputs,
+            is_valid: true,
+            in_next_block: true,
+            block_height: None,
+            fee_sats,
+            replaceable,
+        });
+        Ok(())
+    }
+
+    /// Confirm a mempool transaction in the current tip block; false if
+    /// `txid` is not in the mempool
+    pub fn confirm_mempool_transaction(&self, txid: &str) -> bool {
+        let mut txs = self.transactions.lock().unwrap();
+        let mut utxos = self.utxo_set.lock().unwrap();
+        let tip_height = self.chain_tip.lock().unwrap().height;
+        let Some(tx) = txs.get_mut(txid).filter(|tx| tx.is_valid && tx.confirmations == 0) else {
+            return false;
+        };
+        tx.confirmations = 1;
+        tx.block_height = Some(tip_height);
+        tx.in_next_block = false;
+        refresh_spenders(&txs, &mut utxos);
+        true
+    }
+
+    /// Txids of the valid unconfirmed transactions
+    pub fn mempool_txids(&self) -> HashSet<String> {
+        let txs = self.transactions.lock().unwrap();
+        txs.iter()
+            .filter(|(_, tx)| tx.is_valid && tx.confirmations == 0)
+            .map(|(txid, _)| txid.clone())
+            .collect()
+    }
+
+    /// Update the confirmation count of a known transaction, e.g. to simulate new blocks
+    pub fn set_confirmations(&self, txid: &str, confirmations: u32) {
+        let mut txs = self.transactions.lock().unwrap();
+        if let Some(tx) = txs.get_mut(txid) {
+            tx.confirmations = confirmations;
+        }
+    }
+
+    fn set_vsize(&self, txid: &str, vsize: u64) {
+        let mut txs = self.transactions.lock().unwrap();
+        if let Some(tx) = txs.get_mut(txid) {
+            tx.vsize = vsize;
+        }
+    }
+
+    /// Choose whether an unconfirmed transaction is mined by the next
+    /// `mine_blocks`; new zero-conf transactions are by default
+    pub fn set_in_next_block(&self, txid: &str, in_next_block: bool) {
+        let mut txs = self.transactions.lock().unwrap();
+        if let Some(tx) = txs.get_mut(txid) {
+            tx.in_next_block = in_next_block && tx.confirmations == 0;
+        }
+    }
+
+    /// Mine `count` blocks: every valid transaction that is confirmed or
+    /// flagged for the next block gains `count` confirmations, and the chain
+    /// tip advances by `count`
+    pub fn mine_blocks(&self, count: u32) {
+        if count == 0 {
+            return;
+        }
+        let mut txs = self.transactions.lock().unwrap();
+        let mut utxos = self.utxo_set.lock().unwrap();
+        let mut tip = self.chain_tip.lock().unwrap();
+        for tx in txs.values_mut() {
+            if tx.is_valid && (tx.confirmations > 0 || tx.in_next_block) {
+                tx.confirmations = tx.confirmations.saturating_add(count);
+                tx.block_height.get_or_insert(tip.height.saturating_add(1));
+                tx.in_next_block = false;
+            }
+        }
+        refresh_spenders(&txs, &mut utxos);
+        tip.height = tip.height.saturating_add(count);
+    }
+
+    /// Roll the chain back `depth` blocks, invalidating the transactions
+    /// confirmed in them, without re-including any
+    pub fn simulate_reorg(&self, depth: u32) {
+        self.simulate_reorg_with(depth, &[]);
+    }
+
+    /// Roll the chain back `depth` blocks onto a new fork
+    ///
+    /// Transactions confirmed in the rolled-back blocks become invalid and
+    /// lose their outputs, except those in `reinclude`, which move into the
+    /// new tip block with one confirmation. Surviving transactions have their
+    /// confirmations recounted against the lower tip. The genesis block is
+    /// never rolled back.
+    pub fn simulate_reorg_with(&self, depth: u32, reinclude: &[&str]) {
+        let mut txs = self.transactions.lock().unwrap();
+        let mut utxos = self.utxo_set.lock().unwrap();
+        let mut tip = self.chain_tip.lock().unwrap();
+        let fork_height = tip.height.saturating_sub(depth);
+
+        // Snapshot the blocks about to be replaced, before their transactions move
+        for height in fork_height..=tip.height {
+            if let Some(mut block) = tip.block_info(&txs, height) {
+                block.confirmations = -1;
+                tip.stale_blocks.insert(block.hash, block);
+            }
+        }
+
+        for (txid, tx) in txs.iter_mut() {
+            match tx.block_height {
+                Some(height) if height > fork_height => {
+                    if reinclude.contains(&txid.as_str()) {
+                        tx.block_height = Some(fork_height);
+                    } else {
+                        tx.is_valid = false;
+                        tx.confirmations = 0;
+                        tx.block_height = None;
+                        tx.in_next_block = false;
+                        utxos.retain(|(utxo_txid, _), _| utxo_txid != txid);
+                        continue;
+                    }
+                }
+                Some(_) => {}
+                None => continue,
+            }
+            if let Some(height) = tx.block_height {
+                tx.confirmations = fork_height - height + 1;
+            }
+        }
+
+        refresh_spenders(&txs, &mut utxos);
+        tip.height = fork_height;
+        tip.fork_starts.push(fork_height);
+    }
+
+    /// Height of the last mined block; 0 until `mine_blocks` is called
+    pub fn get_block_height(&self) -> u32 {
+        self.chain_tip.lock().unwrap().height
+    }
+
+    pub fn best_block_hash(&self) -> BlockHash {
+        self.chain_tip.lock().unwrap().hash()
+    }
+
+    /// Hash of the main-chain block at `height`; None above the tip
+    pub fn block_hash(&self, height: u32) -> Option<BlockHash> {
+        self.chain_tip.lock().unwrap().block_hash(height)
+    }
+
+    /// Summary of a main-chain block, or of a block rolled back by a reorg
+    /// (with -1 confirmations); None for hashes the node never produced
+    pub fn block_info(&self, hash: &BlockHash) -> Option<BlockInfo> {
+        let txs = self.transactions.lock().unwrap();
+        let tip = self.chain_tip.lock().unwrap();
+        match tip.block_height(hash) {
+            Some(height) => tip.block_info(&txs, height),
+            None => tip.stale_blocks.get(hash).cloned(),
+        }
+    }
+
+    /// Forget a transaction and its outputs, as if it was replaced or dropped;
+    /// outputs it spent become unspent again
+    pub fn remove_transaction(&self, txid: &str) {
+        let mut txs = self.transactions.lock().unwrap();
+        let mut utxos = self.utxo_set.lock().unwrap();
+        txs.remove(txid);
+        utxos.retain(|(utxo_txid, _), _| utxo_txid != txid);
+        for state in utxos.values_mut() {
+            if matches!(state, MockUtxoState::SpentBy { txid: spender, .. } if spender == txid) {
+                *state = MockUtxoState::Unspent;
+            }
+        }
+    }
+
+    /// Take the node down (or bring it back) for clients using it
+    pub fn set_offline(&self, offline: bool) {
+        self.offline.store(offline, Ordering::Relaxed);
+    }
+
+    pub fn is_offline(&self) -> bool {
+        self.offline.load(Ordering::Relaxed)
+    }
+
+    /// Replace the failures and delays injected into client calls
+    pub fn set_fault_policy(&self, policy: FaultPolicy) {
+        *self.faults.lock().unwrap() = policy;
     }
 
+    /// Fail the next `n` calls to `method` with `error`, on top of the current policy
+    pub fn fail_next(&self, method: MockMethod, n: usize, error: BitcoinRpcError) {
+        let mut faults = self.faults.lock().unwrap();
+        *faults = std::mem::take(&mut *faults).with_failures(method, n, error);
+    }
+
+    /// Delay every call to `method` by `latency`, on top of the current policy
+    pub fn set_latency(&self, method: MockMethod, latency: Duration) {
+        let mut faults = self.faults.lock().unwrap();
+        *faults = std::mem::take(&mut *faults).with_latency(method, latency);
+    }
+
+    fn next_fault(&self, method: MockMethod) -> (Option<Duration>, Option<BitcoinRpcError>) {
+        self.faults.lock().unwrap().next_call(method)
+    }
+
+    /// Mark an output spent by a transaction the node doesn't know (`UNKNOWN_SPENDER`)
     pub fn spend_utxo(&self, txid: &str, vout: u32) {
+        self.spend_utxo_with(txid, vout, UNKNOWN_SPENDER);
+    }
+
+    /// Mark an output spent by `spending_txid`, recording the spender's block if it is confirmed
+    pub fn spend_utxo_with(&self, txid: &str, vout: u32, spending_txid: &str) {
+        let txs = self.transactions.lock().unwrap();
         let mut utxos = self.utxo_set.lock().unwrap();
-        utxos.insert((txid.to_string(), vout), true);
+        let height = txs.get(spending_txid).and_then(|spender| spender.block_height);
+        utxos.insert((txid.to_string(), vout), MockUtxoState::SpentBy { txid: spending_txid.to_string(), height });
     }
 
     pub fn is_utxo_spent(&self, txid: &str, vout: u32) -> Option<bool> {
+        self.utxo_state(txid, vout).map(|state| state != MockUtxoState::Unspent)
+    }
+
+    /// Txid of the transaction that spent an output; None if it is unspent or unknown
+    pub fn get_spender(&self, txid: &str, vout: u32) -> Option<String> {
+        match self.utxo_state(txid, vout)? {
+            MockUtxoState::SpentBy { txid, .. } => Some(txid),
+            MockUtxoState::Unspent => None,
+        }
+    }
+
+    pub fn utxo_state(&self, txid: &str, vout: u32) -> Option<MockUtxoState> {
         let utxos = self.utxo_set.lock().unwrap();
-        utxos.get(&(txid.to_string(), vout)).copied()
+        utxos.get(&(txid.to_string(), vout)).cloned()
     }
 
     pub fn get_transaction(&self, txid: &str) -> Option<MockTransaction> {
         let txs = self.transactions.lock().unwrap();
         txs.get(txid).cloned()
     }
+
+    /// Output `vout` of a transaction the node knows, as it was added
+    pub fn output(&self, txid: &str, vout: u32) -> Option<TxOut> {
+        let txs = self.transactions.lock().unwrap();
+        txs.get(txid)?.outputs.get(vout as usize).cloned()
+    }
+
+    /// Output `vout` as a `UtxoMeta` with its stored amount, script_pubkey and
+    /// confirmations, like `list_unspent` reports it
+    pub fn utxo_meta(&self, txid: &str, vout: u32) -> Option<UtxoMeta> {
+        let txs = self.transactions.lock().unwrap();
+        stored_utxo_meta(txid, vout, txs.get(txid)?)
+    }
+}
+
+fn stored_utxo_meta(txid: &str, vout: u32, tx: &MockTransaction) -> Option<UtxoMeta> {
+    let output = tx.outputs.get(vout as usize)?;
+    let mut utxo = UtxoMeta::new(txid.to_string(), vout, output.value.to_sat());
+    utxo.script_pubkey = hex::encode(output.script_pubkey.as_bytes());
+    utxo.confirmations = tx.confirmations;
+    Some(utxo)
 }
 
 pub struct MockBitcoinRpcClient {
     node: Arc<MockBitcoinNode>,
     config: BitcoinRpcConfig,
+    /// Simulated round-trip time of each status lookup
+    latency: Option<Duration>,
+    utxo_status_calls: AtomicUsize,
+    /// Status lookups currently in progress, and the most seen at once
+    utxo_status_in_flight: AtomicUsize,
+    max_utxo_status_in_flight: AtomicUsize,
+    confirmation_calls: AtomicUsize,
+    block_height_calls: AtomicUsize,
+    /// Answer Invalid for a UTXO whose amount, or script_pubkey when it has
+    /// one, differs from the stored output
+    strict_outputs: bool,
 }
 
 impl MockBitcoinRpcClient {
     pub fn new(config: BitcoinRpcConfig, node: Arc<MockBitcoinNode>) -> Self {
-        Self { node, config }
+        Self {
+            node,
+            config,
+            latency: None,
+            utxo_status_calls: AtomicUsize::new(0),
+            utxo_status_in_flight: AtomicUsize::new(0),
+            max_utxo_status_in_flight: AtomicUsize::new(0),
+            confirmation_calls: AtomicUsize::new(0),
+            block_height_calls: AtomicUsize::new(0),
+            strict_outputs: false,
+        }
+    }
+
+    /// Delay every status lookup by `latency`, to exercise concurrent callers
+    pub fn with_latency(mut self, latency: Duration) -> Self {
+        self.latency = Some(latency);
+        self
+    }
+
+    /// Check the amount and script_pubkey of each UTXO asked about against the
+    /// stored output, instead of only its outpoint
+    pub fn with_strict_outputs(mut self) -> Self {
+        self.strict_outputs = true;
+        self
+    }
+
+    /// Number of `get_utxo_status` calls served so far
+    pub fn utxo_status_calls(&self) -> usize {
+        self.utxo_status_calls.load(Ordering::Relaxed)
+    }
+
+    /// Highest number of `get_utxo_status` calls that were running concurrently
+    pub fn max_concurrent_utxo_status_calls(&self) -> usize {
+        self.max_utxo_status_in_flight.load(Ordering::Relaxed)
+    }
+
+    /// Apply the node's fault policy to a call to `method`, then fail it if the node is offline
+    async fn inject_faults(&self, method: MockMethod) -> Result<(), BitcoinRpcError> {
+        let (latency, error) = self.node.next_fault(method);
+        if let Some(latency) = latency {
+            sleep(latency).await;
+        }
+        if let Some(error) = error {
+            return Err(error);
+        }
+        if self.node.is_offline() {
+            return Err(BitcoinRpcError::ConnectionFailed("mock node is offline".to_string()));
+        }
+        Ok(())
     }
 
     pub async fn get_transaction(&self, txid: &str) -> Result<Transaction, BitcoinRpcError> {
+        self.inject_faults(MockMethod::GetTransaction).await?;
         match self.node.get_transaction(txid) {
-            Some(mock_tx) if mock_tx.is_valid => {
-                Ok(Transaction {
-                    version: Version(2),
-                    lock_time: LockTime::ZERO,
-                    input: vec![],
-                    output: mock_tx.outputs,
-                })
-            }
+            Some(mock_tx) if mock_tx.is_valid => Ok(rebuilt_transaction(mock_tx.outputs)),
             Some(_) => Err(BitcoinRpcError::InvalidResponse("Invalid transaction format".to_string())),
             None => Err(BitcoinRpcError::TxNotFound(txid.to_string())),
         }
     }
 
+    /// The proof set with `MockBitcoinNode::set_txout_proof` for the first of
+    /// `txids`; without `block_hash`, any block's
+    pub async fn get_txout_proof(&self, txids: &[Txid], block_hash: Option<&BlockHash>) -> Result<Vec<u8>, BitcoinRpcError> {
+        self.inject_faults(MockMethod::GetTxOutProof).await?;
+        let txid = txids.first()
+            .ok_or_else(|| BitcoinRpcError::InvalidResponse("No txids given".to_string()))?;
+        let proofs = self.node.txout_proofs.lock().unwrap();
+        proofs.iter()
+            .find(|((hash, proven), _)| proven == txid && block_hash.is_none_or(|wanted| wanted == hash))
+            .map(|(_, proof)| proof.clone())
+            .ok_or_else(|| BitcoinRpcError::TxNotFound(txid.to_string()))
+    }
+
     pub async fn get_utxo_status(&self, utxo: &UtxoMeta) -> Result<UtxoStatus, BitcoinRpcError> {
+        self.utxo_status_calls.fetch_add(1, Ordering::Relaxed);
+        let in_flight = self.utxo_status_in_flight.fetch_add(1, Ordering::Relaxed) + 1;
+        self.max_utxo_status_in_flight.fetch_max(in_flight, Ordering::Relaxed);
+        if let Some(latency) = self.latency {
+            sleep(latency).await;
+        }
+        self.utxo_status_in_flight.fetch_sub(1, Ordering::Relaxed);
+
+        self.inject_faults(MockMethod::GetUtxoStatus).await?;
+
         // First check if transaction exists
         match self.node.get_transaction(&utxo.txid) {
             Some(tx) => {
@@ -124,6 +667,22 @@ impl MockBitcoinRpcClient {
                     return Ok(UtxoStatus::Invalid);
                 }
 
+                let Some(output) = tx.outputs.get(utxo.vout as usize) else {
+                    msg!("Mock: {} has {} outputs, no vout {}", utxo.txid, tx.outputs.len(), utxo.vout);
+                    return Ok(UtxoStatus::Invalid);
+                };
+                if self.strict_outputs {
+                    if output.value.to_sat() != utxo.amount_sats {
+                        msg!("Mock: {}:{} holds {} sats, not {}", utxo.txid, utxo.vout, output.value.to_sat(), utxo.amount_sats);
+                        return Ok(UtxoStatus::Invalid);
+                    }
+                    let script = hex::encode(output.script_pubkey.as_bytes());
+                    if !utxo.script_pubkey.is_empty() && utxo.script_pubkey != script {
+                        msg!("Mock: {}:{} pays script {}, not {}", utxo.txid, utxo.vout, script, utxo.script_pubkey);
+                        return Ok(UtxoStatus::Invalid);
+                    }
+                }
+
                 // For valid transactions, check UTXO status
                 match self.node.is_utxo_spent(&utxo.txid, utxo.vout) {
                     Some(true) => Ok(UtxoStatus::Spent),
@@ -141,12 +700,144 @@ impl MockBitcoinRpcClient {
         }
     }
 
+    pub async fn get_raw_mempool(&self) -> Result<HashSet<String>, BitcoinRpcError> {
+        self.inject_faults(MockMethod::GetRawMempool).await?;
+        Ok(self.node.mempool_txids())
+    }
+
+    /// Mempool entry of an unconfirmed transaction; `TxNotFound` once it is
+    /// confirmed, replaced or unknown
+    pub async fn get_mempool_entry(&self, txid: &str) -> Result<MempoolEntry, BitcoinRpcError> {
+        self.inject_faults(MockMethod::GetMempoolEntry).await?;
+        let tx = self.node.get_transaction(txid)
+            .filter(|tx| tx.is_valid && tx.confirmations == 0)
+            .ok_or_else(|| BitcoinRpcError::TxNotFound(txid.to_string()))?;
+        Ok(MempoolEntry {
+            fee: Amount::from_sat(tx.fee_sats),
+            vsize: tx.vsize,
+            bip125_replaceable: tx.replaceable,
+        })
+    }
+
+    /// Spender of an output, as esplora's outspend endpoint reports it;
+    /// `TxNotFound` for outputs the node doesn't know
+    pub async fn get_outspend(&self, txid: &str, vout: u32) -> Result<Outspend, BitcoinRpcError> {
+        self.inject_faults(MockMethod::GetOutspend).await?;
+        match self.node.utxo_state(txid, vout) {
+            Some(MockUtxoState::SpentBy { txid, height }) => {
+                Ok(Outspend { spent: true, txid: Some(txid), block_height: height })
+            }
+            Some(MockUtxoState::Unspent) => Ok(Outspend { spent: false, txid: None, block_height: None }),
+            None => Err(BitcoinRpcError::TxNotFound(format!("{}:{}", txid, vout))),
+        }
+    }
+
+    pub async fn get_best_block_hash(&self) -> Result<BlockHash, BitcoinRpcError> {
+        self.inject_faults(MockMethod::GetBestBlockHash).await?;
+        Ok(self.node.best_block_hash())
+    }
+
+    /// Number of `get_block_height` calls served so far
+    pub fn block_height_calls(&self) -> usize {
+        self.block_height_calls.load(Ordering::Relaxed)
+    }
+
+    pub async fn get_block_height(&self) -> Result<u32, BitcoinRpcError> {
+        self.block_height_calls.fetch_add(1, Ordering::Relaxed);
+        self.inject_faults(MockMethod::GetBlockHeight).await?;
+        Ok(self.node.get_block_height())
+    }
+
+    /// `get_block_height` in the real client's return type; the mock never caches
+    pub async fn get_block_count(&self) -> Result<u64, BitcoinRpcError> {
+        self.get_block_height().await.map(u64::from)
+    }
+
+    pub async fn get_chain_tip(&self) -> Result<(u64, BlockHash), BitcoinRpcError> {
+        let height = self.get_block_height().await?;
+        let hash = self.node.block_hash(height)
+            .ok_or_else(|| BitcoinRpcError::InvalidResponse(format!("Block height out of range: {}", height)))?;
+        Ok((u64::from(height), hash))
+    }
+
+    pub async fn get_block(&self, hash: &BlockHash) -> Result<BlockInfo, BitcoinRpcError> {
+        self.inject_faults(MockMethod::GetBlock).await?;
+        self.node.block_info(hash)
+            .ok_or_else(|| BitcoinRpcError::InvalidResponse(format!("Block not found: {}", hash)))
+    }
+
+    pub async fn get_block_hash(&self, height: u32) -> Result<BlockHash, BitcoinRpcError> {
+        self.inject_faults(MockMethod::GetBlockHash).await?;
+        self.node.block_hash(height)
+            .ok_or_else(|| BitcoinRpcError::InvalidResponse(format!("Block height out of range: {}", height)))
+    }
+
+    /// Confirmations, block height and block hash of a transaction
+    ///
+    /// Mempool transactions report 0 confirmations with an empty block hash;
+    /// unknown or reorged-out transactions are `TxNotFound`, as on a node
+    /// without a transaction index.
+    pub async fn get_tx_block_info(&self, txid: &str) -> Result<(u32, u32, String), BitcoinRpcError> {
+        self.inject_faults(MockMethod::GetTxBlockInfo).await?;
+        let tx = self.node.get_transaction(txid)
+            .filter(|tx| tx.is_valid)
+            .ok_or_else(|| BitcoinRpcError::TxNotFound(txid.to_string()))?;
+        match tx.block_height {
+            Some(height) => {
+                let hash = self.node.block_hash(height)
+                    .ok_or_else(|| BitcoinRpcError::TxNotFound(txid.to_string()))?;
+                Ok((tx.confirmations, height, hash.to_string()))
+            }
+            None => Ok((0, 0, String::new())),
+        }
+    }
+
+    pub async fn update_utxo_confirmations(&self, utxo: &mut UtxoMeta) -> Result<u32, BitcoinRpcError> {
+        let confirmations = self.get_confirmations(utxo.txid_str()).await?;
+        utxo.confirmations = confirmations;
+        Ok(confirmations)
+    }
+
+    /// Number of `get_confirmations` calls served so far
+    pub fn confirmation_calls(&self) -> usize {
+        self.confirmation_calls.load(Ordering::Relaxed)
+    }
+
     pub async fn get_confirmations(&self, txid: &str) -> Result<u32, BitcoinRpcError> {
+        self.confirmation_calls.fetch_add(1, Ordering::Relaxed);
+        self.inject_faults(MockMethod::GetConfirmations).await?;
         Ok(self.node.get_transaction(txid)
             .map(|tx| tx.confirmations)
             .unwrap_or(0))
     }
 
+    /// Mock counterpart of `BitcoinRpcClient::wait_for_confirmations`, driven by
+    /// changes made to the `MockBitcoinNode`
+    pub async fn wait_for_confirmations(
+        &self,
+        txid: &str,
+        target: u32,
+        poll_interval: Duration,
+        timeout: Duration,
+    ) -> Result<u32, BitcoinRpcError> {
+        let poll = async {
+            loop {
+                let confirmations = match self.node.get_transaction(txid) {
+                    Some(tx) if tx.is_valid => tx.confirmations,
+                    _ => return Err(BitcoinRpcError::TxNotFound(txid.to_string())),
+                };
+                if confirmations >= target {
+                    return Ok(confirmations);
+                }
+                sleep(poll_interval).await;
+            }
+        };
+
+        tokio::time::timeout(timeout, poll)
+            .await
+            .unwrap_or(Err(BitcoinRpcError::Timeout))
+    }
+
     pub async fn validate_utxo(&self, utxo: &UtxoMeta) -> Result<(), BitcoinRpcError> {
         let status = self.get_utxo_status(utxo).await?;
         
@@ -163,10 +854,108 @@ impl MockBitcoinRpcClient {
         }
     }
 
+    /// Add `tx` to the node's mempool and spend the outputs it consumes
+    ///
+    /// Inputs the node has never seen are accepted, since mock chains rarely
+    /// hold a transaction's full ancestry; inputs it knows to be spent are not.
     pub async fn broadcast_transaction(&self, tx: &Transaction) -> Result<String, BitcoinRpcError> {
         if tx.input.is_empty() || tx.output.is_empty() {
             return Err(BitcoinRpcError::InvalidResponse("Invalid transaction format".to_string()));
         }
-        Ok("mock_txid".to_string())
+        self.inject_faults(MockMethod::BroadcastTransaction).await?;
+        let txid = tx.compute_txid().to_string();
+        let prevouts: Vec<(String, u32)> = tx.input.iter()
+            .map(|input| (input.previous_output.txid.to_string(), input.previous_output.vout))
+            .collect();
+        if let Some((prev_txid, vout)) = prevouts.iter()
+            .find(|(prev_txid, vout)| self.node.is_utxo_spent(prev_txid, *vout) == Some(true))
+        {
+            return Err(BitcoinRpcError::InvalidResponse(format!("Input {}:{} is already spent", prev_txid, vout)));
+        }
+
+        // The fee is only known when every input spends an output the node knows
+        let input_sats: Option<u64> = prevouts.iter()
+            .map(|(prev_txid, vout)| {
+                let prev = self.node.get_transaction(prev_txid)?;
+                prev.outputs.get(*vout as usize).map(|output| output.value.to_sat())
+            })
+            .sum();
+        let output_sats: u64 = tx.output.iter().map(|output| output.value.to_sat()).sum();
+        let fee_sats = input_sats.map_or(0, |input_sats| input_sats.saturating_sub(output_sats));
+
+        self.node.add_mempool_transaction(&txid, tx.output.clone(), fee_sats, tx.is_explicitly_rbf());
+        self.node.set_vsize(&txid, tx.vsize() as u64);
+        for (prev_txid, vout) in prevouts {
+            if self.node.is_utxo_spent(&prev_txid, vout).is_some() {
+                self.node.spend_utxo_with(&prev_txid, vout, &txid);
+            }
+        }
+        Ok(txid)
     }
-} 
\ No newline at end of file
+}
+
+#[async_trait]
+impl UtxoStatusSource for MockBitcoinRpcClient {
+    async fn fetch_utxo_status(&self, utxo: &UtxoMeta) -> Result<UtxoStatus, BitcoinRpcError> {
+        self.get_utxo_status(utxo).await
+    }
+}
+
+#[async_trait]
+impl UtxoValidationRpc for MockBitcoinRpcClient {
+    async fn get_best_block_hash(&self) -> Result<BlockHash, BitcoinRpcError> {
+        self.get_best_block_hash().await
+    }
+
+    async fn get_tx_block_info(&self, txid: &str) -> Result<(u32, u32, String), BitcoinRpcError> {
+        self.get_tx_block_info(txid).await
+    }
+
+    async fn get_utxo_status(&self, utxo: &UtxoMeta) -> Result<UtxoStatus, BitcoinRpcError> {
+        self.get_utxo_status(utxo).await
+    }
+
+    async fn get_transaction(&self, txid: &str) -> Result<Transaction, BitcoinRpcError> {
+        self.get_transaction(txid).await
+    }
+
+    async fn get_txout_proof(&self, txids: &[Txid], block_hash: Option<&BlockHash>) -> Result<Vec<u8>, BitcoinRpcError> {
+        self.get_txout_proof(txids, block_hash).await
+    }
+}
+
+#[async_trait]
+impl TrackerRpc for MockBitcoinRpcClient {
+    async fn get_confirmations(&self, txid: &str) -> Result<u32, BitcoinRpcError> {
+        self.get_confirmations(txid).await
+    }
+
+    async fn get_block_count(&self) -> Result<u64, BitcoinRpcError> {
+        self.get_block_count().await
+    }
+
+    async fn get_utxo_status(&self, utxo: &UtxoMeta) -> Result<UtxoStatus, BitcoinRpcError> {
+        self.get_utxo_status(utxo).await
+    }
+
+    async fn get_block(&self, hash: &BlockHash) -> Result<BlockInfo, BitcoinRpcError> {
+        self.get_block(hash).await
+    }
+
+    async fn get_raw_mempool(&self) -> Result<HashSet<String>, BitcoinRpcError> {
+        self.get_raw_mempool().await
+    }
+
+    async fn list_unspent(&self, min_conf: u32, _addresses: &[String]) -> Result<Vec<UtxoMeta>, BitcoinRpcError> {
+        self.inject_faults(MockMethod::ListUnspent).await?;
+        let txs = self.node.transactions.lock().unwrap();
+        let utxos = self.node.utxo_set.lock().unwrap();
+        Ok(utxos.iter()
+            .filter(|(_, state)| **state == MockUtxoState::Unspent)
+            .filter_map(|((txid, vout), _)| {
+                let tx = txs.get(txid).filter(|tx| tx.is_valid && tx.confirmations >= min_conf)?;
+                stored_utxo_meta(txid, *vout, tx)
+            })
+            .collect())
+    }
+}
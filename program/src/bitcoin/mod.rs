@@ -1,15 +1,73 @@
+// What the instruction handlers decode on-chain: payment memos, SPV proofs
+// and the UTXO and treasury script types
+pub mod memo;
+pub mod spv;
 pub mod utxo;
+
+// Node clients, caches and the tracker, which only run off-chain
+#[cfg(feature = "client")]
+pub mod error;
+#[cfg(feature = "client")]
+pub mod block;
+#[cfg(feature = "client")]
 pub mod cache;
+#[cfg(feature = "client")]
+pub mod config;
+#[cfg(feature = "client")]
+pub mod metrics;
+#[cfg(feature = "client")]
+pub mod rate_limit;
+#[cfg(feature = "client")]
+pub mod coalesce;
+#[cfg(feature = "client")]
+pub mod chain_tip;
+#[cfg(feature = "client")]
+pub mod esplora;
+#[cfg(feature = "client")]
+pub mod trace;
 
 // Conditionally import the right implementation
-#[cfg(target_arch = "wasm32")]
+#[cfg(all(feature = "client", target_arch = "wasm32"))]
+#[path = "mock.rs"]
+pub mod mock;
+
+// In-memory node and client for tests
+#[cfg(all(feature = "client", not(target_arch = "wasm32")))]
+#[path = "mock/mod.rs"]
 pub mod mock;
 
-#[cfg(target_arch = "wasm32")]
+#[cfg(all(feature = "client", target_arch = "wasm32"))]
 pub use mock::*;
 
-#[cfg(not(target_arch = "wasm32"))]
+#[cfg(all(feature = "client", not(target_arch = "wasm32")))]
 pub mod rpc;
 
-#[cfg(not(target_arch = "wasm32"))]
+#[cfg(all(feature = "client", not(target_arch = "wasm32")))]
 pub use rpc::*;
+
+#[cfg(all(feature = "client", not(target_arch = "wasm32")))]
+pub mod utxo_tracker;
+
+#[cfg(all(feature = "client", not(target_arch = "wasm32")))]
+pub mod export;
+
+#[cfg(all(feature = "client", not(target_arch = "wasm32")))]
+pub use export::{ExportFormat, ImportError, ImportReport};
+
+#[cfg(all(feature = "client", not(target_arch = "wasm32")))]
+pub use utxo_tracker::{
+    PollConfig, PrunePolicy, Reservation, StatusSummary, TrackerError, TrackerRpc, TrackerSnapshot, TrackerStats, UtxoEvent,
+    UtxoFilter, UtxoTracker, UtxoTracking,
+};
+
+#[cfg(feature = "client")]
+pub use error::BitcoinRpcError;
+#[cfg(feature = "client")]
+pub use config::ConfigError;
+#[cfg(feature = "client")]
+pub use block::BlockInfo;
+#[cfg(feature = "client")]
+pub use utxo::UtxoValidationRpc;
+pub use utxo::{Confirmations, UtxoError, UtxoKey, UtxoMeta, UtxoStatus};
+pub use spv::verify_merkle_proof;
+pub use memo::{parse_payment_memo, PaymentIntent, PaymentMemo};
@@ -0,0 +1,76 @@
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tokio::time::{sleep, Instant};
+
+/// Token-bucket limiter for outbound requests
+///
+/// The bucket holds up to one second's worth of permits, so short bursts go
+/// out immediately and sustained load is spread evenly at `max_per_second`.
+/// Waiters are served in arrival order: `tokio::sync::Mutex` is fair, and the
+/// task at the head of the queue holds it while sleeping for its permit.
+#[derive(Debug)]
+pub struct RateLimiter {
+    bucket: Mutex<Bucket>,
+    max_per_second: f64,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl RateLimiter {
+    pub fn new(max_per_second: u32) -> Self {
+        let max_per_second = f64::from(max_per_second.max(1));
+        Self {
+            bucket: Mutex::new(Bucket {
+                tokens: max_per_second,
+                last_refill: Instant::now(),
+            }),
+            max_per_second,
+        }
+    }
+
+    /// Wait until a request may be sent
+    pub async fn acquire(&self) {
+        let mut bucket = self.bucket.lock().await;
+        loop {
+            let now = Instant::now();
+            let refilled = now.duration_since(bucket.last_refill).as_secs_f64() * self.max_per_second;
+            bucket.tokens = (bucket.tokens + refilled).min(self.max_per_second);
+            bucket.last_refill = now;
+
+            if bucket.tokens >= 1.0 {
+                bucket.tokens -= 1.0;
+                return;
+            }
+
+            let missing = 1.0 - bucket.tokens;
+            sleep(Duration::from_secs_f64(missing / self.max_per_second)).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_burst_then_steady_rate() {
+        let limiter = RateLimiter::new(10);
+        let start = Instant::now();
+
+        // The first second's worth of permits is available immediately
+        for _ in 0..10 {
+            limiter.acquire().await;
+        }
+        assert!(start.elapsed() < Duration::from_millis(50));
+
+        // After that, permits are handed out every 100ms
+        for _ in 0..5 {
+            limiter.acquire().await;
+        }
+        assert!(start.elapsed() >= Duration::from_millis(450));
+    }
+}
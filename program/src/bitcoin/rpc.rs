@@ -1,52 +1,412 @@
 use arch_program::program_error::ProgramError;
-use bitcoin::{Transaction, Amount, BlockHash, Block};
-use crate::bitcoin::utxo::{UtxoMeta, UtxoStatus};
-use crate::bitcoin::cache::{UtxoCache, UtxoCacheConfig, CacheStats};
+use bitcoin::{Transaction, Txid, Amount, BlockHash, Block, FeeRate, Network};
+use crate::bitcoin::block::BlockInfo;
+use crate::bitcoin::error::BitcoinRpcError;
+use crate::bitcoin::utxo::{checked_amount_from_btc, UtxoMeta, UtxoStatus, UtxoValidationRpc};
+use crate::bitcoin::cache::{UtxoCache, UtxoCacheConfig, CacheStats, MaintenanceHandle, UtxoStatusSource};
+use crate::bitcoin::utxo_tracker::TrackerRpc;
+use crate::bitcoin::metrics::{Metrics, MetricsSnapshot};
+use crate::bitcoin::rate_limit::RateLimiter;
+use crate::bitcoin::coalesce::RequestCoalescer;
+use crate::bitcoin::chain_tip::{TipCache, DEFAULT_TIP_CACHE_TTL};
+use crate::bitcoin::esplora::{outspend_status, EsploraOutspend, EsploraTxStatus, SpendInfo};
+use crate::bitcoin::trace;
+use crate::bitcoin::config::{
+    env_secs, env_string, env_value, ConfigError, BITCOIN_RPC_HOST_ENV, BITCOIN_RPC_MAX_RPS_ENV,
+    BITCOIN_RPC_PASSWORD_ENV, BITCOIN_RPC_PORT_ENV, BITCOIN_RPC_TIMEOUT_ENV, BITCOIN_RPC_URL_ENV, BITCOIN_RPC_USER_ENV,
+    BITCOIN_RPC_WALLET_ENV, ELECTRS_URL_ENV, MIN_CONFIRMATIONS_ENV,
+};
+use crate::network_config::{get_network_params, try_get_network};
 #[cfg(not(target_arch = "wasm32"))]
-use reqwest::{Client, ClientBuilder};
+use async_trait::async_trait;
+use reqwest::{Certificate, Client, ClientBuilder};
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::PathBuf;
 use std::time::Duration;
 #[cfg(not(target_arch = "wasm32"))]
 use tokio::time::sleep;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Instant;
+use url::Url;
 
 const MAX_RETRIES: u32 = 3;
 const RETRY_DELAY_MS: u64 = 1000;
 const REQUEST_TIMEOUT_SECS: u64 = 30;
+const CONNECT_TIMEOUT_SECS: u64 = 10;
+const POOL_IDLE_TIMEOUT_SECS: u64 = 90;
+const TCP_KEEPALIVE_SECS: u64 = 60;
 
 #[derive(Debug, Clone)]
 pub struct BitcoinRpcClient {
-    endpoint: String,
-    port: u16,
+    /// Node URL without the wallet path
+    base_url: Url,
     username: String,
     password: String,
+    wallet_name: Option<String>,
     #[cfg(not(target_arch = "wasm32"))]
     http_client: Client,
-    cache: UtxoCache,
+    /// Confirmations from the config, for policies and trackers built on this client
+    min_confirmations: u32,
+    /// Shared between clones, like the metrics
+    cache: Arc<UtxoCache>,
+    /// Background cleanup of `cache`, aborted when the last clone is dropped
+    cache_maintenance: Option<Arc<MaintenanceHandle>>,
+    metrics: Arc<Metrics>,
+    /// Esplora endpoint for outspend lookups, which the node can't answer
+    electrs_endpoint: Option<String>,
+    /// Esplora endpoint used for transaction lookups the node can't serve
+    electrs_fallback: Option<String>,
+    /// Shared outbound rate limiter, None when unlimited
+    rate_limiter: Option<Arc<RateLimiter>>,
+    /// Shares results between identical concurrent calls, None when every call goes out
+    coalescer: Option<Arc<RequestCoalescer>>,
+    /// Recently fetched tip, shared between clones
+    tip_cache: Arc<TipCache>,
+    /// Id for the next JSON-RPC request, so responses can be matched to requests.
+    /// Shared between clones so ids stay unique per connection target.
+    next_request_id: Arc<AtomicU64>,
 }
 
 #[derive(Debug, Clone)]
 pub struct BitcoinRpcConfig {
     pub endpoint: String,
     pub port: u16,
+    /// Full node URL including the scheme, e.g. `https://node.example.com:8443`.
+    /// Takes precedence over `endpoint`/`port`, which always use plain http.
+    pub url: Option<String>,
+    /// Accept self-signed or otherwise invalid TLS certificates (dev setups only)
+    pub accept_invalid_certs: bool,
+    /// PEM file with an additional root CA to trust for https nodes
+    pub root_ca_path: Option<PathBuf>,
     pub username: String,
     pub password: String,
+    /// Maximum time to wait for the TCP connection to be established
+    pub connect_timeout: Duration,
+    /// Maximum time for a whole request, including reading the response body
+    pub request_timeout: Duration,
+    /// How long idle pooled connections are kept alive for reuse (None keeps them forever)
+    pub pool_idle_timeout: Option<Duration>,
+    /// TCP keep-alive interval for pooled connections
+    pub tcp_keepalive: Option<Duration>,
+    /// Optional proxy URL, e.g. `socks5h://127.0.0.1:9050` to reach the node over Tor
+    pub proxy_url: Option<String>,
+    /// Bitcoin Core wallet to route calls to (`/wallet/<name>`), e.g. a watch-only treasury wallet
+    pub wallet_name: Option<String>,
+    /// Outbound request limit; None sends requests as fast as they are made
+    pub max_requests_per_second: Option<u32>,
+    /// Esplora/electrs base URL, e.g. `http://127.0.0.1:3002`
+    pub electrs_endpoint: Option<String>,
+    /// Look transactions up on `electrs_endpoint` when the node has no txindex
+    pub electrs_fallback: bool,
+    /// Share one node call between identical concurrent calls (same method and
+    /// params), and reuse its result for this long after it returns. None sends
+    /// every call; `Duration::ZERO` only shares calls still in flight.
+    pub coalesce_window: Option<Duration>,
+    /// How long `get_block_count` and `get_chain_tip` reuse a fetched tip;
+    /// `Duration::ZERO` always asks the node
+    pub tip_cache_ttl: Duration,
+    /// Confirmations before an output counts as settled on this node's network;
+    /// validation policies, trackers and caches built from this config use it
+    pub min_confirmations: u32,
 }
 
-#[derive(Debug, thiserror::Error)]
-pub enum BitcoinRpcError {
-    #[error("RPC connection failed: {0}")]
-    ConnectionFailed(String),
-    #[error("Invalid response: {0}")]
-    InvalidResponse(String),
-    #[error("Transaction not found: {0}")]
-    TxNotFound(String),
-    #[error("Network error: {0}")]
-    NetworkError(String),
-    #[error("Timeout error")]
-    Timeout,
-    #[error("Invalid credentials")]
-    AuthError,
+impl Default for BitcoinRpcConfig {
+    fn default() -> Self {
+        Self {
+            endpoint: "127.0.0.1".to_string(),
+            port: 8332,
+            url: None,
+            accept_invalid_certs: false,
+            root_ca_path: None,
+            username: String::new(),
+            password: String::new(),
+            connect_timeout: Duration::from_secs(CONNECT_TIMEOUT_SECS),
+            request_timeout: Duration::from_secs(REQUEST_TIMEOUT_SECS),
+            pool_idle_timeout: Some(Duration::from_secs(POOL_IDLE_TIMEOUT_SECS)),
+            tcp_keepalive: Some(Duration::from_secs(TCP_KEEPALIVE_SECS)),
+            proxy_url: None,
+            wallet_name: None,
+            max_requests_per_second: None,
+            electrs_endpoint: None,
+            electrs_fallback: false,
+            coalesce_window: None,
+            tip_cache_ttl: DEFAULT_TIP_CACHE_TTL,
+            min_confirmations: get_network_params(Network::Bitcoin).min_confirmations,
+        }
+    }
+}
+
+impl BitcoinRpcConfig {
+    /// Config for a local node on `network`'s default RPC port, requiring
+    /// `network`'s confirmations
+    pub fn for_network(network: Network) -> Self {
+        let params = get_network_params(network);
+        Self {
+            port: params.rpc_port,
+            min_confirmations: params.min_confirmations,
+            ..Default::default()
+        }
+    }
+
+    pub fn mainnet() -> Self {
+        Self::for_network(Network::Bitcoin)
+    }
+
+    pub fn testnet4() -> Self {
+        Self::for_network(Network::Testnet)
+    }
+
+    pub fn signet() -> Self {
+        Self::for_network(Network::Signet)
+    }
+
+    pub fn regtest() -> Self {
+        Self::for_network(Network::Regtest)
+    }
+
+    /// Config for a node reachable at a full URL, with all other settings defaulted
+    pub fn from_url(url: &str) -> Result<Self, BitcoinRpcError> {
+        parse_node_url(url)?;
+        Ok(Self {
+            url: Some(url.to_string()),
+            ..Default::default()
+        })
+    }
+
+    /// Resolve the node URL, from `url` when set and from `endpoint`/`port` otherwise
+    pub fn node_url(&self) -> Result<Url, BitcoinRpcError> {
+        match &self.url {
+            Some(url) => parse_node_url(url),
+            None => parse_node_url(&format!("http://{}:{}", self.endpoint, self.port)),
+        }
+    }
+
+    /// Reject settings no client can work with, or that contradict each other
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if self.url.is_none() && self.endpoint.trim().is_empty() {
+            return Err(ConfigError::MissingEndpoint);
+        }
+        self.node_url().map_err(|e| match e {
+            BitcoinRpcError::ConnectionFailed(message) => ConfigError::InvalidUrl(message),
+            e => ConfigError::InvalidUrl(e.to_string()),
+        })?;
+        if self.username.is_empty() != self.password.is_empty() {
+            return Err(ConfigError::IncompleteCredentials);
+        }
+        if self.request_timeout.is_zero() {
+            return Err(ConfigError::ZeroDuration("request_timeout"));
+        }
+        if self.connect_timeout.is_zero() {
+            return Err(ConfigError::ZeroDuration("connect_timeout"));
+        }
+        if self.connect_timeout > self.request_timeout {
+            return Err(ConfigError::ConnectTimeoutExceedsRequestTimeout {
+                connect_timeout: self.connect_timeout,
+                request_timeout: self.request_timeout,
+            });
+        }
+        if self.max_requests_per_second == Some(0) {
+            return Err(ConfigError::ZeroRateLimit);
+        }
+        if self.electrs_fallback && self.electrs_endpoint.is_none() {
+            return Err(ConfigError::FallbackWithoutElectrs);
+        }
+        if self.min_confirmations == 0 {
+            return Err(ConfigError::ZeroConfirmations);
+        }
+        Ok(())
+    }
+}
+
+/// Builds a `BitcoinRpcClient`, checking its config with `BitcoinRpcConfig::validate`
+#[derive(Debug, Clone, Default)]
+pub struct BitcoinRpcClientBuilder {
+    config: BitcoinRpcConfig,
+    cache: Option<UtxoCacheConfig>,
+}
+
+impl BitcoinRpcClientBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start from `config` rather than the defaults
+    pub fn from_config(config: BitcoinRpcConfig) -> Self {
+        Self { config, cache: None }
+    }
+
+    /// The preset for the network in `ARCH_NETWORK`, overridden by the
+    /// `BITCOIN_RPC_*`, `ELECTRS_URL` and `BITCOIN_MIN_CONFIRMATIONS` variables that are set
+    ///
+    /// Setters called afterwards override both.
+    pub fn from_env() -> Result<Self, ConfigError> {
+        let mut config = BitcoinRpcConfig::for_network(try_get_network()?);
+        if let Some(url) = env_string(BITCOIN_RPC_URL_ENV) {
+            config.url = Some(url);
+        }
+        if let Some(host) = env_value(BITCOIN_RPC_HOST_ENV, "host name")? {
+            config.endpoint = host;
+        }
+        if let Some(port) = env_value(BITCOIN_RPC_PORT_ENV, "port")? {
+            config.port = port;
+        }
+        if let Some(username) = env_string(BITCOIN_RPC_USER_ENV) {
+            config.username = username;
+        }
+        if let Some(password) = env_string(BITCOIN_RPC_PASSWORD_ENV) {
+            config.password = password;
+        }
+        if let Some(wallet) = env_string(BITCOIN_RPC_WALLET_ENV) {
+            config.wallet_name = Some(wallet);
+        }
+        if let Some(timeout) = env_secs(BITCOIN_RPC_TIMEOUT_ENV)? {
+            config.request_timeout = timeout;
+        }
+        if let Some(limit) = env_value(BITCOIN_RPC_MAX_RPS_ENV, "number of requests per second")? {
+            config.max_requests_per_second = Some(limit);
+        }
+        if let Some(electrs) = env_string(ELECTRS_URL_ENV) {
+            config.electrs_endpoint = Some(electrs);
+        }
+        if let Some(confirmations) = env_value(MIN_CONFIRMATIONS_ENV, "number of confirmations")? {
+            config.min_confirmations = confirmations;
+        }
+        Ok(Self::from_config(config))
+    }
+
+    /// Use `network`'s port and confirmations
+    pub fn with_network(mut self, network: Network) -> Self {
+        let params = get_network_params(network);
+        self.config.port = params.rpc_port;
+        self.config.min_confirmations = params.min_confirmations;
+        self
+    }
+
+    pub fn with_endpoint(mut self, endpoint: impl Into<String>, port: u16) -> Self {
+        self.config.endpoint = endpoint.into();
+        self.config.port = port;
+        self
+    }
+
+    /// Full node URL, taking precedence over the endpoint and port
+    pub fn with_url(mut self, url: impl Into<String>) -> Self {
+        self.config.url = Some(url.into());
+        self
+    }
+
+    pub fn with_auth(mut self, username: impl Into<String>, password: impl Into<String>) -> Self {
+        self.config.username = username.into();
+        self.config.password = password.into();
+        self
+    }
+
+    pub fn with_wallet(mut self, wallet_name: impl Into<String>) -> Self {
+        self.config.wallet_name = Some(wallet_name.into());
+        self
+    }
+
+    pub fn with_connect_timeout(mut self, timeout: Duration) -> Self {
+        self.config.connect_timeout = timeout;
+        self
+    }
+
+    pub fn with_request_timeout(mut self, timeout: Duration) -> Self {
+        self.config.request_timeout = timeout;
+        self
+    }
+
+    pub fn with_proxy(mut self, proxy_url: impl Into<String>) -> Self {
+        self.config.proxy_url = Some(proxy_url.into());
+        self
+    }
+
+    pub fn with_max_requests_per_second(mut self, limit: u32) -> Self {
+        self.config.max_requests_per_second = Some(limit);
+        self
+    }
+
+    /// Esplora endpoint, also used for lookups the node can't serve when `fallback` is set
+    pub fn with_electrs(mut self, endpoint: impl Into<String>, fallback: bool) -> Self {
+        self.config.electrs_endpoint = Some(endpoint.into());
+        self.config.electrs_fallback = fallback;
+        self
+    }
+
+    pub fn with_coalesce_window(mut self, window: Duration) -> Self {
+        self.config.coalesce_window = Some(window);
+        self
+    }
+
+    pub fn with_tip_cache_ttl(mut self, ttl: Duration) -> Self {
+        self.config.tip_cache_ttl = ttl;
+        self
+    }
+
+    pub fn with_min_confirmations(mut self, confirmations: u32) -> Self {
+        self.config.min_confirmations = confirmations;
+        self
+    }
+
+    /// Cache settings, instead of the defaults with the config's `min_confirmations`
+    pub fn with_cache(mut self, cache: UtxoCacheConfig) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// The validated config, without building a client
+    pub fn build_config(&self) -> Result<BitcoinRpcConfig, ConfigError> {
+        self.config.validate()?;
+        if let Some(cache) = &self.cache {
+            cache.validate()?;
+        }
+        Ok(self.config.clone())
+    }
+
+    /// The client, with the cache given to `with_cache` if any
+    ///
+    /// A cache with a `maintenance_interval` spawns its cleanup task, so this
+    /// must then be called from within a tokio runtime.
+    pub fn build(self) -> Result<BitcoinRpcClient, ConfigError> {
+        let config = self.build_config()?;
+        let client = BitcoinRpcClient::new(config)?;
+        Ok(match self.cache {
+            Some(cache) => client.with_cache_config(cache),
+            None => client,
+        })
+    }
+}
+
+impl From<(&str, u16)> for BitcoinRpcConfig {
+    fn from((endpoint, port): (&str, u16)) -> Self {
+        Self {
+            endpoint: endpoint.to_string(),
+            port,
+            ..Default::default()
+        }
+    }
+}
+
+/// Parse a node URL, accepting only http and https URLs with a host
+fn parse_node_url(url: &str) -> Result<Url, BitcoinRpcError> {
+    let parsed = Url::parse(url)
+        .map_err(|e| BitcoinRpcError::ConnectionFailed(format!("Invalid RPC URL {}: {}", url, e)))?;
+
+    match parsed.scheme() {
+        "http" | "https" => {}
+        scheme => {
+            return Err(BitcoinRpcError::ConnectionFailed(format!(
+                "Unsupported RPC URL scheme {}: expected http or https",
+                scheme
+            )))
+        }
+    }
+    if parsed.host_str().is_none() {
+        return Err(BitcoinRpcError::ConnectionFailed(format!("RPC URL {} has no host", url)));
+    }
+
+    Ok(parsed)
 }
 
 #[derive(Debug, Serialize)]
@@ -61,6 +421,7 @@ struct JsonRpcRequest<T> {
 struct JsonRpcResponse<T> {
     result: Option<T>,
     error: Option<JsonRpcError>,
+    id: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -69,46 +430,246 @@ struct JsonRpcError {
     message: String,
 }
 
+/// The fields of a `getblockchaininfo` response the client uses
+#[derive(Debug, Deserialize)]
+struct BlockchainInfo {
+    blocks: u64,
+    bestblockhash: BlockHash,
+}
+
+/// Entry of a `listunspent` wallet response
+#[derive(Debug, Deserialize)]
+struct ListUnspentEntry {
+    txid: String,
+    vout: u32,
+    #[serde(rename = "scriptPubKey")]
+    script_pubkey: String,
+    /// Amount in BTC
+    amount: f64,
+    confirmations: u32,
+}
+
+/// Mempool data for an unconfirmed transaction, as reported by `getmempoolentry`
+#[derive(Debug, Clone, PartialEq)]
+pub struct MempoolEntry {
+    /// Fee paid by the transaction itself (excluding ancestors/descendants)
+    pub fee: Amount,
+    /// Virtual size in vbytes
+    pub vsize: u64,
+    /// Whether the transaction signals BIP125 replaceability (directly or through an ancestor)
+    pub bip125_replaceable: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawMempoolEntry {
+    vsize: u64,
+    fees: RawMempoolFees,
+    #[serde(rename = "bip125-replaceable")]
+    bip125_replaceable: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawMempoolFees {
+    /// Fee in BTC
+    base: f64,
+}
+
+#[derive(Debug, Deserialize)]
+struct SmartFeeEstimate {
+    /// BTC per kvB; missing when the node has too little data to estimate
+    feerate: Option<f64>,
+    #[serde(default)]
+    errors: Vec<String>,
+}
+
+/// Parse a `Retry-After` header given in seconds (the HTTP-date form is ignored)
+fn parse_retry_after(headers: &reqwest::header::HeaderMap) -> Option<Duration> {
+    headers
+        .get(reqwest::header::RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse::<u64>()
+        .ok()
+        .map(Duration::from_secs)
+}
+
 impl BitcoinRpcClient {
-    pub fn new(config: BitcoinRpcConfig) -> Self {
-        let http_client = reqwest::Client::new();
-        Self {
-            endpoint: config.endpoint,
-            port: config.port,
+    pub fn builder() -> BitcoinRpcClientBuilder {
+        BitcoinRpcClientBuilder::new()
+    }
+
+    /// Create a client with an HTTP client built from the timeout, keep-alive,
+    /// proxy and TLS settings in `config`
+    pub fn new(config: BitcoinRpcConfig) -> Result<Self, BitcoinRpcError> {
+        let http_client = Self::build_http_client(&config)?;
+        Self::with_http_client(config, http_client)
+    }
+
+    /// Create a client around a preconfigured HTTP client (custom proxies, TLS, etc.)
+    ///
+    /// The timeout, proxy and TLS fields of `config` are ignored; the caller's client is used as is.
+    pub fn with_http_client(config: BitcoinRpcConfig, http_client: Client) -> Result<Self, BitcoinRpcError> {
+        let cache = Arc::new(UtxoCache::new(UtxoCacheConfig::from_rpc_config(&config)));
+        Ok(Self {
+            base_url: config.node_url()?,
             username: config.username,
             password: config.password,
-            #[cfg(not(target_arch = "wasm32"))]
+            wallet_name: config.wallet_name,
             http_client,
-            cache: Default::default(),
+            min_confirmations: config.min_confirmations,
+            cache,
+            cache_maintenance: None,
+            metrics: Arc::new(Metrics::new()),
+            electrs_fallback: config.electrs_endpoint
+                .as_deref()
+                .filter(|_| config.electrs_fallback)
+                .map(|endpoint| endpoint.trim_end_matches('/').to_string()),
+            electrs_endpoint: config.electrs_endpoint
+                .map(|endpoint| endpoint.trim_end_matches('/').to_string()),
+            rate_limiter: config.max_requests_per_second.map(|limit| Arc::new(RateLimiter::new(limit))),
+            coalescer: config.coalesce_window.map(|window| Arc::new(RequestCoalescer::new(window))),
+            tip_cache: Arc::new(TipCache::new(config.tip_cache_ttl)),
+            next_request_id: Arc::new(AtomicU64::new(1)),
+        })
+    }
+
+    fn build_http_client(config: &BitcoinRpcConfig) -> Result<Client, BitcoinRpcError> {
+        let mut builder = ClientBuilder::new()
+            .connect_timeout(config.connect_timeout)
+            .timeout(config.request_timeout)
+            .pool_idle_timeout(config.pool_idle_timeout)
+            .tcp_keepalive(config.tcp_keepalive);
+
+        if config.node_url()?.scheme() == "https" {
+            builder = builder
+                .use_rustls_tls()
+                .danger_accept_invalid_certs(config.accept_invalid_certs);
+
+            if let Some(path) = &config.root_ca_path {
+                let pem = std::fs::read(path).map_err(|e| {
+                    BitcoinRpcError::ConnectionFailed(format!("Failed to read root CA {}: {}", path.display(), e))
+                })?;
+                let certificate = Certificate::from_pem(&pem)
+                    .map_err(|e| BitcoinRpcError::ConnectionFailed(format!("Invalid root CA certificate: {}", e)))?;
+                builder = builder.add_root_certificate(certificate);
+            }
+        }
+
+        if let Some(proxy_url) = &config.proxy_url {
+            let proxy = reqwest::Proxy::all(proxy_url.as_str())
+                .map_err(|e| BitcoinRpcError::ConnectionFailed(format!("Invalid proxy URL: {}", e)))?;
+            builder = builder.proxy(proxy);
+        }
+
+        builder
+            .build()
+            .map_err(|e| BitcoinRpcError::ConnectionFailed(format!("Failed to build HTTP client: {}", e)))
+    }
+
+    /// JSON-RPC URL, including the wallet path when a wallet is configured
+    fn rpc_url(&self) -> String {
+        let base = self.base_url.as_str().trim_end_matches('/');
+        match &self.wallet_name {
+            Some(wallet) => format!("{}/wallet/{}", base, wallet),
+            None => base.to_string(),
         }
     }
 
     async fn make_rpc_call<T, R>(&self, method: &str, params: T) -> Result<R, BitcoinRpcError>
+    where
+        T: Serialize,
+        R: for<'de> Deserialize<'de>,
+    {
+        let Some(coalescer) = &self.coalescer else {
+            return self.send_rpc_call(method, params).await;
+        };
+
+        // Share the raw result, so each caller deserializes its own copy
+        let key = RequestCoalescer::key(method, &params)?;
+        let (result, shared) = coalescer
+            .run(key, || self.send_rpc_call::<T, serde_json::Value>(method, params))
+            .await;
+        if shared {
+            self.metrics.record_rpc_coalesced();
+        }
+        serde_json::from_value(result?)
+            .map_err(|e| BitcoinRpcError::InvalidResponse(format!("Unexpected {} result: {}", method, e)))
+    }
+
+    /// Send a call to the node, retrying transient failures
+    #[cfg_attr(feature = "tracing", tracing::instrument(
+        name = "rpc_call",
+        skip_all,
+        fields(method = %method, duration_ms = tracing::field::Empty, outcome = tracing::field::Empty),
+    ))]
+    async fn send_rpc_call<T, R>(&self, method: &str, params: T) -> Result<R, BitcoinRpcError>
+    where
+        T: Serialize,
+        R: for<'de> Deserialize<'de>,
+    {
+        let started = Instant::now();
+        let result = self.send_with_retries(method, params).await;
+        trace::record("duration_ms", started.elapsed().as_millis());
+        trace::record_outcome(&result);
+        result
+    }
+
+    async fn send_with_retries<T, R>(&self, method: &str, params: T) -> Result<R, BitcoinRpcError>
     where
         T: Serialize,
         R: for<'de> Deserialize<'de>,
     {
         let request = JsonRpcRequest {
             jsonrpc: "2.0".to_string(),
-            id: "1".to_string(),
+            id: self.next_request_id.fetch_add(1, Ordering::Relaxed).to_string(),
             method: method.to_string(),
             params,
         };
 
+        self.metrics.record_rpc_request(method);
+
         let mut retries = 0;
         while retries < MAX_RETRIES {
-            match self.execute_rpc_call::<T, R>(&request).await {
+            if let Some(limiter) = &self.rate_limiter {
+                self.metrics.rpc_queued();
+                limiter.acquire().await;
+                self.metrics.rpc_dequeued();
+            }
+
+            let started = Instant::now();
+            let result = self.execute_rpc_call::<T, R>(&request).await;
+            self.metrics.record_rpc_latency(started.elapsed());
+
+            match result {
                 Ok(response) => return Ok(response),
+                // Retrying won't make a missing transaction appear, fix bad credentials
+                // or change a node's considered answer
+                Err(e) if e.is_not_found()
+                    || (!e.is_retryable()
+                        && matches!(e, BitcoinRpcError::AuthError | BitcoinRpcError::NodeError { .. })) =>
+                {
+                    self.metrics.record_rpc_failure();
+                    return Err(e);
+                }
                 Err(e) => {
                     if retries == MAX_RETRIES - 1 {
+                        self.metrics.record_rpc_failure();
                         return Err(e);
                     }
                     retries += 1;
-                    sleep(Duration::from_millis(RETRY_DELAY_MS)).await;
+                    self.metrics.record_rpc_retry();
+                    // Honour the node's Retry-After when it throttles us
+                    let delay = match &e {
+                        BitcoinRpcError::RateLimited { retry_after: Some(retry_after) } => *retry_after,
+                        _ => Duration::from_millis(RETRY_DELAY_MS),
+                    };
+                    sleep(delay).await;
                 }
             }
         }
-        Err(BitcoinRpcError::NetworkError("Max retries exceeded".to_string()))
+        self.metrics.record_rpc_failure();
+        Err(BitcoinRpcError::ConnectionFailed("Max retries exceeded".to_string()))
     }
 
     async fn execute_rpc_call<T, R>(&self, request: &JsonRpcRequest<T>) -> Result<R, BitcoinRpcError>
@@ -116,34 +677,74 @@ impl BitcoinRpcClient {
         T: Serialize,
         R: for<'de> Deserialize<'de>,
     {
-        let url = format!("http://{}:{}", self.endpoint, self.port);
+        let url = self.rpc_url();
         let response = self.http_client
             .post(&url)
             .basic_auth(&self.username, Some(&self.password))
             .json(request)
             .send()
-            .await
-            .map_err(|e| BitcoinRpcError::ConnectionFailed(e.to_string()))?;
+            .await?;
 
         if response.status() == reqwest::StatusCode::UNAUTHORIZED {
             return Err(BitcoinRpcError::AuthError);
         }
+        if response.status() == reqwest::StatusCode::TOO_MANY_REQUESTS {
+            return Err(BitcoinRpcError::RateLimited {
+                retry_after: parse_retry_after(response.headers()),
+            });
+        }
 
         let rpc_response: JsonRpcResponse<R> = response
             .json()
-            .await
-            .map_err(|e| BitcoinRpcError::InvalidResponse(e.to_string()))?;
+            .await?;
+
+        // Reject responses that belong to a different request
+        if rpc_response.id.as_deref() != Some(request.id.as_str()) {
+            return Err(BitcoinRpcError::InvalidResponse("id mismatch".to_string()));
+        }
 
         match (rpc_response.result, rpc_response.error) {
             (Some(result), None) => Ok(result),
-            (None, Some(error)) => Err(BitcoinRpcError::InvalidResponse(error.message)),
+            (None, Some(error)) => Err(BitcoinRpcError::from_node_error(error.code, error.message)),
             _ => Err(BitcoinRpcError::InvalidResponse("Invalid JSON-RPC response".to_string())),
         }
     }
 
     pub async fn get_transaction(&self, txid: &str) -> Result<Transaction, BitcoinRpcError> {
         let params = vec![txid];
-        self.make_rpc_call("getrawtransaction", params).await
+        match self.make_rpc_call("getrawtransaction", params).await {
+            // Without txindex the node only knows mempool and wallet transactions
+            Err(BitcoinRpcError::TxNotFound(message)) => match &self.electrs_fallback {
+                Some(electrs_endpoint) => self.get_transaction_from_electrs(electrs_endpoint, txid).await,
+                None => Err(BitcoinRpcError::TxNotFound(message)),
+            },
+            result => result,
+        }
+    }
+
+    /// Fetch a raw transaction from esplora's `GET /tx/:txid/hex`
+    async fn get_transaction_from_electrs(&self, electrs_endpoint: &str, txid: &str) -> Result<Transaction, BitcoinRpcError> {
+        self.metrics.record_electrs_fallback();
+        if let Some(limiter) = &self.rate_limiter {
+            limiter.acquire().await;
+        }
+
+        let url = format!("{}/tx/{}/hex", electrs_endpoint, txid);
+        let response = self.http_client
+            .get(&url)
+            .send()
+            .await?;
+
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Err(BitcoinRpcError::TxNotFound(txid.to_string()));
+        }
+        if !response.status().is_success() {
+            return Err(BitcoinRpcError::InvalidResponse(format!("electrs returned {}", response.status())));
+        }
+
+        let tx_hex = response.text().await?;
+        bitcoin::consensus::encode::deserialize_hex(tx_hex.trim())
+            .map_err(|e| BitcoinRpcError::InvalidResponse(format!("Invalid transaction encoding: {}", e)))
     }
 
     pub async fn get_utxo_status(&self, utxo: &UtxoMeta) -> Result<UtxoStatus, BitcoinRpcError> {
@@ -165,8 +766,62 @@ impl BitcoinRpcClient {
         }
     }
 
-    pub async fn update_utxo_confirmations(&self, utxo: &mut UtxoMeta) -> Result<u64, BitcoinRpcError> {
-        let confirmations = self.get_confirmations(utxo.txid_str()).await? as u64;
+    /// Status of `utxo` from esplora's `GET /tx/:txid/outspend/:vout`, with
+    /// its spender when spent
+    ///
+    /// Unlike `gettxout`, esplora still names the spending transaction after
+    /// it confirms. Unspent outputs are Active once the funding transaction
+    /// has `min_confirmations` against the node's tip; outputs of
+    /// transactions esplora doesn't know are Invalid.
+    pub async fn get_utxo_spend_status(&self, utxo: &UtxoMeta) -> Result<(UtxoStatus, Option<SpendInfo>), BitcoinRpcError> {
+        let electrs_endpoint = self.electrs_endpoint.as_deref()
+            .ok_or_else(|| BitcoinRpcError::ConnectionFailed("No electrs endpoint configured".to_string()))?;
+
+        let url = format!("{}/tx/{}/outspend/{}", electrs_endpoint, utxo.txid_str(), utxo.vout);
+        let outspend: EsploraOutspend = match self.get_from_electrs(&url).await? {
+            Some(outspend) => outspend,
+            None => return Ok((UtxoStatus::Invalid, None)),
+        };
+        if outspend.spent {
+            return Ok((UtxoStatus::Spent, outspend.spend_info()));
+        }
+
+        let url = format!("{}/tx/{}/status", electrs_endpoint, utxo.txid_str());
+        let funding: EsploraTxStatus = match self.get_from_electrs(&url).await? {
+            Some(funding) => funding,
+            None => return Ok((UtxoStatus::Invalid, None)),
+        };
+        let tip_height = self.get_block_count().await?;
+        Ok(outspend_status(&funding, &outspend, tip_height, self.min_confirmations))
+    }
+
+    /// JSON from an esplora GET, `None` when esplora answers 404
+    async fn get_from_electrs<R: serde::de::DeserializeOwned>(&self, url: &str) -> Result<Option<R>, BitcoinRpcError> {
+        if let Some(limiter) = &self.rate_limiter {
+            limiter.acquire().await;
+        }
+
+        let response = self.http_client
+            .get(url)
+            .send()
+            .await?;
+
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        if !response.status().is_success() {
+            return Err(BitcoinRpcError::InvalidResponse(format!("electrs returned {}", response.status())));
+        }
+
+        response
+            .json()
+            .await
+            .map(Some)
+            .map_err(|e| BitcoinRpcError::InvalidResponse(e.to_string()))
+    }
+
+    pub async fn update_utxo_confirmations(&self, utxo: &mut UtxoMeta) -> Result<u32, BitcoinRpcError> {
+        let confirmations = self.get_confirmations(utxo.txid_str()).await?;
         utxo.confirmations = confirmations;
         Ok(confirmations)
     }
@@ -178,20 +833,102 @@ impl BitcoinRpcClient {
         Ok(confirmations)
     }
 
+    /// Poll `get_confirmations` until `txid` has at least `target` confirmations
+    ///
+    /// Returns the confirmation count once reached, `Timeout` if `timeout`
+    /// expires first and `TxNotFound` if the transaction disappears from the
+    /// node (reorg or RBF replacement). Dropping the future cancels the wait.
+    pub async fn wait_for_confirmations(
+        &self,
+        txid: &str,
+        target: u32,
+        poll_interval: Duration,
+        timeout: Duration,
+    ) -> Result<u32, BitcoinRpcError> {
+        let poll = async {
+            loop {
+                let confirmations = self.get_confirmations(txid).await?;
+                if confirmations >= target {
+                    return Ok(confirmations);
+                }
+                sleep(poll_interval).await;
+            }
+        };
+
+        tokio::time::timeout(timeout, poll)
+            .await
+            .unwrap_or(Err(BitcoinRpcError::Timeout))
+    }
+
     pub async fn get_best_block_hash(&self) -> Result<BlockHash, BitcoinRpcError> {
         self.make_rpc_call("getbestblockhash", Vec::<String>::new()).await
     }
 
-    pub async fn get_block(&self, hash: &BlockHash) -> Result<Block, BitcoinRpcError> {
-        let params = vec![hash.to_string()];
+    /// Height of the best block, from `getblockcount` unless fetched within `tip_cache_ttl`
+    pub async fn get_block_count(&self) -> Result<u64, BitcoinRpcError> {
+        if let Some(height) = self.tip_cache.height() {
+            return Ok(height);
+        }
+        let height = self.make_rpc_call("getblockcount", Vec::<String>::new()).await?;
+        self.tip_cache.store_height(height);
+        Ok(height)
+    }
+
+    /// Height and hash of the best block, from one `getblockchaininfo` so
+    /// they always belong together; cached like `get_block_count`
+    pub async fn get_chain_tip(&self) -> Result<(u64, BlockHash), BitcoinRpcError> {
+        if let Some(tip) = self.tip_cache.tip() {
+            return Ok(tip);
+        }
+        let info: BlockchainInfo = self.make_rpc_call("getblockchaininfo", Vec::<String>::new()).await?;
+        self.tip_cache.store_tip(info.blocks, info.bestblockhash);
+        Ok((info.blocks, info.bestblockhash))
+    }
+
+    /// Block summary from `getblock` at verbosity 1
+    pub async fn get_block(&self, hash: &BlockHash) -> Result<BlockInfo, BitcoinRpcError> {
+        let params = serde_json::json!([hash.to_string(), 1]);
         self.make_rpc_call("getblock", params).await
     }
 
+    /// Full consensus-encoded block from `getblock` at verbosity 0
+    pub async fn get_block_raw(&self, hash: &BlockHash) -> Result<Block, BitcoinRpcError> {
+        let params = serde_json::json!([hash.to_string(), 0]);
+        let block_hex: String = self.make_rpc_call("getblock", params).await?;
+        bitcoin::consensus::encode::deserialize_hex(&block_hex)
+            .map_err(|e| BitcoinRpcError::InvalidResponse(format!("Invalid block encoding: {}", e)))
+    }
+
+    pub async fn get_block_hash(&self, height: u32) -> Result<BlockHash, BitcoinRpcError> {
+        self.make_rpc_call("getblockhash", vec![height]).await
+    }
+
+    /// Serialized merkle block proving `txids` are in `block_hash`, from `gettxoutproof`
+    ///
+    /// Without `block_hash` the node finds the block itself, which needs
+    /// txindex or an unspent output of the transactions.
+    pub async fn get_txout_proof(&self, txids: &[Txid], block_hash: Option<&BlockHash>) -> Result<Vec<u8>, BitcoinRpcError> {
+        let txids: Vec<String> = txids.iter().map(Txid::to_string).collect();
+        let params = match block_hash {
+            Some(hash) => serde_json::json!([txids, hash.to_string()]),
+            None => serde_json::json!([txids]),
+        };
+        let proof_hex: String = self.make_rpc_call("gettxoutproof", params).await?;
+        hex::decode(proof_hex.trim())
+            .map_err(|e| BitcoinRpcError::InvalidResponse(format!("Invalid merkle proof encoding: {}", e)))
+    }
+
+    /// Block summary of the main-chain block at `height`
+    pub async fn get_block_at_height(&self, height: u32) -> Result<BlockInfo, BitcoinRpcError> {
+        let hash = self.get_block_hash(height).await?;
+        self.get_block(&hash).await
+    }
+
     /// Get transaction block information including confirmations, height, and hash
-    pub async fn get_tx_block_info(&self, txid: &str) -> Result<(u64, u32, String), BitcoinRpcError> {
+    pub async fn get_tx_block_info(&self, txid: &str) -> Result<(u32, u32, String), BitcoinRpcError> {
         #[derive(Debug, Deserialize)]
         struct TxInfo {
-            confirmations: u64,
+            confirmations: u32,
             blockhash: String,
             blockheight: u32,
         }
@@ -202,9 +939,134 @@ impl BitcoinRpcClient {
         Ok((tx_info.confirmations, tx_info.blockheight, tx_info.blockhash))
     }
 
-    /// Set cache configuration
+    /// Get the mempool entry for an unconfirmed transaction
+    ///
+    /// Returns `TxNotFound` if the transaction is not (or no longer) in the node's mempool.
+    pub async fn get_mempool_entry(&self, txid: &str) -> Result<MempoolEntry, BitcoinRpcError> {
+        let params = vec![txid];
+        let raw: RawMempoolEntry = self.make_rpc_call("getmempoolentry", params).await?;
+        let fee = checked_amount_from_btc(raw.fees.base)
+            .ok_or_else(|| BitcoinRpcError::InvalidResponse(format!("Invalid mempool fee: {}", raw.fees.base)))?;
+
+        Ok(MempoolEntry {
+            fee,
+            vsize: raw.vsize,
+            bip125_replaceable: raw.bip125_replaceable,
+        })
+    }
+
+    /// Fee rate for confirming within `conf_target` blocks, from `estimatesmartfee`
+    ///
+    /// A node without enough fee data to estimate from answers with an `InvalidResponse`.
+    pub async fn estimate_fee_rate(&self, conf_target: u16) -> Result<FeeRate, BitcoinRpcError> {
+        let estimate: SmartFeeEstimate = self.make_rpc_call("estimatesmartfee", vec![conf_target]).await?;
+        let per_kvb = estimate.feerate.and_then(checked_amount_from_btc).ok_or_else(|| {
+            BitcoinRpcError::InvalidResponse(format!(
+                "No fee estimate for {} blocks: {}",
+                conf_target,
+                estimate.errors.join("; ")
+            ))
+        })?;
+        // A kvB is 4000 weight units; round up so the rate is never below the estimate
+        Ok(FeeRate::from_sat_per_kwu(per_kvb.to_sat().div_ceil(4)))
+    }
+
+    /// Get the txids of all transactions currently in the node's mempool
+    pub async fn get_raw_mempool(&self) -> Result<HashSet<String>, BitcoinRpcError> {
+        let txids: Vec<String> = self.make_rpc_call("getrawmempool", Vec::<String>::new()).await?;
+        Ok(txids.into_iter().collect())
+    }
+
+    /// List the unspent outputs of the configured wallet with at least `min_conf` confirmations
+    ///
+    /// An empty `addresses` slice returns every unspent output of the wallet.
+    pub async fn list_unspent(&self, min_conf: u32, addresses: &[String]) -> Result<Vec<UtxoMeta>, BitcoinRpcError> {
+        const MAX_CONF: u32 = 9_999_999;
+        let params = serde_json::json!([min_conf, MAX_CONF, addresses]);
+        let entries: Vec<ListUnspentEntry> = self.make_rpc_call("listunspent", params).await?;
+
+        entries
+            .into_iter()
+            .map(|entry| {
+                let invalid_amount = || {
+                    BitcoinRpcError::InvalidResponse(format!("Invalid amount for {}:{}: {}", entry.txid, entry.vout, entry.amount))
+                };
+                let amount = checked_amount_from_btc(entry.amount).ok_or_else(invalid_amount)?;
+                let mut utxo = UtxoMeta::with_amount(entry.txid.clone(), entry.vout, amount)
+                    .map_err(|_| invalid_amount())?;
+                utxo.script_pubkey = entry.script_pubkey;
+                utxo.confirmations = entry.confirmations;
+                Ok(utxo)
+            })
+            .collect()
+    }
+
+    /// Get the confirmed balance of the configured wallet
+    pub async fn get_wallet_balance(&self) -> Result<Amount, BitcoinRpcError> {
+        let balance_btc: f64 = self.make_rpc_call("getbalance", Vec::<String>::new()).await?;
+        checked_amount_from_btc(balance_btc)
+            .ok_or_else(|| BitcoinRpcError::InvalidResponse(format!("Invalid wallet balance: {}", balance_btc)))
+    }
+
+    /// Snapshot of the RPC, cache and tracker metrics recorded by this client
+    pub fn metrics(&self) -> MetricsSnapshot {
+        self.metrics.snapshot()
+    }
+
+    /// The config's `min_confirmations`
+    pub fn min_confirmations(&self) -> u32 {
+        self.min_confirmations
+    }
+
+    /// Use a cache built from `config`
+    ///
+    /// If `config.maintenance_interval` is set this spawns the cleanup task,
+    /// so it must be called from within a tokio runtime.
+    pub fn with_cache_config(mut self, config: UtxoCacheConfig) -> Self {
+        self.set_cache_config(config);
+        self
+    }
+
+    /// Set cache configuration, replacing the cache and any maintenance task
     pub fn set_cache_config(&mut self, config: UtxoCacheConfig) {
-        self.cache = UtxoCache::new(config);
+        let (cache, maintenance) = UtxoCache::shared(config);
+        self.cache = cache;
+        self.cache_maintenance = maintenance.map(Arc::new);
+    }
+
+    /// Stop the cache maintenance task, waiting for it to exit unless other
+    /// clones of this client still share it
+    pub async fn shutdown_cache_maintenance(&mut self) {
+        if let Some(maintenance) = self.cache_maintenance.take() {
+            if let Ok(maintenance) = Arc::try_unwrap(maintenance) {
+                maintenance.shutdown().await;
+            }
+        }
+    }
+
+    /// UTXO status served from this client's cache, fetched over RPC when stale
+    pub async fn get_cached_utxo_status(&self, utxo: &UtxoMeta) -> Result<UtxoStatus, BitcoinRpcError> {
+        self.cache.get_utxo_status(self, utxo).await
+    }
+
+    /// Warm this client's cache for a batch of UTXOs; see `UtxoCache::prefetch`
+    pub async fn prefetch_utxos(&self, utxos: &[UtxoMeta]) -> Vec<Result<UtxoStatus, BitcoinRpcError>> {
+        self.cache.prefetch(self, utxos).await
+    }
+
+    /// Drop the cached status of one outpoint
+    pub async fn invalidate_cached_utxo(&self, txid: &str, vout: u32) -> bool {
+        self.cache.invalidate(txid, vout).await
+    }
+
+    /// Drop the cached status of every output of `txid`
+    pub async fn invalidate_cached_tx(&self, txid: &str) -> usize {
+        self.cache.invalidate_tx(txid).await
+    }
+
+    /// Mark a cached outpoint as spent without asking the node
+    pub async fn mark_spent(&self, txid: &str, vout: u32) -> bool {
+        self.cache.mark_spent(txid, vout).await
     }
 
     /// Get cache statistics
@@ -217,8 +1079,89 @@ impl BitcoinRpcClient {
         self.cache.cleanup().await;
     }
 
-    /// Handle reorg by invalidating affected cache entries
+    /// Handle reorg by invalidating affected cache entries and the cached tip
+    ///
+    /// Cached confirmations are recounted against the new tip.
     pub async fn handle_reorg(&self, height: u32) {
-        self.cache.handle_reorg(height).await;
+        self.tip_cache.invalidate();
+        let tip_height = match self.get_block_count().await {
+            Ok(tip) => u32::try_from(tip).unwrap_or(u32::MAX),
+            // Without the new tip, take the chain to end just below the fork
+            Err(_) => height.saturating_sub(1),
+        };
+        self.cache.handle_reorg(height, tip_height).await;
     }
-} 
\ No newline at end of file
+}
+
+#[async_trait]
+impl UtxoStatusSource for BitcoinRpcClient {
+    async fn fetch_utxo_status(&self, utxo: &UtxoMeta) -> Result<UtxoStatus, BitcoinRpcError> {
+        self.get_utxo_status(utxo).await
+    }
+
+    fn cache_metrics(&self) -> Option<&Metrics> {
+        Some(&self.metrics)
+    }
+}
+
+#[async_trait]
+impl UtxoValidationRpc for BitcoinRpcClient {
+    async fn get_best_block_hash(&self) -> Result<BlockHash, BitcoinRpcError> {
+        self.get_best_block_hash().await
+    }
+
+    async fn get_tx_block_info(&self, txid: &str) -> Result<(u32, u32, String), BitcoinRpcError> {
+        self.get_tx_block_info(txid).await
+    }
+
+    async fn get_utxo_status(&self, utxo: &UtxoMeta) -> Result<UtxoStatus, BitcoinRpcError> {
+        self.get_utxo_status(utxo).await
+    }
+
+    async fn get_transaction(&self, txid: &str) -> Result<Transaction, BitcoinRpcError> {
+        self.get_transaction(txid).await
+    }
+
+    async fn get_txout_proof(&self, txids: &[Txid], block_hash: Option<&BlockHash>) -> Result<Vec<u8>, BitcoinRpcError> {
+        self.get_txout_proof(txids, block_hash).await
+    }
+}
+
+#[async_trait]
+impl TrackerRpc for BitcoinRpcClient {
+    async fn get_confirmations(&self, txid: &str) -> Result<u32, BitcoinRpcError> {
+        self.get_confirmations(txid).await
+    }
+
+    async fn get_block_count(&self) -> Result<u64, BitcoinRpcError> {
+        self.get_block_count().await
+    }
+
+    async fn get_utxo_status(&self, utxo: &UtxoMeta) -> Result<UtxoStatus, BitcoinRpcError> {
+        self.get_utxo_status(utxo).await
+    }
+
+    async fn get_block(&self, hash: &BlockHash) -> Result<BlockInfo, BitcoinRpcError> {
+        self.get_block(hash).await
+    }
+
+    async fn get_raw_mempool(&self) -> Result<HashSet<String>, BitcoinRpcError> {
+        self.get_raw_mempool().await
+    }
+
+    async fn list_unspent(&self, min_conf: u32, addresses: &[String]) -> Result<Vec<UtxoMeta>, BitcoinRpcError> {
+        self.list_unspent(min_conf, addresses).await
+    }
+
+    async fn mark_spent(&self, txid: &str, vout: u32) {
+        self.mark_spent(txid, vout).await;
+    }
+
+    fn tracker_metrics(&self) -> Option<&Metrics> {
+        Some(&self.metrics)
+    }
+}
+
+#[cfg(test)]
+#[path = "rpc_test.rs"]
+mod rpc_test;
@@ -1,22 +1,104 @@
+use super::*;
+
 #[cfg(all(test, not(target_arch = "wasm32")))]
 mod tests {
     use super::*;
-    use mockito::{mock, server_url};
+    use crate::bitcoin::cache::UtxoCacheBuilder;
+    use mockito::{mock, server_url, Matcher};
+    use serde_json::json;
     use bitcoin::consensus::encode;
     use bitcoin::hashes::hex::FromHex;
     use std::str::FromStr;
     use std::time::Duration;
 
-    fn setup_test_client() -> BitcoinRpcClient {
-        let config = BitcoinRpcConfig {
-            endpoint: server_url().replace("http://", ""),
-            port: 8332,
+    fn test_config(address: &str) -> BitcoinRpcConfig {
+        let (host, port) = address.rsplit_once(':').expect("address must be host:port");
+        BitcoinRpcConfig {
+            endpoint: host.to_string(),
+            port: port.parse().expect("invalid port"),
             username: "testuser".to_string(),
             password: "testpass".to_string(),
-        };
+            ..Default::default()
+        }
+    }
+
+    fn setup_test_client() -> BitcoinRpcClient {
+        let config = test_config(&server_url().replace("http://", ""));
         BitcoinRpcClient::new(config).unwrap()
     }
 
+    /// Mock a successful response to the request with the given method and id
+    fn mock_rpc_response(method: &str, id: u64, result: serde_json::Value) -> mockito::Mock {
+        mock("POST", "/")
+            .match_body(Matcher::PartialJson(json!({ "method": method, "id": id.to_string() })))
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(json!({ "result": result, "error": null, "id": id.to_string() }).to_string())
+            .create()
+    }
+
+    /// Start a TCP server that accepts connections but never answers
+    async fn start_unresponsive_server() -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        tokio::spawn(async move {
+            let mut held = Vec::new();
+            while let Ok((socket, _)) = listener.accept().await {
+                held.push(socket);
+            }
+        });
+        address
+    }
+
+    /// Start a JSON-RPC server that answers every call with `result` after
+    /// `delay`, counting the requests it receives
+    async fn start_slow_rpc_server(delay: Duration, result: serde_json::Value) -> (String, Arc<std::sync::atomic::AtomicUsize>) {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        let requests = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let counter = requests.clone();
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let (counter, result) = (counter.clone(), result.clone());
+                tokio::spawn(async move {
+                    // Read the headers, then as much body as they announce
+                    let mut buffer = Vec::new();
+                    let mut chunk = [0u8; 4096];
+                    let body = loop {
+                        let read = socket.read(&mut chunk).await.unwrap_or(0);
+                        if read == 0 {
+                            return;
+                        }
+                        buffer.extend_from_slice(&chunk[..read]);
+                        let text = String::from_utf8_lossy(&buffer).to_string();
+                        let Some((headers, body)) = text.split_once("\r\n\r\n") else { continue };
+                        let length = headers.lines()
+                            .find_map(|line| line.to_ascii_lowercase().strip_prefix("content-length:").map(|v| v.trim().to_string()))
+                            .and_then(|v| v.parse::<usize>().ok())
+                            .unwrap_or(0);
+                        if body.len() >= length {
+                            break body.to_string();
+                        }
+                    };
+                    counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                    let id = serde_json::from_str::<serde_json::Value>(&body).unwrap()["id"].clone();
+
+                    tokio::time::sleep(delay).await;
+                    let payload = json!({ "result": result, "error": null, "id": id }).to_string();
+                    let response = format!(
+                        "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                        payload.len(),
+                        payload
+                    );
+                    let _ = socket.write_all(response.as_bytes()).await;
+                });
+            }
+        });
+        (address, requests)
+    }
+
     #[tokio::test]
     async fn test_get_transaction() {
         let client = setup_test_client();
@@ -42,81 +124,427 @@ mod tests {
     #[tokio::test]
     async fn test_get_utxo_status() {
         let client = setup_test_client();
-        let utxo = UtxoMeta {
-            txid: "4a5e1e4baab89f3a32518a88c31bc87f618f76673e2cc77ab2127b7afdeda33b".to_string(),
-            vout: 0,
-            amount_sats: 5000000000,
-        };
+        let utxo = UtxoMeta::new(
+            "4a5e1e4baab89f3a32518a88c31bc87f618f76673e2cc77ab2127b7afdeda33b".to_string(),
+            0,
+            5000000000,
+        );
 
-        // Mock response for getrawtransaction
+        let raw_tx = "0100000001000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000";
+
+        // getrawtransaction is issued twice: once directly, once by get_confirmations
+        let _tx = mock_rpc_response("getrawtransaction", 1, json!(raw_tx));
+        let _tx_again = mock_rpc_response("getrawtransaction", 2, json!(raw_tx));
+        let _confirmations = mock_rpc_response("gettxconfirmations", 3, json!(6));
+        let _txout = mock_rpc_response("gettxout", 4, json!(true));
+
+        let result = client.get_utxo_status(&utxo).await;
+        assert!(result.is_ok());
+        assert_eq!(result.unwrap(), UtxoStatus::Active);
+    }
+
+    #[tokio::test]
+    async fn test_get_best_block_hash() {
+        let client = setup_test_client();
+        
+        // Mock response for getbestblockhash
         mock("POST", "/")
             .with_status(200)
             .with_header("content-type", "application/json")
             .with_body(r#"{
-                "result": "0100000001000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000",
+                "result": "000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f",
                 "error": null,
                 "id": "1"
             }"#)
             .create();
 
-        // Mock response for gettxconfirmations
+        let result = client.get_best_block_hash().await;
+        assert!(result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_retry_logic() {
+        let client = setup_test_client();
+        let txid = "4a5e1e4baab89f3a32518a88c31bc87f618f76673e2cc77ab2127b7afdeda33b";
+
+        // First two calls fail, third succeeds
+        mock("POST", "/")
+            .with_status(500)
+            .times(2)
+            .create();
+
         mock("POST", "/")
             .with_status(200)
             .with_header("content-type", "application/json")
             .with_body(r#"{
-                "result": 6,
+                "result": "0100000001000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000",
                 "error": null,
                 "id": "1"
             }"#)
             .create();
 
-        // Mock response for gettxout
+        let result = client.get_transaction(txid).await;
+        assert!(result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_auth_error() {
+        let client = setup_test_client();
+        let txid = "4a5e1e4baab89f3a32518a88c31bc87f618f76673e2cc77ab2127b7afdeda33b";
+
+        mock("POST", "/")
+            .with_status(401)
+            .create();
+
+        let result = client.get_transaction(txid).await;
+        assert!(matches!(result, Err(BitcoinRpcError::AuthError)));
+    }
+
+    #[tokio::test]
+    async fn test_request_timeout() {
+        let address = start_unresponsive_server().await;
+        let request_timeout = Duration::from_millis(200);
+        let config = BitcoinRpcConfig {
+            request_timeout,
+            ..test_config(&address)
+        };
+        let client = BitcoinRpcClient::new(config).unwrap();
+
+        let started = tokio::time::Instant::now();
+        let result = client.get_best_block_hash().await;
+        let elapsed = started.elapsed();
+
+        assert!(matches!(result, Err(BitcoinRpcError::Timeout)), "expected Timeout, got {:?}", result);
+
+        // Every attempt is bounded by the request timeout, plus the delay between retries
+        let bound = request_timeout * MAX_RETRIES
+            + Duration::from_millis(RETRY_DELAY_MS) * (MAX_RETRIES - 1)
+            + Duration::from_millis(500);
+        assert!(elapsed < bound, "timed out after {:?}, expected less than {:?}", elapsed, bound);
+    }
+
+    #[test]
+    fn test_invalid_proxy_url() {
+        let config = BitcoinRpcConfig {
+            proxy_url: Some("not a url".to_string()),
+            ..Default::default()
+        };
+        assert!(matches!(
+            BitcoinRpcClient::new(config),
+            Err(BitcoinRpcError::ConnectionFailed(_))
+        ));
+    }
+
+    #[test]
+    fn test_with_http_client() {
+        let http_client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(5))
+            .build()
+            .unwrap();
+        let client = BitcoinRpcClient::with_http_client(BitcoinRpcConfig::default(), http_client).unwrap();
+        assert_eq!(client.rpc_url(), "http://127.0.0.1:8332");
+    }
+
+    #[test]
+    fn test_node_url_parsing_errors() {
+        for url in ["not a url", "ftp://node.example.com", "unix:/var/run/bitcoind.sock"] {
+            assert!(
+                matches!(BitcoinRpcConfig::from_url(url), Err(BitcoinRpcError::ConnectionFailed(_))),
+                "{} should be rejected",
+                url
+            );
+        }
+
+        let config = BitcoinRpcConfig {
+            url: Some("https://".to_string()),
+            ..Default::default()
+        };
+        assert!(BitcoinRpcClient::new(config).is_err());
+    }
+
+    #[test]
+    fn test_network_presets() {
+        assert_eq!(BitcoinRpcConfig::for_network(bitcoin::Network::Bitcoin).port, 8332);
+        assert_eq!(BitcoinRpcConfig::for_network(bitcoin::Network::Signet).port, 38332);
+        assert_eq!(BitcoinRpcConfig::testnet4().node_url().unwrap().as_str(), "http://127.0.0.1:18332/");
+        assert_eq!(BitcoinRpcConfig::regtest().port, 18443);
+        assert_eq!(BitcoinRpcConfig::signet().port, 38332);
+        assert_eq!(BitcoinRpcConfig::mainnet().port, 8332);
+    }
+
+    #[test]
+    fn test_builder_builds_client() {
+        let client = BitcoinRpcClient::builder()
+            .with_network(bitcoin::Network::Regtest)
+            .with_endpoint("10.0.0.5", 18443)
+            .with_auth("user", "pass")
+            .with_cache(UtxoCacheBuilder::new().with_max_size(10).build_config().unwrap())
+            .build()
+            .unwrap();
+        assert_eq!(client.min_confirmations(), 1);
+
+        let config = BitcoinRpcClientBuilder::from_config(BitcoinRpcConfig::testnet4())
+            .with_url("https://node.example.com:8443")
+            .build_config()
+            .unwrap();
+        assert_eq!(config.node_url().unwrap().as_str(), "https://node.example.com:8443/");
+        assert_eq!(config.min_confirmations, 3);
+    }
+
+    #[test]
+    fn test_builder_rejects_invalid_combinations() {
+        let reject = |builder: BitcoinRpcClientBuilder| builder.build_config().err();
+        assert!(matches!(reject(BitcoinRpcClient::builder().with_endpoint("  ", 8332)), Some(ConfigError::MissingEndpoint)));
+        assert!(matches!(
+            reject(BitcoinRpcClient::builder().with_url("ftp://node.example.com")),
+            Some(ConfigError::InvalidUrl(message)) if message.contains("ftp")
+        ));
+        assert!(matches!(reject(BitcoinRpcClient::builder().with_auth("user", "")), Some(ConfigError::IncompleteCredentials)));
+        assert!(matches!(reject(BitcoinRpcClient::builder().with_auth("", "pass")), Some(ConfigError::IncompleteCredentials)));
+        assert!(matches!(
+            reject(BitcoinRpcClient::builder().with_request_timeout(Duration::ZERO)),
+            Some(ConfigError::ZeroDuration("request_timeout"))
+        ));
+        assert!(matches!(
+            reject(BitcoinRpcClient::builder().with_connect_timeout(Duration::ZERO)),
+            Some(ConfigError::ZeroDuration("connect_timeout"))
+        ));
+        assert!(matches!(
+            reject(BitcoinRpcClient::builder().with_connect_timeout(Duration::from_secs(60)).with_request_timeout(Duration::from_secs(30))),
+            Some(ConfigError::ConnectTimeoutExceedsRequestTimeout { .. })
+        ));
+        assert!(matches!(reject(BitcoinRpcClient::builder().with_max_requests_per_second(0)), Some(ConfigError::ZeroRateLimit)));
+        assert!(matches!(
+            reject(BitcoinRpcClientBuilder::from_config(BitcoinRpcConfig { electrs_fallback: true, ..Default::default() })),
+            Some(ConfigError::FallbackWithoutElectrs)
+        ));
+        assert!(matches!(reject(BitcoinRpcClient::builder().with_min_confirmations(0)), Some(ConfigError::ZeroConfirmations)));
+        // The cache is checked along with the client
+        let cache = UtxoCacheConfig { max_size: 0, ..Default::default() };
+        assert!(matches!(reject(BitcoinRpcClient::builder().with_cache(cache)), Some(ConfigError::ZeroCacheSize)));
+
+        assert!(BitcoinRpcClient::builder().with_electrs("http://127.0.0.1:3002", true).build_config().is_ok());
+    }
+
+    #[test]
+    fn test_preset_confirmations() {
+        assert_eq!(BitcoinRpcConfig::regtest().min_confirmations, 1);
+        assert_eq!(BitcoinRpcConfig::testnet4().min_confirmations, 3);
+        assert_eq!(BitcoinRpcConfig::signet().min_confirmations, 3);
+        assert_eq!(BitcoinRpcConfig::mainnet().min_confirmations, 6);
+        // Unconfigured clients are as strict as mainnet
+        assert_eq!(BitcoinRpcConfig::default().min_confirmations, 6);
+
+        let client = BitcoinRpcClient::new(BitcoinRpcConfig::testnet4()).unwrap();
+        assert_eq!(client.min_confirmations(), 3);
+        assert_eq!(UtxoCacheConfig::from_rpc_config(&BitcoinRpcConfig::regtest()).min_confirmations, 1);
+    }
+
+    #[test]
+    fn test_node_url_scheme_selection() {
+        // Host/port configs keep resolving to plain http
+        let config = BitcoinRpcConfig::from(("10.0.0.5", 18332));
+        assert_eq!(config.node_url().unwrap().as_str(), "http://10.0.0.5:18332/");
+
+        // A full URL wins over endpoint/port
+        let config = BitcoinRpcConfig {
+            endpoint: "10.0.0.5".to_string(),
+            ..BitcoinRpcConfig::from_url("https://node.example.com:8443").unwrap()
+        };
+        let url = config.node_url().unwrap();
+        assert_eq!(url.scheme(), "https");
+        assert_eq!(url.host_str(), Some("node.example.com"));
+        assert_eq!(url.port(), Some(8443));
+    }
+
+    #[test]
+    fn test_https_url_with_path_and_wallet() {
+        let config = BitcoinRpcConfig {
+            wallet_name: Some("treasury".to_string()),
+            accept_invalid_certs: true,
+            ..BitcoinRpcConfig::from_url("https://node.example.com/bitcoin/").unwrap()
+        };
+        let client = BitcoinRpcClient::new(config).unwrap();
+        assert_eq!(client.rpc_url(), "https://node.example.com/bitcoin/wallet/treasury");
+    }
+
+    #[test]
+    fn test_missing_root_ca() {
+        let config = BitcoinRpcConfig {
+            root_ca_path: Some("/nonexistent/ca.pem".into()),
+            ..BitcoinRpcConfig::from_url("https://node.example.com").unwrap()
+        };
+        assert!(matches!(
+            BitcoinRpcClient::new(config),
+            Err(BitcoinRpcError::ConnectionFailed(msg)) if msg.contains("root CA")
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_get_mempool_entry() {
+        let client = setup_test_client();
+        let txid = "4a5e1e4baab89f3a32518a88c31bc87f618f76673e2cc77ab2127b7afdeda33b";
+
         mock("POST", "/")
             .with_status(200)
             .with_header("content-type", "application/json")
             .with_body(r#"{
-                "result": true,
+                "result": {
+                    "vsize": 141,
+                    "weight": 561,
+                    "time": 1700000000,
+                    "height": 100,
+                    "fees": { "base": 0.00001410, "modified": 0.00001410, "ancestor": 0.00001410, "descendant": 0.00001410 },
+                    "bip125-replaceable": true
+                },
                 "error": null,
                 "id": "1"
             }"#)
             .create();
 
-        let result = client.get_utxo_status(&utxo).await;
-        assert!(result.is_ok());
-        assert_eq!(result.unwrap(), UtxoStatus::Active);
+        let entry = client.get_mempool_entry(txid).await.unwrap();
+        assert_eq!(entry.vsize, 141);
+        assert_eq!(entry.fee, Amount::from_sat(1410));
+        assert!(entry.bip125_replaceable);
     }
 
     #[tokio::test]
-    async fn test_get_best_block_hash() {
+    async fn test_get_mempool_entry_not_found() {
         let client = setup_test_client();
-        
-        // Mock response for getbestblockhash
+
         mock("POST", "/")
             .with_status(200)
             .with_header("content-type", "application/json")
             .with_body(r#"{
-                "result": "000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f",
+                "result": null,
+                "error": { "code": -5, "message": "Transaction not in mempool" },
+                "id": "1"
+            }"#)
+            .create();
+
+        let result = client.get_mempool_entry("4a5e1e4baab89f3a32518a88c31bc87f618f76673e2cc77ab2127b7afdeda33b").await;
+        assert!(matches!(result, Err(BitcoinRpcError::TxNotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn test_node_error_is_not_retried() {
+        let client = setup_test_client();
+
+        // Bitcoin Core reports RPC errors with a 500 and a JSON-RPC error body
+        let node_error = mock("POST", "/")
+            .with_status(500)
+            .with_header("content-type", "application/json")
+            .with_body(r#"{
+                "result": null,
+                "error": { "code": -8, "message": "Block height out of range" },
+                "id": "1"
+            }"#)
+            .expect(1)
+            .create();
+
+        let result = client.get_block_hash(1_000_000).await;
+        assert!(
+            matches!(&result, Err(BitcoinRpcError::NodeError { code: -8, message }) if message == "Block height out of range"),
+            "{:?}",
+            result
+        );
+        node_error.assert();
+        assert_eq!(client.metrics().rpc_retries_total, 0);
+    }
+
+    #[tokio::test]
+    async fn test_list_unspent_uses_wallet_path() {
+        let config = BitcoinRpcConfig {
+            wallet_name: Some("treasury".to_string()),
+            ..test_config(&server_url().replace("http://", ""))
+        };
+        let client = BitcoinRpcClient::new(config).unwrap();
+
+        let wallet_mock = mock("POST", "/wallet/treasury")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(r#"{
+                "result": [{
+                    "txid": "4a5e1e4baab89f3a32518a88c31bc87f618f76673e2cc77ab2127b7afdeda33b",
+                    "vout": 1,
+                    "address": "bcrt1qw508d6qejxtdg4y5r3zarvary0c5xw7kygt080",
+                    "scriptPubKey": "0014751e76e8199196d454941c45d1b3a323f1433bd6",
+                    "amount": 0.0025,
+                    "confirmations": 7,
+                    "spendable": false,
+                    "solvable": true,
+                    "safe": true
+                }],
                 "error": null,
                 "id": "1"
             }"#)
             .create();
 
-        let result = client.get_best_block_hash().await;
-        assert!(result.is_ok());
+        let utxos = client.list_unspent(1, &[]).await.unwrap();
+        wallet_mock.assert();
+
+        assert_eq!(utxos.len(), 1);
+        assert_eq!(utxos[0].vout, 1);
+        assert_eq!(utxos[0].amount_sats, 250_000);
+        assert_eq!(utxos[0].confirmations, 7);
+        assert_eq!(utxos[0].script_pubkey, "0014751e76e8199196d454941c45d1b3a323f1433bd6");
     }
 
     #[tokio::test]
-    async fn test_retry_logic() {
+    async fn test_get_wallet_balance() {
+        let config = BitcoinRpcConfig {
+            wallet_name: Some("treasury".to_string()),
+            ..test_config(&server_url().replace("http://", ""))
+        };
+        let client = BitcoinRpcClient::new(config).unwrap();
+
+        let wallet_mock = mock("POST", "/wallet/treasury")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(r#"{ "result": 1.5, "error": null, "id": "1" }"#)
+            .create();
+
+        let balance = client.get_wallet_balance().await.unwrap();
+        wallet_mock.assert();
+        assert_eq!(balance, Amount::from_sat(150_000_000));
+    }
+
+    #[tokio::test]
+    async fn test_estimate_fee_rate() {
+        let client = setup_test_client();
+        let estimate = mock("POST", "/")
+            .match_body(Matcher::PartialJson(json!({ "method": "estimatesmartfee", "params": [6] })))
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(r#"{ "result": { "feerate": 0.00012345, "blocks": 6 }, "error": null, "id": "1" }"#)
+            .create();
+        // 12345 sats per kvB, rounded up to whole sats per kwu
+        assert_eq!(client.estimate_fee_rate(6).await.unwrap(), FeeRate::from_sat_per_kwu(3087));
+        estimate.assert();
+
+        let _no_data = mock("POST", "/")
+            .match_body(Matcher::PartialJson(json!({ "method": "estimatesmartfee", "params": [2] })))
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(r#"{ "result": { "errors": ["Insufficient data or no feerate found"], "blocks": 0 }, "error": null, "id": "1" }"#)
+            .create();
+        assert!(matches!(
+            client.estimate_fee_rate(2).await,
+            Err(BitcoinRpcError::InvalidResponse(message)) if message.contains("Insufficient data")
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_metrics_track_requests_and_retries() {
         let client = setup_test_client();
         let txid = "4a5e1e4baab89f3a32518a88c31bc87f618f76673e2cc77ab2127b7afdeda33b";
 
-        // First two calls fail, third succeeds
-        mock("POST", "/")
+        // One failed attempt before the transaction is returned
+        let _failing = mock("POST", "/")
             .with_status(500)
-            .times(2)
+            .times(1)
             .create();
-
-        mock("POST", "/")
+        let _ok = mock("POST", "/")
             .with_status(200)
             .with_header("content-type", "application/json")
             .with_body(r#"{
@@ -126,20 +554,543 @@ mod tests {
             }"#)
             .create();
 
-        let result = client.get_transaction(txid).await;
-        assert!(result.is_ok());
+        client.get_transaction(txid).await.unwrap();
+
+        let metrics = client.metrics();
+        assert_eq!(metrics.rpc_requests_total.get("getrawtransaction"), Some(&1));
+        assert_eq!(metrics.rpc_retries_total, 1);
+        assert_eq!(metrics.rpc_failures_total, 0);
+        // Latency is recorded per attempt
+        assert_eq!(metrics.rpc_latency_ms.count, 2);
     }
 
     #[tokio::test]
-    async fn test_auth_error() {
+    async fn test_metrics_track_failures() {
+        let client = setup_test_client();
+
+        let _unauthorized = mock("POST", "/")
+            .with_status(401)
+            .create();
+
+        assert!(client.get_best_block_hash().await.is_err());
+
+        let metrics = client.metrics();
+        assert_eq!(metrics.total_rpc_requests(), 1);
+        assert_eq!(metrics.rpc_failures_total, 1);
+        assert_eq!(metrics.rpc_retries_total, 0);
+    }
+
+    #[tokio::test]
+    async fn test_request_ids_increase() {
+        let client = setup_test_client();
+        let block_hash = "000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f";
+
+        let first = mock_rpc_response("getbestblockhash", 1, json!(block_hash));
+        let second = mock_rpc_response("getbestblockhash", 2, json!(block_hash));
+
+        client.get_best_block_hash().await.unwrap();
+        client.get_best_block_hash().await.unwrap();
+        first.assert();
+        second.assert();
+    }
+
+    #[tokio::test]
+    async fn test_response_id_mismatch() {
         let client = setup_test_client();
+
+        let _wrong_id = mock("POST", "/")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(r#"{
+                "result": "000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f",
+                "error": null,
+                "id": "42"
+            }"#)
+            .create();
+
+        let result = client.get_best_block_hash().await;
+        assert!(matches!(result, Err(BitcoinRpcError::InvalidResponse(msg)) if msg == "id mismatch"));
+    }
+
+    #[tokio::test]
+    async fn test_rate_limited_concurrent_calls() {
+        let config = BitcoinRpcConfig {
+            max_requests_per_second: Some(5),
+            ..test_config(&server_url().replace("http://", ""))
+        };
+        let client = BitcoinRpcClient::new(config).unwrap();
+        let block_hash = "000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f";
+        let _mocks: Vec<_> = (1..=20)
+            .map(|id| mock_rpc_response("getbestblockhash", id, json!(block_hash)))
+            .collect();
+
+        let start = std::time::Instant::now();
+        let calls = (0..20).map(|_| {
+            let client = client.clone();
+            async move {
+                client.get_best_block_hash().await.unwrap();
+                start.elapsed()
+            }
+        });
+        let finished = futures::future::join_all(calls).await;
+
+        // 5 permits go out immediately, the remaining 15 are spaced 200ms apart
+        let total = start.elapsed();
+        assert!(total >= Duration::from_millis(2900), "20 calls finished in {:?}", total);
+        assert!(total < Duration::from_secs(5), "20 calls took {:?}", total);
+
+        // Permits are granted in call order, so no call overtakes its slot
+        for (i, elapsed) in finished.iter().enumerate().skip(5) {
+            let slot = Duration::from_millis(200 * (i as u64 - 4));
+            assert!(*elapsed >= slot - Duration::from_millis(50), "call {} finished at {:?}", i, elapsed);
+        }
+        assert_eq!(client.metrics().rpc_queue_depth, 0);
+    }
+
+    #[tokio::test]
+    async fn test_too_many_requests_respects_retry_after() {
+        let client = setup_test_client();
+
+        let _throttled = mock("POST", "/")
+            .with_status(429)
+            .with_header("retry-after", "2")
+            .times(1)
+            .create();
+        let _ok = mock_rpc_response(
+            "getbestblockhash",
+            1,
+            json!("000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f"),
+        );
+
+        let start = std::time::Instant::now();
+        client.get_best_block_hash().await.unwrap();
+        assert!(start.elapsed() >= Duration::from_secs(2));
+        assert_eq!(client.metrics().rpc_retries_total, 1);
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_confirmations_tx_not_found() {
+        let client = setup_test_client();
+
+        let _not_found = mock("POST", "/")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(r#"{
+                "result": null,
+                "error": { "code": -5, "message": "No such mempool or blockchain transaction" },
+                "id": "1"
+            }"#)
+            .create();
+
+        let txid = "4a5e1e4baab89f3a32518a88c31bc87f618f76673e2cc77ab2127b7afdeda33b";
+        let result = client
+            .wait_for_confirmations(txid, 1, Duration::from_millis(10), Duration::from_secs(5))
+            .await;
+        assert!(matches!(result, Err(BitcoinRpcError::TxNotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_confirmations_timeout() {
+        let address = start_unresponsive_server().await;
+        let client = BitcoinRpcClient::new(test_config(&address)).unwrap();
+
+        let start = std::time::Instant::now();
         let txid = "4a5e1e4baab89f3a32518a88c31bc87f618f76673e2cc77ab2127b7afdeda33b";
+        let result = client
+            .wait_for_confirmations(txid, 1, Duration::from_millis(10), Duration::from_millis(300))
+            .await;
+        assert!(matches!(result, Err(BitcoinRpcError::Timeout)));
+        assert!(start.elapsed() < Duration::from_secs(2));
+    }
+
+    #[tokio::test]
+    async fn test_get_block_at_height_maps_verbose_fields() {
+        let client = setup_test_client();
+        let block_hash = "00000000839a8e6886ab5951d76f411475428afc90947ee320161bbf18eb6048";
+
+        let _hash = mock_rpc_response("getblockhash", 1, json!(block_hash));
+        let _block = mock("POST", "/")
+            .match_body(Matcher::PartialJson(json!({ "method": "getblock", "params": [block_hash, 1] })))
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(include_str!("../../tests/fixtures/getblock_verbose_block1.json"))
+            .create();
 
+        let info = client.get_block_at_height(1).await.unwrap();
+        assert_eq!(info.hash, BlockHash::from_str(block_hash).unwrap());
+        assert_eq!(info.height, 1);
+        assert_eq!(info.time, 1231469665);
+        assert_eq!(
+            info.previous_block_hash,
+            Some(BlockHash::from_str("000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f").unwrap())
+        );
+        assert_eq!(
+            info.tx,
+            vec![bitcoin::Txid::from_str("0e3e2357e806b6cdb1f70b54c3a3a17b6714ee1f0e68bebb44a74b1efd512098").unwrap()]
+        );
+        assert_eq!(info.confirmations, 868001);
+        assert!(info.is_in_main_chain());
+    }
+
+    #[tokio::test]
+    async fn test_get_txout_proof_verifies() {
+        let client = setup_test_client();
+        let fixture: serde_json::Value =
+            serde_json::from_str(include_str!("../../tests/fixtures/txoutproof_block_100000.json")).unwrap();
+        let block_hash = BlockHash::from_str(fixture["block_hash"].as_str().unwrap()).unwrap();
+        let txid = bitcoin::Txid::from_str(fixture["included_txid"].as_str().unwrap()).unwrap();
+
+        let _proof = mock("POST", "/")
+            .match_body(Matcher::PartialJson(json!({
+                "method": "gettxoutproof",
+                "params": [[txid.to_string()], block_hash.to_string()],
+            })))
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(json!({ "result": fixture["proof"], "error": null, "id": "1" }).to_string())
+            .create();
+
+        let proof = client.get_txout_proof(&[txid], Some(&block_hash)).await.unwrap();
+        crate::bitcoin::verify_merkle_proof(&proof, &txid, &block_hash).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_get_block_stale() {
+        let client = setup_test_client();
+
+        let _block = mock("POST", "/")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(include_str!("../../tests/fixtures/getblock_verbose_stale_regtest.json"))
+            .create();
+
+        let hash = BlockHash::from_str("3b1f0e6c42f1bd1d2a7e5a3c0f4e2f4b6d8a9c0e1f2a3b4c5d6e7f8091a2b3c4").unwrap();
+        let info = client.get_block(&hash).await.unwrap();
+        assert_eq!(info.height, 102);
+        assert_eq!(info.tx.len(), 2);
+        assert!(!info.is_in_main_chain());
+    }
+
+    #[tokio::test]
+    async fn test_get_block_raw() {
+        let client = setup_test_client();
+        let genesis = bitcoin::blockdata::constants::genesis_block(bitcoin::Network::Bitcoin);
+
+        let _block = mock_rpc_response("getblock", 1, json!(encode::serialize_hex(&genesis)));
+
+        let block = client.get_block_raw(&genesis.block_hash()).await.unwrap();
+        assert_eq!(block.block_hash(), genesis.block_hash());
+        assert_eq!(block.txdata.len(), 1);
+    }
+
+    /// Mock Core answering getrawtransaction as a node without txindex does
+    fn mock_no_txindex() -> mockito::Mock {
         mock("POST", "/")
-            .with_status(401)
+            .match_body(Matcher::PartialJson(json!({ "method": "getrawtransaction" })))
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(r#"{
+                "result": null,
+                "error": { "code": -5, "message": "No such mempool or blockchain transaction. Use gettransaction for wallet transactions." },
+                "id": "1"
+            }"#)
+            .create()
+    }
+
+    #[tokio::test]
+    async fn test_get_transaction_falls_back_to_electrs() {
+        let coinbase = bitcoin::blockdata::constants::genesis_block(bitcoin::Network::Bitcoin).txdata[0].clone();
+        let txid = coinbase.compute_txid().to_string();
+        let config = BitcoinRpcConfig {
+            electrs_endpoint: Some(server_url()),
+            electrs_fallback: true,
+            ..test_config(&server_url().replace("http://", ""))
+        };
+        let client = BitcoinRpcClient::new(config).unwrap();
+
+        let _core = mock_no_txindex();
+        let electrs = mock("GET", format!("/tx/{}/hex", txid).as_str())
+            .with_status(200)
+            .with_body(encode::serialize_hex(&coinbase))
+            .create();
+
+        let tx = client.get_transaction(&txid).await.unwrap();
+        electrs.assert();
+        assert_eq!(tx.compute_txid(), coinbase.compute_txid());
+        assert_eq!(client.metrics().electrs_fallbacks_total, 1);
+    }
+
+    #[tokio::test]
+    async fn test_electrs_fallback_disabled() {
+        let txid = "4a5e1e4baab89f3a32518a88c31bc87f618f76673e2cc77ab2127b7afdeda33b";
+        let config = BitcoinRpcConfig {
+            electrs_endpoint: Some(server_url()),
+            ..test_config(&server_url().replace("http://", ""))
+        };
+        let client = BitcoinRpcClient::new(config).unwrap();
+
+        let _core = mock_no_txindex();
+        let electrs = mock("GET", format!("/tx/{}/hex", txid).as_str())
+            .with_status(200)
+            .expect(0)
             .create();
 
         let result = client.get_transaction(txid).await;
-        assert!(matches!(result, Err(BitcoinRpcError::AuthError)));
+        assert!(matches!(result, Err(BitcoinRpcError::TxNotFound(_))));
+        electrs.assert();
+        assert_eq!(client.metrics().electrs_fallbacks_total, 0);
+    }
+
+    #[tokio::test]
+    async fn test_electrs_fallback_not_found() {
+        let txid = "4a5e1e4baab89f3a32518a88c31bc87f618f76673e2cc77ab2127b7afdeda33b";
+        let config = BitcoinRpcConfig {
+            electrs_endpoint: Some(server_url()),
+            electrs_fallback: true,
+            ..test_config(&server_url().replace("http://", ""))
+        };
+        let client = BitcoinRpcClient::new(config).unwrap();
+
+        let _core = mock_no_txindex();
+        let _electrs = mock("GET", format!("/tx/{}/hex", txid).as_str())
+            .with_status(404)
+            .create();
+
+        let result = client.get_transaction(txid).await;
+        assert!(matches!(result, Err(BitcoinRpcError::TxNotFound(_))));
+    }
+
+    fn esplora_client() -> BitcoinRpcClient {
+        let config = BitcoinRpcConfig {
+            electrs_endpoint: Some(format!("{}/", server_url())),
+            min_confirmations: 6,
+            ..test_config(&server_url().replace("http://", ""))
+        };
+        BitcoinRpcClient::new(config).unwrap()
+    }
+
+    fn mock_esplora(path: String, body: &str) -> mockito::Mock {
+        mock("GET", path.as_str())
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(body)
+            .create()
+    }
+
+    #[tokio::test]
+    async fn test_get_utxo_spend_status_spent() {
+        let client = esplora_client();
+        let confirmed = UtxoMeta::new("1c".repeat(32), 0, 1000);
+        let unconfirmed = UtxoMeta::new("1d".repeat(32), 2, 1000);
+
+        let _confirmed = mock_esplora(
+            format!("/tx/{}/outspend/0", confirmed.txid),
+            include_str!("../../tests/fixtures/esplora_outspend_spent_confirmed.json"),
+        );
+        let _unconfirmed = mock_esplora(
+            format!("/tx/{}/outspend/2", unconfirmed.txid),
+            include_str!("../../tests/fixtures/esplora_outspend_spent_unconfirmed.json"),
+        );
+        // A spent output needs neither the funding status nor the tip
+        let status = mock("GET", Matcher::Regex(r"^/tx/(1c|1d)+/status$".to_string()))
+            .expect(0)
+            .create();
+
+        let spender = "5f1c3a9e8c2d4b7f0a6e9d8c7b5a4f3e2d1c0b9a8f7e6d5c4b3a29180706f5e4".to_string();
+        let (utxo_status, spend) = client.get_utxo_spend_status(&confirmed).await.unwrap();
+        assert_eq!(utxo_status, UtxoStatus::Spent);
+        assert_eq!(spend, Some(SpendInfo { txid: spender.clone(), vin: 1, spent_height: Some(868_004) }));
+
+        let (utxo_status, spend) = client.get_utxo_spend_status(&unconfirmed).await.unwrap();
+        assert_eq!(utxo_status, UtxoStatus::Spent);
+        assert_eq!(spend, Some(SpendInfo { txid: spender, vin: 1, spent_height: None }));
+        status.assert();
+    }
+
+    #[tokio::test]
+    async fn test_get_utxo_spend_status_unspent() {
+        let client = esplora_client();
+        let utxo = UtxoMeta::new("1e".repeat(32), 1, 1000);
+
+        let _outspend = mock_esplora(
+            format!("/tx/{}/outspend/1", utxo.txid),
+            include_str!("../../tests/fixtures/esplora_outspend_unspent.json"),
+        );
+        let _status = mock_esplora(
+            format!("/tx/{}/status", utxo.txid),
+            include_str!("../../tests/fixtures/esplora_tx_status_confirmed.json"),
+        );
+        // Funded at 868000, so the tip at 868004 gives five of the six confirmations
+        let _tip = mock_rpc_response("getblockcount", 1, json!(868_004));
+
+        assert_eq!(client.get_utxo_spend_status(&utxo).await.unwrap(), (UtxoStatus::Pending, None));
+    }
+
+    #[tokio::test]
+    async fn test_get_utxo_spend_status_unknown_tx() {
+        let client = esplora_client();
+        let utxo = UtxoMeta::new("1f".repeat(32), 0, 1000);
+
+        let _outspend = mock("GET", format!("/tx/{}/outspend/0", utxo.txid).as_str())
+            .with_status(404)
+            .create();
+
+        assert_eq!(client.get_utxo_spend_status(&utxo).await.unwrap(), (UtxoStatus::Invalid, None));
+    }
+
+    #[tokio::test]
+    async fn test_get_utxo_spend_status_needs_electrs() {
+        let utxo = UtxoMeta::new("1f".repeat(32), 0, 1000);
+        let result = setup_test_client().get_utxo_spend_status(&utxo).await;
+        assert!(matches!(result, Err(BitcoinRpcError::ConnectionFailed(_))));
+    }
+
+    #[tokio::test]
+    async fn test_with_cache_config_runs_maintenance() {
+        let mut client = setup_test_client().with_cache_config(UtxoCacheConfig {
+            not_found_ttl: Duration::from_millis(50),
+            maintenance_interval: Some(Duration::from_millis(20)),
+            ..Default::default()
+        });
+        let _core = mock_no_txindex();
+
+        let unknown = UtxoMeta::new(
+            "0badbadbadbadbadbadbadbadbadbadbadbadbadbadbadbadbadbadbadbadbad".to_string(),
+            0,
+            1000,
+        );
+        assert_eq!(client.get_cached_utxo_status(&unknown).await.unwrap(), UtxoStatus::Invalid);
+        assert_eq!(client.get_cache_stats().await.total_entries, 1);
+
+        // The negative entry expires and is dropped without a manual cleanup_cache()
+        tokio::time::sleep(Duration::from_millis(150)).await;
+        let stats = client.get_cache_stats().await;
+        assert_eq!(stats.total_entries, 0);
+        assert_eq!(stats.ttl_evictions, 1);
+
+        client.shutdown_cache_maintenance().await;
+    }
+
+    #[tokio::test]
+    async fn test_identical_concurrent_calls_are_coalesced() {
+        let tx_hex = "0100000001000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000";
+        let (address, requests) = start_slow_rpc_server(Duration::from_millis(200), json!(tx_hex)).await;
+        let config = BitcoinRpcConfig {
+            coalesce_window: Some(Duration::ZERO),
+            ..test_config(&address)
+        };
+        let client = BitcoinRpcClient::new(config).unwrap();
+        let txid = "4a5e1e4baab89f3a32518a88c31bc87f618f76673e2cc77ab2127b7afdeda33b";
+
+        let calls = (0..20).map(|_| {
+            let client = client.clone();
+            async move { client.get_transaction(txid).await }
+        });
+        let results = futures::future::join_all(calls).await;
+        assert!(results.iter().all(|result| result.is_ok()));
+        assert_eq!(requests.load(std::sync::atomic::Ordering::SeqCst), 1);
+
+        let metrics = client.metrics();
+        assert_eq!(metrics.rpc_requests_total.get("getrawtransaction"), Some(&1));
+        assert_eq!(metrics.rpc_coalesced_total, 19);
+
+        // With no window, a call after the shared one finished goes to the node again
+        client.get_transaction(txid).await.unwrap();
+        assert_eq!(requests.load(std::sync::atomic::Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_chain_tip_is_cached_for_ttl() {
+        let config = BitcoinRpcConfig {
+            tip_cache_ttl: Duration::from_millis(100),
+            ..test_config(&server_url().replace("http://", ""))
+        };
+        let client = BitcoinRpcClient::new(config).unwrap();
+        let answer = |method: &str, id: u64, result: serde_json::Value| {
+            mock("POST", "/")
+                .match_body(Matcher::PartialJson(json!({ "method": method, "id": id.to_string() })))
+                .with_status(200)
+                .with_header("content-type", "application/json")
+                .with_body(json!({ "result": result, "error": null, "id": id.to_string() }).to_string())
+                .expect(1)
+                .create()
+        };
+        let tip_hash = "0000000000000000000320283a032748cef8227873ff4872689bf23f1cda83a5";
+        let mocks = [
+            answer("getblockcount", 1, json!(100)),
+            answer("getblockcount", 2, json!(101)),
+            answer("getblockcount", 3, json!(102)),
+            answer("getblockchaininfo", 4, json!({ "chain": "main", "blocks": 103, "bestblockhash": tip_hash })),
+        ];
+
+        // Within the TTL the node is asked once
+        assert_eq!(client.get_block_count().await.unwrap(), 100);
+        assert_eq!(client.get_block_count().await.unwrap(), 100);
+
+        tokio::time::sleep(Duration::from_millis(150)).await;
+        assert_eq!(client.get_block_count().await.unwrap(), 101);
+
+        // A reorg drops the cached tip right away, refetching it to recount
+        // cached confirmations
+        client.handle_reorg(95).await;
+        assert_eq!(client.get_block_count().await.unwrap(), 102);
+
+        let tip = (103, BlockHash::from_str(tip_hash).unwrap());
+        assert_eq!(client.get_chain_tip().await.unwrap(), tip);
+        assert_eq!(client.get_chain_tip().await.unwrap(), tip);
+        assert_eq!(client.get_block_count().await.unwrap(), 103);
+
+        for mock in &mocks {
+            mock.assert();
+        }
+        let metrics = client.metrics();
+        assert_eq!(metrics.rpc_requests_total.get("getblockcount"), Some(&3));
+        assert_eq!(metrics.rpc_requests_total.get("getblockchaininfo"), Some(&1));
+    }
+
+    /// Collects what a subscriber writes, where `TestWriter` would hand it to the harness
+    #[cfg(feature = "tracing")]
+    #[derive(Clone, Default)]
+    struct CapturedOutput(std::sync::Arc<std::sync::Mutex<Vec<u8>>>);
+
+    #[cfg(feature = "tracing")]
+    impl std::io::Write for CapturedOutput {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[cfg(feature = "tracing")]
+    impl<'a> tracing_subscriber::fmt::MakeWriter<'a> for CapturedOutput {
+        type Writer = Self;
+
+        fn make_writer(&'a self) -> Self::Writer {
+            self.clone()
+        }
+    }
+
+    #[cfg(feature = "tracing")]
+    #[tokio::test]
+    async fn test_rpc_call_span_records_method() {
+        let output = CapturedOutput::default();
+        let _subscriber = tracing::subscriber::set_default(crate::bitcoin::trace::test_subscriber(output.clone()));
+
+        let _count = mock_rpc_response("getblockcount", 1, json!(100));
+        let client = setup_test_client();
+        assert_eq!(client.get_block_count().await.unwrap(), 100);
+
+        let logged = String::from_utf8(output.0.lock().unwrap().clone()).unwrap();
+        let span = logged
+            .lines()
+            .find(|line| line.contains("rpc_call{"))
+            .unwrap_or_else(|| panic!("no rpc_call span in {:?}", logged));
+        assert!(span.contains("method=getblockcount"), "{}", span);
+        assert!(span.contains("duration_ms="), "{}", span);
+        assert!(span.contains("outcome=ok"), "{}", span);
     }
-} 
\ No newline at end of file
+}
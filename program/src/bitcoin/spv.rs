@@ -0,0 +1,94 @@
+use arch_program::{msg, program_error::ProgramError};
+use bitcoin::{consensus, BlockHash, MerkleBlock, Txid};
+
+use crate::error::OVTError;
+
+/// Check that `proof_bytes`, a serialized merkle block as `gettxoutproof`
+/// returns it, proves `txid` is in the block `expected_block_hash`
+///
+/// Only the header hash and the merkle branch are checked, so the node is
+/// trusted for nothing but relaying the proof; whether `expected_block_hash`
+/// is on the best chain is up to the caller. Every failure is `InvalidMerkleProof`.
+pub fn verify_merkle_proof(proof_bytes: &[u8], txid: &Txid, expected_block_hash: &BlockHash) -> Result<(), ProgramError> {
+    let merkle_block: MerkleBlock = consensus::deserialize(proof_bytes).map_err(|e| {
+        msg!("Malformed merkle proof: {}", e);
+        ProgramError::from(OVTError::InvalidMerkleProof)
+    })?;
+
+    let block_hash = merkle_block.header.block_hash();
+    if block_hash != *expected_block_hash {
+        msg!("Merkle proof is for block {}, expected {}", block_hash, expected_block_hash);
+        return Err(OVTError::InvalidMerkleProof.into());
+    }
+
+    let mut matches = Vec::new();
+    let mut indexes = Vec::new();
+    merkle_block.extract_matches(&mut matches, &mut indexes).map_err(|e| {
+        msg!("Merkle proof does not match block {}: {}", block_hash, e);
+        ProgramError::from(OVTError::InvalidMerkleProof)
+    })?;
+    if !matches.contains(txid) {
+        msg!("Merkle proof for block {} does not include {}", block_hash, txid);
+        return Err(OVTError::InvalidMerkleProof.into());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bitcoin::hashes::Hash;
+    use serde::Deserialize;
+
+    /// `gettxoutproof` for one transaction of mainnet block 100000
+    #[derive(Deserialize)]
+    struct ProofFixture {
+        block_hash: BlockHash,
+        proof: String,
+        included_txid: Txid,
+        excluded_txid: Txid,
+    }
+
+    fn fixture() -> (ProofFixture, Vec<u8>) {
+        let fixture: ProofFixture =
+            serde_json::from_str(include_str!("../../tests/fixtures/txoutproof_block_100000.json")).unwrap();
+        let proof = hex::decode(&fixture.proof).unwrap();
+        (fixture, proof)
+    }
+
+    fn is_invalid_proof(result: Result<(), ProgramError>) -> bool {
+        matches!(result, Err(ProgramError::Custom(code)) if code == OVTError::InvalidMerkleProof.code())
+    }
+
+    #[test]
+    fn test_included_txid_is_proven() {
+        let (fixture, proof) = fixture();
+        verify_merkle_proof(&proof, &fixture.included_txid, &fixture.block_hash).unwrap();
+    }
+
+    #[test]
+    fn test_excluded_txid_is_rejected() {
+        // In the block, but not among the transactions the proof covers
+        let (fixture, proof) = fixture();
+        assert!(is_invalid_proof(verify_merkle_proof(&proof, &fixture.excluded_txid, &fixture.block_hash)));
+    }
+
+    #[test]
+    fn test_proof_for_another_block_is_rejected() {
+        let (fixture, proof) = fixture();
+        let other = BlockHash::all_zeros();
+        assert!(is_invalid_proof(verify_merkle_proof(&proof, &fixture.included_txid, &other)));
+    }
+
+    #[test]
+    fn test_tampered_proofs_are_rejected() {
+        let (fixture, proof) = fixture();
+        // A flipped bit in the included txid's leaf no longer hashes to the header's root
+        let mut tampered = proof.clone();
+        tampered[80 + 4 + 1 + 32] ^= 1;
+        assert!(is_invalid_proof(verify_merkle_proof(&tampered, &fixture.included_txid, &fixture.block_hash)));
+
+        assert!(is_invalid_proof(verify_merkle_proof(&proof[..proof.len() - 1], &fixture.included_txid, &fixture.block_hash)));
+        assert!(is_invalid_proof(verify_merkle_proof(&[], &fixture.included_txid, &fixture.block_hash)));
+    }
+}
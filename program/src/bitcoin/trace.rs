@@ -0,0 +1,44 @@
+use std::fmt::Display;
+
+/// Set `field` on the current span; does nothing without the `tracing` feature
+///
+/// The field must have been declared on the span, usually as
+/// `tracing::field::Empty` in its `instrument` attribute.
+#[cfg(feature = "tracing")]
+pub(crate) fn record(field: &'static str, value: impl Display) {
+    tracing::Span::current().record(field, tracing::field::display(value));
+}
+
+#[cfg(not(feature = "tracing"))]
+pub(crate) fn record(_field: &'static str, _value: impl Display) {}
+
+/// Set the current span's `outcome` to `ok` or the error
+pub(crate) fn record_outcome<T, E: Display>(result: &Result<T, E>) {
+    match result {
+        Ok(_) => record("outcome", "ok"),
+        Err(e) => record("outcome", e),
+    }
+}
+
+/// Print spans as they close, with their fields and timings, to the test
+/// harness' captured output
+///
+/// Safe to call from every test; only the first call installs the subscriber.
+#[cfg(feature = "tracing")]
+pub fn init_test_subscriber() {
+    let _ = tracing::subscriber::set_global_default(test_subscriber(tracing_subscriber::fmt::TestWriter::new()));
+}
+
+/// The subscriber `init_test_subscriber` installs, writing to `writer`
+#[cfg(feature = "tracing")]
+pub(crate) fn test_subscriber<W>(writer: W) -> impl tracing::Subscriber + Send + Sync
+where
+    W: for<'a> tracing_subscriber::fmt::MakeWriter<'a> + Send + Sync + 'static,
+{
+    tracing_subscriber::fmt()
+        .with_writer(writer)
+        .with_ansi(false)
+        .with_max_level(tracing::Level::TRACE)
+        .with_span_events(tracing_subscriber::fmt::format::FmtSpan::CLOSE)
+        .finish()
+}
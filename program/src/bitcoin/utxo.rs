@@ -20,13 +20,30 @@ use bitcoin::{
     Script,
     BlockHash,
     Block,
+    OutPoint,
     Txid,
+    key::XOnlyPublicKey,
+    opcodes::all::OP_CHECKMULTISIG,
+    script::Builder,
+    secp256k1::Secp256k1,
 };
 
 use borsh::{BorshDeserialize, BorshSerialize};
-use super::rpc::{BitcoinRpcClient, BitcoinRpcError};
+#[cfg(feature = "client")]
+use async_trait::async_trait;
+#[cfg(feature = "client")]
+use super::memo::{parse_payment_memo, PaymentMemo};
+#[cfg(feature = "client")]
+use super::error::BitcoinRpcError;
+#[cfg(feature = "client")]
+use super::spv::verify_merkle_proof;
+#[cfg(feature = "client")]
+use super::BitcoinRpcConfig;
+use crate::error::OVTError;
+use crate::network_config::get_network_params;
 use hex::{FromHex, ToHex};
 use std::io::{self, Read, Cursor};
+use std::str::FromStr;
 
 // ByteReader trait for handling byte reading operations
 trait ByteReader {
@@ -42,33 +59,268 @@ impl<R: Read> ByteReader for R {
     }
 }
 
-// Error codes for custom errors
-const ERR_UTXO_VALIDATION: u32 = 1000;
-const ERR_TX_FETCH: u32 = 1001;
-const ERR_INVALID_VOUT: u32 = 1002;
-const ERR_PAYMENT_MISMATCH: u32 = 1003;
-const ERR_INVALID_DESTINATION: u32 = 1004;
-const ERR_INSUFFICIENT_CONF: u32 = 1005;
-const ERR_UTXO_PENDING: u32 = 1006;
-const ERR_UTXO_SPENT: u32 = 1007;
-const ERR_UTXO_INVALID: u32 = 1008;
-const ERR_INSUFFICIENT_CONFIRMATIONS: u32 = 1009;
-const ERR_UTXO_STATUS: u32 = 1010;
-const ERR_REORG_DETECTED: u32 = 1011;
+/// Smallest output `UtxoMeta::try_new` accepts: the dust limit of a P2PKH output
+pub const DEFAULT_DUST_FLOOR_SATS: u64 = 546;
+
+/// Why a UTXO description was rejected before it could be used
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum UtxoError {
+    #[error("Invalid txid {0:?}")]
+    InvalidTxid(String),
+    #[error("vout {0} is reserved for null outpoints")]
+    InvalidVout(u32),
+    #[error("{amount_sats} sats is below the dust floor of {dust_floor_sats} sats")]
+    Dust { amount_sats: u64, dust_floor_sats: u64 },
+    #[error("{0} sats exceeds the 21M BTC supply cap")]
+    AboveMaxMoney(u64),
+}
+
+impl From<UtxoError> for ProgramError {
+    fn from(e: UtxoError) -> Self {
+        msg!("UTXO error: {}", e);
+        match e {
+            UtxoError::InvalidTxid(_) | UtxoError::InvalidVout(_) => ProgramError::InvalidArgument,
+            UtxoError::Dust { .. } => ProgramError::Custom(OVTError::DustUtxo.code()),
+            UtxoError::AboveMaxMoney(_) => ProgramError::Custom(OVTError::AmountOutOfRange.code()),
+        }
+    }
+}
+
+/// Confirmations `validate_utxo` requires unless a policy says otherwise
+pub const DEFAULT_REQUIRED_CONFIRMATIONS: u32 = 6;
+
+/// What `validate_utxo` requires of a UTXO
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ValidationPolicy {
+    pub required_confirmations: u32,
+    /// Outputs worth less than this are rejected (0 by default)
+    pub dust_limit_sats: u64,
+    /// Outputs with more confirmations than this are rejected (no limit by default)
+    pub max_age_blocks: Option<u32>,
+    /// How far a treasury payment may fall short of its declared amount (0 by default)
+    pub payment_tolerance_sats: u64,
+    /// Strict mode: a treasury payment must also be proven, with `gettxoutproof`,
+    /// to be in this block, which the caller trusts without asking the node (off by default)
+    pub trusted_block_hash: Option<BlockHash>,
+}
+
+impl Default for ValidationPolicy {
+    fn default() -> Self {
+        Self {
+            required_confirmations: DEFAULT_REQUIRED_CONFIRMATIONS,
+            dust_limit_sats: 0,
+            max_age_blocks: None,
+            payment_tolerance_sats: 0,
+            trusted_block_hash: None,
+        }
+    }
+}
+
+impl ValidationPolicy {
+    /// Default policy for `network`, with its `NetworkParams` confirmations
+    pub fn for_network(network: Network) -> Self {
+        Self {
+            required_confirmations: get_network_params(network).min_confirmations,
+            ..Self::default()
+        }
+    }
+
+    /// Default policy requiring `config`'s confirmations
+    #[cfg(feature = "client")]
+    pub fn from_rpc_config(config: &BitcoinRpcConfig) -> Self {
+        Self {
+            required_confirmations: config.min_confirmations,
+            ..Self::default()
+        }
+    }
+
+    /// Check a UTXO whose confirmations are up to date and whose `status` was
+    /// just fetched from the node
+    pub fn check(&self, utxo: &UtxoMeta, status: UtxoStatus) -> Result<(), ProgramError> {
+        match status {
+            UtxoStatus::Active => {}
+            UtxoStatus::Pending => {
+                msg!("UTXO is still pending");
+                return Err(OVTError::UnexpectedUtxoStatus.into());
+            }
+            UtxoStatus::Spent => {
+                msg!("UTXO is already spent");
+                return Err(OVTError::UnexpectedUtxoStatus.into());
+            }
+            UtxoStatus::Invalid => {
+                msg!("UTXO is invalid");
+                return Err(OVTError::UnexpectedUtxoStatus.into());
+            }
+        }
+
+        if !utxo.confirmed().meets(self.required_confirmations) {
+            msg!("Insufficient confirmations: {} (required {})", utxo.confirmations, self.required_confirmations);
+            return Err(OVTError::InsufficientConfirmations.into());
+        }
+        if utxo.amount_sats < self.dust_limit_sats {
+            msg!("UTXO value {} sats is below the dust limit of {} sats", utxo.amount_sats, self.dust_limit_sats);
+            return Err(OVTError::DustUtxo.into());
+        }
+        if let Some(max_age) = self.max_age_blocks {
+            if utxo.confirmations > max_age {
+                msg!("UTXO is {} blocks old, policy allows at most {}", utxo.confirmations, max_age);
+                return Err(OVTError::UtxoTooOld.into());
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Amount from a satoshi count, or None above the 21M BTC supply cap
+pub fn checked_amount_from_sat(sats: u64) -> Option<Amount> {
+    let amount = Amount::from_sat(sats);
+    (amount <= Amount::MAX_MONEY).then_some(amount)
+}
+
+/// Amount from a BTC-denominated float as returned by Bitcoin Core and esplora
+///
+/// Returns None for negative values, sub-satoshi precision and anything above
+/// the 21M BTC supply cap.
+pub fn checked_amount_from_btc(btc: f64) -> Option<Amount> {
+    let amount = Amount::from_btc(btc).ok()?;
+    (amount <= Amount::MAX_MONEY).then_some(amount)
+}
+
+/// Outpoint identifying a UTXO, displayed as `txid:vout`
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct UtxoKey {
+    pub txid: String,
+    pub vout: u32,
+}
+
+impl UtxoKey {
+    pub fn new(txid: impl Into<String>, vout: u32) -> Self {
+        Self { txid: txid.into(), vout }
+    }
+}
+
+impl std::fmt::Display for UtxoKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.txid, self.vout)
+    }
+}
+
+impl FromStr for UtxoKey {
+    type Err = ProgramError;
+
+    /// Parses `txid:vout`, lowercasing the txid
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (txid, vout) = s.split_once(':').ok_or_else(|| {
+            msg!("Invalid outpoint {:?}: expected txid:vout", s);
+            ProgramError::InvalidArgument
+        })?;
+        let vout = vout.parse::<u32>().map_err(|_| {
+            msg!("Invalid outpoint {:?}: vout is not a number", s);
+            ProgramError::InvalidArgument
+        })?;
+        Ok(Self::new(normalize_txid(txid)?, vout))
+    }
+}
+
+/// Confirmation count of a transaction
+///
+/// Block heights fit in a u32, so confirmation counts do too.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Confirmations(pub u32);
+
+impl Confirmations {
+    pub const ZERO: Self = Self(0);
+
+    /// Confirmation count reported as a wider integer, saturating at `u32::MAX`
+    pub fn saturating_from(count: u64) -> Self {
+        Self(u32::try_from(count).unwrap_or(u32::MAX))
+    }
+
+    /// Whether the transaction has at least `threshold` confirmations
+    pub fn meets(self, threshold: u32) -> bool {
+        self.0 >= threshold
+    }
+
+    pub fn get(self) -> u32 {
+        self.0
+    }
+}
+
+impl From<u32> for Confirmations {
+    fn from(count: u32) -> Self {
+        Self(count)
+    }
+}
+
+impl From<Confirmations> for u32 {
+    fn from(confirmations: Confirmations) -> Self {
+        confirmations.0
+    }
+}
+
+impl std::fmt::Display for Confirmations {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Layout version prefixed by `UtxoMeta::to_bytes`
+///
+/// Version 1 is the Borsh layout with u64 confirmations; version 2 stores them as u32.
+const UTXO_META_LAYOUT_V1: u8 = 1;
+const UTXO_META_LAYOUT_V2: u8 = 2;
+
+/// Length of the unversioned system-level layout: hex txid, vout and amount
+const UTXO_HEADER_LEN: usize = 64 + 4 + 8;
+
+/// Lowercase form of a 64-character hex txid
+fn normalize_txid(txid: &str) -> Result<String, ProgramError> {
+    if txid.len() != 64 || !txid.bytes().all(|b| b.is_ascii_hexdigit()) {
+        msg!("Invalid txid: {:?}", txid);
+        return Err(ProgramError::InvalidArgument);
+    }
+    Ok(txid.to_ascii_lowercase())
+}
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash, BorshSerialize, BorshDeserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct UtxoMeta {
     pub txid: String,
     pub vout: u32,
     pub amount_sats: u64,
     pub script_pubkey: String,
-    pub confirmations: u64,
+    pub confirmations: u32,
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
     pub block_height: Option<u32>,  // Height of the block containing the transaction
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
     pub block_hash: Option<String>, // Hash of the block containing the transaction
 }
 
+/// UTXOs sort by outpoint so coin selection and snapshots are reproducible;
+/// the remaining fields only break ties between copies of the same outpoint.
+impl Ord for UtxoMeta {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        (&self.txid, self.vout)
+            .cmp(&(&other.txid, other.vout))
+            .then_with(|| self.amount_sats.cmp(&other.amount_sats))
+            .then_with(|| self.script_pubkey.cmp(&other.script_pubkey))
+            .then_with(|| self.confirmations.cmp(&other.confirmations))
+            .then_with(|| self.block_height.cmp(&other.block_height))
+            .then_with(|| self.block_hash.cmp(&other.block_hash))
+    }
+}
+
+impl PartialOrd for UtxoMeta {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
 impl UtxoMeta {
-    /// Creates a new UTXO metadata instance
+    /// Creates a new UTXO metadata instance without checking its fields
+    ///
+    /// Meant for tests and fixtures; anything built from external input should
+    /// go through `try_new`.
     pub fn new(txid: String, vout: u32, amount_sats: u64) -> Self {
         Self { 
             txid, 
@@ -81,6 +333,72 @@ impl UtxoMeta {
         }
     }
 
+    /// Creates a new UTXO metadata instance with a well-formed outpoint and an
+    /// amount between the default dust floor and 21M BTC
+    pub fn try_new(txid: String, vout: u32, amount_sats: u64) -> Result<Self, UtxoError> {
+        Self::try_new_with_dust_floor(txid, vout, amount_sats, DEFAULT_DUST_FLOOR_SATS)
+    }
+
+    /// `try_new` with a custom dust floor
+    pub fn try_new_with_dust_floor(
+        txid: String,
+        vout: u32,
+        amount_sats: u64,
+        dust_floor_sats: u64,
+    ) -> Result<Self, UtxoError> {
+        let txid = normalize_txid(&txid).map_err(|_| UtxoError::InvalidTxid(txid))?;
+        let utxo = Self::new(txid, vout, amount_sats);
+        utxo.check_sanity(dust_floor_sats)?;
+        Ok(utxo)
+    }
+
+    /// Checks the fields `try_new` validates on a UTXO that was built some other way
+    pub fn check_sanity(&self, dust_floor_sats: u64) -> Result<(), UtxoError> {
+        if normalize_txid(&self.txid).ok().as_deref() != Some(self.txid.as_str()) {
+            return Err(UtxoError::InvalidTxid(self.txid.clone()));
+        }
+        if self.vout == u32::MAX {
+            return Err(UtxoError::InvalidVout(self.vout));
+        }
+        if self.amount_sats < dust_floor_sats {
+            return Err(UtxoError::Dust { amount_sats: self.amount_sats, dust_floor_sats });
+        }
+        if checked_amount_from_sat(self.amount_sats).is_none() {
+            return Err(UtxoError::AboveMaxMoney(self.amount_sats));
+        }
+        Ok(())
+    }
+
+    /// Creates a new UTXO metadata instance, rejecting amounts above 21M BTC
+    pub fn with_amount(txid: String, vout: u32, amount: Amount) -> Result<Self, ProgramError> {
+        if amount > Amount::MAX_MONEY {
+            msg!("UTXO amount {} exceeds the 21M BTC supply cap", amount);
+            return Err(OVTError::AmountOutOfRange.into());
+        }
+        Ok(Self::new(txid, vout, amount.to_sat()))
+    }
+
+    /// JSON representation served to the frontend; block info is omitted until known
+    #[cfg(feature = "serde")]
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("UtxoMeta always serializes to JSON")
+    }
+
+    /// Outpoint of this UTXO
+    pub fn key(&self) -> UtxoKey {
+        UtxoKey::new(self.txid.clone(), self.vout)
+    }
+
+    /// Value of the output as a typed amount
+    pub fn amount(&self) -> Amount {
+        Amount::from_sat(self.amount_sats)
+    }
+
+    /// Confirmations of the funding transaction
+    pub fn confirmed(&self) -> Confirmations {
+        Confirmations(self.confirmations)
+    }
+
     /// Update block information
     pub fn update_block_info(&mut self, height: u32, hash: String) {
         self.block_height = Some(height);
@@ -95,7 +413,21 @@ impl UtxoMeta {
         }
     }
 
+    /// Parsed form of the hex txid
+    pub fn txid_parsed(&self) -> Result<Txid, ProgramError> {
+        Txid::from_str(&self.txid).map_err(|_| ProgramError::InvalidArgument)
+    }
+
+    /// Outpoint of this UTXO as used by the `bitcoin` crate
+    pub fn outpoint(&self) -> Result<OutPoint, ProgramError> {
+        Ok(OutPoint::new(self.txid_parsed()?, self.vout))
+    }
+
     /// Convert the string txid to bytes for system-level operations
+    ///
+    /// The bytes are in display order, as written in the hex string. That is
+    /// the reverse of `Txid`'s internal byte order (`to_byte_array`), which is
+    /// what serialized transactions and outpoints use.
     pub fn txid_to_bytes(&self) -> Result<[u8; 32], ProgramError> {
         Vec::from_hex(&self.txid)
             .map_err(|_| ProgramError::InvalidArgument)?
@@ -103,14 +435,60 @@ impl UtxoMeta {
             .map_err(|_| ProgramError::InvalidArgument)
     }
 
-    /// Create a UtxoMeta from system-level byte representation
+    /// Encode as the current versioned layout, readable by `from_bytes`
+    ///
+    /// Unlike `to_header_bytes` this keeps every field, including the script
+    /// and the block info used for reorg tracking.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![UTXO_META_LAYOUT_V2];
+        bytes.extend(borsh::to_vec(self).expect("Borsh serialization into a Vec can't fail"));
+        bytes
+    }
+
+    /// Encode as the system-level layout read by `from_bytes`: hex txid, vout
+    /// and amount, dropping chain data
+    pub fn to_header_bytes(&self) -> Result<Vec<u8>, ProgramError> {
+        let mut bytes = Vec::with_capacity(UTXO_HEADER_LEN);
+        bytes.extend_from_slice(normalize_txid(&self.txid)?.as_bytes());
+        bytes.extend_from_slice(&self.vout.to_le_bytes());
+        bytes.extend_from_slice(&self.amount_sats.to_le_bytes());
+        Ok(bytes)
+    }
+
+    /// Create a UtxoMeta from its byte representation
+    ///
+    /// Accepts the versioned layouts written by `to_bytes` (migrating version 1
+    /// records) as well as the unversioned system-level header, which starts
+    /// with an ASCII hex txid and so never collides with a version byte.
+    ///
+    /// Txids are normalized to lowercase hex; anything else is rejected with
+    /// `InvalidArgument`. A header shorter than 76 bytes gives `AccountDataTooSmall`.
     pub fn from_bytes(bytes: &[u8]) -> Result<Self, ProgramError> {
+        let mut utxo = match bytes.split_first() {
+            Some((&UTXO_META_LAYOUT_V2, body)) => {
+                Self::try_from_slice(body).map_err(|_| ProgramError::InvalidInstructionData)?
+            }
+            Some((&UTXO_META_LAYOUT_V1, body)) => LegacyUtxoMeta::try_from_slice(body)
+                .map(Self::from)
+                .map_err(|_| ProgramError::InvalidInstructionData)?,
+            _ => Self::from_header_bytes(bytes)?,
+        };
+        utxo.txid = normalize_txid(&utxo.txid)?;
+        Ok(utxo)
+    }
+
+    /// Parse the system-level layout: hex txid, vout and amount, with no chain data
+    fn from_header_bytes(bytes: &[u8]) -> Result<Self, ProgramError> {
+        if bytes.len() < UTXO_HEADER_LEN {
+            msg!("UTXO header too short: {} bytes, expected {}", bytes.len(), UTXO_HEADER_LEN);
+            return Err(ProgramError::AccountDataTooSmall);
+        }
         let mut cursor = Cursor::new(bytes);
         
         // Read txid (64 chars = 32 bytes in hex)
         let txid_bytes = cursor.read_exact_vec(64)?;
         let txid = String::from_utf8(txid_bytes)
-            .map_err(|_| ProgramError::InvalidInstructionData)?;
+            .map_err(|_| ProgramError::InvalidArgument)?;
 
         // Read vout (4 bytes)
         let vout_bytes = cursor.read_exact_vec(4)?;
@@ -137,7 +515,41 @@ impl UtxoMeta {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+impl From<(OutPoint, Amount)> for UtxoMeta {
+    fn from((outpoint, amount): (OutPoint, Amount)) -> Self {
+        Self::new(outpoint.txid.to_string(), outpoint.vout, amount.to_sat())
+    }
+}
+
+/// `UtxoMeta` as serialized before confirmations became a u32
+#[derive(Debug, Clone, BorshSerialize, BorshDeserialize)]
+pub(crate) struct LegacyUtxoMeta {
+    pub txid: String,
+    pub vout: u32,
+    pub amount_sats: u64,
+    pub script_pubkey: String,
+    pub confirmations: u64,
+    pub block_height: Option<u32>,
+    pub block_hash: Option<String>,
+}
+
+impl From<LegacyUtxoMeta> for UtxoMeta {
+    fn from(legacy: LegacyUtxoMeta) -> Self {
+        Self {
+            txid: legacy.txid,
+            vout: legacy.vout,
+            amount_sats: legacy.amount_sats,
+            script_pubkey: legacy.script_pubkey,
+            confirmations: Confirmations::saturating_from(legacy.confirmations).get(),
+            block_height: legacy.block_height,
+            block_hash: legacy.block_hash,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, BorshSerialize, BorshDeserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
 pub enum UtxoStatus {
     Active,
     Pending,
@@ -150,56 +562,227 @@ pub struct TreasuryPayment {
     pub txid: String,
     pub amount_sats: u64,
     pub utxo: UtxoMeta,
+    /// Height of the block the payment was mined in, from `get_tx_block_info`;
+    /// set by `verify_treasury_payment` and passed on to BuybackBurn
+    pub block_height: Option<u32>,
+}
+
+impl TreasuryPayment {
+    /// Creates a payment record, rejecting amounts above 21M BTC
+    pub fn new(txid: String, amount: Amount, utxo: UtxoMeta) -> Result<Self, ProgramError> {
+        if amount > Amount::MAX_MONEY {
+            msg!("Payment amount {} exceeds the 21M BTC supply cap", amount);
+            return Err(OVTError::AmountOutOfRange.into());
+        }
+        Ok(Self {
+            txid,
+            amount_sats: amount.to_sat(),
+            utxo,
+            block_height: None,
+        })
+    }
+
+    /// Expected payment value as a typed amount
+    pub fn amount(&self) -> Amount {
+        Amount::from_sat(self.amount_sats)
+    }
+}
+
+/// Result of a successful `verify_treasury_payment`
+#[cfg(feature = "client")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VerifiedTreasuryPayment {
+    /// Every output of the payment transaction paying the treasury
+    pub outpoints: Vec<OutPoint>,
+    /// Payer reference from the transaction's OP_RETURN output, if it has one
+    pub memo: Option<PaymentMemo>,
 }
 
 // OVT-specific UTXO verification
-pub async fn verify_treasury_payment(
-    rpc: &BitcoinRpcClient,
+//
+// The payment may be split across several outputs of the same transaction;
+// the outpoints of every output paying the treasury are returned so they can
+// all be registered as treasury UTXOs.
+#[cfg(feature = "client")]
+pub async fn verify_treasury_payment<R: UtxoValidationRpc + ?Sized>(
+    rpc: &R,
     payment: &mut TreasuryPayment,
     treasury_pubkey: &PublicKey,
-) -> Result<(), ProgramError> {
-    // Validate UTXO first
-    validate_utxo(rpc, &mut payment.utxo).await?;
+    script_kind: &TreasuryScriptKind,
+    policy: &ValidationPolicy,
+) -> Result<VerifiedTreasuryPayment, ProgramError> {
+    // Validate UTXO first, which also looks up its block
+    validate_utxo(rpc, &mut payment.utxo, policy).await?;
+    payment.block_height = payment.utxo.block_height;
+    let outpoint = payment.utxo.outpoint()?;
 
     // Fetch transaction
     let tx = rpc.get_transaction(&payment.txid)
         .await
-        .map_err(|e| match e {
-            BitcoinRpcError::TxNotFound(_) => ProgramError::Custom(ERR_TX_FETCH),
-            _ => ProgramError::Custom(ERR_TX_FETCH),
-        })?;
+        .map_err(ProgramError::from)?;
 
-    // Verify output index exists
-    let output = tx.output.get(payment.utxo.vout as usize)
-        .ok_or(ProgramError::Custom(ERR_INVALID_VOUT))?;
+    // The payment must be the transaction that created the UTXO
+    if tx.compute_txid() != outpoint.txid {
+        msg!("Payment transaction {} does not fund UTXO {}", tx.compute_txid(), outpoint);
+        return Err(OVTError::PaymentMismatch.into());
+    }
 
-    // Verify payment amount
-    if output.value.to_sat() != payment.amount_sats {
-        msg!("Payment amount mismatch: expected {} sats, got {} sats",
-            payment.amount_sats, output.value.to_sat());
-        return Err(ProgramError::Custom(ERR_PAYMENT_MISMATCH));
+    // In strict mode the node must also prove the payment is in the trusted block
+    if let Some(trusted_block_hash) = &policy.trusted_block_hash {
+        let proof = rpc.get_txout_proof(&[outpoint.txid], Some(trusted_block_hash))
+            .await
+            .map_err(ProgramError::from)?;
+        verify_merkle_proof(&proof, &outpoint.txid, trusted_block_hash)?;
     }
 
+    // Verify output index exists
+    let output = tx.output.get(outpoint.vout as usize)
+        .ok_or_else(|| ProgramError::from(OVTError::InvalidVout))?;
+
     // Verify destination
-    let expected_script = get_treasury_script_pubkey(treasury_pubkey)?;
+    let expected_script = script_kind.script_pubkey(treasury_pubkey)?;
     if output.script_pubkey != expected_script {
         msg!("Invalid payment destination");
-        return Err(ProgramError::Custom(ERR_INVALID_DESTINATION));
+        return Err(OVTError::InvalidPaymentDestination.into());
     }
 
-    Ok(())
+    // Verify payment amount across all treasury outputs
+    let outpoints = treasury_outputs(&tx, &expected_script, payment.amount(), policy.payment_tolerance_sats)?;
+
+    Ok(VerifiedTreasuryPayment {
+        outpoints,
+        memo: parse_payment_memo(&tx),
+    })
+}
+
+/// Outpoints of every output of `tx` paying `expected_script`, provided that
+/// together they are worth at least `amount` less `tolerance_sats`
+pub fn treasury_outputs(
+    tx: &Transaction,
+    expected_script: &Script,
+    amount: Amount,
+    tolerance_sats: u64,
+) -> Result<Vec<OutPoint>, ProgramError> {
+    let txid = tx.compute_txid();
+    let mut total = Amount::ZERO;
+    let mut outpoints = Vec::new();
+    for (vout, output) in tx.output.iter().enumerate() {
+        if output.script_pubkey.as_script() == expected_script {
+            total = total.checked_add(output.value)
+                .ok_or_else(|| ProgramError::from(OVTError::AmountOutOfRange))?;
+            outpoints.push(OutPoint::new(txid, vout as u32));
+        }
+    }
+
+    if outpoints.is_empty() {
+        msg!("Invalid payment destination");
+        return Err(OVTError::InvalidPaymentDestination.into());
+    }
+
+    let required = amount.checked_sub(Amount::from_sat(tolerance_sats)).unwrap_or(Amount::ZERO);
+    if total < required {
+        msg!("Payment amount mismatch: expected {}, got {} across {} outputs",
+            amount, total, outpoints.len());
+        return Err(OVTError::PaymentMismatch.into());
+    }
+
+    Ok(outpoints)
 }
 
 // OVT-specific script generation for treasury
 pub fn get_treasury_script_pubkey(pubkey: &PublicKey) -> Result<ScriptBuf, ProgramError> {
-    // Create a P2WPKH script directly
-    use bitcoin::hashes::Hash as HashTrait;
-    let pubkey_hash = bitcoin::hashes::hash160::Hash::hash(&pubkey.to_bytes());
-    // Convert hash160::Hash to WPubkeyHash
-    let wpubkey_hash = bitcoin::key::WPubkeyHash::from_slice(pubkey_hash.as_ref())
-        .map_err(|_| ProgramError::InvalidArgument)?;
-    let script = ScriptBuf::new_p2wpkh(&wpubkey_hash);
-    Ok(script)
+    TreasuryScriptKind::P2wpkh.script_pubkey(pubkey)
+}
+
+/// Number of keys in a treasury multisig
+pub const TREASURY_MULTISIG_KEYS: usize = 3;
+
+/// How the treasury's scriptPubKey is derived
+#[derive(Debug, Clone, Default, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum TreasuryScriptKind {
+    /// Pay to the hash of the treasury key
+    #[default]
+    P2wpkh,
+    /// Key-path-only Taproot output of the treasury key, tweaked as in BIP86
+    P2tr,
+    /// `threshold`-of-3 multisig over `pubkeys`, which are sorted as in BIP67
+    /// before building the witness script
+    P2wshMultisig {
+        threshold: u8,
+        #[cfg_attr(feature = "serde", serde(with = "hex_pubkeys"))]
+        pubkeys: [[u8; 33]; TREASURY_MULTISIG_KEYS],
+    },
+}
+
+impl TreasuryScriptKind {
+    /// scriptPubKey treasury payments must pay to
+    ///
+    /// Multisig outputs are derived from their own keys and ignore `treasury_pubkey`.
+    pub fn script_pubkey(&self, treasury_pubkey: &PublicKey) -> Result<ScriptBuf, ProgramError> {
+        match self {
+            Self::P2wpkh => {
+                use bitcoin::hashes::Hash as HashTrait;
+                let pubkey_hash = bitcoin::hashes::hash160::Hash::hash(&treasury_pubkey.to_bytes());
+                // Convert hash160::Hash to WPubkeyHash
+                let wpubkey_hash = bitcoin::key::WPubkeyHash::from_slice(pubkey_hash.as_ref())
+                    .map_err(|_| ProgramError::InvalidArgument)?;
+                Ok(ScriptBuf::new_p2wpkh(&wpubkey_hash))
+            }
+            Self::P2tr => {
+                let secp = Secp256k1::verification_only();
+                let internal_key = XOnlyPublicKey::from(treasury_pubkey.inner);
+                Ok(ScriptBuf::new_p2tr(&secp, internal_key, None))
+            }
+            Self::P2wshMultisig { threshold, pubkeys } => {
+                if *threshold == 0 || usize::from(*threshold) > pubkeys.len() {
+                    msg!("Invalid multisig threshold {} of {}", threshold, pubkeys.len());
+                    return Err(ProgramError::InvalidArgument);
+                }
+                let mut keys = pubkeys
+                    .iter()
+                    .map(|bytes| PublicKey::from_slice(bytes).map_err(|_| ProgramError::InvalidArgument))
+                    .collect::<Result<Vec<_>, _>>()?;
+                keys.sort_by_key(|key| key.to_bytes());
+
+                let mut builder = Builder::new().push_int(i64::from(*threshold));
+                for key in &keys {
+                    builder = builder.push_key(key);
+                }
+                let witness_script = builder
+                    .push_int(keys.len() as i64)
+                    .push_opcode(OP_CHECKMULTISIG)
+                    .into_script();
+                Ok(ScriptBuf::new_p2wsh(&witness_script.wscript_hash()))
+            }
+        }
+    }
+}
+
+/// Multisig keys as a list of hex strings
+#[cfg(feature = "serde")]
+mod hex_pubkeys {
+    use super::TREASURY_MULTISIG_KEYS;
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(keys: &[[u8; 33]; TREASURY_MULTISIG_KEYS], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(keys.iter().map(hex::encode))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<[[u8; 33]; TREASURY_MULTISIG_KEYS], D::Error> {
+        let keys = Vec::<String>::deserialize(deserializer)?
+            .iter()
+            .map(|key| {
+                hex::decode(key)
+                    .map_err(D::Error::custom)?
+                    .try_into()
+                    .map_err(|_| D::Error::custom("expected a 33-byte public key"))
+            })
+            .collect::<Result<Vec<[u8; 33]>, _>>()?;
+        keys.try_into()
+            .map_err(|_| D::Error::custom(format!("expected {} public keys", TREASURY_MULTISIG_KEYS)))
+    }
 }
 
 // Helper to create Bitcoin transactions
@@ -220,13 +803,34 @@ pub fn verify_utxo_ownership(
     Ok(())
 }
 
-pub async fn validate_utxo(
-    rpc: &BitcoinRpcClient,
+/// Node lookups `validate_utxo` relies on, so it can run against the in-memory mock as well
+#[cfg(feature = "client")]
+#[async_trait]
+pub trait UtxoValidationRpc: Send + Sync {
+    async fn get_best_block_hash(&self) -> Result<BlockHash, BitcoinRpcError>;
+
+    /// Confirmations, block height and block hash of a transaction
+    async fn get_tx_block_info(&self, txid: &str) -> Result<(u32, u32, String), BitcoinRpcError>;
+
+    async fn get_utxo_status(&self, utxo: &UtxoMeta) -> Result<UtxoStatus, BitcoinRpcError>;
+
+    /// Fetch a transaction, for checking what a payment paid to
+    async fn get_transaction(&self, txid: &str) -> Result<Transaction, BitcoinRpcError>;
+
+    /// Serialized merkle block proving `txids` are in a block, as `gettxoutproof` returns it
+    async fn get_txout_proof(&self, txids: &[Txid], block_hash: Option<&BlockHash>) -> Result<Vec<u8>, BitcoinRpcError>;
+}
+
+/// Refresh a UTXO's confirmations and block info from the node, then check it against `policy`
+#[cfg(feature = "client")]
+pub async fn validate_utxo<R: UtxoValidationRpc + ?Sized>(
+    rpc: &R,
     utxo: &mut UtxoMeta,
+    policy: &ValidationPolicy,
 ) -> Result<(), ProgramError> {
     // Get current block info
     let best_block_hash = rpc.get_best_block_hash().await
-        .map_err(|_| ProgramError::Custom(ERR_UTXO_VALIDATION))?;
+        .map_err(ProgramError::from)?;
     
     // Check for reorgs if we have previous block info
     if utxo.needs_revalidation(&best_block_hash.to_string()) {
@@ -239,7 +843,7 @@ pub async fn validate_utxo(
 
     // Update confirmations and block info
     let (confirmations, height, hash) = rpc.get_tx_block_info(utxo.txid_str()).await
-        .map_err(|_| ProgramError::Custom(ERR_UTXO_VALIDATION))?;
+        .map_err(ProgramError::from)?;
     
     utxo.confirmations = confirmations;
     if confirmations > 0 {
@@ -248,36 +852,22 @@ pub async fn validate_utxo(
 
     let status = rpc.get_utxo_status(utxo)
         .await
-        .map_err(|_| ProgramError::Custom(ERR_UTXO_VALIDATION))?;
-
-    match status {
-        UtxoStatus::Active => {
-            if utxo.confirmations < 6 {
-                msg!("Insufficient confirmations: {}", utxo.confirmations);
-                return Err(ProgramError::Custom(ERR_INSUFFICIENT_CONFIRMATIONS));
-            }
-        }
-        UtxoStatus::Pending => {
-            msg!("UTXO is still pending");
-            return Err(ProgramError::Custom(ERR_UTXO_STATUS));
-        }
-        UtxoStatus::Spent => {
-            msg!("UTXO is already spent");
-            return Err(ProgramError::Custom(ERR_UTXO_STATUS));
-        }
-        UtxoStatus::Invalid => {
-            msg!("UTXO is invalid");
-            return Err(ProgramError::Custom(ERR_UTXO_STATUS));
-        }
-    }
+        .map_err(ProgramError::from)?;
 
-    Ok(())
+    policy.check(utxo, status)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    const ERR_PAYMENT_MISMATCH: u32 = OVTError::PaymentMismatch.code();
+    const ERR_INVALID_DESTINATION: u32 = OVTError::InvalidPaymentDestination.code();
+    const ERR_INSUFFICIENT_CONFIRMATIONS: u32 = OVTError::InsufficientConfirmations.code();
+    const ERR_UTXO_STATUS: u32 = OVTError::UnexpectedUtxoStatus.code();
+    const ERR_DUST_UTXO: u32 = OVTError::DustUtxo.code();
+    const ERR_UTXO_TOO_OLD: u32 = OVTError::UtxoTooOld.code();
+
     const TEST_TXID: &str = "1234567890abcdef1234567890abcdef1234567890abcdef1234567890abcdef";
     const TEST_VOUT: u32 = 1;
     const TEST_AMOUNT: u64 = 100000;
@@ -361,6 +951,327 @@ mod tests {
         assert!(UtxoMeta::from_bytes(&short_bytes).is_err());
     }
 
+    /// Coinbase of the genesis block
+    const GENESIS_COINBASE_TXID: &str = "4a5e1e4baab89f3a32518a88c31bc87f618f76673e2cc77ab2127b7afdeda33b";
+
+    /// Compressed public keys for the private keys 1, 2 and 3
+    const PUBKEY_1: &str = "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798";
+    const PUBKEY_2: &str = "02c6047f9441ed7d6d3045406e95c07cd85c778e4b8cef3ca7abac09b95c709ee5";
+    const PUBKEY_3: &str = "02f9308a019258c31049344f85f89d5229b531c845836f99b08601f113bce036f9";
+
+    fn pubkey_bytes(hex: &str) -> [u8; 33] {
+        Vec::from_hex(hex).unwrap().try_into().unwrap()
+    }
+
+    fn script_hex(kind: &TreasuryScriptKind, treasury_pubkey: &str) -> String {
+        let pubkey = PublicKey::from_slice(&pubkey_bytes(treasury_pubkey)).unwrap();
+        kind.script_pubkey(&pubkey).unwrap().as_bytes().encode_hex()
+    }
+
+    #[test]
+    fn test_treasury_script_vectors() {
+        assert_eq!(
+            script_hex(&TreasuryScriptKind::P2wpkh, PUBKEY_1),
+            "0014751e76e8199196d454941c45d1b3a323f1433bd6"
+        );
+        // BIP86 output key of the generator point
+        assert_eq!(
+            script_hex(&TreasuryScriptKind::P2tr, PUBKEY_1),
+            "5120da4710964f7852695de2da025290e24af6d8c281de5a0b902b7135fd9fd74d21"
+        );
+        assert_eq!(
+            script_hex(&TreasuryScriptKind::P2tr, PUBKEY_3),
+            "5120418c46636d9e1a683f58e35b42336e776fdcc3b2d4e39e7a0bf1ab0716e3c5fa"
+        );
+
+        // Key order doesn't matter for sorted multisig
+        let multisig = TreasuryScriptKind::P2wshMultisig {
+            threshold: 2,
+            pubkeys: [pubkey_bytes(PUBKEY_3), pubkey_bytes(PUBKEY_1), pubkey_bytes(PUBKEY_2)],
+        };
+        assert_eq!(
+            script_hex(&multisig, PUBKEY_1),
+            "002012c2ffbc6ec1cf5d746dfbd49b1063356212ea55f43023ffc0145934af20c572"
+        );
+    }
+
+    #[test]
+    fn test_invalid_multisig_threshold() {
+        let pubkey = PublicKey::from_slice(&pubkey_bytes(PUBKEY_1)).unwrap();
+        let pubkeys = [pubkey_bytes(PUBKEY_1), pubkey_bytes(PUBKEY_2), pubkey_bytes(PUBKEY_3)];
+        for threshold in [0, 4] {
+            let kind = TreasuryScriptKind::P2wshMultisig { threshold, pubkeys };
+            assert!(matches!(kind.script_pubkey(&pubkey), Err(ProgramError::InvalidArgument)));
+        }
+    }
+
+    #[test]
+    fn test_utxo_key_round_trip() {
+        let key = UtxoKey::new(TEST_TXID, 7);
+        let parsed: UtxoKey = key.to_string().parse().unwrap();
+        assert_eq!(parsed, key);
+
+        let upper = format!("{}:7", TEST_TXID.to_ascii_uppercase());
+        assert_eq!(upper.parse::<UtxoKey>().unwrap(), key);
+        assert_eq!(format!("{}:{}", TEST_TXID, u32::MAX).parse::<UtxoKey>().unwrap().vout, u32::MAX);
+    }
+
+    #[test]
+    fn test_utxo_key_rejects_malformed_strings() {
+        let malformed = [
+            "txid".to_string(),
+            "txid:".to_string(),
+            "txid:abc".to_string(),
+            TEST_TXID.to_string(),
+            format!("{}:", TEST_TXID),
+            format!("{}:abc", TEST_TXID),
+            format!("{}:-1", TEST_TXID),
+            format!("{}:4294967296", TEST_TXID),
+            format!("{}:0", &TEST_TXID[1..]),
+            format!("{}g:0", &TEST_TXID[1..]),
+        ];
+        for s in &malformed {
+            assert!(matches!(s.parse::<UtxoKey>(), Err(ProgramError::InvalidArgument)), "{:?}", s);
+        }
+    }
+
+    #[test]
+    fn test_utxos_sort_by_outpoint() {
+        let low = "1".repeat(64);
+        let high = "f".repeat(64);
+        let mut utxos = vec![
+            UtxoMeta::new(high.clone(), 0, 1),
+            UtxoMeta::new(low.clone(), 2, 1),
+            UtxoMeta::new(low.clone(), 1, 1_000_000),
+        ];
+        utxos.sort();
+
+        let keys: Vec<String> = utxos.iter().map(|utxo| utxo.key().to_string()).collect();
+        assert_eq!(keys, vec![format!("{}:1", low), format!("{}:2", low), format!("{}:0", high)]);
+    }
+
+    #[test]
+    fn test_try_new_dust_boundary() {
+        assert_eq!(
+            UtxoMeta::try_new(TEST_TXID.to_string(), TEST_VOUT, 545),
+            Err(UtxoError::Dust { amount_sats: 545, dust_floor_sats: DEFAULT_DUST_FLOOR_SATS })
+        );
+        let utxo = UtxoMeta::try_new(TEST_TXID.to_string(), TEST_VOUT, 546).unwrap();
+        assert_eq!(utxo.amount_sats, 546);
+
+        assert!(UtxoMeta::try_new(TEST_TXID.to_string(), TEST_VOUT, 0).is_err());
+        assert!(UtxoMeta::try_new_with_dust_floor(TEST_TXID.to_string(), TEST_VOUT, 1, 1).is_ok());
+    }
+
+    #[test]
+    fn test_try_new_max_money_boundary() {
+        let max = Amount::MAX_MONEY.to_sat();
+        assert!(UtxoMeta::try_new(TEST_TXID.to_string(), TEST_VOUT, max).is_ok());
+        assert_eq!(
+            UtxoMeta::try_new(TEST_TXID.to_string(), TEST_VOUT, max + 1),
+            Err(UtxoError::AboveMaxMoney(max + 1))
+        );
+    }
+
+    #[test]
+    fn test_try_new_rejects_bad_outpoints() {
+        assert_eq!(
+            UtxoMeta::try_new("abc".to_string(), TEST_VOUT, TEST_AMOUNT),
+            Err(UtxoError::InvalidTxid("abc".to_string()))
+        );
+        assert_eq!(
+            UtxoMeta::try_new(TEST_TXID.to_string(), u32::MAX, TEST_AMOUNT),
+            Err(UtxoError::InvalidVout(u32::MAX))
+        );
+        assert!(UtxoMeta::try_new(TEST_TXID.to_string(), u32::MAX - 1, TEST_AMOUNT).is_ok());
+
+        // Uppercase txids are normalized rather than rejected
+        let utxo = UtxoMeta::try_new(TEST_TXID.to_ascii_uppercase(), TEST_VOUT, TEST_AMOUNT).unwrap();
+        assert_eq!(utxo.txid, TEST_TXID);
+
+        assert!(matches!(
+            ProgramError::from(UtxoError::Dust { amount_sats: 1, dust_floor_sats: 546 }),
+            ProgramError::Custom(ERR_DUST_UTXO)
+        ));
+    }
+
+    fn payment_tx(outputs: &[(u64, &ScriptBuf)]) -> Transaction {
+        Transaction {
+            version: Version::TWO,
+            lock_time: LockTime::ZERO,
+            input: vec![],
+            output: outputs
+                .iter()
+                .map(|(sats, script)| TxOut { value: Amount::from_sat(*sats), script_pubkey: (*script).clone() })
+                .collect(),
+        }
+    }
+
+    fn treasury_and_change_scripts() -> (ScriptBuf, ScriptBuf) {
+        let treasury = PublicKey::from_slice(&pubkey_bytes(PUBKEY_1)).unwrap();
+        let change = PublicKey::from_slice(&pubkey_bytes(PUBKEY_2)).unwrap();
+        (get_treasury_script_pubkey(&treasury).unwrap(), get_treasury_script_pubkey(&change).unwrap())
+    }
+
+    #[test]
+    fn test_split_treasury_payment() {
+        let (treasury, change) = treasury_and_change_scripts();
+        let tx = payment_tx(&[(60_000, &treasury), (5_000, &change), (40_000, &treasury)]);
+
+        let outpoints = treasury_outputs(&tx, &treasury, Amount::from_sat(100_000), 0).unwrap();
+        let txid = tx.compute_txid();
+        assert_eq!(outpoints, vec![OutPoint::new(txid, 0), OutPoint::new(txid, 2)]);
+    }
+
+    #[test]
+    fn test_treasury_overpayment_is_accepted() {
+        let (treasury, _) = treasury_and_change_scripts();
+        let tx = payment_tx(&[(150_000, &treasury)]);
+        let outpoints = treasury_outputs(&tx, &treasury, Amount::from_sat(100_000), 0).unwrap();
+        assert_eq!(outpoints.len(), 1);
+    }
+
+    #[test]
+    fn test_partial_treasury_payment_fails() {
+        let (treasury, change) = treasury_and_change_scripts();
+        // The change output doesn't count towards the payment
+        let tx = payment_tx(&[(60_000, &treasury), (40_000, &change)]);
+        assert!(matches!(
+            treasury_outputs(&tx, &treasury, Amount::from_sat(100_000), 0),
+            Err(ProgramError::Custom(ERR_PAYMENT_MISMATCH))
+        ));
+
+        // A shortfall within the tolerance is accepted
+        let tx = payment_tx(&[(60_000, &treasury), (39_990, &treasury)]);
+        assert!(treasury_outputs(&tx, &treasury, Amount::from_sat(100_000), 10).is_ok());
+        assert!(treasury_outputs(&tx, &treasury, Amount::from_sat(100_000), 9).is_err());
+
+        let tx = payment_tx(&[(100_000, &change)]);
+        assert!(matches!(
+            treasury_outputs(&tx, &treasury, Amount::from_sat(100_000), 0),
+            Err(ProgramError::Custom(ERR_INVALID_DESTINATION))
+        ));
+    }
+
+    #[test]
+    fn test_regtest_policy_accepts_one_confirmation() {
+        let policy = ValidationPolicy::for_network(Network::Regtest);
+        let mut utxo = UtxoMeta::new(TEST_TXID.to_string(), TEST_VOUT, TEST_AMOUNT);
+        utxo.confirmations = 1;
+        assert!(policy.check(&utxo, UtxoStatus::Active).is_ok());
+
+        utxo.confirmations = 0;
+        assert!(matches!(
+            policy.check(&utxo, UtxoStatus::Active),
+            Err(ProgramError::Custom(ERR_INSUFFICIENT_CONFIRMATIONS))
+        ));
+    }
+
+    #[test]
+    fn test_testnet_policy_requires_three_confirmations() {
+        let policy = ValidationPolicy::for_network(Network::Testnet);
+        assert_eq!(policy.required_confirmations, 3);
+        assert_eq!(ValidationPolicy::for_network(Network::Bitcoin), ValidationPolicy::default());
+
+        let mut utxo = UtxoMeta::new(TEST_TXID.to_string(), TEST_VOUT, TEST_AMOUNT);
+        utxo.confirmations = 2;
+        assert!(matches!(
+            policy.check(&utxo, UtxoStatus::Active),
+            Err(ProgramError::Custom(ERR_INSUFFICIENT_CONFIRMATIONS))
+        ));
+        utxo.confirmations = 3;
+        assert!(policy.check(&utxo, UtxoStatus::Active).is_ok());
+        assert!(matches!(policy.check(&utxo, UtxoStatus::Spent), Err(ProgramError::Custom(ERR_UTXO_STATUS))));
+    }
+
+    #[test]
+    fn test_policy_dust_and_age_limits() {
+        let policy = ValidationPolicy {
+            required_confirmations: 1,
+            dust_limit_sats: 546,
+            max_age_blocks: Some(1_000),
+            ..ValidationPolicy::default()
+        };
+        let mut utxo = UtxoMeta::new(TEST_TXID.to_string(), TEST_VOUT, 545);
+        utxo.confirmations = 10;
+        assert!(matches!(policy.check(&utxo, UtxoStatus::Active), Err(ProgramError::Custom(ERR_DUST_UTXO))));
+
+        utxo.amount_sats = 546;
+        assert!(policy.check(&utxo, UtxoStatus::Active).is_ok());
+
+        utxo.confirmations = 1_001;
+        assert!(matches!(policy.check(&utxo, UtxoStatus::Active), Err(ProgramError::Custom(ERR_UTXO_TOO_OLD))));
+    }
+
+    #[test]
+    fn test_txid_to_bytes_is_display_order() {
+        use bitcoin::hashes::Hash as _;
+
+        let utxo = UtxoMeta::new(GENESIS_COINBASE_TXID.to_string(), 0, 5_000_000_000);
+        let display_bytes = utxo.txid_to_bytes().unwrap();
+        assert_eq!(display_bytes[0], 0x4a);
+        assert_eq!(display_bytes[31], 0x3b);
+
+        let txid = Txid::from_str(GENESIS_COINBASE_TXID).unwrap();
+        let mut internal = txid.to_byte_array();
+        internal.reverse();
+        assert_eq!(display_bytes, internal);
+        assert_eq!(utxo.txid_parsed().unwrap(), txid);
+    }
+
+    #[test]
+    fn test_outpoint_conversions() {
+        let outpoint = OutPoint::new(Txid::from_str(GENESIS_COINBASE_TXID).unwrap(), 3);
+        let utxo = UtxoMeta::from((outpoint, Amount::from_sat(TEST_AMOUNT)));
+        assert_eq!(utxo.txid, GENESIS_COINBASE_TXID);
+        assert_eq!(utxo.vout, 3);
+        assert_eq!(utxo.amount_sats, TEST_AMOUNT);
+        assert_eq!(utxo.outpoint().unwrap(), outpoint);
+
+        let invalid = UtxoMeta::new("not a txid".to_string(), 0, TEST_AMOUNT);
+        assert!(matches!(invalid.outpoint(), Err(ProgramError::InvalidArgument)));
+    }
+
+    #[test]
+    fn test_txid_validation_errors() {
+        let mut short = TEST_TXID.as_bytes().to_vec();
+        short.extend_from_slice(&TEST_VOUT.to_le_bytes());
+        assert!(matches!(UtxoMeta::from_bytes(&short), Err(ProgramError::AccountDataTooSmall)));
+
+        let mut not_hex = "zz".repeat(32).into_bytes();
+        not_hex.extend_from_slice(&TEST_VOUT.to_le_bytes());
+        not_hex.extend_from_slice(&TEST_AMOUNT.to_le_bytes());
+        assert!(matches!(UtxoMeta::from_bytes(&not_hex), Err(ProgramError::InvalidArgument)));
+
+        // The versioned layout is validated too
+        let garbage = UtxoMeta::new("not a txid".to_string(), TEST_VOUT, TEST_AMOUNT);
+        assert!(matches!(UtxoMeta::from_bytes(&garbage.to_bytes()), Err(ProgramError::InvalidArgument)));
+    }
+
+    #[test]
+    fn test_uppercase_txid_is_normalized() {
+        let upper = TEST_TXID.to_ascii_uppercase();
+        let mut bytes = upper.as_bytes().to_vec();
+        bytes.extend_from_slice(&TEST_VOUT.to_le_bytes());
+        bytes.extend_from_slice(&TEST_AMOUNT.to_le_bytes());
+        assert_eq!(UtxoMeta::from_bytes(&bytes).unwrap().txid, TEST_TXID);
+
+        let utxo = UtxoMeta::new(upper, TEST_VOUT, TEST_AMOUNT);
+        assert_eq!(UtxoMeta::from_bytes(&utxo.to_bytes()).unwrap().txid, TEST_TXID);
+        assert_eq!(&utxo.to_header_bytes().unwrap()[..64], TEST_TXID.as_bytes());
+    }
+
+    #[test]
+    fn test_header_bytes_roundtrip() {
+        let utxo = UtxoMeta::new(TEST_TXID.to_string(), TEST_VOUT, TEST_AMOUNT);
+        let bytes = utxo.to_header_bytes().unwrap();
+        assert_eq!(bytes.len(), UTXO_HEADER_LEN);
+        assert_eq!(UtxoMeta::from_bytes(&bytes).unwrap(), utxo);
+
+        let invalid = UtxoMeta::new("1234".to_string(), TEST_VOUT, TEST_AMOUNT);
+        assert!(matches!(invalid.to_header_bytes(), Err(ProgramError::InvalidArgument)));
+    }
+
     #[test]
     fn test_utxo_status_serialization() {
         let status = UtxoStatus::Active;
@@ -398,6 +1309,98 @@ mod tests {
         assert_eq!(deserialized.confirmations, 3);
     }
 
+    #[test]
+    fn test_versioned_layout_roundtrip() {
+        let mut utxo = UtxoMeta::new(TEST_TXID.to_string(), TEST_VOUT, TEST_AMOUNT);
+        utxo.confirmations = 42;
+        utxo.update_block_info(TEST_BLOCK_HEIGHT, TEST_BLOCK_HASH.to_string());
+
+        let bytes = utxo.to_bytes();
+        assert_eq!(bytes[0], UTXO_META_LAYOUT_V2);
+        assert_eq!(UtxoMeta::from_bytes(&bytes).unwrap(), utxo);
+    }
+
+    /// xorshift64*, so failures reproduce from the seed
+    struct TestRng(u64);
+
+    impl TestRng {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 >> 12;
+            self.0 ^= self.0 << 25;
+            self.0 ^= self.0 >> 27;
+            self.0.wrapping_mul(0x2545_f491_4f6c_dd1d)
+        }
+
+        fn hex(&mut self, bytes: usize) -> String {
+            (0..bytes).map(|_| format!("{:02x}", self.next() as u8)).collect()
+        }
+
+        fn utxo(&mut self) -> UtxoMeta {
+            let mut utxo = UtxoMeta::new(self.hex(32), self.next() as u32, self.next() % 2_100_000_000_000_000);
+            utxo.script_pubkey = self.hex((self.next() % 40) as usize);
+            utxo.confirmations = self.next() as u32;
+            if self.next() % 2 == 0 {
+                utxo.block_height = Some(self.next() as u32);
+            }
+            if self.next() % 2 == 0 {
+                utxo.block_hash = Some(self.hex(32));
+            }
+            utxo
+        }
+    }
+
+    #[test]
+    fn test_randomized_roundtrip_keeps_every_field() {
+        let mut rng = TestRng(0x5eed_0f_0757);
+        for _ in 0..500 {
+            let utxo = rng.utxo();
+            assert_eq!(UtxoMeta::from_bytes(&utxo.to_bytes()).unwrap(), utxo);
+
+            // The header layout only keeps the outpoint and amount
+            let header = UtxoMeta::from_bytes(&utxo.to_header_bytes().unwrap()).unwrap();
+            assert_eq!(header, UtxoMeta::new(utxo.txid.clone(), utxo.vout, utxo.amount_sats));
+        }
+    }
+
+    #[test]
+    fn test_v1_layout_is_migrated() {
+        let legacy = LegacyUtxoMeta {
+            txid: TEST_TXID.to_string(),
+            vout: TEST_VOUT,
+            amount_sats: TEST_AMOUNT,
+            script_pubkey: "0014ab".to_string(),
+            confirmations: 7,
+            block_height: Some(TEST_BLOCK_HEIGHT),
+            block_hash: Some(TEST_BLOCK_HASH.to_string()),
+        };
+        let mut bytes = vec![UTXO_META_LAYOUT_V1];
+        bytes.extend(borsh::to_vec(&legacy).unwrap());
+
+        let migrated = UtxoMeta::from_bytes(&bytes).unwrap();
+        assert_eq!(migrated.confirmations, 7);
+        assert_eq!(migrated.script_pubkey, "0014ab");
+        assert_eq!(migrated.block_height, Some(TEST_BLOCK_HEIGHT));
+
+        // Out-of-range counts saturate rather than wrap
+        let huge = LegacyUtxoMeta { confirmations: u64::MAX, ..legacy };
+        assert_eq!(UtxoMeta::from(huge).confirmations, u32::MAX);
+    }
+
+    #[test]
+    fn test_truncated_versioned_layout_is_rejected() {
+        let bytes = UtxoMeta::new(TEST_TXID.to_string(), TEST_VOUT, TEST_AMOUNT).to_bytes();
+        assert!(UtxoMeta::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn test_confirmations_meets() {
+        assert!(Confirmations(6).meets(6));
+        assert!(!Confirmations(5).meets(6));
+        assert!(Confirmations::ZERO.meets(0));
+        assert_eq!(Confirmations::saturating_from(1 << 40), Confirmations(u32::MAX));
+        assert_eq!(u32::from(Confirmations::from(3)), 3);
+    }
+
     #[test]
     fn test_reorg_detection() {
         let mut utxo = UtxoMeta::new(TEST_TXID.to_string(), TEST_VOUT, TEST_AMOUNT);
@@ -427,4 +1430,239 @@ mod tests {
         assert_eq!(deserialized.block_height, Some(TEST_BLOCK_HEIGHT));
         assert_eq!(deserialized.block_hash.as_deref(), Some(TEST_BLOCK_HASH));
     }
-} 
\ No newline at end of file
+
+    #[test]
+    fn test_checked_amount_from_btc() {
+        assert_eq!(checked_amount_from_btc(0.0025), Some(Amount::from_sat(250_000)));
+        assert_eq!(checked_amount_from_btc(0.00000001), Some(Amount::from_sat(1)));
+        assert_eq!(checked_amount_from_btc(21_000_000.0), Some(Amount::MAX_MONEY));
+
+        // esplora/Core values must never be read as sats or accepted out of range
+        assert_eq!(checked_amount_from_btc(21_000_001.0), None);
+        assert_eq!(checked_amount_from_btc(-0.5), None);
+        assert_eq!(checked_amount_from_btc(0.000000001), None);
+        assert_eq!(checked_amount_from_btc(f64::NAN), None);
+    }
+
+    #[test]
+    fn test_checked_amount_from_sat() {
+        assert_eq!(checked_amount_from_sat(2_100_000_000_000_000), Some(Amount::MAX_MONEY));
+        assert_eq!(checked_amount_from_sat(2_100_000_000_000_001), None);
+    }
+
+    #[test]
+    fn test_checked_constructors_reject_above_max_money() {
+        let too_much = Amount::MAX_MONEY + Amount::from_sat(1);
+        assert!(UtxoMeta::with_amount(TEST_TXID.to_string(), TEST_VOUT, too_much).is_err());
+
+        let utxo = UtxoMeta::with_amount(TEST_TXID.to_string(), TEST_VOUT, Amount::from_sat(TEST_AMOUNT)).unwrap();
+        assert_eq!(utxo.amount(), Amount::from_sat(TEST_AMOUNT));
+
+        assert!(TreasuryPayment::new(TEST_TXID.to_string(), too_much, utxo.clone()).is_err());
+        let payment = TreasuryPayment::new(TEST_TXID.to_string(), utxo.amount(), utxo).unwrap();
+        assert_eq!(payment.amount_sats, TEST_AMOUNT);
+    }
+
+    // Node lookups against the in-memory mock, which is only built with the client
+    #[cfg(feature = "client")]
+    mod against_mock {
+        use super::*;
+        use crate::bitcoin::mock::{MockBitcoinNode, MockBitcoinRpcClient};
+
+        const ERR_INVALID_MERKLE_PROOF: u32 = OVTError::InvalidMerkleProof.code();
+        // From `BitcoinRpcError::code`
+        const ERR_RPC_CONNECTION: u32 = 2000;
+        const ERR_RPC_TX_NOT_FOUND: u32 = 2004;
+
+        fn mock_rpc() -> (std::sync::Arc<MockBitcoinNode>, MockBitcoinRpcClient) {
+            let node = std::sync::Arc::new(MockBitcoinNode::new());
+            let client = MockBitcoinRpcClient::new(Default::default(), node.clone());
+            (node, client)
+        }
+
+        fn funding_output() -> Vec<TxOut> {
+            vec![TxOut { value: Amount::from_sat(TEST_AMOUNT), script_pubkey: ScriptBuf::new() }]
+        }
+
+        #[tokio::test]
+        async fn test_validate_utxo_against_mock() {
+            let (node, rpc) = mock_rpc();
+            let policy = ValidationPolicy { required_confirmations: 2, ..ValidationPolicy::default() };
+            node.add_transaction(TEST_TXID, 0, funding_output(), true);
+            let mut utxo = UtxoMeta::new(TEST_TXID.to_string(), 0, TEST_AMOUNT);
+
+            assert!(matches!(validate_utxo(&rpc, &mut utxo, &policy).await, Err(ProgramError::Custom(ERR_UTXO_STATUS))));
+            assert_eq!(utxo.block_hash, None);
+
+            node.mine_blocks(1);
+            assert!(matches!(
+                validate_utxo(&rpc, &mut utxo, &policy).await,
+                Err(ProgramError::Custom(ERR_INSUFFICIENT_CONFIRMATIONS))
+            ));
+
+            node.mine_blocks(1);
+            validate_utxo(&rpc, &mut utxo, &policy).await.unwrap();
+            assert_eq!(utxo.confirmations, 2);
+            assert_eq!(utxo.block_height, Some(1));
+            assert_eq!(utxo.block_hash, node.block_hash(1).map(|hash| hash.to_string()));
+
+            let block = rpc.get_block(&node.block_hash(1).unwrap()).await.unwrap();
+            assert_eq!(block.tx, vec![TEST_TXID.parse::<Txid>().unwrap()]);
+            assert_eq!(block.confirmations, 2);
+
+            node.spend_utxo(TEST_TXID, 0);
+            assert!(matches!(validate_utxo(&rpc, &mut utxo, &policy).await, Err(ProgramError::Custom(ERR_UTXO_STATUS))));
+        }
+
+        #[tokio::test]
+        async fn test_validate_utxo_honors_preset_confirmations() {
+            for (config, required) in [
+                (BitcoinRpcConfig::regtest(), 1),
+                (BitcoinRpcConfig::testnet4(), 3),
+                (BitcoinRpcConfig::signet(), 3),
+                (BitcoinRpcConfig::mainnet(), 6),
+            ] {
+                let node = std::sync::Arc::new(MockBitcoinNode::new());
+                let rpc = MockBitcoinRpcClient::new(config.clone(), node.clone());
+                let policy = ValidationPolicy::from_rpc_config(&config);
+                assert_eq!(policy.required_confirmations, required);
+                node.add_transaction(TEST_TXID, 0, funding_output(), true);
+                let mut utxo = UtxoMeta::new(TEST_TXID.to_string(), 0, TEST_AMOUNT);
+
+                // An unconfirmed output is rejected for its status instead
+                node.mine_blocks(required - 1);
+                if required > 1 {
+                    assert!(matches!(
+                        validate_utxo(&rpc, &mut utxo, &policy).await,
+                        Err(ProgramError::Custom(ERR_INSUFFICIENT_CONFIRMATIONS))
+                    ));
+                }
+                node.mine_blocks(1);
+                validate_utxo(&rpc, &mut utxo, &policy).await.unwrap();
+                assert_eq!(utxo.confirmations, required);
+            }
+        }
+
+        #[tokio::test]
+        async fn test_verify_treasury_payment_against_mock() {
+            let (node, rpc) = mock_rpc();
+            let rpc = rpc.with_strict_outputs();
+            let policy = ValidationPolicy { required_confirmations: 1, ..ValidationPolicy::default() };
+            let (treasury_script, change_script) = treasury_and_change_scripts();
+            let treasury = PublicKey::from_slice(&pubkey_bytes(PUBKEY_1)).unwrap();
+            node.mine_blocks(3);
+            let txid = node.add_payment_transaction(2, payment_tx(&[(60_000, &change_script), (TEST_AMOUNT, &treasury_script)]).output);
+
+            // The UTXO as the node stores it, treasury script included
+            let utxo = node.utxo_meta(&txid.to_string(), 1).unwrap();
+            assert_eq!(utxo.script_pubkey, hex::encode(treasury_script.as_bytes()));
+            let mut payment = TreasuryPayment::new(txid.to_string(), Amount::from_sat(TEST_AMOUNT), utxo).unwrap();
+            let verified = verify_treasury_payment(&rpc, &mut payment, &treasury, &TreasuryScriptKind::P2wpkh, &policy)
+                .await
+                .unwrap();
+            assert_eq!(verified.outpoints, vec![OutPoint::new(txid, 1)]);
+            assert_eq!(verified.memo, None);
+            assert_eq!(payment.utxo.confirmations, 2);
+            // Two deep on a three-block chain
+            assert_eq!(payment.block_height, Some(2));
+
+            // The change output is a real UTXO of the same transaction, but not a treasury payment
+            payment.utxo = node.utxo_meta(&txid.to_string(), 0).unwrap();
+            assert!(matches!(
+                verify_treasury_payment(&rpc, &mut payment, &treasury, &TreasuryScriptKind::P2wpkh, &policy).await,
+                Err(ProgramError::Custom(ERR_INVALID_DESTINATION))
+            ));
+        }
+
+        /// A block holding `txs`, and the `gettxoutproof` answer for `txid` in it
+        fn block_with_proof(txs: Vec<Transaction>, txid: Txid) -> (BlockHash, Vec<u8>) {
+            use bitcoin::block::{Header, Version as BlockVersion};
+            use bitcoin::hashes::Hash;
+            use bitcoin::{CompactTarget, MerkleBlock, TxMerkleNode};
+
+            let header = Header {
+                version: BlockVersion::ONE,
+                prev_blockhash: BlockHash::all_zeros(),
+                merkle_root: TxMerkleNode::all_zeros(),
+                time: 1_700_000_000,
+                bits: CompactTarget::from_consensus(0x207fffff),
+                nonce: 0,
+            };
+            let mut block = Block { header, txdata: txs };
+            block.header.merkle_root = block.compute_merkle_root().unwrap();
+            let proof = MerkleBlock::from_block_with_predicate(&block, |candidate| *candidate == txid);
+            (block.block_hash(), bitcoin::consensus::serialize(&proof))
+        }
+
+        #[tokio::test]
+        async fn test_strict_mode_requires_proof_in_trusted_block() {
+            let (node, rpc) = mock_rpc();
+            let (treasury_script, change_script) = treasury_and_change_scripts();
+            let treasury = PublicKey::from_slice(&pubkey_bytes(PUBKEY_1)).unwrap();
+            node.mine_blocks(3);
+            let tx = payment_tx(&[(TEST_AMOUNT, &treasury_script)]);
+            let txid = node.add_payment_transaction(2, tx.output.clone());
+            let neighbour = payment_tx(&[(60_000, &change_script)]);
+            let (trusted, proof) = block_with_proof(vec![neighbour, tx.clone()], txid);
+
+            let strict = ValidationPolicy { required_confirmations: 1, trusted_block_hash: Some(trusted), ..ValidationPolicy::default() };
+            let verify = |policy: ValidationPolicy| {
+                let mut payment = TreasuryPayment::new(
+                    txid.to_string(),
+                    Amount::from_sat(TEST_AMOUNT),
+                    UtxoMeta::new(txid.to_string(), 0, TEST_AMOUNT),
+                ).unwrap();
+                let rpc = &rpc;
+                async move { verify_treasury_payment(rpc, &mut payment, &treasury, &TreasuryScriptKind::P2wpkh, &policy).await }
+            };
+
+            // The node can't prove the payment yet
+            assert!(matches!(verify(strict).await, Err(ProgramError::Custom(ERR_RPC_TX_NOT_FOUND))));
+            // Nor by passing off a proof for another block as one for the trusted block
+            let (_, other_proof) = block_with_proof(vec![tx], txid);
+            node.set_txout_proof(trusted, txid, other_proof);
+            assert!(matches!(verify(strict).await, Err(ProgramError::Custom(ERR_INVALID_MERKLE_PROOF))));
+
+            node.set_txout_proof(trusted, txid, proof);
+            let verified = verify(strict).await.unwrap();
+            assert_eq!(verified.outpoints, vec![OutPoint::new(txid, 0)]);
+            // Off by default: no proof is asked for
+            assert!(verify(ValidationPolicy { trusted_block_hash: None, ..strict }).await.is_ok());
+        }
+
+        #[tokio::test]
+        async fn test_validate_utxo_after_mock_reorg() {
+            let (node, rpc) = mock_rpc();
+            let policy = ValidationPolicy { required_confirmations: 1, ..ValidationPolicy::default() };
+            node.mine_blocks(5);
+            node.add_transaction(TEST_TXID, 1, funding_output(), true);
+            let mut utxo = UtxoMeta::new(TEST_TXID.to_string(), 0, TEST_AMOUNT);
+            validate_utxo(&rpc, &mut utxo, &policy).await.unwrap();
+            let funding_block = node.block_hash(5).unwrap();
+            assert_eq!(utxo.block_hash, Some(funding_block.to_string()));
+
+            node.simulate_reorg(1);
+            // The rolled-back funding transaction is invalid now, which the node reports as not found
+            assert!(matches!(validate_utxo(&rpc, &mut utxo, &policy).await, Err(ProgramError::Custom(ERR_RPC_TX_NOT_FOUND))));
+            assert_eq!(utxo.block_hash, None);
+            assert_eq!(utxo.confirmations, 0);
+
+            // The old funding block is still known, just off the main chain
+            let stale = rpc.get_block(&funding_block).await.unwrap();
+            assert!(!stale.is_in_main_chain());
+            assert_eq!(node.block_hash(5), None);
+        }
+
+        #[tokio::test]
+        async fn test_validate_utxo_mock_offline() {
+            let (node, rpc) = mock_rpc();
+            node.add_transaction(TEST_TXID, 1, funding_output(), true);
+            node.set_offline(true);
+            let mut utxo = UtxoMeta::new(TEST_TXID.to_string(), 0, TEST_AMOUNT);
+            assert!(matches!(
+                validate_utxo(&rpc, &mut utxo, &ValidationPolicy::default()).await,
+                Err(ProgramError::Custom(ERR_RPC_CONNECTION))
+            ));
+        }
+    }
+}
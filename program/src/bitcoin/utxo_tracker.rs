@@ -1,21 +1,145 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io;
+use std::path::Path;
 use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use async_trait::async_trait;
-use super::utxo::{UtxoMeta, UtxoStatus};
-use crate::bitcoin::rpc::BitcoinRpcClient;
+use bitcoin::{BlockHash, Script};
+use borsh::{BorshDeserialize, BorshSerialize};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use tokio::sync::{broadcast, RwLock};
+use tokio::task::JoinHandle;
+use tokio::time::{sleep_until, Instant};
+use tokio_util::sync::CancellationToken;
+use super::block::BlockInfo;
+use super::metrics::Metrics;
+use super::utxo::{Confirmations, LegacyUtxoMeta, UtxoError, UtxoKey, UtxoMeta, UtxoStatus, DEFAULT_DUST_FLOOR_SATS};
+use crate::bitcoin::error::BitcoinRpcError;
+use crate::bitcoin::rpc::{BitcoinRpcClient, BitcoinRpcConfig};
+use crate::bitcoin::trace;
+use crate::bitcoin::export::{self, ExportFormat, ExportRow, ImportReport};
 use arch_program::msg;
 
+/// Node operations the tracker relies on, so it can run against the in-memory mock as well
+#[async_trait]
+pub trait TrackerRpc: Send + Sync {
+    async fn get_confirmations(&self, txid: &str) -> Result<u32, BitcoinRpcError>;
+
+    /// Height of the node's best block
+    async fn get_block_count(&self) -> Result<u64, BitcoinRpcError>;
+
+    async fn get_utxo_status(&self, utxo: &UtxoMeta) -> Result<UtxoStatus, BitcoinRpcError>;
+
+    async fn get_block(&self, hash: &BlockHash) -> Result<BlockInfo, BitcoinRpcError>;
+
+    async fn get_raw_mempool(&self) -> Result<HashSet<String>, BitcoinRpcError>;
+
+    async fn list_unspent(&self, min_conf: u32, addresses: &[String]) -> Result<Vec<UtxoMeta>, BitcoinRpcError>;
+
+    /// Record a spend in the client's cache, if it has one
+    async fn mark_spent(&self, _txid: &str, _vout: u32) {}
+
+    /// Metrics the tracker's gauges are published to
+    fn tracker_metrics(&self) -> Option<&Metrics> {
+        None
+    }
+}
+
+/// Errors from tracker bookkeeping, as opposed to node lookups
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum TrackerError {
+    #[error("Tracker is full ({max_tracked} UTXOs)")]
+    CapacityExceeded { max_tracked: usize },
+    #[error("Rejected UTXO: {0}")]
+    InvalidUtxo(#[from] UtxoError),
+}
+
+/// Which entries `UtxoTracker::prune` removes; unset criteria remove nothing
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PrunePolicy {
+    /// Remove Spent UTXOs added at least this long ago
+    pub spent_older_than: Option<Duration>,
+    /// Remove UTXOs worth less than this many sats, whatever their status
+    pub dust_below_sats: Option<u64>,
+}
+
+/// Selects tracked UTXOs for `UtxoTracker::query`; every criterion that is
+/// set must hold, and a filter with none set matches everything
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UtxoFilter {
+    /// Match any of these statuses; reserved UTXOs don't match Active
+    pub statuses: Option<HashSet<UtxoStatus>>,
+    /// Hex scriptPubKey the UTXO must pay to
+    pub script_pubkey: Option<String>,
+    pub min_amount_sats: Option<u64>,
+    pub max_amount_sats: Option<u64>,
+    pub min_confirmations: Option<u32>,
+}
+
+impl UtxoFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Also match `status`
+    pub fn with_status(mut self, status: UtxoStatus) -> Self {
+        self.statuses.get_or_insert_with(HashSet::new).insert(status);
+        self
+    }
+
+    pub fn with_script_pubkey(mut self, script_pubkey: &Script) -> Self {
+        self.script_pubkey = Some(hex::encode(script_pubkey.as_bytes()));
+        self
+    }
+
+    /// Amounts from `min_sats` to `max_sats`, both inclusive
+    pub fn with_amount_range(mut self, min_sats: u64, max_sats: u64) -> Self {
+        self.min_amount_sats = Some(min_sats);
+        self.max_amount_sats = Some(max_sats);
+        self
+    }
+
+    pub fn with_min_amount(mut self, sats: u64) -> Self {
+        self.min_amount_sats = Some(sats);
+        self
+    }
+
+    pub fn with_max_amount(mut self, sats: u64) -> Self {
+        self.max_amount_sats = Some(sats);
+        self
+    }
+
+    pub fn with_min_confirmations(mut self, confirmations: u32) -> Self {
+        self.min_confirmations = Some(confirmations);
+        self
+    }
+
+    fn matches(&self, utxo: &UtxoMeta, status: UtxoStatus, reserved: bool) -> bool {
+        self.statuses.as_ref().is_none_or(|statuses| {
+            statuses.contains(&status) && !(status == UtxoStatus::Active && reserved)
+        }) && self.script_pubkey.as_ref().is_none_or(|script| utxo.script_pubkey.eq_ignore_ascii_case(script))
+            && self.min_amount_sats.is_none_or(|min| utxo.amount_sats >= min)
+            && self.max_amount_sats.is_none_or(|max| utxo.amount_sats <= max)
+            && self.min_confirmations.is_none_or(|min| utxo.confirmed().meets(min))
+    }
+}
+
 /// Trait defining the interface for UTXO tracking
 #[async_trait]
 pub trait UtxoTracking {
     /// Add a new UTXO to the tracker with the specified status
-    async fn add_utxo(&mut self, utxo: UtxoMeta, status: UtxoStatus);
+    ///
+    /// Fails if `utxo` doesn't pass `UtxoMeta::check_sanity`, or if the tracker
+    /// is full and `utxo` isn't tracked yet.
+    async fn add_utxo(&mut self, utxo: UtxoMeta, status: UtxoStatus) -> Result<(), TrackerError>;
     
-    /// Get the current status of a UTXO by its txid
-    async fn get_utxo_status(&self, txid: &str) -> Option<UtxoStatus>;
+    /// Get the current status of the UTXO at `txid:vout`
+    async fn get_utxo_status(&self, txid: &str, vout: u32) -> Option<UtxoStatus>;
     
-    /// Mark a UTXO as spent
-    async fn mark_utxo_spent(&mut self, txid: &str);
+    /// Mark the UTXO at `txid:vout` as spent
+    async fn mark_utxo_spent(&mut self, txid: &str, vout: u32);
     
     /// Update the confirmation status of all tracked UTXOs
     async fn update_confirmations(&mut self);
@@ -24,156 +148,2090 @@ pub trait UtxoTracking {
     async fn handle_chain_reorg(&mut self);
 }
 
+/// First bytes of a tracker state file written by `TrackerSnapshot::save_to_file`
+const SNAPSHOT_FILE_MAGIC: &[u8; 4] = b"OVTT";
+/// Bumped whenever `TrackerSnapshot` changes shape
+const SNAPSHOT_FILE_VERSION: u8 = 2;
+/// Snapshots written before `UtxoMeta::confirmations` became a u32
+const SNAPSHOT_FILE_VERSION_V1: u8 = 1;
+
+/// Serializable copy of everything a `UtxoTracker` tracks
+#[derive(Debug, Clone, Default, PartialEq, Eq, BorshSerialize, BorshDeserialize, Serialize, Deserialize)]
+pub struct TrackerSnapshot {
+    pub utxos: Vec<(UtxoMeta, UtxoStatus)>,
+}
+
+impl TrackerSnapshot {
+    /// Write the snapshot to `path` behind a magic header and version byte
+    pub fn save_to_file(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut data = SNAPSHOT_FILE_MAGIC.to_vec();
+        data.push(SNAPSHOT_FILE_VERSION);
+        self.serialize(&mut data)?;
+
+        let path = path.as_ref();
+        let tmp_path = path.with_extension("tmp");
+        fs::write(&tmp_path, data)?;
+        fs::rename(tmp_path, path)
+    }
+
+    /// Read a snapshot written by `save_to_file`
+    ///
+    /// Version 1 files are migrated to the current layout. Files with the
+    /// wrong header, an unknown version or a corrupt body are rejected with
+    /// `InvalidData`.
+    pub fn load_from_file(path: impl AsRef<Path>) -> io::Result<Self> {
+        let invalid = |reason: String| io::Error::new(io::ErrorKind::InvalidData, reason);
+        let data = fs::read(path)?;
+        let body = data
+            .strip_prefix(SNAPSHOT_FILE_MAGIC.as_slice())
+            .ok_or_else(|| invalid("not a UTXO tracker snapshot".to_string()))?;
+        let (version, body) = body
+            .split_first()
+            .ok_or_else(|| invalid("missing snapshot version".to_string()))?;
+        match *version {
+            SNAPSHOT_FILE_VERSION => Self::try_from_slice(body).map_err(|e| invalid(format!("corrupt snapshot: {}", e))),
+            SNAPSHOT_FILE_VERSION_V1 => {
+                let utxos = Vec::<(LegacyUtxoMeta, UtxoStatus)>::try_from_slice(body)
+                    .map_err(|e| invalid(format!("corrupt snapshot: {}", e)))?;
+                Ok(Self {
+                    utxos: utxos.into_iter().map(|(utxo, status)| (utxo.into(), status)).collect(),
+                })
+            }
+            _ => Err(invalid(format!(
+                "unsupported snapshot version {} (expected {})",
+                version, SNAPSHOT_FILE_VERSION
+            ))),
+        }
+    }
+}
+
+/// Reorgs a UTXO may recover from before it is left Invalid for good
+const DEFAULT_MAX_REORG_RECOVERIES: u32 = 3;
+
+/// Reservations whose spend hasn't confirmed by then are released
+const DEFAULT_RESERVATION_TIMEOUT: Duration = Duration::from_secs(3600);
+
+/// Events buffered per subscriber before slow receivers start lagging
+const EVENT_CHANNEL_CAPACITY: usize = 256;
+
+/// A tracked UTXO changed status
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UtxoEvent {
+    pub outpoint: UtxoKey,
+    pub old_status: UtxoStatus,
+    pub new_status: UtxoStatus,
+    /// Confirmations of the funding transaction at the time of the change
+    pub confirmations: u32,
+}
+
+/// An Active UTXO claimed by a spending transaction that hasn't confirmed yet
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reservation {
+    pub spending_txid: String,
+    /// Unix seconds
+    pub since: u64,
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs()
+}
+
+fn index_script(script_index: &mut HashMap<String, HashSet<UtxoKey>>, key: &UtxoKey, utxo: &UtxoMeta) {
+    script_index.entry(utxo.script_pubkey.to_ascii_lowercase()).or_default().insert(key.clone());
+}
+
+fn unindex_script(script_index: &mut HashMap<String, HashSet<UtxoKey>>, key: &UtxoKey, utxo: &UtxoMeta) {
+    let script = utxo.script_pubkey.to_ascii_lowercase();
+    if let Some(keys) = script_index.get_mut(&script) {
+        keys.remove(key);
+        if keys.is_empty() {
+            script_index.remove(&script);
+        }
+    }
+}
+
+/// Number and value of the UTXOs in one status
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StatusSummary {
+    pub count: usize,
+    pub total_sats: u64,
+}
+
+impl StatusSummary {
+    fn add(&mut self, utxo: &UtxoMeta) {
+        self.count += 1;
+        self.total_sats += utxo.amount_sats;
+    }
+}
+
+/// Overview of a tracker's state, as returned by `UtxoTracker::stats`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TrackerStats {
+    /// Active UTXOs that are not reserved
+    pub active: StatusSummary,
+    /// Active UTXOs reserved for an in-flight spend
+    pub reserved: StatusSummary,
+    pub pending: StatusSummary,
+    pub spent: StatusSummary,
+    pub invalid: StatusSummary,
+    /// Seconds since the longest-waiting Pending UTXO was added
+    pub oldest_pending_age_secs: Option<u64>,
+    /// Unix seconds of the last confirmation poll in which every lookup succeeded
+    pub last_confirmation_poll: Option<u64>,
+}
+
+/// Intervals of the background loop started by `UtxoTracker::start_polling`
+#[derive(Debug, Clone)]
+pub struct PollConfig {
+    /// Time between `update_confirmations` passes
+    pub confirmation_interval: Duration,
+    /// Time between `handle_chain_reorg` passes
+    pub reorg_interval: Duration,
+    /// Up to this much random delay is added to every wait, so that several
+    /// trackers don't poll the node in lockstep
+    pub jitter: Duration,
+    /// Upper bound of the backoff applied after consecutive failed cycles
+    pub max_backoff: Duration,
+}
+
+impl Default for PollConfig {
+    fn default() -> Self {
+        Self {
+            confirmation_interval: Duration::from_secs(30),
+            reorg_interval: Duration::from_secs(60),
+            jitter: Duration::from_secs(5),
+            max_backoff: Duration::from_secs(600),
+        }
+    }
+}
+
+impl PollConfig {
+    /// Extra wait after `failures` consecutive failed cycles: the confirmation
+    /// interval doubled per failure, capped at `max_backoff`
+    fn backoff(&self, failures: u32) -> Duration {
+        if failures == 0 {
+            return Duration::ZERO;
+        }
+        let factor = 2u32.saturating_pow(failures.min(16));
+        self.confirmation_interval.saturating_mul(factor).min(self.max_backoff)
+    }
+
+    fn random_jitter(&self) -> Duration {
+        let max_nanos = self.jitter.as_nanos() as u64;
+        if max_nanos == 0 {
+            return Duration::ZERO;
+        }
+        let mut bytes = [0u8; 8];
+        if getrandom::getrandom(&mut bytes).is_err() {
+            return Duration::ZERO;
+        }
+        Duration::from_nanos(u64::from_le_bytes(bytes) % (max_nanos + 1))
+    }
+}
+
+/// What a polling pass did
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct PollOutcome {
+    checked: usize,
+    /// Lookups that failed because the node was unreachable or overloaded
+    failed: usize,
+}
+
+impl std::ops::AddAssign for PollOutcome {
+    fn add_assign(&mut self, other: Self) {
+        self.checked += other.checked;
+        self.failed += other.failed;
+    }
+}
+
+impl PollOutcome {
+    /// Report the pass's counts on its tracing span
+    fn record_on_span(&self) {
+        trace::record("checked", self.checked);
+        trace::record("failed", self.failed);
+    }
+}
+
 /// Implementation of UTXO tracker that maintains state of all UTXOs
-#[derive(Clone)]
-pub struct UtxoTracker {
-    /// Map of txid to (UtxoMeta, UtxoStatus)
-    utxos: Arc<Mutex<HashMap<String, (UtxoMeta, UtxoStatus)>>>,
+pub struct UtxoTracker<R: ?Sized = BitcoinRpcClient> {
+    /// Map of outpoint to (UtxoMeta, UtxoStatus)
+    ///
+    /// Never held across an RPC call: update passes collect their work under
+    /// a short lock, query the node, then apply the results under another.
+    utxos: Arc<RwLock<HashMap<UtxoKey, (UtxoMeta, UtxoStatus)>>>,
+    /// Tracked outpoints by lowercase hex scriptPubKey, so `query` can skip
+    /// the full scan; only changed while the `utxos` write lock is held
+    script_index: Arc<Mutex<HashMap<String, HashSet<UtxoKey>>>>,
     /// Bitcoin RPC client for interacting with the Bitcoin network
-    rpc_client: Arc<BitcoinRpcClient>,
+    rpc_client: Arc<R>,
     /// Minimum confirmations required for a UTXO to be considered active
     min_confirmations: u32,
+    /// Restored Pending/Active UTXOs whose confirmations the next
+    /// `update_confirmations` pass re-checks against the chain
+    reconcile: Arc<Mutex<Vec<UtxoKey>>>,
+    /// Status transitions, see `subscribe`
+    events: broadcast::Sender<UtxoEvent>,
+    /// Active UTXOs set aside for in-flight spends; still Active until the spend confirms
+    reservations: Arc<Mutex<HashMap<UtxoKey, Reservation>>>,
+    reservation_timeout: Duration,
+    /// How often each UTXO was reorganized out (or failed its reorg check)
+    reorg_counts: Arc<Mutex<HashMap<UtxoKey, u32>>>,
+    max_reorg_recoveries: u32,
+    /// Unix seconds at which each UTXO was added (or restored)
+    added_at: Arc<Mutex<HashMap<UtxoKey, u64>>>,
+    last_confirmation_poll: Arc<Mutex<Option<u64>>>,
+    max_tracked: usize,
+    dust_floor_sats: u64,
 }
 
-impl UtxoTracker {
+impl<R: ?Sized> Clone for UtxoTracker<R> {
+    fn clone(&self) -> Self {
+        Self {
+            utxos: self.utxos.clone(),
+            script_index: self.script_index.clone(),
+            rpc_client: self.rpc_client.clone(),
+            min_confirmations: self.min_confirmations,
+            reconcile: self.reconcile.clone(),
+            events: self.events.clone(),
+            reservations: self.reservations.clone(),
+            reservation_timeout: self.reservation_timeout,
+            reorg_counts: self.reorg_counts.clone(),
+            max_reorg_recoveries: self.max_reorg_recoveries,
+            added_at: self.added_at.clone(),
+            last_confirmation_poll: self.last_confirmation_poll.clone(),
+            max_tracked: self.max_tracked,
+            dust_floor_sats: self.dust_floor_sats,
+        }
+    }
+}
+
+impl<R: TrackerRpc + ?Sized> UtxoTracker<R> {
     /// Create a new UTXO tracker with the specified RPC client
-    pub fn new(rpc_client: Arc<BitcoinRpcClient>, min_confirmations: u32) -> Self {
+    pub fn new(rpc_client: Arc<R>, min_confirmations: u32) -> Self {
         Self {
-            utxos: Arc::new(Mutex::new(HashMap::new())),
+            utxos: Arc::new(RwLock::new(HashMap::new())),
+            script_index: Arc::new(Mutex::new(HashMap::new())),
             rpc_client,
             min_confirmations,
+            reconcile: Arc::new(Mutex::new(Vec::new())),
+            events: broadcast::channel(EVENT_CHANNEL_CAPACITY).0,
+            reservations: Arc::new(Mutex::new(HashMap::new())),
+            reservation_timeout: DEFAULT_RESERVATION_TIMEOUT,
+            reorg_counts: Arc::new(Mutex::new(HashMap::new())),
+            max_reorg_recoveries: DEFAULT_MAX_REORG_RECOVERIES,
+            added_at: Arc::new(Mutex::new(HashMap::new())),
+            last_confirmation_poll: Arc::new(Mutex::new(None)),
+            max_tracked: usize::MAX,
+            dust_floor_sats: DEFAULT_DUST_FLOOR_SATS,
+        }
+    }
+
+    /// Create a tracker requiring the confirmations `config` sets for its network
+    pub fn from_rpc_config(rpc_client: Arc<R>, config: &BitcoinRpcConfig) -> Self {
+        Self::new(rpc_client, config.min_confirmations)
+    }
+
+    /// Release reservations whose spend hasn't confirmed after `timeout` (default 1h)
+    pub fn with_reservation_timeout(mut self, timeout: Duration) -> Self {
+        self.reservation_timeout = timeout;
+        self
+    }
+
+    /// Stop returning a UTXO to Active after it has been reorganized out
+    /// more than `max` times (default 3)
+    pub fn with_max_reorg_recoveries(mut self, max: u32) -> Self {
+        self.max_reorg_recoveries = max;
+        self
+    }
+
+    /// Refuse to track more than `max` UTXOs (unbounded by default); see `prune`
+    /// for making room
+    pub fn with_max_tracked(mut self, max: usize) -> Self {
+        self.max_tracked = max;
+        self
+    }
+
+    /// Refuse to track UTXOs worth less than `sats` (546 by default)
+    pub fn with_dust_floor(mut self, sats: u64) -> Self {
+        self.dust_floor_sats = sats;
+        self
+    }
+
+    /// Number of times a UTXO was reorganized out
+    pub fn reorg_count(&self, outpoint: &UtxoKey) -> u32 {
+        self.reorg_counts.lock().unwrap().get(outpoint).copied().unwrap_or(0)
+    }
+
+    fn record_reorg(&self, outpoint: &UtxoKey) {
+        *self.reorg_counts.lock().unwrap().entry(outpoint.clone()).or_insert(0) += 1;
+    }
+
+    /// Re-query UTXOs that a reorg left Invalid and bring back the ones that
+    /// were mined again: Active once they have enough confirmations, Pending
+    /// until then
+    ///
+    /// UTXOs reorganized out more than `max_reorg_recoveries` times stay Invalid.
+    pub async fn recheck_invalid(&self) {
+        self.poll_invalid().await;
+    }
+
+    /// Bring tracked UTXOs in line with a reorg that replaced the blocks from
+    /// `fork_height` up, after which confirmations can go down as well as up
+    ///
+    /// Pending and Active UTXOs confirmed at or above `fork_height` lose their
+    /// block and go back to Pending until `update_confirmations` finds them
+    /// mined again. Deeper ones have their confirmations recounted from their
+    /// block height against the new tip, and Active ones left below
+    /// `min_confirmations` are demoted to Pending. Nothing changes if the tip
+    /// can't be fetched.
+    pub async fn handle_reorg(&self, fork_height: u32) -> Result<(), BitcoinRpcError> {
+        let tip = self.rpc_client.get_block_count().await?;
+        let tip = u32::try_from(tip).unwrap_or(u32::MAX);
+
+        let mut utxos = self.utxos.write().await;
+        for (key, (utxo, status)) in utxos.iter_mut() {
+            if !matches!(status, UtxoStatus::Pending | UtxoStatus::Active) {
+                continue;
+            }
+            match utxo.block_height {
+                Some(height) if height < fork_height && height <= tip => {
+                    self.apply_confirmations(key, utxo, status, tip - height + 1);
+                }
+                Some(_) => {
+                    utxo.confirmations = 0;
+                    utxo.block_height = None;
+                    utxo.block_hash = None;
+                    if *status == UtxoStatus::Active {
+                        self.record_reorg(key);
+                    }
+                    self.transition(key, utxo, status, UtxoStatus::Pending);
+                    msg!("UTXO {} was confirmed at or above fork height {}", key, fork_height);
+                }
+                None => {}
+            }
+        }
+        self.publish_active_count(&utxos);
+        Ok(())
+    }
+
+    async fn poll_invalid(&self) -> PollOutcome {
+        let mut outcome = PollOutcome::default();
+        let candidates: Vec<UtxoMeta> = {
+            let utxos = self.utxos.read().await;
+            let reorg_counts = self.reorg_counts.lock().unwrap();
+            utxos.iter()
+                .filter(|(key, (_, status))| {
+                    let reorgs = reorg_counts.get(*key).copied().unwrap_or(0);
+                    *status == UtxoStatus::Invalid && reorgs > 0 && reorgs <= self.max_reorg_recoveries
+                })
+                .map(|(_, (utxo, _))| utxo.clone())
+                .collect()
+        };
+
+        for utxo in candidates {
+            outcome.checked += 1;
+            let key = utxo.key();
+            let recovered = match self.rpc_client.get_utxo_status(&utxo).await {
+                Ok(UtxoStatus::Active) => match self.rpc_client.get_confirmations(&utxo.txid).await {
+                    Ok(confirmations) if Confirmations(confirmations).meets(self.min_confirmations) => {
+                        Some((UtxoStatus::Active, confirmations))
+                    }
+                    Ok(confirmations) => Some((UtxoStatus::Pending, confirmations)),
+                    Err(e) => {
+                        outcome.failed += usize::from(e.is_retryable());
+                        None
+                    }
+                },
+                Ok(UtxoStatus::Pending) => Some((UtxoStatus::Pending, 0)),
+                Ok(_) => None,
+                Err(e) => {
+                    outcome.failed += usize::from(e.is_retryable());
+                    msg!("Failed to recheck invalid UTXO {}: {:?}", key, e);
+                    None
+                }
+            };
+
+            if let Some((new_status, confirmations)) = recovered {
+                let mut utxos = self.utxos.write().await;
+                if let Some((meta, status)) = utxos.get_mut(&key) {
+                    meta.confirmations = confirmations;
+                    self.transition(&key, meta, status, new_status);
+                    msg!("UTXO {} recovered from reorg as {:?} with {} confirmations", key, new_status, confirmations);
+                }
+                self.publish_active_count(&utxos);
+            }
+        }
+        outcome
+    }
+
+    /// Set an Active UTXO aside for `spending_txid` so it isn't selected or counted again
+    ///
+    /// Returns false if the UTXO isn't tracked as Active or is already reserved.
+    /// The UTXO becomes Spent once `spending_txid` confirms (checked by
+    /// `update_confirmations`).
+    pub async fn reserve(&self, outpoint: &UtxoKey, spending_txid: &str) -> bool {
+        let utxos = self.utxos.read().await;
+        if !matches!(utxos.get(outpoint), Some((_, UtxoStatus::Active))) {
+            return false;
+        }
+
+        let mut reservations = self.reservations.lock().unwrap();
+        if reservations.contains_key(outpoint) {
+            return false;
+        }
+        reservations.insert(outpoint.clone(), Reservation {
+            spending_txid: spending_txid.to_string(),
+            since: unix_now(),
+        });
+        msg!("Reserved UTXO {} for spending transaction {}", outpoint, spending_txid);
+        true
+    }
+
+    /// Give a reserved UTXO back, e.g. because its spending transaction was abandoned
+    pub fn release(&self, outpoint: &UtxoKey) -> Option<Reservation> {
+        self.reservations.lock().unwrap().remove(outpoint)
+    }
+
+    /// Current reservation of a UTXO, if any
+    pub fn reservation(&self, outpoint: &UtxoKey) -> Option<Reservation> {
+        self.reservations.lock().unwrap().get(outpoint).cloned()
+    }
+
+    /// Release expired reservations, then mark UTXOs Spent whose spending transaction confirmed
+    async fn settle_reservations(&self) {
+        let now = unix_now();
+        let timeout = self.reservation_timeout.as_secs();
+        let to_check: Vec<(UtxoKey, String)> = {
+            let mut reservations = self.reservations.lock().unwrap();
+            reservations.retain(|key, reservation| {
+                let expired = now.saturating_sub(reservation.since) >= timeout;
+                if expired {
+                    msg!("Reservation of UTXO {} by {} timed out", key, reservation.spending_txid);
+                }
+                !expired
+            });
+            reservations.iter()
+                .map(|(key, reservation)| (key.clone(), reservation.spending_txid.clone()))
+                .collect()
+        };
+
+        for (key, spending_txid) in to_check {
+            match self.rpc_client.get_confirmations(&spending_txid).await {
+                Ok(confirmations) if confirmations > 0 => {
+                    self.reservations.lock().unwrap().remove(&key);
+                    let mut utxos = self.utxos.write().await;
+                    if let Some((utxo, status)) = utxos.get_mut(&key) {
+                        self.transition(&key, utxo, status, UtxoStatus::Spent);
+                        msg!("UTXO {} spent by confirmed transaction {}", key, spending_txid);
+                    }
+                    self.publish_active_count(&utxos);
+                }
+                // Still unconfirmed, or not relayed yet
+                Ok(_) => {}
+                Err(e) if e.is_not_found() => {}
+                Err(e) => {
+                    msg!("Failed to check spending transaction {} of UTXO {}: {:?}", spending_txid, key, e);
+                }
+            }
+        }
+    }
+
+    /// Receive an event for every status change of a tracked UTXO
+    ///
+    /// Events are emitted by `update_confirmations`, `mark_utxo_spent`,
+    /// `handle_chain_reorg`, `handle_reorg` and `check_pending_replacements`. Receivers that
+    /// fall more than a few hundred events behind see `RecvError::Lagged`.
+    pub fn subscribe(&self) -> broadcast::Receiver<UtxoEvent> {
+        self.events.subscribe()
+    }
+
+    /// Move a tracked UTXO to `new_status`, notifying subscribers if it changed
+    fn transition(&self, key: &UtxoKey, utxo: &UtxoMeta, status: &mut UtxoStatus, new_status: UtxoStatus) {
+        if *status == new_status {
+            return;
+        }
+        let event = UtxoEvent {
+            outpoint: key.clone(),
+            old_status: *status,
+            new_status,
+            confirmations: utxo.confirmations,
+        };
+        *status = new_status;
+        // No subscribers is fine
+        let _ = self.events.send(event);
+    }
+
+    /// Copy of the tracked UTXOs and their statuses, for persisting across restarts
+    pub async fn snapshot(&self) -> TrackerSnapshot {
+        let utxos = self.utxos.read().await;
+        let mut tracked: Vec<(UtxoMeta, UtxoStatus)> = utxos.values().cloned().collect();
+        tracked.sort_by_key(|(utxo, _)| utxo.key());
+        TrackerSnapshot { utxos: tracked }
+    }
+
+    /// Rebuild a tracker from a snapshot
+    ///
+    /// The snapshot may be out of date, so every Pending and Active UTXO is
+    /// queued for the next `update_confirmations` pass, which should be run
+    /// right after restoring. Snapshots don't record when UTXOs were added, so
+    /// restored entries count as added now.
+    pub fn restore(rpc_client: Arc<R>, min_confirmations: u32, snapshot: TrackerSnapshot) -> Self {
+        let now = unix_now();
+        let mut utxos = HashMap::new();
+        let mut added_at = HashMap::new();
+        let mut script_index = HashMap::new();
+        let mut reconcile = Vec::new();
+        for (utxo, status) in snapshot.utxos {
+            let key = utxo.key();
+            added_at.insert(key.clone(), now);
+            index_script(&mut script_index, &key, &utxo);
+            if matches!(status, UtxoStatus::Pending | UtxoStatus::Active) {
+                reconcile.push(key.clone());
+            }
+            utxos.insert(key, (utxo, status));
+        }
+        msg!("Restored {} UTXOs, {} to reconcile", utxos.len(), reconcile.len());
+
+        let tracker = Self::new(rpc_client, min_confirmations);
+        tracker.publish_active_count(&utxos);
+        Self {
+            utxos: Arc::new(RwLock::new(utxos)),
+            script_index: Arc::new(Mutex::new(script_index)),
+            reconcile: Arc::new(Mutex::new(reconcile)),
+            added_at: Arc::new(Mutex::new(added_at)),
+            ..tracker
+        }
+    }
+    
+    /// Persist the tracker's state; see `TrackerSnapshot::save_to_file`
+    pub async fn save_to_file(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        self.snapshot().await.save_to_file(path)
+    }
+
+    /// Write every tracked UTXO to `writer`, sorted by outpoint, e.g. for audits
+    ///
+    /// CSV exports can be read back with `import_csv`.
+    pub async fn export(&self, format: ExportFormat, writer: impl io::Write) -> io::Result<()> {
+        let tracked = self.snapshot().await.utxos;
+        let rows: Vec<ExportRow> = {
+            let added_at = self.added_at.lock().unwrap();
+            tracked
+                .into_iter()
+                .map(|(utxo, status)| ExportRow { added_at: added_at.get(&utxo.key()).copied(), utxo, status })
+                .collect()
+        };
+        export::write_rows(format, &rows, writer)
+    }
+
+    /// Track the UTXOs of a CSV export, skipping and reporting rows that
+    /// don't parse or that `add_utxo` refuses
+    ///
+    /// The header must match `export::CSV_COLUMNS`; a missing or different
+    /// header fails the whole import with `InvalidData`. As with `restore`,
+    /// imported Pending and Active UTXOs are re-checked by the next
+    /// `update_confirmations` pass. Script and block hash aren't exported,
+    /// so imported UTXOs have neither.
+    pub async fn import_csv(&mut self, reader: impl io::BufRead) -> io::Result<ImportReport> {
+        let mut lines = reader.lines();
+        let header = lines.next().transpose()?.unwrap_or_default();
+        if header.trim() != export::CSV_COLUMNS.join(",") {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, format!("unexpected CSV header {:?}", header)));
+        }
+
+        let mut report = ImportReport::default();
+        for (index, line) in lines.enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            // The header was line 1
+            let line_number = index + 2;
+            let row = match export::parse_csv_row(&line) {
+                Ok(row) => row,
+                Err(e) => {
+                    report.errors.push((line_number, e));
+                    continue;
+                }
+            };
+            let key = row.utxo.key();
+            if let Err(e) = self.add_utxo(row.utxo, row.status).await {
+                report.errors.push((line_number, e.into()));
+                continue;
+            }
+            if let Some(added_at) = row.added_at {
+                self.added_at.lock().unwrap().insert(key.clone(), added_at);
+            }
+            if matches!(row.status, UtxoStatus::Pending | UtxoStatus::Active) {
+                self.reconcile.lock().unwrap().push(key);
+            }
+            report.imported += 1;
+        }
+        msg!("Imported {} UTXOs from CSV, {} rows rejected", report.imported, report.errors.len());
+        Ok(report)
+    }
+
+    /// Restore a tracker persisted with `save_to_file`
+    pub fn load_from_file(
+        rpc_client: Arc<R>,
+        min_confirmations: u32,
+        path: impl AsRef<Path>,
+    ) -> io::Result<Self> {
+        let snapshot = TrackerSnapshot::load_from_file(path)?;
+        Ok(Self::restore(rpc_client, min_confirmations, snapshot))
+    }
+
+    /// Publish the current number of Active UTXOs to the client's metrics
+    fn publish_active_count(&self, utxos: &HashMap<UtxoKey, (UtxoMeta, UtxoStatus)>) {
+        let active = utxos.values().filter(|(_, status)| *status == UtxoStatus::Active).count();
+        if let Some(metrics) = self.rpc_client.tracker_metrics() {
+            metrics.set_utxo_tracker_active(active as u64);
+        }
+    }
+    
+    /// Block summary of the block a UTXO was confirmed in, when known and reachable
+    async fn funding_block(&self, utxo: &UtxoMeta) -> Option<BlockInfo> {
+        let hash = BlockHash::from_str(utxo.block_hash.as_deref()?).ok()?;
+        match self.rpc_client.get_block(&hash).await {
+            Ok(block_info) => Some(block_info),
+            Err(e) => {
+                msg!("Failed to fetch funding block {} for UTXO {}: {:?}", hash, utxo.txid, e);
+                None
+            }
         }
     }
     
     /// Get a list of all tracked UTXOs
     pub async fn get_all_utxos(&self) -> Vec<(UtxoMeta, UtxoStatus)> {
-        let utxos = self.utxos.lock().unwrap();
+        let utxos = self.utxos.read().await;
         utxos.values().cloned().collect()
     }
     
-    /// Get all UTXOs with a specific status
+    /// Get all UTXOs with a specific status; reserved UTXOs are not listed as Active
     pub async fn get_utxos_by_status(&self, status: UtxoStatus) -> Vec<UtxoMeta> {
-        let utxos = self.utxos.lock().unwrap();
-        utxos.values()
-            .filter(|(_, s)| *s == status)
-            .map(|(meta, _)| meta.clone())
+        let utxos = self.utxos.read().await;
+        let reservations = self.reservations.lock().unwrap();
+        utxos.iter()
+            .filter(|(key, (_, s))| *s == status && !(status == UtxoStatus::Active && reservations.contains_key(*key)))
+            .map(|(_, (meta, _))| meta.clone())
+            .collect()
+    }
+
+    /// Get all reserved UTXOs with their reservations
+    pub async fn get_reserved_utxos(&self) -> Vec<(UtxoMeta, Reservation)> {
+        let utxos = self.utxos.read().await;
+        let reservations = self.reservations.lock().unwrap();
+        reservations.iter()
+            .filter_map(|(key, reservation)| {
+                utxos.get(key).map(|(meta, _)| (meta.clone(), reservation.clone()))
+            })
             .collect()
     }
     
-    /// Get the total value of all UTXOs with a specific status
+    /// Get the total value of all UTXOs with a specific status; reserved UTXOs don't count as Active
     pub async fn get_total_value_by_status(&self, status: UtxoStatus) -> u64 {
-        let utxos = self.utxos.lock().unwrap();
-        utxos.values()
-            .filter(|(_, s)| *s == status)
-            .map(|(meta, _)| meta.amount_sats)
+        let utxos = self.utxos.read().await;
+        let reservations = self.reservations.lock().unwrap();
+        utxos.iter()
+            .filter(|(key, (_, s))| *s == status && !(status == UtxoStatus::Active && reservations.contains_key(*key)))
+            .map(|(_, (meta, _))| meta.amount_sats)
             .sum()
     }
-}
 
-#[async_trait]
-impl UtxoTracking for UtxoTracker {
-    async fn add_utxo(&mut self, utxo: UtxoMeta, status: UtxoStatus) {
-        let txid = utxo.txid.clone(); // Clone before move
-        let mut utxos = self.utxos.lock().unwrap();
-        utxos.insert(utxo.txid.clone(), (utxo, status));
-        msg!("Added UTXO with txid: {}", txid);
+    /// Tracked UTXOs matching `filter`, sorted by outpoint
+    pub async fn query(&self, filter: UtxoFilter) -> Vec<(UtxoMeta, UtxoStatus)> {
+        let utxos = self.utxos.read().await;
+        let mut matched: Vec<(UtxoMeta, UtxoStatus)> = self.select(&utxos, &filter).into_iter().cloned().collect();
+        matched.sort_by_key(|(utxo, _)| utxo.key());
+        matched
     }
-    
-    async fn get_utxo_status(&self, txid: &str) -> Option<UtxoStatus> {
-        let utxos = self.utxos.lock().unwrap();
-        utxos.get(txid).map(|(_, status)| status.clone())
+
+    /// Number of tracked UTXOs matching `filter`
+    pub async fn count(&self, filter: UtxoFilter) -> usize {
+        let utxos = self.utxos.read().await;
+        self.select(&utxos, &filter).len()
     }
-    
-    async fn mark_utxo_spent(&mut self, txid: &str) {
-        let mut utxos = self.utxos.lock().unwrap();
-        if let Some((_, status)) = utxos.get_mut(txid) {
-            *status = UtxoStatus::Spent;
-            msg!("Marked UTXO as spent: {}", txid);
+
+    /// Total value of the tracked UTXOs matching `filter`
+    pub async fn sum_sats(&self, filter: UtxoFilter) -> u64 {
+        let utxos = self.utxos.read().await;
+        self.select(&utxos, &filter).iter().map(|(utxo, _)| utxo.amount_sats).sum()
+    }
+
+    /// Entries of `utxos` matching `filter`, looked up through the script
+    /// index when the filter names a script
+    fn select<'a>(
+        &self,
+        utxos: &'a HashMap<UtxoKey, (UtxoMeta, UtxoStatus)>,
+        filter: &UtxoFilter,
+    ) -> Vec<&'a (UtxoMeta, UtxoStatus)> {
+        let reservations = self.reservations.lock().unwrap();
+        let matches = |key: &UtxoKey, (utxo, status): &(UtxoMeta, UtxoStatus)| {
+            filter.matches(utxo, *status, reservations.contains_key(key))
+        };
+        match &filter.script_pubkey {
+            Some(script) => {
+                let script_index = self.script_index.lock().unwrap();
+                script_index
+                    .get(&script.to_ascii_lowercase())
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|key| utxos.get_key_value(key))
+                    .filter(|(key, entry)| matches(key, entry))
+                    .map(|(_, entry)| entry)
+                    .collect()
+            }
+            None => utxos.iter().filter(|(key, entry)| matches(key, entry)).map(|(_, entry)| entry).collect(),
         }
     }
-    
-    async fn update_confirmations(&mut self) {
-        let mut utxos_to_update = Vec::new();
-        
+
+    /// Per-status counts and totals, plus how long the oldest Pending UTXO has
+    /// been waiting and when confirmations were last polled successfully
+    pub async fn stats(&self) -> TrackerStats {
+        let now = unix_now();
+        let utxos = self.utxos.read().await;
+        let reservations = self.reservations.lock().unwrap();
+        let added_at = self.added_at.lock().unwrap();
+
+        let mut stats = TrackerStats {
+            last_confirmation_poll: *self.last_confirmation_poll.lock().unwrap(),
+            ..Default::default()
+        };
+        for (key, (utxo, status)) in utxos.iter() {
+            match status {
+                UtxoStatus::Active if reservations.contains_key(key) => stats.reserved.add(utxo),
+                UtxoStatus::Active => stats.active.add(utxo),
+                UtxoStatus::Pending => {
+                    stats.pending.add(utxo);
+                    if let Some(added) = added_at.get(key) {
+                        let age = now.saturating_sub(*added);
+                        stats.oldest_pending_age_secs = stats.oldest_pending_age_secs.max(Some(age));
+                    }
+                }
+                UtxoStatus::Spent => stats.spent.add(utxo),
+                UtxoStatus::Invalid => stats.invalid.add(utxo),
+            }
+        }
+        stats
+    }
+
+    /// Stop tracking the entries selected by `policy`, returning how many were removed
+    ///
+    /// Reserved UTXOs are kept regardless.
+    pub async fn prune(&self, policy: PrunePolicy) -> usize {
+        let now = unix_now();
+        let mut utxos = self.utxos.write().await;
+        let reservations = self.reservations.lock().unwrap();
+        let mut added_at = self.added_at.lock().unwrap();
+        let mut reorg_counts = self.reorg_counts.lock().unwrap();
+        let mut script_index = self.script_index.lock().unwrap();
+
+        let before = utxos.len();
+        utxos.retain(|key, (utxo, status)| {
+            if reservations.contains_key(key) {
+                return true;
+            }
+            let old_spent = policy.spent_older_than.is_some_and(|min_age| {
+                let age = now.saturating_sub(added_at.get(key).copied().unwrap_or(0));
+                *status == UtxoStatus::Spent && age >= min_age.as_secs()
+            });
+            let dust = policy.dust_below_sats.is_some_and(|threshold| utxo.amount_sats < threshold);
+            if old_spent || dust {
+                added_at.remove(key);
+                reorg_counts.remove(key);
+                unindex_script(&mut script_index, key, utxo);
+                return false;
+            }
+            true
+        });
+        self.publish_active_count(&utxos);
+
+        let pruned = before - utxos.len();
+        msg!("Pruned {} UTXOs, {} still tracked", pruned, utxos.len());
+        pruned
+    }
+
+    /// Bootstrap tracking from the node's wallet: every unspent wallet output
+    /// paying `script_pubkey` is added, as Active if it already has enough
+    /// confirmations and Pending otherwise.
+    ///
+    /// UTXOs below the dust floor are skipped, and importing stops early once
+    /// the tracker is full. Returns the number of
+    /// UTXOs imported.
+    pub async fn import_from_script(&mut self, script_pubkey: &Script) -> Result<usize, BitcoinRpcError> {
+        let script_hex = hex::encode(script_pubkey.as_bytes());
+        let unspent = self.rpc_client.list_unspent(0, &[]).await?;
+
+        let mut imported = 0;
+        for utxo in unspent.into_iter().filter(|utxo| utxo.script_pubkey == script_hex) {
+            let status = if utxo.confirmed().meets(self.min_confirmations) {
+                UtxoStatus::Active
+            } else {
+                UtxoStatus::Pending
+            };
+            match self.add_utxo(utxo, status).await {
+                Ok(()) => {}
+                Err(TrackerError::InvalidUtxo(e)) => {
+                    msg!("Skipped UTXO for script {}: {}", script_hex, e);
+                    continue;
+                }
+                Err(e) => {
+                    msg!("Stopped importing UTXOs for script {}: {}", script_hex, e);
+                    break;
+                }
+            }
+            imported += 1;
+        }
+
+        msg!("Imported {} UTXOs for script {}", imported, script_hex);
+        Ok(imported)
+    }
+
+    /// Body of `update_confirmations`, reporting what it did to the polling loop
+    ///
+    /// Entries whose inclusion height is known get their confirmations from
+    /// the tip height, fetched once per pass; only the rest are looked up one
+    /// by one. A transaction reorganized out keeps counting up from its old
+    /// height until `handle_chain_reorg` notices and clears it.
+    #[cfg_attr(feature = "tracing", tracing::instrument(name = "tracker_confirmations", skip_all, fields(checked = tracing::field::Empty, failed = tracing::field::Empty)))]
+    async fn poll_confirmations(&self) -> PollOutcome {
+        let mut outcome = PollOutcome::default();
+        let mut to_query = Vec::new();
+        let mut by_height = Vec::new();
+
         // First, collect UTXOs that need updating to avoid holding the lock during RPC calls
         {
-            let utxos = self.utxos.lock().unwrap();
-            for (txid, (_, status)) in utxos.iter() {
-                if *status == UtxoStatus::Pending {
-                    utxos_to_update.push(txid.clone());
+            let utxos = self.utxos.read().await;
+            // Active UTXOs restored from a snapshot get one refresh as well
+            let reconcile: HashSet<UtxoKey> = self.reconcile.lock().unwrap().drain(..).collect();
+            for (key, (utxo, status)) in utxos.iter() {
+                let must_check = *status == UtxoStatus::Pending
+                    || (*status == UtxoStatus::Active && reconcile.contains(key));
+                match utxo.block_height {
+                    Some(height) if matches!(status, UtxoStatus::Pending | UtxoStatus::Active) => {
+                        by_height.push((key.clone(), height, must_check));
+                    }
+                    None if must_check => to_query.push(key.clone()),
+                    _ => {}
                 }
             }
         }
-        
-        // Now update each UTXO's confirmation status
-        for txid in utxos_to_update {
-            match self.rpc_client.get_confirmations(&txid).await {
+
+        let tip = if by_height.is_empty() && to_query.is_empty() {
+            None
+        } else {
+            match self.rpc_client.get_block_count().await {
+                // Heights past u32 are beyond what UtxoMeta records anyway
+                Ok(tip) => u32::try_from(tip).ok(),
+                Err(e) => {
+                    outcome.failed += usize::from(e.is_retryable());
+                    msg!("Failed to get the tip height: {:?}", e);
+                    None
+                }
+            }
+        };
+
+        let mut computed = Vec::new();
+        for (key, height, must_check) in by_height {
+            match tip.and_then(|tip| tip.checked_sub(height)) {
+                Some(depth) => computed.push((key, depth.saturating_add(1))),
+                // Without a tip at or above the recorded height, ask about the transaction itself
+                None if must_check => to_query.push(key),
+                None => {}
+            }
+        }
+        if !computed.is_empty() {
+            outcome.checked += computed.len();
+            let mut utxos = self.utxos.write().await;
+            for (key, confirmations) in computed {
+                if let Some((utxo, status)) = utxos.get_mut(&key) {
+                    self.apply_confirmations(&key, utxo, status, confirmations);
+                }
+            }
+            self.publish_active_count(&utxos);
+        }
+
+        // Now query the node for the UTXOs still missing a height
+        for key in to_query {
+            outcome.checked += 1;
+            match self.rpc_client.get_confirmations(&key.txid).await {
                 Ok(confirmations) => {
-                    let mut utxos = self.utxos.lock().unwrap();
-                    if let Some((utxo, status)) = utxos.get_mut(&txid) {
-                        // Update the confirmations in the UtxoMeta
-                        utxo.confirmations = confirmations as u64;
-                        
-                        // Update status if needed
-                        if *status == UtxoStatus::Pending && confirmations >= self.min_confirmations {
-                            *status = UtxoStatus::Active;
-                            msg!("UTXO {} is now active with {} confirmations", txid, confirmations);
-                        }
+                    let mut utxos = self.utxos.write().await;
+                    if let Some((utxo, status)) = utxos.get_mut(&key) {
+                        // Record the height the count implies, so later passes needn't ask
+                        utxo.block_height = match confirmations {
+                            0 => None,
+                            _ => tip
+                                .and_then(|tip| tip.checked_add(1)?.checked_sub(confirmations))
+                                .or(utxo.block_height),
+                        };
+                        self.apply_confirmations(&key, utxo, status, confirmations);
                     }
+                    self.publish_active_count(&utxos);
                 },
                 Err(e) => {
-                    msg!("Failed to get confirmations for UTXO {}: {:?}", txid, e);
+                    outcome.failed += usize::from(e.is_retryable());
+                    msg!("Failed to get confirmations for UTXO {}: {:?}", key, e);
                 }
             }
         }
+
+        if outcome.failed == 0 {
+            *self.last_confirmation_poll.lock().unwrap() = Some(unix_now());
+        }
+        self.settle_reservations().await;
+        outcome.record_on_span();
+        outcome
+    }
+
+    /// Store a fresh confirmation count, promoting Pending UTXOs that reach
+    /// `min_confirmations` and demoting Active ones a reorg left below it
+    fn apply_confirmations(&self, key: &UtxoKey, utxo: &mut UtxoMeta, status: &mut UtxoStatus, confirmations: u32) {
+        utxo.confirmations = confirmations;
+        let deep_enough = utxo.confirmed().meets(self.min_confirmations);
+        match *status {
+            UtxoStatus::Pending if deep_enough => {
+                self.transition(key, utxo, status, UtxoStatus::Active);
+                msg!("UTXO {} is now active with {} confirmations", key, confirmations);
+            }
+            UtxoStatus::Active if !deep_enough => {
+                self.transition(key, utxo, status, UtxoStatus::Pending);
+                msg!("UTXO {} is back to pending with {} confirmations", key, confirmations);
+            }
+            _ => {}
+        }
     }
     
-    async fn handle_chain_reorg(&mut self) {
+    /// Body of `handle_chain_reorg`, reporting what it did to the polling loop
+    #[cfg_attr(feature = "tracing", tracing::instrument(name = "tracker_reorgs", skip_all, fields(checked = tracing::field::Empty, failed = tracing::field::Empty)))]
+    async fn poll_reorgs(&self) -> PollOutcome {
+        let mut outcome = PollOutcome::default();
         let mut utxos_to_check = Vec::new();
         let mut utxo_data = Vec::new();
         
         // Collect active UTXOs to check
         {
-            let utxos = self.utxos.lock().unwrap();
-            for (txid, (utxo, status)) in utxos.iter() {
+            let utxos = self.utxos.read().await;
+            for (key, (utxo, status)) in utxos.iter() {
                 if *status == UtxoStatus::Active {
-                    utxos_to_check.push(txid.clone());
+                    utxos_to_check.push(key.clone());
                     utxo_data.push(utxo.clone());
                 }
             }
         }
         
         // Check each active UTXO's status
-        for (txid, utxo) in utxos_to_check.into_iter().zip(utxo_data) {
+        for (key, utxo) in utxos_to_check.into_iter().zip(utxo_data) {
+            outcome.checked += 1;
+            // A funding block that left the main chain puts the UTXO back to
+            // Pending: the transaction may well be mined again
+            if let Some(block_info) = self.funding_block(&utxo).await {
+                if !block_info.is_in_main_chain() {
+                    let mut utxos = self.utxos.write().await;
+                    if let Some((meta, status)) = utxos.get_mut(&key) {
+                        meta.confirmations = 0;
+                        meta.block_height = None;
+                        meta.block_hash = None;
+                        self.transition(&key, meta, status, UtxoStatus::Pending);
+                        self.record_reorg(&key);
+                        msg!("UTXO {} funding block {} was reorganized out", key, block_info.hash);
+                    }
+                    self.publish_active_count(&utxos);
+                    continue;
+                }
+            }
+
             // Get the new status first
             let new_status = match self.rpc_client.get_utxo_status(&utxo).await {
                 Ok(status) => status,
                 Err(e) => {
-                    msg!("Failed to check status for UTXO {}: {:?}", txid, e);
+                    outcome.failed += usize::from(e.is_retryable());
+                    msg!("Failed to check status for UTXO {}: {:?}", key, e);
                     UtxoStatus::Invalid
                 }
             };
             
             // Then update the status if needed
             if new_status != UtxoStatus::Active {
-                let mut utxos = self.utxos.lock().unwrap();
-                if let Some((_, status)) = utxos.get_mut(&txid) {
-                    *status = new_status;
-                    msg!("UTXO {} status changed to {:?} due to chain reorganization", txid, new_status);
+                let mut utxos = self.utxos.write().await;
+                if let Some((meta, status)) = utxos.get_mut(&key) {
+                    self.transition(&key, meta, status, new_status);
+                    if new_status == UtxoStatus::Invalid {
+                        self.record_reorg(&key);
+                    }
+                    msg!("UTXO {} status changed to {:?} due to chain reorganization", key, new_status);
                 }
+                self.publish_active_count(&utxos);
             }
         }
+
+        // UTXOs invalidated by earlier reorgs may have been mined again since
+        outcome += self.poll_invalid().await;
+        outcome.record_on_span();
+        outcome
+    }
+
+    /// Run `update_confirmations` and `handle_chain_reorg` on a background
+    /// task at the intervals in `config` until `cancel` is triggered
+    ///
+    /// After a cycle in which the node couldn't be reached, the next one is
+    /// delayed by an exponential backoff (see `PollConfig::max_backoff`).
+    pub fn start_polling(&self, config: PollConfig, cancel: CancellationToken) -> JoinHandle<()>
+    where
+        R: 'static,
+    {
+        let tracker = self.clone();
+        tokio::spawn(async move {
+            let mut failures = 0u32;
+            let mut next_confirmations = Instant::now();
+            let mut next_reorg = next_confirmations;
+
+            while !cancel.is_cancelled() {
+                let now = Instant::now();
+                let mut outcome = PollOutcome::default();
+                if now >= next_confirmations {
+                    outcome += tracker.poll_confirmations().await;
+                    next_confirmations = now + config.confirmation_interval;
+                }
+                if now >= next_reorg {
+                    outcome += tracker.poll_reorgs().await;
+                    next_reorg = now + config.reorg_interval;
+                }
+
+                failures = if outcome.failed > 0 { failures.saturating_add(1) } else { 0 };
+                let wake = next_confirmations.min(next_reorg).max(now + config.backoff(failures))
+                    + config.random_jitter();
+                msg!(
+                    "UTXO tracker poll: {} checked, {} failed, {} active, next poll in {:?}",
+                    outcome.checked,
+                    outcome.failed,
+                    tracker.get_utxos_by_status(UtxoStatus::Active).await.len(),
+                    wake.saturating_duration_since(Instant::now())
+                );
+
+                tokio::select! {
+                    _ = cancel.cancelled() => break,
+                    _ = sleep_until(wake) => {}
+                }
+            }
+        })
+    }
+
+    /// Invalidate Pending UTXOs whose funding transaction is neither in the
+    /// mempool nor confirmed any more, e.g. because it was replaced via RBF.
+    ///
+    /// Returns the txids that were marked Invalid.
+    pub async fn check_pending_replacements(&mut self) -> Vec<String> {
+        let pending: Vec<String> = {
+            let utxos = self.utxos.read().await;
+            let mut txids: Vec<String> = utxos.iter()
+                .filter(|(_, (_, status))| *status == UtxoStatus::Pending)
+                .map(|(key, _)| key.txid.clone())
+                .collect();
+            // Several tracked outputs can share one funding transaction
+            txids.sort_unstable();
+            txids.dedup();
+            txids
+        };
+
+        if pending.is_empty() {
+            return Vec::new();
+        }
+
+        let mempool = match self.rpc_client.get_raw_mempool().await {
+            Ok(mempool) => mempool,
+            Err(e) => {
+                msg!("Failed to fetch mempool while checking for replacements: {:?}", e);
+                return Vec::new();
+            }
+        };
+
+        let mut vanished = Vec::new();
+        for txid in pending {
+            if mempool.contains(&txid) {
+                continue;
+            }
+
+            // Left the mempool: either it was mined or it was dropped/replaced
+            match self.rpc_client.get_confirmations(&txid).await {
+                Ok(confirmations) if confirmations > 0 => {}
+                Ok(_) => vanished.push(txid),
+                Err(e) if e.is_not_found() => vanished.push(txid),
+                Err(e) => {
+                    msg!("Failed to check funding transaction {}: {:?}", txid, e);
+                }
+            }
+        }
+
+        let mut utxos = self.utxos.write().await;
+        for (key, (utxo, status)) in utxos.iter_mut() {
+            if *status == UtxoStatus::Pending && vanished.contains(&key.txid) {
+                self.transition(key, utxo, status, UtxoStatus::Invalid);
+                msg!("Funding transaction {} vanished from the mempool (replaced or dropped), UTXO {} marked invalid", key.txid, key);
+            }
+        }
+
+        vanished
+    }
+}
+
+#[async_trait]
+impl<R: TrackerRpc + ?Sized> UtxoTracking for UtxoTracker<R> {
+    async fn add_utxo(&mut self, utxo: UtxoMeta, status: UtxoStatus) -> Result<(), TrackerError> {
+        utxo.check_sanity(self.dust_floor_sats)?;
+        let key = utxo.key();
+        let mut utxos = self.utxos.write().await;
+        if utxos.len() >= self.max_tracked && !utxos.contains_key(&key) {
+            return Err(TrackerError::CapacityExceeded { max_tracked: self.max_tracked });
+        }
+        msg!("Added UTXO {}", key);
+        self.added_at.lock().unwrap().insert(key.clone(), unix_now());
+        {
+            let mut script_index = self.script_index.lock().unwrap();
+            if let Some((replaced, _)) = utxos.get(&key) {
+                unindex_script(&mut script_index, &key, replaced);
+            }
+            index_script(&mut script_index, &key, &utxo);
+        }
+        utxos.insert(key, (utxo, status));
+        self.publish_active_count(&utxos);
+        Ok(())
+    }
+    
+    async fn get_utxo_status(&self, txid: &str, vout: u32) -> Option<UtxoStatus> {
+        let utxos = self.utxos.read().await;
+        utxos.get(&UtxoKey::new(txid, vout)).map(|(_, status)| *status)
+    }
+    
+    async fn mark_utxo_spent(&mut self, txid: &str, vout: u32) {
+        let key = UtxoKey::new(txid, vout);
+        let tracked = {
+            let mut utxos = self.utxos.write().await;
+            let tracked = match utxos.get_mut(&key) {
+                Some((utxo, status)) => {
+                    self.transition(&key, utxo, status, UtxoStatus::Spent);
+                    msg!("Marked UTXO as spent: {}", key);
+                    true
+                }
+                None => false,
+            };
+            self.publish_active_count(&utxos);
+            tracked
+        };
+        self.reservations.lock().unwrap().remove(&key);
+
+        // Keep the client's cache from handing the output out again before the node catches up
+        if tracked {
+            self.rpc_client.mark_spent(txid, vout).await;
+        }
+    }
+    
+    async fn update_confirmations(&mut self) {
+        self.poll_confirmations().await;
+    }
+    
+    async fn handle_chain_reorg(&mut self) {
+        self.poll_reorgs().await;
     }
 }
 
 #[cfg(all(test, not(target_arch = "wasm32")))]
 mod tests {
     use super::*;
+    use crate::bitcoin::mock::{MockBitcoinNode, MockBitcoinRpcClient};
+    use crate::bitcoin::export::ImportError;
+    use crate::bitcoin::rpc::BitcoinRpcConfig;
+    use bitcoin::ScriptBuf;
+    use mockito::{mock, server_url, Matcher};
+    use serde_json::json;
+
+    const TXID_KEPT: &str = "a000000000000000000000000000000000000000000000000000000000000000";
+    const TXID_REPLACED: &str = "b000000000000000000000000000000000000000000000000000000000000000";
+
+    fn mock_rpc_client() -> Arc<BitcoinRpcClient> {
+        let address = server_url().replace("http://", "");
+        let (host, port) = address.rsplit_once(':').unwrap();
+        let config = BitcoinRpcConfig {
+            endpoint: host.to_string(),
+            port: port.parse().unwrap(),
+            username: "testuser".to_string(),
+            password: "testpass".to_string(),
+            ..Default::default()
+        };
+        Arc::new(BitcoinRpcClient::new(config).unwrap())
+    }
+
+    /// Mock the `getrawmempool` response for the client's `request_id`-th call
+    fn mock_mempool(request_id: u64, txids: &[&str]) -> mockito::Mock {
+        mock("POST", "/")
+            .match_body(Matcher::PartialJson(json!({ "method": "getrawmempool" })))
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(json!({ "result": txids, "error": null, "id": request_id.to_string() }).to_string())
+            .create()
+    }
     
     #[tokio::test]
     async fn test_utxo_tracker() {
-        // ... existing test code ...
+        let mut tracker = UtxoTracker::new(mock_rpc_client(), 6);
+        let utxo = UtxoMeta::new(TXID_KEPT.to_string(), 0, 10_000);
+
+        tracker.add_utxo(utxo.clone(), UtxoStatus::Pending).await.unwrap();
+        assert_eq!(tracker.get_utxo_status(TXID_KEPT, 0).await, Some(UtxoStatus::Pending));
+        assert_eq!(tracker.get_total_value_by_status(UtxoStatus::Pending).await, 10_000);
+
+        tracker.mark_utxo_spent(TXID_KEPT, 0).await;
+        assert_eq!(tracker.get_utxo_status(TXID_KEPT, 0).await, Some(UtxoStatus::Spent));
     }
-    
-    // ... other test functions ...
-} 
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_outputs_of_one_tx_are_tracked_separately() {
+        let mut tracker = UtxoTracker::new(mock_rpc_client(), 6);
+        tracker.add_utxo(UtxoMeta::new(TXID_KEPT.to_string(), 0, 10_000), UtxoStatus::Active).await.unwrap();
+        tracker.add_utxo(UtxoMeta::new(TXID_KEPT.to_string(), 1, 20_000), UtxoStatus::Active).await.unwrap();
+        assert_eq!(tracker.get_all_utxos().await.len(), 2);
+
+        tracker.mark_utxo_spent(TXID_KEPT, 1).await;
+        assert_eq!(tracker.get_utxo_status(TXID_KEPT, 1).await, Some(UtxoStatus::Spent));
+        assert_eq!(tracker.get_utxo_status(TXID_KEPT, 0).await, Some(UtxoStatus::Active));
+        assert_eq!(tracker.get_total_value_by_status(UtxoStatus::Active).await, 10_000);
+    }
+
+    #[tokio::test]
+    async fn test_pending_to_active_event() {
+        let coinbase = bitcoin::blockdata::constants::genesis_block(bitcoin::Network::Bitcoin).txdata[0].clone();
+        let txid = coinbase.compute_txid().to_string();
+        let mut tracker = UtxoTracker::new(mock_rpc_client(), 6);
+        tracker.add_utxo(UtxoMeta::new(txid.clone(), 0, 10_000), UtxoStatus::Pending).await.unwrap();
+        let mut events = tracker.subscribe();
+
+        // The funding transaction is now 6 blocks deep
+        let _tip = mock("POST", "/")
+            .match_body(Matcher::PartialJson(json!({ "method": "getblockcount", "id": "1" })))
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(json!({ "result": 105, "error": null, "id": "1" }).to_string())
+            .create();
+        let _tx = mock("POST", "/")
+            .match_body(Matcher::PartialJson(json!({ "method": "getrawtransaction", "id": "2" })))
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(json!({ "result": coinbase, "error": null, "id": "2" }).to_string())
+            .create();
+        let _confirmations = mock("POST", "/")
+            .match_body(Matcher::PartialJson(json!({ "method": "gettxconfirmations", "id": "3" })))
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(json!({ "result": 6, "error": null, "id": "3" }).to_string())
+            .create();
+
+        tracker.update_confirmations().await;
+        assert_eq!(events.try_recv().unwrap(), UtxoEvent {
+            outpoint: UtxoKey::new(txid.clone(), 0),
+            old_status: UtxoStatus::Pending,
+            new_status: UtxoStatus::Active,
+            confirmations: 6,
+        });
+        assert!(events.try_recv().is_err());
+        // Mined at 100 if 6 deep at 105
+        assert_eq!(tracker.get_all_utxos().await[0].0.block_height, Some(100));
+
+        // Spending emits one more event; spending again changes nothing
+        tracker.mark_utxo_spent(&txid, 0).await;
+        tracker.mark_utxo_spent(&txid, 0).await;
+        assert_eq!(events.try_recv().unwrap().new_status, UtxoStatus::Spent);
+        assert!(events.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_reserved_utxos_are_not_counted() {
+        let mut tracker = UtxoTracker::new(mock_rpc_client(), 6);
+        let outpoint = UtxoKey::new(TXID_KEPT, 0);
+        tracker.add_utxo(UtxoMeta::new(TXID_KEPT.to_string(), 0, 10_000), UtxoStatus::Active).await.unwrap();
+        tracker.add_utxo(UtxoMeta::new(TXID_KEPT.to_string(), 1, 20_000), UtxoStatus::Active).await.unwrap();
+        tracker.add_utxo(UtxoMeta::new(TXID_REPLACED.to_string(), 0, 30_000), UtxoStatus::Pending).await.unwrap();
+
+        assert!(tracker.reserve(&outpoint, TXID_REPLACED).await);
+        // Already reserved, or not Active
+        assert!(!tracker.reserve(&outpoint, TXID_REPLACED).await);
+        assert!(!tracker.reserve(&UtxoKey::new(TXID_REPLACED, 0), TXID_KEPT).await);
+
+        assert_eq!(tracker.get_utxo_status(TXID_KEPT, 0).await, Some(UtxoStatus::Active));
+        assert_eq!(tracker.get_total_value_by_status(UtxoStatus::Active).await, 20_000);
+        assert_eq!(tracker.get_utxos_by_status(UtxoStatus::Active).await.len(), 1);
+        assert_eq!(tracker.get_reserved_utxos().await.len(), 1);
+
+        assert_eq!(tracker.release(&outpoint).unwrap().spending_txid, TXID_REPLACED);
+        assert_eq!(tracker.get_total_value_by_status(UtxoStatus::Active).await, 30_000);
+    }
+
+    #[tokio::test]
+    async fn test_stale_reservation_is_released() {
+        let mut tracker = UtxoTracker::new(mock_rpc_client(), 6)
+            .with_reservation_timeout(Duration::ZERO);
+        let outpoint = UtxoKey::new(TXID_KEPT, 0);
+        tracker.add_utxo(UtxoMeta::new(TXID_KEPT.to_string(), 0, 10_000), UtxoStatus::Active).await.unwrap();
+        assert!(tracker.reserve(&outpoint, TXID_REPLACED).await);
+
+        // Expired before the spend was ever seen: released without asking the node
+        tracker.update_confirmations().await;
+        assert_eq!(tracker.reservation(&outpoint), None);
+        assert_eq!(tracker.get_total_value_by_status(UtxoStatus::Active).await, 10_000);
+    }
+
+    #[tokio::test]
+    async fn test_confirmed_spend_settles_reservation() {
+        let spend = bitcoin::blockdata::constants::genesis_block(bitcoin::Network::Bitcoin).txdata[0].clone();
+        let spending_txid = spend.compute_txid().to_string();
+        let mut tracker = UtxoTracker::new(mock_rpc_client(), 6);
+        let outpoint = UtxoKey::new(TXID_KEPT, 0);
+        tracker.add_utxo(UtxoMeta::new(TXID_KEPT.to_string(), 0, 10_000), UtxoStatus::Active).await.unwrap();
+        assert!(tracker.reserve(&outpoint, &spending_txid).await);
+
+        let _tx = mock("POST", "/")
+            .match_body(Matcher::PartialJson(json!({ "method": "getrawtransaction", "id": "1" })))
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(json!({ "result": spend, "error": null, "id": "1" }).to_string())
+            .create();
+        let _confirmations = mock("POST", "/")
+            .match_body(Matcher::PartialJson(json!({ "method": "gettxconfirmations", "id": "2" })))
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(json!({ "result": 1, "error": null, "id": "2" }).to_string())
+            .create();
+
+        tracker.update_confirmations().await;
+        assert_eq!(tracker.get_utxo_status(TXID_KEPT, 0).await, Some(UtxoStatus::Spent));
+        assert_eq!(tracker.reservation(&outpoint), None);
+    }
+
+    fn mock_tracker(node: &Arc<MockBitcoinNode>) -> (Arc<MockBitcoinRpcClient>, UtxoTracker<MockBitcoinRpcClient>) {
+        let config = BitcoinRpcConfig::default();
+        let client = Arc::new(MockBitcoinRpcClient::new(config.clone(), node.clone()));
+        (client.clone(), UtxoTracker::from_rpc_config(client, &config))
+    }
+
+    fn polling_config() -> PollConfig {
+        PollConfig {
+            confirmation_interval: Duration::from_secs(30),
+            reorg_interval: Duration::from_secs(60),
+            jitter: Duration::ZERO,
+            max_backoff: Duration::from_secs(600),
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_readers_are_not_blocked_by_updates() {
+        let node = Arc::new(MockBitcoinNode::new());
+        let outputs = vec![bitcoin::TxOut { value: bitcoin::Amount::from_sat(1_000), script_pubkey: ScriptBuf::new() }];
+        let client = Arc::new(
+            MockBitcoinRpcClient::new(BitcoinRpcConfig::default(), node.clone()).with_latency(Duration::from_millis(50)),
+        );
+        let mut tracker = UtxoTracker::new(client, 6);
+        for i in 0..20u8 {
+            let txid = format!("{:02x}{}", i, &TXID_KEPT[2..]);
+            node.add_transaction(&txid, 6, outputs.clone(), true);
+            tracker.add_utxo(UtxoMeta::new(txid, 0, 1_000), UtxoStatus::Active).await.unwrap();
+        }
+
+        // One status lookup per UTXO, ~1s in total
+        let update = {
+            let mut tracker = tracker.clone();
+            tokio::spawn(async move { tracker.handle_chain_reorg().await })
+        };
+
+        let readers: Vec<_> = (0..50)
+            .map(|_| {
+                let tracker = tracker.clone();
+                tokio::spawn(async move {
+                    assert_eq!(tracker.get_all_utxos().await.len(), 20);
+                    assert_eq!(tracker.get_utxos_by_status(UtxoStatus::Active).await.len(), 20);
+                    assert_eq!(tracker.get_total_value_by_status(UtxoStatus::Active).await, 20_000);
+                })
+            })
+            .collect();
+        for reader in readers {
+            reader.await.unwrap();
+        }
+
+        // Every reader got through while the update was still waiting on the node
+        assert!(!update.is_finished());
+        update.await.unwrap();
+        assert_eq!(tracker.get_utxos_by_status(UtxoStatus::Active).await.len(), 20);
+    }
+
+    #[tokio::test]
+    async fn test_capacity_and_pruning() {
+        let node = Arc::new(MockBitcoinNode::new());
+        let (_, tracker) = mock_tracker(&node);
+        // Dust tracked under an older, lower floor can still be pruned
+        let mut tracker = tracker.with_max_tracked(4).with_dust_floor(0);
+        let txid = |i: u8| format!("{:02x}{}", i, &TXID_KEPT[2..]);
+
+        tracker.add_utxo(UtxoMeta::new(txid(0), 0, 546), UtxoStatus::Active).await.unwrap();
+        tracker.add_utxo(UtxoMeta::new(txid(1), 0, 10_000), UtxoStatus::Active).await.unwrap();
+        tracker.add_utxo(UtxoMeta::new(txid(2), 0, 20_000), UtxoStatus::Spent).await.unwrap();
+        tracker.add_utxo(UtxoMeta::new(txid(3), 0, 300), UtxoStatus::Spent).await.unwrap();
+
+        let full = tracker.add_utxo(UtxoMeta::new(txid(4), 0, 50_000), UtxoStatus::Pending).await;
+        assert_eq!(full, Err(TrackerError::CapacityExceeded { max_tracked: 4 }));
+        // Updating an entry that is already tracked still works when full
+        tracker.add_utxo(UtxoMeta::new(txid(1), 0, 10_000), UtxoStatus::Pending).await.unwrap();
+
+        // The dust UTXO is reserved and survives the dust sweep
+        assert!(tracker.reserve(&UtxoKey::new(txid(0), 0), TXID_REPLACED).await);
+        assert_eq!(tracker.prune(PrunePolicy { dust_below_sats: Some(1_000), ..Default::default() }).await, 1);
+        assert_eq!(tracker.get_utxo_status(&txid(3), 0).await, None);
+        assert_eq!(tracker.get_utxo_status(&txid(0), 0).await, Some(UtxoStatus::Active));
+
+        assert_eq!(tracker.prune(PrunePolicy { spent_older_than: Some(Duration::ZERO), ..Default::default() }).await, 1);
+        assert_eq!(tracker.get_utxo_status(&txid(2), 0).await, None);
+        assert_eq!(tracker.get_all_utxos().await.len(), 2);
+
+        // Space was reclaimed
+        tracker.add_utxo(UtxoMeta::new(txid(4), 0, 50_000), UtxoStatus::Pending).await.unwrap();
+        tracker.add_utxo(UtxoMeta::new(txid(5), 0, 60_000), UtxoStatus::Pending).await.unwrap();
+        assert!(tracker.add_utxo(UtxoMeta::new(txid(6), 0, 70_000), UtxoStatus::Pending).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_add_utxo_rejects_insane_utxos() {
+        let node = Arc::new(MockBitcoinNode::new());
+        let (_, mut tracker) = mock_tracker(&node);
+
+        let dust = tracker.add_utxo(UtxoMeta::new(TXID_KEPT.to_string(), 0, 545), UtxoStatus::Active).await;
+        assert_eq!(dust, Err(TrackerError::InvalidUtxo(UtxoError::Dust { amount_sats: 545, dust_floor_sats: 546 })));
+        let null_vout = tracker.add_utxo(UtxoMeta::new(TXID_KEPT.to_string(), u32::MAX, 10_000), UtxoStatus::Active).await;
+        assert_eq!(null_vout, Err(TrackerError::InvalidUtxo(UtxoError::InvalidVout(u32::MAX))));
+        let bad_txid = tracker.add_utxo(UtxoMeta::new("a0".to_string(), 0, 10_000), UtxoStatus::Active).await;
+        assert!(matches!(bad_txid, Err(TrackerError::InvalidUtxo(UtxoError::InvalidTxid(_)))));
+        assert!(tracker.get_all_utxos().await.is_empty());
+
+        tracker.add_utxo(UtxoMeta::new(TXID_KEPT.to_string(), 0, 546), UtxoStatus::Active).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_prune_keeps_recent_spends() {
+        let node = Arc::new(MockBitcoinNode::new());
+        let (_, mut tracker) = mock_tracker(&node);
+        tracker.add_utxo(UtxoMeta::new(TXID_KEPT.to_string(), 0, 10_000), UtxoStatus::Spent).await.unwrap();
+
+        let policy = PrunePolicy { spent_older_than: Some(Duration::from_secs(3600)), ..Default::default() };
+        assert_eq!(tracker.prune(policy).await, 0);
+        assert_eq!(tracker.prune(PrunePolicy::default()).await, 0);
+        assert_eq!(tracker.get_utxo_status(TXID_KEPT, 0).await, Some(UtxoStatus::Spent));
+    }
+
+    #[tokio::test]
+    async fn test_stats() {
+        let node = Arc::new(MockBitcoinNode::new());
+        let (_, mut tracker) = mock_tracker(&node);
+        let txid = |i: u8| format!("{:02x}{}", i, &TXID_KEPT[2..]);
+        let statuses = [
+            (UtxoStatus::Active, 1_000),
+            (UtxoStatus::Active, 2_000),
+            (UtxoStatus::Active, 4_000),
+            (UtxoStatus::Pending, 8_000),
+            (UtxoStatus::Pending, 16_000),
+            (UtxoStatus::Spent, 32_000),
+            (UtxoStatus::Invalid, 64_000),
+        ];
+        for (i, (status, amount)) in statuses.into_iter().enumerate() {
+            tracker.add_utxo(UtxoMeta::new(txid(i as u8), 0, amount), status).await.unwrap();
+        }
+        assert!(tracker.reserve(&UtxoKey::new(txid(2), 0), TXID_REPLACED).await);
+
+        let stats = tracker.stats().await;
+        assert_eq!(stats.active, StatusSummary { count: 2, total_sats: 3_000 });
+        assert_eq!(stats.reserved, StatusSummary { count: 1, total_sats: 4_000 });
+        assert_eq!(stats.pending, StatusSummary { count: 2, total_sats: 24_000 });
+        assert_eq!(stats.spent, StatusSummary { count: 1, total_sats: 32_000 });
+        assert_eq!(stats.invalid, StatusSummary { count: 1, total_sats: 64_000 });
+        assert!(stats.oldest_pending_age_secs.unwrap() < 60);
+        assert_eq!(stats.last_confirmation_poll, None);
+
+        tracker.update_confirmations().await;
+        assert!(tracker.stats().await.last_confirmation_poll.is_some());
+
+        let json = serde_json::to_value(&stats).unwrap();
+        assert_eq!(json["pending"]["total_sats"], 24_000);
+        assert_eq!(json["last_confirmation_poll"], serde_json::Value::Null);
+    }
+
+    #[tokio::test]
+    async fn test_query_filters() {
+        let node = Arc::new(MockBitcoinNode::new());
+        let (_, mut tracker) = mock_tracker(&node);
+        let treasury = ScriptBuf::from_hex("0014751e76e8199196d454941c45d1b3a323f1433bd6").unwrap();
+        let other = ScriptBuf::from_hex("00140000000000000000000000000000000000000000").unwrap();
+        let txid = |i: u8| format!("{:02x}{}", i, &TXID_KEPT[2..]);
+        let entries = [
+            (&treasury, UtxoStatus::Active, 5_000, 10),
+            (&treasury, UtxoStatus::Active, 50_000, 3),
+            (&treasury, UtxoStatus::Active, 80_000, 12),
+            (&treasury, UtxoStatus::Pending, 90_000, 0),
+            (&other, UtxoStatus::Active, 70_000, 20),
+            (&other, UtxoStatus::Spent, 1_000, 30),
+        ];
+        for (i, (script, status, amount, confirmations)) in entries.into_iter().enumerate() {
+            let mut utxo = UtxoMeta::new(txid(i as u8), 0, amount);
+            utxo.script_pubkey = hex::encode(script.as_bytes());
+            utxo.confirmations = confirmations;
+            tracker.add_utxo(utxo, status).await.unwrap();
+        }
+        let amounts = |matched: Vec<(UtxoMeta, UtxoStatus)>| matched.iter().map(|(utxo, _)| utxo.amount_sats).collect::<Vec<_>>();
+
+        assert_eq!(tracker.count(UtxoFilter::new()).await, 6);
+        let active = UtxoFilter::new().with_status(UtxoStatus::Active);
+        assert_eq!(amounts(tracker.query(active.clone()).await), [5_000, 50_000, 80_000, 70_000]);
+        let active_or_pending = active.clone().with_status(UtxoStatus::Pending);
+        assert_eq!(tracker.count(active_or_pending).await, 5);
+        assert_eq!(tracker.count(UtxoFilter::new().with_script_pubkey(&treasury)).await, 4);
+        assert_eq!(tracker.sum_sats(UtxoFilter::new().with_min_amount(70_000)).await, 240_000);
+        assert_eq!(tracker.sum_sats(UtxoFilter::new().with_max_amount(5_000)).await, 6_000);
+        assert_eq!(amounts(tracker.query(UtxoFilter::new().with_amount_range(50_000, 80_000)).await), [50_000, 80_000, 70_000]);
+        assert_eq!(tracker.count(UtxoFilter::new().with_min_confirmations(12)).await, 3);
+
+        // All Active UTXOs paying the treasury over 10k sats
+        let treasury_active = active.with_script_pubkey(&treasury).with_min_amount(10_000);
+        assert_eq!(amounts(tracker.query(treasury_active.clone()).await), [50_000, 80_000]);
+        assert_eq!(tracker.sum_sats(treasury_active.clone().with_min_confirmations(6)).await, 80_000);
+        assert_eq!(tracker.count(treasury_active.clone().with_max_amount(10_000)).await, 0);
+
+        // Reserved UTXOs drop out of Active, as with get_utxos_by_status
+        assert!(tracker.reserve(&UtxoKey::new(txid(2), 0), TXID_REPLACED).await);
+        assert_eq!(tracker.sum_sats(treasury_active).await, 50_000);
+    }
+
+    #[tokio::test]
+    async fn test_script_index_follows_changes() {
+        let node = Arc::new(MockBitcoinNode::new());
+        let (client, mut tracker) = mock_tracker(&node);
+        let treasury = ScriptBuf::from_hex("0014751e76e8199196d454941c45d1b3a323f1433bd6").unwrap();
+        let other = ScriptBuf::from_hex("00140000000000000000000000000000000000000000").unwrap();
+        let paying = |script: &ScriptBuf, amount| {
+            let mut utxo = UtxoMeta::new(TXID_KEPT.to_string(), 0, amount);
+            // Stored scripts may be uppercase; the filter still matches
+            utxo.script_pubkey = hex::encode_upper(script.as_bytes());
+            utxo
+        };
+
+        tracker.add_utxo(paying(&treasury, 10_000), UtxoStatus::Active).await.unwrap();
+        assert_eq!(tracker.count(UtxoFilter::new().with_script_pubkey(&treasury)).await, 1);
+
+        // Re-adding the outpoint with another script moves it in the index
+        tracker.add_utxo(paying(&other, 10_000), UtxoStatus::Active).await.unwrap();
+        assert_eq!(tracker.count(UtxoFilter::new().with_script_pubkey(&treasury)).await, 0);
+        assert_eq!(tracker.count(UtxoFilter::new().with_script_pubkey(&other)).await, 1);
+
+        let restored = UtxoTracker::restore(client, 6, tracker.snapshot().await);
+        assert_eq!(restored.count(UtxoFilter::new().with_script_pubkey(&other)).await, 1);
+
+        assert_eq!(tracker.prune(PrunePolicy { dust_below_sats: Some(20_000), ..Default::default() }).await, 1);
+        assert_eq!(tracker.count(UtxoFilter::new().with_script_pubkey(&other)).await, 0);
+        assert!(tracker.script_index.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_known_heights_skip_per_utxo_lookups() {
+        let node = Arc::new(MockBitcoinNode::new());
+        node.mine_blocks(200);
+        let (client, mut tracker) = mock_tracker(&node);
+        let txid = |i: u32| format!("{:08x}{}", i, &TXID_KEPT[8..]);
+
+        // 100 confirmed UTXOs, 1 to 100 blocks deep, whose heights are known
+        for i in 0..100 {
+            let confirmations = i + 1;
+            node.add_transaction(&txid(i), confirmations, vec![], true);
+            let mut utxo = UtxoMeta::new(txid(i), 0, 10_000);
+            utxo.block_height = Some(200 - i);
+            let status = if confirmations >= 6 { UtxoStatus::Active } else { UtxoStatus::Pending };
+            tracker.add_utxo(utxo, status).await.unwrap();
+        }
+        // and 3 still in the mempool
+        for i in 100..103 {
+            node.add_transaction(&txid(i), 0, vec![], true);
+            node.set_in_next_block(&txid(i), false);
+            tracker.add_utxo(UtxoMeta::new(txid(i), 0, 10_000), UtxoStatus::Pending).await.unwrap();
+        }
+
+        tracker.update_confirmations().await;
+        assert_eq!(client.block_height_calls(), 1);
+        assert_eq!(client.confirmation_calls(), 3);
+        assert_eq!(tracker.count(UtxoFilter::new().with_status(UtxoStatus::Active)).await, 95);
+
+        // Two blocks later the shallow ones are deep enough, still from one tip lookup
+        node.mine_blocks(2);
+        tracker.update_confirmations().await;
+        assert_eq!(client.block_height_calls(), 2);
+        assert_eq!(client.confirmation_calls(), 6);
+        assert_eq!(tracker.count(UtxoFilter::new().with_status(UtxoStatus::Active)).await, 97);
+        let deepest = tracker.query(UtxoFilter::new().with_min_confirmations(102)).await;
+        assert_eq!(deepest.len(), 1);
+        assert_eq!(deepest[0].0.txid, txid(99));
+
+        // Once mined, the last three get a height and stop being looked up
+        node.confirm_mempool_transaction(&txid(100));
+        node.confirm_mempool_transaction(&txid(101));
+        node.confirm_mempool_transaction(&txid(102));
+        tracker.update_confirmations().await;
+        tracker.update_confirmations().await;
+        assert_eq!(client.confirmation_calls(), 9);
+        assert!(tracker.get_all_utxos().await.iter().all(|(utxo, _)| utxo.block_height.is_some()));
+    }
+
+    #[tokio::test]
+    async fn test_failed_poll_keeps_last_confirmation_poll() {
+        let node = Arc::new(MockBitcoinNode::new());
+        let (_, mut tracker) = mock_tracker(&node);
+        tracker.add_utxo(UtxoMeta::new(TXID_KEPT.to_string(), 0, 1_000), UtxoStatus::Pending).await.unwrap();
+
+        node.set_offline(true);
+        tracker.update_confirmations().await;
+        assert_eq!(tracker.stats().await.last_confirmation_poll, None);
+    }
+
+    #[tokio::test]
+    async fn test_tracker_promotes_at_preset_confirmations() {
+        let node = Arc::new(MockBitcoinNode::new());
+        let config = BitcoinRpcConfig::testnet4();
+        let mut tracker = UtxoTracker::from_rpc_config(Arc::new(MockBitcoinRpcClient::new(config.clone(), node.clone())), &config);
+        node.add_transaction(TXID_KEPT, 2, vec![], true);
+        tracker.add_utxo(UtxoMeta::new(TXID_KEPT.to_string(), 0, 1_000), UtxoStatus::Pending).await.unwrap();
+
+        tracker.update_confirmations().await;
+        assert_eq!(tracker.get_utxo_status(TXID_KEPT, 0).await, Some(UtxoStatus::Pending));
+        // testnet counts an output as settled at 3 confirmations, not mainnet's 6
+        node.mine_blocks(1);
+        tracker.update_confirmations().await;
+        assert_eq!(tracker.get_utxo_status(TXID_KEPT, 0).await, Some(UtxoStatus::Active));
+    }
+
+    #[tokio::test]
+    async fn test_reorged_utxo_recovers_when_remined() {
+        let node = Arc::new(MockBitcoinNode::new());
+        let outputs = vec![bitcoin::TxOut { value: bitcoin::Amount::from_sat(10_000), script_pubkey: ScriptBuf::new() }];
+        node.add_transaction(TXID_KEPT, 6, outputs.clone(), true);
+        let (_, mut tracker) = mock_tracker(&node);
+        let outpoint = UtxoKey::new(TXID_KEPT, 0);
+        tracker.add_utxo(UtxoMeta::new(TXID_KEPT.to_string(), 0, 10_000), UtxoStatus::Active).await.unwrap();
+
+        // Reorged out...
+        node.add_transaction(TXID_KEPT, 0, outputs.clone(), false);
+        tracker.handle_chain_reorg().await;
+        assert_eq!(tracker.get_utxo_status(TXID_KEPT, 0).await, Some(UtxoStatus::Invalid));
+        assert_eq!(tracker.reorg_count(&outpoint), 1);
+
+        // ...mined again, still shallow: Pending for now
+        node.add_transaction(TXID_KEPT, 2, outputs.clone(), true);
+        tracker.handle_chain_reorg().await;
+        assert_eq!(tracker.get_utxo_status(TXID_KEPT, 0).await, Some(UtxoStatus::Pending));
+
+        // ...and deep enough again
+        node.set_confirmations(TXID_KEPT, 6);
+        tracker.update_confirmations().await;
+        assert_eq!(tracker.get_utxo_status(TXID_KEPT, 0).await, Some(UtxoStatus::Active));
+    }
+
+    #[tokio::test]
+    async fn test_reorg_demotes_and_repromotes() {
+        let node = Arc::new(MockBitcoinNode::new());
+        node.mine_blocks(110);
+        let outputs = vec![bitcoin::TxOut { value: bitcoin::Amount::from_sat(10_000), script_pubkey: ScriptBuf::new() }];
+        // Mined at 104 and 109
+        node.add_transaction(TXID_KEPT, 7, outputs.clone(), true);
+        node.add_transaction(TXID_REPLACED, 2, outputs, true);
+        let (_, mut tracker) = mock_tracker(&node);
+        for (txid, height) in [(TXID_KEPT, 104), (TXID_REPLACED, 109)] {
+            let mut utxo = UtxoMeta::new(txid.to_string(), 0, 10_000);
+            utxo.block_height = Some(height);
+            tracker.add_utxo(utxo, UtxoStatus::Pending).await.unwrap();
+        }
+        tracker.update_confirmations().await;
+        assert_eq!(tracker.get_utxo_status(TXID_KEPT, 0).await, Some(UtxoStatus::Active));
+        let mut events = tracker.subscribe();
+
+        // Blocks 108 and up are replaced by a single block at 108 that
+        // includes the shallow transaction again
+        node.simulate_reorg_with(2, &[TXID_REPLACED]);
+        tracker.handle_reorg(108).await.unwrap();
+        assert_eq!(events.try_recv().unwrap(), UtxoEvent {
+            outpoint: UtxoKey::new(TXID_KEPT, 0),
+            old_status: UtxoStatus::Active,
+            new_status: UtxoStatus::Pending,
+            confirmations: 5,
+        });
+        assert!(events.try_recv().is_err());
+        let replaced = tracker.query(UtxoFilter::new().with_status(UtxoStatus::Pending)).await;
+        let replaced = replaced.iter().find(|(utxo, _)| utxo.txid == TXID_REPLACED).unwrap();
+        assert_eq!((replaced.0.confirmations, replaced.0.block_height), (0, None));
+
+        // One block later both are counted from their heights again
+        node.mine_blocks(1);
+        tracker.update_confirmations().await;
+        assert_eq!(events.try_recv().unwrap(), UtxoEvent {
+            outpoint: UtxoKey::new(TXID_KEPT, 0),
+            old_status: UtxoStatus::Pending,
+            new_status: UtxoStatus::Active,
+            confirmations: 6,
+        });
+        let all = tracker.get_all_utxos().await;
+        let replaced = all.iter().find(|(utxo, _)| utxo.txid == TXID_REPLACED).unwrap();
+        assert_eq!((replaced.0.confirmations, replaced.0.block_height, replaced.1), (2, Some(108), UtxoStatus::Pending));
+    }
+
+    #[tokio::test]
+    async fn test_repeatedly_reorged_utxo_stays_invalid() {
+        let node = Arc::new(MockBitcoinNode::new());
+        let outputs = vec![bitcoin::TxOut { value: bitcoin::Amount::from_sat(10_000), script_pubkey: ScriptBuf::new() }];
+        node.add_transaction(TXID_KEPT, 6, outputs.clone(), true);
+        let (_, tracker) = mock_tracker(&node);
+        let mut tracker = tracker.with_max_reorg_recoveries(1);
+        tracker.add_utxo(UtxoMeta::new(TXID_KEPT.to_string(), 0, 10_000), UtxoStatus::Active).await.unwrap();
+
+        for round in 1..=2 {
+            node.add_transaction(TXID_KEPT, 0, outputs.clone(), false);
+            tracker.handle_chain_reorg().await;
+            assert_eq!(tracker.reorg_count(&UtxoKey::new(TXID_KEPT, 0)), round);
+
+            node.add_transaction(TXID_KEPT, 6, outputs.clone(), true);
+            tracker.recheck_invalid().await;
+        }
+
+        // Recovered after the first reorg, but not after the second
+        assert_eq!(tracker.get_utxo_status(TXID_KEPT, 0).await, Some(UtxoStatus::Invalid));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_polling_loop_intervals() {
+        let node = Arc::new(MockBitcoinNode::new());
+        node.add_transaction(TXID_KEPT, 1, vec![], true);
+        let (client, mut tracker) = mock_tracker(&node);
+        tracker.add_utxo(UtxoMeta::new(TXID_KEPT.to_string(), 0, 10_000), UtxoStatus::Pending).await.unwrap();
+
+        let cancel = CancellationToken::new();
+        let polling = tracker.start_polling(polling_config(), cancel.clone());
+
+        // Polls at 0s, 30s, 60s and 90s; after the first one the UTXO's
+        // height is known, so only the tip is fetched
+        tokio::time::sleep(Duration::from_secs(95)).await;
+        assert_eq!(client.block_height_calls(), 4);
+        assert_eq!(client.confirmation_calls(), 1);
+
+        // New blocks picked up by the loop promote the UTXO without any manual call
+        node.mine_blocks(5);
+        tokio::time::sleep(Duration::from_secs(30)).await;
+        assert_eq!(tracker.get_utxo_status(TXID_KEPT, 0).await, Some(UtxoStatus::Active));
+
+        cancel.cancel();
+        polling.await.unwrap();
+        let calls = client.block_height_calls();
+        tokio::time::sleep(Duration::from_secs(300)).await;
+        assert_eq!(client.block_height_calls(), calls);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_polling_backs_off_while_node_is_down() {
+        let node = Arc::new(MockBitcoinNode::new());
+        node.add_transaction(TXID_KEPT, 1, vec![], true);
+        node.set_offline(true);
+        let (client, mut tracker) = mock_tracker(&node);
+        tracker.add_utxo(UtxoMeta::new(TXID_KEPT.to_string(), 0, 10_000), UtxoStatus::Pending).await.unwrap();
+
+        let cancel = CancellationToken::new();
+        let polling = tracker.start_polling(polling_config(), cancel.clone());
+
+        // Failed polls at 0s, 60s (2x backoff) and 180s (4x), instead of every 30s
+        tokio::time::sleep(Duration::from_secs(200)).await;
+        assert_eq!(client.confirmation_calls(), 3);
+
+        cancel.cancel();
+        polling.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_round_trip() {
+        let mut tracker = UtxoTracker::new(mock_rpc_client(), 6);
+        let mut active = UtxoMeta::new(TXID_KEPT.to_string(), 0, 10_000);
+        active.confirmations = 8;
+        tracker.add_utxo(active.clone(), UtxoStatus::Active).await.unwrap();
+        tracker.add_utxo(UtxoMeta::new(TXID_KEPT.to_string(), 1, 20_000), UtxoStatus::Spent).await.unwrap();
+        tracker.add_utxo(UtxoMeta::new(TXID_REPLACED.to_string(), 0, 30_000), UtxoStatus::Pending).await.unwrap();
+
+        let path = std::env::temp_dir().join(format!("utxo-tracker-{}.bin", std::process::id()));
+        tracker.snapshot().await.save_to_file(&path).unwrap();
+        let snapshot = TrackerSnapshot::load_from_file(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(snapshot, tracker.snapshot().await);
+
+        let restored = UtxoTracker::restore(mock_rpc_client(), 6, snapshot);
+        assert_eq!(restored.get_utxo_status(TXID_KEPT, 0).await, Some(UtxoStatus::Active));
+        assert_eq!(restored.get_utxo_status(TXID_KEPT, 1).await, Some(UtxoStatus::Spent));
+        assert_eq!(restored.get_utxo_status(TXID_REPLACED, 0).await, Some(UtxoStatus::Pending));
+        assert_eq!(restored.get_total_value_by_status(UtxoStatus::Active).await, 10_000);
+        assert!(restored.get_all_utxos().await.contains(&(active, UtxoStatus::Active)));
+
+        // Only the outpoints that can still change are reconciled
+        let mut reconcile = restored.reconcile.lock().unwrap().clone();
+        reconcile.sort();
+        assert_eq!(reconcile, vec![UtxoKey::new(TXID_KEPT, 0), UtxoKey::new(TXID_REPLACED, 0)]);
+    }
+
+    #[test]
+    fn test_v1_snapshot_is_migrated() {
+        let legacy = LegacyUtxoMeta {
+            txid: TXID_KEPT.to_string(),
+            vout: 1,
+            amount_sats: 10_000,
+            script_pubkey: String::new(),
+            confirmations: 12,
+            block_height: Some(800_000),
+            block_hash: None,
+        };
+        let mut data = SNAPSHOT_FILE_MAGIC.to_vec();
+        data.push(SNAPSHOT_FILE_VERSION_V1);
+        borsh::to_writer(&mut data, &vec![(legacy, UtxoStatus::Active)]).unwrap();
+
+        let path = std::env::temp_dir().join(format!("utxo-tracker-v1-{}.bin", std::process::id()));
+        std::fs::write(&path, data).unwrap();
+        let snapshot = TrackerSnapshot::load_from_file(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let (utxo, status) = &snapshot.utxos[0];
+        assert_eq!(utxo.key(), UtxoKey::new(TXID_KEPT, 1));
+        assert_eq!(utxo.confirmations, 12);
+        assert_eq!(utxo.block_height, Some(800_000));
+        assert_eq!(*status, UtxoStatus::Active);
+    }
+
+    #[test]
+    fn test_corrupt_snapshot_is_rejected() {
+        let path = std::env::temp_dir().join(format!("utxo-tracker-corrupt-{}.bin", std::process::id()));
+        std::fs::write(&path, b"OVTT\x01\xff\xff\xff\xff").unwrap();
+        let result = TrackerSnapshot::load_from_file(&path);
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::InvalidData);
+
+        std::fs::write(&path, b"not a snapshot").unwrap();
+        let result = TrackerSnapshot::load_from_file(&path);
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::InvalidData);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_check_pending_replacements() {
+        let mut tracker = UtxoTracker::new(mock_rpc_client(), 6);
+        tracker.add_utxo(UtxoMeta::new(TXID_KEPT.to_string(), 0, 10_000), UtxoStatus::Pending).await.unwrap();
+        tracker.add_utxo(UtxoMeta::new(TXID_REPLACED.to_string(), 0, 20_000), UtxoStatus::Pending).await.unwrap();
+
+        // First poll: both funding transactions are still in the mempool
+        let mempool = mock_mempool(1, &[TXID_KEPT, TXID_REPLACED]);
+        assert!(tracker.check_pending_replacements().await.is_empty());
+        assert_eq!(tracker.get_utxo_status(TXID_REPLACED, 0).await, Some(UtxoStatus::Pending));
+        drop(mempool);
+
+        // Second poll: one of them was replaced and is gone from the node entirely
+        let _mempool = mock_mempool(2, &[TXID_KEPT]);
+        let _not_found = mock("POST", "/")
+            .match_body(Matcher::PartialJson(json!({ "method": "getrawtransaction" })))
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(json!({
+                "result": null,
+                "error": { "code": -5, "message": "No such mempool or blockchain transaction" },
+                "id": "3"
+            }).to_string())
+            .create();
+
+        let vanished = tracker.check_pending_replacements().await;
+        assert_eq!(vanished, vec![TXID_REPLACED.to_string()]);
+        assert_eq!(tracker.get_utxo_status(TXID_REPLACED, 0).await, Some(UtxoStatus::Invalid));
+        assert_eq!(tracker.get_utxo_status(TXID_KEPT, 0).await, Some(UtxoStatus::Pending));
+    }
+
+    #[tokio::test]
+    async fn test_rbf_replacement_on_mock_node() {
+        let node = Arc::new(MockBitcoinNode::new());
+        let (_, mut tracker) = mock_tracker(&node);
+        let outputs = vec![bitcoin::TxOut { value: bitcoin::Amount::from_sat(20_000), script_pubkey: ScriptBuf::new() }];
+        node.add_mempool_transaction(TXID_KEPT, outputs.clone(), 500, false);
+        node.add_mempool_transaction(TXID_REPLACED, outputs.clone(), 500, true);
+        tracker.add_utxo(UtxoMeta::new(TXID_KEPT.to_string(), 0, 20_000), UtxoStatus::Pending).await.unwrap();
+        tracker.add_utxo(UtxoMeta::new(TXID_REPLACED.to_string(), 0, 20_000), UtxoStatus::Pending).await.unwrap();
+        assert!(tracker.check_pending_replacements().await.is_empty());
+
+        // Only the replaceable one can be bumped, and only with a higher fee
+        let bumped = "c000000000000000000000000000000000000000000000000000000000000000";
+        assert!(node.replace_transaction(TXID_KEPT, bumped, outputs.clone(), 1_000, true).is_err());
+        assert!(node.replace_transaction(TXID_REPLACED, bumped, outputs.clone(), 500, true).is_err());
+        node.replace_transaction(TXID_REPLACED, bumped, outputs, 1_000, true).unwrap();
+
+        assert_eq!(tracker.check_pending_replacements().await, vec![TXID_REPLACED.to_string()]);
+        assert_eq!(tracker.get_utxo_status(TXID_REPLACED, 0).await, Some(UtxoStatus::Invalid));
+        assert_eq!(tracker.get_utxo_status(TXID_KEPT, 0).await, Some(UtxoStatus::Pending));
+
+        // A mined funding transaction has left the mempool without vanishing
+        assert!(node.confirm_mempool_transaction(TXID_KEPT));
+        assert!(tracker.check_pending_replacements().await.is_empty());
+        assert_eq!(tracker.get_utxo_status(TXID_KEPT, 0).await, Some(UtxoStatus::Pending));
+    }
+
+    #[tokio::test]
+    async fn test_import_from_script() {
+        let treasury_script = ScriptBuf::from_hex("0014751e76e8199196d454941c45d1b3a323f1433bd6").unwrap();
+        let _unspent = mock("POST", "/")
+            .match_body(Matcher::PartialJson(json!({ "method": "listunspent" })))
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(json!({
+                "result": [
+                    { "txid": TXID_KEPT, "vout": 0, "scriptPubKey": "0014751e76e8199196d454941c45d1b3a323f1433bd6", "amount": 0.001, "confirmations": 10 },
+                    { "txid": TXID_REPLACED, "vout": 1, "scriptPubKey": "0014751e76e8199196d454941c45d1b3a323f1433bd6", "amount": 0.002, "confirmations": 2 },
+                    { "txid": TXID_REPLACED, "vout": 2, "scriptPubKey": "00140000000000000000000000000000000000000000", "amount": 0.5, "confirmations": 2 }
+                ],
+                "error": null,
+                "id": "1"
+            }).to_string())
+            .create();
+
+        let mut tracker = UtxoTracker::new(mock_rpc_client(), 6);
+        let imported = tracker.import_from_script(&treasury_script).await.unwrap();
+
+        assert_eq!(imported, 2);
+        assert_eq!(tracker.get_utxo_status(TXID_KEPT, 0).await, Some(UtxoStatus::Active));
+        assert_eq!(tracker.get_utxo_status(TXID_REPLACED, 0).await, Some(UtxoStatus::Pending));
+        assert_eq!(tracker.get_total_value_by_status(UtxoStatus::Active).await, 100_000);
+    }
+
+    #[tokio::test]
+    async fn test_reorg_returns_utxo_to_pending() {
+        let mut tracker = UtxoTracker::new(mock_rpc_client(), 6);
+        let mut utxo = UtxoMeta::new(TXID_KEPT.to_string(), 0, 10_000);
+        utxo.update_block_info(102, "3b1f0e6c42f1bd1d2a7e5a3c0f4e2f4b6d8a9c0e1f2a3b4c5d6e7f8091a2b3c4".to_string());
+        tracker.add_utxo(utxo, UtxoStatus::Active).await.unwrap();
+
+        // The node reports the funding block as no longer on the main chain
+        let _stale_block = mock("POST", "/")
+            .match_body(Matcher::PartialJson(json!({ "method": "getblock" })))
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(include_str!("../../tests/fixtures/getblock_verbose_stale_regtest.json"))
+            .create();
+
+        tracker.handle_chain_reorg().await;
+        assert_eq!(tracker.get_utxo_status(TXID_KEPT, 0).await, Some(UtxoStatus::Pending));
+        assert_eq!(tracker.get_total_value_by_status(UtxoStatus::Active).await, 0);
+    }
+
+    async fn export_tracker() -> UtxoTracker<MockBitcoinRpcClient> {
+        let node = Arc::new(MockBitcoinNode::new());
+        let (_, mut tracker) = mock_tracker(&node);
+        let mut confirmed = UtxoMeta::new(TXID_KEPT.to_string(), 1, 50_000);
+        confirmed.confirmations = 12;
+        confirmed.block_height = Some(800_000);
+        tracker.add_utxo(confirmed, UtxoStatus::Active).await.unwrap();
+        tracker.add_utxo(UtxoMeta::new(TXID_REPLACED.to_string(), 0, 7_000), UtxoStatus::Pending).await.unwrap();
+        tracker.add_utxo(UtxoMeta::new(TXID_KEPT.to_string(), 0, 1_000), UtxoStatus::Spent).await.unwrap();
+        tracker.added_at.lock().unwrap().insert(UtxoKey::new(TXID_KEPT, 1), 1_709_294_400);
+        tracker
+    }
+
+    #[tokio::test]
+    async fn test_export_formats() {
+        let tracker = export_tracker().await;
+        let mut csv = Vec::new();
+        tracker.export(ExportFormat::Csv, &mut csv).await.unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], "txid,vout,amount_sats,status,confirmations,block_height,added_at");
+        // Rows come out in outpoint order
+        let spent = format!("{},0,1000,spent,0,,", TXID_KEPT);
+        let added_now = lines[1].strip_prefix(spent.as_str()).unwrap();
+        assert!(export::parse_rfc3339(added_now).is_some(), "{}", added_now);
+        assert_eq!(lines[2], format!("{},1,50000,active,12,800000,2024-03-01T12:00:00Z", TXID_KEPT));
+        assert!(lines[3].starts_with(&format!("{},0,7000,pending,0,,", TXID_REPLACED)));
+
+        let mut jsonl = Vec::new();
+        tracker.export(ExportFormat::JsonLines, &mut jsonl).await.unwrap();
+        let rows: Vec<serde_json::Value> = String::from_utf8(jsonl)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(rows.len(), 3);
+        assert_eq!(
+            rows[1],
+            json!({
+                "txid": TXID_KEPT,
+                "vout": 1,
+                "amount_sats": 50_000,
+                "status": "active",
+                "confirmations": 12,
+                "block_height": 800_000,
+                "added_at": "2024-03-01T12:00:00Z",
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_csv_round_trip() {
+        let original = export_tracker().await;
+        let mut csv = Vec::new();
+        original.export(ExportFormat::Csv, &mut csv).await.unwrap();
+
+        let node = Arc::new(MockBitcoinNode::new());
+        let (_, mut imported) = mock_tracker(&node);
+        let report = imported.import_csv(csv.as_slice()).await.unwrap();
+        assert_eq!(report, ImportReport { imported: 3, errors: Vec::new() });
+        assert_eq!(imported.snapshot().await, original.snapshot().await);
+        assert_eq!(
+            imported.added_at.lock().unwrap().get(&UtxoKey::new(TXID_KEPT, 1)),
+            Some(&1_709_294_400)
+        );
+        // Pending and Active rows are re-checked like restored ones
+        assert_eq!(imported.reconcile.lock().unwrap().len(), 2);
+
+        let mut again = Vec::new();
+        imported.export(ExportFormat::Csv, &mut again).await.unwrap();
+        assert_eq!(again, csv);
+    }
+
+    #[tokio::test]
+    async fn test_csv_import_reports_bad_rows() {
+        let node = Arc::new(MockBitcoinNode::new());
+        let (_, tracker) = mock_tracker(&node);
+        let mut tracker = tracker.with_max_tracked(2);
+        let csv = format!(
+            "txid,vout,amount_sats,status,confirmations,block_height,added_at\n\
+             {kept},0,10000,active,6,800000,2024-03-01T12:00:00Z\n\
+             {kept},1,10000,active\n\
+             nothex,0,10000,active,6,,\n\
+             {kept},2,100,pending,0,,\n\
+             {kept},3,10000,unknown,0,,\n\
+             {kept},4,10000,pending,0,,yesterday\n\
+             \n\
+             {replaced},0,20000,pending,0,,\n\
+             {replaced},1,20000,pending,0,,\n",
+            kept = TXID_KEPT,
+            replaced = TXID_REPLACED,
+        );
+        let report = tracker.import_csv(csv.as_bytes()).await.unwrap();
+        assert_eq!(report.imported, 2);
+        assert_eq!(
+            report.errors,
+            vec![
+                (3, ImportError::ColumnCount { expected: 7, found: 4 }),
+                (4, ImportError::InvalidUtxo(UtxoError::InvalidTxid("nothex".to_string()))),
+                (5, ImportError::InvalidUtxo(UtxoError::Dust { amount_sats: 100, dust_floor_sats: DEFAULT_DUST_FLOOR_SATS })),
+                (6, ImportError::InvalidField { column: "status", value: "unknown".to_string() }),
+                (7, ImportError::InvalidField { column: "added_at", value: "yesterday".to_string() }),
+                (10, ImportError::Rejected(TrackerError::CapacityExceeded { max_tracked: 2 })),
+            ]
+        );
+        assert_eq!(tracker.get_utxo_status(TXID_REPLACED, 0).await, Some(UtxoStatus::Pending));
+
+        // Without the expected header nothing is imported
+        let headless = format!("{},0,10000,active,6,,\n", TXID_KEPT);
+        let error = tracker.import_csv(headless.as_bytes()).await.unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+}
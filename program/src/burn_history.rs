@@ -0,0 +1,238 @@
+use arch_program::{msg, program_error::ProgramError};
+use borsh::{BorshDeserialize, BorshSerialize};
+
+use crate::error::OVTError;
+use crate::state::OVTState;
+
+/// Burns the history account keeps; the oldest is overwritten once it is full
+pub const BURN_HISTORY_CAPACITY: usize = 64;
+
+/// One BuybackBurn, with what auditors need to reconcile it against the treasury
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct BurnRecord {
+    /// The treasury payment, in the byte order of its hex txid
+    pub payment_txid: [u8; 32],
+    pub payment_amount_sats: u64,
+    pub tokens_burned: u64,
+    /// NAV the burn was priced at
+    pub nav_sats: u64,
+    /// `total_supply` after the burn
+    pub supply_after: u64,
+}
+
+impl BurnRecord {
+    /// Serialized size of a record
+    pub const LEN: usize = 32 + 4 * 8;
+
+    /// The record of burning `tokens_burned` for `payment_txid`, leaving `state`
+    ///
+    /// Fails with `InvalidBitcoinTransaction` unless `payment_txid` is 64 hex characters.
+    pub fn for_burn(
+        payment_txid: &str,
+        payment_amount_sats: u64,
+        tokens_burned: u64,
+        state: &OVTState,
+    ) -> Result<Self, ProgramError> {
+        Ok(Self {
+            payment_txid: parse_payment_txid(payment_txid)?,
+            payment_amount_sats,
+            tokens_burned,
+            nav_sats: state.nav_sats,
+            supply_after: state.total_supply,
+        })
+    }
+
+    pub fn payment_txid_hex(&self) -> String {
+        hex::encode(self.payment_txid)
+    }
+}
+
+/// The bytes of a hex txid, in the same order; `InvalidBitcoinTransaction`
+/// unless it is 64 hex characters
+pub fn parse_payment_txid(payment_txid: &str) -> Result<[u8; 32], ProgramError> {
+    hex::decode(payment_txid)
+        .ok()
+        .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok())
+        .ok_or_else(|| {
+            msg!("Payment txid {:?} is not 32 hex-encoded bytes", payment_txid);
+            ProgramError::from(OVTError::InvalidBitcoinTransaction)
+        })
+}
+
+/// The latest `BURN_HISTORY_CAPACITY` burns, as kept in the burn history account
+///
+/// Old payments drop out of it, so what stops a payment being burned for
+/// twice is its spent-payment account (`spent_payment_address`), which
+/// BuybackBurn creates and which is never removed.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct BurnHistory {
+    /// Burns recorded so far, including overwritten ones
+    pub total_burns: u64,
+    /// A ring buffer: once full, `total_burns % BURN_HISTORY_CAPACITY` is the oldest
+    records: Vec<BurnRecord>,
+}
+
+impl BurnHistory {
+    /// Bytes a full history takes, which the account must be allocated with
+    pub const LEN: usize = 8 + 4 + BURN_HISTORY_CAPACITY * BurnRecord::LEN;
+
+    /// The history in an account's data; empty or zeroed data is an empty history
+    pub fn unpack(data: &[u8]) -> Result<Self, ProgramError> {
+        if data.is_empty() {
+            return Ok(Self::default());
+        }
+        // Borsh reads the records it needs and leaves the unused tail alone
+        let history = Self::deserialize(&mut &data[..]).map_err(|_| ProgramError::InvalidAccountData)?;
+        if history.records.len() > BURN_HISTORY_CAPACITY {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        Ok(history)
+    }
+
+    /// Write the history to the start of `dst`
+    pub fn pack_into(&self, dst: &mut [u8]) -> Result<(), ProgramError> {
+        let data = borsh::to_vec(self).map_err(|_| ProgramError::InvalidAccountData)?;
+        if dst.len() < data.len() {
+            msg!("Burn history account holds {} bytes, needs {}", dst.len(), data.len());
+            return Err(ProgramError::AccountDataTooSmall);
+        }
+        dst[..data.len()].copy_from_slice(&data);
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+
+    /// The kept records, oldest first
+    pub fn records(&self) -> Vec<BurnRecord> {
+        let mut records = self.records.clone();
+        if records.len() == BURN_HISTORY_CAPACITY {
+            records.rotate_left((self.total_burns % BURN_HISTORY_CAPACITY as u64) as usize);
+        }
+        records
+    }
+
+    pub fn contains_payment(&self, payment_txid: &[u8; 32]) -> bool {
+        self.records.iter().any(|record| record.payment_txid == *payment_txid)
+    }
+
+    /// Add `record`, overwriting the oldest once full; `DuplicatePayment` if
+    /// its payment is already recorded
+    pub fn append(&mut self, record: BurnRecord) -> Result<(), ProgramError> {
+        if self.contains_payment(&record.payment_txid) {
+            msg!("Payment {} has already been burned for", record.payment_txid_hex());
+            return Err(OVTError::DuplicatePayment.into());
+        }
+        if self.records.len() < BURN_HISTORY_CAPACITY {
+            self.records.push(record);
+        } else {
+            let oldest = (self.total_burns % BURN_HISTORY_CAPACITY as u64) as usize;
+            self.records[oldest] = record;
+        }
+        self.total_burns += 1;
+        Ok(())
+    }
+}
+
+/// The records in a burn history account's data, oldest first, for clients
+/// reading the account or the log of `OVTInstruction::GetBurnHistory`
+pub fn decode_burn_history(data: &[u8]) -> Result<Vec<BurnRecord>, ProgramError> {
+    Ok(BurnHistory::unpack(data)?.records())
+}
+
+/// The line GetBurnHistory logs for each record
+pub fn burn_record_event(record: &BurnRecord) -> String {
+    format!(
+        "BurnRecord: payment {} for {} sats burned {} at NAV {}, supply {}",
+        record.payment_txid_hex(),
+        record.payment_amount_sats,
+        record.tokens_burned,
+        record.nav_sats,
+        record.supply_after
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(i: u8) -> BurnRecord {
+        BurnRecord {
+            payment_txid: [i; 32],
+            payment_amount_sats: u64::from(i) * 1_000,
+            tokens_burned: u64::from(i),
+            nav_sats: 1_000_000,
+            supply_after: 1_000_000 - u64::from(i),
+        }
+    }
+
+    #[test]
+    fn test_record_for_burn() {
+        let mut state = OVTState::new([2; 33]);
+        state.nav_sats = 1_000_000;
+        state.total_supply = 900_000;
+        let record = BurnRecord::for_burn(&"ab".repeat(32), 100_000, 100_000, &state).unwrap();
+        assert_eq!(record.payment_txid, [0xab; 32]);
+        assert_eq!((record.nav_sats, record.supply_after), (1_000_000, 900_000));
+        assert_eq!(borsh::to_vec(&record).unwrap().len(), BurnRecord::LEN);
+
+        for bad in ["txid123", "ab"] {
+            assert!(matches!(
+                BurnRecord::for_burn(bad, 100_000, 100_000, &state),
+                Err(ProgramError::Custom(code)) if code == OVTError::InvalidBitcoinTransaction.code()
+            ));
+        }
+    }
+
+    #[test]
+    fn test_ring_buffer_keeps_latest() {
+        let mut history = BurnHistory::default();
+        for i in 0..BURN_HISTORY_CAPACITY as u8 + 3 {
+            history.append(record(i)).unwrap();
+        }
+        let mut data = vec![0; BurnHistory::LEN];
+        history.pack_into(&mut data).unwrap();
+
+        let records = decode_burn_history(&data).unwrap();
+        assert_eq!(records.len(), BURN_HISTORY_CAPACITY);
+        assert_eq!(records.first(), Some(&record(3)));
+        assert_eq!(records.last(), Some(&record(BURN_HISTORY_CAPACITY as u8 + 2)));
+        assert_eq!(BurnHistory::unpack(&data).unwrap().total_burns, BURN_HISTORY_CAPACITY as u64 + 3);
+
+        // Overwritten payments are forgotten
+        assert!(!history.contains_payment(&[0; 32]));
+        assert!(history.contains_payment(&[3; 32]));
+    }
+
+    #[test]
+    fn test_duplicate_payment_is_rejected() {
+        let mut history = BurnHistory::default();
+        history.append(record(1)).unwrap();
+        let again = BurnRecord { payment_amount_sats: 5, ..record(1) };
+        assert!(matches!(
+            history.append(again),
+            Err(ProgramError::Custom(code)) if code == OVTError::DuplicatePayment.code()
+        ));
+        assert_eq!((history.len(), history.total_burns), (1, 1));
+    }
+
+    #[test]
+    fn test_blank_and_short_accounts() {
+        assert!(decode_burn_history(&[]).unwrap().is_empty());
+        assert!(decode_burn_history(&[0; BurnHistory::LEN]).unwrap().is_empty());
+
+        let mut history = BurnHistory::default();
+        history.append(record(1)).unwrap();
+        let mut short = vec![0; 8 + 4 + BurnRecord::LEN - 1];
+        assert!(matches!(history.pack_into(&mut short), Err(ProgramError::AccountDataTooSmall)));
+        // A record count the account can't hold
+        let mut corrupt = vec![0; BurnHistory::LEN];
+        corrupt[8..12].copy_from_slice(&(BURN_HISTORY_CAPACITY as u32 + 1).to_le_bytes());
+        assert!(matches!(decode_burn_history(&corrupt), Err(ProgramError::InvalidAccountData)));
+    }
+}
@@ -0,0 +1,167 @@
+use std::sync::Mutex;
+
+/// "expand 32-byte k"
+const CHACHA_CONSTANTS: [u32; 4] = [0x6170_7865, 0x3320_646e, 0x7962_2d32, 0x6b20_6574];
+
+/// Reproducible byte stream: the ChaCha20 keystream for a 32-byte seed
+///
+/// Not a source of secrets. Anyone who knows the seed knows every byte, so this
+/// only backs tests and the wasm getrandom shim behind `insecure-deterministic-rng`.
+#[derive(Debug, Clone)]
+pub struct DeterministicRng {
+    key: [u32; 8],
+    counter: u64,
+    block: [u8; 64],
+    /// Bytes of `block` already handed out
+    offset: usize,
+}
+
+impl DeterministicRng {
+    pub fn from_seed(seed: [u8; 32]) -> Self {
+        let mut key = [0u32; 8];
+        for (word, chunk) in key.iter_mut().zip(seed.chunks_exact(4)) {
+            *word = u32::from_le_bytes(chunk.try_into().unwrap());
+        }
+        Self { key, counter: 0, block: [0; 64], offset: 64 }
+    }
+
+    pub fn fill_bytes(&mut self, dest: &mut [u8]) {
+        let mut filled = 0;
+        while filled < dest.len() {
+            if self.offset == self.block.len() {
+                self.block = chacha20_block(&self.key, self.counter);
+                self.counter = self.counter.wrapping_add(1);
+                self.offset = 0;
+            }
+            let n = (dest.len() - filled).min(self.block.len() - self.offset);
+            dest[filled..filled + n].copy_from_slice(&self.block[self.offset..self.offset + n]);
+            self.offset += n;
+            filled += n;
+        }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        let mut bytes = [0u8; 8];
+        self.fill_bytes(&mut bytes);
+        u64::from_le_bytes(bytes)
+    }
+}
+
+/// The process-wide stream; `None` until first seeded or drawn from
+static GLOBAL_RNG: Mutex<Option<DeterministicRng>> = Mutex::new(None);
+
+/// Restart the process-wide stream from `seed`
+pub fn seed_rng(seed: [u8; 32]) {
+    *GLOBAL_RNG.lock().unwrap_or_else(|e| e.into_inner()) = Some(DeterministicRng::from_seed(seed));
+}
+
+/// Fill `dest` from the process-wide stream, which starts from the all-zero seed
+/// unless `seed_rng` was called
+pub fn fill_bytes(dest: &mut [u8]) {
+    let mut rng = GLOBAL_RNG.lock().unwrap_or_else(|e| e.into_inner());
+    rng.get_or_insert_with(|| DeterministicRng::from_seed([0; 32])).fill_bytes(dest);
+}
+
+fn quarter_round(state: &mut [u32; 16], a: usize, b: usize, c: usize, d: usize) {
+    state[a] = state[a].wrapping_add(state[b]);
+    state[d] = (state[d] ^ state[a]).rotate_left(16);
+    state[c] = state[c].wrapping_add(state[d]);
+    state[b] = (state[b] ^ state[c]).rotate_left(12);
+    state[a] = state[a].wrapping_add(state[b]);
+    state[d] = (state[d] ^ state[a]).rotate_left(8);
+    state[c] = state[c].wrapping_add(state[d]);
+    state[b] = (state[b] ^ state[c]).rotate_left(7);
+}
+
+/// One 64-byte ChaCha20 block with a 64-bit block counter and a zero nonce
+fn chacha20_block(key: &[u32; 8], counter: u64) -> [u8; 64] {
+    let mut initial = [0u32; 16];
+    initial[..4].copy_from_slice(&CHACHA_CONSTANTS);
+    initial[4..12].copy_from_slice(key);
+    initial[12] = counter as u32;
+    initial[13] = (counter >> 32) as u32;
+
+    let mut state = initial;
+    for _ in 0..10 {
+        quarter_round(&mut state, 0, 4, 8, 12);
+        quarter_round(&mut state, 1, 5, 9, 13);
+        quarter_round(&mut state, 2, 6, 10, 14);
+        quarter_round(&mut state, 3, 7, 11, 15);
+        quarter_round(&mut state, 0, 5, 10, 15);
+        quarter_round(&mut state, 1, 6, 11, 12);
+        quarter_round(&mut state, 2, 7, 8, 13);
+        quarter_round(&mut state, 3, 4, 9, 14);
+    }
+
+    let mut out = [0u8; 64];
+    for (i, chunk) in out.chunks_exact_mut(4).enumerate() {
+        chunk.copy_from_slice(&state[i].wrapping_add(initial[i]).to_le_bytes());
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stream(seed: [u8; 32], len: usize) -> Vec<u8> {
+        let mut bytes = vec![0u8; len];
+        DeterministicRng::from_seed(seed).fill_bytes(&mut bytes);
+        bytes
+    }
+
+    #[test]
+    fn test_matches_chacha20_keystream() {
+        // RFC 7539 A.1, test vectors 1 and 2: all-zero key and nonce, blocks 0 and 1
+        let bytes = stream([0; 32], 80);
+        assert_eq!(
+            hex::encode(&bytes[..64]),
+            "76b8e0ada0f13d90405d6ae55386bd28bdd219b8a08ded1aa836efcc8b770dc7\
+             da41597c5157488d7724e03fb8d84a376a43b8f41518a11cc387b669b2ee6586"
+        );
+        assert_eq!(hex::encode(&bytes[64..]), "9f07e7be5551387a98ba977c732d080d");
+    }
+
+    #[test]
+    fn test_same_seed_same_stream() {
+        let seed = [7u8; 32];
+        assert_eq!(stream(seed, 1000), stream(seed, 1000));
+
+        // Draw sizes don't change the stream
+        let mut rng = DeterministicRng::from_seed(seed);
+        let mut pieces = Vec::new();
+        for len in [1, 63, 2, 130, 804] {
+            let mut piece = vec![0u8; len];
+            rng.fill_bytes(&mut piece);
+            pieces.extend(piece);
+        }
+        assert_eq!(pieces, stream(seed, 1000));
+    }
+
+    #[test]
+    fn test_different_seeds_diverge() {
+        let mut other = [7u8; 32];
+        other[31] = 8;
+        let a = stream([7u8; 32], 64);
+        let b = stream(other, 64);
+        assert_ne!(a, b);
+        // Not just one differing byte
+        assert!(a.iter().zip(&b).filter(|(x, y)| x != y).count() > 48);
+    }
+
+    #[test]
+    fn test_global_stream_restarts_on_seed() {
+        seed_rng([3u8; 32]);
+        let mut first = [0u8; 40];
+        fill_bytes(&mut first);
+        let mut next = [0u8; 40];
+        fill_bytes(&mut next);
+        assert_ne!(first, next);
+
+        seed_rng([3u8; 32]);
+        let mut again = [0u8; 40];
+        fill_bytes(&mut again);
+        assert_eq!(first, again);
+        assert_eq!(first.to_vec(), stream([3u8; 32], 40));
+    }
+}
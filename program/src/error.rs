@@ -5,58 +5,201 @@ use arch_program::{
 
 use thiserror::Error;
 
-#[derive(Error, Debug)]
+/// Program errors, surfaced as `ProgramError::Custom(code)`
+///
+/// Codes are part of the wire format the frontend decodes: never renumber a
+/// variant, and give new ones the next free code in their range.
+/// 100-199 cover program state and instructions, 1000-1099 UTXO validation.
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u32)]
 pub enum OVTError {
     #[error("Invalid treasury key")]
-    InvalidTreasuryKey,
+    InvalidTreasuryKey = 100,
 
     #[error("Invalid NAV update")]
-    InvalidNAVUpdate,
+    InvalidNAVUpdate = 101,
 
     #[error("Invalid supply change")]
-    InvalidSupplyChange,
+    InvalidSupplyChange = 102,
 
     #[error("Invalid instruction data")]
-    InvalidInstructionData,
+    InvalidInstructionData = 103,
 
     #[error("Invalid account data")]
-    InvalidAccountData,
+    InvalidAccountData = 104,
 
     #[error("Invalid authority")]
-    InvalidAuthority,
+    InvalidAuthority = 105,
 
     #[error("Invalid program state")]
-    InvalidProgramState,
+    InvalidProgramState = 106,
 
     #[error("Invalid Bitcoin transaction")]
-    InvalidBitcoinTransaction,
+    InvalidBitcoinTransaction = 107,
 
     #[error("Network error")]
-    NetworkError,
+    NetworkError = 108,
 
     #[error("Insufficient funds")]
-    InsufficientFunds,
+    InsufficientFunds = 109,
 
     #[error("Operation in progress")]
-    OperationInProgress,
+    OperationInProgress = 110,
 
     #[error("Operation timeout")]
-    OperationTimeout,
+    OperationTimeout = 111,
 
     #[error("Invalid signature")]
-    InvalidSignature,
+    InvalidSignature = 112,
 
     #[error("Invalid block height")]
-    InvalidBlockHeight,
+    InvalidBlockHeight = 113,
 
     #[error("Invalid UTXO")]
-    InvalidUTXO,
+    InvalidUTXO = 114,
+
+    #[error("Network does not match the one recorded at Initialize")]
+    NetworkMismatch = 115,
+
+    #[error("Program state lags too far behind the Bitcoin node")]
+    StaleNetworkState = 116,
+
+    #[error("Instruction nonce does not match the program state")]
+    NonceMismatch = 117,
+
+    #[error("Treasury payment has already been burned for")]
+    DuplicatePayment = 118,
+
+    #[error("Treasury payment has not matured")]
+    PaymentImmature = 119,
+
+    #[error("Instruction layout is no longer accepted")]
+    UnsupportedInstructionVersion = 120,
+
+    #[error("UTXO validation failed")]
+    UtxoValidationFailed = 1000,
+
+    #[error("Failed to fetch transaction")]
+    TransactionFetchFailed = 1001,
+
+    #[error("Invalid output index")]
+    InvalidVout = 1002,
+
+    #[error("Payment amount mismatch")]
+    PaymentMismatch = 1003,
+
+    #[error("Payment not sent to the treasury")]
+    InvalidPaymentDestination = 1004,
+
+    // 1005-1008 were reserved for UTXO states that never got their own errors
+
+    #[error("Insufficient confirmations")]
+    InsufficientConfirmations = 1009,
+
+    #[error("Unexpected UTXO status")]
+    UnexpectedUtxoStatus = 1010,
+
+    // 1011 was reserved for reorg detection
+
+    #[error("Amount out of range")]
+    AmountOutOfRange = 1012,
+
+    #[error("UTXO below the dust limit")]
+    DustUtxo = 1013,
+
+    #[error("UTXO too old")]
+    UtxoTooOld = 1014,
+
+    #[error("Merkle proof does not place the transaction in the trusted block")]
+    InvalidMerkleProof = 1015,
+}
+
+/// Every error's code and variant name, for generating client-side decoders
+pub const OVT_ERROR_CODES: [(u32, &str); 31] = [
+    (100, "InvalidTreasuryKey"),
+    (101, "InvalidNAVUpdate"),
+    (102, "InvalidSupplyChange"),
+    (103, "InvalidInstructionData"),
+    (104, "InvalidAccountData"),
+    (105, "InvalidAuthority"),
+    (106, "InvalidProgramState"),
+    (107, "InvalidBitcoinTransaction"),
+    (108, "NetworkError"),
+    (109, "InsufficientFunds"),
+    (110, "OperationInProgress"),
+    (111, "OperationTimeout"),
+    (112, "InvalidSignature"),
+    (113, "InvalidBlockHeight"),
+    (114, "InvalidUTXO"),
+    (115, "NetworkMismatch"),
+    (116, "StaleNetworkState"),
+    (117, "NonceMismatch"),
+    (118, "DuplicatePayment"),
+    (119, "PaymentImmature"),
+    (120, "UnsupportedInstructionVersion"),
+    (1000, "UtxoValidationFailed"),
+    (1001, "TransactionFetchFailed"),
+    (1002, "InvalidVout"),
+    (1003, "PaymentMismatch"),
+    (1004, "InvalidPaymentDestination"),
+    (1009, "InsufficientConfirmations"),
+    (1010, "UnexpectedUtxoStatus"),
+    (1012, "AmountOutOfRange"),
+    (1013, "DustUtxo"),
+    (1014, "UtxoTooOld"),
+    (1015, "InvalidMerkleProof"),
+];
+
+impl OVTError {
+    /// The stable code carried in `ProgramError::Custom`
+    pub const fn code(self) -> u32 {
+        self as u32
+    }
+
+    pub fn from_code(code: u32) -> Option<Self> {
+        use OVTError::*;
+        Some(match code {
+            100 => InvalidTreasuryKey,
+            101 => InvalidNAVUpdate,
+            102 => InvalidSupplyChange,
+            103 => InvalidInstructionData,
+            104 => InvalidAccountData,
+            105 => InvalidAuthority,
+            106 => InvalidProgramState,
+            107 => InvalidBitcoinTransaction,
+            108 => NetworkError,
+            109 => InsufficientFunds,
+            110 => OperationInProgress,
+            111 => OperationTimeout,
+            112 => InvalidSignature,
+            113 => InvalidBlockHeight,
+            114 => InvalidUTXO,
+            115 => NetworkMismatch,
+            116 => StaleNetworkState,
+            117 => NonceMismatch,
+            118 => DuplicatePayment,
+            119 => PaymentImmature,
+            120 => UnsupportedInstructionVersion,
+            1000 => UtxoValidationFailed,
+            1001 => TransactionFetchFailed,
+            1002 => InvalidVout,
+            1003 => PaymentMismatch,
+            1004 => InvalidPaymentDestination,
+            1009 => InsufficientConfirmations,
+            1010 => UnexpectedUtxoStatus,
+            1012 => AmountOutOfRange,
+            1013 => DustUtxo,
+            1014 => UtxoTooOld,
+            1015 => InvalidMerkleProof,
+            _ => return None,
+        })
+    }
 }
 
 impl From<OVTError> for ProgramError {
     fn from(e: OVTError) -> Self {
         msg!("OVT Error: {}", e);
-        ProgramError::Custom(e as u32)
+        ProgramError::Custom(e.code())
     }
 }
 
@@ -79,4 +222,57 @@ mod tests {
         let err = OVTError::InvalidSupplyChange;
         assert_eq!(err.to_string(), "Invalid supply change");
     }
-} 
\ No newline at end of file
+
+    #[test]
+    fn test_error_codes_are_stable() {
+        assert_eq!(OVTError::InvalidTreasuryKey.code(), 100);
+        assert_eq!(OVTError::InvalidNAVUpdate.code(), 101);
+        assert_eq!(OVTError::InvalidSupplyChange.code(), 102);
+        assert_eq!(OVTError::InvalidInstructionData.code(), 103);
+        assert_eq!(OVTError::InvalidAccountData.code(), 104);
+        assert_eq!(OVTError::InvalidAuthority.code(), 105);
+        assert_eq!(OVTError::InvalidProgramState.code(), 106);
+        assert_eq!(OVTError::InvalidBitcoinTransaction.code(), 107);
+        assert_eq!(OVTError::NetworkError.code(), 108);
+        assert_eq!(OVTError::InsufficientFunds.code(), 109);
+        assert_eq!(OVTError::OperationInProgress.code(), 110);
+        assert_eq!(OVTError::OperationTimeout.code(), 111);
+        assert_eq!(OVTError::InvalidSignature.code(), 112);
+        assert_eq!(OVTError::InvalidBlockHeight.code(), 113);
+        assert_eq!(OVTError::InvalidUTXO.code(), 114);
+        assert_eq!(OVTError::NetworkMismatch.code(), 115);
+        assert_eq!(OVTError::StaleNetworkState.code(), 116);
+        assert_eq!(OVTError::NonceMismatch.code(), 117);
+        assert_eq!(OVTError::DuplicatePayment.code(), 118);
+        assert_eq!(OVTError::PaymentImmature.code(), 119);
+        assert_eq!(OVTError::UnsupportedInstructionVersion.code(), 120);
+        assert_eq!(OVTError::UtxoValidationFailed.code(), 1000);
+        assert_eq!(OVTError::TransactionFetchFailed.code(), 1001);
+        assert_eq!(OVTError::InvalidVout.code(), 1002);
+        assert_eq!(OVTError::PaymentMismatch.code(), 1003);
+        assert_eq!(OVTError::InvalidPaymentDestination.code(), 1004);
+        assert_eq!(OVTError::InsufficientConfirmations.code(), 1009);
+        assert_eq!(OVTError::UnexpectedUtxoStatus.code(), 1010);
+        assert_eq!(OVTError::AmountOutOfRange.code(), 1012);
+        assert_eq!(OVTError::DustUtxo.code(), 1013);
+        assert_eq!(OVTError::UtxoTooOld.code(), 1014);
+        assert_eq!(OVTError::InvalidMerkleProof.code(), 1015);
+    }
+
+    #[test]
+    fn test_code_table_round_trips() {
+        for (code, name) in OVT_ERROR_CODES {
+            let err = OVTError::from_code(code).unwrap_or_else(|| panic!("no error for code {}", code));
+            assert_eq!(err.code(), code);
+            assert_eq!(format!("{:?}", err), name);
+            assert!(matches!(ProgramError::from(err), ProgramError::Custom(c) if c == code));
+        }
+
+        // Every code from_code knows is in the table
+        let known = (0..2_000).filter(|code| OVTError::from_code(*code).is_some()).count();
+        assert_eq!(known, OVT_ERROR_CODES.len());
+        assert_eq!(OVTError::from_code(0), None);
+        assert_eq!(OVTError::from_code(1005), None);
+    }
+}
+ 
\ No newline at end of file
@@ -0,0 +1,285 @@
+use bitcoin::absolute::LockTime;
+use bitcoin::hashes::Hash;
+use bitcoin::opcodes::all::{OP_CHECKSIG, OP_ENDIF, OP_IF};
+use bitcoin::opcodes::OP_FALSE;
+use bitcoin::script::{Builder, Instruction, PushBytesBuf};
+use bitcoin::secp256k1::{Keypair, Message, Secp256k1};
+use bitcoin::sighash::{Prevouts, SighashCache, TapSighashType};
+use bitcoin::taproot::{LeafVersion, TapLeafHash, TaprootBuilder};
+use bitcoin::transaction::Version;
+use bitcoin::{Amount, FeeRate, OutPoint, ScriptBuf, Sequence, Transaction, TxIn, TxOut, Txid, Witness, XOnlyPublicKey};
+
+use crate::bitcoin::utxo::DEFAULT_DUST_FLOOR_SATS;
+use crate::runes_client::{fund_transaction, Funding, RunesError};
+
+/// Content type of inscribed position records
+pub const POSITION_CONTENT_TYPE: &str = "application/json";
+
+/// Protocol marker opening an ord envelope
+const ENVELOPE_PROTOCOL: &[u8] = b"ord";
+
+/// Envelope field tag of the content type; an empty push starts the body
+const CONTENT_TYPE_TAG: u8 = 1;
+
+/// Largest data push a tapscript may contain, and so a body chunk
+const MAX_SCRIPT_ELEMENT_SIZE: usize = 520;
+
+fn invalid(reason: impl Into<String>) -> RunesError {
+    RunesError::InvalidInscription(reason.into())
+}
+
+fn push_bytes(bytes: &[u8]) -> PushBytesBuf {
+    PushBytesBuf::try_from(bytes.to_vec()).expect("pushes are at most MAX_SCRIPT_ELEMENT_SIZE bytes")
+}
+
+/// Inscription id of the first inscription revealed by `reveal_txid`
+pub fn inscription_id(reveal_txid: &Txid) -> String {
+    format!("{}i0", reveal_txid)
+}
+
+/// The reveal txid of an inscription id; only the first inscription of a
+/// reveal, `i0`, is ever a position record
+pub fn parse_inscription_id(inscription_id: &str) -> Result<Txid, RunesError> {
+    inscription_id
+        .strip_suffix("i0")
+        .and_then(|txid| txid.parse().ok())
+        .ok_or_else(|| invalid(format!("{:?} is not the id of a first inscription", inscription_id)))
+}
+
+/// Tapscript revealing `body`: a check of `reveal_key`'s signature, then the
+/// ord envelope in an `OP_FALSE OP_IF` branch that never runs
+pub fn envelope_script(reveal_key: &XOnlyPublicKey, content_type: &str, body: &[u8]) -> ScriptBuf {
+    let mut builder = Builder::new()
+        .push_x_only_key(reveal_key)
+        .push_opcode(OP_CHECKSIG)
+        .push_opcode(OP_FALSE)
+        .push_opcode(OP_IF)
+        .push_slice(push_bytes(ENVELOPE_PROTOCOL))
+        .push_slice(push_bytes(&[CONTENT_TYPE_TAG]))
+        .push_slice(push_bytes(content_type.as_bytes()))
+        .push_opcode(OP_FALSE);
+    for chunk in body.chunks(MAX_SCRIPT_ELEMENT_SIZE) {
+        builder = builder.push_slice(push_bytes(chunk));
+    }
+    builder.push_opcode(OP_ENDIF).into_script()
+}
+
+/// Content type and body of the envelope revealed by input 0 of `reveal`
+///
+/// Fails with `InvalidInscription` unless the input spends a tapscript
+/// holding a well-formed envelope. Unknown odd fields are skipped, as ord does.
+pub fn decode_envelope(reveal: &Transaction) -> Result<(String, Vec<u8>), RunesError> {
+    let script = reveal.input.first()
+        .and_then(|input| input.witness.tapscript())
+        .ok_or_else(|| invalid("input 0 is not a tapscript spend"))?;
+    let instructions = script
+        .instructions()
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| invalid(format!("unparseable tapscript: {}", e)))?;
+
+    let start = instructions
+        .windows(3)
+        .position(|window| match window {
+            [Instruction::PushBytes(falsy), Instruction::Op(op), Instruction::PushBytes(protocol)] => {
+                falsy.is_empty() && *op == OP_IF && protocol.as_bytes() == ENVELOPE_PROTOCOL
+            }
+            _ => false,
+        })
+        .ok_or_else(|| invalid("no ord envelope"))?;
+
+    let mut fields = instructions[start + 3..].iter();
+    let mut content_type = None;
+    let mut body = Vec::new();
+    loop {
+        match fields.next() {
+            Some(Instruction::PushBytes(tag)) if tag.is_empty() => break,
+            Some(Instruction::PushBytes(tag)) => {
+                let value = match fields.next() {
+                    Some(Instruction::PushBytes(value)) => value.as_bytes(),
+                    _ => return Err(invalid("envelope field has no value")),
+                };
+                match tag.as_bytes() {
+                    [CONTENT_TYPE_TAG] => content_type = Some(value.to_vec()),
+                    [tag] if tag % 2 == 1 => {}
+                    _ => return Err(invalid(format!("unrecognized even envelope tag {:?}", tag.as_bytes()))),
+                }
+            }
+            _ => return Err(invalid("envelope ends before its body")),
+        }
+    }
+    loop {
+        match fields.next() {
+            Some(Instruction::PushBytes(chunk)) => body.extend_from_slice(chunk.as_bytes()),
+            Some(Instruction::Op(op)) if *op == OP_ENDIF => break,
+            _ => return Err(invalid("envelope body is not closed by OP_ENDIF")),
+        }
+    }
+
+    let content_type = content_type.ok_or_else(|| invalid("envelope has no content type"))?;
+    let content_type = String::from_utf8(content_type).map_err(|_| invalid("content type is not UTF-8"))?;
+    Ok((content_type, body))
+}
+
+/// Commit and reveal transactions inscribing `body`
+///
+/// The commit spends `funding` into a taproot output whose only script path
+/// is the envelope, holding the reveal fee and a dust-sized postage; the
+/// reveal spends it with `reveal_key`'s signature and sends the inscribed
+/// postage to the funding's change script. The commit's inputs are left
+/// unsigned, which doesn't change its txid since they are all segwit.
+pub fn inscription_transactions(
+    content_type: &str,
+    body: &[u8],
+    funding: &Funding,
+    fee_rate: FeeRate,
+    reveal_key: &Keypair,
+) -> Result<(Transaction, Transaction), RunesError> {
+    let secp = Secp256k1::new();
+    let (internal_key, _) = reveal_key.x_only_public_key();
+    let script = envelope_script(&internal_key, content_type, body);
+    let spend_info = TaprootBuilder::new()
+        .add_leaf(0, script.clone())
+        .map_err(|e| invalid(e.to_string()))?
+        .finalize(&secp, internal_key)
+        .map_err(|_| invalid("envelope script can't be committed to"))?;
+    let control_block = spend_info
+        .control_block(&(script.clone(), LeafVersion::TapScript))
+        .ok_or_else(|| invalid("envelope script is missing from its own tree"))?;
+    let reveal_witness = |signature: &[u8]| {
+        Witness::from_slice(&[signature.to_vec(), script.to_bytes(), control_block.serialize()])
+    };
+
+    // A placeholder signature of the real one's length prices the reveal
+    let mut reveal = Transaction {
+        version: Version::TWO,
+        lock_time: LockTime::ZERO,
+        input: vec![TxIn {
+            previous_output: OutPoint::null(),
+            script_sig: ScriptBuf::new(),
+            sequence: Sequence::ENABLE_RBF_NO_LOCKTIME,
+            witness: reveal_witness(&[0; 64]),
+        }],
+        output: vec![TxOut {
+            value: Amount::from_sat(DEFAULT_DUST_FLOOR_SATS),
+            script_pubkey: funding.change_script.clone(),
+        }],
+    };
+    let reveal_fee = fee_rate.fee_wu(reveal.weight()).ok_or_else(|| invalid("fee overflows"))?;
+
+    let commit_output = TxOut {
+        value: Amount::from_sat(DEFAULT_DUST_FLOOR_SATS) + reveal_fee,
+        script_pubkey: ScriptBuf::new_p2tr_tweaked(spend_info.output_key()),
+    };
+    let commit = fund_transaction(
+        &funding.utxos,
+        vec![commit_output.clone()],
+        &funding.change_script,
+        fee_rate,
+        RunesError::InvalidInscription,
+    )?;
+
+    reveal.input[0].previous_output = OutPoint::new(commit.compute_txid(), 0);
+    let leaf_hash = TapLeafHash::from_script(&script, LeafVersion::TapScript);
+    let sighash = SighashCache::new(&reveal)
+        .taproot_script_spend_signature_hash(0, &Prevouts::All(&[commit_output]), leaf_hash, TapSighashType::Default)
+        .map_err(|e| invalid(e.to_string()))?;
+    let signature = secp.sign_schnorr_no_aux_rand(&Message::from_digest(sighash.to_byte_array()), reveal_key);
+    reveal.input[0].witness = reveal_witness(&signature.serialize());
+    Ok((commit, reveal))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bitcoin::secp256k1::SecretKey;
+
+    const P2WPKH: &str = "0014751e76e8199196d454941c45d1b3a323f1433bd6";
+
+    fn reveal_key() -> Keypair {
+        Keypair::from_secret_key(&Secp256k1::new(), &SecretKey::from_slice(&[7; 32]).unwrap())
+    }
+
+    fn funding() -> Funding {
+        Funding {
+            utxos: vec![crate::bitcoin::utxo::UtxoMeta {
+                script_pubkey: P2WPKH.to_string(),
+                ..crate::bitcoin::utxo::UtxoMeta::new("44".repeat(32), 2, 50_000)
+            }],
+            change_script: ScriptBuf::from_hex(P2WPKH).unwrap(),
+            fee_rate: None,
+        }
+    }
+
+    #[test]
+    fn test_commit_reveal_pair() {
+        // Longer than one push, so the body is chunked
+        let body = vec![b'x'; MAX_SCRIPT_ELEMENT_SIZE + 10];
+        let fee_rate = FeeRate::from_sat_per_vb(3).unwrap();
+        let (commit, reveal) = inscription_transactions(POSITION_CONTENT_TYPE, &body, &funding(), fee_rate, &reveal_key()).unwrap();
+
+        assert_eq!(reveal.input[0].previous_output, OutPoint::new(commit.compute_txid(), 0));
+        assert!(commit.output[0].script_pubkey.is_p2tr());
+        let reveal_fee = commit.output[0].value - reveal.output[0].value;
+        assert_eq!(reveal_fee, fee_rate.fee_wu(reveal.weight()).unwrap());
+        assert_eq!(decode_envelope(&reveal).unwrap(), (POSITION_CONTENT_TYPE.to_string(), body));
+
+        // The reveal's signature commits to the envelope script it spends
+        let script = reveal.input[0].witness.tapscript().unwrap();
+        let sighash = SighashCache::new(&reveal)
+            .taproot_script_spend_signature_hash(
+                0,
+                &Prevouts::All(&[commit.output[0].clone()]),
+                TapLeafHash::from_script(script, LeafVersion::TapScript),
+                TapSighashType::Default,
+            )
+            .unwrap();
+        let signature = bitcoin::secp256k1::schnorr::Signature::from_slice(reveal.input[0].witness.nth(0).unwrap()).unwrap();
+        let (xonly, _) = reveal_key().x_only_public_key();
+        Secp256k1::verification_only()
+            .verify_schnorr(&signature, &Message::from_digest(sighash.to_byte_array()), &xonly)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_malformed_envelopes() {
+        let (xonly, _) = reveal_key().x_only_public_key();
+        let spend = |script: ScriptBuf| Transaction {
+            version: Version::TWO,
+            lock_time: LockTime::ZERO,
+            input: vec![TxIn {
+                witness: Witness::from_slice(&[vec![0; 64], script.to_bytes(), vec![0xc0; 33]]),
+                ..TxIn::default()
+            }],
+            output: Vec::new(),
+        };
+
+        let unclosed = Builder::new()
+            .push_x_only_key(&xonly)
+            .push_opcode(OP_CHECKSIG)
+            .push_opcode(OP_FALSE)
+            .push_opcode(OP_IF)
+            .push_slice(push_bytes(ENVELOPE_PROTOCOL))
+            .push_slice(push_bytes(&[CONTENT_TYPE_TAG]))
+            .push_slice(push_bytes(b"text/plain"))
+            .push_opcode(OP_FALSE)
+            .push_slice(push_bytes(b"body"))
+            .into_script();
+        let even_tag = Builder::new()
+            .push_opcode(OP_FALSE)
+            .push_opcode(OP_IF)
+            .push_slice(push_bytes(ENVELOPE_PROTOCOL))
+            .push_slice(push_bytes(&[2]))
+            .push_slice(push_bytes(b"?"))
+            .push_opcode(OP_FALSE)
+            .push_opcode(OP_ENDIF)
+            .into_script();
+        let no_envelope = Builder::new().push_x_only_key(&xonly).push_opcode(OP_CHECKSIG).into_script();
+        for script in [unclosed, even_tag, no_envelope] {
+            assert!(matches!(decode_envelope(&spend(script)), Err(RunesError::InvalidInscription(_))));
+        }
+
+        assert!(parse_inscription_id(&format!("{}i1", "ab".repeat(32))).is_err());
+        let txid: Txid = "ab".repeat(32).parse().unwrap();
+        assert_eq!(parse_inscription_id(&inscription_id(&txid)).unwrap(), txid);
+    }
+}
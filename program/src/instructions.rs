@@ -7,20 +7,92 @@ use arch_program::{
 
 use borsh::{BorshDeserialize, BorshSerialize};
 
-pub const OVT_PROGRAM_ID: &str = "aa00000000000000000000000000000000000000000000000000000000000000";
+use crate::address::{burn_history_address, entry_address, spent_payment_address, state_address};
+use crate::burn_history::parse_payment_txid;
+use crate::bitcoin::memo::PaymentMemo;
+use crate::bitcoin::utxo::TreasuryScriptKind;
+use crate::state::NetworkStatus;
 
+pub use crate::program_ids::OVT_PROGRAM_ID;
+
+/// Borsh tags each variant with its position, so that position and the
+/// variant's fields are the wire format: a deployed variant is never moved or
+/// changed. A new layout is a new variant at the end, and the old one is kept
+/// so it still decodes.
 #[derive(BorshSerialize, BorshDeserialize, Debug)]
 pub enum OVTInstruction {
     /// Initialize the OVT program state
     /// 
     /// Accounts expected:
-    /// 0. `[writable]` The state account to initialize
+    /// 0. `[writable]` The state account to initialize, at its derived address; created here
     /// 1. `[signer]` The authority account that pays for the initialization
     /// 2. `[]` The system program
+    /// 3. `[writable]` The burn history account, at its derived address; created here
     Initialize {
+        /// A compressed secp256k1 key; anything else fails with `InvalidTreasuryKey`
         treasury_pubkey_bytes: [u8; 33],
     },
 
+    /// `UpdateNAV` as first deployed, before it carried a nonce
+    ///
+    /// Still decoded so old transactions can be read; the program refuses it
+    /// with `UnsupportedInstructionVersion`.
+    LegacyUpdateNAV {
+        btc_price_sats: u64,
+    },
+
+    /// `BuybackBurn` as first deployed, before it carried the payment's
+    /// height, memo and a nonce
+    ///
+    /// Still decoded so old transactions can be read; the program refuses it
+    /// with `UnsupportedInstructionVersion`.
+    LegacyBuybackBurn {
+        payment_txid: String,
+        payment_amount_sats: u64,
+    },
+
+    /// Report the Bitcoin sync height and network status
+    ///
+    /// Accounts expected:
+    /// 0. `[writable]` The state account
+    /// 1. `[signer]` The authority account
+    UpdateSyncStatus {
+        height: u64,
+        status: NetworkStatus,
+        /// `OVTState::nonce` this report was signed for
+        expected_nonce: u64,
+    },
+
+    /// Log the burn history, oldest first, one `burn_record_event` per record
+    ///
+    /// Accounts expected:
+    /// 0. `[]` The burn history account
+    GetBurnHistory,
+
+    /// Smooth NAV updates into an exponential moving average, or stop
+    ///
+    /// Accounts expected:
+    /// 0. `[writable]` The state account
+    /// 1. `[signer]` The authority account
+    SetNavSmoothing {
+        /// Weight of each new spot NAV, 1 to `MAX_EMA_ALPHA_BPS`; None turns smoothing off
+        ema_alpha_bps: Option<u16>,
+        /// `OVTState::nonce` this change was signed for
+        expected_nonce: u64,
+    },
+
+    /// Require treasury payments to be `blocks` below the synced height
+    /// before they can be burned for; 0 turns the requirement off
+    ///
+    /// Accounts expected:
+    /// 0. `[writable]` The state account
+    /// 1. `[signer]` The authority account
+    SetPaymentMaturity {
+        blocks: u32,
+        /// `OVTState::nonce` this change was signed for
+        expected_nonce: u64,
+    },
+
     /// Update the NAV value
     /// 
     /// Accounts expected:
@@ -29,6 +101,8 @@ pub enum OVTInstruction {
     /// 2. `[]` The clock sysvar
     UpdateNAV {
         btc_price_sats: u64,
+        /// `OVTState::nonce` this update was signed for
+        expected_nonce: u64,
     },
 
     /// Process a buyback and burn operation
@@ -36,41 +110,78 @@ pub enum OVTInstruction {
     /// Accounts expected:
     /// 0. `[writable]` The state account
     /// 1. `[signer]` The authority account
+    /// 2. `[writable]` The burn history account, allocated at `BurnHistory::LEN`
+    /// 3. `[writable]` The payment's spent-payment account, at `spent_payment_address`; created here,
+    ///    so a payment already burned for fails with `DuplicatePayment`
+    /// 4. `[]` The system program
     BuybackBurn {
         payment_txid: String,
         payment_amount_sats: u64,
+        /// Height of the block the payment was mined in, `TreasuryPayment::block_height`
+        payment_height: u64,
+        /// Payer reference from the payment's OP_RETURN output, for attribution
+        memo: Option<PaymentMemo>,
+        /// `OVTState::nonce` this burn was signed for
+        expected_nonce: u64,
+    },
+
+    /// Store `value` under `key`, replacing what was stored there before
+    ///
+    /// Accounts expected:
+    /// 0. `[writable]` The state account
+    /// 1. `[signer]` The authority account, which pays for a new entry account
+    /// 2. `[writable]` The entry account, at `entry_address` for `key`; created
+    ///    at `ENTRY_ACCOUNT_LEN` on the first write
+    /// 3. `[]` The system program
+    WriteEntry {
+        key: Vec<u8>,
+        /// At most `MAX_ENTRY_VALUE_LEN` bytes
+        value: Vec<u8>,
+        /// `OVTState::nonce` this write was signed for
+        expected_nonce: u64,
+    },
+
+    /// Derive the scriptPubKey treasury payments must pay to with `script_kind`
+    /// from now on, e.g. to move the treasury to Taproot or a multisig
+    ///
+    /// Accounts expected:
+    /// 0. `[writable]` The state account
+    /// 1. `[signer]` The authority account
+    SetTreasuryScript {
+        /// A kind whose script can't be derived fails with `InvalidTreasuryKey`
+        script_kind: TreasuryScriptKind,
+        /// `OVTState::nonce` this change was signed for
+        expected_nonce: u64,
     },
 }
 
 impl OVTInstruction {
-    fn program_id() -> Pubkey {
-        let program_id_bytes = hex::decode(OVT_PROGRAM_ID).expect("Invalid program ID");
-        Pubkey::try_from_slice(&program_id_bytes).expect("Invalid program ID bytes")
-    }
-
-    pub fn initialize(treasury_pubkey_bytes: [u8; 33]) -> Instruction {
+    /// `program_id` is usually `ProgramIds::current()?.ovt`
+    pub fn initialize(program_id: &Pubkey, treasury_pubkey_bytes: [u8; 33]) -> Instruction {
         let data = borsh::to_vec(&OVTInstruction::Initialize { treasury_pubkey_bytes })
             .expect("Failed to serialize instruction");
 
         Instruction {
-            program_id: Self::program_id(),
+            program_id: *program_id,
             accounts: vec![
-                AccountMeta::new(Pubkey::new_unique(), false), // state account
+                AccountMeta::new(state_address(program_id), false), // state account
                 AccountMeta::new(Pubkey::new_unique(), true),  // authority
                 AccountMeta::new(Pubkey::system_program(), false), // system program
+                AccountMeta::new(burn_history_address(program_id), false), // burn history
             ],
             data,
         }
     }
 
-    pub fn update_nav(btc_price_sats: u64) -> Instruction {
-        let data = borsh::to_vec(&OVTInstruction::UpdateNAV { btc_price_sats })
+    /// `expected_nonce` is the state's current `nonce`
+    pub fn update_nav(program_id: &Pubkey, btc_price_sats: u64, expected_nonce: u64) -> Instruction {
+        let data = borsh::to_vec(&OVTInstruction::UpdateNAV { btc_price_sats, expected_nonce })
             .expect("Failed to serialize instruction");
 
         Instruction {
-            program_id: Self::program_id(),
+            program_id: *program_id,
             accounts: vec![
-                AccountMeta::new(Pubkey::new_unique(), false), // state account
+                AccountMeta::new(state_address(program_id), false), // state account
                 AccountMeta::new(Pubkey::new_unique(), true),  // authority
                 AccountMeta::new_readonly(Pubkey::new_unique(), false), // clock sysvar
             ],
@@ -78,42 +189,248 @@ impl OVTInstruction {
         }
     }
 
-    pub fn buyback_burn(payment_txid: String, payment_amount_sats: u64) -> Instruction {
+    /// `expected_nonce` is the state's current `nonce`
+    ///
+    /// Panics if `payment_txid` is not 64 hex characters, as the handler would reject it anyway
+    pub fn buyback_burn(
+        program_id: &Pubkey,
+        payment_txid: String,
+        payment_amount_sats: u64,
+        payment_height: u64,
+        memo: Option<PaymentMemo>,
+        expected_nonce: u64,
+    ) -> Instruction {
+        let spent_payment = spent_payment_address(
+            program_id,
+            &parse_payment_txid(&payment_txid).expect("Payment txid is not 32 bytes of hex"),
+        );
         let data = borsh::to_vec(&OVTInstruction::BuybackBurn {
             payment_txid,
             payment_amount_sats,
+            payment_height,
+            memo,
+            expected_nonce,
         })
         .expect("Failed to serialize instruction");
 
         Instruction {
-            program_id: Self::program_id(),
+            program_id: *program_id,
+            accounts: vec![
+                AccountMeta::new(state_address(program_id), false), // state account
+                AccountMeta::new(Pubkey::new_unique(), true),  // authority
+                AccountMeta::new(burn_history_address(program_id), false), // burn history
+                AccountMeta::new(spent_payment, false), // spent payment
+                AccountMeta::new_readonly(Pubkey::system_program(), false), // system program
+            ],
+            data,
+        }
+    }
+
+    /// `expected_nonce` is the state's current `nonce`
+    pub fn update_sync_status(program_id: &Pubkey, height: u64, status: NetworkStatus, expected_nonce: u64) -> Instruction {
+        let data = borsh::to_vec(&OVTInstruction::UpdateSyncStatus { height, status, expected_nonce })
+            .expect("Failed to serialize instruction");
+
+        Instruction {
+            program_id: *program_id,
+            accounts: vec![
+                AccountMeta::new(state_address(program_id), false), // state account
+                AccountMeta::new(Pubkey::new_unique(), true),  // authority
+            ],
+            data,
+        }
+    }
+
+    /// `expected_nonce` is the state's current `nonce`
+    pub fn set_nav_smoothing(program_id: &Pubkey, ema_alpha_bps: Option<u16>, expected_nonce: u64) -> Instruction {
+        let data = borsh::to_vec(&OVTInstruction::SetNavSmoothing { ema_alpha_bps, expected_nonce })
+            .expect("Failed to serialize instruction");
+
+        Instruction {
+            program_id: *program_id,
+            accounts: vec![
+                AccountMeta::new(state_address(program_id), false), // state account
+                AccountMeta::new(Pubkey::new_unique(), true),  // authority
+            ],
+            data,
+        }
+    }
+
+    /// `expected_nonce` is the state's current `nonce`
+    pub fn set_payment_maturity(program_id: &Pubkey, blocks: u32, expected_nonce: u64) -> Instruction {
+        let data = borsh::to_vec(&OVTInstruction::SetPaymentMaturity { blocks, expected_nonce })
+            .expect("Failed to serialize instruction");
+
+        Instruction {
+            program_id: *program_id,
+            accounts: vec![
+                AccountMeta::new(state_address(program_id), false), // state account
+                AccountMeta::new(Pubkey::new_unique(), true),  // authority
+            ],
+            data,
+        }
+    }
+
+    /// `expected_nonce` is the state's current `nonce`
+    pub fn write_entry(program_id: &Pubkey, key: Vec<u8>, value: Vec<u8>, expected_nonce: u64) -> Instruction {
+        let entry = entry_address(program_id, &key);
+        let data = borsh::to_vec(&OVTInstruction::WriteEntry { key, value, expected_nonce })
+            .expect("Failed to serialize instruction");
+
+        Instruction {
+            program_id: *program_id,
+            accounts: vec![
+                AccountMeta::new(state_address(program_id), false), // state account
+                AccountMeta::new(Pubkey::new_unique(), true),  // authority
+                AccountMeta::new(entry, false), // entry
+                AccountMeta::new_readonly(Pubkey::system_program(), false), // system program
+            ],
+            data,
+        }
+    }
+
+    /// `expected_nonce` is the state's current `nonce`
+    pub fn set_treasury_script(program_id: &Pubkey, script_kind: TreasuryScriptKind, expected_nonce: u64) -> Instruction {
+        let data = borsh::to_vec(&OVTInstruction::SetTreasuryScript { script_kind, expected_nonce })
+            .expect("Failed to serialize instruction");
+
+        Instruction {
+            program_id: *program_id,
             accounts: vec![
-                AccountMeta::new(Pubkey::new_unique(), false), // state account
+                AccountMeta::new(state_address(program_id), false), // state account
                 AccountMeta::new(Pubkey::new_unique(), true),  // authority
             ],
             data,
         }
     }
+
+    pub fn get_burn_history(program_id: &Pubkey) -> Instruction {
+        let data = borsh::to_vec(&OVTInstruction::GetBurnHistory)
+            .expect("Failed to serialize instruction");
+
+        Instruction {
+            program_id: *program_id,
+            accounts: vec![
+                AccountMeta::new_readonly(burn_history_address(program_id), false), // burn history
+            ],
+            data,
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::program_ids::DEFAULT_OVT_PROGRAM_ID;
 
     #[test]
     fn test_instruction_creation() {
         let treasury_pubkey_bytes = [0u8; 33];
+        let program_id = DEFAULT_OVT_PROGRAM_ID;
 
         // Test Initialize instruction
-        let init_ix = OVTInstruction::initialize(treasury_pubkey_bytes);
-        assert_eq!(init_ix.accounts.len(), 3);
+        let init_ix = OVTInstruction::initialize(&program_id, treasury_pubkey_bytes);
+        assert_eq!(init_ix.accounts.len(), 4);
+        assert_eq!(init_ix.accounts[3].pubkey, burn_history_address(&program_id));
 
         // Test UpdateNAV instruction
-        let update_nav_ix = OVTInstruction::update_nav(1_000_000);
+        let update_nav_ix = OVTInstruction::update_nav(&program_id, 1_000_000, 0);
         assert_eq!(update_nav_ix.accounts.len(), 3);
 
         // Test BuybackBurn instruction
-        let buyback_burn_ix = OVTInstruction::buyback_burn("txid123".to_string(), 1_000_000);
-        assert_eq!(buyback_burn_ix.accounts.len(), 2);
+        let txid = "ab".repeat(32);
+        let buyback_burn_ix = OVTInstruction::buyback_burn(&program_id, txid, 1_000_000, 120, None, 1);
+        assert_eq!(buyback_burn_ix.accounts.len(), 5);
+        assert!(matches!(
+            borsh::from_slice(&buyback_burn_ix.data).unwrap(),
+            OVTInstruction::BuybackBurn { payment_height: 120, expected_nonce: 1, .. }
+        ));
+        assert_eq!(buyback_burn_ix.accounts[2].pubkey, burn_history_address(&program_id));
+        assert_eq!(buyback_burn_ix.accounts[3].pubkey, spent_payment_address(&program_id, &[0xab; 32]));
+
+        // Test UpdateSyncStatus instruction
+        let sync_ix = OVTInstruction::update_sync_status(&program_id, 100, NetworkStatus::Active, 2);
+        assert_eq!(sync_ix.accounts.len(), 2);
+        assert!(matches!(
+            borsh::from_slice(&sync_ix.data).unwrap(),
+            OVTInstruction::UpdateSyncStatus { height: 100, expected_nonce: 2, .. }
+        ));
+
+        // Test SetNavSmoothing instruction
+        let smoothing_ix = OVTInstruction::set_nav_smoothing(&program_id, Some(2_500), 3);
+        assert_eq!(smoothing_ix.accounts.len(), 2);
+        assert!(matches!(
+            borsh::from_slice(&smoothing_ix.data).unwrap(),
+            OVTInstruction::SetNavSmoothing { ema_alpha_bps: Some(2_500), expected_nonce: 3 }
+        ));
+
+        // Test SetPaymentMaturity instruction
+        let maturity_ix = OVTInstruction::set_payment_maturity(&program_id, 6, 4);
+        assert_eq!(maturity_ix.accounts.len(), 2);
+        assert!(matches!(
+            borsh::from_slice(&maturity_ix.data).unwrap(),
+            OVTInstruction::SetPaymentMaturity { blocks: 6, expected_nonce: 4 }
+        ));
+
+        // Test SetTreasuryScript instruction
+        let script_ix = OVTInstruction::set_treasury_script(&program_id, TreasuryScriptKind::P2tr, 6);
+        assert_eq!(script_ix.accounts.len(), 2);
+        assert!(matches!(
+            borsh::from_slice(&script_ix.data).unwrap(),
+            OVTInstruction::SetTreasuryScript { script_kind: TreasuryScriptKind::P2tr, expected_nonce: 6 }
+        ));
+
+        // Test WriteEntry instruction
+        let entry_ix = OVTInstruction::write_entry(&program_id, b"ovt\0key".to_vec(), b"value".to_vec(), 5);
+        assert_eq!(entry_ix.accounts.len(), 4);
+        assert_eq!(entry_ix.accounts[2].pubkey, entry_address(&program_id, b"ovt\0key"));
+        assert!(matches!(
+            borsh::from_slice(&entry_ix.data).unwrap(),
+            OVTInstruction::WriteEntry { expected_nonce: 5, .. }
+        ));
+
+        // Every instruction goes to the given program and addresses its derived state account
+        let state = state_address(&program_id);
+        for ix in [&init_ix, &update_nav_ix, &buyback_burn_ix, &sync_ix, &smoothing_ix, &maturity_ix, &entry_ix] {
+            assert_eq!(ix.program_id, program_id);
+            assert_eq!(ix.accounts[0].pubkey, state);
+        }
+
+        // GetBurnHistory only reads the burn history account
+        let history_ix = OVTInstruction::get_burn_history(&program_id);
+        assert_eq!(history_ix.accounts.len(), 1);
+        assert_eq!(history_ix.accounts[0].pubkey, burn_history_address(&program_id));
+        assert!(!history_ix.accounts[0].is_writable);
+
+        let staging = Pubkey([0x5a; 32]);
+        let staging_ix = OVTInstruction::update_nav(&staging, 1_000_000, 0);
+        assert_eq!((staging_ix.program_id, staging_ix.accounts[0].pubkey), (staging, state_address(&staging)));
+    }
+
+    #[test]
+    fn test_first_deployed_layouts_still_decode() {
+        // UpdateNAV { btc_price_sats } and BuybackBurn { payment_txid, payment_amount_sats }, as first signed
+        let mut update = vec![1];
+        update.extend(1_000_000u64.to_le_bytes());
+        assert!(matches!(
+            borsh::from_slice(&update).unwrap(),
+            OVTInstruction::LegacyUpdateNAV { btc_price_sats: 1_000_000 }
+        ));
+
+        let mut burn = vec![2];
+        burn.extend(64u32.to_le_bytes());
+        burn.extend("ab".repeat(32).as_bytes());
+        burn.extend(5_000u64.to_le_bytes());
+        match borsh::from_slice(&burn).unwrap() {
+            OVTInstruction::LegacyBuybackBurn { payment_txid, payment_amount_sats } => {
+                assert_eq!((payment_txid, payment_amount_sats), ("ab".repeat(32), 5_000));
+            }
+            other => panic!("decoded as {:?}", other),
+        }
+
+        // The current layouts have their own tags
+        let update_nav = OVTInstruction::update_nav(&DEFAULT_OVT_PROGRAM_ID, 1_000_000, 0);
+        let buyback_burn = OVTInstruction::buyback_burn(&DEFAULT_OVT_PROGRAM_ID, "ab".repeat(32), 5_000, 0, None, 0);
+        assert_eq!((update_nav.data[0], buyback_burn.data[0]), (7, 8));
     }
 } 
\ No newline at end of file
@@ -1,5 +1,5 @@
-// Minimal lib.rs for testnet deployment
-// This is a simplified version of the program for testnet deployment
+// lib.rs for testnet deployment
+// The entrypoint and the modules its instruction handlers need
 
 use arch_program::entrypoint;
 use arch_program::entrypoint::ProgramResult;
@@ -7,58 +7,74 @@ use arch_program::pubkey::Pubkey;
 use arch_program::account::AccountInfo;
 use arch_program::msg;
 
-// Custom implementation for getrandom to work with WebAssembly
-#[cfg(all(target_arch = "wasm32", feature = "custom"))]
+// Reproducible randomness for tests; wasm builds must opt in, since its output is predictable
+#[cfg(any(not(target_arch = "wasm32"), test, feature = "insecure-deterministic-rng"))]
+pub mod deterministic_rng;
+
+// getrandom for WebAssembly, backed by the deterministic stream. Only for
+// building and testing, never for keys or anything else secret.
+#[cfg(all(target_arch = "wasm32", feature = "insecure-deterministic-rng"))]
 mod getrandom_shim {
     use getrandom::Error;
-    
-    // This is a simple implementation that uses a counter as a "random" source
-    // It's only for building purposes and not for actual randomness
+
     pub fn getrandom_inner(dest: &mut [u8]) -> Result<(), Error> {
-        static mut COUNTER: u8 = 0;
-        
-        for byte in dest.iter_mut() {
-            unsafe {
-                COUNTER = COUNTER.wrapping_add(1);
-                *byte = COUNTER;
-            }
-        }
-        
+        crate::deterministic_rng::fill_bytes(dest);
         Ok(())
     }
+
+    getrandom::register_custom_getrandom!(getrandom_inner);
 }
 
 // Network configuration module
-pub mod network_config {
-    use bitcoin::Network;
-    use std::env;
-    use std::str::FromStr;
-    
-    // Get the network from environment variable or default to Testnet
-    pub fn get_network() -> Network {
-        match env::var("ARCH_NETWORK").ok() {
-            Some(network_str) => {
-                match network_str.to_lowercase().as_str() {
-                    "testnet" => Network::Testnet,
-                    "regtest" => Network::Regtest,
-                    "bitcoin" | "mainnet" => Network::Bitcoin,
-                    _ => Network::Testnet, // Default to Testnet if unrecognized
-                }
-            },
-            None => Network::Testnet, // Default to Testnet if not specified
-        }
-    }
-    
-    // Helper function to get network as string
-    pub fn get_network_name() -> String {
-        match get_network() {
-            Network::Testnet => "testnet".to_string(),
-            Network::Regtest => "regtest".to_string(),
-            Network::Bitcoin => "mainnet".to_string(),
-            _ => "testnet".to_string(),
-        }
-    }
-}
+pub mod network_config;
+pub mod program_ids;
+
+// Program state and the instruction handlers the entrypoint dispatches to
+pub mod error;
+pub mod state;
+pub mod instructions;
+pub mod accounts;
+pub mod address;
+// Payment memos, SPV proofs and UTXO types; the node clients need the client feature
+pub mod bitcoin;
+pub mod burn_history;
+pub mod security;
+// Dry runs of instructions against a copy of the state
+pub mod simulation;
+// Borsh layouts as JSON, for frontend codegen
+#[cfg(feature = "serde")]
+pub mod schema;
+
+// Rune transactions built and signed off-chain
+#[cfg(all(feature = "client", not(target_arch = "wasm32")))]
+pub mod runes_client;
+
+// PSBTs for offline signing, built on runes_client's runestone encoding
+#[cfg(all(feature = "client", not(target_arch = "wasm32")))]
+pub mod psbt;
+
+// Commit/reveal inscriptions for the position audit log
+#[cfg(all(feature = "client", not(target_arch = "wasm32")))]
+pub mod inscription;
+
+// Off-chain access to the program for the frontend and integration tests
+#[cfg(all(feature = "client", not(target_arch = "wasm32")))]
+pub mod ovt_client;
+
+// Off-chain sanity checks of NAV updates against market prices
+#[cfg(all(feature = "client", not(target_arch = "wasm32")))]
+pub mod oracle;
+
+// Cross-checks of the treasury between registrations, the tracker and the node
+#[cfg(all(feature = "client", not(target_arch = "wasm32")))]
+pub mod reporting;
+
+pub use instructions::OVTInstruction;
+pub use state::{OVTProgram, OVTState};
+
+// One entry point for integrators, built from a single config
+#[cfg(all(feature = "client", not(target_arch = "wasm32")))]
+pub use ovt_client::{OvtClient, OvtClientConfig, OvtClientError};
 
 // Program entrypoint
 entrypoint!(process_instruction);
@@ -72,13 +88,23 @@ pub fn process_instruction(
     // Log program execution with network information
     msg!(
         "OTORI Vision Token (OVT) program entrypoint on {} network", 
-        network_config::get_network_name()
+        network_config::network_name(network_config::PROGRAM_NETWORK)
     );
     msg!("Program ID: {:?}", program_id);
     msg!("Number of accounts: {}", accounts.len());
     msg!("Instruction data length: {} bytes", instruction_data.len());
-    
-    // This is a minimal implementation for testnet deployment
-    // The actual program logic will be implemented in the full version
-    Ok(())
+
+    // The original log-only entrypoint, for deployments that must not execute instructions yet
+    #[cfg(feature = "minimal-entrypoint")]
+    {
+        msg!("WARNING: minimal entrypoint, instruction not executed");
+        Ok(())
+    }
+
+    // Unknown bytes fail with InvalidInstructionData inside the handlers
+    #[cfg(not(feature = "minimal-entrypoint"))]
+    {
+        use state::Program;
+        OVTProgram::process_instruction(program_id, accounts, instruction_data)
+    }
 }
@@ -0,0 +1,180 @@
+#[cfg(not(target_os = "solana"))]
+use arch_program::msg;
+use bitcoin::Network;
+#[cfg(not(target_os = "solana"))]
+use std::env;
+
+/// Environment variable naming the Bitcoin network the program runs against
+///
+/// Read when the program is built into `PROGRAM_NETWORK`, since a deployed
+/// program has no environment, and again at runtime by off-chain tools.
+pub const NETWORK_ENV_VAR: &str = "ARCH_NETWORK";
+
+/// The network the program was built for: `ARCH_NETWORK` at build time, or
+/// Testnet when it was unset. Initialize records it and every other
+/// instruction refuses state recorded on another network.
+pub const PROGRAM_NETWORK: Network = match option_env!("ARCH_NETWORK") {
+    Some(name) => parse_network_const(name),
+    None => Network::Testnet,
+};
+
+/// `parse_network` for the build-time `ARCH_NETWORK`, which must be one of
+/// the lowercase names; anything else fails the build
+const fn parse_network_const(name: &str) -> Network {
+    match name.as_bytes() {
+        b"bitcoin" | b"mainnet" => Network::Bitcoin,
+        b"testnet" | b"testnet4" => Network::Testnet,
+        b"signet" => Network::Signet,
+        b"regtest" => Network::Regtest,
+        _ => panic!("ARCH_NETWORK must be mainnet, testnet, signet or regtest"),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum NetworkConfigError {
+    #[error("Unknown network {0:?}, expected mainnet, testnet, signet or regtest")]
+    UnknownNetwork(String),
+}
+
+/// Per-network defaults that configs and policies are derived from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NetworkParams {
+    pub network: Network,
+    /// Bitcoin Core's default JSON-RPC port
+    pub rpc_port: u16,
+    /// Human-readable part of segwit addresses
+    pub bech32_hrp: &'static str,
+    /// Confirmations before a UTXO is trusted
+    pub min_confirmations: u32,
+}
+
+/// Parse a network name, ignoring case and surrounding whitespace
+pub fn parse_network(value: &str) -> Result<Network, NetworkConfigError> {
+    match value.trim().to_lowercase().as_str() {
+        "bitcoin" | "mainnet" => Ok(Network::Bitcoin),
+        "testnet" | "testnet4" => Ok(Network::Testnet),
+        "signet" => Ok(Network::Signet),
+        "regtest" => Ok(Network::Regtest),
+        _ => Err(NetworkConfigError::UnknownNetwork(value.to_string())),
+    }
+}
+
+/// The network from `ARCH_NETWORK`, `default` when it is unset, or an error for unknown values
+#[cfg(not(target_os = "solana"))]
+pub fn try_get_network_or(default: Network) -> Result<Network, NetworkConfigError> {
+    match env::var(NETWORK_ENV_VAR) {
+        Ok(value) => parse_network(&value),
+        Err(_) => Ok(default),
+    }
+}
+
+/// Like `try_get_network_or`, but falls back to `default` with a warning for unknown values
+#[cfg(not(target_os = "solana"))]
+pub fn get_network_or(default: Network) -> Network {
+    try_get_network_or(default).unwrap_or_else(|e| {
+        msg!("WARNING: {} in {}, falling back to {}", e, NETWORK_ENV_VAR, network_name(default));
+        default
+    })
+}
+
+/// The network from `ARCH_NETWORK`, Testnet when it is unset, or an error for unknown values
+///
+/// For off-chain tools; the program itself runs on `PROGRAM_NETWORK`.
+#[cfg(not(target_os = "solana"))]
+pub fn try_get_network() -> Result<Network, NetworkConfigError> {
+    try_get_network_or(Network::Testnet)
+}
+
+/// Like `try_get_network`, but falls back to Testnet with a warning for unknown values
+#[cfg(not(target_os = "solana"))]
+pub fn get_network() -> Network {
+    get_network_or(Network::Testnet)
+}
+
+/// Canonical name of `network`, accepted back by `parse_network`
+pub fn network_name(network: Network) -> &'static str {
+    match network {
+        Network::Signet => "signet",
+        Network::Regtest => "regtest",
+        Network::Bitcoin => "mainnet",
+        _ => "testnet",
+    }
+}
+
+// Helper function to get network as string
+#[cfg(not(target_os = "solana"))]
+pub fn get_network_name() -> String {
+    network_name(get_network()).to_string()
+}
+
+pub fn get_network_params(network: Network) -> NetworkParams {
+    let (rpc_port, bech32_hrp, min_confirmations) = match network {
+        Network::Bitcoin => (8332, "bc", 6),
+        Network::Signet => (38332, "tb", 3),
+        Network::Regtest => (18443, "bcrt", 1),
+        _ => (18332, "tb", 3),
+    };
+    NetworkParams { network, rpc_port, bech32_hrp, min_confirmations }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_accepted_names() {
+        assert_eq!(parse_network("bitcoin"), Ok(Network::Bitcoin));
+        assert_eq!(parse_network("mainnet"), Ok(Network::Bitcoin));
+        assert_eq!(parse_network("testnet"), Ok(Network::Testnet));
+        assert_eq!(parse_network("testnet4"), Ok(Network::Testnet));
+        assert_eq!(parse_network("signet"), Ok(Network::Signet));
+        assert_eq!(parse_network("regtest"), Ok(Network::Regtest));
+    }
+
+    #[test]
+    fn test_mixed_case_and_whitespace() {
+        assert_eq!(parse_network("SigNet"), Ok(Network::Signet));
+        assert_eq!(parse_network("MAINNET"), Ok(Network::Bitcoin));
+        assert_eq!(parse_network(" Regtest\n"), Ok(Network::Regtest));
+    }
+
+    #[test]
+    fn test_unknown_names() {
+        assert_eq!(parse_network("testnet3"), Err(NetworkConfigError::UnknownNetwork("testnet3".to_string())));
+        assert!(parse_network("").is_err());
+        assert!(parse_network("main net").is_err());
+    }
+
+    #[test]
+    fn test_build_time_names_match_parse_network() {
+        for name in ["bitcoin", "mainnet", "testnet", "testnet4", "signet", "regtest"] {
+            assert_eq!(Ok(parse_network_const(name)), parse_network(name), "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_names_round_trip() {
+        for network in [Network::Bitcoin, Network::Testnet, Network::Signet, Network::Regtest] {
+            assert_eq!(parse_network(network_name(network)), Ok(network));
+        }
+    }
+
+    #[test]
+    fn test_network_params() {
+        let mainnet = get_network_params(Network::Bitcoin);
+        assert_eq!((mainnet.rpc_port, mainnet.bech32_hrp, mainnet.min_confirmations), (8332, "bc", 6));
+        let signet = get_network_params(Network::Signet);
+        assert_eq!((signet.rpc_port, signet.bech32_hrp, signet.min_confirmations), (38332, "tb", 3));
+        let testnet = get_network_params(Network::Testnet);
+        assert_eq!((testnet.rpc_port, testnet.bech32_hrp, testnet.min_confirmations), (18332, "tb", 3));
+        let regtest = get_network_params(Network::Regtest);
+        assert_eq!((regtest.rpc_port, regtest.bech32_hrp, regtest.min_confirmations), (18443, "bcrt", 1));
+
+        for network in [Network::Bitcoin, Network::Testnet, Network::Signet, Network::Regtest] {
+            let params = get_network_params(network);
+            assert_eq!(params.network, network);
+            let address = bitcoin::Address::p2wsh(bitcoin::Script::new(), network).to_string();
+            assert!(address.starts_with(&format!("{}1", params.bech32_hrp)), "{} for {:?}", address, network);
+        }
+    }
+}
@@ -0,0 +1,289 @@
+use arch_program::instruction::Instruction;
+use arch_program::pubkey::Pubkey;
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use crate::instructions::OVTInstruction;
+
+/// Deviation from the oracle value `NavUpdater` accepts unless told otherwise: 5%
+pub const DEFAULT_NAV_TOLERANCE_BPS: u64 = 500;
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum OracleError {
+    #[error("No price for {0}")]
+    UnknownAsset(String),
+    #[error("Price source unavailable: {0}")]
+    Unavailable(String),
+    #[error("Invalid price response: {0}")]
+    InvalidResponse(String),
+}
+
+/// Market prices to sanity-check NAV updates against
+#[async_trait]
+pub trait PriceOracle: Send + Sync {
+    /// Price of one unit of `asset`, in satoshis
+    async fn price_sats(&self, asset: &str) -> Result<u64, OracleError>;
+}
+
+/// Fixed prices set by the test
+#[derive(Debug, Default)]
+pub struct MockPriceOracle {
+    prices: Mutex<HashMap<String, u64>>,
+}
+
+impl MockPriceOracle {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_price(self, asset: &str, price_sats: u64) -> Self {
+        self.set_price(asset, price_sats);
+        self
+    }
+
+    pub fn set_price(&self, asset: &str, price_sats: u64) {
+        self.prices.lock().unwrap().insert(asset.to_string(), price_sats);
+    }
+}
+
+#[async_trait]
+impl PriceOracle for MockPriceOracle {
+    async fn price_sats(&self, asset: &str) -> Result<u64, OracleError> {
+        self.prices
+            .lock()
+            .unwrap()
+            .get(asset)
+            .copied()
+            .ok_or_else(|| OracleError::UnknownAsset(asset.to_string()))
+    }
+}
+
+/// Prices from an HTTP API answering `GET {base_url}/price/{asset}` with
+/// `{"price_sats": <u64>}`
+#[cfg(feature = "client")]
+pub struct HttpPriceOracle {
+    base_url: String,
+    http: reqwest::Client,
+}
+
+#[cfg(feature = "client")]
+impl HttpPriceOracle {
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self {
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            http: reqwest::Client::new(),
+        }
+    }
+}
+
+#[cfg(feature = "client")]
+#[async_trait]
+impl PriceOracle for HttpPriceOracle {
+    async fn price_sats(&self, asset: &str) -> Result<u64, OracleError> {
+        let url = format!("{}/price/{}", self.base_url, asset);
+        let response = self.http
+            .get(&url)
+            .send()
+            .await
+            .map_err(|e| OracleError::Unavailable(e.to_string()))?;
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Err(OracleError::UnknownAsset(asset.to_string()));
+        }
+        if !response.status().is_success() {
+            return Err(OracleError::Unavailable(format!("{} returned {}", url, response.status())));
+        }
+        let body: serde_json::Value = response
+            .json()
+            .await
+            .map_err(|e| OracleError::InvalidResponse(e.to_string()))?;
+        body["price_sats"]
+            .as_u64()
+            .ok_or_else(|| OracleError::InvalidResponse(format!("No price_sats in {}", body)))
+    }
+}
+
+/// Units of one asset the portfolio holds
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Holding {
+    pub asset: String,
+    pub units: u64,
+}
+
+/// A proposed NAV too far from what the oracle prices the portfolio at
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NavDeviation {
+    pub proposed_nav_sats: u64,
+    /// Portfolio value at oracle prices
+    pub oracle_nav_sats: u64,
+    /// |proposed - oracle| / oracle, in basis points
+    pub deviation_bps: u64,
+    pub tolerance_bps: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum NavUpdateError {
+    #[error("Oracle lookup failed: {0}")]
+    Oracle(#[from] OracleError),
+    #[error("NAV {} deviates {} bps from the oracle value {}, at most {} allowed",
+        .0.proposed_nav_sats, .0.deviation_bps, .0.oracle_nav_sats, .0.tolerance_bps)]
+    Deviation(NavDeviation),
+    #[error("Portfolio value overflows u64")]
+    Overflow,
+}
+
+/// Builds `UpdateNAV` instructions, refusing NAVs that stray from the
+/// portfolio's value at oracle prices
+///
+/// This is an off-chain guard against typos; the program itself only bounds
+/// the relative change between updates.
+pub struct NavUpdater {
+    oracle: Arc<dyn PriceOracle>,
+    holdings: Vec<Holding>,
+    tolerance_bps: u64,
+}
+
+impl NavUpdater {
+    pub fn new(oracle: Arc<dyn PriceOracle>, holdings: Vec<Holding>) -> Self {
+        Self {
+            oracle,
+            holdings,
+            tolerance_bps: DEFAULT_NAV_TOLERANCE_BPS,
+        }
+    }
+
+    pub fn with_tolerance_bps(mut self, tolerance_bps: u64) -> Self {
+        self.tolerance_bps = tolerance_bps;
+        self
+    }
+
+    /// Value of the holdings at the oracle's current prices
+    pub async fn oracle_nav_sats(&self) -> Result<u64, NavUpdateError> {
+        let mut total: u64 = 0;
+        for holding in &self.holdings {
+            let price = self.oracle.price_sats(&holding.asset).await?;
+            let value = price.checked_mul(holding.units).ok_or(NavUpdateError::Overflow)?;
+            total = total.checked_add(value).ok_or(NavUpdateError::Overflow)?;
+        }
+        Ok(total)
+    }
+
+    /// Compare `proposed_nav_sats` with the oracle value, without building anything
+    pub async fn check(&self, proposed_nav_sats: u64) -> Result<(), NavUpdateError> {
+        let oracle_nav_sats = self.oracle_nav_sats().await?;
+        // Compared exactly; the reported bps are rounded up
+        let scaled_difference = u128::from(proposed_nav_sats.abs_diff(oracle_nav_sats)) * 10_000;
+        let allowed = u128::from(self.tolerance_bps) * u128::from(oracle_nav_sats);
+        if scaled_difference > allowed {
+            let deviation_bps = match oracle_nav_sats {
+                0 => u64::MAX,
+                _ => u64::try_from(scaled_difference.div_ceil(u128::from(oracle_nav_sats))).unwrap_or(u64::MAX),
+            };
+            return Err(NavUpdateError::Deviation(NavDeviation {
+                proposed_nav_sats,
+                oracle_nav_sats,
+                deviation_bps,
+                tolerance_bps: self.tolerance_bps,
+            }));
+        }
+        Ok(())
+    }
+
+    /// `OVTInstruction::update_nav` to `program_id` for `proposed_nav_sats`
+    /// at the state's `expected_nonce`, if it is within tolerance
+    pub async fn build_update(
+        &self,
+        program_id: &Pubkey,
+        proposed_nav_sats: u64,
+        expected_nonce: u64,
+    ) -> Result<Instruction, NavUpdateError> {
+        self.check(proposed_nav_sats).await?;
+        Ok(OVTInstruction::update_nav(program_id, proposed_nav_sats, expected_nonce))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::program_ids::DEFAULT_OVT_PROGRAM_ID;
+
+    fn portfolio_updater(btc_units: u64) -> NavUpdater {
+        let oracle = MockPriceOracle::new()
+            .with_price("BTC", 100_000_000)
+            .with_price("ACME", 250_000);
+        let holdings = vec![
+            Holding { asset: "BTC".to_string(), units: btc_units },
+            Holding { asset: "ACME".to_string(), units: 400 },
+        ];
+        NavUpdater::new(Arc::new(oracle), holdings)
+    }
+
+    #[tokio::test]
+    async fn test_within_tolerance() {
+        let updater = portfolio_updater(1);
+        // 1 BTC plus 400 × 250_000 sats
+        assert_eq!(updater.oracle_nav_sats().await.unwrap(), 200_000_000);
+
+        let instruction = updater.build_update(&DEFAULT_OVT_PROGRAM_ID, 200_000_000, 3).await.unwrap();
+        let OVTInstruction::UpdateNAV { btc_price_sats, expected_nonce } = borsh::from_slice(&instruction.data).unwrap() else {
+            panic!("expected UpdateNAV");
+        };
+        assert_eq!((btc_price_sats, expected_nonce), (200_000_000, 3));
+
+        // Exactly 5% off either way is still accepted
+        assert!(updater.check(210_000_000).await.is_ok());
+        assert!(updater.check(190_000_000).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_breach_reports_deviation() {
+        let updater = portfolio_updater(1);
+        // A slipped digit: within the program's 400% band, but far off the market
+        let result = updater.build_update(&DEFAULT_OVT_PROGRAM_ID, 700_000_000, 0).await;
+        assert_eq!(
+            result.unwrap_err(),
+            NavUpdateError::Deviation(NavDeviation {
+                proposed_nav_sats: 700_000_000,
+                oracle_nav_sats: 200_000_000,
+                deviation_bps: 25_000,
+                tolerance_bps: DEFAULT_NAV_TOLERANCE_BPS,
+            })
+        );
+        assert!(matches!(updater.check(189_999_999).await, Err(NavUpdateError::Deviation(_))));
+
+        // A looser tolerance lets the same value through
+        let loose = portfolio_updater(1).with_tolerance_bps(30_000);
+        assert!(loose.check(700_000_000).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_oracle_failures() {
+        let oracle = Arc::new(MockPriceOracle::new().with_price("BTC", 100_000_000));
+        let holdings = vec![Holding { asset: "UNLISTED".to_string(), units: 1 }];
+        let updater = NavUpdater::new(oracle.clone(), holdings);
+        assert_eq!(
+            updater.build_update(&DEFAULT_OVT_PROGRAM_ID, 1, 0).await.unwrap_err(),
+            NavUpdateError::Oracle(OracleError::UnknownAsset("UNLISTED".to_string()))
+        );
+
+        let huge = NavUpdater::new(oracle, vec![Holding { asset: "BTC".to_string(), units: u64::MAX }]);
+        assert_eq!(huge.check(1).await.unwrap_err(), NavUpdateError::Overflow);
+    }
+
+    #[cfg(feature = "client")]
+    #[tokio::test]
+    async fn test_http_oracle() {
+        use mockito::{mock, server_url};
+
+        let _btc = mock("GET", "/price/BTC")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"price_sats": 100000000}"#)
+            .create();
+        let _missing = mock("GET", "/price/NOPE").with_status(404).create();
+
+        let oracle = HttpPriceOracle::new(format!("{}/", server_url()));
+        assert_eq!(oracle.price_sats("BTC").await, Ok(100_000_000));
+        assert_eq!(oracle.price_sats("NOPE").await, Err(OracleError::UnknownAsset("NOPE".to_string())));
+    }
+}
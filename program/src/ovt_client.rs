@@ -0,0 +1,786 @@
+use arch_program::{instruction::Instruction, message::Message as ArchMessage, program_error::ProgramError, pubkey::Pubkey};
+use bitcoin::absolute::LockTime;
+use bitcoin::hashes::{sha256, Hash, HashEngine};
+use bitcoin::key::TapTweak;
+use bitcoin::opcodes::all::OP_RETURN;
+use bitcoin::script::Builder;
+use bitcoin::secp256k1::{schnorr, Keypair, Message, Secp256k1, Signing, Verification, XOnlyPublicKey};
+use bitcoin::sighash::{Prevouts, SighashCache, TapSighashType};
+use bitcoin::transaction::Version;
+use bitcoin::{Amount, Network, OutPoint, ScriptBuf, Sequence, Transaction, TxIn, TxOut, Txid, Witness};
+use borsh::BorshDeserialize;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::address::{entry_address, state_address};
+use crate::bitcoin::error::BitcoinRpcError;
+use crate::bitcoin::mock::{MockBitcoinNode, MockBitcoinRpcClient};
+use crate::bitcoin::rpc::{BitcoinRpcClient, BitcoinRpcConfig};
+use crate::bitcoin::trace;
+use crate::bitcoin::utxo::{
+    validate_utxo, TreasuryPayment, UtxoMeta, UtxoStatus, UtxoValidationRpc, ValidationPolicy, VerifiedTreasuryPayment,
+};
+use crate::bitcoin::utxo_tracker::{TrackerRpc, UtxoFilter, UtxoTracker};
+use crate::burn_history::parse_payment_txid;
+use crate::error::OVTError;
+use crate::instructions::OVTInstruction;
+use crate::program_ids::ProgramIds;
+use crate::runes_client::{RunesClient, RunesError};
+use crate::state::{NetworkStatus, OVTProgram, OVTState};
+
+/// Timeout for a single request to the Arch node
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// `RuntimeTransaction::version` the Arch node accepts
+const TRANSACTION_VERSION: u32 = 0;
+
+/// Secret key the mock backend signs with, and whose key `mock` records as the authority
+const MOCK_AUTHORITY_SECRET: [u8; 32] = [1; 32];
+
+/// Namespace entries are stored under unless `with_namespace` picks another
+pub const DEFAULT_NAMESPACE: &str = "ovt";
+
+/// Node lookups the client needs: UTXO validation and treasury tracking
+pub trait OvtClientRpc: UtxoValidationRpc + TrackerRpc {}
+
+impl<T: UtxoValidationRpc + TrackerRpc + ?Sized> OvtClientRpc for T {}
+
+/// Everything `OvtClient::from_config` needs to reach a deployment
+#[derive(Debug, Clone)]
+pub struct OvtClientConfig {
+    pub network: Network,
+    pub bitcoin_rpc: BitcoinRpcConfig,
+    /// Base URL of the ord-compatible runes API
+    pub runes_url: String,
+    pub runes_auth: Option<(String, String)>,
+    pub program_id: Pubkey,
+    /// JSON-RPC endpoint of the Arch node
+    pub arch_endpoint: String,
+    /// Key Initialize recorded as the program authority; without it the
+    /// client can read but not submit
+    pub authority: Option<Keypair>,
+}
+
+/// A signed message, as the Arch node's `send_transaction` takes it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RuntimeTransaction {
+    pub version: u32,
+    /// One 64-byte BIP-322 signature of `message.hash()` per signer, in
+    /// `message.signers` order
+    pub signatures: Vec<Vec<u8>>,
+    pub message: ArchMessage,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum OvtClientError {
+    /// From the Arch node or the program, including instructions that would fail
+    #[error("Program error: {0:?}")]
+    Program(ProgramError),
+    #[error("Bitcoin node error: {0}")]
+    Bitcoin(#[from] BitcoinRpcError),
+    #[error("Runes API error: {0}")]
+    Runes(#[from] RunesError),
+}
+
+impl From<ProgramError> for OvtClientError {
+    fn from(e: ProgramError) -> Self {
+        OvtClientError::Program(e)
+    }
+}
+
+impl From<OvtClientError> for ProgramError {
+    fn from(e: OvtClientError) -> Self {
+        match e {
+            OvtClientError::Program(e) => e,
+            OvtClientError::Bitcoin(e) => e.into(),
+            OvtClientError::Runes(e) => e.into(),
+        }
+    }
+}
+
+/// How one backend answered `OvtClient::health`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ComponentHealth {
+    /// Answered, with the block height it is at
+    Reachable { height: u64 },
+    Unreachable(String),
+    NotConfigured,
+}
+
+/// Connectivity of the backends an `OvtClient` talks to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClientHealth {
+    pub bitcoin: ComponentHealth,
+    pub runes: ComponentHealth,
+}
+
+impl ClientHealth {
+    /// The Bitcoin node answered, and so did the runes API if there is one
+    pub fn is_healthy(&self) -> bool {
+        matches!(self.bitcoin, ComponentHealth::Reachable { .. })
+            && !matches!(self.runes, ComponentHealth::Unreachable(_))
+    }
+}
+
+/// Where program state comes from and transactions go to
+enum NetworkBackend {
+    /// An Arch node's JSON-RPC endpoint
+    Arch {
+        endpoint: String,
+        http: Client,
+    },
+    /// In-process state, entries and outbox, for tests against the mock node
+    Mock {
+        state: Mutex<OVTState>,
+        entries: Mutex<HashMap<Vec<u8>, Vec<u8>>>,
+        sent: Mutex<Vec<RuntimeTransaction>>,
+    },
+}
+
+#[derive(Debug, Deserialize)]
+struct JsonRpcResponse {
+    result: Option<serde_json::Value>,
+    error: Option<JsonRpcError>,
+}
+
+#[derive(Debug, Deserialize)]
+struct JsonRpcError {
+    code: i64,
+    message: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct AccountInfoResult {
+    data: Vec<u8>,
+}
+
+/// Off-chain access to the OVT program: namespaced entries, program state,
+/// UTXO checks through a Bitcoin node, treasury tracking, runes connectivity
+/// and submission of instructions to Arch
+///
+/// Entries are keyed by namespace, each stored by WriteEntry in an account of
+/// its own; the mock backend keeps them in memory instead.
+pub struct OvtClient {
+    rpc: Arc<dyn OvtClientRpc>,
+    policy: ValidationPolicy,
+    /// Treasury UTXOs seen by `treasury_utxos`, needing the policy's confirmations
+    tracker: UtxoTracker<dyn OvtClientRpc>,
+    runes: Option<RunesClient>,
+    program_id: Pubkey,
+    /// Signs everything the client submits
+    authority: Option<Keypair>,
+    backend: NetworkBackend,
+    namespace: Vec<u8>,
+    /// Outputs `verify_utxo` has found valid, which it won't accept again
+    claimed: Mutex<HashSet<OutPoint>>,
+}
+
+impl OvtClient {
+    /// Client for the deployment `config` describes, with the validation
+    /// policy of its network
+    pub fn from_config(config: OvtClientConfig) -> Result<Self, OvtClientError> {
+        let rpc = Arc::new(BitcoinRpcClient::new(config.bitcoin_rpc)?);
+        let runes = RunesClient::new(config.network, config.runes_url, config.runes_auth);
+        let client = Self::new(rpc, config.arch_endpoint, config.program_id)
+            .with_policy(ValidationPolicy::for_network(config.network))
+            .with_runes(runes);
+        Ok(match config.authority {
+            Some(authority) => client.with_authority(authority),
+            None => client,
+        })
+    }
+
+    /// Client for the program `program_id`: UTXOs are checked through `rpc`,
+    /// and state is read from and transactions are sent to `arch_endpoint`
+    pub fn new(rpc: Arc<dyn OvtClientRpc>, arch_endpoint: impl Into<String>, program_id: Pubkey) -> Self {
+        let http = Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .expect("default reqwest client always builds");
+        Self::with_backend(
+            rpc,
+            ValidationPolicy::default(),
+            program_id,
+            NetworkBackend::Arch { endpoint: arch_endpoint.into(), http },
+        )
+    }
+
+    /// Client against the in-memory `node`, with a synced regtest state whose
+    /// authority is the key the mock signs with, and a local outbox
+    pub fn mock(node: Arc<MockBitcoinNode>) -> Self {
+        let mut state = OVTState::new([0; 33]);
+        state.network_status = NetworkStatus::Active;
+        state.authority = authority_pubkey(&mock_authority()).0;
+        Self::mock_with_state(node, state)
+    }
+
+    /// `mock` holding `state`, with a runes client in mock mode, for the
+    /// compiled-in program id
+    pub fn mock_with_state(node: Arc<MockBitcoinNode>, state: OVTState) -> Self {
+        let rpc = MockBitcoinRpcClient::new(BitcoinRpcConfig::regtest(), node);
+        let runes = RunesClient::new(Network::Regtest, String::new(), None).with_mock_mode(true);
+        Self::with_backend(
+            Arc::new(rpc),
+            ValidationPolicy::for_network(Network::Regtest),
+            ProgramIds::default().ovt,
+            NetworkBackend::Mock { state: Mutex::new(state), entries: Mutex::new(HashMap::new()), sent: Mutex::new(Vec::new()) },
+        )
+        .with_runes(runes)
+        .with_authority(mock_authority())
+    }
+
+    fn with_backend(rpc: Arc<dyn OvtClientRpc>, policy: ValidationPolicy, program_id: Pubkey, backend: NetworkBackend) -> Self {
+        Self {
+            tracker: UtxoTracker::new(rpc.clone(), policy.required_confirmations),
+            rpc,
+            policy,
+            runes: None,
+            program_id,
+            authority: None,
+            backend,
+            namespace: DEFAULT_NAMESPACE.as_bytes().to_vec(),
+            claimed: Mutex::new(HashSet::new()),
+        }
+    }
+
+    /// Also restarts treasury tracking under the new confirmation requirement
+    pub fn with_policy(mut self, policy: ValidationPolicy) -> Self {
+        self.tracker = UtxoTracker::new(self.rpc.clone(), policy.required_confirmations);
+        self.policy = policy;
+        self
+    }
+
+    /// Report `runes` in `health`
+    pub fn with_runes(mut self, runes: RunesClient) -> Self {
+        self.runes = Some(runes);
+        self
+    }
+
+    /// Sign submissions with `authority`, which the program only accepts if
+    /// it is the key Initialize recorded
+    pub fn with_authority(mut self, authority: Keypair) -> Self {
+        self.authority = Some(authority);
+        self
+    }
+
+    /// Keep entries apart from other clients sharing a store, e.g. one per frontend tab
+    pub fn with_namespace(mut self, namespace: &str) -> Self {
+        self.namespace = namespace.as_bytes().to_vec();
+        self
+    }
+
+    /// `namespace ‖ 0x00 ‖ key`, so a namespace can't spell another one's keys
+    fn entry_key(&self, key: &[u8]) -> Vec<u8> {
+        let mut entry_key = Vec::with_capacity(self.namespace.len() + 1 + key.len());
+        entry_key.extend_from_slice(&self.namespace);
+        entry_key.push(0);
+        entry_key.extend_from_slice(key);
+        entry_key
+    }
+
+    /// Store `value` under `key`, sent as a WriteEntry at the state's current
+    /// nonce and checked like `submit_nav_update`
+    ///
+    /// On an Arch node the value can be read once the transaction is processed.
+    pub async fn write_state(&self, key: &[u8], value: &[u8]) -> Result<(), ProgramError> {
+        let entry_key = self.entry_key(key);
+        match &self.backend {
+            NetworkBackend::Mock { entries, .. } => {
+                entries.lock().unwrap_or_else(|e| e.into_inner()).insert(entry_key, value.to_vec());
+                Ok(())
+            }
+            NetworkBackend::Arch { .. } => {
+                let state = self.get_state().await?;
+                let instruction = OVTInstruction::WriteEntry { key: entry_key, value: value.to_vec(), expected_nonce: state.nonce };
+                self.submit(&state, instruction).await?;
+                Ok(())
+            }
+        }
+    }
+
+    /// The value last written under `key`, or `InvalidArgument` if there is none
+    pub async fn read_state(&self, key: &[u8]) -> Result<Vec<u8>, ProgramError> {
+        let entry_key = self.entry_key(key);
+        match &self.backend {
+            NetworkBackend::Mock { entries, .. } => {
+                let entries = entries.lock().unwrap_or_else(|e| e.into_inner());
+                entries.get(&entry_key).cloned().ok_or(ProgramError::InvalidArgument)
+            }
+            NetworkBackend::Arch { endpoint, http } => {
+                let data = read_account_data(http, endpoint, entry_address(&self.program_id, &entry_key)).await?;
+                if data.is_empty() {
+                    return Err(ProgramError::InvalidArgument);
+                }
+                // Leniently, as the account holds stale bytes past a value shorter than an earlier one
+                Vec::<u8>::deserialize(&mut data.as_slice()).map_err(|_| OVTError::InvalidAccountData.into())
+            }
+        }
+    }
+
+    /// The program's `OVTState`, from the state account or the mock
+    pub async fn get_state(&self) -> Result<OVTState, OvtClientError> {
+        match &self.backend {
+            NetworkBackend::Mock { state, .. } => Ok(state.lock().unwrap_or_else(|e| e.into_inner()).clone()),
+            NetworkBackend::Arch { endpoint, http } => {
+                let data = read_account_data(http, endpoint, state_address(&self.program_id)).await?;
+                OVTState::deserialize(&mut data.as_slice())
+                    .map_err(|_| OvtClientError::Program(OVTError::InvalidAccountData.into()))
+            }
+        }
+    }
+
+    pub async fn get_network_status(&self) -> Result<NetworkStatus, OvtClientError> {
+        Ok(self.get_state().await?.network_status)
+    }
+
+    /// Current NAV in satoshis
+    pub async fn get_nav(&self) -> Result<u64, OvtClientError> {
+        Ok(self.get_state().await?.nav_sats)
+    }
+
+    /// Send an UpdateNAV to `nav_sats` at the state's current nonce, returning its id
+    ///
+    /// The update is first run through `OVTProgram::simulate` at the local
+    /// time, so one the program would reject is never sent.
+    pub async fn submit_nav_update(&self, nav_sats: u64) -> Result<String, OvtClientError> {
+        let state = self.get_state().await?;
+        let instruction = OVTInstruction::UpdateNAV { btc_price_sats: nav_sats, expected_nonce: state.nonce };
+        self.submit(&state, instruction).await
+    }
+
+    /// Send a BuybackBurn for `payment`, carrying the memo `verify_treasury_payment`
+    /// found in it, at the state's current nonce, returning its id; checked
+    /// like `submit_nav_update`
+    pub async fn submit_buyback(
+        &self,
+        payment: &TreasuryPayment,
+        verified: &VerifiedTreasuryPayment,
+    ) -> Result<String, OvtClientError> {
+        let state = self.get_state().await?;
+        let instruction = OVTInstruction::BuybackBurn {
+            payment_txid: payment.txid.clone(),
+            payment_amount_sats: payment.amount_sats,
+            payment_height: payment.block_height.map_or(0, u64::from),
+            memo: verified.memo,
+            expected_nonce: state.nonce,
+        };
+        self.submit(&state, instruction).await
+    }
+
+    async fn submit(&self, state: &OVTState, instruction: OVTInstruction) -> Result<String, OvtClientError> {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+        OVTProgram::simulate(state, &instruction, now)?;
+        Ok(self.send_instruction(self.build_instruction(instruction)?).await?)
+    }
+
+    /// The treasury's UTXOs with their tracked status, after picking up any
+    /// new ones the node lists for the treasury script
+    pub async fn treasury_utxos(&self) -> Result<Vec<(UtxoMeta, UtxoStatus)>, OvtClientError> {
+        let script = self.get_state().await?.treasury_script_pubkey()?;
+        // Clones share the tracked set, so this imports into `self.tracker`
+        self.tracker.clone().import_from_script(&script).await?;
+        Ok(self.tracker.query(UtxoFilter::new().with_script_pubkey(&script)).await)
+    }
+
+    /// Whether the Bitcoin node and the runes API answer, and at what height
+    pub async fn health(&self) -> ClientHealth {
+        let bitcoin = match TrackerRpc::get_block_count(self.rpc.as_ref()).await {
+            Ok(height) => ComponentHealth::Reachable { height },
+            Err(e) => ComponentHealth::Unreachable(e.to_string()),
+        };
+        let runes = match &self.runes {
+            Some(runes) => match runes.get_block_height().await {
+                Ok(height) => ComponentHealth::Reachable { height },
+                Err(e) => ComponentHealth::Unreachable(e.to_string()),
+            },
+            None => ComponentHealth::NotConfigured,
+        };
+        ClientHealth { bitcoin, runes }
+    }
+
+    /// Whether output `vout` of `txid` passes `validate_utxo` under this client's policy
+    ///
+    /// `Ok(false)` means the output exists but hasn't reached the required
+    /// confirmations yet; any other failure is an error. The owner is the Arch
+    /// account the UTXO should back, which only the program itself can enforce.
+    ///
+    /// An output that verifies is claimed, and verifying it again fails with
+    /// `InvalidUTXO`, so this client never counts one UTXO twice.
+    pub async fn verify_utxo(&self, txid: &[u8; 32], vout: u32, _owner: &Pubkey) -> Result<bool, ProgramError> {
+        let outpoint = OutPoint::new(Txid::from_byte_array(*txid), vout);
+        let mut utxo = UtxoMeta::new(outpoint.txid.to_string(), vout, 0);
+        match validate_utxo(self.rpc.as_ref(), &mut utxo, &self.policy).await {
+            Ok(()) => {
+                if !self.claimed.lock().unwrap_or_else(|e| e.into_inner()).insert(outpoint) {
+                    return Err(OVTError::InvalidUTXO.into());
+                }
+                Ok(true)
+            }
+            Err(ProgramError::Custom(code)) if code == OVTError::InsufficientConfirmations.code() => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Send a borsh-encoded `OVTInstruction` to the network, returning its id
+    ///
+    /// The instruction gets its accounts from the `OVTInstruction` builder
+    /// for its variant, as `submit_nav_update` and `submit_buyback` do.
+    pub async fn send_network_message(&self, message: &[u8]) -> Result<String, ProgramError> {
+        let instruction = OVTInstruction::try_from_slice(message).map_err(|_| OVTError::InvalidInstructionData)?;
+        self.send_instruction(self.build_instruction(instruction)?).await
+    }
+
+    /// `instruction` as the builder for its variant makes it for this client's program
+    fn build_instruction(&self, instruction: OVTInstruction) -> Result<Instruction, ProgramError> {
+        let program_id = &self.program_id;
+        Ok(match instruction {
+            OVTInstruction::Initialize { treasury_pubkey_bytes } => OVTInstruction::initialize(program_id, treasury_pubkey_bytes),
+            OVTInstruction::UpdateNAV { btc_price_sats, expected_nonce } => {
+                OVTInstruction::update_nav(program_id, btc_price_sats, expected_nonce)
+            }
+            OVTInstruction::BuybackBurn { payment_txid, payment_amount_sats, payment_height, memo, expected_nonce } => {
+                // The builder panics on a txid it can't derive the spent-payment account from
+                parse_payment_txid(&payment_txid)?;
+                OVTInstruction::buyback_burn(program_id, payment_txid, payment_amount_sats, payment_height, memo, expected_nonce)
+            }
+            OVTInstruction::UpdateSyncStatus { height, status, expected_nonce } => {
+                OVTInstruction::update_sync_status(program_id, height, status, expected_nonce)
+            }
+            OVTInstruction::GetBurnHistory => OVTInstruction::get_burn_history(program_id),
+            OVTInstruction::SetNavSmoothing { ema_alpha_bps, expected_nonce } => {
+                OVTInstruction::set_nav_smoothing(program_id, ema_alpha_bps, expected_nonce)
+            }
+            OVTInstruction::SetPaymentMaturity { blocks, expected_nonce } => {
+                OVTInstruction::set_payment_maturity(program_id, blocks, expected_nonce)
+            }
+            OVTInstruction::WriteEntry { key, value, expected_nonce } => {
+                OVTInstruction::write_entry(program_id, key, value, expected_nonce)
+            }
+            OVTInstruction::SetTreasuryScript { script_kind, expected_nonce } => {
+                OVTInstruction::set_treasury_script(program_id, script_kind, expected_nonce)
+            }
+            OVTInstruction::LegacyUpdateNAV { .. } | OVTInstruction::LegacyBuybackBurn { .. } => {
+                return Err(OVTError::UnsupportedInstructionVersion.into())
+            }
+        })
+    }
+
+    /// Sign `instruction` with this client's authority, as the signer the
+    /// builders leave a placeholder for at index 1, and send it
+    async fn send_instruction(&self, mut instruction: Instruction) -> Result<String, ProgramError> {
+        let authority = self.authority.as_ref().ok_or(ProgramError::MissingRequiredSignature)?;
+        if let Some(meta) = instruction.accounts.get_mut(1).filter(|meta| meta.is_signer) {
+            meta.pubkey = authority_pubkey(authority);
+        }
+        let transaction = sign_transaction(authority, instruction);
+
+        match &self.backend {
+            NetworkBackend::Mock { sent, .. } => {
+                let id = hex::encode(transaction.message.hash());
+                sent.lock().unwrap_or_else(|e| e.into_inner()).push(transaction);
+                Ok(id)
+            }
+            NetworkBackend::Arch { endpoint, http } => {
+                let result = arch_call(http, endpoint, "send_transaction", serde_json::json!([transaction])).await?;
+                result.as_str().map(str::to_string).ok_or_else(|| OVTError::NetworkError.into())
+            }
+        }
+    }
+
+    /// Transactions the mock backend has accepted, oldest first; empty for a real node
+    pub fn sent_transactions(&self) -> Vec<RuntimeTransaction> {
+        match &self.backend {
+            NetworkBackend::Mock { sent, .. } => sent.lock().unwrap_or_else(|e| e.into_inner()).clone(),
+            NetworkBackend::Arch { .. } => Vec::new(),
+        }
+    }
+}
+
+/// The Arch account key of `keypair`: its x-only public key
+fn authority_pubkey(keypair: &Keypair) -> Pubkey {
+    Pubkey(keypair.x_only_public_key().0.serialize())
+}
+
+fn mock_authority() -> Keypair {
+    Keypair::from_seckey_slice(&Secp256k1::new(), &MOCK_AUTHORITY_SECRET).expect("MOCK_AUTHORITY_SECRET is a valid key")
+}
+
+/// `instruction` in a message signed by `authority` alone
+fn sign_transaction(authority: &Keypair, instruction: Instruction) -> RuntimeTransaction {
+    let message = ArchMessage { signers: vec![authority_pubkey(authority)], instructions: vec![instruction] };
+    let signature = sign_message_bip322(&Secp256k1::new(), authority, &message.hash());
+    RuntimeTransaction { version: TRANSACTION_VERSION, signatures: vec![signature.serialize().to_vec()], message }
+}
+
+/// BIP-322 simple signature of `message` by the key-path Taproot output of
+/// `keypair`, which is how Arch nodes check transaction signatures
+fn sign_message_bip322<C: Signing + Verification>(secp: &Secp256k1<C>, keypair: &Keypair, message: &[u8]) -> schnorr::Signature {
+    let digest = bip322_digest(secp, keypair.x_only_public_key().0, message);
+    secp.sign_schnorr_no_aux_rand(&digest, &keypair.tap_tweak(secp, None).to_inner())
+}
+
+/// What a BIP-322 simple signature of `message` by the key-path Taproot
+/// output of `internal_key` signs
+fn bip322_digest<C: Verification>(secp: &Secp256k1<C>, internal_key: XOnlyPublicKey, message: &[u8]) -> Message {
+    let (to_sign, prevout) = bip322_to_sign(ScriptBuf::new_p2tr(secp, internal_key, None), message);
+    let sighash = SighashCache::new(&to_sign)
+        .taproot_key_spend_signature_hash(0, &Prevouts::All(&[prevout]), TapSighashType::Default)
+        .expect("to_sign has one input, and its prevout is given");
+    Message::from_digest(sighash.to_byte_array())
+}
+
+/// BIP-322's `to_sign` transaction for `message` from `script_pubkey`, and
+/// the `to_spend` output it spends
+fn bip322_to_sign(script_pubkey: ScriptBuf, message: &[u8]) -> (Transaction, TxOut) {
+    let tag = sha256::Hash::hash(b"BIP0322-signed-message");
+    let mut engine = sha256::Hash::engine();
+    engine.input(tag.as_ref());
+    engine.input(tag.as_ref());
+    engine.input(message);
+    let message_hash = sha256::Hash::from_engine(engine);
+
+    let to_spend = Transaction {
+        version: Version(0),
+        lock_time: LockTime::ZERO,
+        input: vec![TxIn {
+            previous_output: OutPoint { txid: Txid::all_zeros(), vout: u32::MAX },
+            script_sig: Builder::new().push_int(0).push_slice(message_hash.to_byte_array()).into_script(),
+            sequence: Sequence::ZERO,
+            witness: Witness::new(),
+        }],
+        output: vec![TxOut { value: Amount::ZERO, script_pubkey }],
+    };
+    let to_sign = Transaction {
+        version: Version(0),
+        lock_time: LockTime::ZERO,
+        input: vec![TxIn {
+            previous_output: OutPoint::new(to_spend.compute_txid(), 0),
+            script_sig: ScriptBuf::new(),
+            sequence: Sequence::ZERO,
+            witness: Witness::new(),
+        }],
+        output: vec![TxOut { value: Amount::ZERO, script_pubkey: Builder::new().push_opcode(OP_RETURN).into_script() }],
+    };
+    (to_sign, to_spend.output[0].clone())
+}
+
+/// The data held by `account` on the Arch node
+async fn read_account_data(http: &Client, endpoint: &str, account: Pubkey) -> Result<Vec<u8>, ProgramError> {
+    let result = arch_call(http, endpoint, "read_account_info", serde_json::json!([account.0])).await?;
+    let account: AccountInfoResult = serde_json::from_value(result).map_err(|_| ProgramError::from(OVTError::NetworkError))?;
+    Ok(account.data)
+}
+
+/// One JSON-RPC call to the Arch node; transport failures and node errors are
+/// `NetworkError`, with the reason recorded on the `arch_call` span
+#[cfg_attr(feature = "tracing", tracing::instrument(
+    name = "arch_call",
+    skip_all,
+    fields(method = %method, outcome = tracing::field::Empty),
+))]
+async fn arch_call(
+    http: &Client,
+    endpoint: &str,
+    method: &str,
+    params: serde_json::Value,
+) -> Result<serde_json::Value, ProgramError> {
+    let result = send_arch_call(http, endpoint, method, params).await;
+    trace::record_outcome(&result);
+    result.map_err(|_| OVTError::NetworkError.into())
+}
+
+/// `arch_call`, failing with the reason
+async fn send_arch_call(
+    http: &Client,
+    endpoint: &str,
+    method: &str,
+    params: serde_json::Value,
+) -> Result<serde_json::Value, String> {
+    let body = serde_json::json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params });
+    let response: JsonRpcResponse = http
+        .post(endpoint)
+        .json(&body)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| format!("Arch node {} unreachable: {}", endpoint, e))?
+        .json()
+        .await
+        .map_err(|e| format!("Invalid response from the Arch node: {}", e))?;
+
+    match (response.result, response.error) {
+        (_, Some(error)) => Err(format!("Arch node rejected the call: {} ({})", error.message, error.code)),
+        (Some(result), None) => Ok(result),
+        (None, None) => Err("Arch node returned neither a result nor an error".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::{ENTRY_ACCOUNT_LEN, MAX_ENTRY_VALUE_LEN};
+    use mockito::{mock, server_url, Matcher};
+
+    fn arch_client() -> OvtClient {
+        let node = Arc::new(MockBitcoinNode::new());
+        let rpc = MockBitcoinRpcClient::new(BitcoinRpcConfig::regtest(), node);
+        OvtClient::new(Arc::new(rpc), server_url(), Pubkey::new_unique()).with_authority(mock_authority())
+    }
+
+    fn is_network_error<E: Into<ProgramError>>(result: Result<impl std::fmt::Debug, E>) -> bool {
+        matches!(result.map_err(Into::into), Err(ProgramError::Custom(code)) if code == OVTError::NetworkError.code())
+    }
+
+    #[tokio::test]
+    async fn test_entries_are_namespaced() {
+        let node = Arc::new(MockBitcoinNode::new());
+        let client = OvtClient::mock(node.clone());
+        let other = OvtClient::mock(node).with_namespace("other");
+
+        client.write_state(b"key", b"one").await.unwrap();
+        assert_eq!(client.read_state(b"key").await.unwrap(), b"one");
+        assert!(other.read_state(b"key").await.is_err());
+        other.write_state(b"key", b"two").await.unwrap();
+        assert_eq!(client.read_state(b"key").await.unwrap(), b"one");
+    }
+
+    /// The mock keeps what it would have sent: the builder's instruction, signed by the authority
+    #[tokio::test]
+    async fn test_mock_outbox() {
+        let client = OvtClient::mock(Arc::new(MockBitcoinNode::new()));
+        let message = borsh::to_vec(&OVTInstruction::UpdateNAV { btc_price_sats: 1_000_000, expected_nonce: 0 }).unwrap();
+        let id = client.send_network_message(&message).await.unwrap();
+
+        let sent = client.sent_transactions();
+        let [transaction] = &sent[..] else { panic!("{:?}", sent) };
+        let authority = authority_pubkey(&mock_authority());
+        assert_eq!(id, hex::encode(transaction.message.hash()));
+        assert_eq!((transaction.version, &transaction.message.signers[..]), (TRANSACTION_VERSION, &[authority][..]));
+
+        // Just as the builder makes it, signed for by the authority
+        let mut built = OVTInstruction::update_nav(&ProgramIds::default().ovt, 1_000_000, 0);
+        built.accounts[1].pubkey = authority;
+        assert_eq!(transaction.message.instructions, vec![built]);
+        let signature = schnorr::Signature::from_slice(&transaction.signatures[0]).unwrap();
+        let secp = Secp256k1::verification_only();
+        let digest = bip322_digest(&secp, XOnlyPublicKey::from_slice(&authority.0).unwrap(), &transaction.message.hash());
+        let (output_key, _) = XOnlyPublicKey::from_slice(&authority.0).unwrap().tap_tweak(&secp, None);
+        assert!(secp.verify_schnorr(&signature, &digest, &output_key.to_inner()).is_ok());
+
+        let legacy = borsh::to_vec(&OVTInstruction::LegacyUpdateNAV { btc_price_sats: 1_000_000 }).unwrap();
+        assert!(matches!(
+            client.send_network_message(&legacy).await,
+            Err(ProgramError::Custom(code)) if code == OVTError::UnsupportedInstructionVersion.code()
+        ));
+        let unsigned = OvtClient::new(client.rpc.clone(), server_url(), ProgramIds::default().ovt);
+        assert!(matches!(unsigned.send_network_message(&message).await, Err(ProgramError::MissingRequiredSignature)));
+        assert_eq!(client.sent_transactions().len(), 1);
+    }
+
+    /// The message hash and transaction ids of BIP-322's test vectors
+    #[test]
+    fn test_bip322_matches_the_test_vectors() {
+        let script_pubkey = ScriptBuf::from_hex("00142b05d564e6a7a33c087f16e0f730d1440123799d").unwrap();
+        for (message, to_spend, to_sign) in [
+            (&b""[..], "c5680aa69bb8d860bf82d4e9cd3504b55dde018de765a91bb566283c545a99a7", "1e9654e951a5ba44c8604c4de6c67fd78a27e81dcadcfe1edf638ba3aaebaed6"),
+            (&b"Hello World"[..], "b79d196740ad5217771c1098fc4a4b51e0535c32236c71f1ea4d61a2d603352b", "88737ae86f2077145f93cc4b153ae9a1cb8d56afa511988c149c5c8c9d93bddf"),
+        ] {
+            let (transaction, prevout) = bip322_to_sign(script_pubkey.clone(), message);
+            assert_eq!(transaction.input[0].previous_output.txid.to_string(), to_spend);
+            assert_eq!(transaction.compute_txid().to_string(), to_sign);
+            assert_eq!(prevout.script_pubkey, script_pubkey);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_reads_state_account() {
+        let mut state = OVTState::new([2; 33]);
+        state.network_status = NetworkStatus::Syncing;
+        let client = arch_client();
+        let _account = read_account_mock(state_address(&client.program_id), borsh::to_vec(&state).unwrap());
+
+        assert_eq!(client.get_network_status().await.unwrap(), NetworkStatus::Syncing);
+        assert_eq!(client.get_state().await.unwrap().treasury_pubkey_bytes, [2; 33]);
+    }
+
+    /// Answers `read_account_info` for `account` alone, as other tests share the server
+    fn read_account_mock(account: Pubkey, data: Vec<u8>) -> mockito::Mock {
+        mock("POST", "/")
+            .match_body(Matcher::PartialJson(serde_json::json!({ "method": "read_account_info", "params": [account.0] })))
+            .with_body(serde_json::json!({ "result": { "data": data } }).to_string())
+            .create()
+    }
+
+    #[tokio::test]
+    async fn test_entries_live_in_their_accounts() {
+        let client = arch_client();
+        let mut stored = borsh::to_vec(&b"value".to_vec()).unwrap();
+        // Left over from a longer value written before
+        stored.resize(ENTRY_ACCOUNT_LEN, 0xff);
+        let _entry = read_account_mock(entry_address(&client.program_id, &client.entry_key(b"key")), stored);
+        let _unwritten = read_account_mock(entry_address(&client.program_id, &client.entry_key(b"unwritten")), Vec::new());
+        let _state = read_account_mock(state_address(&client.program_id), borsh::to_vec(&OVTState::new([2; 33])).unwrap());
+
+        assert_eq!(client.read_state(b"key").await.unwrap(), b"value");
+        assert!(matches!(client.read_state(b"unwritten").await, Err(ProgramError::InvalidArgument)));
+        // Refused by the simulation, so never sent
+        assert!(matches!(
+            client.write_state(b"key", &[0; MAX_ENTRY_VALUE_LEN + 1]).await,
+            Err(ProgramError::InvalidInstructionData)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_arch_errors_map_to_network_error() {
+        let message = borsh::to_vec(&OVTInstruction::UpdateNAV { btc_price_sats: 1_000_000, expected_nonce: 0 }).unwrap();
+        let _rejected = mock("POST", "/")
+            .match_body(Matcher::PartialJson(serde_json::json!({ "method": "send_transaction" })))
+            .with_body(r#"{"error": {"code": -32000, "message": "invalid signature"}}"#)
+            .create();
+        assert!(is_network_error(arch_client().send_network_message(&message).await));
+
+        let unreachable = OvtClient::new(
+            Arc::new(MockBitcoinRpcClient::new(BitcoinRpcConfig::regtest(), Arc::new(MockBitcoinNode::new()))),
+            "http://127.0.0.1:9",
+            Pubkey::new_unique(),
+        )
+        .with_authority(mock_authority());
+        assert!(is_network_error(unreachable.send_network_message(&message).await));
+        assert!(is_network_error(unreachable.get_network_status().await));
+    }
+
+    #[cfg(feature = "client")]
+    #[tokio::test]
+    async fn test_from_config_wires_every_backend() {
+        let address = server_url().replace("http://", "");
+        let (host, port) = address.rsplit_once(':').unwrap();
+        let config = OvtClientConfig {
+            network: Network::Regtest,
+            bitcoin_rpc: BitcoinRpcConfig {
+                endpoint: host.to_string(),
+                port: port.parse().unwrap(),
+                ..Default::default()
+            },
+            runes_url: server_url(),
+            runes_auth: None,
+            program_id: Pubkey::new_unique(),
+            arch_endpoint: server_url(),
+            authority: None,
+        };
+        let _node = mock("POST", "/")
+            .match_body(Matcher::PartialJson(serde_json::json!({ "method": "getblockcount" })))
+            .with_body(serde_json::json!({ "result": 840_000, "error": null, "id": "1" }).to_string())
+            .create();
+        let _ord = mock("GET", "/r/blockheight").with_body("839990").create();
+
+        let client = OvtClient::from_config(config).unwrap();
+        let health = client.health().await;
+        // Other tests answer getblockcount on the same server, with heights of their own
+        assert!(matches!(health.bitcoin, ComponentHealth::Reachable { .. }), "{:?}", health);
+        assert_eq!(health.runes, ComponentHealth::Reachable { height: 839_990 });
+        assert_eq!(client.policy.required_confirmations, ValidationPolicy::for_network(Network::Regtest).required_confirmations);
+    }
+}
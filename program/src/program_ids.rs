@@ -0,0 +1,112 @@
+use arch_program::msg;
+use arch_program::program_error::ProgramError;
+use arch_program::pubkey::Pubkey;
+
+/// Environment variable holding the program id, as 64 hex characters
+///
+/// Read when the program is built, since a deployed program has no
+/// environment, and again at runtime by off-chain tools through
+/// `ProgramIds::current`.
+pub const PROGRAM_ID_ENV_VAR: &str = "OVT_PROGRAM_ID";
+
+/// Hex form of `DEFAULT_OVT_PROGRAM_ID`: `OVT_PROGRAM_ID` at build time, or
+/// a placeholder for local builds
+pub const OVT_PROGRAM_ID: &str = match option_env!("OVT_PROGRAM_ID") {
+    Some(id) => id,
+    None => "aa00000000000000000000000000000000000000000000000000000000000000",
+};
+
+/// The program id compiled in, the only one the deployed program accepts
+pub const DEFAULT_OVT_PROGRAM_ID: Pubkey = Pubkey(decode_program_id(OVT_PROGRAM_ID));
+
+/// `OVT_PROGRAM_ID` decoded at compile time, failing the build when it isn't 64 hex characters
+const fn decode_program_id(hex: &str) -> [u8; 32] {
+    const fn nibble(c: u8) -> u8 {
+        match c {
+            b'0'..=b'9' => c - b'0',
+            b'a'..=b'f' => c - b'a' + 10,
+            b'A'..=b'F' => c - b'A' + 10,
+            _ => panic!("OVT_PROGRAM_ID must be hex"),
+        }
+    }
+
+    let hex = hex.as_bytes();
+    assert!(hex.len() == 64, "OVT_PROGRAM_ID must be 64 hex characters");
+    let mut bytes = [0u8; 32];
+    let mut i = 0;
+    while i < 32 {
+        bytes[i] = nibble(hex[2 * i]) << 4 | nibble(hex[2 * i + 1]);
+        i += 1;
+    }
+    bytes
+}
+
+/// Program ids of the deployment instructions are built for and accepted from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProgramIds {
+    pub ovt: Pubkey,
+}
+
+impl Default for ProgramIds {
+    fn default() -> Self {
+        Self { ovt: DEFAULT_OVT_PROGRAM_ID }
+    }
+}
+
+impl ProgramIds {
+    /// The ids from `OVT_PROGRAM_ID` in the environment, or the compiled-in
+    /// defaults when it is unset, for off-chain builders and clients
+    ///
+    /// A malformed value is an error rather than a silent fallback, so a
+    /// staging tool can't end up signing for production. The program itself
+    /// checks against `ProgramIds::default()`.
+    #[cfg(not(target_os = "solana"))]
+    pub fn current() -> Result<Self, ProgramError> {
+        match std::env::var(PROGRAM_ID_ENV_VAR) {
+            Ok(value) => Ok(Self { ovt: parse_program_id(&value)? }),
+            Err(_) => Ok(Self::default()),
+        }
+    }
+
+    /// `IncorrectProgramId` unless `program_id` is the OVT program's
+    pub fn check(&self, program_id: &Pubkey) -> Result<(), ProgramError> {
+        if *program_id != self.ovt {
+            msg!("Instruction for program {:?}, expected {:?}", program_id, self.ovt);
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        Ok(())
+    }
+}
+
+/// Parse a program id from 64 hex characters, ignoring surrounding whitespace
+pub fn parse_program_id(value: &str) -> Result<Pubkey, ProgramError> {
+    let bytes = hex::decode(value.trim()).map_err(|_| ProgramError::InvalidArgument)?;
+    let bytes: [u8; 32] = bytes.try_into().map_err(|_| ProgramError::InvalidArgument)?;
+    Ok(Pubkey(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_matches_hex_form() {
+        assert_eq!(parse_program_id(OVT_PROGRAM_ID).unwrap(), DEFAULT_OVT_PROGRAM_ID);
+        assert_eq!(ProgramIds::default().ovt, DEFAULT_OVT_PROGRAM_ID);
+    }
+
+    #[test]
+    fn test_malformed_program_ids() {
+        assert_eq!(parse_program_id(&format!(" {}\n", "0b".repeat(32))).unwrap(), Pubkey([0x0b; 32]));
+        for bad in ["", "aa", "zz".repeat(32).as_str(), "aa".repeat(33).as_str()] {
+            assert!(matches!(parse_program_id(bad), Err(ProgramError::InvalidArgument)), "{:?}", bad);
+        }
+    }
+
+    #[test]
+    fn test_check_rejects_other_programs() {
+        let ids = ProgramIds::default();
+        assert!(ids.check(&DEFAULT_OVT_PROGRAM_ID).is_ok());
+        assert!(matches!(ids.check(&Pubkey([7; 32])), Err(ProgramError::IncorrectProgramId)));
+    }
+}
@@ -0,0 +1,304 @@
+use bitcoin::absolute::LockTime;
+use bitcoin::psbt::Psbt;
+use bitcoin::transaction::{predict_weight, InputWeightPrediction, Version};
+use bitcoin::{Amount, CompressedPublicKey, FeeRate, OutPoint, Script, ScriptBuf, Sequence, Transaction, TxIn, TxOut, Witness};
+
+use crate::bitcoin::utxo::{UtxoMeta, DEFAULT_DUST_FLOOR_SATS};
+use crate::runes_client::{transfer_runestone, Edict, RunesError};
+
+fn invalid(reason: impl Into<String>) -> RunesError {
+    RunesError::InvalidPsbt(reason.into())
+}
+
+/// Largest witness spending `script_pubkey` will need, for the output types
+/// treasury keys use: P2WPKH and P2TR key path
+pub(crate) fn input_weight_prediction(script_pubkey: &Script) -> Option<InputWeightPrediction> {
+    if script_pubkey.is_p2wpkh() {
+        Some(InputWeightPrediction::P2WPKH_MAX)
+    } else if script_pubkey.is_p2tr() {
+        Some(InputWeightPrediction::P2TR_KEY_DEFAULT_SIGHASH)
+    } else {
+        None
+    }
+}
+
+/// Spend `inputs` to `recipients`, then change, then a runestone carrying
+/// `edicts` whose pointer keeps every unallocated rune in the change output
+///
+/// Edicts address outputs by index, so `recipients[i]` is output `i` and the
+/// change is output `recipients.len()`. Inputs must be P2WPKH or P2TR; the
+/// PSBT carries their UTXOs so air-gapped signers can check amounts.
+pub fn build_rune_transfer_psbt(
+    inputs: &[UtxoMeta],
+    recipients: &[TxOut],
+    edicts: &[Edict],
+    change_script: ScriptBuf,
+    fee_rate: FeeRate,
+) -> Result<Psbt, RunesError> {
+    let change_index = recipients.len() as u32;
+    if let Some(edict) = edicts.iter().find(|edict| edict.output > change_index) {
+        return Err(invalid(format!("edict to output {} would burn its runes", edict.output)));
+    }
+    build_psbt(inputs, recipients, transfer_runestone(edicts, Some(change_index)), change_script, fee_rate)
+}
+
+/// Spend treasury `inputs` to `recipients`, returning the rest to `change_script`
+///
+/// Treasury UTXOs can carry OVT, so the spend still gets a runestone: without
+/// one every rune in the inputs would go to the first recipient.
+pub fn build_treasury_spend_psbt(
+    inputs: &[UtxoMeta],
+    recipients: &[TxOut],
+    change_script: ScriptBuf,
+    fee_rate: FeeRate,
+) -> Result<Psbt, RunesError> {
+    build_rune_transfer_psbt(inputs, recipients, &[], change_script, fee_rate)
+}
+
+fn build_psbt(
+    inputs: &[UtxoMeta],
+    recipients: &[TxOut],
+    runestone: ScriptBuf,
+    change_script: ScriptBuf,
+    fee_rate: FeeRate,
+) -> Result<Psbt, RunesError> {
+    if inputs.is_empty() {
+        return Err(invalid("no inputs"));
+    }
+
+    let mut spent = Vec::with_capacity(inputs.len());
+    let mut predictions = Vec::with_capacity(inputs.len());
+    for utxo in inputs {
+        let script_pubkey = ScriptBuf::from_hex(&utxo.script_pubkey)
+            .map_err(|_| invalid(format!("invalid script for {}:{}", utxo.txid, utxo.vout)))?;
+        predictions.push(input_weight_prediction(&script_pubkey).ok_or_else(|| {
+            invalid(format!("{}:{} is neither P2WPKH nor P2TR", utxo.txid, utxo.vout))
+        })?);
+        let txid = utxo.txid.parse().map_err(|_| invalid(format!("invalid txid {}", utxo.txid)))?;
+        spent.push((OutPoint::new(txid, utxo.vout), TxOut { value: Amount::from_sat(utxo.amount_sats), script_pubkey }));
+    }
+
+    let mut output = recipients.to_vec();
+    output.push(TxOut { value: Amount::ZERO, script_pubkey: change_script });
+    output.push(TxOut { value: Amount::ZERO, script_pubkey: runestone });
+
+    let weight = predict_weight(predictions, output.iter().map(|output| output.script_pubkey.len()));
+    let fee = fee_rate.fee_wu(weight).ok_or_else(|| invalid("fee overflows"))?;
+    let input_total: Amount = spent.iter().map(|(_, utxo)| utxo.value).sum();
+    let recipient_total: Amount = recipients.iter().map(|recipient| recipient.value).sum();
+    let change = input_total
+        .checked_sub(recipient_total)
+        .and_then(|rest| rest.checked_sub(fee))
+        .filter(|change| change.to_sat() >= DEFAULT_DUST_FLOOR_SATS)
+        .ok_or_else(|| invalid(format!(
+            "inputs of {} can't pay {} plus a {} fee", input_total, recipient_total, fee
+        )))?;
+    output[recipients.len()].value = change;
+
+    let tx = Transaction {
+        version: Version::TWO,
+        lock_time: LockTime::ZERO,
+        input: spent
+            .iter()
+            .map(|(previous_output, _)| TxIn {
+                previous_output: *previous_output,
+                script_sig: ScriptBuf::new(),
+                sequence: Sequence::ENABLE_RBF_NO_LOCKTIME,
+                witness: Witness::new(),
+            })
+            .collect(),
+        output,
+    };
+    let mut psbt = Psbt::from_unsigned_tx(tx).map_err(|e| invalid(e.to_string()))?;
+    for (input, (_, utxo)) in psbt.inputs.iter_mut().zip(spent) {
+        input.witness_utxo = Some(utxo);
+    }
+    Ok(psbt)
+}
+
+/// Combine the signatures from PSBTs returned by offline signers into `psbt`
+///
+/// Every partial PSBT must be for the same unsigned transaction.
+pub fn merge_signatures(mut psbt: Psbt, partial_sigs: Vec<Psbt>) -> Result<Psbt, RunesError> {
+    for partial in partial_sigs {
+        psbt.combine(partial).map_err(|e| invalid(e.to_string()))?;
+    }
+    Ok(psbt)
+}
+
+/// Build the final witness of every input and extract the signed transaction
+///
+/// P2WPKH inputs need a partial signature from the key they pay to, P2TR
+/// inputs a key-path signature.
+pub fn finalize_and_extract(mut psbt: Psbt) -> Result<Transaction, RunesError> {
+    for (index, input) in psbt.inputs.iter_mut().enumerate() {
+        let script_pubkey = &input.witness_utxo
+            .as_ref()
+            .ok_or_else(|| invalid(format!("input {} has no UTXO", index)))?
+            .script_pubkey;
+
+        let witness = if script_pubkey.is_p2wpkh() {
+            let (pubkey, signature) = input.partial_sigs
+                .iter()
+                .find(|(pubkey, _)| {
+                    CompressedPublicKey::try_from(**pubkey)
+                        .is_ok_and(|key| ScriptBuf::new_p2wpkh(&key.wpubkey_hash()) == *script_pubkey)
+                })
+                .ok_or_else(|| invalid(format!("input {} is not signed by its key", index)))?;
+            Witness::from_slice(&[signature.to_vec(), pubkey.to_bytes()])
+        } else if script_pubkey.is_p2tr() {
+            let signature = input.tap_key_sig
+                .as_ref()
+                .ok_or_else(|| invalid(format!("input {} has no key-path signature", index)))?;
+            Witness::from_slice(&[signature.to_vec()])
+        } else {
+            return Err(invalid(format!("input {} is neither P2WPKH nor P2TR", index)));
+        };
+
+        // BIP174 finalizers drop everything but the UTXO and final fields
+        input.final_script_witness = Some(witness);
+        input.partial_sigs.clear();
+        input.sighash_type = None;
+        input.bip32_derivation.clear();
+        input.tap_key_sig = None;
+        input.tap_internal_key = None;
+        input.tap_key_origins.clear();
+    }
+    psbt.extract_tx().map_err(|e| invalid(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runes_client::{decode_runestone, RuneId};
+    use bitcoin::hashes::Hash;
+    use bitcoin::secp256k1::{Message, Secp256k1, SecretKey};
+    use bitcoin::sighash::{EcdsaSighashType, SighashCache};
+    use bitcoin::PublicKey;
+
+    const OVT: RuneId = RuneId { block: 840_000, tx: 1 };
+
+    fn secret_key(seed: u8) -> SecretKey {
+        SecretKey::from_slice(&[seed; 32]).unwrap()
+    }
+
+    fn pubkey(seed: u8) -> PublicKey {
+        PublicKey::new(secret_key(seed).public_key(&Secp256k1::new()))
+    }
+
+    fn p2wpkh(seed: u8) -> ScriptBuf {
+        ScriptBuf::new_p2wpkh(&CompressedPublicKey(pubkey(seed).inner).wpubkey_hash())
+    }
+
+    /// Mock treasury UTXOs paying to the keys of seeds 1 and 2
+    fn utxos() -> Vec<UtxoMeta> {
+        vec![
+            UtxoMeta { script_pubkey: p2wpkh(1).to_hex_string(), ..UtxoMeta::new("11".repeat(32), 0, 30_000) },
+            UtxoMeta { script_pubkey: p2wpkh(2).to_hex_string(), ..UtxoMeta::new("22".repeat(32), 3, 20_000) },
+        ]
+    }
+
+    fn recipient() -> TxOut {
+        TxOut { value: Amount::from_sat(10_000), script_pubkey: p2wpkh(9) }
+    }
+
+    fn fee_rate() -> FeeRate {
+        FeeRate::from_sat_per_vb(5).unwrap()
+    }
+
+    /// Signs input `index` with the key of `seed`, as an offline signer would
+    fn sign(psbt: &Psbt, index: usize, seed: u8) -> Psbt {
+        let mut signed = psbt.clone();
+        let utxo = psbt.inputs[index].witness_utxo.clone().unwrap();
+        let sighash = SighashCache::new(&psbt.unsigned_tx)
+            .p2wpkh_signature_hash(index, &utxo.script_pubkey, utxo.value, EcdsaSighashType::All)
+            .unwrap();
+        let message = Message::from_digest(sighash.to_byte_array());
+        let signature = Secp256k1::new().sign_ecdsa(&message, &secret_key(seed));
+        signed.inputs[index].partial_sigs.insert(pubkey(seed), bitcoin::ecdsa::Signature::sighash_all(signature));
+        signed
+    }
+
+    #[test]
+    fn test_rune_transfer_psbt_layout() {
+        let edicts = [
+            Edict { id: OVT, amount: 1_000, output: 0 },
+            Edict { id: OVT, amount: 250, output: 1 },
+        ];
+        let psbt = build_rune_transfer_psbt(&utxos(), &[recipient()], &edicts, p2wpkh(1), fee_rate()).unwrap();
+        let tx = &psbt.unsigned_tx;
+
+        // Inputs keep their order and carry their UTXOs for offline signers
+        let previous: Vec<String> = tx.input.iter().map(|input| input.previous_output.to_string()).collect();
+        assert_eq!(previous, vec![format!("{}:0", "11".repeat(32)), format!("{}:3", "22".repeat(32))]);
+        assert_eq!(psbt.inputs[1].witness_utxo, Some(TxOut { value: Amount::from_sat(20_000), script_pubkey: p2wpkh(2) }));
+
+        // Recipients, then change, then the runestone
+        assert_eq!(tx.output.len(), 3);
+        assert_eq!(tx.output[0], recipient());
+        assert_eq!(tx.output[1].script_pubkey, p2wpkh(1));
+        let runestone = decode_runestone(tx).unwrap().unwrap();
+        assert_eq!(runestone.flaw, None);
+        assert_eq!(runestone.pointer, Some(1));
+        assert_eq!(runestone.edicts, edicts.to_vec());
+
+        let predicted = predict_weight(
+            [InputWeightPrediction::P2WPKH_MAX; 2],
+            tx.output.iter().map(|output| output.script_pubkey.len()),
+        );
+        let fee = Amount::from_sat(50_000) - tx.output.iter().map(|output| output.value).sum::<Amount>();
+        assert_eq!(fee, fee_rate().fee_wu(predicted).unwrap());
+    }
+
+    #[test]
+    fn test_rejected_psbts() {
+        let burn = [Edict { id: OVT, amount: 1, output: 2 }];
+        assert!(matches!(
+            build_rune_transfer_psbt(&utxos(), &[recipient()], &burn, p2wpkh(1), fee_rate()),
+            Err(RunesError::InvalidPsbt(_))
+        ));
+
+        let expensive = TxOut { value: Amount::from_sat(49_000), ..recipient() };
+        assert!(matches!(
+            build_treasury_spend_psbt(&utxos(), &[expensive], p2wpkh(1), fee_rate()),
+            Err(RunesError::InvalidPsbt(_))
+        ));
+
+        let legacy = UtxoMeta { script_pubkey: "76a914751e76e8199196d454941c45d1b3a323f1433bd688ac".to_string(), ..utxos()[0].clone() };
+        assert!(matches!(
+            build_treasury_spend_psbt(&[legacy], &[recipient()], p2wpkh(1), fee_rate()),
+            Err(RunesError::InvalidPsbt(_))
+        ));
+        assert!(matches!(build_treasury_spend_psbt(&[], &[recipient()], p2wpkh(1), fee_rate()), Err(RunesError::InvalidPsbt(_))));
+    }
+
+    #[test]
+    fn test_merge_and_finalize() {
+        let psbt = build_treasury_spend_psbt(&utxos(), &[recipient()], p2wpkh(1), fee_rate()).unwrap();
+        let treasury = decode_runestone(&psbt.unsigned_tx).unwrap().unwrap();
+        assert!(treasury.edicts.is_empty());
+        assert_eq!(treasury.pointer, Some(1));
+
+        // One input signed is not enough
+        let half = merge_signatures(psbt.clone(), vec![sign(&psbt, 0, 1)]).unwrap();
+        assert!(matches!(finalize_and_extract(half), Err(RunesError::InvalidPsbt(_))));
+
+        // Nor is a signature by a key the input doesn't pay to
+        let wrong_key = merge_signatures(psbt.clone(), vec![sign(&psbt, 0, 1), sign(&psbt, 1, 3)]).unwrap();
+        assert!(matches!(finalize_and_extract(wrong_key), Err(RunesError::InvalidPsbt(_))));
+
+        let merged = merge_signatures(psbt.clone(), vec![sign(&psbt, 0, 1), sign(&psbt, 1, 2)]).unwrap();
+        let tx = finalize_and_extract(merged).unwrap();
+        assert_eq!(tx.compute_txid(), psbt.unsigned_tx.compute_txid());
+        assert_eq!(tx.input[1].witness.len(), 2);
+        assert_eq!(tx.input[1].witness.nth(1), Some(pubkey(2).to_bytes().as_slice()));
+        assert!(tx.weight() <= predict_weight(
+            [InputWeightPrediction::P2WPKH_MAX; 2],
+            tx.output.iter().map(|output| output.script_pubkey.len()),
+        ));
+
+        // Signers must all work on the same transaction
+        let other = build_treasury_spend_psbt(&utxos()[..1], &[recipient()], p2wpkh(1), fee_rate()).unwrap();
+        assert!(matches!(merge_signatures(psbt, vec![other]), Err(RunesError::InvalidPsbt(_))));
+    }
+}
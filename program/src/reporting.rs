@@ -0,0 +1,266 @@
+use std::collections::BTreeMap;
+
+use bitcoin::Address;
+use serde::Serialize;
+
+use crate::bitcoin::error::BitcoinRpcError;
+use crate::bitcoin::utxo::{UtxoKey, UtxoMeta, UtxoStatus};
+use crate::bitcoin::utxo_tracker::{TrackerRpc, UtxoFilter, UtxoTracker};
+use crate::network_config::get_network;
+use crate::state::OVTState;
+
+/// Where a treasury UTXO is known from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TreasurySource {
+    /// Registered as a treasury UTXO, e.g. by a verified payment
+    Registered,
+    /// Active in the UTXO tracker
+    Tracker,
+    /// Unspent according to the node
+    Node,
+}
+
+/// An outpoint the sources disagree on; amounts are `None` where a source
+/// doesn't have it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Discrepancy {
+    pub outpoint: String,
+    pub registered_sats: Option<u64>,
+    pub tracked_sats: Option<u64>,
+    pub node_sats: Option<u64>,
+    /// Sources that don't have the outpoint
+    pub missing_from: Vec<TreasurySource>,
+    /// The sources that have it disagree on its value
+    pub amount_mismatch: bool,
+}
+
+/// Outcome of `reconcile_treasury`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ReconciliationReport {
+    /// Hex scriptPubKey of the treasury
+    pub treasury_script_pubkey: String,
+    pub registered_sats: u64,
+    pub tracked_sats: u64,
+    pub node_sats: u64,
+    /// Outpoints the sources disagree on, sorted
+    pub discrepancies: Vec<Discrepancy>,
+    /// `node_sats - registered_sats`: positive when the node holds more than
+    /// was registered
+    pub delta_sats: i64,
+}
+
+impl ReconciliationReport {
+    /// Whether all three sources agree
+    pub fn is_balanced(&self) -> bool {
+        self.discrepancies.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ReconciliationError {
+    #[error("The state's treasury key doesn't give a valid script")]
+    InvalidTreasury,
+    #[error("Node lookup failed: {0}")]
+    Node(#[from] BitcoinRpcError),
+}
+
+/// Compare the registered treasury UTXOs with what `tracker` holds as Active
+/// and what the node lists as unspent for the treasury script
+///
+/// The program account doesn't keep the treasury's UTXOs, only its key, so
+/// the registered set is passed in. Reserved UTXOs are still the treasury's
+/// and count as tracked. Unconfirmed outputs the node knows of count as
+/// unspent.
+pub async fn reconcile_treasury<R: TrackerRpc + ?Sized>(
+    state: &OVTState,
+    registered: &[UtxoMeta],
+    tracker: &UtxoTracker<R>,
+    rpc: &R,
+) -> Result<ReconciliationReport, ReconciliationError> {
+    let script = state.treasury_script_pubkey().map_err(|_| ReconciliationError::InvalidTreasury)?;
+    let script_hex = hex::encode(script.as_bytes());
+
+    let tracked: Vec<UtxoMeta> = tracker
+        .query(UtxoFilter::new().with_script_pubkey(&script))
+        .await
+        .into_iter()
+        .filter(|(_, status)| *status == UtxoStatus::Active)
+        .map(|(utxo, _)| utxo)
+        .collect();
+    let addresses: Vec<String> = Address::from_script(&script, get_network())
+        .map(|address| vec![address.to_string()])
+        .unwrap_or_default();
+    // Listing by address is a hint some backends ignore, so filter again
+    let unspent: Vec<UtxoMeta> = rpc
+        .list_unspent(0, &addresses)
+        .await?
+        .into_iter()
+        .filter(|utxo| utxo.script_pubkey.eq_ignore_ascii_case(&script_hex))
+        .collect();
+
+    let mut by_outpoint: BTreeMap<UtxoKey, [Option<u64>; 3]> = BTreeMap::new();
+    for (index, utxos) in [registered, tracked.as_slice(), unspent.as_slice()].into_iter().enumerate() {
+        for utxo in utxos {
+            by_outpoint.entry(utxo.key()).or_default()[index] = Some(utxo.amount_sats);
+        }
+    }
+
+    let total = |utxos: &[UtxoMeta]| utxos.iter().map(|utxo| utxo.amount_sats).sum::<u64>();
+    let mut report = ReconciliationReport {
+        treasury_script_pubkey: script_hex,
+        registered_sats: total(registered),
+        tracked_sats: total(&tracked),
+        node_sats: total(&unspent),
+        ..Default::default()
+    };
+    // Both are at most 21M BTC, well within i64
+    report.delta_sats = report.node_sats as i64 - report.registered_sats as i64;
+
+    let sources = [TreasurySource::Registered, TreasurySource::Tracker, TreasurySource::Node];
+    for (key, amounts) in by_outpoint {
+        let missing_from: Vec<TreasurySource> = sources
+            .into_iter()
+            .zip(amounts)
+            .filter(|(_, amount)| amount.is_none())
+            .map(|(source, _)| source)
+            .collect();
+        let mut present = amounts.into_iter().flatten();
+        let first = present.next();
+        let amount_mismatch = present.any(|amount| Some(amount) != first);
+        if missing_from.is_empty() && !amount_mismatch {
+            continue;
+        }
+        let [registered_sats, tracked_sats, node_sats] = amounts;
+        report.discrepancies.push(Discrepancy {
+            outpoint: key.to_string(),
+            registered_sats,
+            tracked_sats,
+            node_sats,
+            missing_from,
+            amount_mismatch,
+        });
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    use bitcoin::{Amount, ScriptBuf, TxOut};
+
+    use crate::bitcoin::mock::{MockBitcoinNode, MockBitcoinRpcClient};
+    use crate::bitcoin::rpc::BitcoinRpcConfig;
+    use crate::bitcoin::utxo_tracker::UtxoTracking;
+
+    // The secp256k1 generator, a valid compressed key
+    const TREASURY_KEY: &str = "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798";
+
+    fn txid(i: u8) -> String {
+        format!("{:02x}{}", i, "00".repeat(31))
+    }
+
+    struct Fixture {
+        state: OVTState,
+        node: Arc<MockBitcoinNode>,
+        client: Arc<MockBitcoinRpcClient>,
+        tracker: UtxoTracker<MockBitcoinRpcClient>,
+        script: ScriptBuf,
+    }
+
+    impl Fixture {
+        fn new() -> Self {
+            let key: [u8; 33] = hex::decode(TREASURY_KEY).unwrap().try_into().unwrap();
+            let state = OVTState::new(key);
+            let script = state.treasury_script_pubkey().unwrap();
+            let node = Arc::new(MockBitcoinNode::new());
+            let client = Arc::new(MockBitcoinRpcClient::new(BitcoinRpcConfig::default(), node.clone()));
+            let tracker = UtxoTracker::from_rpc_config(client.clone(), &BitcoinRpcConfig::default());
+            Self { state, node, client, tracker, script }
+        }
+
+        /// A confirmed treasury output on the node, tracked as Active;
+        /// returns it as a registration would record it
+        async fn fund(&mut self, i: u8, amount_sats: u64) -> UtxoMeta {
+            let output = TxOut { value: Amount::from_sat(amount_sats), script_pubkey: self.script.clone() };
+            self.node.add_transaction(&txid(i), 10, vec![output], true);
+            let mut utxo = UtxoMeta::new(txid(i), 0, amount_sats);
+            utxo.script_pubkey = hex::encode(self.script.as_bytes());
+            utxo.confirmations = 10;
+            self.tracker.add_utxo(utxo.clone(), UtxoStatus::Active).await.unwrap();
+            utxo
+        }
+
+        async fn reconcile(&self, registered: &[UtxoMeta]) -> ReconciliationReport {
+            reconcile_treasury(&self.state, registered, &self.tracker, self.client.as_ref()).await.unwrap()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_balanced_treasury() {
+        let mut fixture = Fixture::new();
+        let registered = vec![fixture.fund(1, 50_000).await, fixture.fund(2, 30_000).await];
+        // Other scripts are none of the treasury's business
+        let other_script = ScriptBuf::from_hex("00140000000000000000000000000000000000000000").unwrap();
+        let stranger = TxOut { value: Amount::from_sat(9_000), script_pubkey: other_script };
+        fixture.node.add_transaction(&txid(9), 10, vec![stranger], true);
+
+        let report = fixture.reconcile(&registered).await;
+        assert!(report.is_balanced(), "{:?}", report);
+        assert_eq!((report.registered_sats, report.tracked_sats, report.node_sats), (80_000, 80_000, 80_000));
+        assert_eq!(report.delta_sats, 0);
+    }
+
+    #[tokio::test]
+    async fn test_missing_registration_and_spent_registration() {
+        let mut fixture = Fixture::new();
+        let kept = fixture.fund(1, 50_000).await;
+        // Received, but never registered
+        fixture.fund(2, 30_000).await;
+        // Registered, then spent without anyone telling the program or the tracker
+        let spent = fixture.fund(3, 20_000).await;
+        fixture.node.spend_utxo(&txid(3), 0);
+        // Registered for more than it is worth
+        let mut inflated = fixture.fund(4, 10_000).await;
+        inflated.amount_sats = 11_000;
+
+        let report = fixture.reconcile(&[kept, spent, inflated]).await;
+        assert_eq!(
+            report.discrepancies,
+            vec![
+                Discrepancy {
+                    outpoint: format!("{}:0", txid(2)),
+                    registered_sats: None,
+                    tracked_sats: Some(30_000),
+                    node_sats: Some(30_000),
+                    missing_from: vec![TreasurySource::Registered],
+                    amount_mismatch: false,
+                },
+                Discrepancy {
+                    outpoint: format!("{}:0", txid(3)),
+                    registered_sats: Some(20_000),
+                    tracked_sats: Some(20_000),
+                    node_sats: None,
+                    missing_from: vec![TreasurySource::Node],
+                    amount_mismatch: false,
+                },
+                Discrepancy {
+                    outpoint: format!("{}:0", txid(4)),
+                    registered_sats: Some(11_000),
+                    tracked_sats: Some(10_000),
+                    node_sats: Some(10_000),
+                    missing_from: Vec::new(),
+                    amount_mismatch: true,
+                },
+            ]
+        );
+        assert_eq!((report.registered_sats, report.tracked_sats, report.node_sats), (81_000, 110_000, 90_000));
+        assert_eq!(report.delta_sats, 9_000);
+
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["delta_sats"], 9_000);
+        assert_eq!(json["discrepancies"][0]["missing_from"], serde_json::json!(["registered"]));
+    }
+}
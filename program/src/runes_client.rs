@@ -1,12 +1,48 @@
 use bitcoin::{
+    absolute::LockTime,
+    transaction::{predict_weight, Version},
+    Amount,
+    FeeRate,
     Network, 
+    OutPoint,
     PublicKey,
+    Sequence,
     Transaction,
+    TxIn,
+    TxOut,
+    Witness,
 };
+use bitcoin::hashes::{sha256, Hash};
+use bitcoin::opcodes::all::{OP_PUSHNUM_13, OP_RETURN};
+use bitcoin::script::{Builder, Instruction, PushBytesBuf};
+use bitcoin::ScriptBuf;
+use bitcoin::secp256k1::{constants::SCHNORR_SIGNATURE_SIZE, ecdsa, schnorr, Keypair, Message, Secp256k1, SecretKey, VerifyOnly};
 use arch_program::program_error::ProgramError;
+use crate::bitcoin::rpc::BitcoinRpcClient;
+use crate::bitcoin::utxo::{UtxoMeta, DEFAULT_DUST_FLOOR_SATS};
+use crate::inscription::{decode_envelope, inscription_id, inscription_transactions, parse_inscription_id, POSITION_CONTENT_TYPE};
+use crate::psbt::input_weight_prediction;
+use crate::state::OVTState;
+use reqwest::{Client, RequestBuilder};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use thiserror::Error;
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 use std::fmt;
+use tokio::time::{sleep, Instant};
+use url::Url;
+
+/// Timeout for a single request to the runes API
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Admin signatures `verify_admin_multisig` requires unless configured otherwise
+pub const DEFAULT_MULTISIG_THRESHOLD: u8 = 3;
+
+/// Domain separator of the message admins sign to change the admin set
+const ADMIN_SET_TAG: &[u8] = b"OVT admin set";
+
+/// Blocks to confirm within when the fee rate comes from the node's estimate
+pub const FEE_ESTIMATE_TARGET_BLOCKS: u16 = 6;
 
 #[derive(Debug, Error)]
 pub enum RunesError {
@@ -18,6 +54,22 @@ pub enum RunesError {
     InvalidAdminKeys,
     #[error("Bitcoin RPC error: {0}")]
     BitcoinRPC(String),
+    #[error("Circuit breaker open")]
+    CircuitOpen,
+    #[error("Invalid rune etching: {0}")]
+    InvalidEtching(String),
+    #[error("Invalid rune burn: {0}")]
+    InvalidBurn(String),
+    #[error("Invalid rune mint: {0}")]
+    InvalidMint(String),
+    #[error("Insufficient funding: inputs of {available_sats} sats are {shortfall_sats} short of the {needed_sats} needed")]
+    InsufficientFunding { needed_sats: u64, available_sats: u64, shortfall_sats: u64 },
+    #[error("Invalid inscription: {0}")]
+    InvalidInscription(String),
+    #[error("Invalid PSBT: {0}")]
+    InvalidPsbt(String),
+    #[error("Invalid position update: {0}")]
+    InvalidPositionUpdate(String),
 }
 
 impl From<RunesError> for ProgramError {
@@ -28,24 +80,71 @@ impl From<RunesError> for ProgramError {
             RunesError::InsufficientSignatures => ProgramError::Custom(1002),
             RunesError::InvalidAdminKeys => ProgramError::Custom(1003),
             RunesError::BitcoinRPC(_) => ProgramError::Custom(1004),
+            RunesError::CircuitOpen => ProgramError::Custom(1005),
+            RunesError::InvalidEtching(_) => ProgramError::Custom(1006),
+            RunesError::InvalidBurn(_) => ProgramError::Custom(1007),
+            RunesError::InvalidPsbt(_) => ProgramError::Custom(1008),
+            RunesError::InvalidPositionUpdate(_) => ProgramError::Custom(1009),
+            RunesError::InvalidMint(_) => ProgramError::Custom(1010),
+            RunesError::InsufficientFunding { .. } => ProgramError::Custom(1011),
+            RunesError::InvalidInscription(_) => ProgramError::Custom(1012),
         }
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum PositionType {
+    #[serde(rename = "pre_tge")]
     PreTGE,
+    #[serde(rename = "post_tge")]
     PostTGE,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum PositionStatus {
     Active,
     Exited,
     Pending,
 }
 
-#[derive(Debug, Clone)]
+impl PositionType {
+    fn as_str(&self) -> &'static str {
+        match self {
+            PositionType::PreTGE => "pre_tge",
+            PositionType::PostTGE => "post_tge",
+        }
+    }
+}
+
+impl PositionStatus {
+    fn as_str(&self) -> &'static str {
+        match self {
+            PositionStatus::Active => "active",
+            PositionStatus::Exited => "exited",
+            PositionStatus::Pending => "pending",
+        }
+    }
+
+    /// Positions only move forward: Pending to Active, then Active to Exited
+    pub fn can_transition_to(self, next: PositionStatus) -> bool {
+        matches!(
+            (self, next),
+            (PositionStatus::Pending, PositionStatus::Active) | (PositionStatus::Active, PositionStatus::Exited)
+        )
+    }
+}
+
+/// How a position was closed out
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExitDetails {
+    pub exit_price_per_token: u64,
+    /// Sats received for the whole position
+    pub proceeds_sats: u64,
+    pub exit_txid: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct PortfolioPosition {
     pub name: String,
     pub amount: u64,
@@ -56,6 +155,940 @@ pub struct PortfolioPosition {
     pub entry_timestamp: u64,
     pub position_type: PositionType,
     pub status: PositionStatus,
+    /// Set once the position is exited
+    #[serde(default)]
+    pub exit_details: Option<ExitDetails>,
+}
+
+impl PortfolioPosition {
+    /// Exit proceeds minus the sats spent on entry; None until the position is exited
+    pub fn realized_pnl_sats(&self) -> Option<i64> {
+        let exit = self.exit_details.as_ref()?;
+        i64::try_from(i128::from(exit.proceeds_sats) - i128::from(self.currency_spent)).ok()
+    }
+}
+
+/// Which positions `RunesClient::list_positions` returns; unset fields match everything
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PositionFilter {
+    pub position_type: Option<PositionType>,
+    pub status: Option<PositionStatus>,
+    /// Case-insensitive substring of the position name
+    pub name_contains: Option<String>,
+}
+
+impl PositionFilter {
+    pub fn matches(&self, position: &PortfolioPosition) -> bool {
+        self.position_type.as_ref().is_none_or(|position_type| *position_type == position.position_type)
+            && self.status.is_none_or(|status| status == position.status)
+            && self.name_contains.as_ref().is_none_or(|needle| {
+                position.name.to_lowercase().contains(&needle.to_lowercase())
+            })
+    }
+}
+
+/// Positions per page unless a `Pagination` asks for another size
+pub const DEFAULT_PAGE_LIMIT: usize = 50;
+
+/// Page of positions to fetch: up to `limit` positions after `cursor`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pagination {
+    /// `next_cursor` of the previous page; None for the first page
+    pub cursor: Option<String>,
+    pub limit: usize,
+}
+
+impl Default for Pagination {
+    fn default() -> Self {
+        Self { cursor: None, limit: DEFAULT_PAGE_LIMIT }
+    }
+}
+
+/// One page of `RunesClient::list_positions`, ordered by name
+#[derive(Debug, Clone, Deserialize)]
+pub struct PositionPage {
+    pub items: Vec<PortfolioPosition>,
+    /// Positions matching the filter across all pages
+    pub total: usize,
+    /// Cursor for the following page; None on the last page
+    pub next_cursor: Option<String>,
+}
+
+/// Body of `POST /position/:name/status`; its JSON encoding is also what the
+/// admins sign
+#[derive(Debug, Serialize)]
+struct StatusUpdate<'a> {
+    name: &'a str,
+    status: PositionStatus,
+    exit_details: Option<&'a ExitDetails>,
+}
+
+/// Bytes admins sign to approve moving position `name` to `status`
+pub fn position_status_message(name: &str, status: PositionStatus, exit_details: Option<&ExitDetails>) -> Vec<u8> {
+    serde_json::to_vec(&StatusUpdate { name, status, exit_details })
+        .expect("status updates serialize to JSON")
+}
+
+/// Bytes admins sign to approve inscribing `position`: its JSON, fields in
+/// declaration order, without the inscription id it only gets once inscribed
+pub fn position_inscription_message(position: &PortfolioPosition) -> Vec<u8> {
+    let unassigned = PortfolioPosition { safe_inscription_id: None, ..position.clone() };
+    serde_json::to_vec(&unassigned).expect("positions serialize to JSON")
+}
+
+/// Body of a position inscription: the position as the admins signed it,
+/// with the keys and threshold that approved it
+#[derive(Debug, Serialize, Deserialize)]
+struct PositionRecord {
+    position: PortfolioPosition,
+    threshold: u8,
+    admin_pubkeys: Vec<PublicKey>,
+    signatures: Vec<String>,
+}
+
+/// Body of `POST /position/:name/inscription`
+#[derive(Debug, Serialize)]
+struct InscriptionUpdate<'a> {
+    inscription_id: &'a str,
+}
+
+/// Position read back from its inscription, from `RunesClient::fetch_inscribed_position`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InscribedPosition {
+    /// With `safe_inscription_id` set to the inscription it was read from
+    pub position: PortfolioPosition,
+    /// Admins whose signatures the inscription carries, and their threshold
+    pub approvers: AdminSet,
+}
+
+/// Position inscribed by `reveal`, once its signatures check out against the
+/// keys and threshold inscribed with it
+///
+/// Fails with `InvalidInscription` for an envelope that isn't a position
+/// record and `InsufficientSignatures` when the signatures fall short.
+/// Whether the approvers are admins to trust is up to the caller.
+pub fn decode_position_inscription(reveal: &Transaction) -> Result<InscribedPosition, RunesError> {
+    let (content_type, body) = decode_envelope(reveal)?;
+    if content_type != POSITION_CONTENT_TYPE {
+        return Err(RunesError::InvalidInscription(format!("content type {} is not a position record", content_type)));
+    }
+    let record: PositionRecord = serde_json::from_slice(&body)
+        .map_err(|e| RunesError::InvalidInscription(format!("invalid position record: {}", e)))?;
+    let approvers = AdminSet::new(record.admin_pubkeys, record.threshold)?;
+    if !verify_multisig(&approvers, &record.signatures, &position_inscription_message(&record.position))? {
+        return Err(RunesError::InsufficientSignatures);
+    }
+    let position = PortfolioPosition {
+        safe_inscription_id: Some(inscription_id(&reveal.compute_txid())),
+        ..record.position
+    };
+    Ok(InscribedPosition { position, approvers })
+}
+
+/// Key for a single reveal; it only needs to stay secret until the reveal confirms
+fn random_reveal_key() -> Result<Keypair, RunesError> {
+    let mut bytes = [0u8; 32];
+    getrandom::getrandom(&mut bytes)
+        .map_err(|e| RunesError::InvalidInscription(format!("no randomness for a reveal key: {}", e)))?;
+    let secret_key = SecretKey::from_slice(&bytes)
+        .map_err(|_| RunesError::InvalidInscription("drew an invalid reveal key".to_string()))?;
+    Ok(Keypair::from_secret_key(&Secp256k1::new(), &secret_key))
+}
+
+/// Balance of one rune held by an address, as reported by ord
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RuneBalance {
+    /// Spaced rune name, e.g. `OTORI•VISION•TOKEN`
+    pub rune: String,
+    /// Decimal amount formatted with the rune's divisibility
+    pub amount: String,
+    pub symbol: Option<char>,
+}
+
+/// Letters a rune name may have; longer names are reserved by the runes protocol
+pub const MAX_RUNE_NAME_LEN: usize = 26;
+
+/// Highest divisibility the runes protocol allows
+pub const MAX_DIVISIBILITY: u8 = 38;
+
+/// Separator allowed between the letters of a spaced rune name
+const RUNE_SPACER: char = '•';
+
+/// Largest data push a script may contain, and so a runestone chunk
+const MAX_SCRIPT_ELEMENT_SIZE: usize = 520;
+
+/// Runestone field tags, from the runes specification
+mod tag {
+    pub const BODY: u128 = 0;
+    pub const DIVISIBILITY: u128 = 1;
+    pub const FLAGS: u128 = 2;
+    pub const SPACERS: u128 = 3;
+    pub const RUNE: u128 = 4;
+    pub const SYMBOL: u128 = 5;
+    pub const PREMINE: u128 = 6;
+    pub const CAP: u128 = 8;
+    pub const AMOUNT: u128 = 10;
+    pub const HEIGHT_START: u128 = 12;
+    pub const HEIGHT_END: u128 = 14;
+    pub const OFFSET_START: u128 = 16;
+    pub const OFFSET_END: u128 = 18;
+    pub const MINT: u128 = 20;
+    pub const POINTER: u128 = 22;
+}
+
+const FLAG_ETCHING: u128 = 1 << 0;
+const FLAG_TERMS: u128 = 1 << 1;
+const FLAG_TURBO: u128 = 1 << 2;
+
+/// Open-mint terms of an etched rune
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RuneTerms {
+    /// Units each mint creates
+    pub amount: u128,
+    /// How many times the rune may be minted
+    pub cap: u128,
+    pub height_start: Option<u64>,
+    pub height_end: Option<u64>,
+}
+
+/// Everything needed to etch a rune
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RuneEtchSpec {
+    /// Up to 26 letters A-Z, optionally separated by single `•` spacers
+    pub name: String,
+    /// Currency symbol shown next to amounts
+    pub symbol: Option<char>,
+    pub divisibility: u8,
+    /// Units allocated to the etching transaction
+    pub premine: u128,
+    /// None etches a fixed supply of `premine`
+    pub terms: Option<RuneTerms>,
+    /// Opt in to future protocol changes
+    pub turbo: bool,
+}
+
+impl RuneEtchSpec {
+    /// Numeric rune value and spacer bitmap of `name`
+    fn rune(&self) -> Result<(u128, u32), RunesError> {
+        let invalid = |reason: &str| RunesError::InvalidEtching(format!("rune name {:?} {}", self.name, reason));
+        let mut value: u128 = 0;
+        let mut spacers: u32 = 0;
+        let mut letters = 0usize;
+        let mut after_spacer = false;
+        for c in self.name.chars() {
+            match c {
+                'A'..='Z' => {
+                    if letters == MAX_RUNE_NAME_LEN {
+                        return Err(invalid("is longer than 26 letters"));
+                    }
+                    if letters > 0 {
+                        value += 1;
+                    }
+                    value = value * 26 + u128::from(c as u8 - b'A');
+                    letters += 1;
+                    after_spacer = false;
+                }
+                RUNE_SPACER if letters > 0 && !after_spacer => {
+                    spacers |= 1 << (letters - 1);
+                    after_spacer = true;
+                }
+                RUNE_SPACER => return Err(invalid("has a misplaced spacer")),
+                _ => return Err(invalid("may only contain A-Z and spacers")),
+            }
+        }
+        if letters == 0 {
+            return Err(invalid("is empty"));
+        }
+        if after_spacer {
+            return Err(invalid("ends with a spacer"));
+        }
+        Ok((value, spacers))
+    }
+
+    /// Total units that can ever exist: the premine plus every mint
+    pub fn supply(&self) -> Option<u128> {
+        let minted = match &self.terms {
+            Some(terms) => terms.cap.checked_mul(terms.amount)?,
+            None => 0,
+        };
+        self.premine.checked_add(minted)
+    }
+
+    pub fn validate(&self) -> Result<(), RunesError> {
+        self.rune()?;
+        if self.divisibility > MAX_DIVISIBILITY {
+            return Err(RunesError::InvalidEtching(format!(
+                "divisibility {} exceeds {}", self.divisibility, MAX_DIVISIBILITY
+            )));
+        }
+        if self.supply().is_none() {
+            return Err(RunesError::InvalidEtching("supply does not fit in a u128".to_string()));
+        }
+        if let Some(RuneTerms { height_start: Some(start), height_end: Some(end), .. }) = &self.terms {
+            if start > end {
+                return Err(RunesError::InvalidEtching(format!("mint window {}..{} is empty", start, end)));
+            }
+        }
+        Ok(())
+    }
+
+    /// Runestone payload: LEB128 tag/value pairs in the order ord writes them
+    pub fn payload(&self) -> Result<Vec<u8>, RunesError> {
+        self.validate()?;
+        let (rune, spacers) = self.rune()?;
+
+        let mut flags = FLAG_ETCHING;
+        if self.terms.is_some() {
+            flags |= FLAG_TERMS;
+        }
+        if self.turbo {
+            flags |= FLAG_TURBO;
+        }
+
+        let mut payload = Vec::new();
+        let mut field = |tag: u128, value: u128| {
+            encode_varint(tag, &mut payload);
+            encode_varint(value, &mut payload);
+        };
+        field(tag::FLAGS, flags);
+        field(tag::RUNE, rune);
+        if self.divisibility > 0 {
+            field(tag::DIVISIBILITY, self.divisibility.into());
+        }
+        if spacers > 0 {
+            field(tag::SPACERS, spacers.into());
+        }
+        if let Some(symbol) = self.symbol {
+            field(tag::SYMBOL, u32::from(symbol).into());
+        }
+        if self.premine > 0 {
+            field(tag::PREMINE, self.premine);
+        }
+        if let Some(terms) = &self.terms {
+            field(tag::AMOUNT, terms.amount);
+            field(tag::CAP, terms.cap);
+            if let Some(start) = terms.height_start {
+                field(tag::HEIGHT_START, start.into());
+            }
+            if let Some(end) = terms.height_end {
+                field(tag::HEIGHT_END, end.into());
+            }
+        }
+        Ok(payload)
+    }
+
+    /// `OP_RETURN OP_13` output script carrying the runestone
+    pub fn runestone_script(&self) -> Result<ScriptBuf, RunesError> {
+        Ok(runestone_script(&self.payload()?))
+    }
+
+    /// Rune commitment the etching transaction's taproot input must reveal:
+    /// the little-endian rune value without trailing zero bytes
+    pub fn commitment(&self) -> Result<Vec<u8>, RunesError> {
+        let (rune, _) = self.rune()?;
+        let bytes = rune.to_le_bytes();
+        let len = bytes.iter().rposition(|byte| *byte != 0).map_or(0, |last| last + 1);
+        Ok(bytes[..len].to_vec())
+    }
+}
+
+/// `OP_RETURN OP_13` followed by `payload` in pushes of at most 520 bytes
+fn runestone_script(payload: &[u8]) -> ScriptBuf {
+    let mut builder = Builder::new().push_opcode(OP_RETURN).push_opcode(OP_PUSHNUM_13);
+    for chunk in payload.chunks(MAX_SCRIPT_ELEMENT_SIZE) {
+        let chunk = PushBytesBuf::try_from(chunk.to_vec()).expect("chunks fit in a single push");
+        builder = builder.push_slice(chunk);
+    }
+    builder.into_script()
+}
+
+fn encode_varint(mut n: u128, out: &mut Vec<u8>) {
+    while n >> 7 > 0 {
+        out.push((n & 0x7f) as u8 | 0x80);
+        n >>= 7;
+    }
+    out.push(n as u8);
+}
+
+/// Block height and transaction index of a rune's etching, written `block:tx`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct RuneId {
+    pub block: u64,
+    pub tx: u32,
+}
+
+impl fmt::Display for RuneId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.block, self.tx)
+    }
+}
+
+impl std::str::FromStr for RuneId {
+    type Err = RunesError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || RunesError::BitcoinRPC(format!("Invalid rune id {:?}", s));
+        let (block, tx) = s.split_once(':').ok_or_else(invalid)?;
+        Ok(Self {
+            block: block.parse().map_err(|_| invalid())?,
+            tx: tx.parse().map_err(|_| invalid())?,
+        })
+    }
+}
+
+/// A broadcast etching
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EtchResult {
+    pub txid: String,
+    pub rune_id: RuneId,
+}
+
+/// Amounts of a rune in its smallest unit
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RuneSupply {
+    /// Premine plus every mint
+    pub minted: u128,
+    pub burned: u128,
+    /// Minted minus burned
+    pub circulating: u128,
+}
+
+impl RuneSupply {
+    pub fn new(minted: u128, burned: u128) -> Self {
+        Self { minted, burned, circulating: minted.saturating_sub(burned) }
+    }
+
+    /// Circulating supply minus `state.total_supply`
+    ///
+    /// Positive when the program under-counts the rune, negative when it
+    /// over-counts; the syncer submits a supply sync once this is nonzero.
+    pub fn supply_drift(&self, state: &OVTState) -> i128 {
+        i128::try_from(self.circulating).unwrap_or(i128::MAX) - i128::from(state.total_supply)
+    }
+}
+
+/// Rune entry from ord's `GET /rune/:rune`, reduced to the supply fields
+#[derive(Debug, Deserialize)]
+struct RuneResponse {
+    entry: RuneEntryResponse,
+}
+
+#[derive(Debug, Deserialize)]
+struct RuneEntryResponse {
+    burned: u128,
+    mints: u128,
+    premine: u128,
+    terms: Option<RuneTermsResponse>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RuneTermsResponse {
+    amount: Option<u128>,
+}
+
+/// Treasury UTXO holding the OVT that `RunesClient::burn_tokens` burns
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BurnSource {
+    pub rune_id: RuneId,
+    /// Must be P2WPKH or P2TR; its sats also go towards the fee
+    pub utxo: UtxoMeta,
+    /// Receives the unburned runes, in a dust-sized output
+    pub change_script: ScriptBuf,
+}
+
+/// What pays for the transactions `RunesClient::mint_tokens` and
+/// `RunesClient::burn_tokens` build
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Funding {
+    /// P2WPKH or P2TR UTXOs, all of which are spent
+    pub utxos: Vec<UtxoMeta>,
+    /// Receives the surplus once it is above dust, and minted runes
+    pub change_script: ScriptBuf,
+    /// None to use the node's estimate for `FEE_ESTIMATE_TARGET_BLOCKS`
+    pub fee_rate: Option<FeeRate>,
+}
+
+/// Runestone burning `amount` of `rune_id`: one edict to output 0, the
+/// OP_RETURN itself, with the pointer sending leftover runes to output 1
+fn burn_runestone(rune_id: RuneId, amount: u64) -> ScriptBuf {
+    transfer_runestone(&[Edict { id: rune_id, amount: amount.into(), output: 0 }], Some(1))
+}
+
+/// Runestone minting `rune_id` into output 1
+fn mint_runestone(rune_id: RuneId) -> ScriptBuf {
+    let mut payload = Vec::new();
+    for integer in [tag::MINT, rune_id.block.into(), tag::MINT, rune_id.tx.into(), tag::POINTER, 1] {
+        encode_varint(integer, &mut payload);
+    }
+    runestone_script(&payload)
+}
+
+/// Unsigned transaction spending `inputs` to `outputs` at `fee_rate`, with
+/// the surplus going to an output for `change_script` once it is above dust
+///
+/// The fee is paid on the predicted weight of the signed transaction, so
+/// every input must be P2WPKH or P2TR. A surplus that doesn't make a change
+/// output worth its own weight is left to the fee. Fails with
+/// `InsufficientFunding` when the inputs don't cover `outputs` plus the fee,
+/// and with `invalid` for inputs it can't spend.
+pub(crate) fn fund_transaction(
+    inputs: &[UtxoMeta],
+    mut outputs: Vec<TxOut>,
+    change_script: &ScriptBuf,
+    fee_rate: FeeRate,
+    invalid: impl Fn(String) -> RunesError,
+) -> Result<Transaction, RunesError> {
+    let mut input = Vec::with_capacity(inputs.len());
+    let mut predictions = Vec::with_capacity(inputs.len());
+    let mut available_sats = 0u64;
+    for utxo in inputs {
+        let script_pubkey = ScriptBuf::from_hex(&utxo.script_pubkey)
+            .map_err(|_| invalid(format!("invalid script for {}:{}", utxo.txid, utxo.vout)))?;
+        predictions.push(input_weight_prediction(&script_pubkey).ok_or_else(|| {
+            invalid(format!("{}:{} is neither P2WPKH nor P2TR", utxo.txid, utxo.vout))
+        })?);
+        let txid = utxo.txid.parse().map_err(|_| invalid(format!("invalid txid {}", utxo.txid)))?;
+        input.push(TxIn {
+            previous_output: OutPoint::new(txid, utxo.vout),
+            script_sig: ScriptBuf::new(),
+            sequence: Sequence::ENABLE_RBF_NO_LOCKTIME,
+            witness: Witness::new(),
+        });
+        available_sats = available_sats.saturating_add(utxo.amount_sats);
+    }
+    if input.is_empty() {
+        return Err(invalid("no inputs to spend".to_string()));
+    }
+
+    let fee_sats = |outputs: &[TxOut]| {
+        let weight = predict_weight(predictions.iter().copied(), outputs.iter().map(|output| output.script_pubkey.len()));
+        fee_rate.fee_wu(weight).map(Amount::to_sat).ok_or_else(|| invalid("fee overflows".to_string()))
+    };
+    let output_sats = outputs.iter().map(|output| output.value.to_sat()).sum::<u64>();
+    let needed_sats = output_sats.saturating_add(fee_sats(&outputs)?);
+    if available_sats < needed_sats {
+        return Err(RunesError::InsufficientFunding {
+            needed_sats,
+            available_sats,
+            shortfall_sats: needed_sats - available_sats,
+        });
+    }
+
+    let change = TxOut { value: Amount::ZERO, script_pubkey: change_script.clone() };
+    let with_change = [outputs.as_slice(), std::slice::from_ref(&change)].concat();
+    let change_sats = available_sats.saturating_sub(output_sats.saturating_add(fee_sats(&with_change)?));
+    if change_sats >= DEFAULT_DUST_FLOOR_SATS {
+        outputs.push(TxOut { value: Amount::from_sat(change_sats), ..change });
+    }
+
+    Ok(Transaction { version: Version::TWO, lock_time: LockTime::ZERO, input, output: outputs })
+}
+
+/// Unsigned transaction minting `rune_id` into an output for the funding's change script
+fn mint_transaction(rune_id: RuneId, funding: &Funding, fee_rate: FeeRate) -> Result<Transaction, RunesError> {
+    let outputs = vec![
+        TxOut { value: Amount::ZERO, script_pubkey: mint_runestone(rune_id) },
+        TxOut { value: Amount::from_sat(DEFAULT_DUST_FLOOR_SATS), script_pubkey: funding.change_script.clone() },
+    ];
+    fund_transaction(&funding.utxos, outputs, &funding.change_script, fee_rate, RunesError::InvalidMint)
+}
+
+/// Runestone carrying `edicts`, with unallocated runes going to `pointer`
+///
+/// Edicts are sorted by rune id so their ids can be delta-encoded.
+pub(crate) fn transfer_runestone(edicts: &[Edict], pointer: Option<u32>) -> ScriptBuf {
+    let mut payload = Vec::new();
+    if let Some(pointer) = pointer {
+        encode_varint(tag::POINTER, &mut payload);
+        encode_varint(pointer.into(), &mut payload);
+    }
+    if !edicts.is_empty() {
+        let mut edicts = edicts.to_vec();
+        edicts.sort_by_key(|edict| edict.id);
+        encode_varint(tag::BODY, &mut payload);
+        let mut previous = RuneId { block: 0, tx: 0 };
+        for edict in edicts {
+            let block_delta = edict.id.block - previous.block;
+            let tx_delta = if block_delta == 0 { edict.id.tx - previous.tx } else { edict.id.tx };
+            for integer in [block_delta.into(), tx_delta.into(), edict.amount, edict.output.into()] {
+                encode_varint(integer, &mut payload);
+            }
+            previous = edict.id;
+        }
+    }
+    runestone_script(&payload)
+}
+
+impl BurnSource {
+    /// Unsigned transaction spending `utxo`, then the funding UTXOs, into the
+    /// burn runestone, the unburned runes and change
+    fn burn_transaction(&self, amount: u64, funding: &Funding, fee_rate: FeeRate) -> Result<Transaction, RunesError> {
+        let inputs = [std::slice::from_ref(&self.utxo), funding.utxos.as_slice()].concat();
+        let outputs = vec![
+            TxOut { value: Amount::ZERO, script_pubkey: burn_runestone(self.rune_id, amount) },
+            TxOut { value: Amount::from_sat(DEFAULT_DUST_FLOOR_SATS), script_pubkey: self.change_script.clone() },
+        ];
+        fund_transaction(&inputs, outputs, &funding.change_script, fee_rate, RunesError::InvalidBurn)
+    }
+}
+
+/// Body of `POST /etch`; the backend funds, commits and reveals the etching
+#[derive(Debug, Serialize)]
+struct EtchRequest {
+    rune: String,
+    runestone: String,
+    commitment: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct EtchResponse {
+    txid: String,
+    rune_id: String,
+}
+
+/// Instruction in a runestone to move `amount` of rune `id` to output `output`
+///
+/// An `output` equal to the number of outputs splits the amount across all
+/// non-OP_RETURN outputs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Edict {
+    pub id: RuneId,
+    pub amount: u128,
+    pub output: u32,
+}
+
+/// Why a runestone is a cenotaph; cenotaphs burn every rune spent into them
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CenotaphFlaw {
+    /// An edict names an output the transaction doesn't have
+    EdictOutput,
+    /// An edict's rune id deltas don't form a valid rune id
+    EdictRuneId,
+    /// The runestone script doesn't parse
+    InvalidScript,
+    /// The runestone script contains an opcode after the magic
+    Opcode,
+    /// The etched premine plus mints doesn't fit in a u128
+    SupplyOverflow,
+    /// The edicts don't come in groups of four integers
+    TrailingIntegers,
+    /// A tag has no value
+    TruncatedField,
+    UnrecognizedEvenTag,
+    UnrecognizedFlag,
+    /// An integer isn't a valid LEB128 u128
+    Varint,
+}
+
+/// What a runestone does, for auditing supply
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RunestoneKind {
+    Cenotaph,
+    Etching,
+    Mint,
+    Transfer,
+}
+
+/// Runestone decoded from a transaction
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Runestone {
+    /// Always empty for cenotaphs
+    pub edicts: Vec<Edict>,
+    /// Whether the runestone etches a new rune
+    pub etching: bool,
+    /// Rune minted by the transaction; a cenotaph's mint is burned
+    pub mint: Option<RuneId>,
+    /// Output receiving runes not moved by edicts
+    pub pointer: Option<u32>,
+    pub flaw: Option<CenotaphFlaw>,
+}
+
+impl Runestone {
+    fn cenotaph(flaw: CenotaphFlaw) -> Self {
+        Self { edicts: Vec::new(), etching: false, mint: None, pointer: None, flaw: Some(flaw) }
+    }
+
+    pub fn is_cenotaph(&self) -> bool {
+        self.flaw.is_some()
+    }
+
+    pub fn kind(&self) -> RunestoneKind {
+        if self.is_cenotaph() {
+            RunestoneKind::Cenotaph
+        } else if self.mint.is_some() {
+            RunestoneKind::Mint
+        } else if self.etching {
+            RunestoneKind::Etching
+        } else {
+            RunestoneKind::Transfer
+        }
+    }
+}
+
+/// Rune activity of one transaction, from `RunesClient::get_transfers_for_rune`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RuneTransfer {
+    pub txid: String,
+    pub block_height: u32,
+    pub kind: RunestoneKind,
+    /// Edicts moving the rune that was asked about
+    pub edicts: Vec<Edict>,
+}
+
+/// Runestone payload of the first `OP_RETURN OP_13` output
+fn runestone_payload(tx: &Transaction) -> Option<Result<Vec<u8>, CenotaphFlaw>> {
+    for output in &tx.output {
+        let mut instructions = output.script_pubkey.instructions();
+        if instructions.next() != Some(Ok(Instruction::Op(OP_RETURN))) {
+            continue;
+        }
+        if instructions.next() != Some(Ok(Instruction::Op(OP_PUSHNUM_13))) {
+            continue;
+        }
+
+        let mut payload = Vec::new();
+        for instruction in instructions {
+            match instruction {
+                Ok(Instruction::PushBytes(push)) => payload.extend_from_slice(push.as_bytes()),
+                Ok(Instruction::Op(_)) => return Some(Err(CenotaphFlaw::Opcode)),
+                Err(_) => return Some(Err(CenotaphFlaw::InvalidScript)),
+            }
+        }
+        return Some(Ok(payload));
+    }
+    None
+}
+
+/// Decodes one LEB128 integer, returning it and the bytes it took
+fn decode_varint(buffer: &[u8]) -> Option<(u128, usize)> {
+    let mut n: u128 = 0;
+    for (i, &byte) in buffer.iter().enumerate() {
+        if i > 18 {
+            return None;
+        }
+        let value = u128::from(byte & 0x7f);
+        // The 19th byte may only carry the top two bits of a u128
+        if i == 18 && value & 0x7c != 0 {
+            return None;
+        }
+        n |= value << (7 * i);
+        if byte & 0x80 == 0 {
+            return Some((n, i + 1));
+        }
+    }
+    None
+}
+
+/// Runestone tag/value fields, consumed as they are interpreted
+struct Fields(std::collections::HashMap<u128, std::collections::VecDeque<u128>>);
+
+impl Fields {
+    /// Takes the first `N` values of `tag` if `parse` accepts them; rejected
+    /// values stay behind and, for even tags, make the runestone a cenotaph
+    fn take<const N: usize, T>(&mut self, tag: u128, parse: impl FnOnce([u128; N]) -> Option<T>) -> Option<T> {
+        let values = self.0.get_mut(&tag)?;
+        let mut taken = [0; N];
+        for (i, value) in taken.iter_mut().enumerate() {
+            *value = *values.get(i)?;
+        }
+        let parsed = parse(taken)?;
+        values.drain(..N);
+        if values.is_empty() {
+            self.0.remove(&tag);
+        }
+        Some(parsed)
+    }
+}
+
+/// Rune id `delta` past `previous`, as edicts encode them
+fn next_rune_id(previous: RuneId, block_delta: u128, tx_delta: u128) -> Option<RuneId> {
+    let block = previous.block.checked_add(u64::try_from(block_delta).ok()?)?;
+    let tx = if block_delta == 0 {
+        previous.tx.checked_add(u32::try_from(tx_delta).ok()?)?
+    } else {
+        u32::try_from(tx_delta).ok()?
+    };
+    // Only the genesis rune lives in block 0
+    (block > 0 || tx == 0).then_some(RuneId { block, tx })
+}
+
+/// Decode the runestone of `tx`, following the runes specification
+///
+/// Returns None if `tx` has no `OP_RETURN OP_13` output. Malformed runestones
+/// decode as cenotaphs rather than failing.
+pub fn decode_runestone(tx: &Transaction) -> Result<Option<Runestone>, RunesError> {
+    let payload = match runestone_payload(tx) {
+        None => return Ok(None),
+        Some(Err(flaw)) => return Ok(Some(Runestone::cenotaph(flaw))),
+        Some(Ok(payload)) => payload,
+    };
+
+    let mut integers = Vec::new();
+    let mut rest = payload.as_slice();
+    while !rest.is_empty() {
+        match decode_varint(rest) {
+            Some((integer, len)) => {
+                integers.push(integer);
+                rest = &rest[len..];
+            }
+            None => return Ok(Some(Runestone::cenotaph(CenotaphFlaw::Varint))),
+        }
+    }
+
+    let mut fields = Fields(std::collections::HashMap::new());
+    let mut edicts = Vec::new();
+    let mut flaw = None;
+    for i in (0..integers.len()).step_by(2) {
+        let tag = integers[i];
+        if tag == tag::BODY {
+            let mut id = RuneId { block: 0, tx: 0 };
+            for chunk in integers[i + 1..].chunks(4) {
+                if chunk.len() != 4 {
+                    flaw.get_or_insert(CenotaphFlaw::TrailingIntegers);
+                    break;
+                }
+                let Some(next) = next_rune_id(id, chunk[0], chunk[1]) else {
+                    flaw.get_or_insert(CenotaphFlaw::EdictRuneId);
+                    break;
+                };
+                let output = match u32::try_from(chunk[3]) {
+                    Ok(output) if output as usize <= tx.output.len() => output,
+                    _ => {
+                        flaw.get_or_insert(CenotaphFlaw::EdictOutput);
+                        break;
+                    }
+                };
+                id = next;
+                edicts.push(Edict { id, amount: chunk[2], output });
+            }
+            break;
+        }
+        let Some(&value) = integers.get(i + 1) else {
+            flaw.get_or_insert(CenotaphFlaw::TruncatedField);
+            break;
+        };
+        fields.0.entry(tag).or_default().push_back(value);
+    }
+
+    let mut flags = fields.take(tag::FLAGS, |[flags]| Some(flags)).unwrap_or_default();
+    let mut take_flag = |mask: u128| {
+        let set = flags & mask != 0;
+        flags &= !mask;
+        set
+    };
+    let etching = take_flag(FLAG_ETCHING);
+    let terms = take_flag(FLAG_TERMS);
+    take_flag(FLAG_TURBO);
+
+    if etching {
+        fields.take(tag::RUNE, |[rune]| Some(rune));
+        fields.take(tag::DIVISIBILITY, |[divisibility]| {
+            u8::try_from(divisibility).ok().filter(|divisibility| *divisibility <= MAX_DIVISIBILITY)
+        });
+        fields.take(tag::SPACERS, |[spacers]| u32::try_from(spacers).ok().filter(|spacers| *spacers < 1 << 27));
+        fields.take(tag::SYMBOL, |[symbol]| char::from_u32(u32::try_from(symbol).ok()?));
+        let premine = fields.take(tag::PREMINE, |[premine]| Some(premine)).unwrap_or_default();
+        let mut supply = Some(premine);
+        if terms {
+            let cap = fields.take(tag::CAP, |[cap]| Some(cap)).unwrap_or_default();
+            let amount = fields.take(tag::AMOUNT, |[amount]| Some(amount)).unwrap_or_default();
+            for height_or_offset in [tag::HEIGHT_START, tag::HEIGHT_END, tag::OFFSET_START, tag::OFFSET_END] {
+                fields.take(height_or_offset, |[value]| u64::try_from(value).ok());
+            }
+            supply = cap.checked_mul(amount).and_then(|minted| minted.checked_add(premine));
+        }
+        if supply.is_none() {
+            flaw.get_or_insert(CenotaphFlaw::SupplyOverflow);
+        }
+    }
+
+    let mint = fields.take(tag::MINT, |[block, tx]| {
+        let id = RuneId { block: u64::try_from(block).ok()?, tx: u32::try_from(tx).ok()? };
+        (id.block > 0 || id.tx == 0).then_some(id)
+    });
+    let pointer = fields.take(tag::POINTER, |[pointer]| {
+        u32::try_from(pointer).ok().filter(|pointer| (*pointer as usize) < tx.output.len())
+    });
+
+    if flags != 0 {
+        flaw.get_or_insert(CenotaphFlaw::UnrecognizedFlag);
+    }
+    if fields.0.keys().any(|tag| tag % 2 == 0) {
+        flaw.get_or_insert(CenotaphFlaw::UnrecognizedEvenTag);
+    }
+
+    Ok(Some(match flaw {
+        Some(flaw) => Runestone { mint, etching, ..Runestone::cenotaph(flaw) },
+        None => Runestone { edicts, etching, mint, pointer, flaw: None },
+    }))
+}
+
+/// The part of ord's `GET /address/:address` response we use
+#[derive(Debug, Deserialize)]
+struct AddressResponse {
+    #[serde(default)]
+    runes_balances: Vec<(String, String, Option<char>)>,
+}
+
+/// Admin keys allowed to approve admin actions, and how many must sign
+///
+/// Keys are kept sorted and distinct. `epoch` counts the changes made with
+/// `RunesClient::propose_admin_change`, so old approvals can't be replayed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AdminSet {
+    pubkeys: Vec<PublicKey>,
+    threshold: u8,
+    epoch: u64,
+}
+
+impl AdminSet {
+    /// Fails with `InvalidAdminKeys` unless `1 <= threshold <= distinct keys`
+    pub fn new(mut pubkeys: Vec<PublicKey>, threshold: u8) -> Result<Self, RunesError> {
+        pubkeys.sort_by_key(|pubkey| pubkey.to_bytes());
+        pubkeys.dedup();
+        if threshold == 0 || pubkeys.len() < usize::from(threshold) {
+            return Err(RunesError::InvalidAdminKeys);
+        }
+        Ok(Self { pubkeys, threshold, epoch: 0 })
+    }
+
+    /// No keys, so every admin action fails until a set is configured
+    fn unconfigured() -> Self {
+        Self { pubkeys: Vec::new(), threshold: DEFAULT_MULTISIG_THRESHOLD, epoch: 0 }
+    }
+
+    pub fn pubkeys(&self) -> &[PublicKey] {
+        &self.pubkeys
+    }
+
+    pub fn threshold(&self) -> u8 {
+        self.threshold
+    }
+
+    pub fn epoch(&self) -> u64 {
+        self.epoch
+    }
+
+    /// Bytes the current admins sign to approve this set: the tag, the epoch
+    /// as a little-endian u64, the threshold, then each key in order
+    pub fn change_message(&self) -> Vec<u8> {
+        let mut message = ADMIN_SET_TAG.to_vec();
+        message.extend_from_slice(&self.epoch.to_le_bytes());
+        message.push(self.threshold);
+        for pubkey in &self.pubkeys {
+            message.extend_from_slice(&pubkey.to_bytes());
+        }
+        message
+    }
+}
+
+/// Body of `POST /admin-set`
+#[derive(Debug, Serialize)]
+struct AdminChangeRequest {
+    pubkeys: Vec<String>,
+    threshold: u8,
+    epoch: u64,
+    signatures: Vec<String>,
 }
 
 #[derive(Debug, Clone)]
@@ -66,16 +1099,32 @@ pub struct RunesConfig {
     pub retry_config: RetryConfig,
     pub circuit_breaker: CircuitBreaker,
     pub mock_mode: bool,
+    pub admin_set: AdminSet,
 }
 
 #[allow(dead_code)]
 pub struct RunesClient {
     network: Network,
+    /// Base URL of the ord-compatible runes API
     rpc_url: String,
     auth: Option<(String, String)>,
     retry_config: RetryConfig,
     circuit_breaker: CircuitBreaker,
+    /// Serve canned responses instead of calling `rpc_url`
     mock_mode: bool,
+    /// Replaced as a whole by `propose_admin_change`
+    admin_set: Mutex<AdminSet>,
+    http_client: Client,
+    /// Node that `get_transfers_for_rune` reads blocks from
+    bitcoin_rpc: Option<Arc<BitcoinRpcClient>>,
+    burn_source: Option<BurnSource>,
+    funding: Option<Funding>,
+    /// Minted and burned amounts recorded in mock mode, by rune id
+    mock_supply: Mutex<std::collections::BTreeMap<RuneId, RuneSupply>>,
+    /// Positions added or updated in mock mode, by name
+    mock_positions: Mutex<std::collections::BTreeMap<String, PortfolioPosition>>,
+    /// Reveal transactions of mock-mode inscriptions, by inscription id
+    mock_inscriptions: Mutex<std::collections::BTreeMap<String, Transaction>>,
 }
 
 #[derive(Debug, Clone)]
@@ -85,99 +1134,617 @@ struct RetryConfig {
     max_delay: Duration,
 }
 
+/// Stops calling the runes API for `reset_timeout` after `failure_threshold`
+/// consecutive failures, then lets a single probe through
 #[derive(Debug, Clone)]
 struct CircuitBreaker {
     failure_threshold: u32,
     reset_timeout: Duration,
+    /// How long a probe may take before another one is let through
     half_open_timeout: Duration,
+    state: Arc<Mutex<BreakerState>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BreakerState {
+    Closed { failures: u32 },
+    Open { since: Instant },
+    /// A probe request went out at `since` and hasn't reported back
+    HalfOpen { since: Instant },
 }
 
 impl CircuitBreaker {
+    fn new(failure_threshold: u32, reset_timeout: Duration, half_open_timeout: Duration) -> Self {
+        Self {
+            failure_threshold,
+            reset_timeout,
+            half_open_timeout,
+            state: Arc::new(Mutex::new(BreakerState::Closed { failures: 0 })),
+        }
+    }
+
+    /// Whether a request may go out now; moves an expired Open breaker to HalfOpen
     fn check(&self) -> Result<(), RunesError> {
-        // Mock implementation
-        Ok(())
+        let mut state = self.state.lock().unwrap();
+        match *state {
+            BreakerState::Closed { .. } => Ok(()),
+            BreakerState::Open { since } if since.elapsed() >= self.reset_timeout => {
+                *state = BreakerState::HalfOpen { since: Instant::now() };
+                Ok(())
+            }
+            BreakerState::HalfOpen { since } if since.elapsed() >= self.half_open_timeout => {
+                *state = BreakerState::HalfOpen { since: Instant::now() };
+                Ok(())
+            }
+            BreakerState::Open { .. } | BreakerState::HalfOpen { .. } => Err(RunesError::CircuitOpen),
+        }
+    }
+
+    fn is_closed(&self) -> bool {
+        matches!(*self.state.lock().unwrap(), BreakerState::Closed { .. })
     }
 
     fn record_success(&self) {
-        // Mock implementation
+        *self.state.lock().unwrap() = BreakerState::Closed { failures: 0 };
     }
 
     fn record_failure(&self) {
-        // Mock implementation
+        let mut state = self.state.lock().unwrap();
+        *state = match *state {
+            BreakerState::Closed { failures } if failures + 1 < self.failure_threshold => {
+                BreakerState::Closed { failures: failures + 1 }
+            }
+            BreakerState::Open { since } => BreakerState::Open { since },
+            _ => BreakerState::Open { since: Instant::now() },
+        };
     }
 }
 
-impl RunesClient {
-    pub fn new(network: Network, rpc_url: String, auth: Option<(String, String)>) -> Self {
-        Self {
-            network,
-            rpc_url,
-            auth,
-            retry_config: RetryConfig {
-                max_attempts: 3,
+/// Whether `signature` is a valid ECDSA or Schnorr signature of `digest` by `pubkey`
+///
+/// 64 bytes may be either a compact ECDSA or a BIP340 signature, so both are tried.
+fn signature_verifies(secp: &Secp256k1<VerifyOnly>, signature: &[u8], digest: &Message, pubkey: &PublicKey) -> bool {
+    let der = ecdsa::Signature::from_der(signature)
+        .is_ok_and(|signature| secp.verify_ecdsa(digest, &signature, &pubkey.inner).is_ok());
+    let compact = || ecdsa::Signature::from_compact(signature)
+        .is_ok_and(|signature| secp.verify_ecdsa(digest, &signature, &pubkey.inner).is_ok());
+    let bip340 = || schnorr::Signature::from_slice(signature).is_ok_and(|signature| {
+        let (xonly, _) = pubkey.inner.x_only_public_key();
+        secp.verify_schnorr(&signature, digest, &xonly).is_ok()
+    });
+    der || compact() || bip340()
+}
+
+/// Whether at least `admins.threshold` distinct keys of `admins` signed `message`
+fn verify_multisig(admins: &AdminSet, signatures: &[String], message: &[u8]) -> Result<bool, RunesError> {
+    let threshold = usize::from(admins.threshold);
+    if threshold == 0 || admins.pubkeys.len() < threshold {
+        return Err(RunesError::InvalidAdminKeys);
+    }
+    if signatures.len() < threshold {
+        return Err(RunesError::InsufficientSignatures);
+    }
+
+    let signatures = signatures
+        .iter()
+        .map(|signature| hex::decode(signature.trim()).map_err(|_| RunesError::InvalidSignature))
+        .collect::<Result<Vec<_>, _>>()?;
+    if signatures.iter().any(|bytes| {
+        ecdsa::Signature::from_der(bytes).is_err() && bytes.len() != SCHNORR_SIGNATURE_SIZE
+    }) {
+        return Err(RunesError::InvalidSignature);
+    }
+
+    let digest = Message::from_digest(sha256::Hash::hash(message).to_byte_array());
+    let secp = Secp256k1::verification_only();
+    // Count keys rather than signatures so one admin can't sign twice
+    let signers = admins.pubkeys
+        .iter()
+        .filter(|admin| signatures.iter().any(|signature| signature_verifies(&secp, signature, &digest, admin)))
+        .count();
+    Ok(signers >= threshold)
+}
+
+impl RunesClient {
+    pub fn new(network: Network, rpc_url: String, auth: Option<(String, String)>) -> Self {
+        Self {
+            network,
+            rpc_url,
+            auth,
+            retry_config: RetryConfig {
+                max_attempts: 3,
                 base_delay: Duration::from_millis(500),
                 max_delay: Duration::from_millis(5000),
             },
-            circuit_breaker: CircuitBreaker {
-                failure_threshold: 3,
-                reset_timeout: Duration::from_secs(30),
-                half_open_timeout: Duration::from_secs(10),
-            },
+            circuit_breaker: CircuitBreaker::new(3, Duration::from_secs(30), Duration::from_secs(10)),
             mock_mode: false,
+            admin_set: Mutex::new(AdminSet::unconfigured()),
+            http_client: Client::builder()
+                .timeout(REQUEST_TIMEOUT)
+                .build()
+                .expect("default HTTP client configuration is valid"),
+            bitcoin_rpc: None,
+            burn_source: None,
+            funding: None,
+            mock_supply: Mutex::new(std::collections::BTreeMap::new()),
+            mock_positions: Mutex::new(std::collections::BTreeMap::new()),
+            mock_inscriptions: Mutex::new(std::collections::BTreeMap::new()),
+        }
+    }
+
+    pub fn from_config(config: RunesConfig) -> Self {
+        Self {
+            retry_config: config.retry_config,
+            circuit_breaker: config.circuit_breaker,
+            mock_mode: config.mock_mode,
+            admin_set: Mutex::new(config.admin_set),
+            ..Self::new(config.network, config.rpc_url, config.auth)
         }
     }
 
+    /// Approve admin actions with `admin_set`; without one they all fail
+    pub fn with_admin_set(mut self, admin_set: AdminSet) -> Self {
+        *self.admin_set.get_mut().unwrap() = admin_set;
+        self
+    }
+
+    /// Admin keys and threshold currently approving admin actions
+    pub fn admin_set(&self) -> AdminSet {
+        self.admin_set.lock().unwrap().clone()
+    }
+
+    /// Serve canned responses instead of calling the runes API, for tests
+    pub fn with_mock_mode(mut self, mock_mode: bool) -> Self {
+        self.mock_mode = mock_mode;
+        self
+    }
+
+    /// Read blocks from `bitcoin_rpc` when auditing rune transfers
+    pub fn with_bitcoin_rpc(mut self, bitcoin_rpc: Arc<BitcoinRpcClient>) -> Self {
+        self.bitcoin_rpc = Some(bitcoin_rpc);
+        self
+    }
+
+    /// Burn OVT out of `source` in `burn_tokens`, and mint its rune in `mint_tokens`
+    pub fn with_burn_source(mut self, source: BurnSource) -> Self {
+        self.burn_source = Some(source);
+        self
+    }
+
+    /// Pay for mints and burns out of `funding`
+    pub fn with_funding(mut self, funding: Funding) -> Self {
+        self.funding = Some(funding);
+        self
+    }
+
+    /// The funding's fee rate, or else the node's estimate
+    async fn fee_rate(&self, funding: &Funding) -> Result<FeeRate, RunesError> {
+        if let Some(fee_rate) = funding.fee_rate {
+            return Ok(fee_rate);
+        }
+        let bitcoin_rpc = self.bitcoin_rpc.as_ref().ok_or_else(|| {
+            RunesError::BitcoinRPC("no fee rate set and no Bitcoin node to estimate one".to_string())
+        })?;
+        bitcoin_rpc
+            .estimate_fee_rate(FEE_ESTIMATE_TARGET_BLOCKS)
+            .await
+            .map_err(|e| RunesError::BitcoinRPC(e.to_string()))
+    }
+
+    /// URL of `path_segments` below `rpc_url`, with each segment percent-encoded
+    fn endpoint(&self, path_segments: &[&str]) -> Result<Url, RunesError> {
+        let mut url = Url::parse(&self.rpc_url)
+            .map_err(|e| RunesError::BitcoinRPC(format!("Invalid runes API URL {}: {}", self.rpc_url, e)))?;
+        url.path_segments_mut()
+            .map_err(|_| RunesError::BitcoinRPC(format!("Invalid runes API URL {}", self.rpc_url)))?
+            .pop_if_empty()
+            .extend(path_segments);
+        Ok(url)
+    }
+
+    fn authorize(&self, request: RequestBuilder) -> RequestBuilder {
+        match &self.auth {
+            Some((username, password)) => request.basic_auth(username, Some(password)),
+            None => request,
+        }
+    }
+
+    /// Send `request`, failing on transport errors and non-success statuses
+    async fn send(&self, request: RequestBuilder) -> Result<reqwest::Response, RunesError> {
+        let response = self.authorize(request)
+            .send()
+            .await
+            .map_err(|e| RunesError::BitcoinRPC(format!("Runes API request failed: {}", e)))?;
+        let status = response.status();
+        if !status.is_success() {
+            let url = response.url().clone();
+            return Err(RunesError::BitcoinRPC(format!("Runes API returned {} for {}", status, url)));
+        }
+        Ok(response)
+    }
+
+    async fn get_json<T: DeserializeOwned>(&self, path_segments: &[&str]) -> Result<T, RunesError> {
+        self.get_json_at(self.endpoint(path_segments)?).await
+    }
+
+    async fn get_json_at<T: DeserializeOwned>(&self, url: Url) -> Result<T, RunesError> {
+        let url = &url;
+        self.with_retry(|| async move {
+            let request = self.http_client
+                .get(url.clone())
+                .header(reqwest::header::ACCEPT, "application/json");
+            self.send(request)
+                .await?
+                .json()
+                .await
+                .map_err(|e| RunesError::BitcoinRPC(format!("Invalid runes API response: {}", e)))
+        })
+        .await
+    }
+
+    async fn post_json<B: Serialize, T: DeserializeOwned>(&self, path_segments: &[&str], body: &B) -> Result<T, RunesError> {
+        let url = &self.endpoint(path_segments)?;
+        self.with_retry(|| async move {
+            let request = self.http_client
+                .post(url.clone())
+                .header(reqwest::header::ACCEPT, "application/json")
+                .json(body);
+            self.send(request)
+                .await?
+                .json()
+                .await
+                .map_err(|e| RunesError::BitcoinRPC(format!("Invalid runes API response: {}", e)))
+        })
+        .await
+    }
+
+    /// Etch a rune through `POST /etch`, once the admins have signed its runestone
+    ///
+    /// The signatures must cover the runestone script bytes, so admins approve
+    /// exactly the name, symbol and supply that get etched.
+    pub async fn etch_rune(
+        &self,
+        spec: RuneEtchSpec,
+        signatures: &[String],
+    ) -> Result<EtchResult, RunesError> {
+        let runestone = spec.runestone_script()?;
+        if !self.verify_admin_multisig(signatures, runestone.as_bytes()).await? {
+            return Err(RunesError::InsufficientSignatures);
+        }
+
+        if self.mock_mode {
+            return Ok(EtchResult {
+                txid: "mock_txid".to_string(),
+                rune_id: RuneId { block: 1, tx: 0 },
+            });
+        }
+
+        let request = EtchRequest {
+            rune: spec.name.clone(),
+            runestone: hex::encode(runestone.as_bytes()),
+            commitment: hex::encode(spec.commitment()?),
+        };
+        let response: EtchResponse = self.post_json(&["etch"], &request).await?;
+        Ok(EtchResult {
+            rune_id: response.rune_id.parse()?,
+            txid: response.txid,
+        })
+    }
+
+    /// Burn `amount` OVT on Bitcoin and return the burn txid
+    ///
+    /// The admins sign the burn runestone script bytes. The transaction spends
+    /// the treasury UTXO set with `with_burn_source` and the UTXOs set with
+    /// `with_funding`, which pay its fee, and is broadcast with
+    /// `send_transaction`, leaving the inputs for the runes API to sign. Pass
+    /// the returned txid as `payment_txid` of `OVTInstruction::BuybackBurn` so
+    /// the program's supply follows the rune's.
+    pub async fn burn_tokens(
+        &self,
+        amount: u64,
+        signatures: &[String],
+    ) -> Result<String, RunesError> {
+        if amount == 0 {
+            return Err(RunesError::InvalidBurn("amount must be positive".to_string()));
+        }
+
+        let no_source = || RunesError::InvalidBurn("no treasury UTXO to burn from".to_string());
+        let rune_id = match &self.burn_source {
+            Some(source) => source.rune_id,
+            None if self.mock_mode => self.mock_rune_id(),
+            None => return Err(no_source()),
+        };
+        let runestone = burn_runestone(rune_id, amount);
+        if !self.verify_admin_multisig(signatures, runestone.as_bytes()).await? {
+            return Err(RunesError::InsufficientSignatures);
+        }
+
+        if self.mock_mode {
+            self.record_mock_supply(rune_id, 0, amount.into());
+            return Ok("mock_txid".to_string());
+        }
+        let funding = self.funding.as_ref()
+            .ok_or_else(|| RunesError::InvalidBurn("no funding to pay the fee from".to_string()))?;
+        let fee_rate = self.fee_rate(funding).await?;
+        let tx = self.burn_source.as_ref().ok_or_else(no_source)?.burn_transaction(amount, funding, fee_rate)?;
+        self.send_transaction(tx).await
+    }
+
+    /// Mint the burn source's rune into the funding's change script and
+    /// return the mint txid
+    ///
+    /// The rune's terms fix how much a mint creates; `amount` is what mock
+    /// mode records. Outside mock mode the transaction spends the UTXOs set
+    /// with `with_funding` and is broadcast with `send_transaction`.
     pub async fn mint_tokens(
         &self,
-        _amount: u64,  // Prefixed with _ since it's unused in mock
+        amount: u64,
         signatures: Vec<String>,
-        admin_pubkeys: Vec<PublicKey>,
     ) -> Result<String, RunesError> {
-        // Mock implementation for testing
-        if signatures.len() < 3 {
-            return Err(RunesError::InsufficientSignatures);
+        self.check_signature_count(&signatures)?;
+        if self.mock_mode {
+            self.record_mock_supply(self.mock_rune_id(), amount.into(), 0);
+            return Ok("mock_txid".to_string());
         }
-        if admin_pubkeys.len() != 5 {
-            return Err(RunesError::InvalidAdminKeys);
+
+        let source = self.burn_source.as_ref()
+            .ok_or_else(|| RunesError::InvalidMint("no burn source naming the rune to mint".to_string()))?;
+        let funding = self.funding.as_ref()
+            .ok_or_else(|| RunesError::InvalidMint("no funding to pay the fee from".to_string()))?;
+        let fee_rate = self.fee_rate(funding).await?;
+        let tx = mint_transaction(source.rune_id, funding, fee_rate)?;
+        self.send_transaction(tx).await
+    }
+
+    /// Rune mock-mode mints and burns apply to: the burn source's rune, or
+    /// the one `etch_rune` pretends to etch
+    fn mock_rune_id(&self) -> RuneId {
+        self.burn_source.as_ref().map_or(RuneId { block: 1, tx: 0 }, |source| source.rune_id)
+    }
+
+    fn record_mock_supply(&self, rune_id: RuneId, minted: u128, burned: u128) {
+        let mut ledger = self.mock_supply.lock().unwrap();
+        let supply = ledger.entry(rune_id).or_default();
+        *supply = RuneSupply::new(supply.minted.saturating_add(minted), supply.burned.saturating_add(burned));
+    }
+
+    /// Minted, burned and circulating supply of `rune_id`, from ord's
+    /// `GET /rune/:rune`
+    ///
+    /// `rune_id` is a `block:tx` id or, outside mock mode, anything ord
+    /// accepts in its place. Mock mode reports what `mint_tokens` and
+    /// `burn_tokens` recorded.
+    pub async fn get_rune_supply(&self, rune_id: &str) -> Result<RuneSupply, RunesError> {
+        if self.mock_mode {
+            let rune_id: RuneId = rune_id.parse()?;
+            return Ok(self.mock_supply.lock().unwrap().get(&rune_id).copied().unwrap_or_default());
         }
-        Ok("mock_txid".to_string())
+
+        let response: RuneResponse = self.get_json(&["rune", rune_id]).await?;
+        let entry = response.entry;
+        let per_mint = entry.terms.and_then(|terms| terms.amount).unwrap_or_default();
+        let minted = entry.mints
+            .checked_mul(per_mint)
+            .and_then(|minted| minted.checked_add(entry.premine))
+            .ok_or_else(|| RunesError::BitcoinRPC(format!("Supply of rune {} overflows", rune_id)))?;
+        Ok(RuneSupply::new(minted, entry.burned))
     }
 
     pub async fn add_post_tge_position(
         &self,
-        _position: PortfolioPosition,
+        position: PortfolioPosition,
         signatures: &[String],
-        admin_pubkeys: &[PublicKey],
     ) -> Result<String, RunesError> {
         // Mock implementation for testing
-        if signatures.len() < 3 {
+        self.check_signature_count(signatures)?;
+        self.mock_positions.lock().unwrap().insert(position.name.clone(), position);
+        Ok("mock_position_id".to_string())
+    }
+
+    /// Inscribe `position` as an audit record and return the inscription id
+    ///
+    /// `admin_pubkeys` are the admins who signed `position_inscription_message`;
+    /// all must be in the admin set, and there must be at least its threshold
+    /// of them. Their keys and signatures are inscribed with the position so
+    /// `fetch_inscribed_position` can check them without the admin set of the
+    /// day. The commit and reveal are paid for by the UTXOs set with
+    /// `with_funding` and broadcast with `send_transaction`; the id is then
+    /// stored as the position's `safe_inscription_id`.
+    pub async fn inscribe_position(
+        &self,
+        position: &PortfolioPosition,
+        signatures: &[String],
+        admin_pubkeys: &[PublicKey],
+    ) -> Result<String, RunesError> {
+        let admins = self.admin_set();
+        if admin_pubkeys.iter().any(|pubkey| !admins.pubkeys.contains(pubkey)) {
+            return Err(RunesError::InvalidAdminKeys);
+        }
+        let approvers = AdminSet::new(admin_pubkeys.to_vec(), admins.threshold)?;
+        if !verify_multisig(&approvers, signatures, &position_inscription_message(position))? {
             return Err(RunesError::InsufficientSignatures);
         }
-        if admin_pubkeys.len() != 5 {
-            return Err(RunesError::InvalidAdminKeys);
+
+        let record = PositionRecord {
+            position: PortfolioPosition { safe_inscription_id: None, ..position.clone() },
+            threshold: approvers.threshold,
+            admin_pubkeys: approvers.pubkeys,
+            signatures: signatures.to_vec(),
+        };
+        let body = serde_json::to_vec(&record).expect("position records serialize to JSON");
+        let funding = self.funding.as_ref()
+            .ok_or_else(|| RunesError::InvalidInscription("no funding to pay for the inscription".to_string()))?;
+        let fee_rate = self.fee_rate(funding).await?;
+        let (commit, reveal) = inscription_transactions(POSITION_CONTENT_TYPE, &body, funding, fee_rate, &random_reveal_key()?)?;
+
+        let id = inscription_id(&reveal.compute_txid());
+        self.send_transaction(commit).await?;
+        self.send_transaction(reveal.clone()).await?;
+        if self.mock_mode {
+            self.mock_inscriptions.lock().unwrap().insert(id.clone(), reveal);
+            if let Some(stored) = self.mock_positions.lock().unwrap().get_mut(&position.name) {
+                stored.safe_inscription_id = Some(id.clone());
+            }
+            return Ok(id);
         }
-        Ok("mock_position_id".to_string())
+
+        let _: PortfolioPosition = self
+            .post_json(&["position", position.name.as_str(), "inscription"], &InscriptionUpdate { inscription_id: &id })
+            .await?;
+        Ok(id)
     }
 
-    pub async fn verify_admin_multisig(
+    /// The position inscribed as `inscription_id`, checked against the
+    /// signatures inscribed with it
+    ///
+    /// The reveal transaction is read from the node set with
+    /// `with_bitcoin_rpc`, or in mock mode from what `inscribe_position`
+    /// revealed. See `decode_position_inscription` for what is checked.
+    pub async fn fetch_inscribed_position(&self, inscription_id: &str) -> Result<InscribedPosition, RunesError> {
+        let reveal_txid = parse_inscription_id(inscription_id)?;
+        let reveal = if self.mock_mode {
+            self.mock_inscriptions.lock().unwrap().get(inscription_id).cloned()
+                .ok_or_else(|| RunesError::InvalidInscription(format!("no inscription {}", inscription_id)))?
+        } else {
+            let bitcoin_rpc = self.bitcoin_rpc.as_ref()
+                .ok_or_else(|| RunesError::BitcoinRPC("no Bitcoin node to read inscriptions from".to_string()))?;
+            bitcoin_rpc
+                .get_transaction(&reveal_txid.to_string())
+                .await
+                .map_err(|e| RunesError::BitcoinRPC(e.to_string()))?
+        };
+        if reveal.compute_txid() != reveal_txid {
+            return Err(RunesError::InvalidInscription(format!("node returned another transaction for {}", inscription_id)));
+        }
+        decode_position_inscription(&reveal)
+    }
+
+    /// Move position `name` to `new_status`, approved by the admin multisig
+    /// over `position_status_message`
+    ///
+    /// Only Pending to Active and Active to Exited are allowed, and exit
+    /// details must be given exactly when exiting. Returns the updated position.
+    pub async fn update_position_status(
         &self,
+        name: &str,
+        new_status: PositionStatus,
+        exit_details: Option<ExitDetails>,
         signatures: &[String],
-        _message: &[u8],
-        admin_pubkeys: &[PublicKey],
-    ) -> Result<bool, RunesError> {
-        // Mock implementation for testing
-        if signatures.len() < 3 {
+    ) -> Result<PortfolioPosition, RunesError> {
+        match (new_status, &exit_details) {
+            (PositionStatus::Exited, None) => {
+                return Err(RunesError::InvalidPositionUpdate("exiting requires exit details".to_string()));
+            }
+            (PositionStatus::Active | PositionStatus::Pending, Some(_)) => {
+                return Err(RunesError::InvalidPositionUpdate(format!("{:?} positions have no exit details", new_status)));
+            }
+            _ => {}
+        }
+
+        let message = position_status_message(name, new_status, exit_details.as_ref());
+        if !self.verify_admin_multisig(signatures, &message).await? {
             return Err(RunesError::InsufficientSignatures);
         }
-        if admin_pubkeys.len() != 5 {
+
+        let mut position = self.get_position(name).await?;
+        if !position.status.can_transition_to(new_status) {
+            return Err(RunesError::InvalidPositionUpdate(format!(
+                "{} can't move from {:?} to {:?}", name, position.status, new_status
+            )));
+        }
+
+        if !self.mock_mode {
+            let update = StatusUpdate { name, status: new_status, exit_details: exit_details.as_ref() };
+            return self.post_json(&["position", name, "status"], &update).await;
+        }
+
+        position.status = new_status;
+        position.exit_details = exit_details;
+        self.mock_positions.lock().unwrap().insert(name.to_string(), position.clone());
+        Ok(position)
+    }
+
+    /// Whether at least the threshold of distinct keys in the configured
+    /// admin set signed the sha256 of `message`
+    ///
+    /// Signatures are hex-encoded DER or compact ECDSA, or BIP340 Schnorr over
+    /// the admin's x-only key. A signature that matches no admin key is
+    /// ignored; one that doesn't decode fails the whole check.
+    pub async fn verify_admin_multisig(&self, signatures: &[String], message: &[u8]) -> Result<bool, RunesError> {
+        verify_multisig(&self.admin_set(), signatures, message)
+    }
+
+    /// Replace the admin set with `new_set` and `new_threshold`
+    ///
+    /// The *current* admins must approve by signing the new set's
+    /// `change_message`, which includes the next epoch so an approval can only
+    /// be used once. Outside mock mode the change is posted to
+    /// `POST /admin-set` and applied locally only once the runes API accepts it.
+    pub async fn propose_admin_change(
+        &self,
+        new_set: Vec<PublicKey>,
+        new_threshold: u8,
+        signatures: &[String],
+    ) -> Result<AdminSet, RunesError> {
+        let current = self.admin_set();
+        let proposal = AdminSet { epoch: current.epoch + 1, ..AdminSet::new(new_set, new_threshold)? };
+        if !verify_multisig(&current, signatures, &proposal.change_message())? {
+            return Err(RunesError::InsufficientSignatures);
+        }
+
+        if !self.mock_mode {
+            let request = AdminChangeRequest {
+                pubkeys: proposal.pubkeys.iter().map(|pubkey| pubkey.to_string()).collect(),
+                threshold: proposal.threshold,
+                epoch: proposal.epoch,
+                signatures: signatures.to_vec(),
+            };
+            let _: serde::de::IgnoredAny = self.post_json(&["admin-set"], &request).await?;
+        }
+
+        let mut admin_set = self.admin_set.lock().unwrap();
+        // Another change landed while this one was being confirmed
+        if admin_set.epoch != current.epoch {
             return Err(RunesError::InvalidAdminKeys);
         }
-        Ok(true)
+        *admin_set = proposal.clone();
+        Ok(proposal)
     }
 
-    pub async fn send_transaction(&self, _tx: Transaction) -> Result<String, RunesError> {
-        // Mock implementation for testing
-        Ok("mock_txid".to_string())
+    /// Signature count check of the mock admin methods
+    fn check_signature_count(&self, signatures: &[String]) -> Result<(), RunesError> {
+        let admins = self.admin_set();
+        let threshold = usize::from(admins.threshold);
+        if signatures.len() < threshold {
+            return Err(RunesError::InsufficientSignatures);
+        }
+        if admins.pubkeys.len() < threshold {
+            return Err(RunesError::InvalidAdminKeys);
+        }
+        Ok(())
+    }
+
+    /// Broadcast `tx` by posting its hex encoding to `POST /tx`; returns the txid
+    pub async fn send_transaction(&self, tx: Transaction) -> Result<String, RunesError> {
+        if self.mock_mode {
+            return self.mock_send_transaction(tx).await;
+        }
+
+        let url = &self.endpoint(&["tx"])?;
+        let tx_hex = &bitcoin::consensus::encode::serialize_hex(&tx);
+        let txid = self.with_retry(|| async move {
+            let request = self.http_client
+                .post(url.clone())
+                .header(reqwest::header::CONTENT_TYPE, "text/plain")
+                .body(tx_hex.clone());
+            self.send(request)
+                .await?
+                .text()
+                .await
+                .map_err(|e| RunesError::BitcoinRPC(format!("Invalid runes API response: {}", e)))
+        })
+        .await?;
+        Ok(txid.trim().to_string())
     }
 
     pub async fn mock_send_transaction(&self, _tx: Transaction) -> Result<String, RunesError> {
@@ -185,18 +1752,50 @@ impl RunesClient {
         Ok("mock_txid".to_string())
     }
 
-    pub async fn with_retry<F, Fut, T, E>(&self, _f: F) -> Result<T, E>
+    /// Call `f` up to `max_attempts` times, doubling the delay between
+    /// attempts from `base_delay` up to `max_delay`
+    ///
+    /// Every attempt passes through the circuit breaker: an open breaker fails
+    /// the call with `RunesError::CircuitOpen` before `f` runs, and once an
+    /// attempt trips the breaker the remaining attempts are skipped.
+    pub async fn with_retry<F, Fut, T, E>(&self, f: F) -> Result<T, E>
     where
         F: Fn() -> Fut,
         Fut: std::future::Future<Output = Result<T, E>>,
         E: From<RunesError>,
     {
-        // Mock implementation that returns an error without using unsafe code
-        Err(RunesError::BitcoinRPC("Mock retry error".to_string()).into())
+        let max_attempts = self.retry_config.max_attempts.max(1);
+        let mut delay = self.retry_config.base_delay;
+        let mut attempt = 1;
+        loop {
+            self.circuit_breaker.check()?;
+            match f().await {
+                Ok(value) => {
+                    self.circuit_breaker.record_success();
+                    return Ok(value);
+                }
+                Err(e) => {
+                    self.circuit_breaker.record_failure();
+                    if attempt >= max_attempts || !self.circuit_breaker.is_closed() {
+                        return Err(e);
+                    }
+                }
+            }
+            sleep(delay).await;
+            delay = (delay * 2).min(self.retry_config.max_delay);
+            attempt += 1;
+        }
     }
 
+    /// Fetch a portfolio position from `GET /position/:name`
     pub async fn get_position(&self, name: &str) -> Result<PortfolioPosition, RunesError> {
-        // Mock implementation
+        if !self.mock_mode {
+            return self.get_json(&["position", name]).await;
+        }
+        if let Some(position) = self.mock_positions.lock().unwrap().get(name) {
+            return Ok(position.clone());
+        }
+
         Ok(PortfolioPosition {
             name: name.to_string(),
             amount: 1000000,
@@ -207,22 +1806,939 @@ impl RunesClient {
             entry_timestamp: 1677649200,
             position_type: PositionType::PostTGE,
             status: PositionStatus::Active,
+            exit_details: None,
         })
     }
 
+    /// Positions matching `filter`, one page at a time, from `GET /positions`
+    ///
+    /// `filter` maps to the `type`, `status` and `name` query parameters and
+    /// `page` to `cursor` and `limit`. In mock mode the positions added with
+    /// `add_post_tge_position` are served instead.
+    pub async fn list_positions(&self, filter: PositionFilter, page: Pagination) -> Result<PositionPage, RunesError> {
+        let limit = page.limit.max(1);
+        if self.mock_mode {
+            let positions = self.mock_positions.lock().unwrap();
+            let matching: Vec<&PortfolioPosition> = positions.values().filter(|position| filter.matches(position)).collect();
+            let remaining: Vec<&PortfolioPosition> = matching
+                .iter()
+                .copied()
+                .filter(|position| page.cursor.as_ref().is_none_or(|cursor| position.name > *cursor))
+                .collect();
+            let items: Vec<PortfolioPosition> = remaining.iter().take(limit).map(|position| (*position).clone()).collect();
+            let next_cursor = (remaining.len() > limit).then(|| items[limit - 1].name.clone());
+            return Ok(PositionPage { items, total: matching.len(), next_cursor });
+        }
+
+        let mut url = self.endpoint(&["positions"])?;
+        {
+            let mut query = url.query_pairs_mut();
+            if let Some(position_type) = &filter.position_type {
+                query.append_pair("type", position_type.as_str());
+            }
+            if let Some(status) = &filter.status {
+                query.append_pair("status", status.as_str());
+            }
+            if let Some(name) = &filter.name_contains {
+                query.append_pair("name", name);
+            }
+            if let Some(cursor) = &page.cursor {
+                query.append_pair("cursor", cursor);
+            }
+            query.append_pair("limit", &limit.to_string());
+        }
+        self.get_json_at(url).await
+    }
+
+    /// Height ord has indexed up to, from `GET /r/blockheight`; mock mode
+    /// reports 0
+    pub async fn get_block_height(&self) -> Result<u64, RunesError> {
+        if self.mock_mode {
+            return Ok(0);
+        }
+        self.get_json(&["r", "blockheight"]).await
+    }
+
+    /// Rune balances held by `address`, from ord's `GET /address/:address`
+    pub async fn get_rune_balance(&self, address: &str) -> Result<Vec<RuneBalance>, RunesError> {
+        if self.mock_mode {
+            return Ok(vec![RuneBalance {
+                rune: "OTORI•VISION•TOKEN".to_string(),
+                amount: "1000000".to_string(),
+                symbol: None,
+            }]);
+        }
+
+        let response: AddressResponse = self.get_json(&["address", address]).await?;
+        Ok(response.runes_balances
+            .into_iter()
+            .map(|(rune, amount, symbol)| RuneBalance { rune, amount, symbol })
+            .collect())
+    }
+
+    /// Every transaction from `from_height` to the chain tip whose runestone
+    /// mints `rune_id` or moves it with an edict
+    ///
+    /// Cenotaphs that mint `rune_id` are included, since they burn the mint.
+    /// Blocks are read from the node set with `with_bitcoin_rpc`.
+    pub async fn get_transfers_for_rune(&self, rune_id: RuneId, from_height: u32) -> Result<Vec<RuneTransfer>, RunesError> {
+        let rpc = self.bitcoin_rpc
+            .as_ref()
+            .ok_or_else(|| RunesError::BitcoinRPC("No Bitcoin node configured".to_string()))?;
+        let rpc_error = |e: crate::bitcoin::BitcoinRpcError| RunesError::BitcoinRPC(e.to_string());
+
+        let tip = rpc.get_best_block_hash().await.map_err(rpc_error)?;
+        let tip_height = rpc.get_block(&tip).await.map_err(rpc_error)?.height;
+
+        let mut transfers = Vec::new();
+        for height in from_height..=tip_height {
+            let hash = rpc.get_block_hash(height).await.map_err(rpc_error)?;
+            let block = rpc.get_block_raw(&hash).await.map_err(rpc_error)?;
+            for tx in &block.txdata {
+                let Some(runestone) = decode_runestone(tx)? else {
+                    continue;
+                };
+                let edicts: Vec<Edict> = runestone.edicts.iter().filter(|edict| edict.id == rune_id).copied().collect();
+                if runestone.mint == Some(rune_id) || !edicts.is_empty() {
+                    transfers.push(RuneTransfer {
+                        txid: tx.compute_txid().to_string(),
+                        block_height: height,
+                        kind: runestone.kind(),
+                        edicts,
+                    });
+                }
+            }
+        }
+        Ok(transfers)
+    }
+
     pub async fn sign_transaction(
         &self,
         _tx: &Transaction,
         signatures: &[String],
-        admin_pubkeys: &[PublicKey],
     ) -> Result<bool, RunesError> {
         // Mock implementation for testing
-        if signatures.len() < 3 {
-            return Err(RunesError::InsufficientSignatures);
+        self.check_signature_count(signatures)?;
+        Ok(true)
+    }
+} 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bitcoin::secp256k1::{Keypair, SecretKey};
+
+    const MESSAGE: &[u8] = b"Mint 1000000 OVT";
+
+    fn secret_key(seed: u8) -> SecretKey {
+        SecretKey::from_slice(&[seed; 32]).unwrap()
+    }
+
+    fn admin_pubkeys() -> Vec<PublicKey> {
+        let secp = Secp256k1::new();
+        (1..=5).map(|seed| PublicKey::new(secret_key(seed).public_key(&secp))).collect()
+    }
+
+    fn ecdsa_signature(seed: u8, message: &[u8]) -> String {
+        let digest = Message::from_digest(sha256::Hash::hash(message).to_byte_array());
+        Secp256k1::new().sign_ecdsa(&digest, &secret_key(seed)).to_string()
+    }
+
+    fn client() -> RunesClient {
+        RunesClient::new(Network::Regtest, "http://localhost:8332".to_string(), None)
+            .with_admin_set(AdminSet::new(admin_pubkeys(), 3).unwrap())
+    }
+
+    async fn verify(signatures: Vec<String>) -> Result<bool, RunesError> {
+        client().verify_admin_multisig(&signatures, MESSAGE).await
+    }
+
+    fn always_fails(calls: &std::sync::atomic::AtomicU32) -> impl std::future::Future<Output = Result<(), RunesError>> {
+        calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        async { Err(RunesError::BitcoinRPC("connection refused".to_string())) }
+    }
+
+    #[tokio::test]
+    async fn test_retry_backs_off_exponentially() {
+        tokio::time::pause();
+        let mut client = client();
+        client.retry_config.max_attempts = 4;
+        client.circuit_breaker = CircuitBreaker::new(10, Duration::from_secs(30), Duration::from_secs(10));
+        let calls = std::sync::atomic::AtomicU32::new(0);
+
+        let started = Instant::now();
+        let result = client.with_retry(|| always_fails(&calls)).await;
+        assert!(matches!(result, Err(RunesError::BitcoinRPC(_))));
+        assert_eq!(calls.into_inner(), 4);
+        // 500ms, 1s, then 2s, all below the 5s cap
+        assert_eq!(started.elapsed(), Duration::from_millis(500 + 1_000 + 2_000));
+
+        client.retry_config.max_delay = Duration::from_millis(800);
+        let calls = std::sync::atomic::AtomicU32::new(0);
+        let started = Instant::now();
+        let _ = client.with_retry(|| always_fails(&calls)).await;
+        assert_eq!(started.elapsed(), Duration::from_millis(500 + 800 + 800));
+    }
+
+    #[tokio::test]
+    async fn test_breaker_opens_after_consecutive_failures() {
+        tokio::time::pause();
+        let client = client();
+        let calls = std::sync::atomic::AtomicU32::new(0);
+
+        // Three attempts, three failures: the breaker opens
+        assert!(matches!(client.with_retry(|| always_fails(&calls)).await, Err(RunesError::BitcoinRPC(_))));
+        assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 3);
+
+        // Open: nothing goes out
+        assert!(matches!(client.with_retry(|| always_fails(&calls)).await, Err(RunesError::CircuitOpen)));
+        assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_breaker_probes_after_reset_timeout() {
+        tokio::time::pause();
+        let client = client();
+        let calls = std::sync::atomic::AtomicU32::new(0);
+        let _ = client.with_retry(|| always_fails(&calls)).await;
+
+        tokio::time::advance(Duration::from_secs(29)).await;
+        assert!(matches!(client.circuit_breaker.check(), Err(RunesError::CircuitOpen)));
+
+        // A failed probe reopens the breaker straight away, without retrying
+        tokio::time::advance(Duration::from_secs(1)).await;
+        assert!(matches!(client.with_retry(|| always_fails(&calls)).await, Err(RunesError::BitcoinRPC(_))));
+        assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 4);
+        assert!(matches!(client.circuit_breaker.check(), Err(RunesError::CircuitOpen)));
+
+        // A successful probe closes it
+        tokio::time::advance(Duration::from_secs(30)).await;
+        let result: Result<u32, RunesError> = client.with_retry(|| async { Ok(7) }).await;
+        assert_eq!(result.unwrap(), 7);
+        assert!(client.circuit_breaker.is_closed());
+    }
+
+    #[tokio::test]
+    async fn test_half_open_allows_one_probe_at_a_time() {
+        tokio::time::pause();
+        let breaker = CircuitBreaker::new(1, Duration::from_secs(30), Duration::from_secs(10));
+        breaker.record_failure();
+        assert!(matches!(breaker.check(), Err(RunesError::CircuitOpen)));
+
+        tokio::time::advance(Duration::from_secs(30)).await;
+        assert!(breaker.check().is_ok());
+        // The first probe hasn't reported back yet
+        assert!(matches!(breaker.check(), Err(RunesError::CircuitOpen)));
+
+        // ...and is given up on after the half-open timeout
+        tokio::time::advance(Duration::from_secs(10)).await;
+        assert!(breaker.check().is_ok());
+        breaker.record_success();
+        assert!(breaker.check().is_ok());
+    }
+
+    fn ovt_spec() -> RuneEtchSpec {
+        RuneEtchSpec {
+            name: "OTORI•VISION•TOKEN".to_string(),
+            symbol: Some('O'),
+            divisibility: 2,
+            premine: 2_100_000_000,
+            terms: None,
+            turbo: false,
         }
-        if admin_pubkeys.len() < 3 {
-            return Err(RunesError::InvalidAdminKeys);
+    }
+
+    #[test]
+    fn test_rune_name_values() {
+        let value = |name: &str| RuneEtchSpec { name: name.to_string(), ..ovt_spec() }.rune().map(|(rune, _)| rune);
+        assert_eq!(value("A").unwrap(), 0);
+        assert_eq!(value("Z").unwrap(), 25);
+        assert_eq!(value("AA").unwrap(), 26);
+        assert_eq!(value("AAA").unwrap(), 702);
+        assert_eq!(ovt_spec().rune().unwrap(), (26_488_060_834_356_628_608_179, (1 << 4) | (1 << 10)));
+
+        let longest = "Z".repeat(MAX_RUNE_NAME_LEN);
+        assert!(value(&longest).is_ok());
+        for name in ["", "ovt", "O V T", "•OVT", "OVT•", "O••VT", "A".repeat(MAX_RUNE_NAME_LEN + 1).as_str()] {
+            assert!(matches!(value(name), Err(RunesError::InvalidEtching(_))), "{:?}", name);
         }
-        Ok(true)
     }
-} 
\ No newline at end of file
+
+    #[test]
+    fn test_runestone_vectors() {
+        // Flags=etching, Rune, Divisibility=2, Spacers, Symbol='O', Premine
+        assert_eq!(
+            hex::encode(ovt_spec().runestone_script().unwrap().as_bytes()),
+            "6a5d1b020104b3a9f3dee1a283d8ebb7160102039008054f0680eaade907"
+        );
+
+        let open_mint = RuneEtchSpec {
+            name: "OVT".to_string(),
+            symbol: Some('⊙'),
+            divisibility: 0,
+            premine: 0,
+            terms: Some(RuneTerms { amount: 1_000, cap: 21_000, height_start: Some(840_000), height_end: None }),
+            turbo: true,
+        };
+        // Flags=etching|terms|turbo, Rune, Symbol, Amount, Cap, HeightStart
+        assert_eq!(
+            hex::encode(open_mint.payload().unwrap()),
+            "020704eb530599450ae8070888a4010cc0a233"
+        );
+        assert_eq!(open_mint.supply(), Some(21_000_000));
+
+        assert_eq!(hex::encode(ovt_spec().commitment().unwrap()), "b3d4dc1b160db0eb9b05");
+        let aaa = RuneEtchSpec { name: "AAA".to_string(), ..ovt_spec() };
+        assert_eq!(aaa.commitment().unwrap(), vec![0xbe, 0x02]);
+    }
+
+    #[test]
+    fn test_etching_validation() {
+        let overflow = RuneEtchSpec {
+            premine: u128::MAX,
+            terms: Some(RuneTerms { amount: 1, cap: 1, height_start: None, height_end: None }),
+            ..ovt_spec()
+        };
+        assert_eq!(overflow.supply(), None);
+        assert!(matches!(overflow.validate(), Err(RunesError::InvalidEtching(_))));
+
+        let too_divisible = RuneEtchSpec { divisibility: MAX_DIVISIBILITY + 1, ..ovt_spec() };
+        assert!(matches!(too_divisible.payload(), Err(RunesError::InvalidEtching(_))));
+
+        let backwards = RuneEtchSpec {
+            terms: Some(RuneTerms { amount: 1, cap: 1, height_start: Some(10), height_end: Some(9) }),
+            ..ovt_spec()
+        };
+        assert!(matches!(backwards.validate(), Err(RunesError::InvalidEtching(_))));
+    }
+
+    #[tokio::test]
+    async fn test_etching_requires_admin_multisig() {
+        let client = client().with_mock_mode(true);
+        let runestone = ovt_spec().runestone_script().unwrap();
+        let sign = |seed| ecdsa_signature(seed, runestone.as_bytes());
+
+        let result = client.etch_rune(ovt_spec(), &[sign(1), sign(2), sign(3)]).await.unwrap();
+        assert_eq!(result.rune_id.to_string(), "1:0");
+
+        // Signatures over a different runestone don't authorize this one
+        let other = RuneEtchSpec { premine: 1, ..ovt_spec() }.runestone_script().unwrap();
+        let stale: Vec<String> = (1..=3).map(|seed| ecdsa_signature(seed, other.as_bytes())).collect();
+        assert!(matches!(
+            client.etch_rune(ovt_spec(), &stale).await,
+            Err(RunesError::InsufficientSignatures)
+        ));
+    }
+
+    #[test]
+    fn test_rune_id_round_trip() {
+        let id: RuneId = "840000:12".parse().unwrap();
+        assert_eq!(id, RuneId { block: 840_000, tx: 12 });
+        assert_eq!(id.to_string(), "840000:12");
+        assert!("840000".parse::<RuneId>().is_err());
+    }
+
+    fn pending_position() -> PortfolioPosition {
+        PortfolioPosition {
+            name: "Test Project".to_string(),
+            amount: 1_000_000,
+            price_per_token: 100,
+            currency_spent: 100_000_000,
+            transaction_id: None,
+            safe_inscription_id: None,
+            entry_timestamp: 1677649200,
+            position_type: PositionType::PreTGE,
+            status: PositionStatus::Pending,
+            exit_details: None,
+        }
+    }
+
+    fn approve(status: PositionStatus, exit_details: Option<&ExitDetails>) -> Vec<String> {
+        let message = position_status_message("Test Project", status, exit_details);
+        (1..=3).map(|seed| ecdsa_signature(seed, &message)).collect()
+    }
+
+    #[tokio::test]
+    async fn test_position_lifecycle() {
+        let client = client().with_mock_mode(true);
+        client.add_post_tge_position(pending_position(), &approve(PositionStatus::Active, None)).await.unwrap();
+        assert_eq!(client.get_position("Test Project").await.unwrap().status, PositionStatus::Pending);
+
+        let active = client
+            .update_position_status("Test Project", PositionStatus::Active, None, &approve(PositionStatus::Active, None))
+            .await
+            .unwrap();
+        assert_eq!(active.status, PositionStatus::Active);
+        assert_eq!(active.realized_pnl_sats(), None);
+        assert_eq!(client.get_position("Test Project").await.unwrap().status, PositionStatus::Active);
+
+        let exit = ExitDetails {
+            exit_price_per_token: 130,
+            proceeds_sats: 130_000_000,
+            exit_txid: "03".repeat(32),
+        };
+        // Signatures approving a different exit don't count
+        let lowball = ExitDetails { proceeds_sats: 1, ..exit.clone() };
+        assert!(matches!(
+            client
+                .update_position_status("Test Project", PositionStatus::Exited, Some(exit.clone()), &approve(PositionStatus::Exited, Some(&lowball)))
+                .await,
+            Err(RunesError::InsufficientSignatures)
+        ));
+
+        let signatures = approve(PositionStatus::Exited, Some(&exit));
+        client
+            .update_position_status("Test Project", PositionStatus::Exited, Some(exit.clone()), &signatures)
+            .await
+            .unwrap();
+        let exited = client.get_position("Test Project").await.unwrap();
+        assert_eq!(exited.status, PositionStatus::Exited);
+        assert_eq!(exited.exit_details, Some(exit));
+        assert_eq!(exited.realized_pnl_sats(), Some(30_000_000));
+    }
+
+    #[tokio::test]
+    async fn test_illegal_position_transitions() {
+        let client = client().with_mock_mode(true);
+        let exit = ExitDetails { exit_price_per_token: 50, proceeds_sats: 40_000_000, exit_txid: "04".repeat(32) };
+        client
+            .add_post_tge_position(PortfolioPosition { status: PositionStatus::Exited, exit_details: Some(exit.clone()), ..pending_position() }, &approve(PositionStatus::Active, None))
+            .await
+            .unwrap();
+        assert_eq!(client.get_position("Test Project").await.unwrap().realized_pnl_sats(), Some(-60_000_000));
+
+        let reopen = client
+            .update_position_status("Test Project", PositionStatus::Active, None, &approve(PositionStatus::Active, None))
+            .await;
+        assert!(matches!(reopen, Err(RunesError::InvalidPositionUpdate(_))));
+        assert_eq!(client.get_position("Test Project").await.unwrap().status, PositionStatus::Exited);
+
+        // Exit details go with exiting and nothing else
+        let no_details = client
+            .update_position_status("Test Project", PositionStatus::Exited, None, &approve(PositionStatus::Exited, None))
+            .await;
+        assert!(matches!(no_details, Err(RunesError::InvalidPositionUpdate(_))));
+        let stray_details = client
+            .update_position_status("Test Project", PositionStatus::Active, Some(exit.clone()), &approve(PositionStatus::Active, Some(&exit)))
+            .await;
+        assert!(matches!(stray_details, Err(RunesError::InvalidPositionUpdate(_))));
+
+        assert!(!PositionStatus::Pending.can_transition_to(PositionStatus::Exited));
+        assert!(!PositionStatus::Active.can_transition_to(PositionStatus::Pending));
+    }
+
+    #[tokio::test]
+    async fn test_list_positions() {
+        let client = client().with_mock_mode(true);
+        let signatures = approve(PositionStatus::Active, None);
+        for (name, position_type, status) in [
+            ("Alpha Labs", PositionType::PreTGE, PositionStatus::Pending),
+            ("Beta Protocol", PositionType::PostTGE, PositionStatus::Active),
+            ("Gamma Labs", PositionType::PostTGE, PositionStatus::Active),
+            ("Delta Finance", PositionType::PreTGE, PositionStatus::Active),
+            ("Epsilon", PositionType::PostTGE, PositionStatus::Exited),
+        ] {
+            let position = PortfolioPosition { name: name.to_string(), position_type, status, ..pending_position() };
+            client.add_post_tge_position(position, &signatures).await.unwrap();
+        }
+        let names = |page: &PositionPage| page.items.iter().map(|position| position.name.clone()).collect::<Vec<_>>();
+
+        let post_tge = PositionFilter { position_type: Some(PositionType::PostTGE), ..Default::default() };
+        let page = client.list_positions(post_tge, Pagination::default()).await.unwrap();
+        assert_eq!(names(&page), vec!["Beta Protocol", "Epsilon", "Gamma Labs"]);
+        assert_eq!(page.next_cursor, None);
+
+        let active_pre_tge = PositionFilter {
+            position_type: Some(PositionType::PreTGE),
+            status: Some(PositionStatus::Active),
+            ..Default::default()
+        };
+        assert_eq!(names(&client.list_positions(active_pre_tge, Pagination::default()).await.unwrap()), vec!["Delta Finance"]);
+
+        let labs = PositionFilter { name_contains: Some("labs".to_string()), ..Default::default() };
+        assert_eq!(names(&client.list_positions(labs, Pagination::default()).await.unwrap()), vec!["Alpha Labs", "Gamma Labs"]);
+
+        // Two pages of three
+        let first = client.list_positions(PositionFilter::default(), Pagination { cursor: None, limit: 3 }).await.unwrap();
+        assert_eq!(names(&first), vec!["Alpha Labs", "Beta Protocol", "Delta Finance"]);
+        assert_eq!(first.total, 5);
+        assert_eq!(first.next_cursor.as_deref(), Some("Delta Finance"));
+        let second = client
+            .list_positions(PositionFilter::default(), Pagination { cursor: first.next_cursor, limit: 3 })
+            .await
+            .unwrap();
+        assert_eq!(names(&second), vec!["Epsilon", "Gamma Labs"]);
+        assert_eq!(second.total, 5);
+        assert_eq!(second.next_cursor, None);
+    }
+
+    #[tokio::test]
+    async fn test_mock_rune_supply() {
+        let client = client().with_mock_mode(true);
+        let mint_signatures: Vec<String> = (1..=3).map(|seed| ecdsa_signature(seed, MESSAGE)).collect();
+        client.mint_tokens(1_000_000, mint_signatures.clone()).await.unwrap();
+        client.mint_tokens(500_000, mint_signatures).await.unwrap();
+
+        let runestone = burn_runestone(RuneId { block: 1, tx: 0 }, 200_000);
+        let burn_signatures: Vec<String> = (1..=3).map(|seed| ecdsa_signature(seed, runestone.as_bytes())).collect();
+        client.burn_tokens(200_000, &burn_signatures).await.unwrap();
+
+        let supply = client.get_rune_supply("1:0").await.unwrap();
+        assert_eq!(supply, RuneSupply { minted: 1_500_000, burned: 200_000, circulating: 1_300_000 });
+        assert_eq!(client.get_rune_supply("840000:1").await.unwrap(), RuneSupply::default());
+        assert!(client.get_rune_supply("OTORI•VISION•TOKEN").await.is_err());
+
+        let mut state = OVTState::new([2u8; 33]);
+        state.total_supply = 1_300_000;
+        assert_eq!(supply.supply_drift(&state), 0);
+        // The program still counts runes that were burned on Bitcoin
+        state.total_supply = 1_500_000;
+        assert_eq!(supply.supply_drift(&state), -200_000);
+        state.total_supply = 1_000_000;
+        assert_eq!(supply.supply_drift(&state), 300_000);
+    }
+
+    const P2WPKH: &str = "0014751e76e8199196d454941c45d1b3a323f1433bd6";
+
+    fn p2wpkh_utxo(txid_byte: &str, vout: u32, amount_sats: u64) -> UtxoMeta {
+        UtxoMeta { script_pubkey: P2WPKH.to_string(), ..UtxoMeta::new(txid_byte.repeat(32), vout, amount_sats) }
+    }
+
+    fn burn_source() -> BurnSource {
+        BurnSource {
+            rune_id: RuneId { block: 840_000, tx: 1 },
+            utxo: p2wpkh_utxo("11", 0, 10_000),
+            change_script: ScriptBuf::from_hex(P2WPKH).unwrap(),
+        }
+    }
+
+    fn fee_rate() -> FeeRate {
+        FeeRate::from_sat_per_vb(2).unwrap()
+    }
+
+    fn funding(utxos: Vec<UtxoMeta>) -> Funding {
+        Funding { utxos, change_script: ScriptBuf::from_hex(P2WPKH).unwrap(), fee_rate: Some(fee_rate()) }
+    }
+
+    /// Fee at `fee_rate()` for `inputs` P2WPKH inputs spent into `outputs`
+    fn expected_fee(inputs: usize, outputs: &[TxOut]) -> u64 {
+        let weight = predict_weight(
+            vec![bitcoin::transaction::InputWeightPrediction::P2WPKH_MAX; inputs],
+            outputs.iter().map(|output| output.script_pubkey.len()),
+        );
+        fee_rate().fee_wu(weight).unwrap().to_sat()
+    }
+
+    fn fee_paid(tx: &Transaction, input_sats: u64) -> u64 {
+        input_sats - tx.output.iter().map(|output| output.value.to_sat()).sum::<u64>()
+    }
+
+    /// Burn outputs before any change, for working out the fee without one
+    fn burn_outputs(amount: u64) -> Vec<TxOut> {
+        vec![
+            TxOut { value: Amount::ZERO, script_pubkey: burn_runestone(burn_source().rune_id, amount) },
+            TxOut { value: Amount::from_sat(DEFAULT_DUST_FLOOR_SATS), script_pubkey: burn_source().change_script },
+        ]
+    }
+
+    #[test]
+    fn test_burn_transaction() {
+        let tx = burn_source().burn_transaction(2_500, &funding(Vec::new()), fee_rate()).unwrap();
+        assert_eq!(tx.output.len(), 3);
+        assert_eq!(tx.output[1].value, Amount::from_sat(DEFAULT_DUST_FLOOR_SATS));
+        assert_eq!(fee_paid(&tx, 10_000), expected_fee(1, &tx.output));
+        assert_eq!(tx.output[2].value.to_sat(), 10_000 - DEFAULT_DUST_FLOOR_SATS - expected_fee(1, &tx.output));
+
+        // The edict targets the OP_RETURN output, which burns the runes
+        let runestone = decode_runestone(&tx).unwrap().unwrap();
+        assert_eq!(runestone.flaw, None);
+        assert_eq!(runestone.pointer, Some(1));
+        assert_eq!(runestone.edicts, vec![Edict { id: burn_source().rune_id, amount: 2_500, output: 0 }]);
+    }
+
+    #[test]
+    fn test_exact_fit_has_no_change() {
+        let fee = expected_fee(1, &burn_outputs(2_500));
+        let exact = BurnSource { utxo: p2wpkh_utxo("11", 0, DEFAULT_DUST_FLOOR_SATS + fee), ..burn_source() };
+        let tx = exact.burn_transaction(2_500, &funding(Vec::new()), fee_rate()).unwrap();
+        assert_eq!(tx.output, burn_outputs(2_500));
+        assert_eq!(fee_paid(&tx, DEFAULT_DUST_FLOOR_SATS + fee), fee);
+
+        // A surplus too small for a change output goes to the fee
+        let input_sats = DEFAULT_DUST_FLOOR_SATS + fee + 300;
+        let close = BurnSource { utxo: p2wpkh_utxo("11", 0, input_sats), ..burn_source() };
+        let tx = close.burn_transaction(2_500, &funding(Vec::new()), fee_rate()).unwrap();
+        assert_eq!(tx.output.len(), 2);
+        assert_eq!(fee_paid(&tx, input_sats), fee + 300);
+    }
+
+    #[test]
+    fn test_underfunded_burn_lists_shortfall() {
+        let needed = DEFAULT_DUST_FLOOR_SATS + expected_fee(1, &burn_outputs(2_500));
+        let short = BurnSource { utxo: p2wpkh_utxo("11", 0, needed - 1), ..burn_source() };
+        match short.burn_transaction(2_500, &funding(Vec::new()), fee_rate()) {
+            Err(RunesError::InsufficientFunding { needed_sats, available_sats, shortfall_sats }) => {
+                assert_eq!((needed_sats, available_sats, shortfall_sats), (needed, needed - 1, 1));
+            }
+            other => panic!("expected InsufficientFunding, got {:?}", other),
+        }
+
+        // A funding UTXO makes up the difference, and pays for its own input
+        let tx = short.burn_transaction(2_500, &funding(vec![p2wpkh_utxo("22", 3, 5_000)]), fee_rate()).unwrap();
+        let previous: Vec<String> = tx.input.iter().map(|input| input.previous_output.to_string()).collect();
+        assert_eq!(previous, vec![format!("{}:0", "11".repeat(32)), format!("{}:3", "22".repeat(32))]);
+        assert_eq!(tx.output.len(), 3);
+        assert_eq!(fee_paid(&tx, needed - 1 + 5_000), expected_fee(2, &tx.output));
+    }
+
+    #[test]
+    fn test_mint_transaction() {
+        let rune_id = burn_source().rune_id;
+        let tx = mint_transaction(rune_id, &funding(vec![p2wpkh_utxo("33", 1, 20_000)]), fee_rate()).unwrap();
+        let runestone = decode_runestone(&tx).unwrap().unwrap();
+        assert_eq!((runestone.kind(), runestone.mint, runestone.pointer), (RunestoneKind::Mint, Some(rune_id), Some(1)));
+        assert_eq!(tx.output[1].value, Amount::from_sat(DEFAULT_DUST_FLOOR_SATS));
+        assert_eq!(tx.output.len(), 3);
+        assert_eq!(fee_paid(&tx, 20_000), expected_fee(1, &tx.output));
+
+        assert!(matches!(
+            mint_transaction(rune_id, &funding(vec![p2wpkh_utxo("33", 1, 600)]), fee_rate()),
+            Err(RunesError::InsufficientFunding { available_sats: 600, .. })
+        ));
+        assert!(matches!(mint_transaction(rune_id, &funding(Vec::new()), fee_rate()), Err(RunesError::InvalidMint(_))));
+        let legacy = UtxoMeta {
+            script_pubkey: "76a914751e76e8199196d454941c45d1b3a323f1433bd688ac".to_string(),
+            ..p2wpkh_utxo("33", 1, 20_000)
+        };
+        assert!(matches!(mint_transaction(rune_id, &funding(vec![legacy]), fee_rate()), Err(RunesError::InvalidMint(_))));
+    }
+
+    #[tokio::test]
+    async fn test_fee_rate_needs_a_source() {
+        let runestone = burn_runestone(burn_source().rune_id, 2_500);
+        let signatures: Vec<String> = (1..=3).map(|seed| ecdsa_signature(seed, runestone.as_bytes())).collect();
+        let unfunded = client().with_burn_source(burn_source());
+        assert!(matches!(unfunded.burn_tokens(2_500, &signatures).await, Err(RunesError::InvalidBurn(_))));
+
+        // Without a fixed rate the node is asked, and there is none to ask
+        let estimated = client()
+            .with_burn_source(burn_source())
+            .with_funding(Funding { fee_rate: None, ..funding(Vec::new()) });
+        assert!(matches!(estimated.burn_tokens(2_500, &signatures).await, Err(RunesError::BitcoinRPC(_))));
+    }
+
+    #[tokio::test]
+    async fn test_burn_requires_admin_multisig() {
+        let client = client().with_mock_mode(true);
+        let runestone = burn_runestone(RuneId { block: 1, tx: 0 }, 2_500);
+        let signatures: Vec<String> = (1..=3).map(|seed| ecdsa_signature(seed, runestone.as_bytes())).collect();
+        assert_eq!(client.burn_tokens(2_500, &signatures).await.unwrap(), "mock_txid");
+
+        // Signatures for one amount don't authorize another
+        assert!(matches!(
+            client.burn_tokens(2_501, &signatures).await,
+            Err(RunesError::InsufficientSignatures)
+        ));
+        assert!(matches!(
+            client.burn_tokens(2_500, &signatures[..2]).await,
+            Err(RunesError::InsufficientSignatures)
+        ));
+        assert!(matches!(
+            client.burn_tokens(0, &signatures).await,
+            Err(RunesError::InvalidBurn(_))
+        ));
+
+        // Outside mock mode there must be a treasury UTXO to spend
+        assert!(matches!(
+            client().burn_tokens(2_500, &signatures).await,
+            Err(RunesError::InvalidBurn(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_three_of_five() {
+        let signatures = vec![ecdsa_signature(1, MESSAGE), ecdsa_signature(3, MESSAGE), ecdsa_signature(5, MESSAGE)];
+        assert!(verify(signatures).await.unwrap());
+
+        // Compact ECDSA and Schnorr signatures count too
+        let secp = Secp256k1::new();
+        let digest = Message::from_digest(sha256::Hash::hash(MESSAGE).to_byte_array());
+        let compact = hex::encode(secp.sign_ecdsa(&digest, &secret_key(2)).serialize_compact());
+        let schnorr = secp.sign_schnorr_no_aux_rand(&digest, &Keypair::from_secret_key(&secp, &secret_key(4)));
+        let signatures = vec![ecdsa_signature(1, MESSAGE), compact, hex::encode(schnorr.serialize())];
+        assert!(verify(signatures).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_key_counts_once() {
+        let signature = ecdsa_signature(1, MESSAGE);
+        assert!(!verify(vec![signature.clone(), signature, ecdsa_signature(2, MESSAGE)]).await.unwrap());
+
+        // Listing the same admin key twice doesn't lower the bar either
+        let pubkeys = admin_pubkeys();
+        let repeated = vec![pubkeys[0], pubkeys[0], pubkeys[0], pubkeys[1]];
+        assert!(matches!(AdminSet::new(repeated, 3), Err(RunesError::InvalidAdminKeys)));
+        assert_eq!(AdminSet::new(vec![pubkeys[1], pubkeys[0], pubkeys[1]], 2).unwrap().pubkeys().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_forged_signature() {
+        // Signed by an admin, but over a different message
+        let forged = ecdsa_signature(3, b"Mint 9999999 OVT");
+        assert!(!verify(vec![ecdsa_signature(1, MESSAGE), ecdsa_signature(2, MESSAGE), forged]).await.unwrap());
+
+        let garbage = vec![ecdsa_signature(1, MESSAGE), ecdsa_signature(2, MESSAGE), "sig3".to_string()];
+        assert!(matches!(verify(garbage).await, Err(RunesError::InvalidSignature)));
+    }
+
+    #[tokio::test]
+    async fn test_unknown_signer() {
+        let outsider = ecdsa_signature(9, MESSAGE);
+        assert!(!verify(vec![ecdsa_signature(1, MESSAGE), ecdsa_signature(2, MESSAGE), outsider]).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_configurable_threshold() {
+        let signatures = vec![ecdsa_signature(1, MESSAGE), ecdsa_signature(2, MESSAGE)];
+        let client = client().with_admin_set(AdminSet::new(admin_pubkeys(), 2).unwrap());
+        assert!(client.verify_admin_multisig(&signatures, MESSAGE).await.unwrap());
+        assert!(matches!(verify(signatures).await, Err(RunesError::InsufficientSignatures)));
+    }
+
+    fn pubkeys(seeds: std::ops::RangeInclusive<u8>) -> Vec<PublicKey> {
+        let secp = Secp256k1::new();
+        seeds.map(|seed| PublicKey::new(secret_key(seed).public_key(&secp))).collect()
+    }
+
+    fn approve_admin_change(signers: &[u8], new_set: Vec<PublicKey>, threshold: u8, epoch: u64) -> Vec<String> {
+        let proposal = AdminSet { epoch, ..AdminSet::new(new_set, threshold).unwrap() };
+        signers.iter().map(|seed| ecdsa_signature(*seed, &proposal.change_message())).collect()
+    }
+
+    #[tokio::test]
+    async fn test_admin_rotation() {
+        let client = client().with_mock_mode(true);
+        assert_eq!(client.admin_set().pubkeys(), AdminSet::new(admin_pubkeys(), 3).unwrap().pubkeys());
+
+        // Admins 1-3 hand over to keys 4-8
+        let signatures = approve_admin_change(&[1, 2, 3], pubkeys(4..=8), 3, 1);
+        let rotated = client.propose_admin_change(pubkeys(4..=8), 3, &signatures).await.unwrap();
+        assert_eq!(rotated.epoch(), 1);
+        assert_eq!(client.admin_set(), rotated);
+
+        // Retired admins no longer count; new ones do
+        let message = b"Mint 1 OVT";
+        let retired: Vec<String> = (1..=3).map(|seed| ecdsa_signature(seed, message)).collect();
+        assert!(!client.verify_admin_multisig(&retired, message).await.unwrap());
+        let current: Vec<String> = (6..=8).map(|seed| ecdsa_signature(seed, message)).collect();
+        assert!(client.verify_admin_multisig(&current, message).await.unwrap());
+
+        // The same approval can't be replayed into a later epoch
+        assert!(matches!(
+            client.propose_admin_change(pubkeys(4..=8), 3, &signatures).await,
+            Err(RunesError::InsufficientSignatures)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_admin_threshold_increase() {
+        let client = client().with_mock_mode(true);
+        let signatures = approve_admin_change(&[1, 3, 5], admin_pubkeys(), 4, 1);
+        assert_eq!(client.propose_admin_change(admin_pubkeys(), 4, &signatures).await.unwrap().threshold(), 4);
+
+        let message = b"Mint 1 OVT";
+        let three: Vec<String> = (1..=3).map(|seed| ecdsa_signature(seed, message)).collect();
+        assert!(matches!(client.verify_admin_multisig(&three, message).await, Err(RunesError::InsufficientSignatures)));
+        let four: Vec<String> = (1..=4).map(|seed| ecdsa_signature(seed, message)).collect();
+        assert!(client.verify_admin_multisig(&four, message).await.unwrap());
+
+        // A threshold the new set can't meet is refused outright
+        assert!(matches!(
+            client.propose_admin_change(admin_pubkeys(), 6, &four).await,
+            Err(RunesError::InvalidAdminKeys)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_admin_change_needs_current_threshold() {
+        let client = client().with_mock_mode(true);
+        let original = client.admin_set();
+
+        let two = approve_admin_change(&[1, 2], pubkeys(6..=10), 3, 1);
+        assert!(matches!(
+            client.propose_admin_change(pubkeys(6..=10), 3, &two).await,
+            Err(RunesError::InsufficientSignatures)
+        ));
+
+        // Two admins plus a signature from a proposed key are still short
+        let mut padded = two.clone();
+        padded.extend(approve_admin_change(&[6], pubkeys(6..=10), 3, 1));
+        assert!(matches!(
+            client.propose_admin_change(pubkeys(6..=10), 3, &padded).await,
+            Err(RunesError::InsufficientSignatures)
+        ));
+        assert_eq!(client.admin_set(), original);
+    }
+
+    fn fixture(hex: &str) -> Transaction {
+        bitcoin::consensus::encode::deserialize_hex(hex.trim()).unwrap()
+    }
+
+    const OVT_ID: RuneId = RuneId { block: 840_000, tx: 1 };
+
+    #[test]
+    fn test_decode_mint() {
+        let tx = fixture(include_str!("../tests/fixtures/runestone_mint.hex"));
+        let runestone = decode_runestone(&tx).unwrap().unwrap();
+        assert_eq!(runestone.kind(), RunestoneKind::Mint);
+        assert_eq!(runestone.mint, Some(OVT_ID));
+        assert_eq!(runestone.pointer, Some(0));
+        assert!(runestone.edicts.is_empty());
+    }
+
+    #[test]
+    fn test_decode_transfer_edicts() {
+        let tx = fixture(include_str!("../tests/fixtures/runestone_transfer.hex"));
+        let runestone = decode_runestone(&tx).unwrap().unwrap();
+        assert_eq!(runestone.kind(), RunestoneKind::Transfer);
+        // Rune ids are delta-encoded against the previous edict
+        assert_eq!(runestone.edicts, vec![
+            Edict { id: OVT_ID, amount: 1000, output: 0 },
+            Edict { id: OVT_ID, amount: 500, output: 1 },
+            Edict { id: RuneId { block: 840_000, tx: 2 }, amount: 7, output: 1 },
+        ]);
+    }
+
+    #[test]
+    fn test_decode_cenotaph() {
+        let tx = fixture(include_str!("../tests/fixtures/runestone_cenotaph.hex"));
+        let runestone = decode_runestone(&tx).unwrap().unwrap();
+        assert_eq!(runestone.kind(), RunestoneKind::Cenotaph);
+        assert_eq!(runestone.flaw, Some(CenotaphFlaw::UnrecognizedEvenTag));
+        // The mint is kept so it can be counted as burned; edicts are dropped
+        assert_eq!(runestone.mint, Some(OVT_ID));
+        assert!(runestone.edicts.is_empty());
+    }
+
+    #[test]
+    fn test_decode_own_etching() {
+        let tx = Transaction {
+            version: bitcoin::transaction::Version::TWO,
+            lock_time: bitcoin::absolute::LockTime::ZERO,
+            input: vec![],
+            output: vec![bitcoin::TxOut { value: bitcoin::Amount::ZERO, script_pubkey: ovt_spec().runestone_script().unwrap() }],
+        };
+        let runestone = decode_runestone(&tx).unwrap().unwrap();
+        assert_eq!(runestone.kind(), RunestoneKind::Etching);
+        assert_eq!(runestone.flaw, None);
+
+        let plain = Transaction { output: vec![bitcoin::TxOut { value: bitcoin::Amount::ZERO, script_pubkey: ScriptBuf::new_op_return([1u8, 2, 3]) }], ..tx };
+        assert_eq!(decode_runestone(&plain).unwrap(), None);
+    }
+
+    #[test]
+    fn test_decode_malformed_payloads() {
+        let decode = |payload: &[u8]| {
+            let script = Builder::new()
+                .push_opcode(OP_RETURN)
+                .push_opcode(OP_PUSHNUM_13)
+                .push_slice(PushBytesBuf::try_from(payload.to_vec()).unwrap())
+                .into_script();
+            let tx = Transaction {
+                version: bitcoin::transaction::Version::TWO,
+                lock_time: bitcoin::absolute::LockTime::ZERO,
+                input: vec![],
+                output: vec![bitcoin::TxOut { value: bitcoin::Amount::ZERO, script_pubkey: script }],
+            };
+            decode_runestone(&tx).unwrap().unwrap().flaw
+        };
+        assert_eq!(decode(&[0x80]), Some(CenotaphFlaw::Varint));
+        assert_eq!(decode(&[0x14]), Some(CenotaphFlaw::TruncatedField));
+        assert_eq!(decode(&[0x02, 0x80, 0x01]), Some(CenotaphFlaw::UnrecognizedFlag));
+        assert_eq!(decode(&[0x00, 0x01, 0x00, 0x01]), Some(CenotaphFlaw::TrailingIntegers));
+        // Edict to output 2 of a one-output transaction
+        assert_eq!(decode(&[0x00, 0x01, 0x00, 0x01, 0x02]), Some(CenotaphFlaw::EdictOutput));
+        // Block 0 with a nonzero tx index
+        assert_eq!(decode(&[0x00, 0x00, 0x01, 0x01, 0x00]), Some(CenotaphFlaw::EdictRuneId));
+        // Odd tags are ignored
+        assert_eq!(decode(&[0x7f, 0x01]), None);
+    }
+
+    fn approve_inscription(position: &PortfolioPosition, seeds: std::ops::RangeInclusive<u8>) -> Vec<String> {
+        seeds.map(|seed| ecdsa_signature(seed, &position_inscription_message(position))).collect()
+    }
+
+    #[tokio::test]
+    async fn test_inscribe_and_fetch_position() {
+        let client = client().with_mock_mode(true).with_funding(funding(vec![p2wpkh_utxo("55", 0, 50_000)]));
+        let position = pending_position();
+        client.add_post_tge_position(position.clone(), &approve(PositionStatus::Active, None)).await.unwrap();
+
+        let signers = &admin_pubkeys()[..3];
+        let id = client.inscribe_position(&position, &approve_inscription(&position, 1..=3), signers).await.unwrap();
+        assert!(id.ends_with("i0"));
+        assert_eq!(client.get_position("Test Project").await.unwrap().safe_inscription_id, Some(id.clone()));
+
+        let inscribed = client.fetch_inscribed_position(&id).await.unwrap();
+        assert_eq!(inscribed.position, PortfolioPosition { safe_inscription_id: Some(id.clone()), ..position.clone() });
+        assert_eq!(inscribed.approvers, AdminSet::new(signers.to_vec(), 3).unwrap());
+
+        let missing = format!("{}i0", "ab".repeat(32));
+        assert!(matches!(client.fetch_inscribed_position(&missing).await, Err(RunesError::InvalidInscription(_))));
+    }
+
+    #[tokio::test]
+    async fn test_inscription_needs_admin_approval() {
+        let client = client().with_mock_mode(true).with_funding(funding(vec![p2wpkh_utxo("55", 0, 50_000)]));
+        let position = pending_position();
+        let signatures = approve_inscription(&position, 1..=3);
+
+        // Signers must be admins, and reach the threshold
+        let outsider = PublicKey::new(secret_key(9).public_key(&Secp256k1::new()));
+        let with_outsider = [&admin_pubkeys()[..2], &[outsider]].concat();
+        assert!(matches!(
+            client.inscribe_position(&position, &signatures, &with_outsider).await,
+            Err(RunesError::InvalidAdminKeys)
+        ));
+        assert!(matches!(
+            client.inscribe_position(&position, &signatures, &admin_pubkeys()[..2]).await,
+            Err(RunesError::InvalidAdminKeys)
+        ));
+
+        // Signatures cover the position as it is inscribed
+        let changed = PortfolioPosition { amount: 2_000_000, ..position.clone() };
+        assert!(matches!(
+            client.inscribe_position(&changed, &signatures, &admin_pubkeys()[..3]).await,
+            Err(RunesError::InsufficientSignatures)
+        ));
+        // but not the inscription id, which doesn't exist yet
+        let reinscribed = PortfolioPosition { safe_inscription_id: Some("old".to_string()), ..position.clone() };
+        client.inscribe_position(&reinscribed, &signatures, &admin_pubkeys()[..3]).await.unwrap();
+
+        let underfunded = client().with_mock_mode(true).with_funding(funding(vec![p2wpkh_utxo("55", 0, 1_000)]));
+        assert!(matches!(
+            underfunded.inscribe_position(&position, &signatures, &admin_pubkeys()[..3]).await,
+            Err(RunesError::InsufficientFunding { .. })
+        ));
+    }
+
+    #[test]
+    fn test_decode_inscribed_position() {
+        let reveal_hex = include_str!("../tests/fixtures/inscribed_position_reveal.hex");
+        let tx = fixture(reveal_hex);
+        let inscribed = decode_position_inscription(&tx).unwrap();
+        assert_eq!(inscribed.position.safe_inscription_id, Some(format!("{}i0", tx.compute_txid())));
+        assert_eq!(inscribed.position.name, "Test Project");
+        assert_eq!(inscribed.position.position_type, PositionType::PostTGE);
+        assert_eq!(inscribed.position.transaction_id, Some("01".repeat(32)));
+        assert_eq!(inscribed.approvers, AdminSet::new(admin_pubkeys()[..3].to_vec(), 3).unwrap());
+
+        // An edited record no longer matches its signatures
+        let edited = reveal_hex.replace(&hex::encode("\"amount\":1000000"), &hex::encode("\"amount\":9000000"));
+        assert_ne!(edited, reveal_hex);
+        assert!(matches!(decode_position_inscription(&fixture(&edited)), Err(RunesError::InsufficientSignatures)));
+
+        // Nor is a runestone an inscription
+        let runestone = fixture(include_str!("../tests/fixtures/runestone_mint.hex"));
+        assert!(matches!(decode_position_inscription(&runestone), Err(RunesError::InvalidInscription(_))));
+    }
+}
@@ -0,0 +1,483 @@
+use std::fmt;
+
+use serde::{Serialize, Serializer};
+
+use crate::bitcoin::memo::{PaymentIntent, PaymentMemo, PAYER_ID_LEN};
+use crate::burn_history::BurnRecord;
+use crate::bitcoin::utxo::{TreasuryScriptKind, UtxoMeta, UtxoStatus, TREASURY_MULTISIG_KEYS};
+use crate::instructions::OVTInstruction;
+use crate::state::{NetworkStatus, OVTState};
+
+/// Borsh type of a field, written the way the schema document spells it
+///
+/// Integers are little-endian. Strings are a u32 byte length then UTF-8,
+/// vectors a u32 element count then the elements, options a 0/1 byte then
+/// the value, enums a u8 discriminant then the variant's fields.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FieldType {
+    U8,
+    U16,
+    U32,
+    U64,
+    String,
+    Array(Box<FieldType>, usize),
+    Vec(Box<FieldType>),
+    Option(Box<FieldType>),
+    /// Another type described in the same document
+    Named(&'static str),
+}
+
+impl fmt::Display for FieldType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::U8 => write!(f, "u8"),
+            Self::U16 => write!(f, "u16"),
+            Self::U32 => write!(f, "u32"),
+            Self::U64 => write!(f, "u64"),
+            Self::String => write!(f, "string"),
+            Self::Array(inner, len) => write!(f, "[{}; {}]", inner, len),
+            Self::Vec(inner) => write!(f, "vec<{}>", inner),
+            Self::Option(inner) => write!(f, "option<{}>", inner),
+            Self::Named(name) => write!(f, "{}", name),
+        }
+    }
+}
+
+impl Serialize for FieldType {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FieldSchema {
+    /// Position for tuple fields, e.g. `0`
+    pub name: &'static str,
+    #[serde(rename = "type")]
+    pub ty: FieldType,
+    /// Serialized size in bytes, `None` when it depends on the value
+    pub size: Option<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct VariantSchema {
+    pub name: &'static str,
+    /// Leading byte of the serialized variant
+    pub discriminant: u8,
+    pub fields: Vec<FieldSchema>,
+    /// Serialized size including the discriminant, `None` when it varies
+    pub size: Option<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum TypeBody {
+    /// Fields serialized in order, without framing
+    Struct { fields: Vec<FieldSchema> },
+    Enum { variants: Vec<VariantSchema> },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TypeSchema {
+    pub name: &'static str,
+    #[serde(flatten)]
+    pub body: TypeBody,
+    /// Serialized size in bytes, `None` when it depends on the value
+    pub size: Option<usize>,
+}
+
+/// Byte layouts of the Borsh types the frontend reads and writes, from
+/// `generate_schema`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SchemaDoc {
+    pub encoding: &'static str,
+    pub types: Vec<TypeSchema>,
+}
+
+impl SchemaDoc {
+    pub fn get(&self, name: &str) -> Option<&TypeSchema> {
+        self.types.iter().find(|schema| schema.name == name)
+    }
+
+    pub fn to_json_pretty(&self) -> String {
+        serde_json::to_string_pretty(self).expect("SchemaDoc always serializes to JSON")
+    }
+
+    /// Serialized size of `ty`, `None` when it depends on the value
+    pub fn size_of(&self, ty: &FieldType) -> Option<usize> {
+        match ty {
+            FieldType::U8 => Some(1),
+            FieldType::U16 => Some(2),
+            FieldType::U32 => Some(4),
+            FieldType::U64 => Some(8),
+            FieldType::String | FieldType::Vec(_) | FieldType::Option(_) => None,
+            FieldType::Array(inner, len) => self.size_of(inner).map(|size| size * len),
+            FieldType::Named(name) => match &self.get(name)?.body {
+                TypeBody::Struct { fields } => fields_size(self, fields),
+                TypeBody::Enum { variants } => {
+                    let mut sizes = variants.iter().map(|variant| fields_size(self, &variant.fields).map(|size| size + 1));
+                    let first = sizes.next()??;
+                    sizes.all(|size| size == Some(first)).then_some(first)
+                }
+            },
+        }
+    }
+
+    /// Fill in every `size` from the field types
+    fn with_sizes(mut self) -> Self {
+        let sized = self.clone();
+        for schema in &mut self.types {
+            schema.size = sized.size_of(&FieldType::Named(schema.name));
+            match &mut schema.body {
+                TypeBody::Struct { fields } => set_field_sizes(&sized, fields),
+                TypeBody::Enum { variants } => {
+                    for variant in variants {
+                        set_field_sizes(&sized, &mut variant.fields);
+                        variant.size = fields_size(&sized, &variant.fields).map(|size| size + 1);
+                    }
+                }
+            }
+        }
+        self
+    }
+}
+
+fn fields_size(doc: &SchemaDoc, fields: &[FieldSchema]) -> Option<usize> {
+    fields.iter().map(|field| doc.size_of(&field.ty)).sum()
+}
+
+fn set_field_sizes(doc: &SchemaDoc, fields: &mut [FieldSchema]) {
+    for field in fields {
+        field.size = doc.size_of(&field.ty);
+    }
+}
+
+fn field(name: &'static str, ty: FieldType) -> FieldSchema {
+    FieldSchema { name, ty, size: None }
+}
+
+fn array(inner: FieldType, len: usize) -> FieldType {
+    FieldType::Array(Box::new(inner), len)
+}
+
+fn vec(inner: FieldType) -> FieldType {
+    FieldType::Vec(Box::new(inner))
+}
+
+fn option(inner: FieldType) -> FieldType {
+    FieldType::Option(Box::new(inner))
+}
+
+fn structure(name: &'static str, fields: Vec<FieldSchema>) -> TypeSchema {
+    TypeSchema { name, body: TypeBody::Struct { fields }, size: None }
+}
+
+/// An enum whose variants are numbered in declaration order, as Borsh does
+fn enumeration(name: &'static str, variants: Vec<(&'static str, Vec<FieldSchema>)>) -> TypeSchema {
+    let variants = variants
+        .into_iter()
+        .enumerate()
+        .map(|(index, (name, fields))| VariantSchema {
+            name,
+            discriminant: u8::try_from(index).expect("at most 256 variants"),
+            fields,
+            size: None,
+        })
+        .collect();
+    TypeSchema { name, body: TypeBody::Enum { variants }, size: None }
+}
+
+/// Layouts of OVTState, OVTInstruction, UtxoMeta, BurnRecord and the types they contain
+///
+/// The descriptions are written out by hand; `layout_guard` stops this
+/// module compiling when a described type gains, loses or renames a field
+/// or variant, and the tests check the layouts against real serializations.
+pub fn generate_schema() -> SchemaDoc {
+    use FieldType::*;
+
+    let pubkey = || array(U8, 33);
+    SchemaDoc {
+        encoding: "borsh",
+        types: vec![
+            structure("OVTState", vec![
+                field("nav_sats", U64),
+                field("treasury_pubkey_bytes", pubkey()),
+                field("total_supply", U64),
+                field("last_nav_update", U64),
+                field("network_status", Named("NetworkStatus")),
+                field("last_sync_height", U64),
+                field("required_confirmations", U32),
+                field("treasury_script_kind", Named("TreasuryScriptKind")),
+                field("network", String),
+                field("nonce", U64),
+                field("treasury_script_hash", array(U8, 32)),
+                field("spot_nav_sats", U64),
+                field("ema_alpha_bps", option(U16)),
+                field("payment_maturity_blocks", U32),
+                field("authority", array(U8, 32)),
+            ]),
+            enumeration("NetworkStatus", vec![
+                ("Syncing", vec![]),
+                ("Active", vec![]),
+                ("Error", vec![field("0", String)]),
+            ]),
+            enumeration("TreasuryScriptKind", vec![
+                ("P2wpkh", vec![]),
+                ("P2tr", vec![]),
+                ("P2wshMultisig", vec![
+                    field("threshold", U8),
+                    field("pubkeys", array(pubkey(), TREASURY_MULTISIG_KEYS)),
+                ]),
+            ]),
+            enumeration("OVTInstruction", vec![
+                ("Initialize", vec![field("treasury_pubkey_bytes", pubkey())]),
+                ("LegacyUpdateNAV", vec![field("btc_price_sats", U64)]),
+                ("LegacyBuybackBurn", vec![field("payment_txid", String), field("payment_amount_sats", U64)]),
+                ("UpdateSyncStatus", vec![
+                    field("height", U64),
+                    field("status", Named("NetworkStatus")),
+                    field("expected_nonce", U64),
+                ]),
+                ("GetBurnHistory", vec![]),
+                ("SetNavSmoothing", vec![
+                    field("ema_alpha_bps", option(U16)),
+                    field("expected_nonce", U64),
+                ]),
+                ("SetPaymentMaturity", vec![
+                    field("blocks", U32),
+                    field("expected_nonce", U64),
+                ]),
+                ("UpdateNAV", vec![field("btc_price_sats", U64), field("expected_nonce", U64)]),
+                ("BuybackBurn", vec![
+                    field("payment_txid", String),
+                    field("payment_amount_sats", U64),
+                    field("payment_height", U64),
+                    field("memo", option(Named("PaymentMemo"))),
+                    field("expected_nonce", U64),
+                ]),
+                ("WriteEntry", vec![
+                    field("key", vec(U8)),
+                    field("value", vec(U8)),
+                    field("expected_nonce", U64),
+                ]),
+                ("SetTreasuryScript", vec![
+                    field("script_kind", Named("TreasuryScriptKind")),
+                    field("expected_nonce", U64),
+                ]),
+            ]),
+            structure("PaymentMemo", vec![
+                field("payer_id", array(U8, PAYER_ID_LEN)),
+                field("intent", Named("PaymentIntent")),
+                field("nonce", U64),
+            ]),
+            enumeration("PaymentIntent", vec![("Buyback", vec![])]),
+            structure("UtxoMeta", vec![
+                field("txid", String),
+                field("vout", U32),
+                field("amount_sats", U64),
+                field("script_pubkey", String),
+                field("confirmations", U32),
+                field("block_height", option(U32)),
+                field("block_hash", option(String)),
+            ]),
+            enumeration("UtxoStatus", vec![
+                ("Active", vec![]),
+                ("Pending", vec![]),
+                ("Spent", vec![]),
+                ("Invalid", vec![]),
+            ]),
+            structure("BurnRecord", vec![
+                field("payment_txid", array(U8, 32)),
+                field("payment_amount_sats", U64),
+                field("tokens_burned", U64),
+                field("nav_sats", U64),
+                field("supply_after", U64),
+            ]),
+        ],
+    }
+    .with_sizes()
+}
+
+/// Never called; exhaustive patterns over every described type, so changing
+/// one without updating `generate_schema` is a compile error
+#[allow(dead_code, clippy::too_many_arguments)]
+fn layout_guard(
+    state: &OVTState,
+    status: &NetworkStatus,
+    kind: &TreasuryScriptKind,
+    instruction: &OVTInstruction,
+    memo: &PaymentMemo,
+    intent: &PaymentIntent,
+    utxo: &UtxoMeta,
+    utxo_status: &UtxoStatus,
+    burn: &BurnRecord,
+) {
+    let OVTState {
+        nav_sats: _,
+        treasury_pubkey_bytes: _,
+        total_supply: _,
+        last_nav_update: _,
+        network_status: _,
+        last_sync_height: _,
+        required_confirmations: _,
+        treasury_script_kind: _,
+        network: _,
+        nonce: _,
+        treasury_script_hash: _,
+        spot_nav_sats: _,
+        ema_alpha_bps: _,
+        payment_maturity_blocks: _,
+        authority: _,
+    } = state;
+    match status {
+        NetworkStatus::Syncing | NetworkStatus::Active | NetworkStatus::Error(_) => {}
+    }
+    match kind {
+        TreasuryScriptKind::P2wpkh | TreasuryScriptKind::P2tr => {}
+        TreasuryScriptKind::P2wshMultisig { threshold: _, pubkeys: _ } => {}
+    }
+    match instruction {
+        OVTInstruction::Initialize { treasury_pubkey_bytes: _ } => {}
+        OVTInstruction::LegacyUpdateNAV { btc_price_sats: _ } => {}
+        OVTInstruction::LegacyBuybackBurn { payment_txid: _, payment_amount_sats: _ } => {}
+        OVTInstruction::UpdateNAV { btc_price_sats: _, expected_nonce: _ } => {}
+        OVTInstruction::BuybackBurn { payment_txid: _, payment_amount_sats: _, payment_height: _, memo: _, expected_nonce: _ } => {}
+        OVTInstruction::UpdateSyncStatus { height: _, status: _, expected_nonce: _ } => {}
+        OVTInstruction::GetBurnHistory => {}
+        OVTInstruction::SetNavSmoothing { ema_alpha_bps: _, expected_nonce: _ } => {}
+        OVTInstruction::SetPaymentMaturity { blocks: _, expected_nonce: _ } => {}
+        OVTInstruction::WriteEntry { key: _, value: _, expected_nonce: _ } => {}
+        OVTInstruction::SetTreasuryScript { script_kind: _, expected_nonce: _ } => {}
+    }
+    let PaymentMemo { payer_id: _, intent: _, nonce: _ } = memo;
+    match intent {
+        PaymentIntent::Buyback => {}
+    }
+    let UtxoMeta {
+        txid: _,
+        vout: _,
+        amount_sats: _,
+        script_pubkey: _,
+        confirmations: _,
+        block_height: _,
+        block_hash: _,
+    } = utxo;
+    match utxo_status {
+        UtxoStatus::Active | UtxoStatus::Pending | UtxoStatus::Spent | UtxoStatus::Invalid => {}
+    }
+    let BurnRecord {
+        payment_txid: _,
+        payment_amount_sats: _,
+        tokens_burned: _,
+        nav_sats: _,
+        supply_after: _,
+    } = burn;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Bytes `ty` takes at the start of `bytes`, read as the schema says
+    fn read(doc: &SchemaDoc, ty: &FieldType, bytes: &[u8]) -> usize {
+        let u32_at = |bytes: &[u8]| u32::from_le_bytes(bytes[..4].try_into().unwrap()) as usize;
+        match ty {
+            FieldType::String => 4 + u32_at(bytes),
+            FieldType::Option(inner) => match bytes[0] {
+                0 => 1,
+                1 => 1 + read(doc, inner, &bytes[1..]),
+                tag => panic!("bad option tag {}", tag),
+            },
+            FieldType::Array(inner, len) => (0..*len).fold(0, |offset, _| offset + read(doc, inner, &bytes[offset..])),
+            FieldType::Vec(inner) => (0..u32_at(bytes)).fold(4, |offset, _| offset + read(doc, inner, &bytes[offset..])),
+            FieldType::Named(name) => match &doc.get(name).expect("described type").body {
+                TypeBody::Struct { fields } => read_fields(doc, fields, bytes),
+                TypeBody::Enum { variants } => {
+                    let variant = variants.iter().find(|variant| variant.discriminant == bytes[0]).expect("known variant");
+                    1 + read_fields(doc, &variant.fields, &bytes[1..])
+                }
+            },
+            fixed => doc.size_of(fixed).unwrap(),
+        }
+    }
+
+    fn read_fields(doc: &SchemaDoc, fields: &[FieldSchema], bytes: &[u8]) -> usize {
+        fields.iter().fold(0, |offset, field| offset + read(doc, &field.ty, &bytes[offset..]))
+    }
+
+    /// The schema accounts for exactly the bytes Borsh writes for `value`
+    fn assert_layout(doc: &SchemaDoc, name: &'static str, value: &impl borsh::BorshSerialize) {
+        let bytes = borsh::to_vec(value).unwrap();
+        assert_eq!(read(doc, &FieldType::Named(name), &bytes), bytes.len(), "{} layout", name);
+        if let Some(size) = doc.get(name).unwrap().size {
+            assert_eq!(size, bytes.len(), "{} size", name);
+        }
+    }
+
+    #[test]
+    fn test_layouts_match_borsh() {
+        let doc = generate_schema();
+        let memo = PaymentMemo { payer_id: [0x42; PAYER_ID_LEN], intent: PaymentIntent::Buyback, nonce: 7 };
+
+        let mut state = OVTState::new([2; 33]);
+        assert_layout(&doc, "OVTState", &state);
+        state.network_status = NetworkStatus::Error("node unreachable".to_string());
+        state.treasury_script_kind = TreasuryScriptKind::P2wshMultisig { threshold: 2, pubkeys: [[3; 33]; 3] };
+        state.network = "testnet".to_string();
+        state.ema_alpha_bps = Some(2_000);
+        assert_layout(&doc, "OVTState", &state);
+
+        for instruction in [
+            OVTInstruction::Initialize { treasury_pubkey_bytes: [2; 33] },
+            OVTInstruction::LegacyUpdateNAV { btc_price_sats: 1_000_000 },
+            OVTInstruction::LegacyBuybackBurn { payment_txid: "ab".repeat(32), payment_amount_sats: 5 },
+            OVTInstruction::UpdateNAV { btc_price_sats: 1_000_000, expected_nonce: 4 },
+            OVTInstruction::BuybackBurn { payment_txid: "ab".repeat(32), payment_amount_sats: 5, payment_height: 800_000, memo: Some(memo), expected_nonce: 5 },
+            OVTInstruction::BuybackBurn { payment_txid: String::new(), payment_amount_sats: 5, payment_height: 0, memo: None, expected_nonce: 6 },
+            OVTInstruction::UpdateSyncStatus { height: 9, status: NetworkStatus::Active, expected_nonce: 10 },
+            OVTInstruction::GetBurnHistory,
+            OVTInstruction::SetNavSmoothing { ema_alpha_bps: Some(2_000), expected_nonce: 7 },
+            OVTInstruction::SetNavSmoothing { ema_alpha_bps: None, expected_nonce: 8 },
+            OVTInstruction::SetPaymentMaturity { blocks: 6, expected_nonce: 9 },
+            OVTInstruction::WriteEntry { key: b"ovt\0key".to_vec(), value: vec![7; 40], expected_nonce: 11 },
+            OVTInstruction::SetTreasuryScript { script_kind: TreasuryScriptKind::P2tr, expected_nonce: 12 },
+            OVTInstruction::SetTreasuryScript {
+                script_kind: TreasuryScriptKind::P2wshMultisig { threshold: 2, pubkeys: [[3; 33]; 3] },
+                expected_nonce: 13,
+            },
+        ] {
+            assert_layout(&doc, "OVTInstruction", &instruction);
+        }
+        assert_layout(&doc, "PaymentMemo", &memo);
+
+        let mut utxo = UtxoMeta::new("cd".repeat(32), 1, 10_000);
+        assert_layout(&doc, "UtxoMeta", &utxo);
+        utxo.update_block_info(800_000, "00".repeat(32));
+        assert_layout(&doc, "UtxoMeta", &utxo);
+        for status in [UtxoStatus::Active, UtxoStatus::Pending, UtxoStatus::Spent, UtxoStatus::Invalid] {
+            assert_layout(&doc, "UtxoStatus", &status);
+        }
+
+        let burn = BurnRecord::for_burn(&"ab".repeat(32), 100_000, 90_909, &state).unwrap();
+        assert_layout(&doc, "BurnRecord", &burn);
+        assert_eq!(doc.get("BurnRecord").unwrap().size, Some(BurnRecord::LEN));
+
+        // Discriminants are what Borsh writes, not e.g. PaymentIntent::to_byte
+        assert_eq!(borsh::to_vec(&UtxoStatus::Invalid).unwrap(), [3]);
+        assert_eq!(borsh::to_vec(&PaymentIntent::Buyback).unwrap(), [0]);
+    }
+
+    /// Regenerate the fixture with `cargo run --features serde --example dump_schema` after
+    /// an intended layout change, and update the frontend with it
+    #[test]
+    fn test_schema_matches_fixture() {
+        let fixture: serde_json::Value =
+            serde_json::from_str(include_str!("../tests/fixtures/borsh_schema.json")).unwrap();
+        let generated = serde_json::to_value(generate_schema()).unwrap();
+        assert!(
+            generated == fixture,
+            "Borsh layouts changed; if intended, update tests/fixtures/borsh_schema.json:\n{}",
+            generate_schema().to_json_pretty()
+        );
+    }
+}
@@ -1,15 +1,116 @@
-// In program/src/security.rs
+use arch_program::{msg, program_error::ProgramError};
+use bitcoin::hashes::{sha256, Hash};
+use bitcoin::secp256k1::{ecdsa::Signature, Message, PublicKey, Secp256k1};
+
+use crate::error::OVTError;
+use crate::state::{NetworkStatus, OVTProgram, OVTState};
+
+/// Blocks `last_sync_height` may trail the node by before state counts as stale
+pub const DEFAULT_MAX_SYNC_LAG: u64 = 6;
 
 impl OVTProgram {
-    pub async fn verify_network_signature(&self, 
-        message: &[u8], 
-        signature: &[u8], 
-        pubkey: &[u8]
+    /// Check a 64-byte compact ECDSA `signature` over sha256(`message`) against
+    /// a 33-byte compressed `pubkey`, the format of `treasury_pubkey_bytes`
+    ///
+    /// Malformed keys and signatures are errors; a well-formed signature that
+    /// doesn't match is `Ok(false)`.
+    pub fn verify_network_signature(
+        &self,
+        message: &[u8],
+        signature: &[u8],
+        pubkey: &[u8],
     ) -> Result<bool, ProgramError> {
-        // Implement network-specific signature verification
+        if pubkey.len() != 33 {
+            return Err(OVTError::InvalidTreasuryKey.into());
+        }
+        let pubkey = PublicKey::from_slice(pubkey).map_err(|_| OVTError::InvalidTreasuryKey)?;
+        let signature = Signature::from_compact(signature).map_err(|_| OVTError::InvalidSignature)?;
+        let digest = Message::from_digest(sha256::Hash::hash(message).to_byte_array());
+
+        Ok(Secp256k1::verification_only().verify_ecdsa(&digest, &signature, &pubkey).is_ok())
+    }
+
+    /// Reject `state` if its sync is in error, ahead of `node_height`, or more
+    /// than `max_sync_lag` blocks behind it
+    pub fn validate_network_state(
+        &self,
+        state: &OVTState,
+        node_height: u64,
+        max_sync_lag: u64,
+    ) -> Result<(), ProgramError> {
+        if let NetworkStatus::Error(reason) = &state.network_status {
+            msg!("Network status is in error: {}", reason);
+            return Err(OVTError::NetworkError.into());
+        }
+        if state.last_sync_height > node_height {
+            msg!("Synced to {} but the node is only at {}", state.last_sync_height, node_height);
+            return Err(OVTError::InvalidBlockHeight.into());
+        }
+        let lag = node_height - state.last_sync_height;
+        if lag > max_sync_lag {
+            msg!("State is {} blocks behind the node, at most {} allowed", lag, max_sync_lag);
+            return Err(OVTError::StaleNetworkState.into());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Key sha256("ovt security test key"), RFC 6979 signature over sha256(MESSAGE)
+    const PUBKEY: &str = "03d84708b616c8cc5308c5eba77949eca2f1e35ed12ae25cc73335cccfe6ada1ac";
+    const SIGNATURE: &str = "51485189e5b71b5b9b4308c5ab850429502193d24399a5d79f1b6e8883a5c505\
+                             761919381430e8783a429b3ca497b1d0748b660d74d333af38eb7a4eb9fb1750";
+    const MESSAGE: &[u8] = b"ovt:update_nav:1000000";
+
+    fn is_error(result: Result<impl std::fmt::Debug, ProgramError>, error: OVTError) -> bool {
+        matches!(result, Err(ProgramError::Custom(code)) if code == error.code())
     }
 
-    pub async fn validate_network_state(&self) -> Result<(), ProgramError> {
-        // Implement network state validation
+    #[test]
+    fn test_signature_vectors() {
+        let program = OVTProgram::new();
+        let pubkey = hex::decode(PUBKEY).unwrap();
+        let signature = hex::decode(SIGNATURE).unwrap();
+
+        assert!(program.verify_network_signature(MESSAGE, &signature, &pubkey).unwrap());
+        assert!(!program.verify_network_signature(b"ovt:update_nav:1000001", &signature, &pubkey).unwrap());
+
+        let mut tampered = signature.clone();
+        tampered[40] ^= 1;
+        assert!(!program.verify_network_signature(MESSAGE, &tampered, &pubkey).unwrap());
+    }
+
+    #[test]
+    fn test_malformed_keys_and_signatures() {
+        let program = OVTProgram::new();
+        let pubkey = hex::decode(PUBKEY).unwrap();
+        let signature = hex::decode(SIGNATURE).unwrap();
+
+        assert!(is_error(program.verify_network_signature(MESSAGE, &signature[..63], &pubkey), OVTError::InvalidSignature));
+        // x-only keys aren't accepted, only compressed ones
+        assert!(is_error(program.verify_network_signature(MESSAGE, &signature, &pubkey[1..]), OVTError::InvalidTreasuryKey));
+        let mut off_curve = pubkey.clone();
+        off_curve[0] = 0x05;
+        assert!(is_error(program.verify_network_signature(MESSAGE, &signature, &off_curve), OVTError::InvalidTreasuryKey));
+    }
+
+    #[test]
+    fn test_network_state_lag() {
+        let program = OVTProgram::new();
+        let mut state = OVTState::new([2; 33]);
+        state.network_status = NetworkStatus::Active;
+        state.last_sync_height = 100;
+
+        assert!(program.validate_network_state(&state, 100, DEFAULT_MAX_SYNC_LAG).is_ok());
+        assert!(program.validate_network_state(&state, 106, DEFAULT_MAX_SYNC_LAG).is_ok());
+        assert!(is_error(program.validate_network_state(&state, 107, DEFAULT_MAX_SYNC_LAG), OVTError::StaleNetworkState));
+        assert!(program.validate_network_state(&state, 107, 10).is_ok());
+        assert!(is_error(program.validate_network_state(&state, 99, DEFAULT_MAX_SYNC_LAG), OVTError::InvalidBlockHeight));
+
+        state.network_status = NetworkStatus::Error("node unreachable".to_string());
+        assert!(is_error(program.validate_network_state(&state, 100, DEFAULT_MAX_SYNC_LAG), OVTError::NetworkError));
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,242 @@
+use std::fmt::Debug;
+
+use arch_program::program_error::ProgramError;
+
+use crate::instructions::OVTInstruction;
+use crate::state::{OVTProgram, OVTState};
+
+/// A state field an instruction would change, with both values in `Debug` form
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldChange {
+    pub field: &'static str,
+    pub before: String,
+    pub after: String,
+}
+
+/// What executing an instruction would do
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SimulationOutcome {
+    /// The state the instruction would leave behind
+    pub state: OVTState,
+    /// Fields that differ from the input state, in declaration order
+    pub changes: Vec<FieldChange>,
+    pub tokens_burned: u64,
+    /// Log lines the handler would emit
+    pub events: Vec<String>,
+}
+
+impl OVTProgram {
+    /// Run `instruction` against a copy of `state` at unix time `now`, as the
+    /// handler would, without touching any account
+    ///
+    /// Uses the handlers' own `OVTState::apply_instruction`, so this fails
+    /// wherever the state transition would. It has no accounts, so it skips
+    /// what the handlers check against them: the accounts, the signer and
+    /// whether it is the stored `authority`, the network the state was initialized on, whether a BuybackBurn
+    /// payment's spent-payment account is still empty, and whether a
+    /// WriteEntry's entry account is the one derived from its key. A payment
+    /// that has already been burned for simulates successfully.
+    pub fn simulate(state: &OVTState, instruction: &OVTInstruction, now: u64) -> Result<SimulationOutcome, ProgramError> {
+        let mut after = state.clone();
+        let transition = after.apply_instruction(instruction, now)?;
+        Ok(SimulationOutcome {
+            changes: changed_fields(state, &after),
+            state: after,
+            tokens_burned: transition.tokens_burned,
+            events: transition.events,
+        })
+    }
+}
+
+fn changed_fields(before: &OVTState, after: &OVTState) -> Vec<FieldChange> {
+    let mut changes = Vec::new();
+    let mut compare = |field: &'static str, old: &dyn Debug, new: &dyn Debug| {
+        let (before, after) = (format!("{:?}", old), format!("{:?}", new));
+        if before != after {
+            changes.push(FieldChange { field, before, after });
+        }
+    };
+    // Destructured so a new field can't be left out of the diff
+    let OVTState {
+        nav_sats,
+        treasury_pubkey_bytes,
+        total_supply,
+        last_nav_update,
+        network_status,
+        last_sync_height,
+        required_confirmations,
+        treasury_script_kind,
+        network,
+        nonce,
+        treasury_script_hash,
+        spot_nav_sats,
+        ema_alpha_bps,
+        payment_maturity_blocks,
+        authority,
+    } = before;
+    compare("nav_sats", nav_sats, &after.nav_sats);
+    compare("treasury_pubkey_bytes", treasury_pubkey_bytes, &after.treasury_pubkey_bytes);
+    compare("total_supply", total_supply, &after.total_supply);
+    compare("last_nav_update", last_nav_update, &after.last_nav_update);
+    compare("network_status", network_status, &after.network_status);
+    compare("last_sync_height", last_sync_height, &after.last_sync_height);
+    compare("required_confirmations", required_confirmations, &after.required_confirmations);
+    compare("treasury_script_kind", treasury_script_kind, &after.treasury_script_kind);
+    compare("network", network, &after.network);
+    compare("nonce", nonce, &after.nonce);
+    compare("treasury_script_hash", treasury_script_hash, &after.treasury_script_hash);
+    compare("spot_nav_sats", spot_nav_sats, &after.spot_nav_sats);
+    compare("ema_alpha_bps", ema_alpha_bps, &after.ema_alpha_bps);
+    compare("payment_maturity_blocks", payment_maturity_blocks, &after.payment_maturity_blocks);
+    compare("authority", authority, &after.authority);
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arch_program::account::AccountInfo;
+    use arch_program::program_pack::Pack;
+    use arch_program::pubkey::Pubkey;
+    use std::{cell::RefCell, rc::Rc};
+
+    use crate::address::{burn_history_address, spent_payment_address, state_address};
+    use crate::burn_history::{parse_payment_txid, BurnHistory};
+    use crate::error::OVTError;
+    use crate::program_ids::ProgramIds;
+    use crate::state::{buyback_burn_event, NetworkStatus, Program};
+
+    const PAYMENT_TXID: &str = "c000000000000000000000000000000000000000000000000000000000000000";
+
+    fn account<'a>(key: &'a Pubkey, owner: &'a Pubkey, data: Vec<u8>, is_signer: bool) -> AccountInfo<'a> {
+        AccountInfo {
+            key,
+            is_signer,
+            is_writable: true,
+            lamports: Rc::new(RefCell::new(100_000)),
+            data: Rc::new(RefCell::new(data)),
+            owner,
+            executable: false,
+            rent_epoch: 0,
+        }
+    }
+
+    /// Run `instruction` through the real entrypoint against an account
+    /// holding `state`, returning the stored result
+    fn execute(state: &OVTState, instruction: &OVTInstruction, now: u64) -> Result<OVTState, ProgramError> {
+        let program_id = ProgramIds::default().ovt;
+        let state_key = state_address(&program_id);
+        let authority_key = Pubkey(state.authority);
+        let clock_key = Pubkey::new_unique();
+        let history_key = burn_history_address(&program_id);
+        let system_key = Pubkey::system_program();
+        let spent_key;
+        let mut accounts = vec![
+            account(&state_key, &program_id, borsh::to_vec(state).unwrap(), false),
+            account(&authority_key, &program_id, Vec::new(), true),
+        ];
+        // BuybackBurn takes the burn history and the payment's spent-payment
+        // account where UpdateNAV takes the clock
+        match instruction {
+            OVTInstruction::BuybackBurn { payment_txid, .. } => {
+                spent_key = spent_payment_address(&program_id, &parse_payment_txid(payment_txid)?);
+                accounts.push(account(&history_key, &program_id, vec![0; BurnHistory::LEN], false));
+                accounts.push(account(&spent_key, &program_id, Vec::new(), false));
+                accounts.push(account(&system_key, &system_key, Vec::new(), false));
+            }
+            _ => accounts.push(account(&clock_key, &program_id, now.to_le_bytes().to_vec(), false)),
+        }
+        OVTProgram::process_instruction(&program_id, &accounts, &borsh::to_vec(instruction).unwrap())?;
+        let data = accounts[0].try_borrow_data().map_err(|_| ProgramError::AccountBorrowFailed)?;
+        Pack::unpack_from_slice(&data)
+    }
+
+    fn live_state() -> OVTState {
+        let mut state = OVTState::new([2; 33]);
+        state.nav_sats = 1_000_000;
+        state.total_supply = 1_000_000;
+        state.last_nav_update = 100;
+        state.last_sync_height = 800_000;
+        state.authority = [7; 32];
+        state
+    }
+
+    fn change(field: &'static str, before: &str, after: &str) -> FieldChange {
+        FieldChange { field, before: before.to_string(), after: after.to_string() }
+    }
+
+    #[test]
+    fn test_simulate_matches_execute() {
+        let state = live_state();
+        let burn = OVTInstruction::BuybackBurn {
+            payment_txid: PAYMENT_TXID.to_string(),
+            payment_amount_sats: 100_000,
+            payment_height: 0,
+            memo: None,
+            expected_nonce: 0,
+        };
+        let cases = [
+            (OVTInstruction::UpdateNAV { btc_price_sats: 2_000_000, expected_nonce: 0 }, 200),
+            (burn, 0),
+            (OVTInstruction::UpdateSyncStatus { height: 800_001, status: NetworkStatus::Active, expected_nonce: 0 }, 0),
+        ];
+        for (instruction, now) in &cases {
+            let simulated = OVTProgram::simulate(&state, instruction, *now).unwrap();
+            assert_eq!(simulated.state, execute(&state, instruction, *now).unwrap(), "{:?}", instruction);
+            assert_eq!(state, live_state(), "simulating {:?} changed its input", instruction);
+        }
+
+        let nav = OVTProgram::simulate(&state, &cases[0].0, 200).unwrap();
+        assert_eq!(
+            nav.changes,
+            vec![
+                change("nav_sats", "1000000", "2000000"),
+                change("last_nav_update", "100", "200"),
+                change("network_status", "Syncing", "Active"),
+                change("nonce", "0", "1"),
+                change("spot_nav_sats", "0", "2000000"),
+            ]
+        );
+        assert_eq!((nav.tokens_burned, nav.events.len()), (0, 0));
+
+        let burned = OVTProgram::simulate(&state, &cases[1].0, 0).unwrap();
+        assert_eq!(burned.changes, vec![change("total_supply", "1000000", "900000"), change("nonce", "0", "1")]);
+        assert_eq!(burned.tokens_burned, 100_000);
+        assert_eq!(burned.events, vec![buyback_burn_event(PAYMENT_TXID, 100_000, None)]);
+    }
+
+    #[test]
+    fn test_simulate_fails_like_execute() {
+        let state = live_state();
+        let is_error = |result: Result<_, ProgramError>, error: OVTError| {
+            matches!(result, Err(ProgramError::Custom(code)) if code == error.code())
+        };
+
+        // Too soon after the last update
+        let early = OVTInstruction::UpdateNAV { btc_price_sats: 1_100_000, expected_nonce: 0 };
+        assert!(is_error(OVTProgram::simulate(&state, &early, 110).map(|outcome| outcome.state), OVTError::OperationTimeout));
+        assert!(is_error(execute(&state, &early, 110), OVTError::OperationTimeout));
+
+        // Burning more than the 10 BTC a payment may be worth
+        let oversized = OVTInstruction::BuybackBurn {
+            payment_txid: PAYMENT_TXID.to_string(),
+            payment_amount_sats: 2_000_000_000,
+            payment_height: 0,
+            memo: None,
+            expected_nonce: 0,
+        };
+        assert!(is_error(OVTProgram::simulate(&state, &oversized, 0).map(|outcome| outcome.state), OVTError::InvalidBitcoinTransaction));
+        assert!(is_error(execute(&state, &oversized, 0), OVTError::InvalidBitcoinTransaction));
+
+        // Signed for a nonce the state has already moved past
+        let replayed = OVTInstruction::UpdateNAV { btc_price_sats: 1_100_000, expected_nonce: 0 };
+        let mut advanced = state.clone();
+        advanced.nonce = 1;
+        assert!(is_error(OVTProgram::simulate(&advanced, &replayed, 200).map(|outcome| outcome.state), OVTError::NonceMismatch));
+        assert!(is_error(execute(&advanced, &replayed, 200), OVTError::NonceMismatch));
+
+        let initialize = OVTInstruction::Initialize { treasury_pubkey_bytes: [3; 33] };
+        assert!(matches!(OVTProgram::simulate(&state, &initialize, 0), Err(ProgramError::InvalidInstructionData)));
+        assert_eq!(state, live_state());
+    }
+}
@@ -16,7 +16,18 @@ pub trait Program {
 
 use crate::error::OVTError;
 use crate::instructions::OVTInstruction;
-use crate::utils::{create_program_account, initialize_account};
+use crate::accounts::{create_program_account, initialize_account};
+use crate::address::{
+    check_burn_history_account, check_state_account, entry_key_hash, BURN_HISTORY_SEED,
+    ENTRY_SEED, OVT_STATE_SEED, SPENT_PAYMENT_SEED,
+};
+use crate::burn_history::{burn_record_event, BurnHistory, BurnRecord};
+use crate::bitcoin::memo::PaymentMemo;
+use crate::bitcoin::utxo::{TreasuryScriptKind, ValidationPolicy, DEFAULT_REQUIRED_CONFIRMATIONS, TREASURY_MULTISIG_KEYS};
+use crate::network_config::network_name;
+use crate::program_ids::ProgramIds;
+use bitcoin::hashes::{sha256, Hash};
+use bitcoin::{Network, PublicKey, Script, ScriptBuf};
 
 #[derive(BorshSerialize, BorshDeserialize)]
 pub struct OVTProgram;
@@ -34,11 +45,13 @@ impl Default for OVTProgram {
 }
 
 /// Program state storing NAV and treasury data
-#[derive(BorshSerialize, BorshDeserialize, Clone, Debug)]
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct OVTState {
     /// Current NAV in satoshis
     pub nav_sats: u64,
-    /// Treasury Bitcoin public key bytes
+    /// Treasury Bitcoin public key bytes (hex in JSON)
+    #[cfg_attr(feature = "serde", serde(with = "hex_bytes"))]
     pub treasury_pubkey_bytes: [u8; 33],
     /// Total OVT supply (tracked from Runes)
     pub total_supply: u64,
@@ -48,42 +61,256 @@ pub struct OVTState {
     pub network_status: NetworkStatus,
     /// Last synced Bitcoin block height
     pub last_sync_height: u64,
+    /// Confirmations a treasury payment needs before it is accepted
+    pub required_confirmations: u32,
+    /// How the treasury's scriptPubKey is derived from its key material, set
+    /// by SetTreasuryScript
+    pub treasury_script_kind: TreasuryScriptKind,
+    /// `network_name` of the network at Initialize; empty for states that predate it
+    pub network: String,
+    /// Authority instructions applied so far; each must carry it as
+    /// `expected_nonce`, so a replayed instruction is rejected
+    pub nonce: u64,
+    /// sha256 of `treasury_script_pubkey` as of Initialize or the last
+    /// SetTreasuryScript, so payments can be
+    /// matched without re-deriving it (hex in JSON); zero for states that
+    /// predate it
+    #[cfg_attr(feature = "serde", serde(with = "hex_bytes"))]
+    pub treasury_script_hash: [u8; 32],
+    /// NAV as last submitted by UpdateNAV; `nav_sats` follows it exactly
+    /// unless smoothing is on. Zero for states that predate it
+    pub spot_nav_sats: u64,
+    /// Weight in basis points of each new spot in the smoothed `nav_sats`,
+    /// set by SetNavSmoothing; None applies spot values as they are
+    pub ema_alpha_bps: Option<u16>,
+    /// Blocks a treasury payment must be below `last_sync_height` before
+    /// BuybackBurn accepts it, set by SetPaymentMaturity; 0 checks nothing
+    pub payment_maturity_blocks: u32,
+    /// Key that signed Initialize, which every authority instruction after it
+    /// must be signed by (hex in JSON); zero until Initialize records it
+    #[cfg_attr(feature = "serde", serde(with = "hex_bytes"))]
+    pub authority: [u8; 32],
 }
 
+/// Basis points in a whole, the largest `ema_alpha_bps`
+pub const MAX_EMA_ALPHA_BPS: u16 = 10_000;
+
+/// Longest `network_name` Initialize records: "testnet", "regtest" or "mainnet"
+pub const MAX_NETWORK_NAME_LEN: usize = 7;
+
+/// Longest `NetworkStatus::Error` message `update_sync_status` accepts, in bytes,
+/// so the serialized state stays within `OVTState::LEN`
+pub const MAX_STATUS_MESSAGE_LEN: usize = 32;
+
+/// Largest value WriteEntry stores, in bytes
+pub const MAX_ENTRY_VALUE_LEN: usize = 1_024;
+
+/// Size every entry account is created at: a value of up to
+/// `MAX_ENTRY_VALUE_LEN` bytes after its Borsh length prefix
+pub const ENTRY_ACCOUNT_LEN: usize = 4 + MAX_ENTRY_VALUE_LEN;
+
 #[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
 pub enum NetworkStatus {
     Syncing,
     Active,
     Error(String),
 }
 
+/// Fixed-size byte arrays as hex strings (serde only derives arrays up to 32
+/// bytes, and those as lists of numbers)
+#[cfg(feature = "serde")]
+mod hex_bytes {
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer, const N: usize>(bytes: &[u8; N], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&hex::encode(bytes))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>, const N: usize>(deserializer: D) -> Result<[u8; N], D::Error> {
+        let encoded = String::deserialize(deserializer)?;
+        hex::decode(&encoded)
+            .map_err(D::Error::custom)?
+            .try_into()
+            .map_err(|_| D::Error::custom(format!("expected {} bytes", N)))
+    }
+}
+
 impl Sealed for OVTState {}
 
 impl Pack for OVTState {
-    const LEN: usize = std::mem::size_of::<Self>();
+    /// Longest encoding the program can produce: every string and enum at its
+    /// widest, since Initialize can't grow the account later
+    const LEN: usize = 8 // nav_sats
+        + 33 // treasury_pubkey_bytes
+        + 8 // total_supply
+        + 8 // last_nav_update
+        + 1 + 4 + MAX_STATUS_MESSAGE_LEN // network_status, as Error
+        + 8 // last_sync_height
+        + 4 // required_confirmations
+        + 1 + 1 + 33 * TREASURY_MULTISIG_KEYS // treasury_script_kind, as P2wshMultisig
+        + 4 + MAX_NETWORK_NAME_LEN // network
+        + 8 // nonce
+        + 32 // treasury_script_hash
+        + 8 // spot_nav_sats
+        + 1 + 2 // ema_alpha_bps
+        + 4 // payment_maturity_blocks
+        + 32; // authority
 
     fn pack_into_slice(&self, dst: &mut [u8]) {
         let data = borsh::to_vec(self).unwrap();
         dst[..data.len()].copy_from_slice(&data);
+        dst[data.len()..].fill(0);
     }
 
+    /// Reads the encoding at the front of the account, which is usually
+    /// shorter than `LEN`; the zero padding after it is ignored
     fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
-        borsh::from_slice(src).map_err(|_| ProgramError::InvalidAccountData)
+        BorshDeserialize::deserialize(&mut &src[..]).map_err(|_| ProgramError::InvalidAccountData)
     }
 }
 
 impl OVTState {
+    /// JSON representation served to the frontend
+    #[cfg(feature = "serde")]
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("OVTState always serializes to JSON")
+    }
+
+    /// A fresh state for `treasury_pubkey_bytes`, which isn't checked; the
+    /// script hash is left zero if the key is invalid
+    ///
+    /// Initialize uses `try_new`.
     pub fn new(treasury_pubkey_bytes: [u8; 33]) -> Self {
-        Self {
+        let mut state = Self {
             nav_sats: 0,
             treasury_pubkey_bytes,
             total_supply: 0,
             last_nav_update: 0,
             network_status: NetworkStatus::Syncing,
             last_sync_height: 0,
+            required_confirmations: DEFAULT_REQUIRED_CONFIRMATIONS,
+            treasury_script_kind: TreasuryScriptKind::P2wpkh,
+            network: String::new(),
+            nonce: 0,
+            treasury_script_hash: [0; 32],
+            spot_nav_sats: 0,
+            ema_alpha_bps: None,
+            payment_maturity_blocks: 0,
+            authority: [0; 32],
+        };
+        if let Ok(script) = state.treasury_script_pubkey() {
+            state.treasury_script_hash = script_hash(&script);
+        }
+        state
+    }
+
+    /// `new`, rejecting anything but a valid compressed secp256k1 point with
+    /// `InvalidTreasuryKey`
+    pub fn try_new(treasury_pubkey_bytes: [u8; 33]) -> Result<Self, ProgramError> {
+        parse_treasury_key(&treasury_pubkey_bytes)?;
+        Ok(Self::new(treasury_pubkey_bytes))
+    }
+
+    /// Reject a state whose treasury key isn't a valid compressed point
+    pub fn validate_treasury(&self) -> Result<(), ProgramError> {
+        parse_treasury_key(&self.treasury_pubkey_bytes).map(|_| ())
+    }
+
+    /// Reject an authority instruction signed for another nonce than the
+    /// current one, i.e. a replay or one from a stale view of the state
+    pub fn check_nonce(&self, expected_nonce: u64) -> Result<(), ProgramError> {
+        if expected_nonce != self.nonce {
+            msg!("Instruction expects nonce {}, state is at {}", expected_nonce, self.nonce);
+            return Err(OVTError::NonceMismatch.into());
+        }
+        Ok(())
+    }
+
+    /// Reject an authority instruction signed by any key but `authority`
+    pub fn check_authority(&self, signer: &Pubkey) -> Result<(), ProgramError> {
+        if signer.0 != self.authority {
+            msg!("Signer {:?} is not the program authority", signer);
+            return Err(OVTError::InvalidAuthority.into());
+        }
+        Ok(())
+    }
+
+    /// Reject `network` if Initialize recorded a different one
+    pub fn validate_network(&self, network: Network) -> Result<(), ProgramError> {
+        let name = network_name(network);
+        if !self.network.is_empty() && self.network != name {
+            msg!("Network validation failed: state was initialized on {}, running on {}", self.network, name);
+            return Err(OVTError::NetworkMismatch.into());
+        }
+        Ok(())
+    }
+
+    /// Record a sync report: `height` may not go backwards, and error messages
+    /// are limited to `MAX_STATUS_MESSAGE_LEN` bytes
+    pub fn update_sync_status(&mut self, height: u64, status: NetworkStatus) -> Result<(), ProgramError> {
+        if height < self.last_sync_height {
+            msg!("Sync height {} is behind the recorded {}", height, self.last_sync_height);
+            return Err(OVTError::InvalidBlockHeight.into());
+        }
+        if let NetworkStatus::Error(message) = &status {
+            if message.len() > MAX_STATUS_MESSAGE_LEN {
+                msg!("Status message is {} bytes, at most {} allowed", message.len(), MAX_STATUS_MESSAGE_LEN);
+                return Err(OVTError::InvalidInstructionData.into());
+            }
         }
+
+        self.last_sync_height = height;
+        self.network_status = status;
+        Ok(())
+    }
+
+    /// scriptPubKey treasury payments must pay to, for the configured script kind
+    pub fn treasury_script_pubkey(&self) -> Result<ScriptBuf, ProgramError> {
+        let treasury_pubkey = PublicKey::from_slice(&self.treasury_pubkey_bytes)
+            .map_err(|_| ProgramError::InvalidAccountData)?;
+        self.treasury_script_kind.script_pubkey(&treasury_pubkey)
+    }
+
+    /// Derive the treasury's scriptPubKey with `kind` from now on, storing
+    /// its hash; a kind whose script can't be derived, such as a multisig
+    /// with an invalid key or threshold, fails with `InvalidTreasuryKey`
+    pub fn set_treasury_script(&mut self, kind: TreasuryScriptKind) -> Result<(), ProgramError> {
+        let treasury_pubkey = parse_treasury_key(&self.treasury_pubkey_bytes)?;
+        let script = kind.script_pubkey(&treasury_pubkey).map_err(|_| -> ProgramError {
+            msg!("Treasury script {:?} can't be derived", kind);
+            OVTError::InvalidTreasuryKey.into()
+        })?;
+        self.treasury_script_hash = script_hash(&script);
+        self.treasury_script_kind = kind;
+        Ok(())
+    }
+
+    /// Whether `script` is the treasury's scriptPubKey, by the stored hash
+    /// where there is one
+    pub fn is_treasury_script(&self, script: &Script) -> bool {
+        if self.treasury_script_hash != [0; 32] {
+            return script_hash(script) == self.treasury_script_hash;
+        }
+        self.treasury_script_pubkey().map_or(false, |treasury| treasury.as_script() == script)
+    }
+
+    /// Policy off-chain verification of treasury payments must apply
+    pub fn validation_policy(&self) -> ValidationPolicy {
+        ValidationPolicy {
+            required_confirmations: self.required_confirmations,
+            ..ValidationPolicy::default()
+        }
+    }
+
+    /// The value the next spot NAV is compared against: the last spot, or
+    /// `nav_sats` for states that predate it
+    pub fn reference_nav_sats(&self) -> u64 {
+        if self.spot_nav_sats != 0 { self.spot_nav_sats } else { self.nav_sats }
     }
 
+    /// Check a submitted spot NAV against `reference_nav_sats`
     pub fn validate_nav_update(&self, new_nav_sats: u64) -> Result<(), ProgramError> {
         // Prevent zero NAV
         if new_nav_sats == 0 {
@@ -91,62 +318,117 @@ impl OVTState {
         }
 
         // If this is the first update, allow any value
-        if self.nav_sats == 0 {
+        let reference = self.reference_nav_sats();
+        if reference == 0 {
             return Ok(());
         }
 
         // Calculate percentage change
-        let change = if new_nav_sats > self.nav_sats {
+        let change = if new_nav_sats > reference {
             // For increases: Calculate percentage increase
-            (new_nav_sats - self.nav_sats) * 100 / self.nav_sats
+            (new_nav_sats - reference) * 100 / reference
         } else {
             // For decreases: Calculate percentage decrease
-            (self.nav_sats - new_nav_sats) * 100 / self.nav_sats
+            (reference - new_nav_sats) * 100 / reference
         };
 
         // For increases: limit to 400% (5x)
         // For decreases: limit to 80% (0.2x)
-        if (new_nav_sats > self.nav_sats && change > 400) || 
-           (new_nav_sats < self.nav_sats && change > 80) {
+        if (new_nav_sats > reference && change > 400) || 
+           (new_nav_sats < reference && change > 80) {
             return Err(OVTError::InvalidNAVUpdate.into());
         }
 
         Ok(())
     }
 
+    /// Turn NAV smoothing on with weight `ema_alpha_bps`, or off with None;
+    /// `nav_sats` is left alone until the next UpdateNAV
+    pub fn set_nav_smoothing(&mut self, ema_alpha_bps: Option<u16>) -> Result<(), ProgramError> {
+        if let Some(alpha) = ema_alpha_bps {
+            if alpha == 0 || alpha > MAX_EMA_ALPHA_BPS {
+                msg!("EMA weight {} bps is outside 1..={}", alpha, MAX_EMA_ALPHA_BPS);
+                return Err(OVTError::InvalidInstructionData.into());
+            }
+        }
+        self.ema_alpha_bps = ema_alpha_bps;
+        Ok(())
+    }
+
     pub fn update_nav(
         &mut self,
         btc_price_sats: u64,
         clock_info: &AccountInfo,
     ) -> Result<(), ProgramError> {
-        // Get current timestamp from clock sysvar
-        let clock_data = clock_info.try_borrow_data().map_err(|_| ProgramError::AccountBorrowFailed)?;
-        let current_time = u64::from_le_bytes(clock_data[..8].try_into().map_err(|_| ProgramError::InvalidAccountData)?);
+        self.apply_nav_update(btc_price_sats, read_clock(clock_info)?)
+    }
 
-        // Ensure sufficient time has passed since last update (15 seconds minimum)
-        if current_time - self.last_nav_update < 15 {
+    /// `update_nav` with the time already read from the clock
+    pub fn apply_nav_update(&mut self, btc_price_sats: u64, current_time: u64) -> Result<(), ProgramError> {
+        // Ensure sufficient time has passed since last update (15 seconds minimum);
+        // a clock behind the last update counts as too soon
+        if current_time.saturating_sub(self.last_nav_update) < 15 {
             return Err(OVTError::OperationTimeout.into());
         }
 
         // Validate the NAV update
         self.validate_nav_update(btc_price_sats)?;
 
-        self.nav_sats = btc_price_sats;
+        self.nav_sats = match self.ema_alpha_bps {
+            Some(alpha) if self.nav_sats != 0 => ema_nav_sats(self.nav_sats, btc_price_sats, alpha),
+            _ => btc_price_sats,
+        };
+        self.spot_nav_sats = btc_price_sats;
         self.last_nav_update = current_time;
+
+        // A priced update after a sync has been reported means the program is live
+        if self.network_status == NetworkStatus::Syncing && self.last_sync_height > 0 {
+            self.network_status = NetworkStatus::Active;
+        }
+        Ok(())
+    }
+
+    /// Reject a payment mined at `payment_height` unless it is at least
+    /// `payment_maturity_blocks` below the last reported sync height
+    ///
+    /// Measured against `last_sync_height` rather than the confirmations
+    /// one node reports. A height of 0, i.e. unknown, is never mature while
+    /// a maturity is set.
+    pub fn check_payment_maturity(&self, payment_height: u64) -> Result<(), ProgramError> {
+        if self.payment_maturity_blocks == 0 {
+            return Ok(());
+        }
+        let mature_at = payment_height.saturating_add(u64::from(self.payment_maturity_blocks));
+        if payment_height == 0 || mature_at > self.last_sync_height {
+            msg!(
+                "Payment at height {} matures at {}, synced to {}",
+                payment_height, mature_at, self.last_sync_height
+            );
+            return Err(OVTError::PaymentImmature.into());
+        }
         Ok(())
     }
 
+    /// Burn the tokens `payment_amount_sats` buys back at the current NAV;
+    /// returns how many were burned
     pub fn process_buyback_burn(
         &mut self,
         payment_amount_sats: u64,
-    ) -> Result<(), ProgramError> {
+    ) -> Result<u64, ProgramError> {
         // Verify payment amount is reasonable
         if payment_amount_sats == 0 || payment_amount_sats > 1_000_000_000 {
             return Err(OVTError::InvalidBitcoinTransaction.into());
         }
 
-        // Calculate tokens to burn based on NAV
-        let tokens_to_burn = (payment_amount_sats * self.total_supply) / self.nav_sats;
+        if self.nav_sats == 0 {
+            msg!("No NAV has been set to price the burn at");
+            return Err(OVTError::InvalidNAVUpdate.into());
+        }
+
+        // Tokens in proportion to the payment's share of the NAV, in u128
+        // since the product can exceed u64
+        let tokens_to_burn = u128::from(payment_amount_sats) * u128::from(self.total_supply) / u128::from(self.nav_sats);
+        let tokens_to_burn = u64::try_from(tokens_to_burn).map_err(|_| OVTError::InvalidSupplyChange)?;
         if tokens_to_burn == 0 {
             return Err(OVTError::InsufficientFunds.into());
         }
@@ -155,42 +437,314 @@ impl OVTState {
         self.total_supply = self.total_supply.checked_sub(tokens_to_burn)
             .ok_or(OVTError::InvalidSupplyChange)?;
 
-        Ok(())
+        Ok(tokens_to_burn)
+    }
+
+    /// Apply `instruction` at unix time `now`, which only UpdateNAV reads
+    ///
+    /// This is the whole state transition of an instruction, shared by the
+    /// handlers and `OVTProgram::simulate`: nothing changes unless it succeeds.
+    /// Initialize creates the state rather than changing it, so isn't accepted,
+    /// and neither are the legacy layouts without a nonce.
+    /// UpdateNAV, BuybackBurn, UpdateSyncStatus, WriteEntry and the setters
+    /// are checked against and advance `nonce`.
+    /// GetBurnHistory doesn't touch the state.
+    pub fn apply_instruction(&mut self, instruction: &OVTInstruction, now: u64) -> Result<Transition, ProgramError> {
+        match instruction {
+            OVTInstruction::Initialize { .. } => {
+                msg!("Initialize creates the state account and can't be applied to a state");
+                Err(ProgramError::InvalidInstructionData)
+            }
+            OVTInstruction::LegacyUpdateNAV { .. } | OVTInstruction::LegacyBuybackBurn { .. } => Err(legacy_instruction()),
+            OVTInstruction::UpdateNAV { btc_price_sats, expected_nonce } => {
+                self.check_nonce(*expected_nonce)?;
+                self.apply_nav_update(*btc_price_sats, now)?;
+                self.nonce += 1;
+                Ok(Transition::default())
+            }
+            OVTInstruction::UpdateSyncStatus { height, status, expected_nonce } => {
+                self.check_nonce(*expected_nonce)?;
+                self.update_sync_status(*height, status.clone())?;
+                self.nonce += 1;
+                Ok(Transition::default())
+            }
+            OVTInstruction::BuybackBurn { payment_txid, payment_amount_sats, payment_height, memo, expected_nonce } => {
+                self.check_nonce(*expected_nonce)?;
+                self.check_payment_maturity(*payment_height)?;
+                let tokens_burned = self.process_buyback_burn(*payment_amount_sats)?;
+                let record = BurnRecord::for_burn(payment_txid, *payment_amount_sats, tokens_burned, self)?;
+                self.nonce += 1;
+                Ok(Transition {
+                    tokens_burned,
+                    events: vec![buyback_burn_event(payment_txid, *payment_amount_sats, memo.as_ref())],
+                    burn: Some(record),
+                    ..Transition::default()
+                })
+            }
+            OVTInstruction::GetBurnHistory => Ok(Transition::default()),
+            OVTInstruction::SetNavSmoothing { ema_alpha_bps, expected_nonce } => {
+                self.check_nonce(*expected_nonce)?;
+                self.set_nav_smoothing(*ema_alpha_bps)?;
+                self.nonce += 1;
+                Ok(Transition::default())
+            }
+            OVTInstruction::SetPaymentMaturity { blocks, expected_nonce } => {
+                self.check_nonce(*expected_nonce)?;
+                self.payment_maturity_blocks = *blocks;
+                self.nonce += 1;
+                Ok(Transition::default())
+            }
+            OVTInstruction::SetTreasuryScript { script_kind, expected_nonce } => {
+                self.check_nonce(*expected_nonce)?;
+                self.set_treasury_script(script_kind.clone())?;
+                self.nonce += 1;
+                Ok(Transition::default())
+            }
+            OVTInstruction::WriteEntry { key, value, expected_nonce } => {
+                self.check_nonce(*expected_nonce)?;
+                if value.len() > MAX_ENTRY_VALUE_LEN {
+                    msg!("Entry value is {} bytes, at most {} allowed", value.len(), MAX_ENTRY_VALUE_LEN);
+                    return Err(ProgramError::InvalidInstructionData);
+                }
+                self.nonce += 1;
+                Ok(Transition { entry: Some((key.clone(), value.clone())), ..Transition::default() })
+            }
+        }
+    }
+}
+
+/// `alpha_bps` of `spot` plus the rest of `prior`, in u128 so the products
+/// can't overflow
+///
+/// Rounded towards `spot`, so repeated updates at the same spot reach it
+/// rather than stalling a satoshi short.
+pub fn ema_nav_sats(prior: u64, spot: u64, alpha_bps: u16) -> u64 {
+    let whole = u128::from(MAX_EMA_ALPHA_BPS);
+    let alpha = u128::from(alpha_bps.min(MAX_EMA_ALPHA_BPS));
+    let weighted = alpha * u128::from(spot) + (whole - alpha) * u128::from(prior);
+    let ema = if spot > prior { weighted.div_ceil(whole) } else { weighted / whole };
+    // A weighted mean of two u64 values fits in a u64
+    ema as u64
+}
+
+/// What `OVTState::apply_instruction` did besides changing the state
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Transition {
+    pub tokens_burned: u64,
+    /// Log lines the handler emits once the new state is stored
+    pub events: Vec<String>,
+    /// What BuybackBurn appends to the burn history
+    pub burn: Option<BurnRecord>,
+    /// The key and value WriteEntry stores in the key's entry account
+    pub entry: Option<(Vec<u8>, Vec<u8>)>,
+}
+
+fn parse_treasury_key(bytes: &[u8; 33]) -> Result<PublicKey, ProgramError> {
+    PublicKey::from_slice(bytes).map_err(|_| {
+        msg!("Treasury key {} is not a valid compressed public key", hex::encode(bytes));
+        OVTError::InvalidTreasuryKey.into()
+    })
+}
+
+fn script_hash(script: &Script) -> [u8; 32] {
+    sha256::Hash::hash(script.as_bytes()).to_byte_array()
+}
+
+/// The error for a legacy instruction layout, which carries no nonce to check
+fn legacy_instruction() -> ProgramError {
+    msg!("Instruction uses a layout from before nonces; sign its current variant instead");
+    OVTError::UnsupportedInstructionVersion.into()
+}
+
+/// Unix time from the clock sysvar's first 8 bytes
+fn read_clock(clock_info: &AccountInfo) -> Result<u64, ProgramError> {
+    let clock_data = clock_info.try_borrow_data().map_err(|_| ProgramError::AccountBorrowFailed)?;
+    let bytes = clock_data.get(..8).ok_or(ProgramError::InvalidAccountData)?;
+    Ok(u64::from_le_bytes(bytes.try_into().map_err(|_| ProgramError::InvalidAccountData)?))
+}
+
+/// Accounts BuybackBurn records a burn in
+struct BurnAccounts<'b, 'a> {
+    history: &'b AccountInfo<'a>,
+    /// At `spent_payment_address` for the payment; empty until it is burned for
+    spent_payment: &'b AccountInfo<'a>,
+    payer: &'b AccountInfo<'a>,
+    system_program: &'b AccountInfo<'a>,
+}
+
+impl BurnAccounts<'_, '_> {
+    /// Append `record` to the history and create the payment's spent-payment
+    /// account, failing with `DuplicatePayment` if it exists already
+    fn record(&self, program_id: &Pubkey, record: BurnRecord) -> Result<(), ProgramError> {
+        let (address, bump) = Pubkey::find_program_address(&[SPENT_PAYMENT_SEED, &record.payment_txid], program_id);
+        if *self.spent_payment.key != address {
+            msg!("Spent payment account {:?} is not derived from payment {}", self.spent_payment.key, record.payment_txid_hex());
+            return Err(OVTError::InvalidProgramState.into());
+        }
+        if !self.spent_payment.try_borrow_data()?.is_empty() {
+            msg!("Payment {} has already been burned for", record.payment_txid_hex());
+            return Err(OVTError::DuplicatePayment.into());
+        }
+
+        let mut history_data = self.history.try_borrow_mut_data().map_err(|_| ProgramError::AccountBorrowFailed)?;
+        let mut history = BurnHistory::unpack(&history_data)?;
+        history.append(record.clone())?;
+
+        create_program_account(
+            program_id,
+            self.spent_payment,
+            self.payer,
+            BurnRecord::LEN as u64,
+            self.system_program,
+            &[SPENT_PAYMENT_SEED, &record.payment_txid, &[bump]],
+        )?;
+        initialize_account(program_id, self.spent_payment, &record)?;
+        history.pack_into(&mut history_data)
+    }
+}
+
+/// Accounts WriteEntry stores an entry in
+struct EntryAccounts<'b, 'a> {
+    /// At `entry_address` for the key; empty until its first write
+    entry: &'b AccountInfo<'a>,
+    payer: &'b AccountInfo<'a>,
+    system_program: &'b AccountInfo<'a>,
+}
+
+impl EntryAccounts<'_, '_> {
+    /// Write `value` as a Borsh `Vec<u8>` at the start of the entry account
+    /// for `key`, creating the account on the first write
+    ///
+    /// Bytes past the value are left over from longer values written before
+    /// and are never read.
+    fn store(&self, program_id: &Pubkey, key: &[u8], value: &[u8]) -> Result<(), ProgramError> {
+        let key_hash = entry_key_hash(key);
+        let (address, bump) = Pubkey::find_program_address(&[ENTRY_SEED, &key_hash], program_id);
+        if *self.entry.key != address {
+            msg!("Entry account {:?} is not derived from its key", self.entry.key);
+            return Err(OVTError::InvalidProgramState.into());
+        }
+        if self.entry.try_borrow_data()?.is_empty() {
+            create_program_account(
+                program_id,
+                self.entry,
+                self.payer,
+                ENTRY_ACCOUNT_LEN as u64,
+                self.system_program,
+                &[ENTRY_SEED, &key_hash, &[bump]],
+            )?;
+        }
+        initialize_account(program_id, self.entry, &value.to_vec())
+    }
+}
+
+/// Accounts besides the state that `execute` stores what a transition records in
+enum RecordAccounts<'b, 'a> {
+    /// The instruction only changes the state
+    StateOnly,
+    Burn(BurnAccounts<'b, 'a>),
+    Entry(EntryAccounts<'b, 'a>),
+}
+
+/// Unpack the state in `state_info`, apply `instruction` and store the result,
+/// emitting its events only once the new state is stored
+///
+/// A state Initialize recorded for another network than the one the program
+/// was built for is refused with `NetworkMismatch`, and a signer other than the
+/// authority Initialize recorded with `InvalidAuthority`.
+///
+/// A burn or an entry is stored in `records` first, so a duplicate payment or
+/// a full account leaves the state as it was.
+fn execute(
+    program_id: &Pubkey,
+    state_info: &AccountInfo,
+    authority: &Pubkey,
+    records: &RecordAccounts,
+    instruction: &OVTInstruction,
+    now: u64,
+) -> Result<(), ProgramError> {
+    let mut data = state_info.try_borrow_mut_data().map_err(|_| ProgramError::AccountBorrowFailed)?;
+    let mut state: OVTState = Pack::unpack_from_slice(&data)?;
+    state.validate_network(crate::network_config::PROGRAM_NETWORK)?;
+    state.check_authority(authority)?;
+    let transition = state.apply_instruction(instruction, now)?;
+    if let Some(record) = transition.burn.clone() {
+        let RecordAccounts::Burn(burn_accounts) = records else { return Err(ProgramError::NotEnoughAccountKeys) };
+        burn_accounts.record(program_id, record)?;
+    }
+    if let Some((key, value)) = &transition.entry {
+        let RecordAccounts::Entry(entry_accounts) = records else { return Err(ProgramError::NotEnoughAccountKeys) };
+        entry_accounts.store(program_id, key, value)?;
+    }
+    Pack::pack_into_slice(&state, &mut data);
+    for event in &transition.events {
+        msg!("{}", event);
+    }
+    Ok(())
+}
+
+/// The burn event indexers use to attribute buybacks to payers
+pub fn buyback_burn_event(payment_txid: &str, payment_amount_sats: u64, memo: Option<&PaymentMemo>) -> String {
+    match memo {
+        Some(memo) => format!("BuybackBurn: payment {} for {} sats, {}", payment_txid, payment_amount_sats, memo),
+        None => format!("BuybackBurn: payment {} for {} sats, no memo", payment_txid, payment_amount_sats),
     }
 }
 
 impl Program for OVTProgram {
     fn process_instruction(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> Result<(), ProgramError> {
+        ProgramIds::default().check(program_id)?;
         let instruction = OVTInstruction::try_from_slice(data)
             .map_err(|_| ProgramError::InvalidInstructionData)?;
         
         match instruction {
             OVTInstruction::Initialize { treasury_pubkey_bytes } => {
                 let state_info = accounts.get(0).ok_or(ProgramError::NotEnoughAccountKeys)?;
+                check_state_account(program_id, state_info)?;
                 let authority_info = accounts.get(1).ok_or(ProgramError::NotEnoughAccountKeys)?;
                 let system_program = accounts.get(2).ok_or(ProgramError::NotEnoughAccountKeys)?;
+                let history_info = accounts.get(3).ok_or(ProgramError::NotEnoughAccountKeys)?;
+                check_burn_history_account(program_id, history_info)?;
 
                 if !authority_info.is_signer {
                     return Err(ProgramError::MissingRequiredSignature);
                 }
+                // Before the account exists, so a bad key leaves nothing behind
+                let mut state = OVTState::try_new(treasury_pubkey_bytes)?;
 
-                // Create and initialize state account
+                // Create and initialize state account, signing for its address
+                let (_, bump) = Pubkey::find_program_address(&[OVT_STATE_SEED], program_id);
                 create_program_account(
                     program_id,
                     state_info,
                     authority_info,
                     OVTState::LEN as u64,
                     system_program,
+                    &[OVT_STATE_SEED, &[bump]],
                 )?;
 
-                // Initialize new state
-                let state = OVTState::new(treasury_pubkey_bytes);
-                let mut data = state_info.try_borrow_mut_data().map_err(|_| ProgramError::AccountBorrowFailed)?;
-                Pack::pack_into_slice(&state, &mut data);
-                Ok(())
+                // Initialize new state, requiring as many confirmations as the network warrants
+                let network = crate::network_config::PROGRAM_NETWORK;
+                state.required_confirmations = ValidationPolicy::for_network(network).required_confirmations;
+                state.network = network_name(network).to_string();
+                state.authority = authority_info.key.0;
+                initialize_account(program_id, state_info, &state)?;
+
+                // Zeroed data is an empty history
+                let (_, bump) = Pubkey::find_program_address(&[BURN_HISTORY_SEED], program_id);
+                create_program_account(
+                    program_id,
+                    history_info,
+                    authority_info,
+                    BurnHistory::LEN as u64,
+                    system_program,
+                    &[BURN_HISTORY_SEED, &[bump]],
+                )
             }
-            OVTInstruction::UpdateNAV { btc_price_sats } => {
+            OVTInstruction::LegacyUpdateNAV { .. } | OVTInstruction::LegacyBuybackBurn { .. } => Err(legacy_instruction()),
+            OVTInstruction::UpdateNAV { .. } => {
                 let state_info = accounts.get(0).ok_or(ProgramError::NotEnoughAccountKeys)?;
+                check_state_account(program_id, state_info)?;
                 let authority_info = accounts.get(1).ok_or(ProgramError::NotEnoughAccountKeys)?;
                 let clock_info = accounts.get(2).ok_or(ProgramError::NotEnoughAccountKeys)?;
 
@@ -198,24 +752,66 @@ impl Program for OVTProgram {
                     return Err(ProgramError::MissingRequiredSignature);
                 }
 
-                let mut data = state_info.try_borrow_mut_data().map_err(|_| ProgramError::AccountBorrowFailed)?;
-                let mut state: OVTState = Pack::unpack_from_slice(&data)?;
-                state.update_nav(btc_price_sats, clock_info)?;
-                Pack::pack_into_slice(&state, &mut data);
-                Ok(())
+                execute(program_id, state_info, authority_info.key, &RecordAccounts::StateOnly, &instruction, read_clock(clock_info)?)
+            }
+            OVTInstruction::UpdateSyncStatus { .. }
+            | OVTInstruction::SetNavSmoothing { .. }
+            | OVTInstruction::SetPaymentMaturity { .. }
+            | OVTInstruction::SetTreasuryScript { .. } => {
+                let state_info = accounts.get(0).ok_or(ProgramError::NotEnoughAccountKeys)?;
+                check_state_account(program_id, state_info)?;
+                let authority_info = accounts.get(1).ok_or(ProgramError::NotEnoughAccountKeys)?;
+
+                if !authority_info.is_signer {
+                    return Err(ProgramError::MissingRequiredSignature);
+                }
+
+                // None of these read the clock
+                execute(program_id, state_info, authority_info.key, &RecordAccounts::StateOnly, &instruction, 0)
             }
-            OVTInstruction::BuybackBurn { payment_txid: _, payment_amount_sats } => {
+            OVTInstruction::BuybackBurn { .. } => {
                 let state_info = accounts.get(0).ok_or(ProgramError::NotEnoughAccountKeys)?;
+                check_state_account(program_id, state_info)?;
                 let authority_info = accounts.get(1).ok_or(ProgramError::NotEnoughAccountKeys)?;
+                let history_info = accounts.get(2).ok_or(ProgramError::NotEnoughAccountKeys)?;
+                check_burn_history_account(program_id, history_info)?;
+                let burn_accounts = BurnAccounts {
+                    history: history_info,
+                    spent_payment: accounts.get(3).ok_or(ProgramError::NotEnoughAccountKeys)?,
+                    payer: authority_info,
+                    system_program: accounts.get(4).ok_or(ProgramError::NotEnoughAccountKeys)?,
+                };
 
                 if !authority_info.is_signer {
                     return Err(ProgramError::MissingRequiredSignature);
                 }
 
-                let mut data = state_info.try_borrow_mut_data().map_err(|_| ProgramError::AccountBorrowFailed)?;
-                let mut state: OVTState = Pack::unpack_from_slice(&data)?;
-                state.process_buyback_burn(payment_amount_sats)?;
-                Pack::pack_into_slice(&state, &mut data);
+                execute(program_id, state_info, authority_info.key, &RecordAccounts::Burn(burn_accounts), &instruction, 0)
+            }
+            OVTInstruction::WriteEntry { .. } => {
+                let state_info = accounts.get(0).ok_or(ProgramError::NotEnoughAccountKeys)?;
+                check_state_account(program_id, state_info)?;
+                let authority_info = accounts.get(1).ok_or(ProgramError::NotEnoughAccountKeys)?;
+                let entry_accounts = EntryAccounts {
+                    entry: accounts.get(2).ok_or(ProgramError::NotEnoughAccountKeys)?,
+                    payer: authority_info,
+                    system_program: accounts.get(3).ok_or(ProgramError::NotEnoughAccountKeys)?,
+                };
+
+                if !authority_info.is_signer {
+                    return Err(ProgramError::MissingRequiredSignature);
+                }
+
+                execute(program_id, state_info, authority_info.key, &RecordAccounts::Entry(entry_accounts), &instruction, 0)
+            }
+            OVTInstruction::GetBurnHistory => {
+                let history_info = accounts.get(0).ok_or(ProgramError::NotEnoughAccountKeys)?;
+                check_burn_history_account(program_id, history_info)?;
+
+                let data = history_info.try_borrow_data().map_err(|_| ProgramError::AccountBorrowFailed)?;
+                for record in BurnHistory::unpack(&data)?.records() {
+                    msg!("{}", burn_record_event(&record));
+                }
                 Ok(())
             }
         }
@@ -225,6 +821,8 @@ impl Program for OVTProgram {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::address::spent_payment_address;
+    use crate::burn_history::BURN_HISTORY_CAPACITY;
     use arch_program::utxo::UtxoMeta;
     use std::{rc::Rc, cell::RefCell};
     
@@ -232,6 +830,26 @@ mod tests {
     static mut TEST_NAV_SATS: u64 = 1_000_000;
     static mut TEST_SUPPLY: u64 = 1_000_000_000;
     
+    // The secp256k1 generator, a valid compressed key
+    const TREASURY_KEY: &str = "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798";
+
+    fn treasury_key() -> [u8; 33] {
+        hex::decode(TREASURY_KEY).unwrap().try_into().unwrap()
+    }
+
+    fn owned_account<'a>(key: &'a Pubkey, owner: &'a Pubkey, data: Vec<u8>, is_signer: bool) -> AccountInfo<'a> {
+        AccountInfo {
+            key,
+            is_signer,
+            is_writable: true,
+            lamports: Rc::new(RefCell::new(0)),
+            data: Rc::new(RefCell::new(data)),
+            owner,
+            executable: false,
+            rent_epoch: 0,
+        }
+    }
+
     // Helper function to create test account info
     fn create_test_account_info(data: &mut [u8]) -> AccountInfo {
         let key = Pubkey::new_unique();
@@ -255,11 +873,9 @@ mod tests {
     fn test_nav_validation() {
         let mut state = OVTState {
             nav_sats: 1_000_000,
-            treasury_pubkey_bytes: [0; 33],
             total_supply: 1_000_000,
-            last_nav_update: 0,
-            network_status: NetworkStatus::Syncing,
-            last_sync_height: 0,
+            required_confirmations: 6,
+            ..OVTState::new(treasury_key())
         };
 
         // First update at t = 16 (valid: enough time passed)
@@ -297,11 +913,9 @@ mod tests {
     fn test_supply_validation() {
         let mut state = OVTState {
             nav_sats: 1_000_000,
-            treasury_pubkey_bytes: [0; 33],
             total_supply: 1_000_000,
-            last_nav_update: 0,
-            network_status: NetworkStatus::Syncing,
-            last_sync_height: 0,
+            required_confirmations: 6,
+            ..OVTState::new(treasury_key())
         };
 
         // Test valid changes
@@ -312,4 +926,464 @@ mod tests {
         assert!(state.process_buyback_burn(2_000_000).is_err()); // Too large
         assert!(state.process_buyback_burn(0).is_err()); // Zero amount
     }
-} 
\ No newline at end of file
+
+    #[test]
+    fn test_sync_height_is_monotonic() {
+        let mut state = OVTState::new(treasury_key());
+        assert!(state.update_sync_status(100, NetworkStatus::Syncing).is_ok());
+        assert!(state.update_sync_status(100, NetworkStatus::Syncing).is_ok());
+        assert!(matches!(
+            state.update_sync_status(99, NetworkStatus::Active),
+            Err(ProgramError::Custom(code)) if code == OVTError::InvalidBlockHeight.code()
+        ));
+        assert_eq!((state.last_sync_height, &state.network_status), (100, &NetworkStatus::Syncing));
+
+        assert!(state.update_sync_status(101, NetworkStatus::Active).is_ok());
+        assert_eq!((state.last_sync_height, &state.network_status), (101, &NetworkStatus::Active));
+    }
+
+    #[test]
+    fn test_oversized_status_message() {
+        let mut state = OVTState::new(treasury_key());
+        let longest = "e".repeat(MAX_STATUS_MESSAGE_LEN);
+        assert!(state.update_sync_status(1, NetworkStatus::Error(longest.clone())).is_ok());
+
+        let oversized = NetworkStatus::Error("e".repeat(MAX_STATUS_MESSAGE_LEN + 1));
+        assert!(matches!(
+            state.update_sync_status(2, oversized),
+            Err(ProgramError::Custom(code)) if code == OVTError::InvalidInstructionData.code()
+        ));
+        assert_eq!(state.last_sync_height, 1);
+
+        // The largest state the program can produce fills its account exactly
+        state.treasury_script_kind = TreasuryScriptKind::P2wshMultisig { threshold: 2, pubkeys: [[2; 33]; 3] };
+        state.network = "testnet".to_string();
+        state.ema_alpha_bps = Some(MAX_EMA_ALPHA_BPS);
+        assert_eq!(borsh::to_vec(&state).unwrap().len(), OVTState::LEN);
+        for network in [Network::Bitcoin, Network::Testnet, Network::Signet, Network::Regtest] {
+            assert!(network_name(network).len() <= MAX_NETWORK_NAME_LEN);
+        }
+    }
+
+    /// Initialize allocates `OVTState::LEN` bytes, more than a fresh state
+    /// encodes to, and the next instruction must still read it back
+    #[test]
+    fn test_initialize_then_update_nav() {
+        let program_id = ProgramIds::default().ovt;
+        let state_key = crate::address::state_address(&program_id);
+        let (authority_key, system_key, clock_key) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let state_info = owned_account(&state_key, &program_id, Vec::new(), false);
+        let authority = owned_account(&authority_key, &system_key, Vec::new(), true);
+        let system_program = owned_account(&system_key, &system_key, Vec::new(), false);
+        let clock = owned_account(&clock_key, &system_key, 16u64.to_le_bytes().to_vec(), false);
+        let history_key = crate::address::burn_history_address(&program_id);
+        let history = owned_account(&history_key, &program_id, Vec::new(), false);
+
+        let initialize = OVTInstruction::Initialize { treasury_pubkey_bytes: treasury_key() };
+        let accounts = [state_info.clone(), authority.clone(), system_program, history.clone()];
+        OVTProgram::process_instruction(&program_id, &accounts, &borsh::to_vec(&initialize).unwrap()).unwrap();
+        assert_eq!(state_info.data.borrow().len(), OVTState::LEN);
+        assert_eq!(history.data.borrow().len(), BurnHistory::LEN);
+        assert!(BurnHistory::unpack(&history.data.borrow()).unwrap().is_empty());
+        let fresh = OVTState::unpack_from_slice(&state_info.data.borrow()).unwrap();
+        assert!(borsh::to_vec(&fresh).unwrap().len() < OVTState::LEN);
+        assert_eq!(fresh.authority, authority_key.0);
+
+        let update = OVTInstruction::UpdateNAV { btc_price_sats: 1_000_000, expected_nonce: 0 };
+        let accounts = [state_info.clone(), authority, clock];
+        OVTProgram::process_instruction(&program_id, &accounts, &borsh::to_vec(&update).unwrap()).unwrap();
+        let state = OVTState::unpack_from_slice(&state_info.data.borrow()).unwrap();
+        assert_eq!((state.nav_sats, state.last_nav_update, state.nonce), (1_000_000, 16, 1));
+        assert_eq!(state_info.data.borrow().len(), OVTState::LEN);
+    }
+
+    /// The ring keeps the last `BURN_HISTORY_CAPACITY` burns, but a payment
+    /// that has dropped out of it is still refused by its spent-payment account
+    #[test]
+    fn test_payment_stays_spent_after_leaving_the_history() {
+        let program_id = ProgramIds::default().ovt;
+        let state_key = crate::address::state_address(&program_id);
+        let history_key = crate::address::burn_history_address(&program_id);
+        let (authority_key, system_key) = (Pubkey::new_unique(), Pubkey::system_program());
+        let mut state = OVTState::new(treasury_key());
+        state.nav_sats = 1_000_000_000;
+        state.total_supply = 1_000_000_000;
+        state.authority = authority_key.0;
+        let mut state_data = vec![0; OVTState::LEN];
+        state.pack_into_slice(&mut state_data);
+        let state_info = owned_account(&state_key, &program_id, state_data, false);
+        let authority = owned_account(&authority_key, &system_key, Vec::new(), true);
+        let history = owned_account(&history_key, &program_id, vec![0; BurnHistory::LEN], false);
+        let system_program = owned_account(&system_key, &system_key, Vec::new(), false);
+
+        let txids: Vec<[u8; 32]> = (0..=BURN_HISTORY_CAPACITY as u8).map(|i| [i; 32]).collect();
+        let spent_keys: Vec<Pubkey> = txids.iter().map(|txid| spent_payment_address(&program_id, txid)).collect();
+        let spent: Vec<AccountInfo> = spent_keys.iter().map(|key| owned_account(key, &program_id, Vec::new(), false)).collect();
+        let burn = |i: usize| {
+            let instruction = OVTInstruction::BuybackBurn {
+                payment_txid: hex::encode(txids[i]),
+                payment_amount_sats: 1_000,
+                payment_height: 0,
+                memo: None,
+                expected_nonce: OVTState::unpack_from_slice(&state_info.data.borrow()).unwrap().nonce,
+            };
+            let accounts = [state_info.clone(), authority.clone(), history.clone(), spent[i].clone(), system_program.clone()];
+            OVTProgram::process_instruction(&program_id, &accounts, &borsh::to_vec(&instruction).unwrap())
+        };
+
+        for i in 0..txids.len() {
+            burn(i).unwrap();
+        }
+        let records = BurnHistory::unpack(&history.data.borrow()).unwrap();
+        assert!(records.records().iter().all(|record| record.payment_txid != txids[0]));
+        assert_eq!(spent[0].data.borrow().len(), BurnRecord::LEN);
+
+        assert!(matches!(burn(0), Err(ProgramError::Custom(code)) if code == OVTError::DuplicatePayment.code()));
+        let after = OVTState::unpack_from_slice(&state_info.data.borrow()).unwrap();
+        assert_eq!(after.nonce, txids.len() as u64);
+        assert_eq!(BurnHistory::unpack(&history.data.borrow()).unwrap(), records);
+
+        // Only the payment's own account will do
+        let forged = OVTInstruction::BuybackBurn {
+            payment_txid: hex::encode([0xee; 32]),
+            payment_amount_sats: 1_000,
+            payment_height: 0,
+            memo: None,
+            expected_nonce: after.nonce,
+        };
+        let unused = owned_account(&spent_keys[1], &program_id, Vec::new(), false);
+        let accounts = [state_info.clone(), authority.clone(), history.clone(), unused, system_program.clone()];
+        assert!(matches!(
+            OVTProgram::process_instruction(&program_id, &accounts, &borsh::to_vec(&forged).unwrap()),
+            Err(ProgramError::Custom(code)) if code == OVTError::InvalidProgramState.code()
+        ));
+    }
+
+    /// Entries go in one account per key, created on the first write and
+    /// overwritten by later ones
+    #[test]
+    fn test_write_entry_stores_the_value_in_its_account() {
+        let program_id = ProgramIds::default().ovt;
+        let state_key = crate::address::state_address(&program_id);
+        let entry_key = crate::address::entry_address(&program_id, b"ovt\0key");
+        let (authority_key, system_key) = (Pubkey::new_unique(), Pubkey::system_program());
+        let mut state = OVTState::new(treasury_key());
+        state.authority = authority_key.0;
+        let mut state_data = vec![0; OVTState::LEN];
+        state.pack_into_slice(&mut state_data);
+        let state_info = owned_account(&state_key, &program_id, state_data, false);
+        let authority = owned_account(&authority_key, &system_key, Vec::new(), true);
+        let entry = owned_account(&entry_key, &program_id, Vec::new(), false);
+        let system_program = owned_account(&system_key, &system_key, Vec::new(), false);
+        let write = |entry: &AccountInfo, value: Vec<u8>| {
+            let instruction = OVTInstruction::WriteEntry {
+                key: b"ovt\0key".to_vec(),
+                value,
+                expected_nonce: OVTState::unpack_from_slice(&state_info.data.borrow()).unwrap().nonce,
+            };
+            let accounts = [state_info.clone(), authority.clone(), entry.clone(), system_program.clone()];
+            OVTProgram::process_instruction(&program_id, &accounts, &borsh::to_vec(&instruction).unwrap())
+        };
+        let stored = |entry: &AccountInfo| Vec::<u8>::deserialize(&mut &entry.data.borrow()[..]).unwrap();
+
+        write(&entry, b"a longer value".to_vec()).unwrap();
+        assert_eq!(entry.data.borrow().len(), ENTRY_ACCOUNT_LEN);
+        write(&entry, b"short".to_vec()).unwrap();
+        assert_eq!(stored(&entry), b"short");
+        assert_eq!(OVTState::unpack_from_slice(&state_info.data.borrow()).unwrap().nonce, 2);
+
+        assert!(matches!(write(&entry, vec![0; MAX_ENTRY_VALUE_LEN + 1]), Err(ProgramError::InvalidInstructionData)));
+        let other_key = crate::address::entry_address(&program_id, b"ovt\0other");
+        let other = owned_account(&other_key, &program_id, Vec::new(), false);
+        assert!(matches!(
+            write(&other, b"value".to_vec()),
+            Err(ProgramError::Custom(code)) if code == OVTError::InvalidProgramState.code()
+        ));
+        assert_eq!(stored(&entry), b"short");
+        assert_eq!(OVTState::unpack_from_slice(&state_info.data.borrow()).unwrap().nonce, 2);
+    }
+
+    #[test]
+    fn test_nav_update_activates_synced_state() {
+        let mut state = OVTState::new(treasury_key());
+        let mut clock_data = 16u64.to_le_bytes();
+        let clock_info = create_test_account_info(&mut clock_data);
+
+        // No sync reported yet, so the program stays in Syncing
+        assert!(state.update_nav(1_000_000, &clock_info).is_ok());
+        assert_eq!(state.network_status, NetworkStatus::Syncing);
+
+        state.update_sync_status(100, NetworkStatus::Syncing).unwrap();
+        let mut clock_data = 32u64.to_le_bytes();
+        let clock_info = create_test_account_info(&mut clock_data);
+        assert!(state.update_nav(1_100_000, &clock_info).is_ok());
+        assert_eq!(state.network_status, NetworkStatus::Active);
+
+        // Errors are only cleared by a sync report
+        state.update_sync_status(101, NetworkStatus::Error("node unreachable".to_string())).unwrap();
+        let mut clock_data = 48u64.to_le_bytes();
+        let clock_info = create_test_account_info(&mut clock_data);
+        assert!(state.update_nav(1_200_000, &clock_info).is_ok());
+        assert!(matches!(state.network_status, NetworkStatus::Error(_)));
+    }
+
+    #[test]
+    fn test_ema_nav_math() {
+        for (prior, spot, alpha, expected) in [
+            (1_000_000, 2_000_000, 2_500, 1_250_000),
+            (1_250_000, 2_000_000, 2_500, 1_437_500),
+            // Rounded up while rising, down while falling
+            (1_000_000, 1_000_003, 3_333, 1_000_001),
+            (1_000_003, 1_000_000, 3_333, 1_000_002),
+            (999, 1_000, 5_000, 1_000),
+            (1_001, 1_000, 5_000, 1_000),
+            (5, 7, MAX_EMA_ALPHA_BPS, 7),
+            (u64::MAX, 1, 1, 18_444_899_399_302_180_659),
+        ] {
+            assert_eq!(ema_nav_sats(prior, spot, alpha), expected, "{} -> {} at {} bps", prior, spot, alpha);
+        }
+    }
+
+    #[test]
+    fn test_smoothed_nav_follows_spot() {
+        let mut state = OVTState::new(treasury_key());
+        let smoothing = |ema_alpha_bps, expected_nonce| OVTInstruction::SetNavSmoothing { ema_alpha_bps, expected_nonce };
+        let update = |btc_price_sats, expected_nonce| OVTInstruction::UpdateNAV { btc_price_sats, expected_nonce };
+
+        // The first NAV is taken as is
+        state.apply_instruction(&smoothing(Some(2_500), 0), 0).unwrap();
+        state.apply_instruction(&update(1_000_000, 1), 100).unwrap();
+        assert_eq!((state.nav_sats, state.spot_nav_sats), (1_000_000, 1_000_000));
+
+        state.apply_instruction(&update(2_000_000, 2), 200).unwrap();
+        assert_eq!((state.nav_sats, state.spot_nav_sats), (1_250_000, 2_000_000));
+        state.apply_instruction(&update(2_000_000, 3), 300).unwrap();
+        assert_eq!(state.nav_sats, 1_437_500);
+
+        // The allowed band is measured from the last spot: 4.75x the spot is allowed, though 6.6x the NAV
+        state.apply_instruction(&update(9_500_000, 4), 400).unwrap();
+        assert_eq!((state.nav_sats, state.spot_nav_sats), (3_453_125, 9_500_000));
+        assert!(matches!(
+            state.apply_instruction(&update(1_800_000, 5), 500),
+            Err(ProgramError::Custom(code)) if code == OVTError::InvalidNAVUpdate.code()
+        ));
+
+        // Once off, the next update moves the NAV to the spot exactly
+        state.apply_instruction(&smoothing(None, 5), 500).unwrap();
+        assert_eq!(state.nav_sats, 3_453_125);
+        for (i, spot) in [9_000_000, 9_100_007].into_iter().enumerate() {
+            state.apply_instruction(&update(spot, 6 + i as u64), 600 + 100 * i as u64).unwrap();
+            assert_eq!((state.nav_sats, state.spot_nav_sats), (spot, spot));
+        }
+        assert_eq!(state.nonce, 8);
+    }
+
+    #[test]
+    fn test_nav_smoothing_weight_bounds() {
+        let mut state = OVTState::new(treasury_key());
+        for alpha in [0, MAX_EMA_ALPHA_BPS + 1, u16::MAX] {
+            assert!(matches!(
+                state.apply_instruction(&OVTInstruction::SetNavSmoothing { ema_alpha_bps: Some(alpha), expected_nonce: 0 }, 0),
+                Err(ProgramError::Custom(code)) if code == OVTError::InvalidInstructionData.code()
+            ));
+        }
+        assert_eq!((state.ema_alpha_bps, state.nonce), (None, 0));
+
+        state.set_nav_smoothing(Some(MAX_EMA_ALPHA_BPS)).unwrap();
+        assert_eq!(state.ema_alpha_bps, Some(MAX_EMA_ALPHA_BPS));
+    }
+
+    #[test]
+    fn test_set_treasury_script() {
+        let mut state = OVTState::try_new(treasury_key()).unwrap();
+        let p2wpkh = state.treasury_script_pubkey().unwrap();
+        let set = |script_kind, expected_nonce| OVTInstruction::SetTreasuryScript { script_kind, expected_nonce };
+
+        state.apply_instruction(&set(TreasuryScriptKind::P2tr, 0), 0).unwrap();
+        let p2tr = state.treasury_script_pubkey().unwrap();
+        assert!(p2tr.is_p2tr());
+        assert_eq!(state.treasury_script_hash, script_hash(&p2tr));
+        assert!(state.is_treasury_script(&p2tr));
+        assert!(!state.is_treasury_script(&p2wpkh));
+
+        // Replays and underivable scripts leave the kind as it was
+        assert!(matches!(
+            state.apply_instruction(&set(TreasuryScriptKind::P2wpkh, 0), 0),
+            Err(ProgramError::Custom(code)) if code == OVTError::NonceMismatch.code()
+        ));
+        let bad_multisig = TreasuryScriptKind::P2wshMultisig { threshold: 4, pubkeys: [treasury_key(); 3] };
+        assert!(matches!(
+            state.apply_instruction(&set(bad_multisig, 1), 0),
+            Err(ProgramError::Custom(code)) if code == OVTError::InvalidTreasuryKey.code()
+        ));
+        assert_eq!((&state.treasury_script_kind, state.nonce), (&TreasuryScriptKind::P2tr, 1));
+    }
+
+    #[test]
+    fn test_network_validation() {
+        let mut state = OVTState::new(treasury_key());
+        // Nothing recorded, so any network is accepted
+        assert!(state.validate_network(Network::Bitcoin).is_ok());
+
+        state.network = "regtest".to_string();
+        assert!(state.validate_network(Network::Regtest).is_ok());
+        assert!(matches!(
+            state.validate_network(Network::Testnet),
+            Err(ProgramError::Custom(code)) if code == OVTError::NetworkMismatch.code()
+        ));
+    }
+
+    #[test]
+    fn test_handlers_reject_state_from_another_network() {
+        let program_id = ProgramIds::default().ovt;
+        let state_key = crate::address::state_address(&program_id);
+        let (authority_key, clock_key) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let running = crate::network_config::PROGRAM_NETWORK;
+        let other = if running == Network::Regtest { Network::Signet } else { Network::Regtest };
+
+        let mut state = OVTState::new(treasury_key());
+        state.network = network_name(other).to_string();
+        state.authority = authority_key.0;
+        let mut data = vec![0; OVTState::LEN];
+        state.pack_into_slice(&mut data);
+        let state_info = owned_account(&state_key, &program_id, data.clone(), false);
+        let authority = owned_account(&authority_key, &clock_key, Vec::new(), true);
+        let clock = owned_account(&clock_key, &clock_key, 16u64.to_le_bytes().to_vec(), false);
+
+        let update = OVTInstruction::UpdateNAV { btc_price_sats: 1_000_000, expected_nonce: 0 };
+        let accounts = [state_info.clone(), authority.clone(), clock.clone()];
+        assert!(matches!(
+            OVTProgram::process_instruction(&program_id, &accounts, &borsh::to_vec(&update).unwrap()),
+            Err(ProgramError::Custom(code)) if code == OVTError::NetworkMismatch.code()
+        ));
+        assert_eq!(*state_info.data.borrow(), data);
+
+        state.network = network_name(running).to_string();
+        state.pack_into_slice(&mut state_info.data.borrow_mut());
+        OVTProgram::process_instruction(&program_id, &accounts, &borsh::to_vec(&update).unwrap()).unwrap();
+    }
+
+    /// Signing isn't enough: only the key Initialize recorded may report sync
+    /// status, and only for the next nonce
+    #[test]
+    fn test_sync_status_is_checked_against_the_authority() {
+        let program_id = ProgramIds::default().ovt;
+        let state_key = crate::address::state_address(&program_id);
+        let (authority_key, impostor_key, system_key) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let mut state = OVTState::new(treasury_key());
+        state.authority = authority_key.0;
+        let mut data = vec![0; OVTState::LEN];
+        state.pack_into_slice(&mut data);
+        let state_info = owned_account(&state_key, &program_id, data.clone(), false);
+        let authority = owned_account(&authority_key, &system_key, Vec::new(), true);
+        let impostor = owned_account(&impostor_key, &system_key, Vec::new(), true);
+        let sync = |signer: &AccountInfo, height: u64, expected_nonce: u64| {
+            let instruction = OVTInstruction::UpdateSyncStatus { height, status: NetworkStatus::Active, expected_nonce };
+            OVTProgram::process_instruction(&program_id, &[state_info.clone(), signer.clone()], &borsh::to_vec(&instruction).unwrap())
+        };
+        let fails_with = |result: Result<(), ProgramError>, error: OVTError| {
+            matches!(result, Err(ProgramError::Custom(code)) if code == error.code())
+        };
+
+        assert!(fails_with(sync(&impostor, 100, 0), OVTError::InvalidAuthority));
+        assert_eq!(*state_info.data.borrow(), data);
+
+        sync(&authority, 100, 0).unwrap();
+        assert!(fails_with(sync(&authority, 101, 0), OVTError::NonceMismatch));
+        assert!(fails_with(sync(&authority, 99, 1), OVTError::InvalidBlockHeight));
+        let state = OVTState::unpack_from_slice(&state_info.data.borrow()).unwrap();
+        assert_eq!((state.last_sync_height, state.nonce), (100, 1));
+    }
+
+    #[test]
+    fn test_nonce_rejects_replays() {
+        let mut state = OVTState::new(treasury_key());
+        state.nav_sats = 1_000_000;
+        state.total_supply = 1_000_000;
+        let update = OVTInstruction::UpdateNAV { btc_price_sats: 1_100_000, expected_nonce: 0 };
+        let burn = OVTInstruction::BuybackBurn {
+            payment_txid: "ab".repeat(32),
+            payment_amount_sats: 100_000,
+            payment_height: 0,
+            memo: None,
+            expected_nonce: 1,
+        };
+        let is_mismatch = |result: Result<Transition, ProgramError>| {
+            matches!(result, Err(ProgramError::Custom(code)) if code == OVTError::NonceMismatch.code())
+        };
+
+        // Out of order
+        assert!(is_mismatch(state.apply_instruction(&burn, 0)));
+        state.apply_instruction(&update, 16).unwrap();
+        assert_eq!(state.nonce, 1);
+
+        // The same update again, even once enough time has passed
+        assert!(is_mismatch(state.apply_instruction(&update, 32)));
+        assert_eq!((state.nav_sats, state.last_nav_update), (1_100_000, 16));
+
+        state.apply_instruction(&burn, 0).unwrap();
+        assert!(is_mismatch(state.apply_instruction(&burn, 0)));
+        assert_eq!((state.nonce, state.total_supply), (2, 909_091));
+
+        // A failing instruction doesn't use up the nonce
+        let empty_payment = OVTInstruction::BuybackBurn {
+            payment_txid: "ab".repeat(32),
+            payment_amount_sats: 0,
+            payment_height: 0,
+            memo: None,
+            expected_nonce: 2,
+        };
+        assert!(state.apply_instruction(&empty_payment, 0).is_err());
+        assert_eq!(state.nonce, 2);
+
+        // Layouts from before the nonce can't be checked, so are refused outright
+        let unsupported = OVTError::UnsupportedInstructionVersion.code();
+        for legacy in [
+            OVTInstruction::LegacyUpdateNAV { btc_price_sats: 1_200_000 },
+            OVTInstruction::LegacyBuybackBurn { payment_txid: "cd".repeat(32), payment_amount_sats: 100_000 },
+        ] {
+            assert!(matches!(state.apply_instruction(&legacy, 48), Err(ProgramError::Custom(code)) if code == unsupported));
+        }
+        assert_eq!((state.nonce, state.nav_sats, state.total_supply), (2, 1_100_000, 909_091));
+    }
+
+    #[test]
+    fn test_try_new_rejects_invalid_treasury_keys() {
+        fn is_invalid_key<T>(result: Result<T, ProgramError>) -> bool {
+            matches!(result, Err(ProgramError::Custom(code)) if code == OVTError::InvalidTreasuryKey.code())
+        }
+        let mut bad_prefix = treasury_key();
+        bad_prefix[0] = 0x05;
+        // x = 5 has no y with y^2 = x^3 + 7
+        let mut off_curve = [0; 33];
+        off_curve[0] = 0x02;
+        off_curve[32] = 5;
+
+        assert!(is_invalid_key(OVTState::try_new([0; 33])));
+        assert!(is_invalid_key(OVTState::try_new(bad_prefix)));
+        assert!(is_invalid_key(OVTState::try_new(off_curve)));
+        assert!(is_invalid_key(OVTState::new(off_curve).validate_treasury()));
+
+        let state = OVTState::try_new(treasury_key()).unwrap();
+        assert!(state.validate_treasury().is_ok());
+        assert_eq!(state, OVTState::new(treasury_key()));
+    }
+
+    #[test]
+    fn test_stored_treasury_script_hash() {
+        let state = OVTState::try_new(treasury_key()).unwrap();
+        let script = state.treasury_script_pubkey().unwrap();
+        assert_eq!(
+            hex::encode(state.treasury_script_hash),
+            "8838f796bf4970b148779c05b74b8c49515b322d04035f7faa5d9b2375df2396"
+        );
+        assert!(state.is_treasury_script(&script));
+        assert!(!state.is_treasury_script(&ScriptBuf::new()));
+
+        // States from before the hash was stored derive the script instead
+        let legacy = OVTState { treasury_script_hash: [0; 32], ..state.clone() };
+        assert!(legacy.is_treasury_script(&script));
+        assert!(!legacy.is_treasury_script(&ScriptBuf::new()));
+        assert!(!OVTState::new([0; 33]).is_treasury_script(&script));
+    }
+}
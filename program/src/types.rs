@@ -1,2 +0,0 @@
-// Re-export types from the bitcoin module
-pub use crate::bitcoin::utxo::{UtxoMeta, UtxoStatus, TreasuryPayment}; 
\ No newline at end of file
@@ -0,0 +1,140 @@
+/// Treasury payment to burn, end to end: detection on the mock node,
+/// `verify_treasury_payment`, then BuybackBurn through the mock SDK
+mod harness;
+
+use bitcoin::Amount;
+use harness::mock_sdk::ProgramError;
+use harness::BuybackHarness;
+use program::bitcoin::utxo::TreasuryScriptKind;
+use program::bitcoin::{PaymentIntent, PaymentMemo};
+use program::error::OVTError;
+use program::state::buyback_burn_event;
+
+const NAV_SATS: u64 = 100_000_000;
+const TOTAL_SUPPLY: u64 = 1_000_000;
+
+const MEMO: PaymentMemo = PaymentMemo {
+    payer_id: [0x42; 16],
+    intent: PaymentIntent::Buyback,
+    nonce: 1,
+};
+
+#[tokio::test]
+async fn test_payment_is_detected_verified_and_burned() -> Result<(), Box<dyn std::error::Error>> {
+    let harness = BuybackHarness::new(NAV_SATS, TOTAL_SUPPLY)?;
+    let txid = harness.seed_payment(Amount::from_sat(10_000_000), Some(MEMO), 3);
+
+    let mut payments = harness.detect_payments().await?;
+    assert_eq!(payments.len(), 1);
+    let payment = &mut payments[0];
+    assert_eq!(payment.txid, txid.to_string());
+
+    let verified = harness.verify(payment).await?;
+    assert_eq!(verified.memo, Some(MEMO));
+    assert_eq!(payment.utxo.confirmations, 3);
+
+    harness.buyback(payment, &verified)?;
+    // 10_000_000 sats at a NAV of 100_000_000 buys back a tenth of the supply
+    assert_eq!(harness.state().total_supply, 900_000);
+    assert_eq!(
+        harness.client.logs(),
+        vec![buyback_burn_event(&txid.to_string(), 10_000_000, Some(&MEMO))]
+    );
+    let history = harness.burn_history();
+    assert_eq!(history.len(), 1);
+    assert_eq!(history[0].payment_txid_hex(), txid.to_string());
+    assert_eq!((history[0].tokens_burned, history[0].nav_sats, history[0].supply_after), (100_000, NAV_SATS, 900_000));
+
+    // The same payment can't buy back twice
+    let duplicate = OVTError::DuplicatePayment.code();
+    assert!(matches!(harness.buyback(payment, &verified), Err(ProgramError::Custom(code)) if code == duplicate));
+    assert_eq!(harness.state().total_supply, 900_000);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_unconfirmed_payment_is_not_burned() -> Result<(), Box<dyn std::error::Error>> {
+    let harness = BuybackHarness::new(NAV_SATS, TOTAL_SUPPLY)?;
+    harness.seed_payment(Amount::from_sat(10_000_000), None, 0);
+
+    let mut payments = harness.detect_payments().await?;
+    let result = harness.verify(&mut payments[0]).await;
+    let unexpected_status = OVTError::UnexpectedUtxoStatus.code();
+    assert!(matches!(result, Err(ProgramError::Custom(code)) if code == unexpected_status));
+
+    // Once mined it goes through
+    harness.node.mine_blocks(1);
+    let verified = harness.verify(&mut payments[0]).await?;
+    assert_eq!(verified.memo, None);
+    harness.buyback(&payments[0], &verified)?;
+    assert_eq!(harness.state().total_supply, 900_000);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_recent_payment_waits_for_maturity() -> Result<(), Box<dyn std::error::Error>> {
+    let harness = BuybackHarness::new(NAV_SATS, TOTAL_SUPPLY)?;
+    harness.set_payment_maturity(3)?;
+    // Mined in the tip block, height 10
+    harness.seed_payment(Amount::from_sat(10_000_000), None, 1);
+    harness.sync()?;
+
+    let mut payments = harness.detect_payments().await?;
+    let verified = harness.verify(&mut payments[0]).await?;
+    assert_eq!(payments[0].block_height, Some(10));
+    let immature = OVTError::PaymentImmature.code();
+    assert!(matches!(harness.buyback(&payments[0], &verified), Err(ProgramError::Custom(code)) if code == immature));
+
+    // Two blocks later it is still one short, whatever the node says about confirmations
+    harness.node.mine_blocks(2);
+    harness.sync()?;
+    assert!(matches!(harness.buyback(&payments[0], &verified), Err(ProgramError::Custom(code)) if code == immature));
+
+    // Mined, but not yet reported: the synced height is what counts
+    harness.node.mine_blocks(1);
+    assert!(matches!(harness.buyback(&payments[0], &verified), Err(ProgramError::Custom(code)) if code == immature));
+    harness.sync()?;
+    harness.buyback(&payments[0], &verified)?;
+    assert_eq!(harness.state().total_supply, 900_000);
+
+    // A payment whose height was never looked up doesn't get through either
+    let txid = harness.seed_payment(Amount::from_sat(20_000_000), None, 6);
+    let unverified = harness.detect_payments().await?.into_iter().find(|payment| payment.txid == txid.to_string()).unwrap();
+    assert!(matches!(harness.buyback(&unverified, &verified), Err(ProgramError::Custom(code)) if code == immature));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_payment_to_taproot_treasury_is_burned() -> Result<(), Box<dyn std::error::Error>> {
+    let harness = BuybackHarness::new(NAV_SATS, TOTAL_SUPPLY)?;
+    let p2wpkh = harness.treasury_script();
+    harness.set_treasury_script(TreasuryScriptKind::P2tr)?;
+    assert!(harness.treasury_script().is_p2tr());
+
+    let txid = harness.seed_payment(Amount::from_sat(10_000_000), None, 3);
+    let mut payments = harness.detect_payments().await?;
+    assert_eq!(payments.len(), 1);
+    assert_eq!(payments[0].txid, txid.to_string());
+    let verified = harness.verify(&mut payments[0]).await?;
+    harness.buyback(&payments[0], &verified)?;
+    assert_eq!(harness.state().total_supply, 900_000);
+
+    // The old P2WPKH script no longer counts as the treasury
+    let old = harness.node.add_payment_transaction(3, vec![bitcoin::TxOut { value: Amount::from_sat(10_000_000), script_pubkey: p2wpkh }]);
+    assert!(harness.detect_payments().await?.iter().all(|payment| payment.txid != old.to_string()));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_payment_elsewhere_is_not_detected() -> Result<(), Box<dyn std::error::Error>> {
+    let harness = BuybackHarness::new(NAV_SATS, TOTAL_SUPPLY)?;
+    harness.node.add_payment_transaction(
+        3,
+        vec![bitcoin::TxOut { value: Amount::from_sat(10_000_000), script_pubkey: bitcoin::ScriptBuf::new() }],
+    );
+
+    assert!(harness.detect_payments().await?.is_empty());
+    assert!(harness.client.logs().is_empty());
+    assert_eq!(harness.state().total_supply, TOTAL_SUPPLY);
+    Ok(())
+}
@@ -0,0 +1,80 @@
+/// Environment layering of `BitcoinRpcClientBuilder` and `UtxoCacheBuilder`
+///
+/// Kept in its own test binary, with a single test, since it changes the
+/// process environment the builders read.
+use std::env;
+use std::time::Duration;
+
+use program::bitcoin::cache::UtxoCacheBuilder;
+use program::bitcoin::config::{
+    BITCOIN_RPC_HOST_ENV, BITCOIN_RPC_PORT_ENV, BITCOIN_RPC_TIMEOUT_ENV, BITCOIN_RPC_URL_ENV, MIN_CONFIRMATIONS_ENV,
+    UTXO_CACHE_INVALID_TTL_ENV, UTXO_CACHE_MAX_SIZE_ENV, UTXO_CACHE_REFRESH_ENV,
+};
+use program::bitcoin::{BitcoinRpcClientBuilder, ConfigError};
+use program::network_config::NETWORK_ENV_VAR;
+
+const VARS: [&str; 9] = [
+    NETWORK_ENV_VAR,
+    BITCOIN_RPC_URL_ENV,
+    BITCOIN_RPC_HOST_ENV,
+    BITCOIN_RPC_PORT_ENV,
+    BITCOIN_RPC_TIMEOUT_ENV,
+    MIN_CONFIRMATIONS_ENV,
+    UTXO_CACHE_MAX_SIZE_ENV,
+    UTXO_CACHE_REFRESH_ENV,
+    UTXO_CACHE_INVALID_TTL_ENV,
+];
+
+#[test]
+fn test_env_layers_between_defaults_and_setters() {
+    VARS.iter().for_each(env::remove_var);
+
+    // Unset: the testnet preset and the cache defaults
+    let config = BitcoinRpcClientBuilder::from_env().unwrap().build_config().unwrap();
+    assert_eq!((config.endpoint.as_str(), config.port, config.min_confirmations), ("127.0.0.1", 18332, 3));
+    assert_eq!(UtxoCacheBuilder::from_env().unwrap().build_config().unwrap().max_size, 1000);
+
+    // The network picks the preset, and the other variables override it
+    env::set_var(NETWORK_ENV_VAR, "regtest");
+    env::set_var(BITCOIN_RPC_HOST_ENV, "bitcoind");
+    env::set_var(BITCOIN_RPC_TIMEOUT_ENV, "45");
+    env::set_var(MIN_CONFIRMATIONS_ENV, " 2 ");
+    env::set_var(UTXO_CACHE_MAX_SIZE_ENV, "50");
+    env::set_var(UTXO_CACHE_REFRESH_ENV, "120");
+    let config = BitcoinRpcClientBuilder::from_env().unwrap().build_config().unwrap();
+    assert_eq!((config.endpoint.as_str(), config.port), ("bitcoind", 18443));
+    assert_eq!((config.request_timeout, config.min_confirmations), (Duration::from_secs(45), 2));
+    let cache = UtxoCacheBuilder::from_env().unwrap().build_config().unwrap();
+    assert_eq!((cache.max_size, cache.refresh_interval), (50, Duration::from_secs(120)));
+
+    // Setters called after `from_env` win
+    let config = BitcoinRpcClientBuilder::from_env()
+        .unwrap()
+        .with_endpoint("10.0.0.5", 8332)
+        .with_min_confirmations(6)
+        .build_config()
+        .unwrap();
+    assert_eq!((config.endpoint.as_str(), config.port, config.min_confirmations), ("10.0.0.5", 8332, 6));
+    let cache = UtxoCacheBuilder::from_env().unwrap().with_max_size(7).build_config().unwrap();
+    assert_eq!((cache.max_size, cache.refresh_interval), (7, Duration::from_secs(120)));
+
+    // Values from the environment are validated like any other
+    env::set_var(UTXO_CACHE_INVALID_TTL_ENV, "60");
+    assert!(matches!(
+        UtxoCacheBuilder::from_env().unwrap().build_config(),
+        Err(ConfigError::RefreshIntervalExceedsInvalidTtl { .. })
+    ));
+    env::set_var(BITCOIN_RPC_URL_ENV, "ftp://bitcoind");
+    assert!(matches!(BitcoinRpcClientBuilder::from_env().unwrap().build_config(), Err(ConfigError::InvalidUrl(_))));
+
+    // and ones that don't parse are rejected by name
+    env::set_var(BITCOIN_RPC_PORT_ENV, "eighteen thousand");
+    assert!(matches!(
+        BitcoinRpcClientBuilder::from_env(),
+        Err(ConfigError::InvalidEnvVar { var, .. }) if var == BITCOIN_RPC_PORT_ENV
+    ));
+    env::set_var(NETWORK_ENV_VAR, "testnet3");
+    assert!(matches!(BitcoinRpcClientBuilder::from_env(), Err(ConfigError::Network(_))));
+
+    VARS.iter().for_each(env::remove_var);
+}
@@ -0,0 +1,103 @@
+/// Fault policies on the mock node, driven through the runes client's retry logic
+use bitcoin::{Amount, ScriptBuf, TxOut};
+use program::bitcoin::mock::{FaultPolicy, MockBitcoinNode, MockBitcoinRpcClient, MockMethod};
+use program::bitcoin::{BitcoinRpcConfig, BitcoinRpcError, UtxoMeta, UtxoStatus};
+use program::runes_client::{RunesClient, RunesError};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::Instant;
+
+const TXID: &str = "a000000000000000000000000000000000000000000000000000000000000000";
+
+fn setup() -> (Arc<MockBitcoinNode>, MockBitcoinRpcClient, RunesClient) {
+    let node = Arc::new(MockBitcoinNode::new());
+    node.add_transaction(TXID, 1, vec![TxOut { value: Amount::from_sat(50_000), script_pubkey: ScriptBuf::new() }], true);
+    let rpc = MockBitcoinRpcClient::new(BitcoinRpcConfig::default(), node.clone());
+    let runes = RunesClient::new(bitcoin::Network::Regtest, "http://127.0.0.1:9".to_string(), None);
+    (node, rpc, runes)
+}
+
+fn injected() -> BitcoinRpcError {
+    BitcoinRpcError::ConnectionFailed("injected".to_string())
+}
+
+async fn fetch_with_retry(runes: &RunesClient, rpc: &MockBitcoinRpcClient) -> Result<u32, RunesError> {
+    runes.with_retry(|| async move {
+        rpc.get_transaction(TXID)
+            .await
+            .map(|tx| tx.output.len() as u32)
+            .map_err(|e| RunesError::BitcoinRPC(e.to_string()))
+    }).await
+}
+
+#[tokio::test]
+async fn test_retry_outlasts_scripted_failures() {
+    tokio::time::pause();
+    let (node, rpc, runes) = setup();
+    node.fail_next(MockMethod::GetTransaction, 2, injected());
+
+    let started = Instant::now();
+    assert_eq!(fetch_with_retry(&runes, &rpc).await.unwrap(), 1);
+    // Two failed attempts, backing off 500ms then 1s
+    assert_eq!(started.elapsed(), Duration::from_millis(1_500));
+}
+
+#[tokio::test]
+async fn test_exhausted_retries_surface_the_injected_error() {
+    tokio::time::pause();
+    let (node, rpc, runes) = setup();
+    node.fail_next(MockMethod::GetTransaction, 3, injected());
+
+    match fetch_with_retry(&runes, &rpc).await {
+        Err(RunesError::BitcoinRPC(message)) => assert!(message.contains("injected"), "{}", message),
+        other => panic!("expected the injected error, got {:?}", other),
+    }
+
+    // The node would answer now, but the breaker opened on the third failure
+    assert!(matches!(fetch_with_retry(&runes, &rpc).await, Err(RunesError::CircuitOpen)));
+    assert!(rpc.get_transaction(TXID).await.is_ok());
+}
+
+#[tokio::test]
+async fn test_failure_rate_with_retries() {
+    tokio::time::pause();
+    let (node, rpc, runes) = setup();
+    node.set_fault_policy(
+        FaultPolicy::new()
+            .with_seed(11)
+            .with_failure_rate(MockMethod::GetTransaction, 0.5, injected()),
+    );
+
+    // Half the attempts fail, but with this seed never three in a row, so
+    // every call gets through after at most two backoffs
+    let started = Instant::now();
+    for _ in 0..20 {
+        assert_eq!(fetch_with_retry(&runes, &rpc).await.unwrap(), 1);
+    }
+    assert_eq!(started.elapsed(), Duration::from_millis(6_500));
+
+    // Other methods are unaffected
+    let utxo = UtxoMeta::new(TXID.to_string(), 0, 50_000);
+    for _ in 0..10 {
+        assert_eq!(rpc.get_utxo_status(&utxo).await.unwrap(), UtxoStatus::Active);
+    }
+}
+
+#[tokio::test]
+async fn test_latency_trips_caller_timeouts() {
+    tokio::time::pause();
+    let (node, rpc, _) = setup();
+    node.set_latency(MockMethod::GetUtxoStatus, Duration::from_millis(500));
+    let utxo = UtxoMeta::new(TXID.to_string(), 0, 50_000);
+
+    let late = tokio::time::timeout(Duration::from_millis(100), rpc.get_utxo_status(&utxo)).await;
+    assert!(late.is_err());
+
+    let started = Instant::now();
+    assert_eq!(rpc.get_utxo_status(&utxo).await.unwrap(), UtxoStatus::Active);
+    assert_eq!(started.elapsed(), Duration::from_millis(500));
+    // Only the delayed method slows down
+    let started = Instant::now();
+    rpc.get_confirmations(TXID).await.unwrap();
+    assert_eq!(started.elapsed(), Duration::ZERO);
+}
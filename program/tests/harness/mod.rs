@@ -0,0 +1,160 @@
+// Treasury buyback flow against the mock Bitcoin node and the mock SDK:
+// a payment is detected on chain, verified, then burned through BuybackBurn.
+// Shared by tests/buyback_flow.rs and examples/buyback_flow.rs.
+
+#[path = "../mock_sdk/mock_sdk.rs"]
+pub mod mock_sdk;
+
+use std::sync::Arc;
+
+use bitcoin::script::PushBytesBuf;
+use bitcoin::{Amount, PublicKey, ScriptBuf, TxOut, Txid};
+use mock_sdk::{
+    program_types::{mock_error, OVT_STATE_SEED},
+    test_utils::TestClient,
+    ProgramError,
+    Pubkey,
+};
+use program::bitcoin::mock::{MockBitcoinNode, MockBitcoinRpcClient};
+use program::bitcoin::rpc::BitcoinRpcConfig;
+use program::bitcoin::utxo::{verify_treasury_payment, TreasuryPayment, TreasuryScriptKind, ValidationPolicy, VerifiedTreasuryPayment};
+use program::bitcoin::{PaymentMemo, TrackerRpc};
+use program::burn_history::{decode_burn_history, BurnRecord};
+use program::program_ids::ProgramIds;
+use program::state::NetworkStatus;
+use program::{OVTInstruction, OVTState};
+
+/// Treasury key of the harness program (the secp256k1 generator)
+pub const TREASURY_PUBKEY: &str = "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798";
+
+/// Blocks mined before any payment is seeded
+pub const INITIAL_BLOCKS: u32 = 10;
+
+pub struct BuybackHarness {
+    pub node: Arc<MockBitcoinNode>,
+    pub rpc: MockBitcoinRpcClient,
+    pub client: TestClient,
+    pub program_id: Pubkey,
+    pub admin: Pubkey,
+    pub state_account: Pubkey,
+    pub burn_history_account: Pubkey,
+    pub treasury_pubkey: PublicKey,
+    pub policy: ValidationPolicy,
+}
+
+impl BuybackHarness {
+    /// A program at the builders' program id holding `nav_sats` and
+    /// `total_supply`, next to a regtest chain `INITIAL_BLOCKS` deep
+    pub fn new(nav_sats: u64, total_supply: u64) -> Result<Self, ProgramError> {
+        let program_id = Pubkey(ProgramIds::current().map_err(mock_error)?.ovt.0);
+        let treasury_pubkey = PublicKey::from_slice(&hex::decode(TREASURY_PUBKEY).unwrap()).unwrap();
+
+        let mut client = TestClient::new();
+        let admin = client.create_admin_account(program_id)?;
+        let state_account = client.create_derived_account(&[OVT_STATE_SEED], program_id)?;
+        let mut state = OVTState::new(treasury_pubkey.inner.serialize());
+        state.nav_sats = nav_sats;
+        state.total_supply = total_supply;
+        state.authority = admin.key.0;
+        state_account.set_data(&state).map_err(|_| ProgramError::AccountDataTooSmall)?;
+        let burn_history_account = client.create_burn_history_account(program_id)?;
+        client.create_system_program_account()?;
+
+        let node = Arc::new(MockBitcoinNode::new());
+        node.mine_blocks(INITIAL_BLOCKS);
+        let rpc = MockBitcoinRpcClient::new(BitcoinRpcConfig::regtest(), node.clone());
+
+        Ok(Self {
+            node,
+            rpc,
+            client,
+            program_id,
+            admin: admin.key,
+            state_account: state_account.key,
+            burn_history_account: burn_history_account.key,
+            treasury_pubkey,
+            policy: ValidationPolicy::for_network(bitcoin::Network::Regtest),
+        })
+    }
+
+    pub fn treasury_script(&self) -> ScriptBuf {
+        self.state().treasury_script_pubkey().unwrap()
+    }
+
+    /// Put a transaction paying `amount` to the treasury on the mock chain,
+    /// `confirmations` deep, with `memo` in an OP_RETURN output after the payment
+    pub fn seed_payment(&self, amount: Amount, memo: Option<PaymentMemo>, confirmations: u32) -> Txid {
+        let mut outputs = vec![TxOut { value: amount, script_pubkey: self.treasury_script() }];
+        if let Some(memo) = memo {
+            let payload = PushBytesBuf::try_from(memo.to_bytes().to_vec()).unwrap();
+            outputs.push(TxOut { value: Amount::ZERO, script_pubkey: ScriptBuf::new_op_return(payload) });
+        }
+        self.node.add_payment_transaction(confirmations, outputs)
+    }
+
+    /// Unspent outputs paying the treasury, as payments to verify
+    pub async fn detect_payments(&self) -> Result<Vec<TreasuryPayment>, ProgramError> {
+        let treasury_script = hex::encode(self.treasury_script().as_bytes());
+        let utxos = self.rpc.list_unspent(0, &[]).await.map_err(|e| mock_error(e.into()))?;
+        let mut payments: Vec<TreasuryPayment> = utxos
+            .into_iter()
+            .filter(|utxo| utxo.script_pubkey == treasury_script)
+            .map(|utxo| TreasuryPayment::new(utxo.txid.clone(), utxo.amount(), utxo))
+            .collect::<Result<_, _>>()
+            .map_err(mock_error)?;
+        payments.sort_by(|a, b| a.utxo.cmp(&b.utxo));
+        Ok(payments)
+    }
+
+    pub async fn verify(&self, payment: &mut TreasuryPayment) -> Result<VerifiedTreasuryPayment, ProgramError> {
+        let script_kind = self.state().treasury_script_kind;
+        verify_treasury_payment(&self.rpc, payment, &self.treasury_pubkey, &script_kind, &self.policy)
+            .await
+            .map_err(mock_error)
+    }
+
+    /// Burn for a verified payment, with the instruction from `OVTInstruction::buyback_burn`
+    /// at the state's current nonce
+    pub fn buyback(&self, payment: &TreasuryPayment, verified: &VerifiedTreasuryPayment) -> Result<(), ProgramError> {
+        let nonce = self.state().nonce;
+        let height = payment.block_height.map_or(0, u64::from);
+        self.client.spent_payment_account(self.program_id, &payment.txid)?;
+        let instruction =
+            OVTInstruction::buyback_burn(&self.arch_program_id(), payment.txid.clone(), payment.amount_sats, height, verified.memo, nonce);
+        self.client.process_built_instruction(&instruction, self.admin)
+    }
+
+    /// Require payments to be `blocks` deep before they are burned for
+    pub fn set_payment_maturity(&self, blocks: u32) -> Result<(), ProgramError> {
+        let instruction = OVTInstruction::set_payment_maturity(&self.arch_program_id(), blocks, self.state().nonce);
+        self.client.process_built_instruction(&instruction, self.admin)
+    }
+
+    /// Have treasury payments pay to the script `script_kind` derives
+    pub fn set_treasury_script(&self, script_kind: TreasuryScriptKind) -> Result<(), ProgramError> {
+        let instruction = OVTInstruction::set_treasury_script(&self.arch_program_id(), script_kind, self.state().nonce);
+        self.client.process_built_instruction(&instruction, self.admin)
+    }
+
+    /// Report the mock chain's tip as the synced height
+    pub fn sync(&self) -> Result<(), ProgramError> {
+        let height = u64::from(self.node.get_block_height());
+        let instruction =
+            OVTInstruction::update_sync_status(&self.arch_program_id(), height, NetworkStatus::Active, self.state().nonce);
+        self.client.process_built_instruction(&instruction, self.admin)
+    }
+
+    fn arch_program_id(&self) -> ::arch_program::pubkey::Pubkey {
+        ::arch_program::pubkey::Pubkey(self.program_id.0)
+    }
+
+    /// Burns recorded so far, oldest first
+    pub fn burn_history(&self) -> Vec<BurnRecord> {
+        let accounts = self.client.accounts.lock().unwrap();
+        decode_burn_history(&accounts[&self.burn_history_account].data.borrow()).unwrap()
+    }
+
+    pub fn state(&self) -> OVTState {
+        self.client.get_account_data(&self.state_account).unwrap()
+    }
+}
@@ -0,0 +1,69 @@
+//! Golden-file tests for the JSON served to the frontend
+#![cfg(feature = "serde")]
+
+use program::bitcoin::{UtxoMeta, UtxoStatus};
+use program::state::{NetworkStatus, OVTState};
+use serde_json::{json, Value};
+
+const TXID: &str = "1234567890abcdef1234567890abcdef1234567890abcdef1234567890abcdef";
+const TREASURY_PUBKEY: &str = "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798";
+
+fn golden(json: &str) -> Value {
+    serde_json::from_str(json).expect("golden file is valid JSON")
+}
+
+#[test]
+fn test_confirmed_utxo_json() {
+    let mut utxo = UtxoMeta::new(TXID.to_string(), 1, 250_000);
+    utxo.script_pubkey = "0014751e76e8199196d454941c45d1b3a323f1433bd6".to_string();
+    utxo.confirmations = 6;
+    utxo.update_block_info(
+        868_000,
+        "00000000000000000001b4a6e5c8d7f9a1b2c3d4e5f60718293a4b5c6d7e8f90".to_string(),
+    );
+
+    let encoded: Value = serde_json::from_str(&utxo.to_json()).unwrap();
+    assert_eq!(encoded, golden(include_str!("fixtures/utxo_meta_confirmed.json")));
+    assert_eq!(serde_json::from_value::<UtxoMeta>(encoded).unwrap(), utxo);
+}
+
+#[test]
+fn test_unconfirmed_utxo_omits_block_info() {
+    let utxo = UtxoMeta::new(TXID.to_string(), 0, 1_000);
+
+    let encoded: Value = serde_json::from_str(&utxo.to_json()).unwrap();
+    assert_eq!(encoded, golden(include_str!("fixtures/utxo_meta_unconfirmed.json")));
+    assert_eq!(serde_json::from_value::<UtxoMeta>(encoded).unwrap(), utxo);
+}
+
+#[test]
+fn test_status_strings() {
+    assert_eq!(serde_json::to_value(UtxoStatus::Active).unwrap(), json!("active"));
+    assert_eq!(serde_json::to_value(UtxoStatus::Pending).unwrap(), json!("pending"));
+    assert_eq!(serde_json::to_value(UtxoStatus::Spent).unwrap(), json!("spent"));
+    assert_eq!(serde_json::to_value(UtxoStatus::Invalid).unwrap(), json!("invalid"));
+
+    assert_eq!(serde_json::to_value(NetworkStatus::Syncing).unwrap(), json!("syncing"));
+    assert_eq!(
+        serde_json::to_value(NetworkStatus::Error("rpc down".to_string())).unwrap(),
+        json!({ "error": "rpc down" })
+    );
+}
+
+#[test]
+fn test_ovt_state_json() {
+    let mut state = OVTState::new(hex::decode(TREASURY_PUBKEY).unwrap().try_into().unwrap());
+    state.nav_sats = 150_000_000;
+    state.total_supply = 21_000_000;
+    state.last_nav_update = 1_735_689_600;
+    state.network_status = NetworkStatus::Active;
+    state.last_sync_height = 868_000;
+
+    let encoded: Value = serde_json::from_str(&state.to_json()).unwrap();
+    assert_eq!(encoded, golden(include_str!("fixtures/ovt_state.json")));
+
+    let decoded: OVTState = serde_json::from_value(encoded).unwrap();
+    assert_eq!(decoded.treasury_pubkey_bytes, state.treasury_pubkey_bytes);
+    assert_eq!(decoded.treasury_script_hash, state.treasury_script_hash);
+    assert_eq!(decoded.network_status, NetworkStatus::Active);
+}
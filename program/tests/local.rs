@@ -10,17 +10,35 @@ use program;
 #[path = "mock_sdk/mock_sdk.rs"]
 mod mock_sdk;
 use mock_sdk::{
+    program_types::{mock_error, BURN_HISTORY_SEED, OVT_STATE_SEED, TREASURY_SEED},
     AccountInfo,
     Pubkey,
+    ProgramError,
     test_utils::TestClient,
     AccountMeta,
 };
 use program::{OVTInstruction, OVTState};
+use program::error::OVTError;
+use program::program_ids::ProgramIds;
+use program::burn_history::{burn_record_event, decode_burn_history};
 use program::state::NetworkStatus;
+use program::bitcoin::utxo::TreasuryScriptKind;
 use std::cell::RefCell;
 use borsh::BorshSerialize;
 use std::sync::Arc;
 
+// The secp256k1 generator, a valid compressed key
+const TREASURY_KEY: &str = "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798";
+
+fn treasury_key() -> [u8; 33] {
+    hex::decode(TREASURY_KEY).unwrap().try_into().unwrap()
+}
+
+/// The program id the deployed entrypoint accepts
+fn ovt_program_id() -> Pubkey {
+    Pubkey(ProgramIds::default().ovt.0)
+}
+
 /// Test program initialization with proper UTXO handling
 /// 
 /// Verifies:
@@ -41,7 +59,7 @@ fn test_initialize() -> Result<(), Box<dyn std::error::Error>> {
     }
     
     // Create state account with enough space for OVTState
-    let state_account = client.create_account(program_id)?;
+    let state_account = client.create_derived_account(&[OVT_STATE_SEED], program_id)?;
     
     // Verify default UTXO state
     let default_utxo = client.get_account_utxo(&state_account.key)?;
@@ -70,6 +88,15 @@ fn test_initialize() -> Result<(), Box<dyn std::error::Error>> {
             last_nav_update: 0,
             network_status: NetworkStatus::Syncing,
             last_sync_height: 0,
+            required_confirmations: 6,
+            treasury_script_kind: TreasuryScriptKind::P2wpkh,
+            network: String::new(),
+            nonce: 0,
+            treasury_script_hash: [0; 32],
+            spot_nav_sats: 0,
+            ema_alpha_bps: None,
+            payment_maturity_blocks: 0,
+            authority: [0; 32],
         };
         let serialized = borsh::to_vec(&initial_state)?;
         account.data = Arc::new(RefCell::new(serialized));
@@ -109,17 +136,19 @@ fn test_initialize() -> Result<(), Box<dyn std::error::Error>> {
     let signatures: Vec<String> = (0..3).map(|i| format!("sig_{}", i)).collect();
     assert!(client.verify_action(&action_type, &signatures)?);
 
-    // Initialize program with multi-sig approval
+    // Initialize program with multi-sig approval, which creates the burn history
+    let burn_history = client.create_derived_account(&[BURN_HISTORY_SEED], program_id)?;
     let instruction = OVTInstruction::Initialize {
-        treasury_pubkey_bytes: [0u8; 33],
+        treasury_pubkey_bytes: treasury_key(),
     };
 
     client.process_transaction(
         program_id,
         vec![
-            AccountMeta::new(state_account.key, true),
+            AccountMeta::new(state_account.key, false),
             AccountMeta::new_readonly(admin_accounts[0].key, true),
             AccountMeta::new_readonly(system_program, false),
+            AccountMeta::new(burn_history.key, false),
         ],
         borsh::to_vec(&instruction)?,
     )?;
@@ -128,7 +157,10 @@ fn test_initialize() -> Result<(), Box<dyn std::error::Error>> {
     let state: OVTState = client.get_account_data(&state_account.key)?;
     assert_eq!(state.nav_sats, 0);
     assert_eq!(state.total_supply, 0);
+    assert_eq!(state.authority, admin_accounts[0].key.0);
     assert_eq!(state.last_nav_update, 0);
+    assert_eq!(burn_history.data.borrow().len(), program::burn_history::BurnHistory::LEN);
+    assert!(decode_burn_history(&burn_history.data.borrow()).map_err(mock_error)?.is_empty());
 
     // Verify admin status
     for admin in &admin_accounts {
@@ -158,7 +190,7 @@ fn test_nav_update() -> Result<(), Box<dyn std::error::Error>> {
     }
     
     // Create state account and verify UTXO handling
-    let state_account = client.create_account(program_id)?;
+    let state_account = client.create_derived_account(&[OVT_STATE_SEED], program_id)?;
     
     // Set and verify a test UTXO
     let test_txid = [2u8; 32];
@@ -182,6 +214,15 @@ fn test_nav_update() -> Result<(), Box<dyn std::error::Error>> {
             last_nav_update: 0,
             network_status: NetworkStatus::Syncing,
             last_sync_height: 0,
+            required_confirmations: 6,
+            treasury_script_kind: TreasuryScriptKind::P2wpkh,
+            network: String::new(),
+            nonce: 0,
+            treasury_script_hash: [0; 32],
+            spot_nav_sats: 0,
+            ema_alpha_bps: None,
+            payment_maturity_blocks: 0,
+            authority: [0; 32],
         };
         let serialized = borsh::to_vec(&initial_state)?;
         account.data = Arc::new(RefCell::new(serialized));
@@ -221,16 +262,18 @@ fn test_nav_update() -> Result<(), Box<dyn std::error::Error>> {
     assert!(client.verify_action(&init_action_type, &init_signatures)?);
 
     // Initialize through proper instruction flow
+    let burn_history = client.create_derived_account(&[BURN_HISTORY_SEED], program_id)?;
     let instruction = OVTInstruction::Initialize {
-        treasury_pubkey_bytes: [0u8; 33],
+        treasury_pubkey_bytes: treasury_key(),
     };
 
     client.process_transaction(
         program_id,
         vec![
-            AccountMeta::new(state_account.key, true),
+            AccountMeta::new(state_account.key, false),
             AccountMeta::new_readonly(admin_accounts[0].key, true),
             AccountMeta::new_readonly(system_program, false),
+            AccountMeta::new(burn_history.key, false),
         ],
         borsh::to_vec(&instruction)?,
     )?;
@@ -255,22 +298,26 @@ fn test_nav_update() -> Result<(), Box<dyn std::error::Error>> {
     assert!(client.verify_action(&nav_action_type, &nav_signatures)?);
 
     // Update NAV through proper instruction flow
+    let clock = client.create_clock_account(1_700_000_000)?;
     let instruction = OVTInstruction::UpdateNAV {
         btc_price_sats: new_nav,
+        expected_nonce: 0,
     };
 
     client.process_transaction(
         program_id,
         vec![
-            AccountMeta::new(state_account.key, true),
+            AccountMeta::new(state_account.key, false),
             AccountMeta::new_readonly(admin_accounts[0].key, true),
+            AccountMeta::new_readonly(clock.key, false),
         ],
         borsh::to_vec(&instruction)?,
     )?;
 
-    // Verify NAV was updated correctly
+    // Verify NAV was updated correctly, stamped with the clock's time
     let state: OVTState = client.get_account_data(&state_account.key)?;
     assert_eq!(state.nav_sats, new_nav, "NAV was not updated correctly");
+    assert_eq!(state.last_nav_update, 1_700_000_000, "NAV update should use the clock account's time");
 
     Ok(())
 }
@@ -294,7 +341,7 @@ fn test_nav_validation() -> Result<(), Box<dyn std::error::Error>> {
     }
     
     // Create state account and verify UTXO handling
-    let state_account = client.create_account(program_id)?;
+    let state_account = client.create_derived_account(&[OVT_STATE_SEED], program_id)?;
     
     // Set and verify a test UTXO for validation
     let test_txid = [3u8; 32];
@@ -319,6 +366,15 @@ fn test_nav_validation() -> Result<(), Box<dyn std::error::Error>> {
             last_nav_update: 0,
             network_status: NetworkStatus::Syncing,
             last_sync_height: 0,
+            required_confirmations: 6,
+            treasury_script_kind: TreasuryScriptKind::P2wpkh,
+            network: String::new(),
+            nonce: 0,
+            treasury_script_hash: [0; 32],
+            spot_nav_sats: 0,
+            ema_alpha_bps: None,
+            payment_maturity_blocks: 0,
+            authority: [0; 32],
         };
         let serialized = borsh::to_vec(&initial_state)?;
         account.data = Arc::new(RefCell::new(serialized));
@@ -357,16 +413,18 @@ fn test_nav_validation() -> Result<(), Box<dyn std::error::Error>> {
     let init_signatures: Vec<String> = (0..3).map(|i| format!("init_sig_{}", i)).collect();
     assert!(client.verify_action(&init_action_type, &init_signatures)?);
 
+    let burn_history = client.create_derived_account(&[BURN_HISTORY_SEED], program_id)?;
     let instruction = OVTInstruction::Initialize {
-        treasury_pubkey_bytes: [0u8; 33],
+        treasury_pubkey_bytes: treasury_key(),
     };
 
     client.process_transaction(
         program_id,
         vec![
-            AccountMeta::new(state_account.key, true),
+            AccountMeta::new(state_account.key, false),
             AccountMeta::new_readonly(admin_accounts[0].key, true),
             AccountMeta::new_readonly(system_program, false),
+            AccountMeta::new(burn_history.key, false),
         ],
         borsh::to_vec(&instruction)?,
     )?;
@@ -379,6 +437,15 @@ fn test_nav_validation() -> Result<(), Box<dyn std::error::Error>> {
         last_nav_update: 0,
         network_status: NetworkStatus::Syncing,
         last_sync_height: 0,
+        required_confirmations: 6,
+        treasury_script_kind: TreasuryScriptKind::P2wpkh,
+        network: String::new(),
+        nonce: 0,
+        treasury_script_hash: [0; 32],
+        spot_nav_sats: 0,
+        ema_alpha_bps: None,
+        payment_maturity_blocks: 0,
+        authority: admin_accounts[0].key.0,
     };
 
     {
@@ -388,4 +455,677 @@ fn test_nav_validation() -> Result<(), Box<dyn std::error::Error>> {
     }
 
     Ok(())
-} 
\ No newline at end of file
+} 
+
+/// Submit an UpdateNAV instruction signed by `admin` for the state's current
+/// nonce, reading the time from `clock`
+fn update_nav(
+    client: &TestClient,
+    program_id: Pubkey,
+    state: Pubkey,
+    admin: Pubkey,
+    clock: Pubkey,
+    btc_price_sats: u64,
+) -> Result<(), ProgramError> {
+    let expected_nonce = client.get_account_data::<OVTState>(&state)?.nonce;
+    let instruction = OVTInstruction::UpdateNAV { btc_price_sats, expected_nonce };
+    client.process_transaction(
+        program_id,
+        vec![
+            AccountMeta::new(state, false),
+            AccountMeta::new_readonly(admin, true),
+            AccountMeta::new_readonly(clock, false),
+        ],
+        borsh::to_vec(&instruction).unwrap(),
+    )
+}
+
+/// NAV update timing and bounds, driven through `process_transaction` with a controlled clock
+///
+/// Mirrors the state.rs unit test: updates need 15 seconds between them,
+/// may raise NAV by at most 400% and lower it by at most 80%.
+#[test]
+fn test_nav_update_timing_through_clock() -> Result<(), Box<dyn std::error::Error>> {
+    let mut client = TestClient::new();
+    let program_id = Pubkey::new_unique();
+    let admin = client.create_admin_account(program_id)?;
+    let state_account = client.create_derived_account(&[OVT_STATE_SEED], program_id)?;
+    let mut state = OVTState::new([0u8; 33]);
+    state.authority = admin.key.0;
+    state.nav_sats = 1_000_000;
+    state.total_supply = 1_000_000;
+    state_account.set_data(&state)?;
+    let clock = client.create_clock_account(16)?;
+    let update = |btc_price_sats| {
+        update_nav(&client, program_id, state_account.key, admin.key, clock.key, btc_price_sats)
+    };
+    let timeout = OVTError::OperationTimeout.code();
+    let invalid_nav = OVTError::InvalidNAVUpdate.code();
+
+    // t = 16: 100% increase
+    update(2_000_000)?;
+    // t = 20: too soon after the last update
+    client.advance_clock(&clock.key, 4)?;
+    assert!(matches!(update(500_000), Err(ProgramError::Custom(code)) if code == timeout));
+    // t = 32: 300% increase
+    client.advance_clock(&clock.key, 12)?;
+    update(8_000_000)?;
+    // t = 48: 525% increase
+    client.advance_clock(&clock.key, 16)?;
+    assert!(matches!(update(50_000_000), Err(ProgramError::Custom(code)) if code == invalid_nav));
+    // t = 64: 87.5% decrease
+    client.advance_clock(&clock.key, 16)?;
+    assert!(matches!(update(1_000_000), Err(ProgramError::Custom(code)) if code == invalid_nav));
+    // t = 80: 75% decrease
+    assert_eq!(client.advance_clock(&clock.key, 16)?, 80);
+    update(2_000_000)?;
+
+    let state: OVTState = client.get_account_data(&state_account.key)?;
+    assert_eq!(state.nav_sats, 2_000_000);
+    assert_eq!(state.last_nav_update, 80);
+    Ok(())
+}
+
+#[test]
+fn test_nav_update_needs_clock_account() -> Result<(), Box<dyn std::error::Error>> {
+    let mut client = TestClient::new();
+    let program_id = Pubkey::new_unique();
+    let admin = client.create_admin_account(program_id)?;
+    let state_account = client.create_derived_account(&[OVT_STATE_SEED], program_id)?;
+    let mut state = OVTState::new([0u8; 33]);
+    state.authority = admin.key.0;
+    state_account.set_data(&state)?;
+
+    let instruction = OVTInstruction::UpdateNAV { btc_price_sats: 1_000_000, expected_nonce: 0 };
+    let result = client.process_transaction(
+        program_id,
+        vec![
+            AccountMeta::new(state_account.key, false),
+            AccountMeta::new_readonly(admin.key, true),
+        ],
+        borsh::to_vec(&instruction)?,
+    );
+    assert!(matches!(result, Err(ProgramError::NotEnoughAccountKeys)));
+
+    // An account too short to hold a timestamp is not a clock
+    let not_a_clock = client.create_account(program_id)?;
+    let result = update_nav(&client, program_id, state_account.key, admin.key, not_a_clock.key, 1_000_000);
+    assert!(matches!(result, Err(ProgramError::InvalidAccountData)));
+    Ok(())
+}
+
+/// Initialize → UpdateNAV → BuybackBurn, all through `process_transaction`
+#[test]
+fn test_initialize_update_nav_buyback_burn() -> Result<(), Box<dyn std::error::Error>> {
+    let mut client = TestClient::new();
+    let program_id = Pubkey::new_unique();
+    let admin = client.create_admin_account(program_id)?;
+    let state_account = client.create_derived_account(&[OVT_STATE_SEED], program_id)?;
+    let system_program = client.create_account(program_id)?;
+    let burn_history = client.create_derived_account(&[BURN_HISTORY_SEED], program_id)?;
+    let clock = client.create_clock_account(1_700_000_000)?;
+
+    let initialize = OVTInstruction::Initialize { treasury_pubkey_bytes: treasury_key() };
+    client.process_transaction(
+        program_id,
+        vec![
+            AccountMeta::new(state_account.key, false),
+            AccountMeta::new_readonly(admin.key, true),
+            AccountMeta::new_readonly(system_program.key, false),
+            AccountMeta::new(burn_history.key, false),
+        ],
+        borsh::to_vec(&initialize)?,
+    )?;
+    update_nav(&client, program_id, state_account.key, admin.key, clock.key, 1_000_000)?;
+
+    // Supply is minted through Runes, outside the program
+    let mut state: OVTState = client.get_account_data(&state_account.key)?;
+    state.total_supply = 1_000_000;
+    state_account.set_data(&state)?;
+
+    let spent_payment = client.spent_payment_account(program_id, &"ab".repeat(32))?;
+    let burn = |payment_amount_sats| {
+        let instruction = OVTInstruction::BuybackBurn {
+            payment_txid: "ab".repeat(32),
+            payment_amount_sats,
+            payment_height: 0,
+            memo: None,
+            expected_nonce: client.get_account_data::<OVTState>(&state_account.key)?.nonce,
+        };
+        client.process_transaction(
+            program_id,
+            vec![
+                AccountMeta::new(state_account.key, false),
+                AccountMeta::new_readonly(admin.key, true),
+                AccountMeta::new(burn_history.key, false),
+                AccountMeta::new(spent_payment.key, false),
+                AccountMeta::new_readonly(system_program.key, false),
+            ],
+            borsh::to_vec(&instruction).unwrap(),
+        )
+    };
+
+    // 100k sats at a NAV of 1M sats buys back a tenth of the supply
+    burn(100_000)?;
+    let state: OVTState = client.get_account_data(&state_account.key)?;
+    assert_eq!(state.total_supply, 900_000);
+    assert_eq!(state.nav_sats, 1_000_000);
+
+    let invalid_payment = OVTError::InvalidBitcoinTransaction.code();
+    assert!(matches!(burn(0), Err(ProgramError::Custom(code)) if code == invalid_payment));
+    assert!(matches!(burn(2_000_000_000), Err(ProgramError::Custom(code)) if code == invalid_payment));
+    let state: OVTState = client.get_account_data(&state_account.key)?;
+    assert_eq!(state.total_supply, 900_000, "Rejected burns must not touch the supply");
+    Ok(())
+}
+
+/// Three burns through the mock handlers, read back from the burn history account
+#[test]
+fn test_burn_history_records_each_burn() -> Result<(), Box<dyn std::error::Error>> {
+    let mut client = TestClient::new();
+    let program_id = ovt_program_id();
+    let admin = client.create_admin_account(program_id)?;
+    let state_account = client.create_derived_account(&[OVT_STATE_SEED], program_id)?;
+    let mut state = OVTState::new(treasury_key());
+    state.authority = admin.key.0;
+    state.nav_sats = 1_000_000;
+    state.total_supply = 1_000_000;
+    state_account.set_data(&state)?;
+    let burn_history = client.create_burn_history_account(program_id)?;
+    let system_program = client.create_system_program_account()?;
+    let burn = |txid: &str, payment_amount_sats| {
+        let instruction = OVTInstruction::BuybackBurn {
+            payment_txid: txid.to_string(),
+            payment_amount_sats,
+            payment_height: 0,
+            memo: None,
+            expected_nonce: client.get_account_data::<OVTState>(&state_account.key)?.nonce,
+        };
+        client.process_transaction(
+            program_id,
+            vec![
+                AccountMeta::new(state_account.key, false),
+                AccountMeta::new_readonly(admin.key, true),
+                AccountMeta::new(burn_history.key, false),
+                AccountMeta::new(client.spent_payment_account(program_id, txid)?.key, false),
+                AccountMeta::new_readonly(system_program.key, false),
+            ],
+            borsh::to_vec(&instruction).unwrap(),
+        )
+    };
+
+    let payments = ["01".repeat(32), "02".repeat(32), "03".repeat(32)];
+    for (txid, payment_amount_sats) in payments.iter().zip([100_000, 50_000, 200_000]) {
+        burn(txid, payment_amount_sats)?;
+    }
+
+    let records = decode_burn_history(&burn_history.data.borrow()).map_err(mock_error)?;
+    let summary: Vec<(String, u64, u64, u64)> = records
+        .iter()
+        .map(|record| (record.payment_txid_hex(), record.payment_amount_sats, record.tokens_burned, record.supply_after))
+        .collect();
+    assert_eq!(
+        summary,
+        vec![
+            (payments[0].clone(), 100_000, 100_000, 900_000),
+            (payments[1].clone(), 50_000, 45_000, 855_000),
+            (payments[2].clone(), 200_000, 171_000, 684_000),
+        ]
+    );
+    assert!(records.iter().all(|record| record.nav_sats == 1_000_000));
+    let state: OVTState = client.get_account_data(&state_account.key)?;
+    assert_eq!((state.total_supply, state.nonce), (684_000, 3));
+
+    // A payment already burned for is refused, and nothing is recorded
+    let duplicate = OVTError::DuplicatePayment.code();
+    assert!(matches!(burn(&payments[1], 50_000), Err(ProgramError::Custom(code)) if code == duplicate));
+    assert_eq!(decode_burn_history(&burn_history.data.borrow()).map_err(mock_error)?, records);
+    assert_eq!(client.get_account_data::<OVTState>(&state_account.key)?.total_supply, 684_000);
+
+    // GetBurnHistory logs the same records, oldest first
+    let get_history = borsh::to_vec(&OVTInstruction::GetBurnHistory)?;
+    client.process_transaction(program_id, vec![AccountMeta::new_readonly(burn_history.key, false)], get_history.clone())?;
+    let logged: Vec<String> = records.iter().map(burn_record_event).collect();
+    assert!(client.logs().ends_with(&logged));
+    client.process_entrypoint(program_id, vec![AccountMeta::new_readonly(burn_history.key, false)], &get_history)?;
+    Ok(())
+}
+
+#[test]
+fn test_unregistered_signer_is_rejected() -> Result<(), Box<dyn std::error::Error>> {
+    let mut client = TestClient::new();
+    let program_id = Pubkey::new_unique();
+    // An account that exists but was never created as a signer, e.g. someone else's key
+    let impostor = client.create_clock_account(0)?;
+    let state_account = client.create_derived_account(&[OVT_STATE_SEED], program_id)?;
+    let mut state = OVTState::new([0u8; 33]);
+    state.nav_sats = 1_000_000;
+    state.total_supply = 1_000_000;
+    state.authority = impostor.key.0;
+    state_account.set_data(&state)?;
+
+    let burn_history = client.create_burn_history_account(program_id)?;
+    let spent_payment = client.spent_payment_account(program_id, &"ab".repeat(32))?;
+    let system_program = client.create_system_program_account()?;
+
+    let instruction = OVTInstruction::BuybackBurn {
+        payment_txid: "ab".repeat(32),
+        payment_amount_sats: 100_000,
+        payment_height: 0,
+        memo: None,
+        expected_nonce: 0,
+    };
+    let metas = |signer: Pubkey| {
+        vec![
+            AccountMeta::new(state_account.key, false),
+            AccountMeta::new_readonly(signer, true),
+            AccountMeta::new(burn_history.key, false),
+            AccountMeta::new(spent_payment.key, false),
+            AccountMeta::new_readonly(system_program.key, false),
+        ]
+    };
+
+    let result = client.process_transaction(program_id, metas(impostor.key), borsh::to_vec(&instruction)?);
+    assert!(matches!(result, Err(ProgramError::MissingRequiredSignature)));
+    let state: OVTState = client.get_account_data(&state_account.key)?;
+    assert_eq!(state.total_supply, 1_000_000);
+
+    // A key that may sign but isn't the state's authority
+    let admin = client.create_admin_account(program_id)?;
+    let result = client.process_transaction(program_id, metas(admin.key), borsh::to_vec(&instruction)?);
+    assert!(matches!(result, Err(ProgramError::Custom(code)) if code == OVTError::InvalidAuthority.code()));
+    let state: OVTState = client.get_account_data(&state_account.key)?;
+    assert_eq!(state.total_supply, 1_000_000);
+
+    // Once registered, the same key may sign
+    client.register_signer(impostor.key);
+    client.process_transaction(program_id, metas(impostor.key), borsh::to_vec(&instruction)?)?;
+    let state: OVTState = client.get_account_data(&state_account.key)?;
+    assert_eq!(state.total_supply, 900_000);
+    Ok(())
+}
+
+#[test]
+fn test_failed_instruction_leaves_state_untouched() -> Result<(), Box<dyn std::error::Error>> {
+    let mut client = TestClient::new();
+    let program_id = Pubkey::new_unique();
+    let admin = client.create_admin_account(program_id)?;
+    let state_account = client.create_derived_account(&[OVT_STATE_SEED], program_id)?;
+    let mut state = OVTState::new([0u8; 33]);
+    state.authority = admin.key.0;
+    state.nav_sats = 1_000_000;
+    state.total_supply = 1_000_000;
+    state_account.set_data(&state)?;
+    let clock = client.create_clock_account(16)?;
+
+    update_nav(&client, program_id, state_account.key, admin.key, clock.key, 2_000_000)?;
+    let before = state_account.data.borrow().clone();
+    let lamports = *state_account.lamports.borrow();
+
+    // Too soon after the last update
+    client.advance_clock(&clock.key, 1)?;
+    let timeout = OVTError::OperationTimeout.code();
+    let result = update_nav(&client, program_id, state_account.key, admin.key, clock.key, 2_500_000);
+    assert!(matches!(result, Err(ProgramError::Custom(code)) if code == timeout));
+
+    assert_eq!(*state_account.data.borrow(), before);
+    assert_eq!(*state_account.lamports.borrow(), lamports);
+    assert_eq!(*state_account.owner.borrow(), program_id);
+    let state: OVTState = client.get_account_data(&state_account.key)?;
+    assert_eq!(state.nav_sats, 2_000_000);
+    assert_eq!(state.last_nav_update, 16);
+    Ok(())
+}
+
+#[test]
+fn test_snapshot_branches_scenarios() -> Result<(), Box<dyn std::error::Error>> {
+    let mut client = TestClient::new();
+    let program_id = Pubkey::new_unique();
+    let admin = client.create_admin_account(program_id)?;
+    let state_account = client.create_derived_account(&[OVT_STATE_SEED], program_id)?;
+    let mut state = OVTState::new([0u8; 33]);
+    state.authority = admin.key.0;
+    state.nav_sats = 1_000_000;
+    state.total_supply = 1_000_000;
+    state_account.set_data(&state)?;
+    let clock = client.create_clock_account(16)?;
+    let setup = client.snapshot();
+
+    // Branch one: the NAV doubles, and a late signer joins
+    update_nav(&client, program_id, state_account.key, admin.key, clock.key, 2_000_000)?;
+    let late = client.create_account(program_id)?;
+    assert!(client.is_registered_signer(&late.key));
+
+    // Branch two starts from the common setup
+    client.restore(&setup);
+    let state: OVTState = client.get_account_data(&state_account.key)?;
+    assert_eq!(state.nav_sats, 1_000_000);
+    assert_eq!(state.last_nav_update, 0);
+    assert!(!client.is_registered_signer(&late.key));
+    assert!(client.get_account_data::<OVTState>(&late.key).is_err());
+    // Handles taken before the snapshot see the restored data
+    assert_eq!(borsh::from_slice::<OVTState>(&state_account.data.borrow())?.nav_sats, 1_000_000);
+
+    client.advance_clock(&clock.key, 20)?;
+    update_nav(&client, program_id, state_account.key, admin.key, clock.key, 500_000)?;
+    let state: OVTState = client.get_account_data(&state_account.key)?;
+    assert_eq!(state.nav_sats, 500_000);
+    assert_eq!(state.last_nav_update, 36);
+    Ok(())
+}
+
+#[test]
+fn test_program_address_derivation_is_deterministic() {
+    let program_id = Pubkey([7u8; 32]);
+    let (state, bump) = Pubkey::find_program_address(&[OVT_STATE_SEED], &program_id);
+    for _ in 0..10 {
+        assert_eq!(Pubkey::find_program_address(&[OVT_STATE_SEED], &program_id), (state, bump));
+    }
+    assert_eq!(Pubkey::create_program_address(&[OVT_STATE_SEED], bump, &program_id), Some(state));
+
+    let (treasury, _) = Pubkey::find_program_address(&[TREASURY_SEED], &program_id);
+    assert_ne!(treasury, state);
+    let (other_program, _) = Pubkey::find_program_address(&[OVT_STATE_SEED], &Pubkey([8u8; 32]));
+    assert_ne!(other_program, state);
+    // Seeds are hashed in order
+    let (split, _) = Pubkey::find_program_address(&[b"ovt_", b"state"], &program_id);
+    assert_eq!(split, state);
+}
+
+#[test]
+fn test_initialize_requires_derived_state_account() -> Result<(), Box<dyn std::error::Error>> {
+    let mut client = TestClient::new();
+    let program_id = Pubkey::new_unique();
+    let admin = client.create_admin_account(program_id)?;
+    let system_program = client.create_account(program_id)?;
+    let burn_history = client.create_derived_account(&[BURN_HISTORY_SEED], program_id)?;
+    let initialize = |state: Pubkey| {
+        let instruction = OVTInstruction::Initialize { treasury_pubkey_bytes: treasury_key() };
+        client.process_transaction(
+            program_id,
+            vec![
+                AccountMeta::new(state, false),
+                AccountMeta::new_readonly(admin.key, true),
+                AccountMeta::new_readonly(system_program.key, false),
+                AccountMeta::new(burn_history.key, false),
+            ],
+            borsh::to_vec(&instruction).unwrap(),
+        )
+    };
+
+    let arbitrary = client.create_account(program_id)?;
+    let invalid_state = OVTError::InvalidProgramState.code();
+    assert!(matches!(initialize(arbitrary.key), Err(ProgramError::Custom(code)) if code == invalid_state));
+    assert!(arbitrary.data.borrow().is_empty());
+
+    let state_account = client.create_derived_account(&[OVT_STATE_SEED], program_id)?;
+    assert_eq!(state_account.key, Pubkey::find_program_address(&[OVT_STATE_SEED], &program_id).0);
+    assert!(!client.is_registered_signer(&state_account.key));
+    initialize(state_account.key)?;
+    let state: OVTState = client.get_account_data(&state_account.key)?;
+    assert_eq!(state.treasury_pubkey_bytes, treasury_key());
+
+    assert!(matches!(
+        client.create_derived_account(&[OVT_STATE_SEED], program_id),
+        Err(ProgramError::AccountAlreadyInitialized)
+    ));
+    Ok(())
+}
+
+#[test]
+fn test_wrong_state_account_is_rejected() -> Result<(), Box<dyn std::error::Error>> {
+    let mut client = TestClient::new();
+    let program_id = Pubkey::new_unique();
+    let admin = client.create_admin_account(program_id)?;
+    let clock = client.create_clock_account(16)?;
+    let mut state = OVTState::new([0u8; 33]);
+    state.authority = admin.key.0;
+    state.nav_sats = 1_000_000;
+    state.total_supply = 1_000_000;
+
+    // A competing account holding a plausible state, and the one derived for another program
+    let impostor = client.create_account(program_id)?;
+    impostor.set_data(&state)?;
+    let foreign = client.create_derived_account(&[OVT_STATE_SEED], Pubkey::new_unique())?;
+    foreign.set_data(&state)?;
+    let burn_history = client.create_burn_history_account(program_id)?;
+    let spent_payment = client.spent_payment_account(program_id, &"ab".repeat(32))?;
+    let system_program = client.create_system_program_account()?;
+    let invalid_state = OVTError::InvalidProgramState.code();
+
+    for wrong in [impostor.key, foreign.key] {
+        let result = update_nav(&client, program_id, wrong, admin.key, clock.key, 2_000_000);
+        assert!(matches!(result, Err(ProgramError::Custom(code)) if code == invalid_state));
+
+        let instruction = OVTInstruction::BuybackBurn {
+            payment_txid: "ab".repeat(32),
+            payment_amount_sats: 100_000,
+            payment_height: 0,
+            memo: None,
+            expected_nonce: 0,
+        };
+        let result = client.process_transaction(
+            program_id,
+            vec![
+                AccountMeta::new(wrong, false),
+                AccountMeta::new_readonly(admin.key, true),
+                AccountMeta::new(burn_history.key, false),
+                AccountMeta::new(spent_payment.key, false),
+                AccountMeta::new_readonly(system_program.key, false),
+            ],
+            borsh::to_vec(&instruction)?,
+        );
+        assert!(matches!(result, Err(ProgramError::Custom(code)) if code == invalid_state));
+    }
+
+    let untouched: OVTState = client.get_account_data(&impostor.key)?;
+    assert_eq!((untouched.nav_sats, untouched.total_supply), (1_000_000, 1_000_000));
+    Ok(())
+}
+
+#[test]
+fn test_deterministic_keys_follow_the_seed() {
+    program::deterministic_rng::seed_rng([9u8; 32]);
+    let first: Vec<Pubkey> = (0..3).map(|_| Pubkey::new_deterministic()).collect();
+    assert_ne!(first[0], first[1]);
+
+    program::deterministic_rng::seed_rng([9u8; 32]);
+    let again: Vec<Pubkey> = (0..3).map(|_| Pubkey::new_deterministic()).collect();
+    assert_eq!(first, again);
+
+    program::deterministic_rng::seed_rng([10u8; 32]);
+    assert_ne!(Pubkey::new_deterministic(), first[0]);
+}
+
+/// Raw instruction bytes through `program::process_instruction`, the deployed entrypoint
+#[test]
+fn test_entrypoint_dispatches_raw_instructions() -> Result<(), Box<dyn std::error::Error>> {
+    let mut client = TestClient::new();
+    let program_id = ovt_program_id();
+    let admin = client.create_admin_account(program_id)?;
+    let state_account = client.create_derived_account(&[OVT_STATE_SEED], program_id)?;
+    let mut state = OVTState::new([0u8; 33]);
+    state.authority = admin.key.0;
+    state.nav_sats = 1_000_000;
+    state.total_supply = 1_000_000;
+    state_account.set_data(&state)?;
+    let clock = client.create_clock_account(16)?;
+    let metas = || vec![
+        AccountMeta::new(state_account.key, false),
+        AccountMeta::new_readonly(admin.key, true),
+        AccountMeta::new_readonly(clock.key, false),
+    ];
+
+    let instruction = OVTInstruction::UpdateNAV { btc_price_sats: 2_000_000, expected_nonce: 0 };
+    client.process_entrypoint(program_id, metas(), &borsh::to_vec(&instruction)?)?;
+    let state: OVTState = client.get_account_data(&state_account.key)?;
+    assert_eq!((state.nav_sats, state.last_nav_update, state.nonce), (2_000_000, 16, 1));
+
+    client.advance_clock(&clock.key, 16)?;
+    let result = client.process_entrypoint(program_id, metas(), &[0xff, 0x01, 0x02]);
+    assert!(matches!(result, Err(ProgramError::InvalidInstructionData)));
+    let result = client.process_entrypoint(program_id, metas(), &[]);
+    assert!(matches!(result, Err(ProgramError::InvalidInstructionData)));
+
+    let unchanged: OVTState = client.get_account_data(&state_account.key)?;
+    assert_eq!(unchanged.nav_sats, 2_000_000);
+    Ok(())
+}
+
+/// A deployment under another program id is turned away, even with its own state account
+#[test]
+fn test_entrypoint_rejects_other_program_ids() -> Result<(), Box<dyn std::error::Error>> {
+    let mut client = TestClient::new();
+    let program_id = Pubkey::new_unique();
+    let admin = client.create_admin_account(program_id)?;
+    let state_account = client.create_derived_account(&[OVT_STATE_SEED], program_id)?;
+    let mut state = OVTState::new([0u8; 33]);
+    state.authority = admin.key.0;
+    state.nav_sats = 1_000_000;
+    state.total_supply = 1_000_000;
+    state_account.set_data(&state)?;
+    let clock = client.create_clock_account(16)?;
+    let metas = vec![
+        AccountMeta::new(state_account.key, false),
+        AccountMeta::new_readonly(admin.key, true),
+        AccountMeta::new_readonly(clock.key, false),
+    ];
+
+    let update = borsh::to_vec(&OVTInstruction::UpdateNAV { btc_price_sats: 2_000_000, expected_nonce: 0 })?;
+    let result = client.process_entrypoint(program_id, metas, &update);
+    assert!(matches!(result, Err(ProgramError::IncorrectProgramId)));
+    let unchanged: OVTState = client.get_account_data(&state_account.key)?;
+    assert_eq!((unchanged.nav_sats, unchanged.nonce), (1_000_000, 0));
+    Ok(())
+}
+
+/// Sync reports need the authority's signature, and the next NAV update activates the program
+#[test]
+fn test_sync_status_then_nav_update_activates() -> Result<(), Box<dyn std::error::Error>> {
+    let mut client = TestClient::new();
+    let program_id = Pubkey::new_unique();
+    let admin = client.create_admin_account(program_id)?;
+    let state_account = client.create_derived_account(&[OVT_STATE_SEED], program_id)?;
+    let mut state = OVTState::new([0u8; 33]);
+    state.authority = admin.key.0;
+    state_account.set_data(&state)?;
+    let clock = client.create_clock_account(16)?;
+    let sync = |height, signed| {
+        let expected_nonce = client.get_account_data::<OVTState>(&state_account.key).unwrap().nonce;
+        let instruction = OVTInstruction::UpdateSyncStatus { height, status: NetworkStatus::Syncing, expected_nonce };
+        client.process_transaction(
+            program_id,
+            vec![
+                AccountMeta::new(state_account.key, false),
+                AccountMeta::new_readonly(admin.key, signed),
+            ],
+            borsh::to_vec(&instruction).unwrap(),
+        )
+    };
+
+    assert!(matches!(sync(850_000, false), Err(ProgramError::MissingRequiredSignature)));
+    sync(850_000, true)?;
+    let invalid_height = OVTError::InvalidBlockHeight.code();
+    assert!(matches!(sync(849_999, true), Err(ProgramError::Custom(code)) if code == invalid_height));
+
+    update_nav(&client, program_id, state_account.key, admin.key, clock.key, 1_000_000)?;
+    let state: OVTState = client.get_account_data(&state_account.key)?;
+    assert_eq!((state.last_sync_height, state.network_status), (850_000, NetworkStatus::Active));
+    Ok(())
+}
+
+/// A replayed UpdateNAV or BuybackBurn is rejected by the nonce, through the
+/// mock handlers and the deployed entrypoint alike
+#[test]
+fn test_replayed_instruction_is_rejected() -> Result<(), Box<dyn std::error::Error>> {
+    let mut client = TestClient::new();
+    let program_id = ovt_program_id();
+    let admin = client.create_admin_account(program_id)?;
+    let state_account = client.create_derived_account(&[OVT_STATE_SEED], program_id)?;
+    let mut state = OVTState::new([0u8; 33]);
+    state.authority = admin.key.0;
+    state.nav_sats = 1_000_000;
+    state.total_supply = 1_000_000;
+    state_account.set_data(&state)?;
+    let clock = client.create_clock_account(16)?;
+    let nav_metas = || vec![
+        AccountMeta::new(state_account.key, false),
+        AccountMeta::new_readonly(admin.key, true),
+        AccountMeta::new_readonly(clock.key, false),
+    ];
+    let burn_history = client.create_burn_history_account(program_id)?;
+    let spent_payment = client.spent_payment_account(program_id, &"ab".repeat(32))?;
+    let system_program = client.create_system_program_account()?;
+    let burn_metas = || vec![
+        AccountMeta::new(state_account.key, false),
+        AccountMeta::new_readonly(admin.key, true),
+        AccountMeta::new(burn_history.key, false),
+        AccountMeta::new(spent_payment.key, false),
+        AccountMeta::new_readonly(system_program.key, false),
+    ];
+    let mismatch = OVTError::NonceMismatch.code();
+
+    let update = borsh::to_vec(&OVTInstruction::UpdateNAV { btc_price_sats: 1_100_000, expected_nonce: 0 })?;
+    client.process_transaction(program_id, nav_metas(), update.clone())?;
+    // Late enough to pass the rate limit, so only the nonce stops it
+    client.advance_clock(&clock.key, 16)?;
+    let result = client.process_transaction(program_id, nav_metas(), update.clone());
+    assert!(matches!(result, Err(ProgramError::Custom(code)) if code == mismatch));
+    let result = client.process_entrypoint(program_id, nav_metas(), &update);
+    assert!(matches!(result, Err(ProgramError::Custom(code)) if code == mismatch));
+
+    let burn = borsh::to_vec(&OVTInstruction::BuybackBurn {
+        payment_txid: "ab".repeat(32),
+        payment_amount_sats: 110_000,
+        payment_height: 0,
+        memo: None,
+        expected_nonce: 1,
+    })?;
+    client.process_transaction(program_id, burn_metas(), burn.clone())?;
+    let result = client.process_entrypoint(program_id, burn_metas(), &burn);
+    assert!(matches!(result, Err(ProgramError::Custom(code)) if code == mismatch));
+
+    let state: OVTState = client.get_account_data(&state_account.key)?;
+    assert_eq!((state.nav_sats, state.last_nav_update), (1_100_000, 16));
+    assert_eq!((state.total_supply, state.nonce), (900_000, 2));
+    Ok(())
+}
+
+#[test]
+fn test_initialize_rejects_invalid_treasury_key() -> Result<(), Box<dyn std::error::Error>> {
+    let mut client = TestClient::new();
+    let program_id = ovt_program_id();
+    let admin = client.create_admin_account(program_id)?;
+    let state_account = client.create_derived_account(&[OVT_STATE_SEED], program_id)?;
+    let system_program = client.create_account(program_id)?;
+    let burn_history = client.create_derived_account(&[BURN_HISTORY_SEED], program_id)?;
+    let metas = || vec![
+        AccountMeta::new(state_account.key, false),
+        AccountMeta::new_readonly(admin.key, true),
+        AccountMeta::new_readonly(system_program.key, false),
+        AccountMeta::new(burn_history.key, false),
+    ];
+    let invalid_key = OVTError::InvalidTreasuryKey.code();
+
+    let mut bad_prefix = treasury_key();
+    bad_prefix[0] = 0x05;
+    // x = 5 has no y with y^2 = x^3 + 7
+    let mut off_curve = [0u8; 33];
+    off_curve[0] = 0x02;
+    off_curve[32] = 5;
+    for key in [[0u8; 33], bad_prefix, off_curve] {
+        let initialize = borsh::to_vec(&OVTInstruction::Initialize { treasury_pubkey_bytes: key })?;
+        let result = client.process_transaction(program_id, metas(), initialize.clone());
+        assert!(matches!(result, Err(ProgramError::Custom(code)) if code == invalid_key), "{}", hex::encode(key));
+        let result = client.process_entrypoint(program_id, metas(), &initialize);
+        assert!(matches!(result, Err(ProgramError::Custom(code)) if code == invalid_key), "{}", hex::encode(key));
+        assert!(state_account.data.borrow().is_empty());
+    }
+
+    let initialize = borsh::to_vec(&OVTInstruction::Initialize { treasury_pubkey_bytes: treasury_key() })?;
+    client.process_transaction(program_id, metas(), initialize)?;
+    let state: OVTState = client.get_account_data(&state_account.key)?;
+    assert_eq!(state.treasury_script_hash, OVTState::new(treasury_key()).treasury_script_hash);
+    assert!(state.is_treasury_script(&state.treasury_script_pubkey()?));
+    Ok(())
+}
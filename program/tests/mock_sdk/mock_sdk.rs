@@ -2,7 +2,7 @@ use borsh::{BorshDeserialize, BorshSerialize};
 use std::convert::From;
 use std::io;
 use std::cell::RefCell;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fmt;
 use std::sync::{Arc, Mutex};
 use bitcoin::hashes::{sha256, Hash};
@@ -14,8 +14,71 @@ use program::state::NetworkStatus;
 // Import program types for mock implementation
 pub mod program_types {
     pub use ::program::{OVTInstruction, OVTState};
-    pub use ::program::state::NetworkStatus;
+    pub use ::program::error::OVTError;
+    pub use ::program::state::buyback_burn_event;
     use borsh::BorshDeserialize;
+
+    /// Minimum seconds between NAV updates, as enforced by `OVTState::update_nav`
+    pub const NAV_UPDATE_INTERVAL_SECS: u64 = 15;
+
+    pub use ::program::address::{entry_key_hash, BURN_HISTORY_SEED, ENTRY_SEED, OVT_STATE_SEED, SPENT_PAYMENT_SEED, TREASURY_SEED};
+    pub use ::program::state::{ENTRY_ACCOUNT_LEN, MAX_ENTRY_VALUE_LEN};
+    pub use ::program::burn_history::{burn_record_event, parse_payment_txid, BurnHistory, BurnRecord};
+
+    /// Map an error from the real program onto the mock error type
+    pub fn mock_error(error: ::arch_program::program_error::ProgramError) -> super::ProgramError {
+        use ::arch_program::program_error::ProgramError as RealError;
+        match error {
+            RealError::Custom(code) => super::ProgramError::Custom(code),
+            RealError::InvalidInstructionData => super::ProgramError::InvalidInstructionData,
+            RealError::InvalidAccountData => super::ProgramError::InvalidAccountData,
+            RealError::AccountDataTooSmall => super::ProgramError::AccountDataTooSmall,
+            RealError::MissingRequiredSignature => super::ProgramError::MissingRequiredSignature,
+            RealError::NotEnoughAccountKeys => super::ProgramError::NotEnoughAccountKeys,
+            RealError::AccountBorrowFailed => super::ProgramError::AccountBorrowFailed,
+            RealError::IllegalOwner => super::ProgramError::IllegalOwner,
+            RealError::IncorrectProgramId => super::ProgramError::IncorrectProgramId,
+            _ => super::ProgramError::InvalidArgument,
+        }
+    }
+
+    /// Reject any state account other than the one derived from `OVT_STATE_SEED`, like `check_state_account`
+    fn check_state_account(ctx: &super::ProgramContext, state_account: &super::AccountInfo) -> Result<(), super::ProgramError> {
+        let (state_address, _) = super::Pubkey::find_program_address(&[OVT_STATE_SEED], &ctx.program_id);
+        if state_account.key != state_address {
+            return Err(super::ProgramError::Custom(OVTError::InvalidProgramState.code()));
+        }
+        Ok(())
+    }
+
+    /// Reject any burn history account other than the one derived from `BURN_HISTORY_SEED`
+    fn check_burn_history_account(ctx: &super::ProgramContext, history_account: &super::AccountInfo) -> Result<(), super::ProgramError> {
+        let (history_address, _) = super::Pubkey::find_program_address(&[BURN_HISTORY_SEED], &ctx.program_id);
+        if history_account.key != history_address {
+            return Err(super::ProgramError::Custom(OVTError::InvalidProgramState.code()));
+        }
+        Ok(())
+    }
+
+    /// The state in `state_account`, read like `Pack::unpack_from_slice` so the
+    /// padding up to `OVTState::LEN` is ignored, and refused like `execute` if
+    /// it was initialized on another network or `admin_account` isn't its authority
+    fn load_state(state_account: &super::AccountInfo, admin_account: &super::AccountInfo) -> Result<OVTState, super::ProgramError> {
+        use ::arch_program::program_pack::Pack;
+        let state = OVTState::unpack_from_slice(&state_account.data.borrow()).map_err(mock_error)?;
+        state.validate_network(::program::network_config::PROGRAM_NETWORK).map_err(mock_error)?;
+        if admin_account.key.0 != state.authority {
+            return Err(super::ProgramError::Custom(OVTError::InvalidAuthority.code()));
+        }
+        Ok(state)
+    }
+
+    /// Unix timestamp held by a clock account: the first 8 bytes, little-endian
+    pub fn clock_unix_timestamp(clock_account: &super::AccountInfo) -> Result<u64, super::ProgramError> {
+        let data = clock_account.data.try_borrow().map_err(|_| super::ProgramError::AccountBorrowFailed)?;
+        let timestamp = data.get(..8).ok_or(super::ProgramError::InvalidAccountData)?;
+        Ok(u64::from_le_bytes(timestamp.try_into().map_err(|_| super::ProgramError::InvalidAccountData)?))
+    }
     
     // Mock implementation of process_instruction that works with our mock types
     pub fn process_instruction(ctx: &super::ProgramContext, data: &[u8]) -> Result<(), super::ProgramError> {
@@ -29,7 +92,7 @@ pub mod program_types {
         match instruction {
             OVTInstruction::Initialize { treasury_pubkey_bytes } => {
                 // Mock implementation for Initialize
-                if ctx.accounts.len() < 3 {
+                if ctx.accounts.len() < 4 {
                     return Err(super::ProgramError::NotEnoughAccountKeys);
                 }
                 
@@ -37,29 +100,35 @@ pub mod program_types {
                 if !state_account.is_writable {
                     return Err(super::ProgramError::InvalidArgument);
                 }
+                check_state_account(ctx, state_account)?;
+                let history_account = &ctx.accounts[3];
+                check_burn_history_account(ctx, history_account)?;
                 
                 let admin_account = &ctx.accounts[1];
                 if !admin_account.is_signer {
                     return Err(super::ProgramError::MissingRequiredSignature);
                 }
                 
-                // Initialize state
-                let state = OVTState {
-                    nav_sats: 0,
-                    treasury_pubkey_bytes,
-                    total_supply: 0,
-                    last_nav_update: 0,
-                    network_status: NetworkStatus::Syncing,
-                    last_sync_height: 0,
-                };
+                // Initialize state in an account allocated at `OVTState::LEN`, as the runtime would
+                let mut state = OVTState::try_new(treasury_pubkey_bytes).map_err(mock_error)?;
+                state.authority = admin_account.key.0;
+                if state_account.data.borrow().is_empty() {
+                    *state_account.data.borrow_mut() = vec![0; <OVTState as ::arch_program::program_pack::Pack>::LEN];
+                }
                 
                 state_account.set_data(&state).map_err(|_| super::ProgramError::AccountDataTooSmall)?;
+
+                // Created zeroed, as an empty history
+                if !history_account.data.borrow().is_empty() {
+                    return Err(super::ProgramError::AccountAlreadyInitialized);
+                }
+                *history_account.data.borrow_mut() = vec![0; BurnHistory::LEN];
                 
                 Ok(())
             },
-            OVTInstruction::UpdateNAV { btc_price_sats } => {
+            OVTInstruction::UpdateNAV { btc_price_sats, expected_nonce } => {
                 // Mock implementation for UpdateNAV
-                if ctx.accounts.len() < 2 {
+                if ctx.accounts.len() < 3 {
                     return Err(super::ProgramError::NotEnoughAccountKeys);
                 }
                 
@@ -67,6 +136,7 @@ pub mod program_types {
                 if !state_account.is_writable {
                     return Err(super::ProgramError::InvalidArgument);
                 }
+                check_state_account(ctx, state_account)?;
                 
                 let admin_account = &ctx.accounts[1];
                 if !admin_account.is_signer {
@@ -74,21 +144,223 @@ pub mod program_types {
                 }
                 
                 // Update state
-                let mut state: OVTState = borsh::from_slice(&state_account.data.borrow())
-                    .map_err(|_| super::ProgramError::InvalidAccountData)?;
+                let mut state = load_state(state_account, admin_account)?;
                 
-                state.nav_sats = btc_price_sats;
-                state.last_nav_update = std::time::SystemTime::now()
-                    .duration_since(std::time::UNIX_EPOCH)
-                    .unwrap()
-                    .as_secs() as u64;
+                state.check_nonce(expected_nonce).map_err(mock_error)?;
+
+                // Same checks as `OVTState::update_nav`, with the time taken from the clock account
+                let current_time = clock_unix_timestamp(&ctx.accounts[2])?;
+                if current_time.saturating_sub(state.last_nav_update) < NAV_UPDATE_INTERVAL_SECS {
+                    return Err(super::ProgramError::Custom(OVTError::OperationTimeout.code()));
+                }
+                // Smoothing included
+                state.apply_nav_update(btc_price_sats, current_time).map_err(mock_error)?;
+                state.nonce += 1;
                 
                 state_account.set_data(&state).map_err(|_| super::ProgramError::AccountDataTooSmall)?;
                 
                 Ok(())
             },
-            // Add other instruction handlers as needed
-            _ => Err(super::ProgramError::InvalidInstructionData),
+            OVTInstruction::BuybackBurn { payment_txid, payment_amount_sats, payment_height, memo, expected_nonce } => {
+                // Mock implementation for BuybackBurn
+                if ctx.accounts.len() < 5 {
+                    return Err(super::ProgramError::NotEnoughAccountKeys);
+                }
+
+                let state_account = &ctx.accounts[0];
+                if !state_account.is_writable {
+                    return Err(super::ProgramError::InvalidArgument);
+                }
+                check_state_account(ctx, state_account)?;
+
+                let admin_account = &ctx.accounts[1];
+                if !admin_account.is_signer {
+                    return Err(super::ProgramError::MissingRequiredSignature);
+                }
+
+                let history_account = &ctx.accounts[2];
+                check_burn_history_account(ctx, history_account)?;
+
+                let mut state = load_state(state_account, admin_account)?;
+                state.check_nonce(expected_nonce).map_err(mock_error)?;
+                state.check_payment_maturity(payment_height).map_err(mock_error)?;
+                let tokens_burned = state.process_buyback_burn(payment_amount_sats).map_err(mock_error)?;
+                state.nonce += 1;
+
+                let mut history = BurnHistory::unpack(&history_account.data.borrow()).map_err(mock_error)?;
+                let record = BurnRecord::for_burn(&payment_txid, payment_amount_sats, tokens_burned, &state).map_err(mock_error)?;
+
+                // Like `BurnAccounts::record`, the payment's account must be empty until it is burned for
+                let spent_account = &ctx.accounts[3];
+                let (spent_address, _) =
+                    super::Pubkey::find_program_address(&[SPENT_PAYMENT_SEED, &record.payment_txid], &ctx.program_id);
+                if spent_account.key != spent_address {
+                    return Err(super::ProgramError::Custom(OVTError::InvalidProgramState.code()));
+                }
+                if !spent_account.data.borrow().is_empty() {
+                    return Err(super::ProgramError::Custom(OVTError::DuplicatePayment.code()));
+                }
+
+                history.append(record.clone()).map_err(mock_error)?;
+                history.pack_into(&mut history_account.data.borrow_mut()).map_err(mock_error)?;
+                spent_account.set_data(&record).map_err(|_| super::ProgramError::AccountDataTooSmall)?;
+
+                state_account.set_data(&state).map_err(|_| super::ProgramError::AccountDataTooSmall)?;
+                ctx.logs.borrow_mut().push(buyback_burn_event(&payment_txid, payment_amount_sats, memo.as_ref()));
+
+                Ok(())
+            },
+            OVTInstruction::UpdateSyncStatus { height, status, expected_nonce } => {
+                if ctx.accounts.len() < 2 {
+                    return Err(super::ProgramError::NotEnoughAccountKeys);
+                }
+
+                let state_account = &ctx.accounts[0];
+                if !state_account.is_writable {
+                    return Err(super::ProgramError::InvalidArgument);
+                }
+                check_state_account(ctx, state_account)?;
+
+                let admin_account = &ctx.accounts[1];
+                if !admin_account.is_signer {
+                    return Err(super::ProgramError::MissingRequiredSignature);
+                }
+
+                let mut state = load_state(state_account, admin_account)?;
+                state.check_nonce(expected_nonce).map_err(mock_error)?;
+                state.update_sync_status(height, status).map_err(mock_error)?;
+                state.nonce += 1;
+
+                state_account.set_data(&state).map_err(|_| super::ProgramError::AccountDataTooSmall)?;
+
+                Ok(())
+            },
+            OVTInstruction::LegacyUpdateNAV { .. } | OVTInstruction::LegacyBuybackBurn { .. } => {
+                Err(super::ProgramError::Custom(OVTError::UnsupportedInstructionVersion.code()))
+            },
+            OVTInstruction::GetBurnHistory => {
+                let history_account = ctx.accounts.first().ok_or(super::ProgramError::NotEnoughAccountKeys)?;
+                check_burn_history_account(ctx, history_account)?;
+
+                let history = BurnHistory::unpack(&history_account.data.borrow()).map_err(mock_error)?;
+                ctx.logs.borrow_mut().extend(history.records().iter().map(burn_record_event));
+
+                Ok(())
+            },
+            OVTInstruction::SetNavSmoothing { ema_alpha_bps, expected_nonce } => {
+                if ctx.accounts.len() < 2 {
+                    return Err(super::ProgramError::NotEnoughAccountKeys);
+                }
+
+                let state_account = &ctx.accounts[0];
+                if !state_account.is_writable {
+                    return Err(super::ProgramError::InvalidArgument);
+                }
+                check_state_account(ctx, state_account)?;
+
+                let admin_account = &ctx.accounts[1];
+                if !admin_account.is_signer {
+                    return Err(super::ProgramError::MissingRequiredSignature);
+                }
+
+                let mut state = load_state(state_account, admin_account)?;
+                state.check_nonce(expected_nonce).map_err(mock_error)?;
+                state.set_nav_smoothing(ema_alpha_bps).map_err(mock_error)?;
+                state.nonce += 1;
+
+                state_account.set_data(&state).map_err(|_| super::ProgramError::AccountDataTooSmall)?;
+
+                Ok(())
+            },
+            OVTInstruction::SetPaymentMaturity { blocks, expected_nonce } => {
+                if ctx.accounts.len() < 2 {
+                    return Err(super::ProgramError::NotEnoughAccountKeys);
+                }
+
+                let state_account = &ctx.accounts[0];
+                if !state_account.is_writable {
+                    return Err(super::ProgramError::InvalidArgument);
+                }
+                check_state_account(ctx, state_account)?;
+
+                let admin_account = &ctx.accounts[1];
+                if !admin_account.is_signer {
+                    return Err(super::ProgramError::MissingRequiredSignature);
+                }
+
+                let mut state = load_state(state_account, admin_account)?;
+                state.check_nonce(expected_nonce).map_err(mock_error)?;
+                state.payment_maturity_blocks = blocks;
+                state.nonce += 1;
+
+                state_account.set_data(&state).map_err(|_| super::ProgramError::AccountDataTooSmall)?;
+
+                Ok(())
+            },
+            OVTInstruction::SetTreasuryScript { script_kind, expected_nonce } => {
+                if ctx.accounts.len() < 2 {
+                    return Err(super::ProgramError::NotEnoughAccountKeys);
+                }
+
+                let state_account = &ctx.accounts[0];
+                if !state_account.is_writable {
+                    return Err(super::ProgramError::InvalidArgument);
+                }
+                check_state_account(ctx, state_account)?;
+
+                let admin_account = &ctx.accounts[1];
+                if !admin_account.is_signer {
+                    return Err(super::ProgramError::MissingRequiredSignature);
+                }
+
+                let mut state = load_state(state_account, admin_account)?;
+                state.check_nonce(expected_nonce).map_err(mock_error)?;
+                state.set_treasury_script(script_kind).map_err(mock_error)?;
+                state.nonce += 1;
+
+                state_account.set_data(&state).map_err(|_| super::ProgramError::AccountDataTooSmall)?;
+
+                Ok(())
+            },
+            OVTInstruction::WriteEntry { key, value, expected_nonce } => {
+                if ctx.accounts.len() < 4 {
+                    return Err(super::ProgramError::NotEnoughAccountKeys);
+                }
+
+                let state_account = &ctx.accounts[0];
+                if !state_account.is_writable {
+                    return Err(super::ProgramError::InvalidArgument);
+                }
+                check_state_account(ctx, state_account)?;
+
+                let admin_account = &ctx.accounts[1];
+                if !admin_account.is_signer {
+                    return Err(super::ProgramError::MissingRequiredSignature);
+                }
+
+                let mut state = load_state(state_account, admin_account)?;
+                state.check_nonce(expected_nonce).map_err(mock_error)?;
+                if value.len() > MAX_ENTRY_VALUE_LEN {
+                    return Err(super::ProgramError::InvalidInstructionData);
+                }
+                state.nonce += 1;
+
+                // Like `EntryAccounts::store`, at the key's derived address, allocated on the first write
+                let entry_account = &ctx.accounts[2];
+                let (entry_address, _) =
+                    super::Pubkey::find_program_address(&[ENTRY_SEED, &entry_key_hash(&key)], &ctx.program_id);
+                if entry_account.key != entry_address {
+                    return Err(super::ProgramError::Custom(OVTError::InvalidProgramState.code()));
+                }
+                if entry_account.data.borrow().is_empty() {
+                    *entry_account.data.borrow_mut() = vec![0; ENTRY_ACCOUNT_LEN];
+                }
+                entry_account.set_data(&value).map_err(|_| super::ProgramError::AccountDataTooSmall)?;
+
+                state_account.set_data(&state).map_err(|_| super::ProgramError::AccountDataTooSmall)?;
+
+                Ok(())
+            },
         }
     }
 }
@@ -97,6 +369,8 @@ pub mod program_types {
 pub struct ProgramContext {
     pub accounts: Vec<AccountInfo>,
     pub program_id: Pubkey,
+    /// Messages the instruction logged, in order
+    pub logs: RefCell<Vec<String>>,
 }
 
 pub struct AccountMeta {
@@ -160,6 +434,31 @@ pub mod pubkey {
             bytes.copy_from_slice(&hash[..]);
             Self(bytes)
         }
+
+        /// Next key from `deterministic_rng`, reproducible after `seed_rng`
+        pub fn new_deterministic() -> Self {
+            let mut bytes = [0u8; 32];
+            ::program::deterministic_rng::fill_bytes(&mut bytes);
+            Self(bytes)
+        }
+
+        /// Derive a program address from `seeds` with `arch_program`'s
+        /// derivation, so the mock finds the same addresses the program checks
+        pub fn find_program_address(seeds: &[&[u8]], program_id: &Pubkey) -> (Pubkey, u8) {
+            let program_id = ::arch_program::pubkey::Pubkey(program_id.0);
+            let (key, bump) = ::arch_program::pubkey::Pubkey::find_program_address(seeds, &program_id);
+            (Self(key.0), bump)
+        }
+
+        /// The program address for `seeds` and `bump`, or `None` if the runtime rejects them
+        pub fn create_program_address(seeds: &[&[u8]], bump: u8, program_id: &Pubkey) -> Option<Pubkey> {
+            let bump = [bump];
+            let seeds: Vec<&[u8]> = seeds.iter().copied().chain([&bump[..]]).collect();
+            let program_id = ::arch_program::pubkey::Pubkey(program_id.0);
+            ::arch_program::pubkey::Pubkey::create_program_address(&seeds, &program_id)
+                .ok()
+                .map(|key| Self(key.0))
+        }
     }
 
     impl BorshSerialize for Pubkey {
@@ -258,9 +557,16 @@ pub mod account_info {
             }
         }
 
+        /// Write `data` at the front of the account and zero the rest, keeping its
+        /// length as the runtime does; only accounts too small for it are grown
         pub fn set_data<T: BorshSerialize>(&self, data: &T) -> Result<(), io::Error> {
             let serialized = borsh::to_vec(data).map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
-            *self.data.borrow_mut() = serialized;
+            let mut account_data = self.data.borrow_mut();
+            if account_data.len() < serialized.len() {
+                account_data.resize(serialized.len(), 0);
+            }
+            account_data[..serialized.len()].copy_from_slice(&serialized);
+            account_data[serialized.len()..].fill(0);
             Ok(())
         }
     }
@@ -340,13 +646,53 @@ pub mod test_utils {
         ScriptBuf::new()
     }
 
+    /// Copy of an account's mutable fields
+    #[derive(Debug, Clone)]
+    pub struct AccountSnapshot {
+        lamports: u64,
+        data: Vec<u8>,
+        owner: Pubkey,
+        utxo: UtxoMeta,
+    }
+
+    impl AccountSnapshot {
+        pub fn capture(account: &AccountInfo) -> Self {
+            Self {
+                lamports: *account.lamports.borrow(),
+                data: account.data.borrow().clone(),
+                owner: *account.owner.borrow(),
+                utxo: account.utxo,
+            }
+        }
+
+        /// Write the captured lamports, data and owner back through `account`'s
+        /// shared cells, so every handle to the account sees them
+        pub fn restore_into(&self, account: &AccountInfo) {
+            *account.lamports.borrow_mut() = self.lamports;
+            *account.data.borrow_mut() = self.data.clone();
+            *account.owner.borrow_mut() = self.owner;
+        }
+    }
+
+    /// Everything a `TestClient` knows, to branch scenarios from a common setup
+    #[derive(Debug, Clone)]
+    pub struct StateSnapshot {
+        accounts: HashMap<Pubkey, AccountSnapshot>,
+        admin_accounts: HashMap<Pubkey, bool>,
+        signers: HashSet<Pubkey>,
+    }
+
     #[derive(Debug)]
     pub struct TestClient {
         pub accounts: Arc<Mutex<HashMap<Pubkey, AccountInfo>>>,
         pub admin_accounts: HashMap<Pubkey, bool>,
         pub action_signatures: HashMap<String, Vec<String>>,
         pub action_descriptions: HashMap<String, String>,
+        /// Keys allowed to appear as signers in `process_transaction`
+        signers: HashSet<Pubkey>,
         next_pubkey: u64,
+        /// Messages logged by successful `process_transaction` calls
+        logs: Arc<Mutex<Vec<String>>>,
     }
 
     impl TestClient {
@@ -356,7 +702,9 @@ pub mod test_utils {
                 admin_accounts: HashMap::new(),
                 action_signatures: HashMap::new(),
                 action_descriptions: HashMap::new(),
+                signers: HashSet::new(),
                 next_pubkey: 1,
+                logs: Arc::new(Mutex::new(Vec::new())),
             }
         }
 
@@ -374,10 +722,104 @@ pub mod test_utils {
             
             let mut accounts = self.accounts.lock().unwrap();
             accounts.insert(key, account.clone());
+            self.signers.insert(key);
             
             Ok(account)
         }
 
+        /// Create the program-owned account at the address derived from `seeds`
+        ///
+        /// Nobody holds a key for a derived address, so it is not registered as a signer.
+        pub fn create_derived_account(&self, seeds: &[&[u8]], program_id: Pubkey) -> Result<AccountInfo, ProgramError> {
+            let (key, _) = Pubkey::find_program_address(seeds, &program_id);
+            let mut accounts = self.accounts.lock().unwrap();
+            if accounts.contains_key(&key) {
+                return Err(ProgramError::AccountAlreadyInitialized);
+            }
+            let account = AccountInfo {
+                key,
+                is_signer: false,
+                is_writable: true,
+                lamports: Arc::new(RefCell::new(1000000)),
+                data: Arc::new(RefCell::new(Vec::new())),
+                owner: Arc::new(RefCell::new(program_id)),
+                utxo: UtxoMeta::from_slice(&[0; 36]),
+            };
+            accounts.insert(key, account.clone());
+            Ok(account)
+        }
+
+        /// The derived burn history account, allocated at `BurnHistory::LEN` zeroed bytes
+        /// as BuybackBurn expects
+        pub fn create_burn_history_account(&mut self, program_id: Pubkey) -> Result<AccountInfo, ProgramError> {
+            let account = self.create_derived_account(&[program_types::BURN_HISTORY_SEED], program_id)?;
+            *account.data.borrow_mut() = vec![0; program_types::BurnHistory::LEN];
+            Ok(account)
+        }
+
+        /// The spent-payment account for `payment_txid`, which BuybackBurn fills
+        ///
+        /// Created empty the first time, as the runtime passes in an address
+        /// that holds no account yet.
+        pub fn spent_payment_account(&self, program_id: Pubkey, payment_txid: &str) -> Result<AccountInfo, ProgramError> {
+            let txid = program_types::parse_payment_txid(payment_txid).map_err(program_types::mock_error)?;
+            let (key, _) = Pubkey::find_program_address(&[program_types::SPENT_PAYMENT_SEED, &txid], &program_id);
+            if let Some(account) = self.accounts.lock().unwrap().get(&key) {
+                return Ok(account.clone());
+            }
+            self.create_derived_account(&[program_types::SPENT_PAYMENT_SEED, &txid], program_id)
+        }
+
+        /// The system program account, at the key the `OVTInstruction` builders pass
+        pub fn create_system_program_account(&mut self) -> Result<AccountInfo, ProgramError> {
+            let key = Pubkey(::arch_program::pubkey::Pubkey::system_program().0);
+            let account = AccountInfo::new(key, false, false, 1, Vec::new(), Pubkey::new(), UtxoMeta::from_slice(&[0; 36]));
+            self.accounts.lock().unwrap().insert(key, account.clone());
+            Ok(account)
+        }
+
+        /// Allow `key` to sign transactions; accounts from `create_account` are registered already
+        pub fn register_signer(&mut self, key: Pubkey) {
+            self.signers.insert(key);
+        }
+
+        pub fn is_registered_signer(&self, key: &Pubkey) -> bool {
+            self.signers.contains(key)
+        }
+
+        /// Read-only clock sysvar account whose data is `unix_seconds` as a little-endian u64
+        pub fn create_clock_account(&mut self, unix_seconds: u64) -> Result<AccountInfo, ProgramError> {
+            let key = Pubkey::new_unique();
+            let account = AccountInfo {
+                key,
+                is_signer: false,
+                is_writable: false,
+                lamports: Arc::new(RefCell::new(1)),
+                data: Arc::new(RefCell::new(unix_seconds.to_le_bytes().to_vec())),
+                owner: Arc::new(RefCell::new(Pubkey::new())),
+                utxo: UtxoMeta::from_slice(&[0; 36]),
+            };
+
+            let mut accounts = self.accounts.lock().unwrap();
+            accounts.insert(key, account.clone());
+
+            Ok(account)
+        }
+
+        /// Move a clock account's time forward by `delta_secs`, returning the new time
+        pub fn advance_clock(&self, account: &Pubkey, delta_secs: u64) -> Result<u64, ProgramError> {
+            let accounts = self.accounts.lock().unwrap();
+            let account = accounts.get(account).ok_or(ProgramError::InvalidArgument)?;
+            let mut data = account.data.borrow_mut();
+            let current = data.get(..8)
+                .and_then(|bytes| bytes.try_into().ok())
+                .map(u64::from_le_bytes)
+                .ok_or(ProgramError::InvalidAccountData)?;
+            let advanced = current.checked_add(delta_secs).ok_or(ProgramError::ArithmeticOverflow)?;
+            data[..8].copy_from_slice(&advanced.to_le_bytes());
+            Ok(advanced)
+        }
+
         pub fn create_admin_account(&mut self, owner: Pubkey) -> Result<AccountInfo, ProgramError> {
             let account = self.create_account(owner)?;
             self.admin_accounts.insert(account.key, true);
@@ -425,6 +867,9 @@ pub mod test_utils {
             let account_map = self.accounts.lock().unwrap();
             
             for meta in account_metas {
+                if meta.is_signer && !self.is_registered_signer(&meta.pubkey) {
+                    return Err(ProgramError::MissingRequiredSignature);
+                }
                 if let Some(account) = account_map.get(&meta.pubkey) {
                     accounts.push(AccountInfo {
                         key: account.key,
@@ -440,14 +885,117 @@ pub mod test_utils {
                 }
             }
             
+            // Copy-on-write: a failing instruction must leave its accounts untouched
+            let saved: Vec<AccountSnapshot> = accounts.iter().map(AccountSnapshot::capture).collect();
+
             // Create a context for our mock program
             let ctx = ProgramContext {
                 accounts,
                 program_id,
+                logs: RefCell::new(Vec::new()),
             };
             
             // Call our mock implementation of process_instruction
-            program_types::process_instruction(&ctx, &instruction_data)
+            let result = program_types::process_instruction(&ctx, &instruction_data);
+            if result.is_err() {
+                for (account, snapshot) in ctx.accounts.iter().zip(&saved) {
+                    snapshot.restore_into(account);
+                }
+            } else {
+                self.logs.lock().unwrap().extend(ctx.logs.into_inner());
+            }
+            result
+        }
+
+        /// Run an instruction from the `OVTInstruction` builders, with `authority`
+        /// signing in place of the placeholder key the builders put at index 1
+        pub fn process_built_instruction(
+            &self,
+            instruction: &::arch_program::instruction::Instruction,
+            authority: Pubkey,
+        ) -> Result<(), ProgramError> {
+            let account_metas = instruction.accounts.iter().enumerate()
+                .map(|(i, meta)| AccountMeta {
+                    pubkey: if i == 1 { authority } else { Pubkey(meta.pubkey.0) },
+                    is_signer: meta.is_signer,
+                    is_writable: meta.is_writable,
+                })
+                .collect();
+            self.process_transaction(Pubkey(instruction.program_id.0), account_metas, instruction.data.clone())
+        }
+
+        /// Everything successful transactions have logged so far
+        pub fn logs(&self) -> Vec<String> {
+            self.logs.lock().unwrap().clone()
+        }
+
+        /// Run raw `instruction_data` through the program's real entrypoint,
+        /// writing account data and lamports back only if it succeeds
+        pub fn process_entrypoint(&self, program_id: Pubkey, account_metas: Vec<AccountMeta>, instruction_data: &[u8]) -> Result<(), ProgramError> {
+            use ::arch_program::pubkey::Pubkey as RealPubkey;
+            use std::rc::Rc;
+
+            let account_map = self.accounts.lock().unwrap();
+            let mut accounts = Vec::new();
+            for meta in &account_metas {
+                if meta.is_signer && !self.is_registered_signer(&meta.pubkey) {
+                    return Err(ProgramError::MissingRequiredSignature);
+                }
+                let account = account_map.get(&meta.pubkey).ok_or(ProgramError::InvalidArgument)?;
+                accounts.push(account.clone());
+            }
+
+            let keys: Vec<RealPubkey> = accounts.iter().map(|account| RealPubkey(account.key.0)).collect();
+            let owners: Vec<RealPubkey> = accounts.iter().map(|account| RealPubkey(account.owner.borrow().0)).collect();
+            let infos: Vec<::arch_program::account::AccountInfo> = accounts
+                .iter()
+                .zip(&account_metas)
+                .enumerate()
+                .map(|(i, (account, meta))| ::arch_program::account::AccountInfo {
+                    key: &keys[i],
+                    is_signer: meta.is_signer,
+                    is_writable: meta.is_writable,
+                    lamports: Rc::new(RefCell::new(*account.lamports.borrow())),
+                    data: Rc::new(RefCell::new(account.data.borrow().clone())),
+                    owner: &owners[i],
+                    executable: false,
+                    rent_epoch: 0,
+                })
+                .collect();
+
+            let program_key = RealPubkey(program_id.0);
+            ::program::process_instruction(&program_key, &infos, instruction_data)
+                .map_err(program_types::mock_error)?;
+
+            for (account, info) in accounts.iter().zip(&infos) {
+                *account.data.borrow_mut() = info.data.borrow().clone();
+                *account.lamports.borrow_mut() = *info.lamports.borrow();
+            }
+            Ok(())
+        }
+
+        /// Capture every account along with the admin and signer registrations
+        pub fn snapshot(&self) -> StateSnapshot {
+            let accounts = self.accounts.lock().unwrap();
+            StateSnapshot {
+                accounts: accounts.iter().map(|(key, account)| (*key, AccountSnapshot::capture(account))).collect(),
+                admin_accounts: self.admin_accounts.clone(),
+                signers: self.signers.clone(),
+            }
+        }
+
+        /// Return to `snapshot`: accounts are restored in place, so handles to
+        /// them stay valid, and accounts created since are dropped
+        pub fn restore(&mut self, snapshot: &StateSnapshot) {
+            let mut accounts = self.accounts.lock().unwrap();
+            accounts.retain(|key, _| snapshot.accounts.contains_key(key));
+            for (key, account) in accounts.iter_mut() {
+                let saved = &snapshot.accounts[key];
+                saved.restore_into(account);
+                account.utxo = saved.utxo;
+            }
+            self.admin_accounts = snapshot.admin_accounts.clone();
+            self.signers = snapshot.signers.clone();
         }
 
         pub fn get_account_data<T: BorshDeserialize>(&self, key: &Pubkey) -> Result<T, ProgramError> {
@@ -455,7 +1003,8 @@ pub mod test_utils {
             let account = accounts.get(key).ok_or(ProgramError::InvalidArgument)?;
             let data = account.data.borrow();
             
-            T::try_from_slice(&data).map_err(|_| ProgramError::InvalidAccountData)
+            // Accounts are zero-padded past their encoding
+            T::deserialize(&mut &data[..]).map_err(|_| ProgramError::InvalidAccountData)
         }
 
         pub fn create_utxo(&self, txid: [u8; 32], vout: u32) -> UtxoMeta {
@@ -500,6 +1049,10 @@ pub fn process_transaction(
     accounts: &[Pubkey],
     signers: &[Pubkey],
 ) -> Result<(), ProgramError> {
+    if signers.iter().any(|signer| !client.is_registered_signer(signer)) {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
     let accounts_map = client.accounts.lock().unwrap();
     let mut account_infos: Vec<AccountInfo> = Vec::new();
 
@@ -517,10 +1070,18 @@ pub fn process_transaction(
         }
     }
 
+    let saved: Vec<test_utils::AccountSnapshot> = account_infos.iter().map(test_utils::AccountSnapshot::capture).collect();
     let ctx = ProgramContext {
         accounts: account_infos,
         program_id: accounts[0],
+        logs: RefCell::new(Vec::new()),
     };
 
-    program_types::process_instruction(&ctx, instruction_data)
+    let result = program_types::process_instruction(&ctx, instruction_data);
+    if result.is_err() {
+        for (account, snapshot) in ctx.accounts.iter().zip(&saved) {
+            snapshot.restore_into(account);
+        }
+    }
+    result
 } 
\ No newline at end of file
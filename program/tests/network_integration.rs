@@ -1,7 +1,28 @@
+use arch_program::program_error::ProgramError;
 use arch_program::pubkey::Pubkey;
-use program::OVTProgram;
+use bitcoin::{Amount, ScriptBuf, Txid, TxOut};
+use bitcoin::hashes::Hash;
+use program::bitcoin::mock::{MockBitcoinNode, MockBitcoinRpcClient};
+use program::bitcoin::rpc::BitcoinRpcConfig;
+use program::bitcoin::utxo::{TreasuryPayment, VerifiedTreasuryPayment};
+use program::bitcoin::{PaymentIntent, PaymentMemo, UtxoMeta, UtxoStatus};
+use program::error::OVTError;
+use program::program_ids::ProgramIds;
+use program::ovt_client::{ComponentHealth, OvtClient, OvtClientError};
 use program::state::NetworkStatus;
-use program::bitcoin::BitcoinRpcConfig;
+use program::{OVTInstruction, OVTState};
+use std::sync::Arc;
+
+/// A mock node ten blocks tall, holding confirmed outputs of txids `[1; 32]` and `[2; 32]`
+fn mock_node() -> Arc<MockBitcoinNode> {
+    let node = Arc::new(MockBitcoinNode::new());
+    node.mine_blocks(10);
+    for txid in [[1u8; 32], [2u8; 32]] {
+        let output = TxOut { value: Amount::from_sat(50_000), script_pubkey: ScriptBuf::new() };
+        node.add_transaction(&Txid::from_byte_array(txid).to_string(), 3, vec![output], true);
+    }
+    node
+}
 
 #[cfg(test)]
 mod tests {
@@ -9,7 +30,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_network_state_persistence() {
-        let program = OVTProgram::new();
+        let program = OvtClient::mock(mock_node());
         let test_key = b"test_key";
         let test_value = b"test_value";
         
@@ -30,7 +51,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_network_utxo_verification() {
-        let program = OVTProgram::new();
+        let program = OvtClient::mock(mock_node());
         
         // Create test transaction
         let test_txid = [1u8; 32];
@@ -46,7 +67,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_network_error_handling() {
-        let program = OVTProgram::new();
+        let program = OvtClient::mock(mock_node());
         
         // Test invalid UTXO
         let invalid_txid = [0u8; 32];
@@ -73,4 +94,98 @@ mod tests {
         let result = program.verify_utxo(&test_txid, test_vout, &owner).await;
         assert!(result.is_err(), "Should fail after reorg");
     }
+
+    /// The secp256k1 generator, a valid compressed key
+    const TREASURY_PUBKEY: &str = "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798";
+
+    fn live_state() -> OVTState {
+        let mut state = OVTState::new(hex::decode(TREASURY_PUBKEY).unwrap().try_into().unwrap());
+        state.nav_sats = 100_000_000;
+        state.total_supply = 1_000_000;
+        state.network_status = NetworkStatus::Active;
+        state.nonce = 3;
+        state
+    }
+
+    #[tokio::test]
+    async fn test_facade_reads_and_submits() {
+        let program = OvtClient::mock_with_state(mock_node(), live_state());
+        assert_eq!(program.get_state().await.unwrap(), live_state());
+        assert_eq!(program.get_nav().await.unwrap(), 100_000_000);
+
+        program.submit_nav_update(110_000_000).await.unwrap();
+        let txid = "ab".repeat(32);
+        let mut payment =
+            TreasuryPayment::new(txid.clone(), Amount::from_sat(10_000_000), UtxoMeta::new(txid, 0, 10_000_000)).unwrap();
+        payment.block_height = Some(868_000);
+        let memo = PaymentMemo { payer_id: [0x42; 16], intent: PaymentIntent::Buyback, nonce: 1 };
+        let verified = VerifiedTreasuryPayment { outpoints: Vec::new(), memo: Some(memo) };
+        program.submit_buyback(&payment, &verified).await.unwrap();
+        let sent: Vec<OVTInstruction> = program
+            .sent_transactions()
+            .iter()
+            .map(|transaction| borsh::from_slice(&transaction.message.instructions[0].data).unwrap())
+            .collect();
+        assert!(matches!(
+            sent[..],
+            [
+                OVTInstruction::UpdateNAV { btc_price_sats: 110_000_000, expected_nonce: 3 },
+                OVTInstruction::BuybackBurn { payment_amount_sats: 10_000_000, payment_height: 868_000, memo: Some(sent_memo), expected_nonce: 3, .. },
+            ] if sent_memo == memo
+        ));
+        // Each one for the program, with the accounts the builders give it and a signature from its signer
+        for transaction in program.sent_transactions() {
+            let [instruction] = &transaction.message.instructions[..] else { panic!("{:?}", transaction) };
+            assert_eq!(instruction.program_id, ProgramIds::default().ovt);
+            assert_eq!(instruction.accounts[1].pubkey, transaction.message.signers[0]);
+            assert_eq!(transaction.signatures.len(), 1);
+        }
+
+        // What the program would reject never leaves the client
+        let result = program.submit_nav_update(0).await;
+        assert!(matches!(
+            result,
+            Err(OvtClientError::Program(ProgramError::Custom(code))) if code == OVTError::InvalidNAVUpdate.code()
+        ));
+        assert_eq!(program.sent_transactions().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_facade_treasury_utxos_and_health() {
+        let node = mock_node();
+        let program = OvtClient::mock_with_state(node.clone(), live_state());
+        let treasury_script = live_state().treasury_script_pubkey().unwrap();
+        for (txid, confirmations) in [([3u8; 32], 6), ([4u8; 32], 0)] {
+            let output = TxOut { value: Amount::from_sat(25_000), script_pubkey: treasury_script.clone() };
+            node.add_transaction(&Txid::from_byte_array(txid).to_string(), confirmations, vec![output], true);
+        }
+
+        // Only the treasury's outputs, not the other ones on the node
+        let mut utxos = program.treasury_utxos().await.unwrap();
+        utxos.sort_by_key(|(_, status)| *status != UtxoStatus::Active);
+        let statuses: Vec<UtxoStatus> = utxos.iter().map(|(_, status)| *status).collect();
+        assert_eq!(statuses, vec![UtxoStatus::Active, UtxoStatus::Pending]);
+        assert!(utxos.iter().all(|(utxo, _)| utxo.amount_sats == 25_000));
+
+        let health = program.health().await;
+        assert_eq!(health.bitcoin, ComponentHealth::Reachable { height: 10 });
+        assert_eq!(health.runes, ComponentHealth::Reachable { height: 0 });
+        assert!(health.is_healthy());
+    }
+
+    #[tokio::test]
+    async fn test_facade_maps_unreachable_arch_node() {
+        let rpc = MockBitcoinRpcClient::new(BitcoinRpcConfig::regtest(), mock_node());
+        let program = OvtClient::new(Arc::new(rpc), "http://127.0.0.1:9", Pubkey::new_unique());
+        let network_error = OVTError::NetworkError.code();
+        for result in [program.get_nav().await.map(drop), program.submit_nav_update(1).await.map(drop)] {
+            assert!(matches!(result, Err(OvtClientError::Program(ProgramError::Custom(code))) if code == network_error));
+        }
+        assert!(matches!(program.treasury_utxos().await, Err(OvtClientError::Program(_))));
+
+        // No runes API to report on doesn't make the client unhealthy
+        let health = program.health().await;
+        assert_eq!(health.runes, ComponentHealth::NotConfigured);
+        assert!(health.is_healthy());
+    }
 }
@@ -0,0 +1,33 @@
+/// `OVT_PROGRAM_ID` overrides
+///
+/// Kept in its own test binary, with a single test, since it changes the
+/// process environment the other tests read the program id from.
+use arch_program::program_error::ProgramError;
+use arch_program::pubkey::Pubkey;
+use program::address::state_address;
+use program::program_ids::{ProgramIds, DEFAULT_OVT_PROGRAM_ID, PROGRAM_ID_ENV_VAR};
+use program::OVTInstruction;
+
+#[test]
+fn test_env_override() {
+    std::env::remove_var(PROGRAM_ID_ENV_VAR);
+    assert_eq!(ProgramIds::current().unwrap(), ProgramIds::default());
+
+    let staging = Pubkey([0x5a; 32]);
+    std::env::set_var(PROGRAM_ID_ENV_VAR, "5a".repeat(32));
+    let ids = ProgramIds::current().unwrap();
+    assert_eq!(ids.ovt, staging);
+    let instruction = OVTInstruction::update_nav(&ids.ovt, 1_000_000, 0);
+    assert_eq!((instruction.program_id, instruction.accounts[0].pubkey), (staging, state_address(&staging)));
+    // The compiled-in id is now the stranger
+    assert!(ids.check(&staging).is_ok());
+    assert!(matches!(ids.check(&DEFAULT_OVT_PROGRAM_ID), Err(ProgramError::IncorrectProgramId)));
+    // The entrypoint only knows the id it was built with
+    assert_eq!(ProgramIds::default().ovt, DEFAULT_OVT_PROGRAM_ID);
+
+    std::env::set_var(PROGRAM_ID_ENV_VAR, "not a program id");
+    assert!(matches!(ProgramIds::current(), Err(ProgramError::InvalidArgument)));
+
+    std::env::remove_var(PROGRAM_ID_ENV_VAR);
+    assert_eq!(ProgramIds::current().unwrap().ovt, DEFAULT_OVT_PROGRAM_ID);
+}
@@ -0,0 +1,189 @@
+/// Tests for the HTTP backend of the runes client
+///
+/// Each test mocks a different path on the shared mockito server.
+use bitcoin::{absolute::LockTime, transaction::Version, Amount, ScriptBuf, Transaction, TxOut};
+use mockito::{mock, server_url, Matcher};
+use program::runes_client::{
+    Pagination, PositionFilter, PositionStatus, PositionType, RuneBalance, RuneSupply, RunesClient, RunesError,
+};
+
+/// `user:pass`, base64-encoded
+const BASIC_AUTH: &str = "Basic dXNlcjpwYXNz";
+
+fn client() -> RunesClient {
+    RunesClient::new(
+        bitcoin::Network::Regtest,
+        server_url(),
+        Some(("user".to_string(), "pass".to_string())),
+    )
+}
+
+#[tokio::test]
+async fn test_get_position() {
+    let _position = mock("GET", "/position/Test%20Project")
+        .match_header("authorization", BASIC_AUTH)
+        .match_header("accept", "application/json")
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(r#"{
+            "name": "Test Project",
+            "amount": 2500000,
+            "price_per_token": 120,
+            "currency_spent": 300000000,
+            "transaction_id": "0202020202020202020202020202020202020202020202020202020202020202",
+            "safe_inscription_id": null,
+            "entry_timestamp": 1700000000,
+            "position_type": "pre_tge",
+            "status": "pending"
+        }"#)
+        .create();
+
+    let position = client().get_position("Test Project").await.unwrap();
+    assert_eq!(position.name, "Test Project");
+    assert_eq!(position.amount, 2_500_000);
+    assert_eq!(position.currency_spent, 300_000_000);
+    assert_eq!(position.safe_inscription_id, None);
+    assert_eq!(position.position_type, PositionType::PreTGE);
+    assert_eq!(position.status, PositionStatus::Pending);
+}
+
+#[tokio::test]
+async fn test_list_positions_query() {
+    let _positions = mock("GET", "/positions")
+        .match_query(Matcher::AllOf(vec![
+            Matcher::UrlEncoded("type".into(), "post_tge".into()),
+            Matcher::UrlEncoded("status".into(), "active".into()),
+            Matcher::UrlEncoded("name".into(), "Test".into()),
+            Matcher::UrlEncoded("cursor".into(), "Alpha".into()),
+            Matcher::UrlEncoded("limit".into(), "2".into()),
+        ]))
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(r#"{
+            "items": [{
+                "name": "Test Project",
+                "amount": 1000,
+                "price_per_token": 100,
+                "currency_spent": 100000,
+                "transaction_id": null,
+                "safe_inscription_id": null,
+                "entry_timestamp": 1700000000,
+                "position_type": "post_tge",
+                "status": "active"
+            }],
+            "total": 3,
+            "next_cursor": "Test Project"
+        }"#)
+        .create();
+
+    let filter = PositionFilter {
+        position_type: Some(PositionType::PostTGE),
+        status: Some(PositionStatus::Active),
+        name_contains: Some("Test".to_string()),
+    };
+    let page = client()
+        .list_positions(filter, Pagination { cursor: Some("Alpha".to_string()), limit: 2 })
+        .await
+        .unwrap();
+    assert_eq!(page.items.len(), 1);
+    assert_eq!(page.total, 3);
+    assert_eq!(page.next_cursor.as_deref(), Some("Test Project"));
+}
+
+#[tokio::test]
+async fn test_get_rune_balance() {
+    let _address = mock("GET", "/address/bcrt1qtreasury")
+        .match_header("authorization", BASIC_AUTH)
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(r#"{
+            "outputs": [],
+            "inscriptions": [],
+            "sat_balance": 10000,
+            "runes_balances": [["OTORI•VISION•TOKEN", "1500.5", "O"], ["OTHER•RUNE", "7", null]]
+        }"#)
+        .create();
+
+    let balances = client().get_rune_balance("bcrt1qtreasury").await.unwrap();
+    assert_eq!(balances, vec![
+        RuneBalance { rune: "OTORI•VISION•TOKEN".to_string(), amount: "1500.5".to_string(), symbol: Some('O') },
+        RuneBalance { rune: "OTHER•RUNE".to_string(), amount: "7".to_string(), symbol: None },
+    ]);
+}
+
+#[tokio::test]
+async fn test_get_rune_supply() {
+    let _rune = mock("GET", "/rune/840000:1")
+        .match_header("accept", "application/json")
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(r#"{
+            "entry": {
+                "block": 840000,
+                "burned": 2500,
+                "divisibility": 2,
+                "mints": 30,
+                "number": 7,
+                "premine": 100000,
+                "spaced_rune": "OTORI•VISION•TOKEN",
+                "symbol": "O",
+                "terms": {"amount": 1000, "cap": 21000, "height": [null, null], "offset": [null, null]},
+                "turbo": false
+            },
+            "id": "840000:1",
+            "mintable": true
+        }"#)
+        .create();
+
+    let supply = client().get_rune_supply("840000:1").await.unwrap();
+    assert_eq!(supply, RuneSupply { minted: 130_000, burned: 2_500, circulating: 127_500 });
+}
+
+#[tokio::test]
+async fn test_send_transaction_posts_hex() {
+    let tx = Transaction {
+        version: Version::TWO,
+        lock_time: LockTime::ZERO,
+        input: vec![],
+        output: vec![TxOut { value: Amount::from_sat(50_000), script_pubkey: ScriptBuf::new() }],
+    };
+    let txid = tx.compute_txid().to_string();
+
+    let _broadcast = mock("POST", "/tx")
+        .match_header("authorization", BASIC_AUTH)
+        .match_body(Matcher::Exact(bitcoin::consensus::encode::serialize_hex(&tx)))
+        .with_status(200)
+        .with_body(format!("{}\n", txid))
+        .create();
+
+    assert_eq!(client().send_transaction(tx).await.unwrap(), txid);
+}
+
+#[tokio::test]
+async fn test_http_errors_map_to_rpc_errors() {
+    let _missing = mock("GET", "/position/Unknown")
+        .with_status(404)
+        .create();
+    let _garbled = mock("GET", "/address/bcrt1qgarbled")
+        .with_status(200)
+        .with_body("not json")
+        .create();
+
+    // Separate clients, so the first failures don't open the second call's circuit breaker
+    assert!(matches!(client().get_position("Unknown").await, Err(RunesError::BitcoinRPC(_))));
+    assert!(matches!(client().get_rune_balance("bcrt1qgarbled").await, Err(RunesError::BitcoinRPC(_))));
+
+    // Nothing listens on port 9
+    let offline = RunesClient::new(bitcoin::Network::Regtest, "http://127.0.0.1:9".to_string(), None);
+    assert!(matches!(offline.get_position("Test Project").await, Err(RunesError::BitcoinRPC(_))));
+}
+
+#[tokio::test]
+async fn test_mock_mode_skips_the_network() {
+    let client = RunesClient::new(bitcoin::Network::Regtest, "http://127.0.0.1:9".to_string(), None)
+        .with_mock_mode(true);
+
+    let position = client.get_position("Anything").await.unwrap();
+    assert_eq!(position.name, "Anything");
+    assert_eq!(client.get_rune_balance("bcrt1qtreasury").await.unwrap().len(), 1);
+}
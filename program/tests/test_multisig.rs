@@ -10,7 +10,7 @@ use bitcoin::{PublicKey, Transaction, TxIn, TxOut};
 use bitcoin::hashes::Hash as BitcoinHash;
 use bitcoin::hashes::sha256;
 // Import directly from the runes_client.rs file
-use program::runes_client::{RunesClient, PortfolioPosition, PositionType, PositionStatus};
+use program::runes_client::{AdminSet, RunesClient, RunesError, PortfolioPosition, PositionType, PositionStatus};
 use bitcoin::key::rand;
 use bitcoin::Amount;
 
@@ -23,13 +23,6 @@ use bitcoin::Amount;
 /// - Portfolio position management
 #[tokio::test]
 async fn test_multisig_flow() {
-    // Initialize client with Bitcoin network configuration
-    let client = RunesClient::new(
-        bitcoin::Network::Regtest,
-        "http://localhost:8332".to_string(),
-        None,
-    );
-
     // Generate 5 admin keypairs
     let secp = Secp256k1::new();
     let mut admin_pubkeys = Vec::new();
@@ -41,6 +34,15 @@ async fn test_multisig_flow() {
         admin_privkeys.push(privkey);
     }
 
+    // Initialize client with Bitcoin network configuration and a 3-of-5 admin set
+    let client = RunesClient::new(
+        bitcoin::Network::Regtest,
+        "http://localhost:8332".to_string(),
+        None,
+    )
+    .with_mock_mode(true)
+    .with_admin_set(AdminSet::new(admin_pubkeys.clone(), 3).unwrap());
+
     // Create a test Bitcoin transaction
     let test_txid = bitcoin::Txid::from_slice(&[1u8; 32]).unwrap();
     let test_vout = 0u32;
@@ -75,7 +77,6 @@ async fn test_multisig_flow() {
     let mint_result = client.mint_tokens(
         amount,
         signatures.clone(),
-        admin_pubkeys.clone(),
     ).await;
     assert!(mint_result.is_ok(), "Token minting should succeed");
 
@@ -90,12 +91,12 @@ async fn test_multisig_flow() {
         entry_timestamp: 1677649200,
         position_type: PositionType::PostTGE,
         status: PositionStatus::Active,
+        exit_details: None,
     };
 
     let add_position_result = client.add_post_tge_position(
         position.clone(),
         &signatures,
-        &admin_pubkeys,
     ).await;
     assert!(add_position_result.is_ok(), "Position addition should succeed");
 
@@ -109,19 +110,20 @@ async fn test_multisig_flow() {
     let invalid_result = client.mint_tokens(
         amount,
         insufficient_sigs,
-        admin_pubkeys.clone(),
     ).await;
     assert!(invalid_result.is_err(), "Should fail with insufficient signatures");
 
-    // Test with invalid admin keys
-    let mut invalid_pubkeys = admin_pubkeys.clone();
-    invalid_pubkeys.pop();
-    let invalid_keys_result = client.mint_tokens(
+    // Test without an admin set configured
+    let unconfigured = RunesClient::new(
+        bitcoin::Network::Regtest,
+        "http://localhost:8332".to_string(),
+        None,
+    ).with_mock_mode(true);
+    let invalid_keys_result = unconfigured.mint_tokens(
         amount,
         signatures,
-        invalid_pubkeys,
     ).await;
-    assert!(invalid_keys_result.is_err(), "Should fail with invalid admin keys");
+    assert!(matches!(invalid_keys_result, Err(RunesError::InvalidAdminKeys)), "Should fail with invalid admin keys");
 }
 
 /// Test real signature verification with Bitcoin transactions
@@ -132,12 +134,6 @@ async fn test_multisig_flow() {
 /// - Admin multisig requirements
 #[tokio::test]
 async fn test_real_signatures() {
-    let client = RunesClient::new(
-        bitcoin::Network::Regtest,
-        "http://localhost:8332".to_string(),
-        None,
-    );
-
     // Generate admin keys and messages
     let secp = Secp256k1::new();
     let message = b"Test mint 1000000 OVT";
@@ -181,11 +177,18 @@ async fn test_real_signatures() {
         }
     }
 
+    let client = RunesClient::new(
+        bitcoin::Network::Regtest,
+        "http://localhost:8332".to_string(),
+        None,
+    )
+    .with_mock_mode(true)
+    .with_admin_set(AdminSet::new(admin_pubkeys, 3).unwrap());
+
     // Verify multisig with transaction
     let result = client.verify_admin_multisig(
         &signatures,
         message,
-        &admin_pubkeys,
     ).await;
     assert!(result.is_ok(), "Multisig verification should succeed");
     assert!(result.unwrap(), "Multisig should be valid");
@@ -194,7 +197,6 @@ async fn test_real_signatures() {
     let tx_sign_result = client.sign_transaction(
         &test_tx,
         &signatures[0..3],
-        &admin_pubkeys[0..3],
     ).await;
     assert!(tx_sign_result.is_ok(), "Transaction signing should succeed");
 } 
\ No newline at end of file
@@ -4,18 +4,17 @@ use program::bitcoin::{
     BitcoinRpcConfig, 
     UtxoMeta, 
     UtxoStatus,
-    UtxoCacheConfig,
     BitcoinRpcError,
 };
 use bitcoin::{Transaction, TxOut, Amount, ScriptBuf};
 use std::time::Duration;
-use std::num::NonZeroUsize;
 use tokio::time::sleep;
 use futures::future;
 use std::sync::Arc;
 
 // Import mock implementation
-use program::bitcoin::mock::{MockBitcoinNode, MockBitcoinRpcClient};
+use program::bitcoin::cache::UtxoCacheBuilder;
+use program::bitcoin::mock::{MockBitcoinNode, MockBitcoinRpcClient, MockUtxoState, Outspend, UNKNOWN_SPENDER};
 
 // Helper to create a test mock client
 fn setup_mock_client() -> (Arc<MockBitcoinNode>, MockBitcoinRpcClient) {
@@ -25,6 +24,7 @@ fn setup_mock_client() -> (Arc<MockBitcoinNode>, MockBitcoinRpcClient) {
         port: 0,
         username: "mock".to_string(),
         password: "mock".to_string(),
+        ..Default::default()
     };
     let client = MockBitcoinRpcClient::new(config, node.clone());
     (node, client)
@@ -56,6 +56,48 @@ async fn test_utxo_lifecycle() {
     assert_eq!(status, UtxoStatus::Spent, "UTXO should be marked as spent");
 }
 
+#[tokio::test]
+async fn test_status_of_missing_output_is_invalid() {
+    let (node, client) = setup_mock_client();
+    let txid = "a000000000000000000000000000000000000000000000000000000000000000";
+    let outputs = vec![
+        TxOut { value: Amount::from_sat(10_000), script_pubkey: ScriptBuf::new() },
+        TxOut { value: Amount::from_sat(20_000), script_pubkey: ScriptBuf::new() },
+    ];
+    node.add_transaction(txid, 6, outputs, true);
+
+    assert_eq!(client.get_utxo_status(&UtxoMeta::new(txid.to_string(), 1, 20_000)).await.unwrap(), UtxoStatus::Active);
+    assert_eq!(client.get_utxo_status(&UtxoMeta::new(txid.to_string(), 5, 20_000)).await.unwrap(), UtxoStatus::Invalid);
+    // Even when marked spent, an output the transaction doesn't have isn't a UTXO
+    node.spend_utxo(txid, 2);
+    assert_eq!(client.get_utxo_status(&UtxoMeta::new(txid.to_string(), 2, 20_000)).await.unwrap(), UtxoStatus::Invalid);
+    assert!(node.output(txid, 2).is_none());
+}
+
+#[tokio::test]
+async fn test_strict_outputs_check_amount_and_script() {
+    let (node, client) = setup_mock_client();
+    let txid = "a000000000000000000000000000000000000000000000000000000000000000";
+    let script = ScriptBuf::from_hex("0014ab00000000000000000000000000000000000000").unwrap();
+    node.add_transaction(txid, 6, vec![TxOut { value: Amount::from_sat(10_000), script_pubkey: script.clone() }], true);
+    let wrong_amount = UtxoMeta::new(txid.to_string(), 0, 9_999);
+
+    // By default only the outpoint is looked at
+    assert_eq!(client.get_utxo_status(&wrong_amount).await.unwrap(), UtxoStatus::Active);
+
+    let client = client.with_strict_outputs();
+    assert_eq!(client.get_utxo_status(&wrong_amount).await.unwrap(), UtxoStatus::Invalid);
+    // A UTXO without a script is only checked by amount
+    assert_eq!(client.get_utxo_status(&UtxoMeta::new(txid.to_string(), 0, 10_000)).await.unwrap(), UtxoStatus::Active);
+
+    let stored = node.utxo_meta(txid, 0).unwrap();
+    assert_eq!((stored.amount_sats, stored.script_pubkey.as_str()), (10_000, "0014ab00000000000000000000000000000000000000"));
+    assert_eq!(client.get_utxo_status(&stored).await.unwrap(), UtxoStatus::Active);
+    let mut other_script = stored.clone();
+    other_script.script_pubkey = "0014cd00000000000000000000000000000000000000".to_string();
+    assert_eq!(client.get_utxo_status(&other_script).await.unwrap(), UtxoStatus::Invalid);
+}
+
 #[tokio::test]
 async fn test_network_errors() {
     let (node, client) = setup_mock_client();
@@ -85,44 +127,64 @@ async fn test_network_errors() {
 #[tokio::test]
 async fn test_reorg_handling() {
     let (node, client) = setup_mock_client();
+    node.mine_blocks(10);
     
     // Create test UTXOs
-    let txid1 = "b000000000000000000000000000000000000000000000000000000000000000";
-    let txid2 = "c000000000000000000000000000000000000000000000000000000000000000";
+    let deep = "b000000000000000000000000000000000000000000000000000000000000000";
+    let shallow = "c000000000000000000000000000000000000000000000000000000000000000";
+    let tip_tx = "d000000000000000000000000000000000000000000000000000000000000000";
     
     let outputs = vec![TxOut {
         value: Amount::from_sat(10000),
         script_pubkey: ScriptBuf::new(),
     }];
     
-    // Add transactions with 6 confirmations
-    node.add_transaction(txid1, 6, outputs.clone(), true);
-    node.add_transaction(txid2, 6, outputs.clone(), true);
+    // Confirmed in blocks 5, 9 and 10 of a 10-block chain
+    node.add_transaction(deep, 6, outputs.clone(), true);
+    node.add_transaction(shallow, 2, outputs.clone(), true);
+    node.add_transaction(tip_tx, 1, outputs.clone(), true);
     
-    let utxo1 = UtxoMeta::new(txid1.to_string(), 0, 10000);
-    let utxo2 = UtxoMeta::new(txid2.to_string(), 1, 20000);
+    let deep_utxo = UtxoMeta::new(deep.to_string(), 0, 10000);
+    let shallow_utxo = UtxoMeta::new(shallow.to_string(), 0, 10000);
+    let tip_utxo = UtxoMeta::new(tip_tx.to_string(), 0, 10000);
 
     // Verify initial active status
-    let status1 = client.get_utxo_status(&utxo1).await.unwrap();
-    let status2 = client.get_utxo_status(&utxo2).await.unwrap();
-    assert_eq!(status1, UtxoStatus::Active);
-    assert_eq!(status2, UtxoStatus::Active);
-
-    // Simulate reorg by marking transactions as invalid
-    node.add_transaction(txid1, 0, outputs.clone(), false);
-    node.add_transaction(txid2, 0, outputs.clone(), false);
-
-    // Verify status changes
-    let new_status1 = client.get_utxo_status(&utxo1).await.unwrap();
-    let new_status2 = client.get_utxo_status(&utxo2).await.unwrap();
-    assert_eq!(new_status1, UtxoStatus::Invalid);
-    assert_eq!(new_status2, UtxoStatus::Invalid);
+    for utxo in [&deep_utxo, &shallow_utxo, &tip_utxo] {
+        assert_eq!(client.get_utxo_status(utxo).await.unwrap(), UtxoStatus::Active);
+    }
+    let old_tip = client.get_best_block_hash().await.unwrap();
+
+    // A 3-block reorg drops blocks 8-10 and everything confirmed in them
+    node.simulate_reorg(3);
+    assert_eq!(client.get_block_height().await.unwrap(), 7);
+    assert_ne!(client.get_best_block_hash().await.unwrap(), old_tip);
+    assert_eq!(client.get_utxo_status(&shallow_utxo).await.unwrap(), UtxoStatus::Invalid);
+    assert_eq!(client.get_utxo_status(&tip_utxo).await.unwrap(), UtxoStatus::Invalid);
+
+    // The deep transaction survives with its confirmations recounted
+    assert_eq!(client.get_utxo_status(&deep_utxo).await.unwrap(), UtxoStatus::Active);
+    assert_eq!(client.get_confirmations(deep).await.unwrap(), 3);
+
+    // The new chain grows on, and a reorg can carry transactions over
+    let replayed = "e000000000000000000000000000000000000000000000000000000000000000";
+    node.add_transaction(replayed, 0, outputs.clone(), true);
+    node.mine_blocks(2);
+    assert_eq!(client.get_confirmations(replayed).await.unwrap(), 2);
+    let fork_tip = client.get_best_block_hash().await.unwrap();
+
+    node.simulate_reorg_with(2, &[replayed]);
+    assert_ne!(client.get_best_block_hash().await.unwrap(), fork_tip);
+    assert_eq!(client.get_confirmations(replayed).await.unwrap(), 1);
+    assert_eq!(client.get_utxo_status(&UtxoMeta::new(replayed.to_string(), 0, 10000)).await.unwrap(), UtxoStatus::Active);
+    assert_eq!(client.get_confirmations(deep).await.unwrap(), 3);
 }
 
 #[tokio::test]
 async fn test_concurrent_access() {
     let (node, client) = setup_mock_client();
-    let client = Arc::new(client);
+    // Slow enough that all lookups overlap the first upstream call
+    let client = Arc::new(client.with_latency(Duration::from_millis(50)));
+    let cache = Arc::new(UtxoCacheBuilder::new().with_max_size(100).build().unwrap());
 
     // Create test UTXO
     let txid = "a000000000000000000000000000000000000000000000000000000000000000";
@@ -140,9 +202,10 @@ async fn test_concurrent_access() {
     let mut handles = vec![];
     for _ in 0..10 {
         let client = client.clone();
+        let cache = cache.clone();
         let utxo = utxo.clone();
         handles.push(tokio::spawn(async move {
-            client.get_utxo_status(&utxo).await.unwrap()
+            cache.get_utxo_status(client.as_ref(), &utxo).await.unwrap()
         }));
     }
 
@@ -152,4 +215,214 @@ async fn test_concurrent_access() {
     for result in &results[1..] {
         assert_eq!(result.as_ref().unwrap(), first_status, "All concurrent accesses should return same status");
     }
-} 
\ No newline at end of file
+
+    // The cold key was fetched from the node exactly once
+    assert_eq!(client.utxo_status_calls(), 1);
+    let stats = cache.get_stats().await;
+    assert_eq!(stats.misses, 1);
+    assert_eq!(stats.hits, 9);
+}
+
+#[tokio::test]
+async fn test_mine_blocks_confirms_pending_utxo() {
+    let (node, client) = setup_mock_client();
+    let outputs = vec![TxOut {
+        value: Amount::from_sat(10000),
+        script_pubkey: ScriptBuf::new(),
+    }];
+    let txid = "a000000000000000000000000000000000000000000000000000000000000000";
+    let held = "b000000000000000000000000000000000000000000000000000000000000000";
+    let reorged = "c000000000000000000000000000000000000000000000000000000000000000";
+    node.add_transaction(txid, 0, outputs.clone(), true);
+    node.add_transaction(held, 0, outputs.clone(), true);
+    node.set_in_next_block(held, false);
+    node.add_transaction(reorged, 2, outputs, false);
+
+    let utxo = UtxoMeta::new(txid.to_string(), 0, 10000);
+    assert_eq!(client.get_utxo_status(&utxo).await.unwrap(), UtxoStatus::Pending);
+    let genesis = client.get_best_block_hash().await.unwrap();
+    assert_eq!(client.get_block_height().await.unwrap(), 0);
+
+    node.mine_blocks(6);
+    assert_eq!(client.get_utxo_status(&utxo).await.unwrap(), UtxoStatus::Active);
+    assert_eq!(client.get_confirmations(txid).await.unwrap(), 6);
+
+    // Held transactions stay in the mempool and invalid ones are never mined
+    assert_eq!(client.get_confirmations(held).await.unwrap(), 0);
+    assert_eq!(client.get_confirmations(reorged).await.unwrap(), 2);
+
+    assert_eq!(client.get_block_height().await.unwrap(), 6);
+    let tip = client.get_best_block_hash().await.unwrap();
+    assert_ne!(tip, genesis);
+    node.mine_blocks(1);
+    assert_ne!(client.get_best_block_hash().await.unwrap(), tip);
+    assert_eq!(client.get_confirmations(txid).await.unwrap(), 7);
+}
+
+#[tokio::test]
+async fn test_wait_for_confirmations() {
+    let (node, client) = setup_mock_client();
+    let txid = "a000000000000000000000000000000000000000000000000000000000000000";
+    node.add_transaction(txid, 0, vec![TxOut {
+        value: Amount::from_sat(10000),
+        script_pubkey: ScriptBuf::new(),
+    }], true);
+
+    // Mine a block every 20ms from another task
+    let miner = {
+        let node = node.clone();
+        tokio::spawn(async move {
+            for _ in 0..3 {
+                sleep(Duration::from_millis(20)).await;
+                node.mine_blocks(1);
+            }
+        })
+    };
+
+    let confirmations = client
+        .wait_for_confirmations(txid, 3, Duration::from_millis(5), Duration::from_secs(2))
+        .await
+        .unwrap();
+    assert_eq!(confirmations, 3);
+    miner.await.unwrap();
+}
+
+#[tokio::test]
+async fn test_wait_for_confirmations_timeout() {
+    let (node, client) = setup_mock_client();
+    let txid = "a000000000000000000000000000000000000000000000000000000000000000";
+    node.add_transaction(txid, 1, vec![], true);
+
+    let result = client
+        .wait_for_confirmations(txid, 6, Duration::from_millis(10), Duration::from_millis(50))
+        .await;
+    assert!(matches!(result, Err(BitcoinRpcError::Timeout)));
+}
+
+#[tokio::test]
+async fn test_wait_for_confirmations_tx_disappears() {
+    let (node, client) = setup_mock_client();
+    let txid = "a000000000000000000000000000000000000000000000000000000000000000";
+    node.add_transaction(txid, 0, vec![], true);
+
+    // The funding transaction gets replaced while we wait
+    let replacer = {
+        let node = node.clone();
+        tokio::spawn(async move {
+            sleep(Duration::from_millis(20)).await;
+            node.remove_transaction(txid);
+        })
+    };
+
+    let result = client
+        .wait_for_confirmations(txid, 1, Duration::from_millis(5), Duration::from_secs(2))
+        .await;
+    assert!(matches!(result, Err(BitcoinRpcError::TxNotFound(_))));
+    replacer.await.unwrap();
+}
+
+#[tokio::test]
+async fn test_broadcast_registers_transaction() {
+    let (node, client) = setup_mock_client();
+    let funding_txid = "a000000000000000000000000000000000000000000000000000000000000000";
+    node.add_transaction(funding_txid, 1, vec![TxOut { value: Amount::from_sat(100_000), script_pubkey: ScriptBuf::new() }], true);
+
+    let spend = Transaction {
+        version: bitcoin::transaction::Version::TWO,
+        lock_time: bitcoin::absolute::LockTime::ZERO,
+        input: vec![bitcoin::TxIn {
+            previous_output: bitcoin::OutPoint { txid: funding_txid.parse().unwrap(), vout: 0 },
+            ..Default::default()
+        }],
+        output: vec![TxOut { value: Amount::from_sat(90_000), script_pubkey: ScriptBuf::new() }],
+    };
+    let txid = client.broadcast_transaction(&spend).await.unwrap();
+    assert_eq!(txid, spend.compute_txid().to_string());
+
+    // The spend sits in the mempool and its input is gone
+    let spent_utxo = UtxoMeta::new(funding_txid.to_string(), 0, 100_000);
+    assert_eq!(client.get_utxo_status(&spent_utxo).await.unwrap(), UtxoStatus::Spent);
+    let change = UtxoMeta::new(txid.clone(), 0, 90_000);
+    assert_eq!(client.get_utxo_status(&change).await.unwrap(), UtxoStatus::Pending);
+    assert_eq!(client.get_tx_block_info(&txid).await.unwrap(), (0, 0, String::new()));
+
+    // Spending the same input twice is rejected
+    let mut double_spend = spend.clone();
+    double_spend.output[0].value = Amount::from_sat(80_000);
+    assert!(client.broadcast_transaction(&double_spend).await.is_err());
+
+    node.mine_blocks(1);
+    let (confirmations, height, hash) = client.get_tx_block_info(&txid).await.unwrap();
+    assert_eq!((confirmations, height), (1, 1));
+    assert_eq!(hash, client.get_best_block_hash().await.unwrap().to_string());
+    let mut change = change;
+    assert_eq!(client.update_utxo_confirmations(&mut change).await.unwrap(), 1);
+    assert_eq!(change.confirmations, 1);
+}
+
+#[tokio::test]
+async fn test_spender_tracking() {
+    let (node, client) = setup_mock_client();
+    let funding = "a000000000000000000000000000000000000000000000000000000000000000";
+    let spend = "b000000000000000000000000000000000000000000000000000000000000000";
+    let chained = "c000000000000000000000000000000000000000000000000000000000000000";
+    let output = || vec![TxOut { value: Amount::from_sat(50_000), script_pubkey: ScriptBuf::new() }];
+    node.add_transaction(funding, 1, output(), true);
+    assert_eq!(node.get_spender(funding, 0), None);
+
+    // A second transaction spends the funding output, then a third spends that one
+    node.add_transaction(spend, 0, output(), true);
+    node.spend_utxo_with(funding, 0, spend);
+    node.add_transaction(chained, 0, output(), true);
+    node.spend_utxo_with(spend, 0, chained);
+
+    assert_eq!(node.get_spender(funding, 0).as_deref(), Some(spend));
+    assert_eq!(node.get_spender(spend, 0).as_deref(), Some(chained));
+    let funding_utxo = UtxoMeta::new(funding.to_string(), 0, 50_000);
+    assert_eq!(client.get_utxo_status(&funding_utxo).await.unwrap(), UtxoStatus::Spent);
+    assert_eq!(
+        client.get_outspend(funding, 0).await.unwrap(),
+        Outspend { spent: true, txid: Some(spend.to_string()), block_height: None }
+    );
+
+    // The spender's block shows up once it is mined
+    node.mine_blocks(1);
+    assert_eq!(
+        node.utxo_state(funding, 0),
+        Some(MockUtxoState::SpentBy { txid: spend.to_string(), height: Some(1) })
+    );
+    assert!(!client.get_outspend(chained, 0).await.unwrap().spent);
+    assert!(matches!(client.get_outspend(chained, 1).await, Err(BitcoinRpcError::TxNotFound(_))));
+
+    // Dropping the last spender frees its input; the old wrapper records an unknown spender
+    node.remove_transaction(chained);
+    assert_eq!(node.get_spender(spend, 0), None);
+    node.spend_utxo(spend, 0);
+    assert_eq!(node.get_spender(spend, 0).as_deref(), Some(UNKNOWN_SPENDER));
+}
+
+#[tokio::test]
+async fn test_mempool_entries() {
+    let (node, client) = setup_mock_client();
+    let txid = "a000000000000000000000000000000000000000000000000000000000000000";
+    let replacement = "b000000000000000000000000000000000000000000000000000000000000000";
+    let outputs = vec![TxOut { value: Amount::from_sat(50_000), script_pubkey: ScriptBuf::new() }];
+    node.add_mempool_transaction(txid, outputs.clone(), 300, true);
+
+    let entry = client.get_mempool_entry(txid).await.unwrap();
+    assert_eq!(entry.fee, Amount::from_sat(300));
+    assert!(entry.bip125_replaceable);
+    assert!(client.get_raw_mempool().await.unwrap().contains(txid));
+
+    node.replace_transaction(txid, replacement, outputs, 600, false).unwrap();
+    assert!(matches!(client.get_mempool_entry(txid).await, Err(BitcoinRpcError::TxNotFound(_))));
+    assert!(!client.get_mempool_entry(replacement).await.unwrap().bip125_replaceable);
+
+    // Confirmed in the current tip block, and out of the mempool
+    node.mine_blocks(3);
+    assert!(!node.confirm_mempool_transaction(replacement));
+    node.add_mempool_transaction(txid, vec![], 300, false);
+    assert!(node.confirm_mempool_transaction(txid));
+    assert_eq!(client.get_tx_block_info(txid).await.unwrap().1, 3);
+    assert!(client.get_raw_mempool().await.unwrap().is_empty());
+}
@@ -19,7 +19,7 @@ use mock_sdk::{
     test_utils::TestClient,
     AccountMeta,
 };
-use program::bitcoin::{UtxoMeta, UtxoStatus};
+use program::bitcoin::{UtxoKey, UtxoMeta, UtxoStatus};
 use std::cell::RefCell;
 use std::sync::Arc;
 use tokio::sync::Mutex;
@@ -80,28 +80,28 @@ async fn test_utxo_state_transitions() {
     tracker.add_utxo(utxo.clone(), UtxoStatus::Pending).await;
     
     // Verify initial state
-    let initial_status = tracker.get_utxo_status(&utxo.txid).await;
+    let initial_status = tracker.get_utxo_status(&utxo.txid, utxo.vout).await;
     assert_eq!(initial_status, Some(UtxoStatus::Pending), "New UTXO should be in Pending state");
     
     // Simulate confirmation process (0 blocks initially)
     let bitcoin_rpc = mock_bitcoin_rpc_client();
     tracker.update_confirmations(&bitcoin_rpc, 0).await;
-    assert_eq!(tracker.get_utxo_status(&utxo.txid).await, Some(UtxoStatus::Pending), 
+    assert_eq!(tracker.get_utxo_status(&utxo.txid, utxo.vout).await, Some(UtxoStatus::Pending), 
         "UTXO should remain Pending with 0 confirmations");
     
     // Simulate 1 confirmation (still below threshold)
     tracker.update_confirmations(&bitcoin_rpc, 1).await;
-    assert_eq!(tracker.get_utxo_status(&utxo.txid).await, Some(UtxoStatus::Pending), 
+    assert_eq!(tracker.get_utxo_status(&utxo.txid, utxo.vout).await, Some(UtxoStatus::Pending), 
         "UTXO should remain Pending with 1 confirmation");
     
     // Simulate 6 confirmations (above threshold)
     tracker.update_confirmations(&bitcoin_rpc, 6).await;
-    assert_eq!(tracker.get_utxo_status(&utxo.txid).await, Some(UtxoStatus::Active), 
+    assert_eq!(tracker.get_utxo_status(&utxo.txid, utxo.vout).await, Some(UtxoStatus::Active), 
         "UTXO should transition to Active with 6 confirmations");
     
     // Mark UTXO as spent
-    tracker.mark_utxo_spent(&utxo.txid).await;
-    assert_eq!(tracker.get_utxo_status(&utxo.txid).await, Some(UtxoStatus::Spent), 
+    tracker.mark_utxo_spent(&utxo.txid, utxo.vout).await;
+    assert_eq!(tracker.get_utxo_status(&utxo.txid, utxo.vout).await, Some(UtxoStatus::Spent), 
         "UTXO should transition to Spent when consumed");
 }
 
@@ -137,14 +137,37 @@ async fn test_utxo_reorg_handling() {
     tracker.check_for_reorgs(&bitcoin_rpc).await;
     
     // utxo1 should be marked as Invalid due to reorg
-    assert_eq!(tracker.get_utxo_status(&utxo1.txid).await, Some(UtxoStatus::Invalid), 
+    assert_eq!(tracker.get_utxo_status(&utxo1.txid, utxo1.vout).await, Some(UtxoStatus::Invalid), 
         "UTXO affected by reorg should be marked Invalid");
     
     // utxo2 should remain Pending as it wasn't confirmed yet
-    assert_eq!(tracker.get_utxo_status(&utxo2.txid).await, Some(UtxoStatus::Pending), 
+    assert_eq!(tracker.get_utxo_status(&utxo2.txid, utxo2.vout).await, Some(UtxoStatus::Pending), 
         "Unconfirmed UTXO should remain Pending during reorg");
 }
 
+/// Test outputs of the same transaction are tracked independently
+/// 
+/// Verifies:
+/// - Two vouts of one funding transaction don't overwrite each other
+/// - Spending one output leaves its sibling untouched
+#[tokio::test]
+async fn test_utxo_tracking_by_outpoint() {
+    let txid = "test_txid_with_two_outputs";
+    let change = UtxoMeta::new(txid.to_string(), 0, 5000);
+    let treasury = UtxoMeta::new(txid.to_string(), 1, 50000);
+
+    let mut tracker = UtxoTracker::new();
+    tracker.add_utxo(change.clone(), UtxoStatus::Active).await;
+    tracker.add_utxo(treasury.clone(), UtxoStatus::Active).await;
+
+    tracker.mark_utxo_spent(txid, 1).await;
+    assert_eq!(tracker.get_utxo_status(txid, 1).await, Some(UtxoStatus::Spent),
+        "Spent output should be marked Spent");
+    assert_eq!(tracker.get_utxo_status(txid, 0).await, Some(UtxoStatus::Active),
+        "Sibling output should stay Active");
+    assert_eq!(treasury.key().to_string(), "test_txid_with_two_outputs:1");
+}
+
 /// Mock function that would be implemented as part of the UTXO tracking system
 async fn validate_utxo(bitcoin_rpc: &MockBitcoinRpc, utxo: &UtxoMeta) -> Result<(), ProgramError> {
     // In real implementation, this would make RPC calls to verify UTXO exists
@@ -162,14 +185,14 @@ async fn validate_utxo(bitcoin_rpc: &MockBitcoinRpc, utxo: &UtxoMeta) -> Result<
 struct MockBitcoinRpc {
     // Mock state for simulating Bitcoin node responses
     confirmed_txs: Arc<Mutex<Vec<String>>>,
-    confirmations: Arc<Mutex<u64>>,
+    confirmations: Arc<Mutex<u32>>,
     reorg_detected: Arc<Mutex<bool>>,
 }
 
 // Mock implementation of a UTXO tracker
 struct UtxoTracker {
-    // Using txid as the key instead of the whole UtxoMeta
-    utxos: Arc<Mutex<HashMap<String, (UtxoMeta, UtxoStatus)>>>,
+    // Keyed by outpoint, so outputs of the same transaction are tracked separately
+    utxos: Arc<Mutex<HashMap<UtxoKey, (UtxoMeta, UtxoStatus)>>>,
 }
 
 impl UtxoTracker {
@@ -181,15 +204,15 @@ impl UtxoTracker {
     
     async fn add_utxo(&mut self, utxo: UtxoMeta, status: UtxoStatus) {
         let mut utxos = self.utxos.lock().await;
-        utxos.insert(utxo.txid.clone(), (utxo, status));
+        utxos.insert(utxo.key(), (utxo, status));
     }
     
-    async fn get_utxo_status(&self, txid: &str) -> Option<UtxoStatus> {
+    async fn get_utxo_status(&self, txid: &str, vout: u32) -> Option<UtxoStatus> {
         let utxos = self.utxos.lock().await;
-        utxos.get(txid).map(|(_, status)| status.clone())
+        utxos.get(&UtxoKey::new(txid, vout)).map(|(_, status)| status.clone())
     }
     
-    async fn update_confirmations(&mut self, bitcoin_rpc: &MockBitcoinRpc, confirmations: u64) {
+    async fn update_confirmations(&mut self, bitcoin_rpc: &MockBitcoinRpc, confirmations: u32) {
         *bitcoin_rpc.confirmations.lock().await = confirmations;
         
         let mut utxos = self.utxos.lock().await;
@@ -204,9 +227,9 @@ impl UtxoTracker {
         }
     }
     
-    async fn mark_utxo_spent(&mut self, txid: &str) {
+    async fn mark_utxo_spent(&mut self, txid: &str, vout: u32) {
         let mut utxos = self.utxos.lock().await;
-        if let Some((_, status)) = utxos.get_mut(txid) {
+        if let Some((_, status)) = utxos.get_mut(&UtxoKey::new(txid, vout)) {
             *status = UtxoStatus::Spent;
         }
     }